@@ -0,0 +1,409 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use nalgebra::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+use optima_file::cache::{OAssetCache, OAssetCacheKey};
+use crate::OTriMesh;
+
+/// When `OTriMesh::decimate` stops simplifying.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum OMeshDecimation {
+    /// Simplify until at most this many triangles are left, or until no edge can be collapsed
+    /// without flipping a triangle, tearing the surface, or moving an open boundary.
+    TargetTriangleCount(usize),
+    /// Simplify as long as every vertex stays within this distance (in the mesh's units) of the
+    /// planes of the original triangles around it.
+    MaxError(f64)
+}
+impl OMeshDecimation {
+    /// Describes the settings, e.g., for cache keys.
+    pub fn params_string(&self) -> String {
+        match self {
+            OMeshDecimation::TargetTriangleCount(n) => { format!("target_triangle_count={}", n) }
+            OMeshDecimation::MaxError(e) => { format!("max_error={}", e) }
+        }
+    }
+}
+
+impl OTriMesh {
+    /// Simplifies the mesh by repeatedly collapsing the edge that changes the surface the least,
+    /// measured with quadric error metrics (Garland and Heckbert, 1997).  Vertices at the same
+    /// position are merged first, since stl files list the corners of every triangle separately.
+    /// Collapses that would flip a triangle or make the surface non-manifold (checked with the link
+    /// condition) are skipped, and vertices on open boundaries are never moved or removed, so the
+    /// outline of an open mesh and the rims of its holes stay exactly where they were.
+    pub fn decimate(&self, decimation: &OMeshDecimation) -> OTriMesh {
+        let (mut points, mut faces) = weld_vertices(&self.points, &self.indices);
+        let mut face_alive = vec![true; faces.len()];
+        let mut vertex_alive = vec![true; points.len()];
+        let mut vertex_faces: Vec<Vec<usize>> = vec![vec![]; points.len()];
+        faces.iter().enumerate().for_each(|(f, face)| face.iter().for_each(|v| vertex_faces[*v].push(f)));
+
+        let mut quadrics = vec![Quadric::zero(); points.len()];
+        let mut edge_counts: HashMap<(usize, usize), usize> = HashMap::new();
+        for face in &faces {
+            if let Some(normal) = face_normal(&points, face) {
+                let q = Quadric::from_plane(&normal, &points[face[0]]);
+                face.iter().for_each(|v| quadrics[*v].add_assign(&q));
+            }
+            for k in 0..3 { *edge_counts.entry(edge_key(face[k], face[(k + 1) % 3])).or_insert(0) += 1; }
+        }
+        // vertices on an edge with a single triangle (or more than two, where the input is already
+        // non-manifold) are locked.
+        let mut locked = vec![false; points.len()];
+        edge_counts.iter().filter(|(_, count)| **count != 2).for_each(|((a, b), _)| {
+            locked[*a] = true;
+            locked[*b] = true;
+        });
+
+        let (target_triangle_count, max_cost) = match decimation {
+            OMeshDecimation::TargetTriangleCount(n) => { (*n, f64::INFINITY) }
+            OMeshDecimation::MaxError(e) => { (0, e * e) }
+        };
+
+        let mut versions = vec![0usize; points.len()];
+        let mut heap = BinaryHeap::new();
+        heap.extend(edge_counts.keys().filter_map(|(a, b)| Collapse::new(*a, *b, &points, &quadrics, &versions, &locked)));
+
+        let mut num_faces = faces.len();
+        while num_faces > target_triangle_count {
+            let Some(c) = heap.pop() else { break; };
+            if !vertex_alive[c.a] || !vertex_alive[c.b] || versions[c.a] != c.version_a || versions[c.b] != c.version_b { continue; }
+            // the heap is ordered by cost, so every remaining collapse is at least as bad.
+            if c.cost > max_cost { break; }
+            if !collapse_keeps_manifold(&faces, &face_alive, &vertex_faces, &c) { continue; }
+            if collapse_flips_a_face(&points, &faces, &face_alive, &vertex_faces, &c) { continue; }
+
+            let (a, b) = (c.a, c.b);
+            points[a] = c.position;
+            let qb = quadrics[b].clone();
+            quadrics[a].add_assign(&qb);
+            vertex_alive[b] = false;
+            for f in std::mem::take(&mut vertex_faces[b]) {
+                if !face_alive[f] { continue; }
+                if faces[f].contains(&a) {
+                    face_alive[f] = false;
+                    num_faces -= 1;
+                } else {
+                    faces[f].iter_mut().for_each(|v| if *v == b { *v = a; });
+                    vertex_faces[a].push(f);
+                }
+            }
+            vertex_faces[a].retain(|f| face_alive[*f]);
+            versions[a] += 1;
+
+            let neighbors: HashSet<usize> = vertex_faces[a].iter().flat_map(|f| faces[*f]).filter(|v| *v != a).collect();
+            heap.extend(neighbors.iter().filter_map(|v| Collapse::new(a, *v, &points, &quadrics, &versions, &locked)));
+        }
+
+        let mut new_idxs = vec![usize::MAX; points.len()];
+        let mut out = OTriMesh::new_empty();
+        faces.iter().zip(face_alive.iter()).filter(|(_, alive)| **alive).for_each(|(face, _)| {
+            let face = face.map(|v| {
+                if new_idxs[v] == usize::MAX {
+                    new_idxs[v] = out.points.len();
+                    out.points.push([points[v].x, points[v].y, points[v].z]);
+                }
+                new_idxs[v]
+            });
+            out.indices.push(face);
+        });
+
+        out
+    }
+    /// Convex hull of the decimated mesh, scaled up about its centroid just enough to contain every
+    /// vertex of this mesh (and so this mesh's convex hull).  Decimating a convex hull directly can
+    /// cut its corners off, which would let collision queries miss contacts; this stays
+    /// conservative.  Falls back to this mesh's convex hull if the decimated mesh is flat.
+    pub fn decimated_convex_hull(&self, decimation: &OMeshDecimation) -> OTriMesh {
+        let hull = self.decimate(decimation).to_convex_hull();
+        if hull.indices.is_empty() { return self.to_convex_hull(); }
+
+        let hull_points: Vec<Vector3<f64>> = hull.points.iter().map(|x| Vector3::from(*x)).collect();
+        let centroid = hull_points.iter().sum::<Vector3<f64>>() / hull_points.len() as f64;
+
+        let mut scale = 1.0_f64;
+        for face in &hull.indices {
+            let [a, b, c] = face.map(|v| hull_points[v]);
+            let Some(mut normal) = (b - a).cross(&(c - a)).try_normalize(f64::EPSILON) else { continue; };
+            let mut height = normal.dot(&(a - centroid));
+            if height < 0.0 { normal = -normal; height = -height; }
+            if height <= f64::EPSILON { continue; }
+            self.points.iter().for_each(|x| scale = scale.max(normal.dot(&(Vector3::from(*x) - centroid)) / height));
+        }
+
+        let points = hull_points.iter().map(|x| {
+            let p = centroid + (x - centroid) * scale;
+            [p.x, p.y, p.z]
+        }).collect();
+        OTriMesh::new(points, hull.indices)
+    }
+    /// Same as `decimate`, but reuses a previous result for an identical mesh and settings if one
+    /// is in the cache.
+    pub fn decimate_cached(&self, decimation: &OMeshDecimation, cache: &OAssetCache) -> OTriMesh {
+        let key = OAssetCacheKey::new_from_object("mesh_decimation", &decimation.params_string(), self);
+        cache.get_or_insert_with(&key, || self.decimate(decimation))
+    }
+}
+
+/// `v^T A v + 2 b^T v + c`, the sum of squared distances from `v` to a set of planes.
+#[derive(Clone, Debug)]
+struct Quadric {
+    a: Matrix3<f64>,
+    b: Vector3<f64>,
+    c: f64
+}
+impl Quadric {
+    fn zero() -> Self {
+        Self { a: Matrix3::zeros(), b: Vector3::zeros(), c: 0.0 }
+    }
+    /// `normal` must be unit length.
+    fn from_plane(normal: &Vector3<f64>, point_on_plane: &Vector3<f64>) -> Self {
+        let d = -normal.dot(point_on_plane);
+        Self { a: normal * normal.transpose(), b: normal * d, c: d * d }
+    }
+    fn add_assign(&mut self, other: &Self) {
+        self.a += other.a;
+        self.b += other.b;
+        self.c += other.c;
+    }
+    fn cost(&self, v: &Vector3<f64>) -> f64 {
+        (v.dot(&(self.a * v)) + 2.0 * self.b.dot(v) + self.c).max(0.0)
+    }
+}
+
+struct Collapse {
+    cost: f64,
+    /// `b` is merged into `a`, which moves to `position`.  `b` is never locked.
+    a: usize,
+    b: usize,
+    position: Vector3<f64>,
+    version_a: usize,
+    version_b: usize
+}
+impl Collapse {
+    /// None if both vertices are locked.  If one is, the other is merged into it, and it stays put.
+    fn new(a: usize, b: usize, points: &Vec<Vector3<f64>>, quadrics: &Vec<Quadric>, versions: &Vec<usize>, locked: &Vec<bool>) -> Option<Self> {
+        if locked[a] && locked[b] { return None; }
+        let (a, b) = if locked[b] { (b, a) } else { (a, b) };
+
+        let mut q = quadrics[a].clone();
+        q.add_assign(&quadrics[b]);
+
+        let (pa, pb) = (points[a], points[b]);
+        let mut candidates = vec![pa];
+        if !locked[a] {
+            candidates.push(pb);
+            candidates.push((pa + pb) * 0.5);
+            // the minimizer of the quadric, unless the planes leave it unconstrained in some
+            // direction (e.g., on a flat patch), where it could slide arbitrarily far away.
+            if q.a.determinant().abs() > 1e-12 {
+                if let Some(inverse) = q.a.try_inverse() {
+                    let v = -(inverse * q.b);
+                    if (v - (pa + pb) * 0.5).norm() <= 2.0 * (pb - pa).norm() { candidates.push(v); }
+                }
+            }
+        }
+        let (position, cost) = candidates.iter().map(|v| (*v, q.cost(v))).min_by(|x, y| x.1.total_cmp(&y.1)).expect("error");
+
+        Some(Self { cost, a, b, position, version_a: versions[a], version_b: versions[b] })
+    }
+}
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal
+    }
+}
+impl Eq for Collapse { }
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Collapse {
+    /// Reversed, so that `BinaryHeap` pops the cheapest collapse first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+fn weld_vertices(points: &Vec<[f64; 3]>, indices: &Vec<[usize; 3]>) -> (Vec<Vector3<f64>>, Vec<[usize; 3]>) {
+    let mut welded_points = vec![];
+    let mut point_to_idx: HashMap<[u64; 3], usize> = HashMap::new();
+    let new_idxs: Vec<usize> = points.iter().map(|p| {
+        *point_to_idx.entry(p.map(|x| x.to_bits())).or_insert_with(|| {
+            welded_points.push(Vector3::new(p[0], p[1], p[2]));
+            welded_points.len() - 1
+        })
+    }).collect();
+
+    let faces = indices.iter()
+        .map(|face| face.map(|v| new_idxs[v]))
+        .filter(|face| face[0] != face[1] && face[1] != face[2] && face[0] != face[2])
+        .collect();
+
+    (welded_points, faces)
+}
+
+fn face_normal(points: &Vec<Vector3<f64>>, face: &[usize; 3]) -> Option<Vector3<f64>> {
+    (points[face[1]] - points[face[0]]).cross(&(points[face[2]] - points[face[0]])).try_normalize(f64::EPSILON)
+}
+
+#[inline(always)]
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b { (a, b) } else { (b, a) }
+}
+
+/// The link condition: the vertices adjacent to both `c.a` and `c.b` must be exactly the third
+/// corners of the triangles on the edge between them.  Otherwise, merging the two would pinch the
+/// surface (e.g., collapse a tunnel) or leave a triangle covering another.
+fn collapse_keeps_manifold(faces: &Vec<[usize; 3]>, face_alive: &Vec<bool>, vertex_faces: &Vec<Vec<usize>>, c: &Collapse) -> bool {
+    let neighbors = |v: usize| -> HashSet<usize> {
+        vertex_faces[v].iter().filter(|f| face_alive[**f]).flat_map(|f| faces[*f]).filter(|x| *x != v).collect()
+    };
+    let shared: HashSet<usize> = neighbors(c.a).intersection(&neighbors(c.b)).copied().collect();
+    let opposite: HashSet<usize> = vertex_faces[c.a].iter()
+        .filter(|f| face_alive[**f] && faces[**f].contains(&c.b))
+        .flat_map(|f| faces[*f])
+        .filter(|x| *x != c.a && *x != c.b)
+        .collect();
+
+    shared == opposite
+}
+
+/// Whether moving `c.a` and `c.b` to `c.position` would turn any of their remaining triangles
+/// over (or flatten it).
+fn collapse_flips_a_face(points: &Vec<Vector3<f64>>, faces: &Vec<[usize; 3]>, face_alive: &Vec<bool>, vertex_faces: &Vec<Vec<usize>>, c: &Collapse) -> bool {
+    vertex_faces[c.a].iter().chain(vertex_faces[c.b].iter()).any(|f| {
+        let face = &faces[*f];
+        if !face_alive[*f] || (face.contains(&c.a) && face.contains(&c.b)) { return false; }
+        let Some(before) = face_normal(points, face) else { return false; };
+        let moved: Vec<Vector3<f64>> = face.iter().map(|v| if *v == c.a || *v == c.b { c.position } else { points[*v] }).collect();
+        match (moved[1] - moved[0]).cross(&(moved[2] - moved[0])).try_normalize(f64::EPSILON) {
+            None => { true }
+            Some(after) => { before.dot(&after) < 0.2 }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use nalgebra::Vector3;
+    use crate::OTriMesh;
+    use super::OMeshDecimation;
+
+    /// An open, gently curved `n` by `n` grid of vertices.
+    fn open_grid(n: usize) -> OTriMesh {
+        let mut points = vec![];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 / (n - 1) as f64, j as f64 / (n - 1) as f64);
+                points.push([x, y, 0.1 * (3.0 * x).sin() * (2.0 * y).cos()]);
+            }
+        }
+        let mut indices = vec![];
+        for i in 0..n - 1 {
+            for j in 0..n - 1 {
+                let (v00, v01, v10, v11) = (i * n + j, i * n + j + 1, (i + 1) * n + j, (i + 1) * n + j + 1);
+                indices.push([v00, v10, v11]);
+                indices.push([v00, v11, v01]);
+            }
+        }
+        OTriMesh::new(points, indices)
+    }
+
+    /// Number of triangles on each edge, with edges keyed by their end points' positions.
+    fn edge_counts(mesh: &OTriMesh) -> HashMap<[[u64; 3]; 2], usize> {
+        let mut out = HashMap::new();
+        for face in &mesh.indices {
+            for k in 0..3 {
+                let mut key = [mesh.points[face[k]].map(|x| x.to_bits()), mesh.points[face[(k + 1) % 3]].map(|x| x.to_bits())];
+                key.sort();
+                *out.entry(key).or_insert(0) += 1;
+            }
+        }
+        out
+    }
+
+    fn boundary_edges(mesh: &OTriMesh) -> Vec<[[u64; 3]; 2]> {
+        let mut out: Vec<[[u64; 3]; 2]> = edge_counts(mesh).into_iter().filter(|(_, count)| *count == 1).map(|(key, _)| key).collect();
+        out.sort();
+        out
+    }
+
+    /// A closed, slightly bumpy uv sphere of radius about 1.
+    fn closed_bumpy_sphere(n: usize) -> OTriMesh {
+        let mut points = vec![[0.0, 0.0, 1.0], [0.0, 0.0, -1.0]];
+        for i in 1..n {
+            for j in 0..2 * n {
+                let (theta, phi) = (std::f64::consts::PI * i as f64 / n as f64, std::f64::consts::PI * j as f64 / n as f64);
+                let r = 1.0 + 0.05 * (5.0 * phi).sin() * (3.0 * theta).sin();
+                points.push([r * theta.sin() * phi.cos(), r * theta.sin() * phi.sin(), r * theta.cos()]);
+            }
+        }
+        let ring = |i: usize, j: usize| 2 + (i - 1) * 2 * n + j % (2 * n);
+        let mut indices = vec![];
+        for j in 0..2 * n {
+            indices.push([0, ring(1, j), ring(1, j + 1)]);
+            indices.push([1, ring(n - 1, j + 1), ring(n - 1, j)]);
+            for i in 1..n - 1 {
+                indices.push([ring(i, j), ring(i + 1, j), ring(i + 1, j + 1)]);
+                indices.push([ring(i, j), ring(i + 1, j + 1), ring(i, j + 1)]);
+            }
+        }
+        OTriMesh::new(points, indices)
+    }
+
+    /// Largest distance of a point of `mesh` outside the planes of the closed convex `hull`.
+    fn max_distance_outside(hull: &OTriMesh, mesh: &OTriMesh) -> f64 {
+        let hull_points: Vec<Vector3<f64>> = hull.points.iter().map(|x| Vector3::from(*x)).collect();
+        let centroid = hull_points.iter().sum::<Vector3<f64>>() / hull_points.len() as f64;
+        let mut out = f64::MIN;
+        for face in &hull.indices {
+            let [a, b, c] = face.map(|v| hull_points[v]);
+            let Some(mut normal) = (b - a).cross(&(c - a)).try_normalize(f64::EPSILON) else { continue; };
+            if normal.dot(&(a - centroid)) < 0.0 { normal = -normal; }
+            mesh.points.iter().for_each(|x| out = out.max(normal.dot(&(Vector3::from(*x) - a))));
+        }
+        out
+    }
+
+    #[test]
+    fn decimated_convex_hulls_contain_the_mesh() {
+        let mesh = closed_bumpy_sphere(16);
+        let decimation = OMeshDecimation::TargetTriangleCount(60);
+
+        let hull = mesh.decimated_convex_hull(&decimation);
+        assert!(hull.indices.len() < mesh.to_convex_hull().indices.len());
+        assert!(edge_counts(&hull).values().all(|count| *count == 2));
+        assert!(max_distance_outside(&hull, &mesh) < 1e-9, "{}", max_distance_outside(&hull, &mesh));
+    }
+
+    #[test]
+    fn decimating_an_open_mesh_keeps_its_boundary() {
+        let mesh = open_grid(12);
+        let decimated = mesh.decimate(&OMeshDecimation::TargetTriangleCount(60));
+
+        assert!(decimated.indices.len() < mesh.indices.len());
+        assert_eq!(boundary_edges(&decimated), boundary_edges(&mesh));
+    }
+
+    #[test]
+    fn decimating_an_open_mesh_keeps_it_manifold() {
+        let decimated = open_grid(12).decimate(&OMeshDecimation::TargetTriangleCount(40));
+
+        // no edge is shared by more than two triangles.
+        assert!(edge_counts(&decimated).values().all(|count| *count <= 2));
+
+        // the triangles around each vertex form a single fan, so a vertex touches either zero
+        // boundary edges (inside) or two (on the boundary).
+        let mut boundary_edges_per_vertex: HashMap<[u64; 3], usize> = HashMap::new();
+        decimated.points.iter().for_each(|p| { boundary_edges_per_vertex.insert(p.map(|x| x.to_bits()), 0); });
+        for [a, b] in boundary_edges(&decimated) {
+            *boundary_edges_per_vertex.get_mut(&a).expect("error") += 1;
+            *boundary_edges_per_vertex.get_mut(&b).expect("error") += 1;
+        }
+        assert!(boundary_edges_per_vertex.values().all(|count| *count == 0 || *count == 2));
+    }
+}
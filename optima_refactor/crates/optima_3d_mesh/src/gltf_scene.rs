@@ -0,0 +1,96 @@
+use gltf::mesh::Mode;
+use nalgebra::{Matrix4, Point3};
+use optima_file::path::OGltfImport;
+use crate::{OTriMesh, ToTriMesh};
+
+/// The triangle primitives of a glTF file's default scene (or of its first scene if none is marked as
+/// the default), each paired with the transform from the primitive's frame to the file's frame.  glTF
+/// is y up, so the transform also rotates y up onto z up, the convention of urdf meshes; this undoes
+/// the root node added by `OSceneExport::to_gltf_string`.
+pub fn gltf_triangle_primitives(gltf: &OGltfImport) -> Vec<(gltf::Primitive, Matrix4<f32>)> {
+    let y_up_to_z_up = Matrix4::new(1.0, 0.0, 0.0, 0.0,
+                                    0.0, 0.0, -1.0, 0.0,
+                                    0.0, 1.0, 0.0, 0.0,
+                                    0.0, 0.0, 0.0, 1.0);
+
+    let mut out = vec![];
+    if let Some(scene) = gltf.document.default_scene().or_else(|| gltf.document.scenes().next()) {
+        scene.nodes().for_each(|node| add_node_primitives(node, &y_up_to_z_up, &mut out));
+    }
+    out
+}
+
+fn add_node_primitives<'a>(node: gltf::Node<'a>, parent_transform: &Matrix4<f32>, out: &mut Vec<(gltf::Primitive<'a>, Matrix4<f32>)>) {
+    // gltf matrices are column major, which is also how nalgebra reads nested arrays.
+    let transform = parent_transform * Matrix4::from(node.transform().matrix());
+    if let Some(mesh) = node.mesh() {
+        mesh.primitives().filter(|x| x.mode() == Mode::Triangles).for_each(|x| out.push((x, transform)));
+    }
+    node.children().for_each(|child| add_node_primitives(child, &transform, out));
+}
+
+/// All triangle primitives of the scene are merged into one trimesh, in the file's frame rotated to z
+/// up (see `gltf_triangle_primitives`); materials are dropped.
+impl ToTriMesh for OGltfImport {
+    fn to_trimesh(&self) -> OTriMesh {
+        let mut out_trimesh = OTriMesh::new_empty();
+
+        for (primitive, transform) in gltf_triangle_primitives(self) {
+            let reader = primitive.reader(|x| self.buffers.get(x.index()).map(|x| &x.0[..]));
+            let Some(positions) = reader.read_positions() else { continue; };
+            let points: Vec<[f64; 3]> = positions.map(|x| {
+                let p = transform.transform_point(&Point3::from(x));
+                [p.x as f64, p.y as f64, p.z as f64]
+            }).collect();
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => { indices.into_u32().collect() }
+                None => { (0..points.len() as u32).collect() }
+            };
+            let indices: Vec<[usize; 3]> = indices.chunks_exact(3).map(|x| [x[0] as usize, x[1] as usize, x[2] as usize]).collect();
+            out_trimesh.extend_from_points_and_indices(&points, &indices);
+        }
+
+        out_trimesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use optima_file::path::OPath;
+    use crate::scene_export::OSceneExport;
+    use super::*;
+
+    fn temp_path(name: &str) -> OPath {
+        let dir = std::env::temp_dir().join(format!("optima_gltf_scene_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("error");
+        OPath::Path(dir.join(name))
+    }
+
+    fn exported_scene() -> OSceneExport {
+        let mut scene = OSceneExport::new();
+        let mesh = scene.add_mesh("triangle", OTriMesh::new(vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]], vec![[0, 1, 2]]), Some([1.0, 0.0, 0.0, 1.0]));
+        let root = scene.add_node("root", None, [0.0, 0.0, 2.0], [1.0, 0.0, 0.0, 0.0], None).expect("error");
+        scene.add_node("child", Some(root), [1.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0], Some(mesh)).expect("error");
+        scene
+    }
+
+    fn assert_points_eq(trimesh: &OTriMesh, expected: &[[f64; 3]]) {
+        assert_eq!(trimesh.points().len(), expected.len());
+        trimesh.points().iter().zip(expected.iter()).for_each(|(a, b)| {
+            (0..3).for_each(|i| assert!((a[i] - b[i]).abs() < 1e-6, "{:?} != {:?}", a, b));
+        });
+    }
+
+    #[test]
+    fn exported_gltf_and_glb_load_back_in_the_z_up_frame() {
+        let expected = [[1.0, 0.0, 2.0], [2.0, 0.0, 2.0], [1.0, 0.0, 3.0]];
+        for name in ["scene.gltf", "scene.glb"] {
+            let path = temp_path(name);
+            exported_scene().save_from_extension(&path).expect("error");
+
+            let trimesh = path.load_gltf().expect("error").to_trimesh();
+            assert_points_eq(&trimesh, &expected);
+            assert_eq!(trimesh.indices(), &vec![[0, 1, 2]]);
+        }
+    }
+}
@@ -1,5 +1,9 @@
 pub mod collada;
 pub mod stl;
+pub mod mesh_scene;
+pub mod gltf_scene;
+pub mod scene_export;
+pub mod decimation;
 
 use ad_trait::AD;
 use nalgebra::{Point, Point3};
@@ -7,7 +11,9 @@ use parry3d_f64::transformation::convex_hull;
 use parry3d_f64::transformation::vhacd::{VHACD, VHACDParameters};
 use parry3d_f64::transformation::voxelization::FillMode;
 use optima_3d_spatial::optima_3d_vec::{O3DVec, O3DVecCategoryPoint3};
+use optima_file::cache::{OAssetCache, OAssetCacheKey};
 use optima_file::path::{OPath, OStemCellPath};
+use serde::{Deserialize, Serialize};
 
 pub trait ToTriMesh {
     fn to_trimesh(&self) -> OTriMesh;
@@ -20,7 +26,7 @@ pub trait ToTriMesh {
 */
 
 /*
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OTriMesh {
     pub (crate) triangles: Vec<[[f64; 3]; 3]>
 }
@@ -31,12 +37,15 @@ impl OTriMesh {
 }
 */
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OTriMesh {
     pub (crate) points: Vec<[f64;3]>,
     pub (crate) indices: Vec<[usize;3]>,
 }
 impl OTriMesh {
+    pub fn new(points: Vec<[f64; 3]>, indices: Vec<[usize; 3]>) -> Self {
+        Self { points, indices }
+    }
     pub fn new_empty() -> Self {
         Self { points: vec![], indices: vec![] }
     }
@@ -48,6 +57,8 @@ impl OTriMesh {
         if let Some(res) = res { return Some(res.to_trimesh()) }
         let res = path.try_function_on_all_optima_file_paths_return_option(OPath::load_dae);
         if let Some(res) = res { return Some(res.to_trimesh()) }
+        let res = path.try_function_on_all_optima_file_paths_return_option(OPath::load_gltf);
+        if let Some(res) = res { return Some(res.to_trimesh()) }
         None
     }
     pub (crate) fn extend_from_points_and_indices(&mut self, new_points: &Vec<[f64; 3]>, new_indices: &Vec<[usize;3]>) {
@@ -57,7 +68,7 @@ impl OTriMesh {
         self.indices.extend(new_indices);
     }
     pub fn save_to_stl(&self, path: &OStemCellPath) {
-        path.verify_extension(&vec!["stl", "STL"]);
+        path.verify_extension_unchecked(&vec!["stl", "STL"]);
 
         let mut mesh = vec![];
 
@@ -81,7 +92,7 @@ impl OTriMesh {
             mesh.push(triangle);
         });
 
-        let mut f = path.get_file_for_writing();
+        let mut f = path.get_file_for_writing_unchecked();
         stl_io::write_stl(&mut f, mesh.iter()).expect("could not write stl");
     }
     pub fn to_triangles(&self) -> Vec<[[f64; 3]; 3]> {
@@ -132,6 +143,18 @@ impl OTriMesh {
 
         out
     }
+    /// Same as `to_convex_hull`, but reuses a previous result for an identical mesh if one is in
+    /// the cache.
+    pub fn to_convex_hull_cached(&self, cache: &OAssetCache) -> OTriMesh {
+        let key = OAssetCacheKey::new_from_object("convex_hull", "", self);
+        cache.get_or_insert_with(&key, || self.to_convex_hull())
+    }
+    /// Same as `to_convex_decomposition`, but reuses a previous result for an identical mesh and
+    /// `max_convex_hulls` if one is in the cache.
+    pub fn to_convex_decomposition_cached(&self, max_convex_hulls: u32, cache: &OAssetCache) -> Vec<OTriMesh> {
+        let key = OAssetCacheKey::new_from_object("convex_decomposition", &format!("max_convex_hulls={},resolution=128", max_convex_hulls), self);
+        cache.get_or_insert_with(&key, || self.to_convex_decomposition(max_convex_hulls))
+    }
     pub fn to_convex_decomposition_levels(&self, max_convex_hulls_per_level: Vec<u32>) -> Vec<Vec<OTriMesh>> {
         let mut out = vec![];
         max_convex_hulls_per_level.iter().for_each(|x| {
@@ -0,0 +1,17 @@
+use mesh_loader::Scene;
+use crate::{OTriMesh, ToTriMesh};
+
+/// All meshes in the scene are merged into one trimesh; materials are dropped.
+impl ToTriMesh for Scene {
+    fn to_trimesh(&self) -> OTriMesh {
+        let mut out_trimesh = OTriMesh::new_empty();
+
+        self.meshes.iter().for_each(|mesh| {
+            let points: Vec<[f64; 3]> = mesh.vertices.iter().map(|x| [x[0] as f64, x[1] as f64, x[2] as f64]).collect();
+            let indices: Vec<[usize; 3]> = mesh.faces.iter().map(|x| [x[0] as usize, x[1] as usize, x[2] as usize]).collect();
+            out_trimesh.extend_from_points_and_indices(&points, &indices);
+        });
+
+        out_trimesh
+    }
+}
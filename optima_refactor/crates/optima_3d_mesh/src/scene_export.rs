@@ -0,0 +1,421 @@
+use std::collections::HashSet;
+use ad_trait::AD;
+use serde_json::json;
+use optima_3d_spatial::optima_3d_pose::O3DPose;
+use optima_3d_spatial::optima_3d_rotation::O3DRotation;
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_error::OptimaError;
+use optima_file::path::OPath;
+use crate::OTriMesh;
+
+/// A scene (meshes, poses, and hierarchy) in a form that can be written to formats read by
+/// external tools.  Poses are in Optima's z-up convention; the glTF writer adds a root transform
+/// to convert to glTF's y-up convention and the USD writer declares the stage as z-up.
+#[derive(Clone, Debug)]
+pub struct OSceneExport {
+    meshes: Vec<OSceneExportMesh>,
+    nodes: Vec<OSceneExportNode>
+}
+impl OSceneExport {
+    pub fn new() -> Self {
+        Self { meshes: vec![], nodes: vec![] }
+    }
+    /// Returns the mesh index, to be referenced by nodes.  A mesh can be referenced by any number
+    /// of nodes.
+    pub fn add_mesh(&mut self, name: &str, trimesh: OTriMesh, color: Option<[f64; 4]>) -> usize {
+        self.meshes.push(OSceneExportMesh { name: name.to_string(), trimesh, color });
+        self.meshes.len() - 1
+    }
+    /// `translation` and `rotation` (a wxyz unit quaternion) are relative to the parent node, or to
+    /// the world if there is no parent.  Parents must be added before their children.  Returns the
+    /// node index, or `IdxOutOfBounds` if `parent` or `mesh` has not been added.
+    pub fn add_node(&mut self, name: &str, parent: Option<usize>, translation: [f64; 3], rotation: [f64; 4], mesh: Option<usize>) -> Result<usize, OptimaError> {
+        if let Some(parent) = parent { OptimaError::check_idx("node", parent, self.nodes.len())?; }
+        if let Some(mesh) = mesh { OptimaError::check_idx("mesh", mesh, self.meshes.len())?; }
+        self.nodes.push(OSceneExportNode { name: name.to_string(), parent, translation, rotation, mesh });
+        Ok(self.nodes.len() - 1)
+    }
+    pub fn add_node_from_pose<T: AD, P: O3DPose<T>>(&mut self, name: &str, parent: Option<usize>, pose: &P, mesh: Option<usize>) -> Result<usize, OptimaError> {
+        let translation = pose.translation().to_arr().map(|x| x.to_constant());
+        let rotation = pose.rotation().unit_quaternion_as_wxyz_slice().map(|x| x.to_constant());
+        self.add_node(name, parent, translation, rotation, mesh)
+    }
+    /// Adds the meshes and nodes of `other`, with its root nodes placed under `parent`.  Returns the
+    /// index that each of `other`'s nodes has in this scene, or `IdxOutOfBounds` (adding nothing) if
+    /// `parent` has not been added.
+    pub fn append(&mut self, other: &OSceneExport, parent: Option<usize>) -> Result<Vec<usize>, OptimaError> {
+        if let Some(parent) = parent { OptimaError::check_idx("node", parent, self.nodes.len())?; }
+        let mesh_offset = self.meshes.len();
+        let node_offset = self.nodes.len();
+
+        self.meshes.extend(other.meshes.iter().cloned());
+        other.nodes.iter().for_each(|x| {
+            self.nodes.push(OSceneExportNode {
+                name: x.name.clone(),
+                parent: match x.parent { None => { parent } Some(p) => { Some(p + node_offset) } },
+                translation: x.translation,
+                rotation: x.rotation,
+                mesh: x.mesh.map(|m| m + mesh_offset),
+            });
+        });
+
+        Ok((node_offset..self.nodes.len()).collect())
+    }
+    #[inline(always)]
+    pub fn meshes(&self) -> &Vec<OSceneExportMesh> {
+        &self.meshes
+    }
+    #[inline(always)]
+    pub fn nodes(&self) -> &Vec<OSceneExportNode> {
+        &self.nodes
+    }
+    pub fn save(&self, path: &OPath, format: OSceneExportFormat) -> Result<(), String> {
+        match format {
+            OSceneExportFormat::Gltf => { path.write_string_to_file(&self.to_gltf_string()) }
+            OSceneExportFormat::Glb => { path.write_bytes_to_file(&self.to_glb_bytes()) }
+            OSceneExportFormat::Usda => { path.write_string_to_file(&self.to_usda_string()) }
+        }
+    }
+    /// Picks the format from the path's extension.
+    pub fn save_from_extension(&self, path: &OPath) -> Result<(), String> {
+        let extension = path.extension().ok_or(format!("path {:?} has no extension", path))?;
+        let format = OSceneExportFormat::from_extension(&extension).ok_or(format!("extension {} is not a supported scene export format", extension))?;
+        self.save(path, format)
+    }
+    /// glTF 2.0 json with the geometry embedded as a base64 data uri.
+    pub fn to_gltf_string(&self) -> String {
+        let (mut gltf, buffer) = self.gltf_json_and_buffer();
+        gltf["buffers"] = json!([{ "byteLength": buffer.len(), "uri": format!("data:application/octet-stream;base64,{}", base64_encode(&buffer)) }]);
+        serde_json::to_string_pretty(&gltf).expect("error")
+    }
+    /// Binary glTF 2.0.
+    pub fn to_glb_bytes(&self) -> Vec<u8> {
+        let (mut gltf, mut buffer) = self.gltf_json_and_buffer();
+        gltf["buffers"] = json!([{ "byteLength": buffer.len() }]);
+        let mut json_bytes = serde_json::to_vec(&gltf).expect("error");
+        while json_bytes.len() % 4 != 0 { json_bytes.push(b' '); }
+        while buffer.len() % 4 != 0 { buffer.push(0); }
+
+        let total_length = 12 + 8 + json_bytes.len() + 8 + buffer.len();
+        let mut out = Vec::with_capacity(total_length);
+        out.extend(b"glTF");
+        out.extend(2u32.to_le_bytes());
+        out.extend((total_length as u32).to_le_bytes());
+        out.extend((json_bytes.len() as u32).to_le_bytes());
+        out.extend(b"JSON");
+        out.extend(json_bytes);
+        out.extend((buffer.len() as u32).to_le_bytes());
+        out.extend(b"BIN\0");
+        out.extend(buffer);
+        out
+    }
+    /// USD ascii.  Node hierarchy becomes an Xform hierarchy and each node's mesh becomes a Mesh
+    /// prim under it.
+    pub fn to_usda_string(&self) -> String {
+        let mut out = String::new();
+        out += "#usda 1.0\n(\n    defaultPrim = \"optima_scene\"\n    metersPerUnit = 1\n    upAxis = \"Z\"\n)\n\n";
+        out += "def Xform \"optima_scene\"\n{\n";
+        let children = self.children_of_each_node();
+        let roots: Vec<usize> = (0..self.nodes.len()).filter(|i| self.nodes[*i].parent.is_none()).collect();
+        let mut used_names = HashSet::new();
+        for root in roots { self.write_usda_node(root, &children, 1, &mut used_names, &mut out); }
+        out += "}\n";
+        out
+    }
+    fn write_usda_node(&self, node_idx: usize, children: &Vec<Vec<usize>>, depth: usize, sibling_names: &mut HashSet<String>, out: &mut String) {
+        let node = &self.nodes[node_idx];
+        let indent = "    ".repeat(depth);
+        let t = node.translation;
+        let r = node.rotation;
+
+        *out += &format!("{}def Xform \"{}\"\n{}{{\n", indent, unique_usd_name(&node.name, sibling_names), indent);
+        *out += &format!("{}    double3 xformOp:translate = ({}, {}, {})\n", indent, t[0], t[1], t[2]);
+        *out += &format!("{}    quatd xformOp:orient = ({}, {}, {}, {})\n", indent, r[0], r[1], r[2], r[3]);
+        *out += &format!("{}    uniform token[] xformOpOrder = [\"xformOp:translate\", \"xformOp:orient\"]\n", indent);
+
+        let mut child_names = HashSet::new();
+        if let Some(mesh_idx) = node.mesh {
+            let mesh = &self.meshes[mesh_idx];
+            let points: Vec<String> = mesh.trimesh.points.iter().map(|p| format!("({}, {}, {})", p[0], p[1], p[2])).collect();
+            let indices: Vec<String> = mesh.trimesh.indices.iter().flat_map(|x| x.iter()).map(|x| x.to_string()).collect();
+
+            *out += &format!("\n{}    def Mesh \"{}\"\n{}    {{\n", indent, unique_usd_name(&mesh.name, &mut child_names), indent);
+            *out += &format!("{}        uniform token subdivisionScheme = \"none\"\n", indent);
+            *out += &format!("{}        point3f[] points = [{}]\n", indent, points.join(", "));
+            *out += &format!("{}        int[] faceVertexCounts = [{}]\n", indent, vec!["3"; mesh.trimesh.indices.len()].join(", "));
+            *out += &format!("{}        int[] faceVertexIndices = [{}]\n", indent, indices.join(", "));
+            if let Some(c) = mesh.color {
+                *out += &format!("{}        color3f[] primvars:displayColor = [({}, {}, {})]\n", indent, c[0], c[1], c[2]);
+                *out += &format!("{}        float[] primvars:displayOpacity = [{}]\n", indent, c[3]);
+            }
+            *out += &format!("{}    }}\n", indent);
+        }
+
+        for child in &children[node_idx] {
+            *out += "\n";
+            self.write_usda_node(*child, children, depth + 1, &mut child_names, out);
+        }
+        *out += &format!("{}}}\n", indent);
+    }
+    fn gltf_json_and_buffer(&self) -> (serde_json::Value, Vec<u8>) {
+        let mut buffer: Vec<u8> = vec![];
+        let mut buffer_views = vec![];
+        let mut accessors = vec![];
+        let mut materials = vec![];
+        let mut meshes = vec![];
+
+        for mesh in &self.meshes {
+            let points = &mesh.trimesh.points;
+            let mut min = [f64::INFINITY; 3];
+            let mut max = [f64::NEG_INFINITY; 3];
+            let positions_offset = buffer.len();
+            for p in points {
+                for i in 0..3 {
+                    min[i] = min[i].min(p[i]);
+                    max[i] = max[i].max(p[i]);
+                    buffer.extend((p[i] as f32).to_le_bytes());
+                }
+            }
+            let positions_length = buffer.len() - positions_offset;
+            let indices_offset = buffer.len();
+            for idxs in &mesh.trimesh.indices {
+                for idx in idxs { buffer.extend((*idx as u32).to_le_bytes()); }
+            }
+            let indices_length = buffer.len() - indices_offset;
+            if points.is_empty() { min = [0.0; 3]; max = [0.0; 3]; }
+
+            buffer_views.push(json!({ "buffer": 0, "byteOffset": positions_offset, "byteLength": positions_length, "target": 34962 }));
+            accessors.push(json!({ "bufferView": buffer_views.len() - 1, "componentType": 5126, "count": points.len(), "type": "VEC3", "min": min, "max": max }));
+            let position_accessor = accessors.len() - 1;
+            buffer_views.push(json!({ "buffer": 0, "byteOffset": indices_offset, "byteLength": indices_length, "target": 34963 }));
+            accessors.push(json!({ "bufferView": buffer_views.len() - 1, "componentType": 5125, "count": mesh.trimesh.indices.len() * 3, "type": "SCALAR" }));
+            let indices_accessor = accessors.len() - 1;
+
+            let mut primitive = json!({ "attributes": { "POSITION": position_accessor }, "indices": indices_accessor, "mode": 4 });
+            if let Some(c) = mesh.color {
+                let mut material = json!({ "pbrMetallicRoughness": { "baseColorFactor": c, "metallicFactor": 0.0, "roughnessFactor": 0.8 } });
+                if c[3] < 1.0 { material["alphaMode"] = json!("BLEND"); }
+                materials.push(material);
+                primitive["material"] = json!(materials.len() - 1);
+            }
+            meshes.push(json!({ "name": mesh.name, "primitives": [primitive] }));
+        }
+
+        // node 0 rotates optima's z-up frame into glTF's y-up frame.
+        let children = self.children_of_each_node();
+        let roots: Vec<usize> = (0..self.nodes.len()).filter(|i| self.nodes[*i].parent.is_none()).map(|i| i + 1).collect();
+        let half_sqrt_2 = std::f64::consts::FRAC_1_SQRT_2;
+        let mut nodes = vec![json!({ "name": "optima_scene", "rotation": [-half_sqrt_2, 0.0, 0.0, half_sqrt_2], "children": roots })];
+        for (i, node) in self.nodes.iter().enumerate() {
+            let r = node.rotation;
+            let mut n = json!({ "name": node.name, "translation": node.translation, "rotation": [r[1], r[2], r[3], r[0]] });
+            if let Some(mesh) = node.mesh { n["mesh"] = json!(mesh); }
+            if !children[i].is_empty() { n["children"] = json!(children[i].iter().map(|x| x + 1).collect::<Vec<usize>>()); }
+            nodes.push(n);
+        }
+
+        let mut gltf = json!({
+            "asset": { "version": "2.0", "generator": "optima" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": nodes,
+            "meshes": meshes,
+            "accessors": accessors,
+            "bufferViews": buffer_views
+        });
+        if !materials.is_empty() { gltf["materials"] = json!(materials); }
+
+        (gltf, buffer)
+    }
+    fn children_of_each_node(&self) -> Vec<Vec<usize>> {
+        let mut out = vec![vec![]; self.nodes.len()];
+        self.nodes.iter().enumerate().for_each(|(i, x)| { if let Some(parent) = x.parent { out[parent].push(i); } });
+        out
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OSceneExportMesh {
+    pub name: String,
+    pub trimesh: OTriMesh,
+    /// rgba in [0, 1].
+    pub color: Option<[f64; 4]>
+}
+
+#[derive(Clone, Debug)]
+pub struct OSceneExportNode {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub translation: [f64; 3],
+    /// wxyz unit quaternion.
+    pub rotation: [f64; 4],
+    pub mesh: Option<usize>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OSceneExportFormat {
+    Gltf,
+    Glb,
+    Usda
+}
+impl OSceneExportFormat {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "gltf" => { Some(Self::Gltf) }
+            "glb" => { Some(Self::Glb) }
+            "usda" | "usd" => { Some(Self::Usda) }
+            _ => { None }
+        }
+    }
+}
+
+/// USD prim names must be identifiers and unique among siblings.
+fn unique_usd_name(name: &str, used: &mut HashSet<String>) -> String {
+    let mut base: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    if base.is_empty() || base.chars().next().unwrap().is_ascii_digit() { base = format!("_{}", base); }
+
+    let mut out = base.clone();
+    let mut i = 1;
+    while used.contains(&out) { out = format!("{}_{}", base, i); i += 1; }
+    used.insert(out.clone());
+    out
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | (b[2] as u32);
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> OTriMesh {
+        OTriMesh::new(vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]], vec![[0, 1, 2]])
+    }
+
+    #[test]
+    fn add_node_returns_the_node_idx_and_keeps_the_hierarchy() {
+        let mut scene = OSceneExport::new();
+        let mesh = scene.add_mesh("triangle", triangle(), None);
+        let root = scene.add_node("root", None, [0.0; 3], [1.0, 0.0, 0.0, 0.0], None).expect("error");
+        let child = scene.add_node("child", Some(root), [1.0, 2.0, 3.0], [1.0, 0.0, 0.0, 0.0], Some(mesh)).expect("error");
+
+        assert_eq!((root, child), (0, 1));
+        assert_eq!(scene.nodes()[child].parent, Some(root));
+        assert_eq!(scene.nodes()[child].mesh, Some(mesh));
+        assert_eq!(scene.children_of_each_node(), vec![vec![1], vec![]]);
+    }
+
+    fn two_node_scene() -> OSceneExport {
+        let mut scene = OSceneExport::new();
+        let mesh = scene.add_mesh("triangle", triangle(), Some([1.0, 0.0, 0.0, 0.5]));
+        let root = scene.add_node("link", None, [0.0; 3], [1.0, 0.0, 0.0, 0.0], None).expect("error");
+        scene.add_node("link", Some(root), [1.0, 2.0, 3.0], [1.0, 0.0, 0.0, 0.0], Some(mesh)).expect("error");
+        scene
+    }
+
+    #[test]
+    fn append_offsets_the_nodes_and_meshes_of_the_other_scene() {
+        let mut scene = two_node_scene();
+        let idxs = scene.append(&two_node_scene(), Some(1)).expect("error");
+
+        assert_eq!(idxs, vec![2, 3]);
+        assert_eq!(scene.nodes()[2].parent, Some(1));
+        assert_eq!(scene.nodes()[3].parent, Some(2));
+        assert_eq!(scene.nodes()[3].mesh, Some(1));
+        assert_eq!(scene.meshes().len(), 2);
+    }
+
+    #[test]
+    fn append_rejects_a_missing_parent() {
+        let mut scene = two_node_scene();
+        assert!(matches!(scene.append(&two_node_scene(), Some(5)), Err(OptimaError::IdxOutOfBounds { idx: 5, len: 2, .. })));
+        assert_eq!((scene.nodes().len(), scene.meshes().len()), (2, 1));
+    }
+
+    #[test]
+    fn gltf_string_has_a_y_up_root_and_an_embedded_buffer() {
+        let gltf: serde_json::Value = serde_json::from_str(&two_node_scene().to_gltf_string()).expect("error");
+
+        assert_eq!(gltf["asset"]["version"], "2.0");
+        assert_eq!(gltf["scenes"][0]["nodes"], json!([0]));
+        let nodes = gltf["nodes"].as_array().expect("error");
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[0]["children"], json!([1]));
+        assert_eq!(nodes[1]["children"], json!([2]));
+        assert_eq!(nodes[2]["mesh"], json!(0));
+        assert_eq!(nodes[2]["translation"], json!([1.0, 2.0, 3.0]));
+
+        // three f32 points and three u32 indices.
+        assert_eq!(gltf["buffers"][0]["byteLength"], json!(48));
+        let uri = gltf["buffers"][0]["uri"].as_str().expect("error");
+        assert_eq!(uri.strip_prefix("data:application/octet-stream;base64,").map(|x| x.len()), Some(64));
+        assert_eq!(gltf["accessors"][0]["max"], json!([1.0, 1.0, 0.0]));
+        assert_eq!(gltf["materials"][0]["pbrMetallicRoughness"]["baseColorFactor"], json!([1.0, 0.0, 0.0, 0.5]));
+        assert_eq!(gltf["materials"][0]["alphaMode"], "BLEND");
+    }
+
+    #[test]
+    fn glb_bytes_have_a_valid_header_and_aligned_chunks() {
+        let bytes = two_node_scene().to_glb_bytes();
+        let u32_at = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]) as usize;
+
+        assert_eq!(&bytes[0..4], b"glTF");
+        assert_eq!(u32_at(4), 2);
+        assert_eq!(u32_at(8), bytes.len());
+
+        let json_length = u32_at(12);
+        assert_eq!(&bytes[16..20], b"JSON");
+        assert_eq!(json_length % 4, 0);
+        let gltf: serde_json::Value = serde_json::from_slice(&bytes[20..20 + json_length]).expect("error");
+        assert!(gltf["buffers"][0].get("uri").is_none());
+
+        let bin_start = 20 + json_length;
+        assert_eq!(&bytes[bin_start + 4..bin_start + 8], b"BIN\0");
+        assert_eq!(u32_at(bin_start), 48);
+        assert_eq!(bin_start + 8 + 48, bytes.len());
+    }
+
+    #[test]
+    fn usda_string_is_z_up_with_unique_prim_names() {
+        let mut scene = two_node_scene();
+        scene.add_node("link", None, [0.0; 3], [1.0, 0.0, 0.0, 0.0], None).expect("error");
+        let usda = scene.to_usda_string();
+
+        assert!(usda.starts_with("#usda 1.0"));
+        assert!(usda.contains("upAxis = \"Z\""));
+        // the two roots share a name, so the second gets a suffix; the child is under another parent.
+        assert_eq!(usda.matches("def Xform \"link\"").count(), 2);
+        assert_eq!(usda.matches("def Xform \"link_1\"").count(), 1);
+        assert!(usda.contains("double3 xformOp:translate = (1, 2, 3)"));
+        assert!(usda.contains("point3f[] points = [(0, 0, 0), (1, 0, 0), (0, 1, 0)]"));
+        assert!(usda.contains("int[] faceVertexIndices = [0, 1, 2]"));
+        assert!(usda.contains("float[] primvars:displayOpacity = [0.5]"));
+    }
+
+    #[test]
+    fn base64_encode_pads_partial_chunks() {
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+        assert_eq!(base64_encode(b"Ma"), "TWE=");
+        assert_eq!(base64_encode(b"M"), "TQ==");
+        assert_eq!(base64_encode(b""), "");
+    }
+
+    #[test]
+    fn add_node_rejects_a_missing_parent_or_mesh() {
+        let mut scene = OSceneExport::new();
+        assert!(matches!(scene.add_node("orphan", Some(0), [0.0; 3], [1.0, 0.0, 0.0, 0.0], None), Err(OptimaError::IdxOutOfBounds { idx: 0, len: 0, .. })));
+        assert!(matches!(scene.add_node("no_mesh", None, [0.0; 3], [1.0, 0.0, 0.0, 0.0], Some(2)), Err(OptimaError::IdxOutOfBounds { idx: 2, len: 0, .. })));
+        assert!(scene.nodes().is_empty());
+    }
+}
@@ -1,4 +1,10 @@
 
 pub mod optima_3d_vec;
 pub mod optima_3d_rotation;
+pub mod optima_3d_rotation_spline;
 pub mod optima_3d_pose;
+pub mod optima_3d_twist;
+pub mod optima_3d_pose_chain;
+pub mod optima_3d_pose_covariance;
+pub mod optima_3d_transform_tree;
+pub mod optima_2d_pose;
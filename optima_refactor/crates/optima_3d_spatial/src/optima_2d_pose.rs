@@ -0,0 +1,296 @@
+use std::borrow::Cow;
+use std::fmt::Debug;
+use ad_trait::{AD, SerdeAD};
+use as_any::AsAny;
+use nalgebra::{Isometry2, Vector2, Vector3};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use crate::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use crate::optima_3d_rotation::{O3DRotation, ScaledAxis};
+use crate::optima_3d_vec::O3DVec;
+
+#[derive(Clone, Debug, Copy, Eq, PartialEq)]
+pub enum O2DPoseType {
+    TranslationAngle, NalgebraIsometry2
+}
+
+pub trait O2DPoseCategory:
+    Clone + Debug + Serialize + for<'a> Deserialize<'a> + Send + Sync
+{
+    type P<T: AD> : O2DPose<T>;
+}
+
+/// A planar (SE(2)) pose, i.e., a translation in the xy plane and an angle about z, e.g., for the
+/// base of a mobile robot.  The Lie algebra vector is `[angle, v_x, v_y]`, with the rotational
+/// part first as in `O3DPose::ln`.
+pub trait O2DPose<T: AD> :
+    Clone + Debug + Serialize + for<'a> Deserialize<'a> + Send + Sync + AsAny
+{
+    type Category: O2DPoseCategory;
+
+    fn type_identifier() -> O2DPoseType;
+    fn identity() -> Self;
+    fn from_translation_and_angle(translation: &[T], angle: T) -> Self;
+    fn translation(&self) -> [T; 2];
+    /// in radians, in (-pi, pi].
+    fn angle(&self) -> T;
+    fn update_translation(&mut self, translation: &[T]);
+    fn update_angle(&mut self, angle: T);
+    fn mul(&self, other: &Self) -> Self;
+    #[inline(always)]
+    fn mul_by_point(&self, point: &[T]) -> [T; 2] {
+        let (s, c) = (self.angle().sin(), self.angle().cos());
+        let t = self.translation();
+        [c * point[0] - s * point[1] + t[0], s * point[0] + c * point[1] + t[1]]
+    }
+    fn inverse(&self) -> Self;
+    #[inline(always)]
+    fn displacement(&self, other: &Self) -> Self {
+        self.inverse().mul(other)
+    }
+    #[inline(always)]
+    fn magnitude(&self) -> T {
+        self.ln().norm()
+    }
+    #[inline(always)]
+    fn dis(&self, other: &Self) -> T {
+        self.displacement(other).ln().norm()
+    }
+    /// Translation is interpolated linearly and the angle along the shorter direction.
+    #[inline(always)]
+    fn interpolate(&self, to: &Self, t: T) -> Self {
+        let (a, b) = (self.translation(), to.translation());
+        let translation = [(T::one() - t) * a[0] + t * b[0], (T::one() - t) * a[1] + t * b[1]];
+        let angle = self.angle() + t * wrap_angle(to.angle() - self.angle());
+        Self::from_translation_and_angle(&translation, angle)
+    }
+    #[inline(always)]
+    fn ln(&self) -> Vector3<T> {
+        let theta = self.angle();
+        let t = self.translation();
+
+        // inverse of the left jacobian, [[a, b], [-b, a]].
+        let a = if theta.abs() < T::constant(0.00000001) {
+            T::one() - theta.powi(2) / T::constant(12.0)
+        } else {
+            (theta * theta.sin()) / (T::constant(2.0) * (T::one() - theta.cos()))
+        };
+        let b = theta / T::constant(2.0);
+
+        Vector3::new(theta, a * t[0] + b * t[1], -b * t[0] + a * t[1])
+    }
+    #[inline(always)]
+    fn exp(lie: &Vector3<T>) -> Self {
+        let theta = lie[0];
+
+        let (a, b) = if theta.abs() < T::constant(0.00000001) {
+            (T::one() - theta.powi(2) / T::constant(6.0), theta / T::constant(2.0) - theta.powi(3) / T::constant(24.0))
+        } else {
+            (theta.sin() / theta, (T::one() - theta.cos()) / theta)
+        };
+
+        let translation = [a * lie[1] - b * lie[2], b * lie[1] + a * lie[2]];
+        Self::from_translation_and_angle(&translation, wrap_angle(theta))
+    }
+    #[inline(always)]
+    fn o2dpose_to_constant_ads(&self) -> Self {
+        let t = self.translation();
+        Self::from_translation_and_angle(&[T::constant(t[0].to_constant()), T::constant(t[1].to_constant())], T::constant(self.angle().to_constant()))
+    }
+    fn o2dpose_to_other_generic_category<T2: AD, C: O2DPoseCategory>(&self) -> C::P<T2> {
+        let t = self.translation();
+        C::P::from_translation_and_angle(&[T2::constant(t[0].to_constant()), T2::constant(t[1].to_constant())], T2::constant(self.angle().to_constant()))
+    }
+    fn o2dpose_to_other_ad_type<T2: AD>(&self) -> <Self::Category as O2DPoseCategory>::P::<T2> {
+        self.o2dpose_to_other_generic_category::<T2, Self::Category>()
+    }
+    #[inline(always)]
+    fn o2dpose_downcast_or_convert<P: O2DPose<T>>(&self) -> Cow<P> {
+        let downcast = self.as_any().downcast_ref::<P>();
+        match downcast {
+            Some(d) => { Cow::Borrowed(d) }
+            None => {
+                let out = P::from_translation_and_angle(&self.translation(), self.angle());
+                Cow::Owned(out)
+            }
+        }
+    }
+    /// The same pose in 3D, in the z = 0 plane and rotated about z.
+    fn o2dpose_to_o3dpose<T2: AD, C: O3DPoseCategory>(&self) -> C::P<T2> {
+        let t = self.translation();
+        C::P::from_constructors(&[T2::constant(t[0].to_constant()), T2::constant(t[1].to_constant()), T2::zero()], &ScaledAxis([T2::zero(), T2::zero(), T2::constant(self.angle().to_constant())]))
+    }
+    /// Projects a 3D pose onto the xy plane, keeping its yaw.
+    fn from_o3dpose<P: O3DPose<T>>(pose: &P) -> Self {
+        let t = pose.translation().o3dvec_as_slice();
+        let yaw = pose.rotation().euler_angles()[2];
+        Self::from_translation_and_angle(&[t[0], t[1]], yaw)
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct O2DTranslationAngle<T: AD> {
+    #[serde(deserialize_with = "Vector2::<T>::deserialize")]
+    translation: Vector2<T>,
+    #[serde_as(as = "SerdeAD<T>")]
+    angle: T
+}
+impl<T: AD> O2DPose<T> for O2DTranslationAngle<T> {
+    type Category = O2DPoseCategoryTranslationAngle;
+
+    #[inline(always)]
+    fn type_identifier() -> O2DPoseType {
+        O2DPoseType::TranslationAngle
+    }
+
+    #[inline(always)]
+    fn identity() -> Self {
+        Self { translation: Vector2::zeros(), angle: T::zero() }
+    }
+
+    #[inline(always)]
+    fn from_translation_and_angle(translation: &[T], angle: T) -> Self {
+        Self { translation: Vector2::new(translation[0], translation[1]), angle: wrap_angle(angle) }
+    }
+
+    #[inline(always)]
+    fn translation(&self) -> [T; 2] {
+        [self.translation[0], self.translation[1]]
+    }
+
+    #[inline(always)]
+    fn angle(&self) -> T {
+        self.angle
+    }
+
+    #[inline(always)]
+    fn update_translation(&mut self, translation: &[T]) {
+        self.translation = Vector2::new(translation[0], translation[1]);
+    }
+
+    #[inline(always)]
+    fn update_angle(&mut self, angle: T) {
+        self.angle = wrap_angle(angle);
+    }
+
+    #[inline(always)]
+    fn mul(&self, other: &Self) -> Self {
+        let translation = self.mul_by_point(other.translation.as_slice());
+        Self::from_translation_and_angle(&translation, self.angle + other.angle)
+    }
+
+    #[inline(always)]
+    fn inverse(&self) -> Self {
+        let (s, c) = (self.angle.sin(), self.angle.cos());
+        let t = &self.translation;
+        Self::from_translation_and_angle(&[-(c * t[0] + s * t[1]), s * t[0] - c * t[1]], -self.angle)
+    }
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct O2DPoseCategoryTranslationAngle;
+impl O2DPoseCategory for O2DPoseCategoryTranslationAngle {
+    type P<T: AD> = O2DTranslationAngle<T>;
+}
+
+impl<T: AD> O2DPose<T> for Isometry2<T> {
+    type Category = O2DPoseCategoryIsometry2;
+
+    #[inline(always)]
+    fn type_identifier() -> O2DPoseType {
+        O2DPoseType::NalgebraIsometry2
+    }
+
+    #[inline(always)]
+    fn identity() -> Self {
+        Isometry2::identity()
+    }
+
+    #[inline(always)]
+    fn from_translation_and_angle(translation: &[T], angle: T) -> Self {
+        Isometry2::new(Vector2::new(translation[0], translation[1]), angle)
+    }
+
+    #[inline(always)]
+    fn translation(&self) -> [T; 2] {
+        [self.translation.vector[0], self.translation.vector[1]]
+    }
+
+    #[inline(always)]
+    fn angle(&self) -> T {
+        wrap_angle(self.rotation.angle())
+    }
+
+    #[inline(always)]
+    fn update_translation(&mut self, translation: &[T]) {
+        self.translation = Vector2::new(translation[0], translation[1]).into();
+    }
+
+    #[inline(always)]
+    fn update_angle(&mut self, angle: T) {
+        *self = Isometry2::new(self.translation.vector, angle);
+    }
+
+    #[inline(always)]
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+
+    #[inline(always)]
+    fn inverse(&self) -> Self {
+        self.inverse()
+    }
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct O2DPoseCategoryIsometry2;
+impl O2DPoseCategory for O2DPoseCategoryIsometry2 {
+    type P<T: AD> = Isometry2<T>;
+}
+
+/// Wraps an angle into (-pi, pi].
+#[inline(always)]
+pub fn wrap_angle<T: AD>(angle: T) -> T {
+    let out = angle.sin().atan2(angle.cos());
+    // atan2 returns -pi when the sine rounds to -0.0 or a tiny negative value; that is moved to pi
+    // by adding 2 pi rather than replaced by a constant, so derivatives still pass through.
+    if out <= -T::constant(std::f64::consts::PI) { out + T::constant(2.0 * std::f64::consts::PI) } else { out }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use super::*;
+
+    #[test]
+    fn wrap_angle_maps_minus_pi_to_pi() {
+        assert_eq!(wrap_angle(PI), PI);
+        assert_eq!(wrap_angle(-PI), PI);
+        assert_eq!(wrap_angle(3.0 * PI), PI);
+        assert_eq!(wrap_angle(-3.0 * PI), PI);
+    }
+
+    #[test]
+    fn wrap_angle_stays_in_range_and_keeps_the_direction() {
+        for i in -40..=40 {
+            let angle = i as f64 * 0.37;
+            let wrapped = wrap_angle(angle);
+            assert!(wrapped > -PI && wrapped <= PI, "{} wrapped to {}", angle, wrapped);
+            assert!((wrapped.sin() - angle.sin()).abs() < 1e-12 && (wrapped.cos() - angle.cos()).abs() < 1e-12, "{} wrapped to {}", angle, wrapped);
+        }
+        assert!((wrap_angle(1.5 * PI) + 0.5 * PI).abs() < 1e-12);
+        assert!((wrap_angle(0.25) - 0.25).abs() < 1e-15);
+    }
+
+    #[test]
+    fn angles_of_both_pose_types_are_in_range() {
+        let a = O2DTranslationAngle::from_translation_and_angle(&[1.0, 2.0], -PI);
+        let b = Isometry2::from_translation_and_angle(&[1.0, 2.0], -PI);
+        assert_eq!(a.angle(), PI);
+        assert_eq!(b.angle(), PI);
+
+        let c = O2DTranslationAngle::from_translation_and_angle(&[0.0, 0.0], 0.75 * PI);
+        assert!((c.mul(&c).angle() + 0.5 * PI).abs() < 1e-12);
+        let d = Isometry2::from_translation_and_angle(&[0.0, 0.0], 0.75 * PI);
+        assert!((d.mul(&d).angle() + 0.5 * PI).abs() < 1e-12);
+    }
+}
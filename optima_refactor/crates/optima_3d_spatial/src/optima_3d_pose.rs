@@ -4,18 +4,21 @@ use std::fmt::Debug;
 use std::marker::PhantomData;
 use ad_trait::AD;
 use as_any::AsAny;
-use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion, Vector3, Vector6};
+use nalgebra::{Isometry3, IsometryMatrix3, Matrix3, Matrix4, Quaternion, Rotation3, Translation3, UnitQuaternion, Vector3, Vector6};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde::de::{SeqAccess, Visitor};
-use serde::ser::SerializeTuple;
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeStruct, SerializeTuple};
 use serde_with::{DeserializeAs, SerializeAs};
+use optima_interpolation::get_interpolation_range;
+use optima_interpolation::time_parameterization::velocity_limited_segment_duration;
+use optima_error::OptimaError;
 use optima_linalg::OVec;
 use crate::optima_3d_vec::{O3DVec, O3DVecCategoryArr};
 use crate::optima_3d_rotation::{O3DRotation, O3DRotationConstructor, ScaledAxis};
 
-#[derive(Clone, Debug, Copy, Eq, PartialEq)]
+#[derive(Clone, Debug, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum O3DPoseType {
-    ImplicitDualQuaternion, NalgebraIsometry3
+    ImplicitDualQuaternion, NalgebraIsometry3, NalgebraIsometryMatrix3, Matrix4
 }
 
 pub trait O3DPoseCategory:
@@ -82,6 +85,68 @@ pub trait O3DPose<T: AD> :
         let new_ln = ln.ovec_scalar_div(&n).ovec_scalar_mul(&n.min(max_translation_and_rotation));
         self.mul(&Self::exp(&new_ln))
     }
+    /// Weighted average of `poses` (weights need not sum to one): translations are averaged
+    /// directly and rotations by normalizing the weighted sum of their quaternions, after flipping
+    /// each onto the same hemisphere as the first.  Close to the true rotation average when the
+    /// rotations are close together, e.g., for smoothing a noisy pose stream.
+    ///
+    /// Returns an error if `poses` and `weights` differ in length, if there are no poses, or if the
+    /// weights are negative or sum to zero.
+    fn weighted_average(poses: &[Self], weights: &[T]) -> Result<Self, OptimaError> {
+        check_weighted_average_inputs(poses.len(), weights)?;
+
+        let mut total = T::zero();
+        let mut translation = [T::zero(); 3];
+        let mut quaternion = [T::zero(); 4];
+        let q0 = poses[0].rotation().unit_quaternion_as_wxyz_slice();
+        for (pose, weight) in poses.iter().zip(weights.iter()) {
+            total += *weight;
+            let t = pose.translation().o3dvec_as_slice();
+            for i in 0..3 { translation[i] += *weight * t[i]; }
+            let q = pose.rotation().unit_quaternion_as_wxyz_slice();
+            let d = q[0] * q0[0] + q[1] * q0[1] + q[2] * q0[2] + q[3] * q0[3];
+            let w = if d < T::zero() { -*weight } else { *weight };
+            for i in 0..4 { quaternion[i] += w * q[i]; }
+        }
+        for i in 0..3 { translation[i] /= total; }
+
+        let rotation = Self::RotationType::from_unit_quaternion_as_wxyz_slice(&quaternion);
+        Ok(Self::from_translation_and_rotation(&translation, &rotation))
+    }
+    fn weighted_average_unchecked(poses: &[Self], weights: &[T]) -> Self {
+        Self::weighted_average(poses, weights).expect("error")
+    }
+    /// Time to move from `self` to `to` along `interpolate` with the translation moving at most
+    /// `max_linear_velocity` and the rotation at most `max_angular_velocity` (rad/s).
+    fn velocity_limited_duration(&self, to: &Self, max_linear_velocity: T, max_angular_velocity: T) -> Result<T, OptimaError> {
+        let linear_distance = to.translation().o3dvec_sub(self.translation()).o3dvec_to_other_generic_category::<T, O3DVecCategoryArr>().norm();
+        let angular_distance = self.rotation().displacement(to.rotation()).angle();
+        velocity_limited_segment_duration(&[linear_distance, angular_distance], &[max_linear_velocity, max_angular_velocity])
+    }
+    /// `interpolate` from `self` to `to` as an executable motion: (time, pose) samples every
+    /// `time_step` seconds, taking `velocity_limited_duration`.  The last sample is always `to`.
+    fn velocity_limited_interpolation(&self, to: &Self, max_linear_velocity: T, max_angular_velocity: T, time_step: T) -> Result<Vec<(T, Self)>, OptimaError> {
+        if time_step <= T::zero() { return Err(OptimaError::InvalidInput("time step must be positive.".to_string())); }
+        let duration = self.velocity_limited_duration(to, max_linear_velocity, max_angular_velocity)?;
+        if duration <= T::zero() { return Ok(vec![(T::zero(), to.clone())]); }
+
+        let mut out: Vec<(T, Self)> = get_interpolation_range(T::zero(), duration, time_step).into_iter().map(|t| (t, self.interpolate(to, (t / duration).min(T::one())))).collect();
+        // the range ends within a small tolerance of the duration, so pin the last sample to it.
+        *out.last_mut().expect("error") = (duration, to.clone());
+        Ok(out)
+    }
+    /// `velocity_limited_interpolation` through each of `waypoints` in turn, with times continuing
+    /// from one segment to the next.
+    fn velocity_limited_path(waypoints: &[Self], max_linear_velocity: T, max_angular_velocity: T, time_step: T) -> Result<Vec<(T, Self)>, OptimaError> {
+        if waypoints.is_empty() { return Ok(vec![]); }
+        let mut out = vec![(T::zero(), waypoints[0].clone())];
+        for segment in waypoints.windows(2) {
+            let start_time = out.last().expect("error").0;
+            let samples = segment[0].velocity_limited_interpolation(&segment[1], max_linear_velocity, max_angular_velocity, time_step)?;
+            out.extend(samples.into_iter().skip(1).map(|(t, pose)| (start_time + t, pose)));
+        }
+        Ok(out)
+    }
     #[inline(always)]
     fn o3dpose_to_constant_ads(&self) -> Self {
         let translation = self.translation().o3dvec_to_constant_ads();
@@ -245,6 +310,33 @@ impl<T: AD> O3DPose<T> for ImplicitDualQuaternion<T>
         let (t, r) = generic_pose_exp(&lie);
         Self::from_translation_and_rotation(&t, &r)
     }
+
+    /// Dual quaternion linear blending: the weighted sum of the poses' dual quaternions (flipped
+    /// onto the same hemisphere as the first), normalized.
+    fn weighted_average(poses: &[Self], weights: &[T]) -> Result<Self, OptimaError> {
+        check_weighted_average_inputs(poses.len(), weights)?;
+
+        let zero = Quaternion::new(T::zero(), T::zero(), T::zero(), T::zero());
+        let (mut real, mut dual) = (zero.clone(), zero);
+        let q0 = poses[0].rotation.quaternion().clone();
+        for (pose, weight) in poses.iter().zip(weights.iter()) {
+            let qr = pose.rotation.quaternion().clone();
+            let qd = Quaternion::from_imag(pose.translation.clone()) * qr.clone() * T::constant(0.5);
+            let w = if qr.coords.dot(&q0.coords) < T::zero() { -*weight } else { *weight };
+            real = real + qr * w;
+            dual = dual + qd * w;
+        }
+
+        let n = real.norm();
+        let real = real / n;
+        let dual = dual / n;
+        let translation = (dual * real.conjugate() * T::constant(2.0)).imag();
+
+        Ok(Self {
+            translation,
+            rotation: UnitQuaternion::new_unchecked(real)
+        })
+    }
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct O3DPoseCategoryImplicitDualQuaternion;
@@ -349,6 +441,308 @@ impl O3DPoseCategory for O3DPoseCategoryIsometry3 {
     type P<T: AD> = Isometry3<T>;
 }
 
+/// Isometry with a `Rotation3` instead of a `UnitQuaternion`, so rotations are composed as plain
+/// matrix products with no quaternion normalization in AD graphs.
+impl<T: AD> O3DPose<T> for IsometryMatrix3<T> {
+    type Category = O3DPoseCategoryRotationMatrix;
+    type RotationType = Rotation3<T>;
+    type LieAlgebraType = Vector6<T>;
+
+    fn type_identifier() -> O3DPoseType {
+        O3DPoseType::NalgebraIsometryMatrix3
+    }
+
+    #[inline(always)]
+    fn identity() -> Self {
+        IsometryMatrix3::identity()
+    }
+
+    fn from_translation_and_rotation<V: O3DVec<T>, R: O3DRotation<T>>(translation: &V, rotation: &R) -> Self {
+        let rotation = Rotation3::from_matrix_unchecked(Matrix3::from_column_slice(&rotation.rotation_matrix_as_column_major_slice()));
+        IsometryMatrix3::from_parts(Translation3::new(translation.x(), translation.y(), translation.z()), rotation)
+    }
+
+    fn from_constructors<V: O3DVec<T>, RC: O3DRotationConstructor<T, Self::RotationType>>(translation: &V, rotation_constructor: &RC) -> Self {
+        IsometryMatrix3::from_parts(Translation3::new(translation.x(), translation.y(), translation.z()), rotation_constructor.construct())
+    }
+
+    #[inline(always)]
+    fn translation(&self) -> &Vector3<T> {
+        &self.translation.vector
+    }
+
+    #[inline(always)]
+    fn rotation(&self) -> &Rotation3<T> {
+        &self.rotation
+    }
+
+    fn update_translation(&mut self, translation: &[T]) {
+        self.translation = Vector3::from_column_slice(translation).into();
+    }
+
+    fn update_rotation_constructor<RC: O3DRotationConstructor<T, Self::RotationType>>(&mut self, rotation: &RC) {
+        self.rotation = rotation.construct();
+    }
+
+    fn update_rotation_native(&mut self, rotation: &Self::RotationType) {
+        self.rotation = rotation.clone();
+    }
+
+    fn update_rotation_direct<R: O3DRotation<T>>(&mut self, rotation: &R) {
+        self.rotation = Rotation3::from_matrix_unchecked(Matrix3::from_column_slice(&rotation.rotation_matrix_as_column_major_slice()));
+    }
+
+    #[inline(always)]
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+
+    #[inline(always)]
+    fn inverse(&self) -> Self {
+        self.inverse()
+    }
+
+    #[inline(always)]
+    fn displacement(&self, other: &Self) -> Self {
+        self.inverse() * other
+    }
+
+    #[inline(always)]
+    fn magnitude(&self) -> T {
+        self.ln().norm()
+    }
+
+    #[inline(always)]
+    fn dis(&self, other: &Self) -> T {
+        self.displacement(other).ln().norm()
+    }
+
+    #[inline(always)]
+    fn interpolate(&self, to: &Self, t: T) -> Self {
+        let rotation = rotation_matrix_slerp(&self.rotation, &to.rotation, t);
+        let translation = (T::one() - t).mul_by_nalgebra_matrix_ref(&self.translation.vector) + t.mul_by_nalgebra_matrix_ref(&to.translation.vector);
+        IsometryMatrix3::from_parts(translation.into(), rotation)
+    }
+
+    #[inline(always)]
+    fn ln(&self) -> Self::LieAlgebraType {
+        rotation_matrix_pose_ln(&self.translation.vector, &self.rotation)
+    }
+
+    #[inline(always)]
+    fn exp(lie: &Self::LieAlgebraType) -> Self {
+        let (t, r) = rotation_matrix_pose_exp(&lie);
+        IsometryMatrix3::from_parts(t.into(), r)
+    }
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct O3DPoseCategoryRotationMatrix;
+impl O3DPoseCategory for O3DPoseCategoryRotationMatrix {
+    type P<T: AD> = IsometryMatrix3<T>;
+}
+
+/// A pose stored as a homogeneous 4x4 matrix, for interfacing with graphics pipelines and external
+/// libraries that expect one (see `OMatrix4Pose::matrix`).  Since `O3DPose` hands out its
+/// translation and rotation by reference, they are kept alongside the matrix and updated with it.
+///
+/// The rotation block is re-orthonormalized after every composition, so rounding errors do not
+/// build up over long chains of products.
+#[derive(Clone, Debug)]
+pub struct OMatrix4Pose<T: AD> {
+    matrix: Matrix4<T>,
+    translation: Vector3<T>,
+    rotation: Rotation3<T>
+}
+impl<T: AD> OMatrix4Pose<T> {
+    /// How far (entrywise) a matrix may be from a rigid transform and still be accepted by
+    /// `from_matrix`.
+    pub const TOLERANCE: f64 = 1e-6;
+
+    /// Returns an error unless the top left 3x3 block is a rotation matrix (orthonormal, with
+    /// determinant 1) and the bottom row is `[0, 0, 0, 1]`, up to `TOLERANCE`.
+    pub fn from_matrix(matrix: Matrix4<T>) -> Result<Self, OptimaError> {
+        let m = matrix.map(|x| x.to_constant());
+        let bottom_row = [m[(3, 0)], m[(3, 1)], m[(3, 2)], m[(3, 3)] - 1.0];
+        if bottom_row.iter().any(|x| x.abs() > Self::TOLERANCE) {
+            return Err(OptimaError::InvalidInput(format!("the bottom row of a pose matrix has to be [0, 0, 0, 1], not {:?}.", [m[(3, 0)], m[(3, 1)], m[(3, 2)], m[(3, 3)]])));
+        }
+        let r = m.fixed_view::<3, 3>(0, 0).into_owned();
+        if (r.transpose() * r - Matrix3::identity()).amax() > Self::TOLERANCE {
+            return Err(OptimaError::InvalidInput("the rotation block of a pose matrix is not orthonormal.".to_string()));
+        }
+        if (r.determinant() - 1.0).abs() > Self::TOLERANCE {
+            return Err(OptimaError::InvalidInput("the rotation block of a pose matrix is a reflection.".to_string()));
+        }
+
+        let translation = Vector3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
+        let rotation = Rotation3::from_matrix_unchecked(matrix.fixed_view::<3, 3>(0, 0).into_owned());
+        Ok(Self::from_parts(translation, rotation))
+    }
+    pub fn from_matrix_unchecked(matrix: Matrix4<T>) -> Self {
+        Self::from_matrix(matrix).expect("error")
+    }
+    #[inline(always)]
+    pub fn matrix(&self) -> &Matrix4<T> {
+        &self.matrix
+    }
+    fn from_parts(translation: Vector3<T>, rotation: Rotation3<T>) -> Self {
+        let mut matrix = Matrix4::identity();
+        matrix.fixed_view_mut::<3, 3>(0, 0).copy_from(rotation.matrix());
+        matrix.fixed_view_mut::<3, 1>(0, 3).copy_from(&translation);
+        Self { matrix, translation, rotation }
+    }
+    fn update_matrix(&mut self) {
+        *self = Self::from_parts(self.translation, self.rotation.clone());
+    }
+}
+
+/// Gram-Schmidt on the columns of a nearly orthonormal matrix; the third column is rebuilt as the
+/// cross product of the first two, so the result is a proper rotation.
+fn orthonormalize_rotation_matrix<T: AD>(m: &Matrix3<T>) -> Rotation3<T> {
+    let (c0, c1): (Vector3<T>, Vector3<T>) = (m.column(0).into_owned(), m.column(1).into_owned());
+    let x = c0.normalize();
+    let y = (&c1 - x.scale(x.dot(&c1))).normalize();
+    let z = x.cross(&y);
+    Rotation3::from_matrix_unchecked(Matrix3::from_columns(&[x, y, z]))
+}
+impl<T: AD> O3DPose<T> for OMatrix4Pose<T> {
+    type Category = O3DPoseCategoryMatrix4;
+    type RotationType = Rotation3<T>;
+    type LieAlgebraType = Vector6<T>;
+
+    #[inline(always)]
+    fn type_identifier() -> O3DPoseType {
+        O3DPoseType::Matrix4
+    }
+
+    #[inline(always)]
+    fn identity() -> Self {
+        Self::from_parts(Vector3::zeros(), Rotation3::identity())
+    }
+
+    #[inline(always)]
+    fn from_translation_and_rotation<V: O3DVec<T>, R: O3DRotation<T>>(translation: &V, rotation: &R) -> Self {
+        let rotation = Rotation3::from_matrix_unchecked(Matrix3::from_column_slice(&rotation.rotation_matrix_as_column_major_slice()));
+        Self::from_parts(Vector3::from_column_slice(translation.o3dvec_as_slice()), rotation)
+    }
+
+    #[inline(always)]
+    fn from_constructors<V: O3DVec<T>, RC: O3DRotationConstructor<T, Self::RotationType>>(translation: &V, rotation_constructor: &RC) -> Self {
+        Self::from_parts(Vector3::from_column_slice(translation.o3dvec_as_slice()), rotation_constructor.construct())
+    }
+
+    #[inline(always)]
+    fn translation(&self) -> &Vector3<T> {
+        &self.translation
+    }
+
+    #[inline(always)]
+    fn rotation(&self) -> &Rotation3<T> {
+        &self.rotation
+    }
+
+    #[inline(always)]
+    fn update_translation(&mut self, translation: &[T]) {
+        self.translation = Vector3::from_column_slice(translation);
+        self.update_matrix();
+    }
+
+    #[inline(always)]
+    fn update_rotation_constructor<RC: O3DRotationConstructor<T, Rotation3<T>>>(&mut self, rotation: &RC) {
+        self.rotation = rotation.construct();
+        self.update_matrix();
+    }
+
+    #[inline(always)]
+    fn update_rotation_native(&mut self, rotation: &Rotation3<T>) {
+        self.rotation = rotation.clone();
+        self.update_matrix();
+    }
+
+    #[inline(always)]
+    fn update_rotation_direct<R: O3DRotation<T>>(&mut self, rotation: &R) {
+        self.rotation = Rotation3::from_matrix_unchecked(Matrix3::from_column_slice(&rotation.rotation_matrix_as_column_major_slice()));
+        self.update_matrix();
+    }
+
+    #[inline(always)]
+    fn mul(&self, other: &Self) -> Self {
+        let translation = &self.rotation * &other.translation + &self.translation;
+        let rotation = orthonormalize_rotation_matrix(&(self.rotation.matrix() * other.rotation.matrix()));
+        Self::from_parts(translation, rotation)
+    }
+
+    #[inline(always)]
+    fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        let translation = &rotation * -&self.translation;
+        Self::from_parts(translation, rotation)
+    }
+
+    #[inline(always)]
+    fn displacement(&self, other: &Self) -> Self {
+        O3DPose::inverse(self).mul(other)
+    }
+
+    #[inline(always)]
+    fn magnitude(&self) -> T {
+        O3DPose::ln(self).norm()
+    }
+
+    #[inline(always)]
+    fn dis(&self, other: &Self) -> T {
+        O3DPose::ln(&self.displacement(other)).norm()
+    }
+
+    #[inline(always)]
+    fn interpolate(&self, to: &Self, t: T) -> Self {
+        let rotation = rotation_matrix_slerp(&self.rotation, &to.rotation, t);
+        let translation = (T::one() - t).mul_by_nalgebra_matrix_ref(&self.translation) + t.mul_by_nalgebra_matrix_ref(&to.translation);
+        Self::from_parts(translation, rotation)
+    }
+
+    #[inline(always)]
+    fn ln(&self) -> Self::LieAlgebraType {
+        rotation_matrix_pose_ln(&self.translation, &self.rotation)
+    }
+
+    #[inline(always)]
+    fn exp(lie: &Self::LieAlgebraType) -> Self {
+        let (t, r) = rotation_matrix_pose_exp(&lie);
+        Self::from_parts(t, r)
+    }
+}
+impl<T: AD> O3DLieAlgebraPose<T> for OMatrix4Pose<T> {
+    type LnVecType = Vector6<T>;
+
+    #[inline(always)]
+    fn ln(&self) -> Vector6<T> {
+        <Self as O3DPose<T>>::ln(self)
+    }
+
+    #[inline(always)]
+    fn exp(ln_vec: &Vector6<T>) -> Self {
+        <Self as O3DPose<T>>::exp(ln_vec)
+    }
+}
+/// Only the matrix is written, as a column major array of 16 numbers.
+impl<T: AD> Serialize for OMatrix4Pose<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        self.matrix.serialize(serializer)
+    }
+}
+impl<'de, T: AD> Deserialize<'de> for OMatrix4Pose<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let matrix = Matrix4::<T>::deserialize(deserializer)?;
+        Self::from_matrix(matrix).map_err(<D::Error as serde::de::Error>::custom)
+    }
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct O3DPoseCategoryMatrix4;
+impl O3DPoseCategory for O3DPoseCategoryMatrix4 {
+    type P<T: AD> = OMatrix4Pose<T>;
+}
+
 pub trait O3DLieAlgebraPose<T: AD> : O3DPose<T> {
     type LnVecType;
 
@@ -364,6 +758,22 @@ pub struct ImplicitDualQuaternion<T: AD> {
     rotation: UnitQuaternion<T>
 }
 
+fn check_weighted_average_inputs<T: AD>(num_poses: usize, weights: &[T]) -> Result<(), OptimaError> {
+    if num_poses != weights.len() {
+        return Err(OptimaError::InvalidInput(format!("weighted average of {} poses was given {} weights.", num_poses, weights.len())));
+    }
+    if num_poses == 0 {
+        return Err(OptimaError::InvalidInput("weighted average of no poses.".to_string()));
+    }
+    if weights.iter().any(|x| x.to_constant() < 0.0 || !x.to_constant().is_finite()) {
+        return Err(OptimaError::InvalidInput("weighted average weights have to be finite and non-negative.".to_string()));
+    }
+    if weights.iter().all(|x| x.to_constant() == 0.0) {
+        return Err(OptimaError::InvalidInput("weighted average weights are all zero.".to_string()));
+    }
+    Ok(())
+}
+
 fn generic_pose_ln<T: AD>(translation: &Vector3<T>, rotation: &UnitQuaternion<T>) -> Vector6<T> {
     let h_v = Vector3::new(rotation.i, rotation.j, rotation.k);
     let s: T = h_v.norm();
@@ -374,6 +784,12 @@ fn generic_pose_ln<T: AD>(translation: &Vector3<T>, rotation: &UnitQuaternion<T>
     // let rot_vec_diff = a * h_v;
     let rot_vec_diff = a.mul_by_nalgebra_matrix_ref(&h_v);
 
+    pose_ln_from_half_angle(translation, &rot_vec_diff, phi, s, c)
+}
+
+/// The rest of `generic_pose_ln` once the rotation is known as `rot_vec_diff`, its scaled axis at
+/// half the angle, where `phi` is half the angle and `s` and `c` are its sine and cosine.
+fn pose_ln_from_half_angle<T: AD>(translation: &Vector3<T>, rot_vec_diff: &Vector3<T>, phi: T, s: T, c: T) -> Vector6<T> {
     let mu_r;
     let mu_d;
 
@@ -391,7 +807,7 @@ fn generic_pose_ln<T: AD>(translation: &Vector3<T>, rotation: &UnitQuaternion<T>
 
     let tmp = translation / T::constant(2.0);
 
-    let translation_diff = (mu_d * tmp.dot(&rot_vec_diff)).mul_by_nalgebra_matrix_ref(&rot_vec_diff) + mu_r.mul_by_nalgebra_matrix_ref(&tmp) + tmp.cross(&rot_vec_diff);
+    let translation_diff = (mu_d * tmp.dot(rot_vec_diff)).mul_by_nalgebra_matrix_ref(rot_vec_diff) + mu_r.mul_by_nalgebra_matrix_ref(&tmp) + tmp.cross(rot_vec_diff);
 
     let out_vec = Vector6::new(rot_vec_diff[0], rot_vec_diff[1], rot_vec_diff[2], translation_diff[0], translation_diff[1], translation_diff[2]);
 
@@ -402,10 +818,22 @@ fn generic_pose_exp<T: AD>(ln_vec: &Vector6<T>) -> (Vector3<T>, UnitQuaternion<T
     let w = Vector3::new(ln_vec[0], ln_vec[1], ln_vec[2]);
     let v = Vector3::new(ln_vec[3], ln_vec[4], ln_vec[5]);
 
+    let (c, mu_r, translation) = pose_exp_translation(&w, &v);
+
+    let h_v: Vector3<T> = mu_r.mul_by_nalgebra_matrix_ref(&w);
+    let quat_ = Quaternion::new(c, h_v[0], h_v[1], h_v[2]);
+    let rotation = UnitQuaternion::from_quaternion(quat_);
+
+    return (translation, rotation);
+}
+
+/// The translation of `generic_pose_exp`, along with the cosine of half the rotation angle and
+/// `mu_r` (the sine of half the angle over half the angle), which give the rotation.
+fn pose_exp_translation<T: AD>(w: &Vector3<T>, v: &Vector3<T>) -> (T, T, Vector3<T>) {
     let phi = w.norm();
     let s = phi.sin();
     let c = phi.cos();
-    let gamma = w.dot(&v);
+    let gamma = w.dot(v);
 
     let mu_r;
     let mu_d;
@@ -418,13 +846,73 @@ fn generic_pose_exp<T: AD>(ln_vec: &Vector6<T>) -> (Vector3<T>, UnitQuaternion<T
         mu_d = (T::constant(2.0) - c * (T::constant(2.0) * mu_r)) / phi.powi(2);
     }
 
-    let h_v: Vector3<T> = mu_r.mul_by_nalgebra_matrix_ref(&w);
-    let quat_ = Quaternion::new(c, h_v[0], h_v[1], h_v[2]);
-    let rotation = UnitQuaternion::from_quaternion(quat_);
+    let h_v: Vector3<T> = mu_r.mul_by_nalgebra_matrix_ref(w);
+    let translation = T::constant(2.0).mul_by_nalgebra_matrix_ref(&mu_r.mul_by_nalgebra_matrix_ref(&h_v.cross(v))) + (c * T::constant(2.0) * mu_r).mul_by_nalgebra_matrix_ref(v) + (mu_d * gamma).mul_by_nalgebra_matrix_ref(w);
 
-    let translation = T::constant(2.0).mul_by_nalgebra_matrix_ref(&mu_r.mul_by_nalgebra_matrix_ref(&h_v.cross(&v))) + (c * T::constant(2.0) * mu_r).mul_by_nalgebra_matrix_ref(&v) + (mu_d * gamma).mul_by_nalgebra_matrix_ref(&w);
+    (c, mu_r, translation)
+}
 
-    return (translation, rotation);
+/// `generic_pose_ln` for poses whose rotation is a matrix, without going through a quaternion.
+fn rotation_matrix_pose_ln<T: AD>(translation: &Vector3<T>, rotation: &Rotation3<T>) -> Vector6<T> {
+    let rot_vec_diff = rotation_matrix_ln(rotation) / T::constant(2.0);
+    let phi = rot_vec_diff.norm();
+    pose_ln_from_half_angle(translation, &rot_vec_diff, phi, phi.sin(), phi.cos())
+}
+
+/// `generic_pose_exp` for poses whose rotation is a matrix, without going through a quaternion.
+fn rotation_matrix_pose_exp<T: AD>(ln_vec: &Vector6<T>) -> (Vector3<T>, Rotation3<T>) {
+    let w = Vector3::new(ln_vec[0], ln_vec[1], ln_vec[2]);
+    let v = Vector3::new(ln_vec[3], ln_vec[4], ln_vec[5]);
+
+    let (_, _, translation) = pose_exp_translation(&w, &v);
+    (translation, rotation_matrix_exp(&(w * T::constant(2.0))))
+}
+
+/// The scaled axis (axis times angle, with the angle in [0, pi]) of a rotation matrix.  Near zero
+/// the skew symmetric part alone is used, and near pi, where it vanishes, the axis is read off the
+/// symmetric part instead.
+fn rotation_matrix_ln<T: AD>(rotation: &Rotation3<T>) -> Vector3<T> {
+    let r = rotation.matrix();
+    // sin(angle) times the axis.
+    let sin_axis = Vector3::new(r[(2, 1)] - r[(1, 2)], r[(0, 2)] - r[(2, 0)], r[(1, 0)] - r[(0, 1)]) / T::constant(2.0);
+    let sin_angle = sin_axis.norm();
+    let cos_angle = (r.trace() - T::one()) / T::constant(2.0);
+    let angle = sin_angle.atan2(cos_angle);
+
+    if sin_angle > T::constant(0.000001) {
+        return sin_axis * (angle / sin_angle);
+    }
+    if cos_angle > T::zero() {
+        // angle / sin(angle) ~ 1 + angle^2 / 6.
+        return sin_axis * (T::one() + angle.powi(2) / T::constant(6.0));
+    }
+
+    // near pi: (r + r^T) / 2 - cos(angle) I = (1 - cos(angle)) axis axis^T.
+    let outer = ((r + r.transpose()) / T::constant(2.0) - Matrix3::identity() * cos_angle) / (T::one() - cos_angle);
+    let k = if outer[(0, 0)] >= outer[(1, 1)] && outer[(0, 0)] >= outer[(2, 2)] { 0 } else if outer[(1, 1)] >= outer[(2, 2)] { 1 } else { 2 };
+    let axis: Vector3<T> = outer.column(k).into_owned() / outer[(k, k)].sqrt();
+    // the sign is only defined by the (tiny) skew symmetric part; at exactly pi both signs are the
+    // same rotation.
+    let axis = if axis.dot(&sin_axis) < T::zero() { -axis } else { axis };
+    axis * angle
+}
+
+/// Rodrigues' formula for the rotation matrix of a scaled axis.
+fn rotation_matrix_exp<T: AD>(scaled_axis: &Vector3<T>) -> Rotation3<T> {
+    let angle = scaled_axis.norm();
+    let (a, b) = if angle < T::constant(0.00000001) {
+        (T::one() - angle.powi(2) / T::constant(6.0) + angle.powi(4) / T::constant(120.0), T::constant(0.5) - angle.powi(2) / T::constant(24.0) + angle.powi(4) / T::constant(720.0))
+    } else {
+        (angle.sin() / angle, (T::one() - angle.cos()) / angle.powi(2))
+    };
+    let k = scaled_axis.cross_matrix();
+    Rotation3::from_matrix_unchecked(Matrix3::identity() + k * a + (k * k) * b)
+}
+
+/// Interpolates from `from` (at `t = 0`) to `to` (at `t = 1`) along the shorter arc.
+fn rotation_matrix_slerp<T: AD>(from: &Rotation3<T>, to: &Rotation3<T>, t: T) -> Rotation3<T> {
+    let displacement = Rotation3::from_matrix_unchecked(from.matrix().transpose() * to.matrix());
+    Rotation3::from_matrix_unchecked(from.matrix() * rotation_matrix_exp(&(rotation_matrix_ln(&displacement) * t)).matrix())
 }
 
 impl<T: AD> ImplicitDualQuaternion<T>
@@ -441,65 +929,174 @@ impl<T: AD> ImplicitDualQuaternion<T>
     }
 }
 
+/// Version written by `o3d_pose_custom_serialize`.
+pub const O3D_POSE_SERDE_VERSION: u32 = 1;
+
+/// Writes a pose as `{ version, pose_type, translation: [x, y, z], rotation: [w, x, y, z] }`.  The
+/// rotation is kept as a unit quaternion, so it round-trips without the wrap-around of scaled-axis
+/// form, and values are written as full precision f64s (derivative parts of AD types are not
+/// written).  See `o3d_pose_compact_serialize` for a shorter form.
 pub fn o3d_pose_custom_serialize<S, T: AD, P: O3DPose<T>>(value: &P, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-    let translation_slice = value.translation().o3dvec_as_slice();
-    let binding = value.rotation().scaled_axis_of_rotation();
-    let rotation_slice = binding.as_slice();
-    let slice_as_f64 = [
-        translation_slice[0].to_constant(),
-        translation_slice[1].to_constant(),
-        translation_slice[2].to_constant(),
-        rotation_slice[0].to_constant(),
-        rotation_slice[1].to_constant(),
-        rotation_slice[2].to_constant()
-    ];
-    let mut tuple = serializer.serialize_tuple(6)?;
-    for element in &slice_as_f64 {
+    let (translation, rotation) = o3d_pose_to_f64_parts(value);
+    let mut state = serializer.serialize_struct("O3DPose", 4)?;
+    state.serialize_field("version", &O3D_POSE_SERDE_VERSION)?;
+    state.serialize_field("pose_type", &P::type_identifier())?;
+    state.serialize_field("translation", &translation)?;
+    state.serialize_field("rotation", &rotation)?;
+    state.end()
+}
+
+/// Writes a pose as a tuple of 7 numbers, `(x, y, z, qw, qx, qy, qz)`.
+pub fn o3d_pose_compact_serialize<S, T: AD, P: O3DPose<T>>(value: &P, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+    let (translation, rotation) = o3d_pose_to_f64_parts(value);
+    let mut tuple = serializer.serialize_tuple(7)?;
+    for element in translation.iter().chain(rotation.iter()) {
         tuple.serialize_element(element)?;
     }
     tuple.end()
 }
 
+fn o3d_pose_to_f64_parts<T: AD, P: O3DPose<T>>(value: &P) -> ([f64; 3], [f64; 4]) {
+    let t = value.translation().o3dvec_as_slice();
+    let q = value.rotation().unit_quaternion_as_wxyz_slice();
+    ([t[0].to_constant(), t[1].to_constant(), t[2].to_constant()], [q[0].to_constant(), q[1].to_constant(), q[2].to_constant(), q[3].to_constant()])
+}
+
+fn o3d_pose_from_f64_parts<T: AD, P: O3DPose<T>>(translation: &[f64], rotation: &P::RotationType) -> P {
+    P::from_translation_and_rotation(&[T::constant(translation[0]), T::constant(translation[1]), T::constant(translation[2])], rotation)
+}
+
+const O3D_POSE_SERDE_FIELDS: [&str; 4] = ["version", "pose_type", "translation", "rotation"];
+
+/// Which form the visitor is asked for.  Human-readable formats can say what they hold, so any form
+/// is accepted there; binary formats are read field by field in the order they were written.
+#[derive(Clone, Copy)]
+enum O3DPoseSerdeForm {
+    Any, Versioned, Compact
+}
+
 struct O3dPoseMyVisitor<T2: AD, P2: O3DPose<T2>> {
+    form: O3DPoseSerdeForm,
     _phantom_data: PhantomData<(T2, P2)>
 }
+impl<T2: AD, P2: O3DPose<T2>> O3dPoseMyVisitor<T2, P2> {
+    fn new(form: O3DPoseSerdeForm) -> Self {
+        Self { form, _phantom_data: PhantomData::default() }
+    }
+}
 
 impl<'de, T2: AD, P2: O3DPose<T2>> Visitor<'de> for O3dPoseMyVisitor<T2, P2> {
     type Value = P2;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a tuple of size 6")
+        match self.form {
+            O3DPoseSerdeForm::Any => { formatter.write_str("a pose map, a tuple of 7 (translation and wxyz quaternion), or a tuple of 6 (translation and scaled axis)") }
+            O3DPoseSerdeForm::Versioned => { formatter.write_str("a pose with fields version, pose_type, translation, and rotation") }
+            O3DPoseSerdeForm::Compact => { formatter.write_str("a tuple of 7 (translation and wxyz quaternion)") }
+        }
     }
 
+    /// Compact tuples of 7, and the tuples of 6 (translation and scaled axis) written before poses
+    /// were versioned.  Binary formats also hand the versioned form over as a sequence.
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
     where
         A: SeqAccess<'de>,
     {
-        let x: f64 = seq.next_element().expect("error").expect("error");
-        let y: f64 = seq.next_element().expect("error").expect("error");
-        let z: f64 = seq.next_element().expect("error").expect("error");
-        let rx: f64 = seq.next_element().expect("error").expect("error");
-        let ry: f64 = seq.next_element().expect("error").expect("error");
-        let rz: f64 = seq.next_element().expect("error").expect("error");
-        let xad = T2::constant(x);
-        let yad = T2::constant(y);
-        let zad = T2::constant(z);
-        let rxad = T2::constant(rx);
-        let ryad = T2::constant(ry);
-        let rzad = T2::constant(rz);
+        if let O3DPoseSerdeForm::Versioned = self.form {
+            let version: u32 = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+            let pose_type: O3DPoseType = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+            let translation: [f64; 3] = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(2, &self))?;
+            let rotation: [f64; 4] = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(3, &self))?;
+            return o3d_pose_from_versioned_parts::<T2, P2, A::Error>(version, pose_type, &translation, &rotation);
+        }
+
+        let mut values: Vec<f64> = vec![];
+        while let Some(value) = seq.next_element::<f64>()? {
+            values.push(value);
+        }
 
-        let translation = [xad, yad, zad];
-        let rotation = P2::RotationType::from_scaled_axis_of_rotation(&[rxad, ryad, rzad]);
+        let rotation = match (values.len(), self.form) {
+            (6, O3DPoseSerdeForm::Any) => { P2::RotationType::from_scaled_axis_of_rotation(&[T2::constant(values[3]), T2::constant(values[4]), T2::constant(values[5])]) }
+            (7, _) => { P2::RotationType::from_unit_quaternion_as_wxyz_slice(&[T2::constant(values[3]), T2::constant(values[4]), T2::constant(values[5]), T2::constant(values[6])]) }
+            (n, _) => { return Err(serde::de::Error::invalid_length(n, &self)); }
+        };
 
-        Ok(P2::from_translation_and_rotation(&translation, &rotation))
+        Ok(o3d_pose_from_f64_parts::<T2, P2>(&values[0..3], &rotation))
     }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut version: Option<u32> = None;
+        let mut pose_type: Option<O3DPoseType> = None;
+        let mut translation: Option<[f64; 3]> = None;
+        let mut rotation: Option<[f64; 4]> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "version" => { version = Some(map.next_value()?); }
+                "pose_type" => { pose_type = Some(map.next_value()?); }
+                "translation" => { translation = Some(map.next_value()?); }
+                "rotation" => { rotation = Some(map.next_value()?); }
+                _ => { map.next_value::<serde::de::IgnoredAny>()?; }
+            }
+        }
+
+        let version = version.ok_or_else(|| serde::de::Error::missing_field("version"))?;
+        let pose_type = pose_type.ok_or_else(|| serde::de::Error::missing_field("pose_type"))?;
+        let translation = translation.ok_or_else(|| serde::de::Error::missing_field("translation"))?;
+        let rotation = rotation.ok_or_else(|| serde::de::Error::missing_field("rotation"))?;
+
+        o3d_pose_from_versioned_parts::<T2, P2, A::Error>(version, pose_type, &translation, &rotation)
+    }
+}
+
+/// Builds a `P` from the fields of the versioned form.  The pose is first built as the type it was
+/// written as (`pose_type`), then converted to `P` if that is a different type.
+fn o3d_pose_from_versioned_parts<T: AD, P: O3DPose<T>, E: serde::de::Error>(version: u32, pose_type: O3DPoseType, translation: &[f64; 3], rotation: &[f64; 4]) -> Result<P, E> {
+    if version > O3D_POSE_SERDE_VERSION { return Err(E::custom(format!("pose was written with serde version {}, but only versions up to {} can be read.", version, O3D_POSE_SERDE_VERSION))); }
+
+    let out = match pose_type {
+        O3DPoseType::ImplicitDualQuaternion => { o3d_pose_from_f64_quaternion_parts::<T, ImplicitDualQuaternion<T>>(translation, rotation).o3dpose_downcast_or_convert::<P>().into_owned() }
+        O3DPoseType::NalgebraIsometry3 => { o3d_pose_from_f64_quaternion_parts::<T, Isometry3<T>>(translation, rotation).o3dpose_downcast_or_convert::<P>().into_owned() }
+        O3DPoseType::NalgebraIsometryMatrix3 => { o3d_pose_from_f64_quaternion_parts::<T, IsometryMatrix3<T>>(translation, rotation).o3dpose_downcast_or_convert::<P>().into_owned() }
+        O3DPoseType::Matrix4 => { o3d_pose_from_f64_quaternion_parts::<T, OMatrix4Pose<T>>(translation, rotation).o3dpose_downcast_or_convert::<P>().into_owned() }
+    };
+
+    Ok(out)
 }
 
+fn o3d_pose_from_f64_quaternion_parts<T: AD, P: O3DPose<T>>(translation: &[f64; 3], rotation: &[f64; 4]) -> P {
+    let rotation = P::RotationType::from_unit_quaternion_as_wxyz_slice(&rotation.map(|x| T::constant(x)));
+    o3d_pose_from_f64_parts::<T, P>(translation, &rotation)
+}
+
+/// Reads a pose written by `o3d_pose_custom_serialize`.  In human-readable formats (e.g., json or
+/// ron), the compact form and the older scaled-axis form are read as well.  Binary formats (e.g.,
+/// MessagePack, bincode, or postcard) do not always say what they hold, so only the versioned form
+/// is read there; use `o3d_pose_compact_deserialize` for data written in the compact form.
 pub fn o3d_pose_custom_deserialize<'de, D, T: AD, P: O3DPose<T>>(deserializer: D) -> Result<P, D::Error>
 where
     D: Deserializer<'de>,
 {
-    deserializer.deserialize_tuple(6, O3dPoseMyVisitor::<T, P> { _phantom_data: PhantomData::default() })
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_any(O3dPoseMyVisitor::<T, P>::new(O3DPoseSerdeForm::Any))
+    } else {
+        deserializer.deserialize_struct("O3DPose", &O3D_POSE_SERDE_FIELDS, O3dPoseMyVisitor::<T, P>::new(O3DPoseSerdeForm::Versioned))
+    }
+}
+
+/// Reads a pose written by `o3d_pose_compact_serialize`.  Human-readable formats accept any form,
+/// as in `o3d_pose_custom_deserialize`.
+pub fn o3d_pose_compact_deserialize<'de, D, T: AD, P: O3DPose<T>>(deserializer: D) -> Result<P, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_any(O3dPoseMyVisitor::<T, P>::new(O3DPoseSerdeForm::Any))
+    } else {
+        deserializer.deserialize_tuple(7, O3dPoseMyVisitor::<T, P>::new(O3DPoseSerdeForm::Compact))
+    }
 }
 
 pub struct SerdeO3DPose<T: AD, P: O3DPose<T>>(pub P, PhantomData<T>);
@@ -513,4 +1110,308 @@ impl<'de, T: AD, P: O3DPose<T>> DeserializeAs<'de, P> for SerdeO3DPose<T, P> {
     fn deserialize_as<D>(deserializer: D) -> Result<P, D::Error> where D: Deserializer<'de> {
         o3d_pose_custom_deserialize(deserializer)
     }
-}
\ No newline at end of file
+}
+
+/// Same as `SerdeO3DPose`, but writes the compact tuple form (see `o3d_pose_compact_serialize`),
+/// e.g., for large pose lists.
+pub struct SerdeO3DPoseCompact<T: AD, P: O3DPose<T>>(pub P, PhantomData<T>);
+
+impl<T: AD, P: O3DPose<T>> SerializeAs<P> for SerdeO3DPoseCompact<T, P> {
+    fn serialize_as<S>(source: &P, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        o3d_pose_compact_serialize(source, serializer)
+    }
+}
+impl<'de, T: AD, P: O3DPose<T>> DeserializeAs<'de, P> for SerdeO3DPoseCompact<T, P> {
+    fn deserialize_as<D>(deserializer: D) -> Result<P, D::Error> where D: Deserializer<'de> {
+        o3d_pose_compact_deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use ad_trait::forward_ad::adfn::adfn;
+    use super::*;
+
+    fn test_pose<T: AD, P: O3DPose<T>>() -> P {
+        P::from_translation_and_rotation(&[T::constant(0.1), T::constant(-0.2), T::constant(0.3)], &P::RotationType::from_scaled_axis_of_rotation(&[T::constant(0.3), T::constant(0.2), T::constant(-0.1)]))
+    }
+
+    fn assert_same_pose<T: AD, P: O3DPose<T>>(a: &P, b: &P) {
+        let (ta, qa) = o3d_pose_to_f64_parts::<T, P>(a);
+        let (tb, qb) = o3d_pose_to_f64_parts::<T, P>(b);
+        ta.iter().zip(tb.iter()).for_each(|(x, y)| assert!((x - y).abs() < 1e-12, "{:?} != {:?}", ta, tb));
+        let d: f64 = qa.iter().zip(qb.iter()).map(|(x, y)| x * y).sum();
+        assert!((d.abs() - 1.0).abs() < 1e-12, "{:?} != {:?}", qa, qb);
+    }
+
+    fn round_trip<T: AD, P: O3DPose<T>>() {
+        let pose = test_pose::<T, P>();
+
+        let versioned = o3d_pose_custom_serialize::<_, T, P>(&pose, serde_json::value::Serializer).expect("error");
+        assert_eq!(versioned["version"], O3D_POSE_SERDE_VERSION);
+        assert_eq!(versioned["pose_type"], format!("{:?}", P::type_identifier()));
+        assert_same_pose::<T, P>(&pose, &o3d_pose_custom_deserialize::<_, T, P>(versioned).expect("error"));
+
+        let compact = o3d_pose_compact_serialize::<_, T, P>(&pose, serde_json::value::Serializer).expect("error");
+        assert_eq!(compact.as_array().expect("error").len(), 7);
+        assert_same_pose::<T, P>(&pose, &o3d_pose_custom_deserialize::<_, T, P>(compact.clone()).expect("error"));
+        assert_same_pose::<T, P>(&pose, &o3d_pose_compact_deserialize::<_, T, P>(compact).expect("error"));
+
+        let legacy = serde_json::json!([0.1, -0.2, 0.3, 0.3, 0.2, -0.1]);
+        assert_same_pose::<T, P>(&pose, &o3d_pose_custom_deserialize::<_, T, P>(legacy).expect("error"));
+
+        let mut bytes = vec![];
+        o3d_pose_custom_serialize::<_, T, P>(&pose, &mut bincode::Serializer::new(&mut bytes, bincode::DefaultOptions::new())).expect("error");
+        let read = o3d_pose_custom_deserialize::<_, T, P>(&mut bincode::Deserializer::from_slice(&bytes, bincode::DefaultOptions::new())).expect("error");
+        assert_same_pose::<T, P>(&pose, &read);
+
+        let mut bytes = vec![];
+        o3d_pose_compact_serialize::<_, T, P>(&pose, &mut bincode::Serializer::new(&mut bytes, bincode::DefaultOptions::new())).expect("error");
+        let read = o3d_pose_compact_deserialize::<_, T, P>(&mut bincode::Deserializer::from_slice(&bytes, bincode::DefaultOptions::new())).expect("error");
+        assert_same_pose::<T, P>(&pose, &read);
+    }
+
+    #[test]
+    fn implicit_dual_quaternion_round_trips() {
+        round_trip::<f64, ImplicitDualQuaternion<f64>>();
+        round_trip::<adfn<1>, ImplicitDualQuaternion<adfn<1>>>();
+    }
+
+    #[test]
+    fn isometry3_round_trips() {
+        round_trip::<f64, Isometry3<f64>>();
+        round_trip::<adfn<1>, Isometry3<adfn<1>>>();
+    }
+
+    #[test]
+    fn matrix4_pose_round_trips() {
+        round_trip::<f64, OMatrix4Pose<f64>>();
+        round_trip::<adfn<1>, OMatrix4Pose<adfn<1>>>();
+    }
+
+    #[test]
+    fn matrix4_pose_rejects_matrices_that_are_not_rigid_transforms() {
+        let pose: Isometry3<f64> = test_pose::<f64, Isometry3<f64>>();
+        let matrix = pose.to_homogeneous();
+        let read = OMatrix4Pose::from_matrix(matrix).expect("error");
+        assert!((read.matrix() - matrix).amax() < 1e-12);
+
+        let mut bottom_row = matrix;
+        bottom_row[(3, 0)] = 0.1;
+        assert!(OMatrix4Pose::from_matrix(bottom_row).is_err());
+
+        let mut scaled = matrix;
+        scaled.fixed_view_mut::<3, 3>(0, 0).scale_mut(1.1);
+        assert!(OMatrix4Pose::from_matrix(scaled).is_err());
+
+        let mut reflected = matrix;
+        reflected.fixed_view_mut::<3, 1>(0, 2).neg_mut();
+        assert!(OMatrix4Pose::from_matrix(reflected).is_err());
+
+        let json = serde_json::to_value(&scaled).expect("error");
+        assert!(serde_json::from_value::<OMatrix4Pose<f64>>(json).is_err());
+        let json = serde_json::to_value(&matrix).expect("error");
+        assert!(serde_json::from_value::<OMatrix4Pose<f64>>(json).is_ok());
+    }
+
+    #[test]
+    fn matrix4_pose_composition_matches_isometry3_and_stays_orthonormal() {
+        let a = test_pose::<f64, OMatrix4Pose<f64>>();
+        let b = OMatrix4Pose::from_constructors(&[0.5, 0.0, -0.1], &ScaledAxis([0.0, 0.0, 1e-3]));
+        let (ia, ib): (Isometry3<f64>, Isometry3<f64>) = (test_pose::<f64, Isometry3<f64>>(), Isometry3::from_constructors(&[0.5, 0.0, -0.1], &ScaledAxis([0.0, 0.0, 1e-3])));
+
+        let expected = (ia * ib).to_homogeneous();
+        assert!((a.mul(&b).matrix() - expected).amax() < 1e-12);
+
+        let mut chained = a.clone();
+        let mut expected = ia;
+        for _ in 0..10000 {
+            chained = chained.mul(&b);
+            expected = expected * ib;
+        }
+        let r = chained.rotation().matrix();
+        assert!((r.transpose() * r - Matrix3::identity()).amax() < 1e-12);
+        assert!((chained.matrix() - expected.to_homogeneous()).amax() < 1e-6);
+        assert!(OMatrix4Pose::from_matrix(*chained.matrix()).is_ok());
+    }
+
+    fn rotations_at_awkward_angles() -> Vec<[f64; 3]> {
+        let axis = Vector3::new(0.3, -0.5, 0.8).normalize();
+        let mut out = vec![[0.0, 0.0, 0.0], [1e-12, 0.0, 0.0], [0.0, 1e-9, -1e-9], [0.3, 0.2, -0.1], [0.0, 0.0, PI], [PI, 0.0, 0.0]];
+        for angle in [1.0, 2.5, PI - 1e-4, PI - 1e-7, PI - 1e-10, PI] {
+            let v = axis * angle;
+            out.push([v[0], v[1], v[2]]);
+        }
+        out
+    }
+
+    fn assert_same_isometry(a: &IsometryMatrix3<f64>, b: &IsometryMatrix3<f64>, tolerance: f64) {
+        assert!((a.to_homogeneous() - b.to_homogeneous()).amax() < tolerance, "{} != {}", a.to_homogeneous(), b.to_homogeneous());
+    }
+
+    #[test]
+    fn rotation_matrix_ln_and_exp_match_the_quaternion_versions() {
+        for r in rotations_at_awkward_angles() {
+            let translation = [0.4, -1.0, 2.0];
+            let a: IsometryMatrix3<f64> = IsometryMatrix3::from_constructors(&translation, &ScaledAxis(r));
+            let b: Isometry3<f64> = Isometry3::from_constructors(&translation, &ScaledAxis(r));
+
+            let (ln_a, ln_b) = (O3DPose::ln(&a), O3DPose::ln(&b));
+            assert!(ln_a.iter().all(|x| x.is_finite()), "{:?}", r);
+            // at exactly pi the axis can come out with either sign, so only the rotation it stands
+            // for has to match.
+            if (ln_a - ln_b).amax() > 1e-6 {
+                assert!((r[0].powi(2) + r[1].powi(2) + r[2].powi(2)).sqrt() > PI - 1e-6, "{:?}: {} != {}", r, ln_a, ln_b);
+            }
+            assert_same_isometry(&<IsometryMatrix3<f64> as O3DPose<f64>>::exp(&ln_a), &a, 1e-9);
+
+            let (t, q) = generic_pose_exp(&ln_a);
+            assert_same_isometry(&IsometryMatrix3::from_parts(t.into(), q.to_rotation_matrix()), &a, 1e-9);
+        }
+    }
+
+    #[test]
+    fn rotation_matrix_exp_is_rodrigues_at_small_and_large_angles() {
+        for r in rotations_at_awkward_angles() {
+            let expected = UnitQuaternion::from_scaled_axis(Vector3::from(r)).to_rotation_matrix();
+            let out = rotation_matrix_exp(&Vector3::from(r));
+            assert!((out.matrix() - expected.matrix()).amax() < 1e-12, "{:?}", r);
+            let ln = rotation_matrix_ln(&out);
+            assert!((rotation_matrix_exp(&ln).matrix() - expected.matrix()).amax() < 1e-9, "{:?}", r);
+        }
+    }
+
+    #[test]
+    fn rotation_matrix_interpolation_follows_the_shorter_arc() {
+        let a: IsometryMatrix3<f64> = IsometryMatrix3::from_constructors(&[0.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.2]));
+        let b: IsometryMatrix3<f64> = IsometryMatrix3::from_constructors(&[2.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, -3.0]));
+
+        assert_same_isometry(&a.interpolate(&b, 0.0), &a, 1e-12);
+        assert_same_isometry(&a.interpolate(&b, 1.0), &b, 1e-9);
+
+        // from 0.2 to -3.0 the short way round goes up through pi.
+        let mid = a.interpolate(&b, 0.5);
+        let expected: IsometryMatrix3<f64> = IsometryMatrix3::from_constructors(&[1.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.2 + (2.0 * PI - 3.2) / 2.0]));
+        assert_same_isometry(&mid, &expected, 1e-9);
+
+        let (ia, ib): (Isometry3<f64>, Isometry3<f64>) = (Isometry3::from_constructors(&[0.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.2])), Isometry3::from_constructors(&[2.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, -3.0])));
+        let quaternion_mid = ia.interpolate(&ib, 0.5);
+        assert!((mid.to_homogeneous() - quaternion_mid.to_homogeneous()).amax() < 1e-9);
+    }
+
+    fn weighted_average_checks<P: O3DPose<f64>>() {
+        let a = P::from_constructors(&[0.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.2]));
+        let b = P::from_constructors(&[2.0, 4.0, 0.0], &ScaledAxis([0.0, 0.0, 0.6]));
+        let poses = vec![a.clone(), b.clone()];
+
+        assert!(P::weighted_average(&poses, &[1.0]).is_err());
+        assert!(P::weighted_average(&[], &[]).is_err());
+        assert!(P::weighted_average(&poses, &[0.0, 0.0]).is_err());
+        assert!(P::weighted_average(&poses, &[2.0, -1.0]).is_err());
+        assert!(P::weighted_average(&poses, &[1.0, f64::NAN]).is_err());
+
+        let average = P::weighted_average(&poses, &[3.0, 3.0]).expect("error");
+        assert!((average.rotation().scaled_axis_of_rotation()[2] - 0.4).abs() < 1e-12);
+        let only_b = P::weighted_average(&poses, &[0.0, 0.5]).expect("error");
+        assert_same_pose::<f64, P>(&only_b, &b);
+
+        // rotations by 3 and -3 about z are 0.28 apart the short way round, through pi.
+        let c = P::from_constructors(&[0.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 3.0]));
+        let d = P::from_constructors(&[0.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, -3.0]));
+        let average = P::weighted_average(&[c, d], &[1.0, 1.0]).expect("error");
+        assert!((average.rotation().angle() - PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weighted_average_rejects_bad_weights_and_averages_rotations() {
+        weighted_average_checks::<Isometry3<f64>>();
+        weighted_average_checks::<ImplicitDualQuaternion<f64>>();
+        weighted_average_checks::<IsometryMatrix3<f64>>();
+
+        let a: Isometry3<f64> = Isometry3::from_constructors(&[0.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.2]));
+        let b: Isometry3<f64> = Isometry3::from_constructors(&[2.0, 4.0, 0.0], &ScaledAxis([0.0, 0.0, 0.6]));
+        let average = Isometry3::weighted_average(&[a, b], &[1.0, 3.0]).expect("error");
+        assert!((average.translation.vector - Vector3::new(1.5, 3.0, 0.0)).amax() < 1e-12);
+    }
+
+    #[test]
+    fn dual_quaternion_blending_averages_translations_of_equally_rotated_poses() {
+        let a = ImplicitDualQuaternion::from_constructors(&[0.0, 0.0, 0.0], &ScaledAxis([0.1, 0.2, 0.3]));
+        let b = ImplicitDualQuaternion::from_constructors(&[2.0, 4.0, -1.0], &ScaledAxis([0.1, 0.2, 0.3]));
+        let average = ImplicitDualQuaternion::weighted_average(&[a.clone(), b], &[1.0, 3.0]).expect("error");
+        assert!((average.translation() - Vector3::new(1.5, 3.0, -0.75)).amax() < 1e-12);
+        assert!(average.rotation().angle_to(a.rotation()) < 1e-12);
+    }
+
+    #[test]
+    fn velocity_limited_duration_is_set_by_the_slower_part() {
+        let a: Isometry3<f64> = Isometry3::from_constructors(&[0.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.0]));
+        let b: Isometry3<f64> = Isometry3::from_constructors(&[2.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.5]));
+
+        assert!((a.velocity_limited_duration(&b, 1.0, 1.0).expect("error") - 2.0).abs() < 1e-12);
+        assert!((a.velocity_limited_duration(&b, 4.0, 0.1).expect("error") - 5.0).abs() < 1e-12);
+        assert!(a.velocity_limited_duration(&b, 0.0, 1.0).is_err());
+        assert!(a.velocity_limited_duration(&b, 1.0, -1.0).is_err());
+    }
+
+    #[test]
+    fn velocity_limited_interpolation_respects_the_limits_and_ends_at_the_goal() {
+        let a: Isometry3<f64> = Isometry3::from_constructors(&[0.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.0]));
+        let b: Isometry3<f64> = Isometry3::from_constructors(&[1.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.3]));
+        let samples = a.velocity_limited_interpolation(&b, 0.5, 1.0, 0.1).expect("error");
+
+        assert_eq!(samples[0].0, 0.0);
+        assert_same_pose::<f64, Isometry3<f64>>(&samples[0].1, &a);
+        let (last_time, last_pose) = samples.last().expect("error");
+        assert!((last_time - 2.0).abs() < 1e-12);
+        assert_same_pose::<f64, Isometry3<f64>>(last_pose, &b);
+        for w in samples.windows(2) {
+            let dt = w[1].0 - w[0].0;
+            assert!(dt > 0.0 && dt <= 0.1 + 1e-9);
+            assert!((w[1].1.translation.vector - w[0].1.translation.vector).norm() <= 0.5 * dt + 1e-9);
+            assert!(w[0].1.rotation.angle_to(&w[1].1.rotation) <= 1.0 * dt + 1e-9);
+        }
+
+        assert!(a.velocity_limited_interpolation(&b, 0.5, 1.0, 0.0).is_err());
+        assert_eq!(a.velocity_limited_interpolation(&a, 0.5, 1.0, 0.1).expect("error").len(), 1);
+    }
+
+    #[test]
+    fn velocity_limited_path_continues_times_across_segments() {
+        let waypoints: Vec<Isometry3<f64>> = vec![Isometry3::translation(0.0, 0.0, 0.0), Isometry3::translation(1.0, 0.0, 0.0), Isometry3::translation(1.0, 2.0, 0.0)];
+        let samples = Isometry3::velocity_limited_path(&waypoints, 1.0, 1.0, 0.25).expect("error");
+
+        assert!(samples.windows(2).all(|w| w[1].0 > w[0].0));
+        let at_second_waypoint = samples.iter().find(|(t, _)| (t - 1.0).abs() < 1e-9).expect("error");
+        assert_same_pose::<f64, Isometry3<f64>>(&at_second_waypoint.1, &waypoints[1]);
+        let (last_time, last_pose) = samples.last().expect("error");
+        assert!((last_time - 3.0).abs() < 1e-9);
+        assert_same_pose::<f64, Isometry3<f64>>(last_pose, &waypoints[2]);
+
+        assert!(Isometry3::<f64>::velocity_limited_path(&[], 1.0, 1.0, 0.25).expect("error").is_empty());
+        assert!(Isometry3::velocity_limited_path(&waypoints, 1.0, 1.0, -0.25).is_err());
+    }
+
+    #[test]
+    fn pose_written_as_one_type_reads_as_another() {
+        let pose = test_pose::<f64, ImplicitDualQuaternion<f64>>();
+        let versioned = o3d_pose_custom_serialize(&pose, serde_json::value::Serializer).expect("error");
+        let read = o3d_pose_custom_deserialize::<_, f64, Isometry3<f64>>(versioned).expect("error");
+        assert_same_pose::<f64, Isometry3<f64>>(&test_pose::<f64, Isometry3<f64>>(), &read);
+    }
+
+    #[test]
+    fn unknown_pose_type_and_newer_version_are_rejected() {
+        let pose = test_pose::<f64, Isometry3<f64>>();
+        let versioned = o3d_pose_custom_serialize(&pose, serde_json::value::Serializer).expect("error");
+
+        let mut unknown_type = versioned.clone();
+        unknown_type["pose_type"] = serde_json::json!("DualQuaternion");
+        assert!(o3d_pose_custom_deserialize::<_, f64, Isometry3<f64>>(unknown_type).is_err());
+
+        let mut newer = versioned;
+        newer["version"] = serde_json::json!(O3D_POSE_SERDE_VERSION + 1);
+        assert!(o3d_pose_custom_deserialize::<_, f64, Isometry3<f64>>(newer).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use std::marker::PhantomData;
+use ad_trait::AD;
+use optima_error::OptimaError;
+use crate::optima_3d_pose::O3DPose;
+
+/// An ordered list of poses `[p_0, p_1, ..., p_n]` composed left to right, e.g., the joint
+/// transforms of a kinematic chain outside of `ORobot`.  Prefix products `p_0 * ... * p_i` are
+/// computed when first asked for and cached; changing `p_i` only invalidates the prefix products
+/// from `i` on, so updating the end of a long chain is cheap.
+#[derive(Clone, Debug)]
+pub struct OPoseChain<T: AD, P: O3DPose<T>> {
+    poses: Vec<P>,
+    prefix_products: Vec<P>,
+    _phantom_data: PhantomData<T>
+}
+impl<T: AD, P: O3DPose<T>> OPoseChain<T, P> {
+    pub fn new() -> Self {
+        Self { poses: vec![], prefix_products: vec![], _phantom_data: PhantomData::default() }
+    }
+    pub fn from_poses(poses: Vec<P>) -> Self {
+        Self { poses, prefix_products: vec![], _phantom_data: PhantomData::default() }
+    }
+    pub fn push(&mut self, pose: P) {
+        self.poses.push(pose);
+    }
+    pub fn pop(&mut self) -> Option<P> {
+        self.prefix_products.truncate(self.poses.len().saturating_sub(1));
+        self.poses.pop()
+    }
+    /// Replaces `p_idx`, invalidating the cached prefix products from `idx` on.
+    pub fn set(&mut self, idx: usize, pose: P) {
+        self.poses[idx] = pose;
+        self.prefix_products.truncate(idx);
+    }
+    #[inline(always)]
+    pub fn get(&self, idx: usize) -> &P {
+        &self.poses[idx]
+    }
+    #[inline(always)]
+    pub fn poses(&self) -> &Vec<P> {
+        &self.poses
+    }
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.poses.len()
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.poses.is_empty()
+    }
+    /// `p_0 * ... * p_idx`.
+    pub fn prefix_product(&mut self, idx: usize) -> Result<&P, OptimaError> {
+        OptimaError::check_idx("pose chain", idx, self.poses.len())?;
+        while self.prefix_products.len() <= idx {
+            let i = self.prefix_products.len();
+            let next = match self.prefix_products.last() {
+                None => { self.poses[0].clone() }
+                Some(prev) => { prev.mul(&self.poses[i]) }
+            };
+            self.prefix_products.push(next);
+        }
+        Ok(&self.prefix_products[idx])
+    }
+    pub fn prefix_product_unchecked(&mut self, idx: usize) -> &P {
+        self.prefix_product(idx).expect("error")
+    }
+    /// `p_0 * ... * p_n`, or the identity for an empty chain.
+    pub fn full_product(&mut self) -> P {
+        if self.poses.is_empty() { return P::identity(); }
+        self.prefix_product_unchecked(self.poses.len() - 1).clone()
+    }
+    /// `p_start * ... * p_end` (both inclusive).  Ranges starting at 0 come from the cached prefix
+    /// products; others are multiplied out directly rather than recovered as
+    /// `(p_0 * ... * p_{start-1})^-1 * (p_0 * ... * p_end)`, which loses precision (and, for AD
+    /// types, adds an inverse to the graph) as the chain grows.
+    pub fn range_product(&mut self, start: usize, end: usize) -> Result<P, OptimaError> {
+        OptimaError::check_idx("pose chain", end, self.poses.len())?;
+        if start > end {
+            return Err(OptimaError::InvalidInput(format!("pose chain range starts at {} after it ends at {}.", start, end)));
+        }
+        if start == 0 { return self.prefix_product(end).cloned(); }
+
+        let mut out = self.poses[start].clone();
+        for pose in &self.poses[start + 1..=end] { out = out.mul(pose); }
+        Ok(out)
+    }
+    pub fn range_product_unchecked(&mut self, start: usize, end: usize) -> P {
+        self.range_product(start, end).expect("error")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Isometry3;
+    use crate::optima_3d_rotation::ScaledAxis;
+    use super::*;
+
+    fn chain() -> OPoseChain<f64, Isometry3<f64>> {
+        OPoseChain::from_poses((0..6).map(|i| {
+            let i = i as f64;
+            Isometry3::from_constructors(&[0.1 * i, 1.0, -0.2 * i], &ScaledAxis([0.3, -0.1 * i, 0.2]))
+        }).collect())
+    }
+
+    fn direct_product(chain: &OPoseChain<f64, Isometry3<f64>>, start: usize, end: usize) -> Isometry3<f64> {
+        chain.poses()[start..=end].iter().fold(Isometry3::identity(), |acc, x| acc * x)
+    }
+
+    fn assert_same_pose(a: &Isometry3<f64>, b: &Isometry3<f64>) {
+        assert!((a.to_homogeneous() - b.to_homogeneous()).amax() < 1e-12, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn range_products_match_direct_multiplication() {
+        let mut chain = chain();
+        for start in 0..chain.len() {
+            for end in start..chain.len() {
+                let expected = direct_product(&chain, start, end);
+                assert_same_pose(&chain.range_product(start, end).expect("error"), &expected);
+            }
+        }
+        assert_same_pose(&chain.full_product(), &direct_product(&chain, 0, 5));
+    }
+
+    #[test]
+    fn bad_ranges_are_errors() {
+        let mut chain = chain();
+        assert!(chain.prefix_product(6).is_err());
+        assert!(chain.range_product(2, 6).is_err());
+        assert!(chain.range_product(3, 2).is_err());
+        assert!(OPoseChain::<f64, Isometry3<f64>>::new().prefix_product(0).is_err());
+        assert_same_pose(&OPoseChain::<f64, Isometry3<f64>>::new().full_product(), &Isometry3::identity());
+    }
+
+    #[test]
+    fn set_and_pop_invalidate_the_cached_prefix_products() {
+        let mut chain = chain();
+        chain.full_product();
+
+        chain.set(2, Isometry3::translation(5.0, 0.0, 0.0));
+        assert_same_pose(chain.prefix_product(4).expect("error"), &direct_product(&chain, 0, 4));
+        assert_same_pose(chain.prefix_product(1).expect("error"), &direct_product(&chain, 0, 1));
+
+        chain.pop();
+        chain.push(Isometry3::translation(0.0, 0.0, 1.0));
+        assert_same_pose(&chain.full_product(), &direct_product(&chain, 0, 5));
+    }
+}
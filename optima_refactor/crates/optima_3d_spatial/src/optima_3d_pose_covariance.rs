@@ -0,0 +1,136 @@
+use std::marker::PhantomData;
+use ad_trait::AD;
+use nalgebra::{Matrix3, Matrix6};
+use serde::{Deserialize, Serialize};
+use crate::optima_3d_pose::O3DPose;
+use crate::optima_3d_twist::adjoint_matrix;
+
+/// A pose with Gaussian uncertainty, `pose * exp(xi)` with `xi ~ N(0, covariance)`, where `xi` is
+/// a twist in the pose's own (body) frame with its angular part first, as in `OTwist`.
+/// Covariances are propagated to first order with adjoints (see `adjoint_matrix`), so they hold
+/// for small uncertainties.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct O3DPoseWithCovariance<T: AD, P: O3DPose<T>> {
+    #[serde(deserialize_with = "P::deserialize")]
+    pose: P,
+    #[serde(deserialize_with = "Matrix6::<T>::deserialize")]
+    covariance: Matrix6<T>,
+    _phantom_data: PhantomData<T>
+}
+impl<T: AD, P: O3DPose<T>> O3DPoseWithCovariance<T, P> {
+    pub fn new(pose: P, covariance: Matrix6<T>) -> Self {
+        Self { pose, covariance, _phantom_data: PhantomData::default() }
+    }
+    /// A pose known exactly.
+    pub fn from_pose(pose: P) -> Self {
+        Self::new(pose, Matrix6::zeros())
+    }
+    /// Independent rotational and translational uncertainty, with standard deviations in radians
+    /// and meters.
+    pub fn from_std_devs(pose: P, rotation_std_dev: T, translation_std_dev: T) -> Self {
+        let mut covariance = Matrix6::zeros();
+        for i in 0..3 {
+            covariance[(i, i)] = rotation_std_dev * rotation_std_dev;
+            covariance[(i + 3, i + 3)] = translation_std_dev * translation_std_dev;
+        }
+        Self::new(pose, covariance)
+    }
+    #[inline(always)]
+    pub fn pose(&self) -> &P {
+        &self.pose
+    }
+    #[inline(always)]
+    pub fn covariance(&self) -> &Matrix6<T> {
+        &self.covariance
+    }
+    pub fn rotation_covariance(&self) -> Matrix3<T> {
+        self.covariance.fixed_view::<3, 3>(0, 0).into_owned()
+    }
+    /// In the pose's own frame, as with the rest of the covariance.
+    pub fn translation_covariance(&self) -> Matrix3<T> {
+        self.covariance.fixed_view::<3, 3>(3, 3).into_owned()
+    }
+    /// `self * other`, assuming the two are independent.
+    pub fn compose(&self, other: &Self) -> Self {
+        let ad = adjoint_matrix(&other.pose.inverse());
+        let covariance = ad * self.covariance * ad.transpose() + other.covariance;
+        Self::new(self.pose.mul(&other.pose), covariance)
+    }
+    /// `self * pose` for an exactly known `pose`, e.g., a fixed sensor mount.
+    pub fn compose_with_pose(&self, pose: &P) -> Self {
+        let ad = adjoint_matrix(&pose.inverse());
+        Self::new(self.pose.mul(pose), ad * self.covariance * ad.transpose())
+    }
+    pub fn inverse(&self) -> Self {
+        let ad = adjoint_matrix(&self.pose);
+        Self::new(self.pose.inverse(), ad * self.covariance * ad.transpose())
+    }
+    /// `self^-1 * other`, i.e., `other` in `self`'s frame, assuming the two are independent.
+    pub fn displacement(&self, other: &Self) -> Self {
+        self.inverse().compose(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Isometry3, Matrix4};
+    use crate::optima_3d_rotation::ScaledAxis;
+    use super::*;
+
+    fn test_pose() -> Isometry3<f64> {
+        Isometry3::from_constructors(&[0.5, -1.0, 2.0], &ScaledAxis([0.3, -0.7, 1.1]))
+    }
+
+    fn assert_matrix6_eq(a: &Matrix6<f64>, b: &Matrix6<f64>) {
+        assert!((a - b).amax() < 1e-12, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn std_devs_fill_the_diagonal_blocks() {
+        let p = O3DPoseWithCovariance::from_std_devs(test_pose(), 0.1, 0.2);
+        assert!((p.rotation_covariance() - Matrix3::identity() * 0.01).amax() < 1e-15);
+        assert!((p.translation_covariance() - Matrix3::identity() * 0.04).amax() < 1e-15);
+        assert_eq!(p.covariance().fixed_view::<3, 3>(0, 3).into_owned(), Matrix3::zeros());
+    }
+
+    #[test]
+    fn rotational_uncertainty_moves_the_end_of_a_lever_arm() {
+        let p = O3DPoseWithCovariance::from_std_devs(Isometry3::identity(), 0.1, 0.0);
+        let arm = Isometry3::translation(2.0, 0.0, 0.0);
+        let c = p.compose_with_pose(&arm).covariance().clone();
+
+        // at the end of the arm, rotation about z moves along y and rotation about y along -z.
+        assert!((c[(3, 3)]).abs() < 1e-15);
+        assert!((c[(4, 4)] - 0.04).abs() < 1e-12);
+        assert!((c[(5, 5)] - 0.04).abs() < 1e-12);
+        assert!((c[(2, 4)] - 0.02).abs() < 1e-12);
+        assert!((c[(1, 5)] + 0.02).abs() < 1e-12);
+        assert_matrix6_eq(&c, &c.transpose());
+    }
+
+    #[test]
+    fn composition_and_inversion_are_consistent() {
+        let a = O3DPoseWithCovariance::from_std_devs(test_pose(), 0.05, 0.1);
+        let b = O3DPoseWithCovariance::from_std_devs(Isometry3::from_constructors(&[1.0, 0.0, 0.0], &ScaledAxis([0.0, 0.4, 0.0])), 0.02, 0.3);
+
+        assert_matrix6_eq(a.inverse().inverse().covariance(), a.covariance());
+        assert_matrix6_eq(O3DPoseWithCovariance::from_pose(test_pose()).compose(&b).covariance(), b.covariance());
+        assert_matrix6_eq(a.compose(&O3DPoseWithCovariance::from_pose(b.pose().clone())).covariance(), a.compose_with_pose(b.pose()).covariance());
+
+        let ab = a.compose(&b);
+        assert!((ab.pose().to_homogeneous() - (test_pose() * b.pose()).to_homogeneous()).amax() < 1e-12);
+        // a displacement from a pose to itself (treated as independent) is the identity, with twice
+        // the uncertainty once moved into the same frame.
+        let d = a.displacement(&a);
+        assert!((d.pose().to_homogeneous() - Matrix4::identity()).amax() < 1e-12);
+        assert_matrix6_eq(d.covariance(), &(a.covariance() * 2.0));
+    }
+
+    #[test]
+    fn covariance_round_trips_through_serde() {
+        let a = O3DPoseWithCovariance::from_std_devs(test_pose(), 0.05, 0.1);
+        let read: O3DPoseWithCovariance<f64, Isometry3<f64>> = serde_json::from_str(&serde_json::to_string(&a).expect("error")).expect("error");
+        assert_matrix6_eq(read.covariance(), a.covariance());
+        assert!((read.pose().to_homogeneous() - a.pose().to_homogeneous()).amax() < 1e-12);
+    }
+}
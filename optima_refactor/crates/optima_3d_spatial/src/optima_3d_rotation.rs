@@ -54,6 +54,62 @@ pub trait O3DRotation<T: AD> :
     fn displacement(&self, other: &Self) -> Self;
     fn dis(&self, other: &Self) -> T;
     fn interpolate(&self, to: &Self, t: T) -> Self;
+    /// Rotation from `angles` applied about the axes of `convention` in order, about the rotating
+    /// (intrinsic) or fixed (extrinsic) axes.  `from_euler_angles` is `OEulerConvention::XYZ` with
+    /// `OEulerFrame::Extrinsic` (i.e., roll, pitch, yaw as in urdf).
+    fn from_euler_angles_with_convention<V: O3DVec<T>>(angles: &V, convention: OEulerConvention, frame: OEulerFrame) -> Self {
+        let angles = [angles.x(), angles.y(), angles.z()];
+        let axes = convention.axes();
+        let mut out = Self::from_scaled_axis_of_rotation(&[T::zero(), T::zero(), T::zero()]);
+        for (axis, angle) in axes.iter().zip(angles.iter()) {
+            let mut scaled_axis = [T::zero(), T::zero(), T::zero()];
+            scaled_axis[*axis] = *angle;
+            let r = Self::from_scaled_axis_of_rotation(&scaled_axis);
+            out = match frame {
+                OEulerFrame::Intrinsic => { out.mul(&r) }
+                OEulerFrame::Extrinsic => { r.mul(&out) }
+            };
+        }
+        out
+    }
+    /// Inverse of `from_euler_angles_with_convention`.  The middle angle is in [-pi/2, pi/2] for
+    /// Tait-Bryan conventions and [0, pi] for proper Euler conventions.  At gimbal lock, the third
+    /// angle is set to zero.
+    fn euler_angles_with_convention(&self, convention: OEulerConvention, frame: OEulerFrame) -> [T; 3] {
+        let m = self.rotation_matrix_as_column_major_slice();
+        let r = |row: usize, col: usize| m[col * 3 + row];
+
+        // an extrinsic sequence is the intrinsic sequence of the reversed axes, with reversed angles.
+        let mut axes = convention.axes();
+        if frame == OEulerFrame::Extrinsic { axes.reverse(); }
+        let (i, j) = (axes[0], axes[1]);
+        let k = 3 - i - j;
+        let e = if (i, j, k) == (0, 1, 2) || (i, j, k) == (1, 2, 0) || (i, j, k) == (2, 0, 1) { T::one() } else { -T::one() };
+        let eps = T::constant(0.0000001);
+
+        // the middle angle is taken from both its sine and cosine, rather than an asin or acos of
+        // one of them, so it stays accurate close to gimbal lock.
+        let mut out = if convention.is_tait_bryan() {
+            let c = (r(i, i) * r(i, i) + r(i, j) * r(i, j)).sqrt();
+            let b = (e * r(i, k)).atan2(c);
+            if c < eps {
+                [(e * r(k, j)).atan2(r(j, j)), b, T::zero()]
+            } else {
+                [(-e * r(j, k)).atan2(r(k, k)), b, (-e * r(i, j)).atan2(r(i, i))]
+            }
+        } else {
+            let s = (r(i, j) * r(i, j) + r(i, k) * r(i, k)).sqrt();
+            let b = s.atan2(r(i, i));
+            if s < eps {
+                [(e * r(k, j)).atan2(r(j, j)), b, T::zero()]
+            } else {
+                [r(j, i).atan2(-e * r(k, i)), b, r(i, j).atan2(e * r(i, k))]
+            }
+        };
+
+        if frame == OEulerFrame::Extrinsic { out.reverse(); }
+        out
+    }
     #[inline(always)]
     fn o3drot_to_constant_ads(&self) -> Self {
         let axis: Vec<T> = self.scaled_axis_of_rotation().iter().map(|x| T::constant(x.to_constant()) ).collect();
@@ -331,9 +387,9 @@ impl<'de, T2: AD, R2: O3DRotation<T2>> Visitor<'de> for O3dRotationMyVisitor<T2,
     where
         A: SeqAccess<'de>,
     {
-        let x: f64 = seq.next_element().expect("error").expect("error");
-        let y: f64 = seq.next_element().expect("error").expect("error");
-        let z: f64 = seq.next_element().expect("error").expect("error");
+        let x: f64 = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+        let y: f64 = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+        let z: f64 = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(2, &self))?;
         let xad = T2::constant(x);
         let yad = T2::constant(y);
         let zad = T2::constant(z);
@@ -416,6 +472,63 @@ impl<T, TargetRotationType> O3DRotationConstructor<T, TargetRotationType> for Un
     }
 }
 
+/// Order of the axes that euler angles rotate about.  The first six are Tait-Bryan conventions
+/// (three different axes, e.g., roll, pitch, yaw) and the last six proper Euler conventions (first
+/// and last axis the same).
+#[derive(Clone, Debug, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum OEulerConvention {
+    XYZ, XZY, YXZ, YZX, ZXY, ZYX,
+    XYX, XZX, YXY, YZY, ZXZ, ZYZ
+}
+impl OEulerConvention {
+    /// 0, 1, and 2 for x, y, and z.
+    pub fn axes(&self) -> [usize; 3] {
+        match self {
+            OEulerConvention::XYZ => { [0, 1, 2] }
+            OEulerConvention::XZY => { [0, 2, 1] }
+            OEulerConvention::YXZ => { [1, 0, 2] }
+            OEulerConvention::YZX => { [1, 2, 0] }
+            OEulerConvention::ZXY => { [2, 0, 1] }
+            OEulerConvention::ZYX => { [2, 1, 0] }
+            OEulerConvention::XYX => { [0, 1, 0] }
+            OEulerConvention::XZX => { [0, 2, 0] }
+            OEulerConvention::YXY => { [1, 0, 1] }
+            OEulerConvention::YZY => { [1, 2, 1] }
+            OEulerConvention::ZXZ => { [2, 0, 2] }
+            OEulerConvention::ZYZ => { [2, 1, 2] }
+        }
+    }
+    pub fn is_tait_bryan(&self) -> bool {
+        let a = self.axes();
+        a[0] != a[2]
+    }
+}
+
+/// Whether euler angles rotate about the axes of the rotating frame (intrinsic) or the fixed frame
+/// (extrinsic).  Intrinsic `ZYX` is the same rotation as extrinsic `XYZ` with the angles reversed.
+#[derive(Clone, Debug, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum OEulerFrame {
+    Intrinsic, Extrinsic
+}
+
+pub struct EulerAnglesConstructor<T: AD> {
+    pub angles: [T; 3],
+    pub convention: OEulerConvention,
+    pub frame: OEulerFrame
+}
+impl<T: AD> EulerAnglesConstructor<T> {
+    pub fn new(angles: [T; 3], convention: OEulerConvention, frame: OEulerFrame) -> Self {
+        Self { angles, convention, frame }
+    }
+}
+impl<T, TargetRotationType> O3DRotationConstructor<T, TargetRotationType> for EulerAnglesConstructor<T>
+    where T: AD,
+          TargetRotationType: O3DRotation<T> {
+    fn construct(&self) -> TargetRotationType {
+        TargetRotationType::from_euler_angles_with_convention(&self.angles, self.convention, self.frame)
+    }
+}
+
 pub struct QuatConstructor<T: AD>{pub w: T, pub x: T, pub y: T, pub z: T}
 impl<T: AD> QuatConstructor<T> {
     pub fn new(w: T, x: T, y: T, z: T) -> Self {
@@ -438,3 +551,90 @@ impl<T, TargetRotationType> O3DRotationConstructor<T, TargetRotationType> for Qu
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+    use super::*;
+
+    const CONVENTIONS: [OEulerConvention; 12] = [
+        OEulerConvention::XYZ, OEulerConvention::XZY, OEulerConvention::YXZ, OEulerConvention::YZX, OEulerConvention::ZXY, OEulerConvention::ZYX,
+        OEulerConvention::XYX, OEulerConvention::XZX, OEulerConvention::YXY, OEulerConvention::YZY, OEulerConvention::ZXZ, OEulerConvention::ZYZ
+    ];
+    const FRAMES: [OEulerFrame; 2] = [OEulerFrame::Intrinsic, OEulerFrame::Extrinsic];
+
+    fn assert_same_rotation<R: O3DRotation<f64>>(a: &R, b: &R, tolerance: f64) {
+        let (a, b) = (a.rotation_matrix_as_column_major_slice(), b.rotation_matrix_as_column_major_slice());
+        assert!(a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < tolerance), "{:?} != {:?}", a, b);
+    }
+
+    /// angles with the middle one at, near, and away from gimbal lock.
+    fn test_angles(convention: OEulerConvention) -> Vec<[f64; 3]> {
+        let middles = if convention.is_tait_bryan() { [FRAC_PI_2, -FRAC_PI_2, FRAC_PI_2 - 1e-5, -FRAC_PI_2 + 1e-5] } else { [0.0, PI, 1e-5, PI - 1e-5] };
+        let mut out = vec![[0.3, -0.4, 1.2], [-2.9, 1.1, 0.1], [1.0, 0.2, -3.0], [0.0, 0.0, 0.0]];
+        out.extend(middles.iter().map(|b| [0.4, *b, -0.3]));
+        out
+    }
+
+    fn round_trips<R: O3DRotation<f64>>() {
+        for convention in CONVENTIONS {
+            for frame in FRAMES {
+                for angles in test_angles(convention) {
+                    let rotation = R::from_euler_angles_with_convention(&angles, convention, frame);
+                    let read = rotation.euler_angles_with_convention(convention, frame);
+                    assert_same_rotation(&R::from_euler_angles_with_convention(&read, convention, frame), &rotation, 1e-9);
+
+                    let middle = read[1];
+                    if convention.is_tait_bryan() {
+                        assert!(middle >= -FRAC_PI_2 - 1e-12 && middle <= FRAC_PI_2 + 1e-12, "{:?} {:?}: {:?}", convention, frame, read);
+                    } else {
+                        assert!(middle >= 0.0 && middle <= PI + 1e-12, "{:?} {:?}: {:?}", convention, frame, read);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn euler_angles_round_trip_in_every_convention() {
+        round_trips::<UnitQuaternion<f64>>();
+        round_trips::<Rotation3<f64>>();
+    }
+
+    #[test]
+    fn euler_angles_away_from_gimbal_lock_are_read_back_exactly() {
+        let angles = [0.3, -0.4, 1.2];
+        for convention in CONVENTIONS {
+            for frame in FRAMES {
+                let angles = if convention.is_tait_bryan() { angles } else { [angles[0], 0.4, angles[2]] };
+                let read = UnitQuaternion::from_euler_angles_with_convention(&angles, convention, frame).euler_angles_with_convention(convention, frame);
+                assert!(read.iter().zip(angles.iter()).all(|(x, y)| (x - y).abs() < 1e-9), "{:?} {:?}: {:?} != {:?}", convention, frame, read, angles);
+            }
+        }
+    }
+
+    #[test]
+    fn conventions_match_single_axis_rotations_and_each_other() {
+        let (a, b, c) = (0.3, -0.4, 1.2);
+        let x = |t: f64| UnitQuaternion::from_scaled_axis_of_rotation(&[t, 0.0, 0.0]);
+        let y = |t: f64| UnitQuaternion::from_scaled_axis_of_rotation(&[0.0, t, 0.0]);
+        let z = |t: f64| UnitQuaternion::from_scaled_axis_of_rotation(&[0.0, 0.0, t]);
+
+        let intrinsic_zyx = UnitQuaternion::from_euler_angles_with_convention(&[a, b, c], OEulerConvention::ZYX, OEulerFrame::Intrinsic);
+        assert_same_rotation(&intrinsic_zyx, &(z(a) * y(b) * x(c)), 1e-12);
+        let extrinsic_xyz = UnitQuaternion::from_euler_angles_with_convention(&[c, b, a], OEulerConvention::XYZ, OEulerFrame::Extrinsic);
+        assert_same_rotation(&intrinsic_zyx, &extrinsic_xyz, 1e-12);
+
+        let intrinsic_zxz = UnitQuaternion::from_euler_angles_with_convention(&[a, b, c], OEulerConvention::ZXZ, OEulerFrame::Intrinsic);
+        assert_same_rotation(&intrinsic_zxz, &(z(a) * x(b) * z(c)), 1e-12);
+
+        // urdf roll, pitch, yaw.
+        assert_same_rotation(&<UnitQuaternion<f64> as O3DRotation<f64>>::from_euler_angles(&[a, b, c]), &extrinsic_xyz_of(a, b, c), 1e-12);
+        let constructed: Rotation3<f64> = EulerAnglesConstructor::new([a, b, c], OEulerConvention::ZYX, OEulerFrame::Intrinsic).construct();
+        assert_same_rotation(&constructed, &Rotation3::from_euler_angles_with_convention(&[a, b, c], OEulerConvention::ZYX, OEulerFrame::Intrinsic), 1e-12);
+    }
+
+    fn extrinsic_xyz_of(roll: f64, pitch: f64, yaw: f64) -> UnitQuaternion<f64> {
+        UnitQuaternion::from_euler_angles_with_convention(&[roll, pitch, yaw], OEulerConvention::XYZ, OEulerFrame::Extrinsic)
+    }
+}
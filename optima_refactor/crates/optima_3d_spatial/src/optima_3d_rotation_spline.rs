@@ -0,0 +1,125 @@
+use std::marker::PhantomData;
+use ad_trait::AD;
+use nalgebra::{Quaternion, UnitQuaternion};
+use optima_error::OptimaError;
+use crate::optima_3d_rotation::O3DRotation;
+
+/// Spherical quadrangle (SQUAD) interpolation through a sequence of rotations, giving an orientation
+/// path with continuous angular velocity through every waypoint, unlike slerping each pair.  As with
+/// the splines in optima_interpolation, `t` runs from 0 at the first waypoint to `num_waypoints - 1`
+/// at the last, with waypoint `i` at `t = i`.
+#[derive(Clone, Debug)]
+pub struct OSquadInterpolator<T: AD, R: O3DRotation<T>> {
+    waypoints: Vec<UnitQuaternion<T>>,
+    control_points: Vec<UnitQuaternion<T>>,
+    _phantom_data: PhantomData<R>
+}
+impl<T: AD, R: O3DRotation<T>> OSquadInterpolator<T, R> {
+    pub fn new(waypoints: &Vec<R>) -> Result<Self, OptimaError> {
+        if waypoints.is_empty() { return Err(OptimaError::InvalidInput("a rotation spline needs at least one waypoint.".to_string())); }
+
+        // each waypoint is flipped onto the same hemisphere as the previous one, so the path takes
+        // the shorter way around between them.
+        let mut quaternions: Vec<UnitQuaternion<T>> = vec![];
+        for waypoint in waypoints {
+            let q = waypoint.unit_quaternion_as_wxyz_slice();
+            let mut q = UnitQuaternion::from_quaternion(Quaternion::new(q[0], q[1], q[2], q[3]));
+            if let Some(prev) = quaternions.last() {
+                if prev.coords.dot(&q.coords) < T::zero() { q = UnitQuaternion::new_unchecked(-q.into_inner()); }
+            }
+            quaternions.push(q);
+        }
+
+        let n = quaternions.len();
+        let control_points = (0..n).map(|i| {
+            if i == 0 || i == n - 1 { return quaternions[i].clone(); }
+            let q_inv = quaternions[i].inverse();
+            let to_next = (q_inv * quaternions[i + 1]).into_inner().ln();
+            let to_prev = (q_inv * quaternions[i - 1]).into_inner().ln();
+            let s = (to_next + to_prev) * T::constant(-0.25);
+            quaternions[i] * UnitQuaternion::new_normalize(s.exp())
+        }).collect();
+
+        Ok(Self { waypoints: quaternions, control_points, _phantom_data: PhantomData::default() })
+    }
+    pub fn interpolate(&self, t: T) -> R {
+        if self.waypoints.len() == 1 { return to_rotation(&self.waypoints[0]); }
+        let t = t.max(T::zero()).min(self.max_t());
+        let i = (t.to_constant().floor() as usize).min(self.waypoints.len() - 2);
+
+        let h = t - T::constant(i as f64);
+        let a = slerp_shortest(&self.waypoints[i], &self.waypoints[i + 1], h);
+        let b = slerp_shortest(&self.control_points[i], &self.control_points[i + 1], h);
+        to_rotation(&slerp_shortest(&a, &b, T::constant(2.0) * h * (T::one() - h)))
+    }
+    #[inline(always)]
+    pub fn max_t(&self) -> T {
+        T::constant(self.waypoints.len().saturating_sub(1) as f64)
+    }
+    /// `u` from 0 to 1 over the whole path.
+    pub fn interpolate_normalized(&self, u: T) -> R {
+        self.interpolate(u * self.max_t())
+    }
+    pub fn interpolate_points_by_num_points(&self, num_points: usize) -> Vec<R> {
+        if num_points < 2 { return vec![self.interpolate(T::zero())]; }
+        (0..num_points).map(|i| self.interpolate_normalized(T::constant(i as f64 / (num_points - 1) as f64))).collect()
+    }
+}
+
+fn slerp_shortest<T: AD>(a: &UnitQuaternion<T>, b: &UnitQuaternion<T>, t: T) -> UnitQuaternion<T> {
+    let b = if a.coords.dot(&b.coords) < T::zero() { UnitQuaternion::new_unchecked(-b.clone().into_inner()) } else { b.clone() };
+    a.try_slerp(&b, t, T::constant(0.0000001)).unwrap_or(b)
+}
+
+fn to_rotation<T: AD, R: O3DRotation<T>>(q: &UnitQuaternion<T>) -> R {
+    R::from_unit_quaternion_as_wxyz_slice(&[q.w, q.i, q.j, q.k])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn about_z(angle: f64) -> UnitQuaternion<f64> {
+        UnitQuaternion::from_scaled_axis_of_rotation(&[0.0, 0.0, angle])
+    }
+
+    fn waypoints() -> Vec<UnitQuaternion<f64>> {
+        vec![about_z(0.0), UnitQuaternion::from_scaled_axis_of_rotation(&[0.5, 0.2, 0.3]), UnitQuaternion::from_scaled_axis_of_rotation(&[0.1, -0.6, 1.0]), about_z(2.0)]
+    }
+
+    #[test]
+    fn path_passes_through_every_waypoint() {
+        let waypoints = waypoints();
+        let spline = OSquadInterpolator::new(&waypoints).expect("error");
+        assert_eq!(spline.max_t(), 3.0);
+        for (i, waypoint) in waypoints.iter().enumerate() {
+            assert!(spline.interpolate(i as f64).angle_to(waypoint) < 1e-9, "waypoint {}", i);
+        }
+        assert!(spline.interpolate(-1.0).angle_to(&waypoints[0]) < 1e-9);
+        assert!(spline.interpolate(10.0).angle_to(&waypoints[3]) < 1e-9);
+        assert_eq!(spline.interpolate_points_by_num_points(7).len(), 7);
+    }
+
+    #[test]
+    fn angular_velocity_is_continuous_through_interior_waypoints() {
+        let spline: OSquadInterpolator<f64, UnitQuaternion<f64>> = OSquadInterpolator::new(&waypoints()).expect("error");
+        let h = 1e-5;
+        for i in 1..3 {
+            let t = i as f64;
+            let before = (spline.interpolate(t - h).inverse() * spline.interpolate(t)).scaled_axis() / h;
+            let after = (spline.interpolate(t).inverse() * spline.interpolate(t + h)).scaled_axis() / h;
+            assert!((before - after).norm() < 1e-3, "waypoint {}: {} != {}", i, before, after);
+        }
+    }
+
+    #[test]
+    fn two_waypoints_are_slerped_the_short_way_round() {
+        let spline: OSquadInterpolator<f64, UnitQuaternion<f64>> = OSquadInterpolator::new(&vec![about_z(3.0), about_z(-3.0)]).expect("error");
+        let mid = spline.interpolate(0.5);
+        assert!(mid.angle_to(&about_z(std::f64::consts::PI)) < 1e-9);
+
+        let single: OSquadInterpolator<f64, UnitQuaternion<f64>> = OSquadInterpolator::new(&vec![about_z(1.0)]).expect("error");
+        assert!(single.interpolate(0.7).angle_to(&about_z(1.0)) < 1e-12);
+        assert!(OSquadInterpolator::<f64, UnitQuaternion<f64>>::new(&vec![]).is_err());
+    }
+}
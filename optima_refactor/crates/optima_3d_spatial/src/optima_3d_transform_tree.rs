@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use ad_trait::AD;
+use optima_error::OptimaError;
+use crate::optima_3d_pose::O3DPose;
+
+/// Named frames, each with a pose relative to its parent frame (or to the world, for root frames),
+/// e.g., cameras, fixtures, and targets in a scene.  Poses are timestamped (in seconds) and the
+/// last `max_history_length` of them are kept per frame, so `lookup` can be asked about the past;
+/// poses between two updates are interpolated, and stamps outside of a frame's history use its
+/// first or last pose.
+#[derive(Clone, Debug)]
+pub struct OTransformTree<T: AD, P: O3DPose<T>> {
+    frames: Vec<OTransformTreeFrame<T, P>>,
+    name_to_idx: HashMap<String, usize>,
+    pub max_history_length: usize,
+    _phantom_data: PhantomData<T>
+}
+impl<T: AD, P: O3DPose<T>> OTransformTree<T, P> {
+    pub fn new() -> Self {
+        Self { frames: vec![], name_to_idx: HashMap::new(), max_history_length: 100, _phantom_data: PhantomData::default() }
+    }
+    /// `parent` of `None` makes a root frame, whose pose is relative to the world.
+    pub fn add_frame(&mut self, name: &str, parent: Option<&str>, pose: P, stamp: f64) -> Result<(), OptimaError> {
+        if self.name_to_idx.contains_key(name) { return Err(OptimaError::InvalidInput(format!("frame {} already exists.", name))); }
+        let parent_idx = match parent {
+            None => { None }
+            Some(parent) => { Some(self.frame_idx(parent)?) }
+        };
+        self.name_to_idx.insert(name.to_string(), self.frames.len());
+        self.frames.push(OTransformTreeFrame { name: name.to_string(), parent_idx, history: vec![(stamp, pose)], _phantom_data: PhantomData::default() });
+        Ok(())
+    }
+    /// Records the pose of `name` relative to its parent at `stamp`.  Updates may come in out of
+    /// order.
+    pub fn set_transform(&mut self, name: &str, pose: P, stamp: f64) -> Result<(), OptimaError> {
+        let idx = self.frame_idx(name)?;
+        let max_history_length = self.max_history_length.max(1);
+        let history = &mut self.frames[idx].history;
+        let i = history.partition_point(|(s, _)| *s <= stamp);
+        history.insert(i, (stamp, pose));
+        if history.len() > max_history_length { history.drain(0..history.len() - max_history_length); }
+        Ok(())
+    }
+    /// Moves `name` (and the frames below it) under `parent`, keeping its stored poses.
+    pub fn set_parent(&mut self, name: &str, parent: Option<&str>) -> Result<(), OptimaError> {
+        let idx = self.frame_idx(name)?;
+        let parent_idx = match parent {
+            None => { None }
+            Some(parent) => {
+                let parent_idx = self.frame_idx(parent)?;
+                if self.chain_to_root(parent_idx).contains(&idx) { return Err(OptimaError::InvalidInput(format!("frame {} cannot be a child of its descendant {}.", name, parent))); }
+                Some(parent_idx)
+            }
+        };
+        self.frames[idx].parent_idx = parent_idx;
+        Ok(())
+    }
+    /// Removes `name`; its children become children of its parent.  Their histories are rebuilt
+    /// relative to the new parent at the stamps of both their own and the removed frame's history
+    /// (up to `max_history_length`), so their world transforms at those stamps do not change.
+    pub fn remove_frame(&mut self, name: &str) -> Result<(), OptimaError> {
+        let idx = self.frame_idx(name)?;
+        let removed = self.frames.remove(idx);
+        let max_history_length = self.max_history_length.max(1);
+        for frame in self.frames.iter_mut() {
+            if frame.parent_idx == Some(idx) {
+                let mut stamps: Vec<f64> = frame.history.iter().chain(removed.history.iter()).map(|(s, _)| *s).collect();
+                stamps.sort_by(|a, b| a.total_cmp(b));
+                stamps.dedup();
+                let stamps = &stamps[stamps.len().saturating_sub(max_history_length)..];
+
+                frame.history = stamps.iter().map(|s| (*s, removed.pose_at(Some(*s)).mul(&frame.pose_at(Some(*s))))).collect();
+                frame.parent_idx = removed.parent_idx;
+            }
+        }
+        for frame in self.frames.iter_mut() {
+            if let Some(parent_idx) = frame.parent_idx.as_mut() { if *parent_idx > idx { *parent_idx -= 1; } }
+        }
+        self.name_to_idx = self.frames.iter().enumerate().map(|(i, x)| (x.name.clone(), i)).collect();
+        Ok(())
+    }
+    /// Pose of `name` relative to its parent at `stamp`, or its latest pose if `stamp` is `None`.
+    pub fn local_transform(&self, name: &str, stamp: Option<f64>) -> Result<P, OptimaError> {
+        Ok(self.frames[self.frame_idx(name)?].pose_at(stamp))
+    }
+    /// Pose of `name` relative to the world.
+    pub fn world_transform(&self, name: &str, stamp: Option<f64>) -> Result<P, OptimaError> {
+        let idx = self.frame_idx(name)?;
+        Ok(self.world_transform_by_idx(idx, stamp))
+    }
+    /// Pose of frame `from` expressed in frame `to`, i.e., the transform taking points in `from`'s
+    /// coordinates to `to`'s.
+    pub fn lookup(&self, from: &str, to: &str, stamp: Option<f64>) -> Result<P, OptimaError> {
+        let from_idx = self.frame_idx(from)?;
+        let to_idx = self.frame_idx(to)?;
+        let from_world = self.world_transform_by_idx(from_idx, stamp);
+        let to_world = self.world_transform_by_idx(to_idx, stamp);
+        Ok(to_world.inverse().mul(&from_world))
+    }
+    pub fn contains_frame(&self, name: &str) -> bool {
+        self.name_to_idx.contains_key(name)
+    }
+    pub fn frame_names(&self) -> Vec<&str> {
+        self.frames.iter().map(|x| x.name.as_str()).collect()
+    }
+    pub fn parent(&self, name: &str) -> Result<Option<&str>, OptimaError> {
+        let idx = self.frame_idx(name)?;
+        Ok(self.frames[idx].parent_idx.map(|x| self.frames[x].name.as_str()))
+    }
+    pub fn children(&self, name: Option<&str>) -> Result<Vec<&str>, OptimaError> {
+        let idx = match name {
+            None => { None }
+            Some(name) => { Some(self.frame_idx(name)?) }
+        };
+        Ok(self.frames.iter().filter(|x| x.parent_idx == idx).map(|x| x.name.as_str()).collect())
+    }
+    /// Stamp of the latest pose of `name`.
+    pub fn latest_stamp(&self, name: &str) -> Result<f64, OptimaError> {
+        let idx = self.frame_idx(name)?;
+        Ok(self.frames[idx].history.last().expect("error").0)
+    }
+    fn frame_idx(&self, name: &str) -> Result<usize, OptimaError> {
+        self.name_to_idx.get(name).cloned().ok_or_else(|| OptimaError::InvalidInput(format!("frame {} does not exist.", name)))
+    }
+    /// `idx` followed by its ancestors, up to its root frame.
+    fn chain_to_root(&self, idx: usize) -> Vec<usize> {
+        let mut out = vec![idx];
+        while let Some(parent_idx) = self.frames[*out.last().expect("error")].parent_idx { out.push(parent_idx); }
+        out
+    }
+    fn world_transform_by_idx(&self, idx: usize, stamp: Option<f64>) -> P {
+        self.chain_to_root(idx).iter().rev().fold(P::identity(), |acc, x| acc.mul(&self.frames[*x].pose_at(stamp)))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct OTransformTreeFrame<T: AD, P: O3DPose<T>> {
+    name: String,
+    parent_idx: Option<usize>,
+    /// sorted by stamp, never empty.
+    history: Vec<(f64, P)>,
+    _phantom_data: PhantomData<T>
+}
+impl<T: AD, P: O3DPose<T>> OTransformTreeFrame<T, P> {
+    fn pose_at(&self, stamp: Option<f64>) -> P {
+        let Some(stamp) = stamp else { return self.history.last().expect("error").1.clone(); };
+        let i = self.history.partition_point(|(s, _)| *s <= stamp);
+        if i == 0 { return self.history[0].1.clone(); }
+        if i == self.history.len() { return self.history[i - 1].1.clone(); }
+
+        let (s0, p0) = &self.history[i - 1];
+        let (s1, p1) = &self.history[i];
+        let t = if s1 > s0 { (stamp - s0) / (s1 - s0) } else { 0.0 };
+        p0.interpolate(p1, T::constant(t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Isometry3;
+    use crate::optima_3d_rotation::ScaledAxis;
+    use super::*;
+
+    fn pose(x: f64, angle: f64) -> Isometry3<f64> {
+        Isometry3::from_constructors(&[x, 0.5, 0.0], &ScaledAxis([0.0, 0.0, angle]))
+    }
+
+    fn assert_same_pose(a: &Isometry3<f64>, b: &Isometry3<f64>) {
+        assert!((a.to_homogeneous() - b.to_homogeneous()).amax() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn frames_compose_and_bad_names_are_errors() {
+        let mut tree: OTransformTree<f64, Isometry3<f64>> = OTransformTree::new();
+        tree.add_frame("base", None, pose(1.0, 0.3), 0.0).expect("error");
+        tree.add_frame("camera", Some("base"), pose(0.2, -0.5), 0.0).expect("error");
+        tree.add_frame("target", None, pose(-2.0, 1.0), 0.0).expect("error");
+
+        assert!(tree.add_frame("camera", None, pose(0.0, 0.0), 0.0).is_err());
+        assert!(tree.add_frame("lidar", Some("missing"), pose(0.0, 0.0), 0.0).is_err());
+        assert!(tree.world_transform("missing", None).is_err());
+        assert!(tree.set_parent("base", Some("camera")).is_err());
+
+        assert_same_pose(&tree.world_transform("camera", None).expect("error"), &(pose(1.0, 0.3) * pose(0.2, -0.5)));
+        let expected = pose(-2.0, 1.0).inverse() * pose(1.0, 0.3) * pose(0.2, -0.5);
+        assert_same_pose(&tree.lookup("camera", "target", None).expect("error"), &expected);
+        assert_eq!(tree.parent("camera").expect("error"), Some("base"));
+        assert_eq!(tree.children(None).expect("error"), vec!["base", "target"]);
+    }
+
+    #[test]
+    fn history_is_interpolated_and_clamped() {
+        let mut tree: OTransformTree<f64, Isometry3<f64>> = OTransformTree::new();
+        tree.max_history_length = 3;
+        tree.add_frame("base", None, pose(0.0, 0.0), 0.0).expect("error");
+        tree.set_transform("base", pose(2.0, 0.0), 2.0).expect("error");
+        // out of order.
+        tree.set_transform("base", pose(1.0, 0.0), 1.0).expect("error");
+
+        assert_same_pose(&tree.local_transform("base", Some(0.5)).expect("error"), &pose(0.5, 0.0));
+        assert_same_pose(&tree.local_transform("base", Some(-1.0)).expect("error"), &pose(0.0, 0.0));
+        assert_same_pose(&tree.local_transform("base", Some(5.0)).expect("error"), &pose(2.0, 0.0));
+        assert_eq!(tree.latest_stamp("base").expect("error"), 2.0);
+
+        tree.set_transform("base", pose(3.0, 0.0), 3.0).expect("error");
+        assert_same_pose(&tree.local_transform("base", Some(0.0)).expect("error"), &pose(1.0, 0.0));
+    }
+
+    #[test]
+    fn removing_a_frame_keeps_the_world_transforms_of_its_children_over_time() {
+        let mut tree: OTransformTree<f64, Isometry3<f64>> = OTransformTree::new();
+        tree.add_frame("root", None, pose(0.5, 0.1), 0.0).expect("error");
+        tree.add_frame("arm", Some("root"), pose(0.0, 0.0), 0.0).expect("error");
+        tree.set_transform("arm", pose(1.0, 1.0), 1.0).expect("error");
+        tree.add_frame("gripper", Some("arm"), pose(0.3, 0.2), 0.5).expect("error");
+        tree.set_transform("gripper", pose(0.6, -0.4), 2.0).expect("error");
+
+        let stamps = [0.0, 0.5, 1.0, 2.0];
+        let before: Vec<Isometry3<f64>> = stamps.iter().map(|s| tree.world_transform("gripper", Some(*s)).expect("error")).collect();
+
+        tree.remove_frame("arm").expect("error");
+        assert!(!tree.contains_frame("arm"));
+        assert_eq!(tree.parent("gripper").expect("error"), Some("root"));
+        stamps.iter().zip(before.iter()).for_each(|(s, x)| assert_same_pose(&tree.world_transform("gripper", Some(*s)).expect("error"), x));
+        assert!(tree.remove_frame("arm").is_err());
+    }
+}
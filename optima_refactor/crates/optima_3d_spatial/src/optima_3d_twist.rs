@@ -0,0 +1,245 @@
+use ad_trait::AD;
+use nalgebra::{Matrix3, Matrix6, Vector3, Vector6};
+use serde::{Deserialize, Serialize};
+use crate::optima_3d_pose::O3DPose;
+use crate::optima_3d_rotation::O3DRotation;
+use crate::optima_3d_vec::O3DVec;
+
+/// A spatial velocity, with its angular part first as in `O3DPose::ln`.  Twists and wrenches are
+/// expressed in some frame; `transform` moves them from a frame `B` to a frame `A` given the pose of
+/// `B` in `A`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OTwist<T: AD> {
+    #[serde(deserialize_with = "Vector3::<T>::deserialize")]
+    angular: Vector3<T>,
+    #[serde(deserialize_with = "Vector3::<T>::deserialize")]
+    linear: Vector3<T>
+}
+impl<T: AD> OTwist<T> {
+    pub fn new<V1: O3DVec<T>, V2: O3DVec<T>>(angular: &V1, linear: &V2) -> Self {
+        Self { angular: to_vector3(angular), linear: to_vector3(linear) }
+    }
+    pub fn zero() -> Self {
+        Self { angular: Vector3::zeros(), linear: Vector3::zeros() }
+    }
+    /// `[w_x, w_y, w_z, v_x, v_y, v_z]`.
+    pub fn from_vector6(v: &Vector6<T>) -> Self {
+        Self { angular: v.fixed_rows::<3>(0).into_owned(), linear: v.fixed_rows::<3>(3).into_owned() }
+    }
+    #[inline(always)]
+    pub fn angular(&self) -> &Vector3<T> {
+        &self.angular
+    }
+    #[inline(always)]
+    pub fn linear(&self) -> &Vector3<T> {
+        &self.linear
+    }
+    pub fn to_vector6(&self) -> Vector6<T> {
+        Vector6::new(self.angular[0], self.angular[1], self.angular[2], self.linear[0], self.linear[1], self.linear[2])
+    }
+    pub fn add(&self, other: &Self) -> Self {
+        Self { angular: self.angular + other.angular, linear: self.linear + other.linear }
+    }
+    pub fn scale(&self, s: T) -> Self {
+        Self { angular: s.mul_by_nalgebra_matrix_ref(&self.angular), linear: s.mul_by_nalgebra_matrix_ref(&self.linear) }
+    }
+    /// Adjoint transform: the same motion expressed in frame `A`, where `pose` is frame `B` (the
+    /// twist's frame) in `A`.
+    pub fn transform<P: O3DPose<T>>(&self, pose: &P) -> Self {
+        let (r, p) = pose_parts(pose);
+        let angular = r * self.angular;
+        Self { linear: r * self.linear + p.cross(&angular), angular }
+    }
+    /// Inverse of `transform`: the twist in frame `B`, given the twist in `A` and `B`'s pose in `A`.
+    pub fn inverse_transform<P: O3DPose<T>>(&self, pose: &P) -> Self {
+        let (r, p) = pose_parts(pose);
+        let r_t = r.transpose();
+        Self { angular: r_t * self.angular, linear: r_t * (self.linear - p.cross(&self.angular)) }
+    }
+    /// The same motion with its linear part measured at `point` instead of the frame origin.
+    pub fn at_point<V: O3DVec<T>>(&self, point: &V) -> Self {
+        let point = to_vector3(point);
+        Self { angular: self.angular, linear: self.linear + self.angular.cross(&point) }
+    }
+    /// Twist cross product, `ad_self(other)`, e.g., the Lie bracket of two twists.
+    pub fn cross_twist(&self, other: &Self) -> Self {
+        Self { angular: self.angular.cross(&other.angular), linear: self.angular.cross(&other.linear) + self.linear.cross(&other.angular) }
+    }
+    /// Dual cross product, `-ad_self^T(wrench)`, e.g., the rate of change of a wrench (or momentum)
+    /// fixed in a frame moving with this twist.
+    pub fn cross_wrench(&self, wrench: &OWrench<T>) -> OWrench<T> {
+        OWrench { moment: self.angular.cross(&wrench.moment) + self.linear.cross(&wrench.force), force: self.angular.cross(&wrench.force) }
+    }
+    /// Power delivered by `wrench` along this twist.
+    pub fn dot(&self, wrench: &OWrench<T>) -> T {
+        self.angular.dot(&wrench.moment) + self.linear.dot(&wrench.force)
+    }
+    /// Matrix form of `cross_twist`, so `ad_matrix() * other.to_vector6() == cross_twist(other)`.
+    pub fn ad_matrix(&self) -> Matrix6<T> {
+        let w = skew(&self.angular);
+        let v = skew(&self.linear);
+        let mut out = Matrix6::zeros();
+        out.fixed_view_mut::<3, 3>(0, 0).copy_from(&w);
+        out.fixed_view_mut::<3, 3>(3, 0).copy_from(&v);
+        out.fixed_view_mut::<3, 3>(3, 3).copy_from(&w);
+        out
+    }
+}
+
+/// A spatial force, with its moment first to line up with `OTwist`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OWrench<T: AD> {
+    #[serde(deserialize_with = "Vector3::<T>::deserialize")]
+    moment: Vector3<T>,
+    #[serde(deserialize_with = "Vector3::<T>::deserialize")]
+    force: Vector3<T>
+}
+impl<T: AD> OWrench<T> {
+    pub fn new<V1: O3DVec<T>, V2: O3DVec<T>>(moment: &V1, force: &V2) -> Self {
+        Self { moment: to_vector3(moment), force: to_vector3(force) }
+    }
+    pub fn zero() -> Self {
+        Self { moment: Vector3::zeros(), force: Vector3::zeros() }
+    }
+    /// `[m_x, m_y, m_z, f_x, f_y, f_z]`.
+    pub fn from_vector6(v: &Vector6<T>) -> Self {
+        Self { moment: v.fixed_rows::<3>(0).into_owned(), force: v.fixed_rows::<3>(3).into_owned() }
+    }
+    #[inline(always)]
+    pub fn moment(&self) -> &Vector3<T> {
+        &self.moment
+    }
+    #[inline(always)]
+    pub fn force(&self) -> &Vector3<T> {
+        &self.force
+    }
+    pub fn to_vector6(&self) -> Vector6<T> {
+        Vector6::new(self.moment[0], self.moment[1], self.moment[2], self.force[0], self.force[1], self.force[2])
+    }
+    pub fn add(&self, other: &Self) -> Self {
+        Self { moment: self.moment + other.moment, force: self.force + other.force }
+    }
+    pub fn scale(&self, s: T) -> Self {
+        Self { moment: s.mul_by_nalgebra_matrix_ref(&self.moment), force: s.mul_by_nalgebra_matrix_ref(&self.force) }
+    }
+    /// The same wrench expressed in frame `A`, where `pose` is frame `B` (the wrench's frame) in `A`.
+    /// Preserves power, i.e., `twist.transform(pose).dot(&wrench.transform(pose)) == twist.dot(&wrench)`.
+    pub fn transform<P: O3DPose<T>>(&self, pose: &P) -> Self {
+        let (r, p) = pose_parts(pose);
+        let force = r * self.force;
+        Self { moment: r * self.moment + p.cross(&force), force }
+    }
+    /// Inverse of `transform`.
+    pub fn inverse_transform<P: O3DPose<T>>(&self, pose: &P) -> Self {
+        let (r, p) = pose_parts(pose);
+        let r_t = r.transpose();
+        Self { moment: r_t * (self.moment - p.cross(&self.force)), force: r_t * self.force }
+    }
+    /// The same wrench with its moment taken about `point` instead of the frame origin.
+    pub fn at_point<V: O3DVec<T>>(&self, point: &V) -> Self {
+        let point = to_vector3(point);
+        Self { moment: self.moment + self.force.cross(&point), force: self.force }
+    }
+}
+
+/// The 6x6 adjoint matrix of `pose`, so `adjoint_matrix(pose) * twist.to_vector6() == twist.transform(pose).to_vector6()`.
+/// Its inverse transpose does the same for wrenches.
+pub fn adjoint_matrix<T: AD, P: O3DPose<T>>(pose: &P) -> Matrix6<T> {
+    let (r, p) = pose_parts(pose);
+    let mut out = Matrix6::zeros();
+    out.fixed_view_mut::<3, 3>(0, 0).copy_from(&r);
+    out.fixed_view_mut::<3, 3>(3, 0).copy_from(&(skew(&p) * r));
+    out.fixed_view_mut::<3, 3>(3, 3).copy_from(&r);
+    out
+}
+
+/// Cross product matrix, i.e., `skew(a) * b == a.cross(&b)`.
+pub fn skew<T: AD>(v: &Vector3<T>) -> Matrix3<T> {
+    Matrix3::new(T::zero(), -v[2], v[1],
+                 v[2], T::zero(), -v[0],
+                 -v[1], v[0], T::zero())
+}
+
+fn to_vector3<T: AD, V: O3DVec<T>>(v: &V) -> Vector3<T> {
+    Vector3::from_column_slice(v.o3dvec_as_slice())
+}
+
+fn pose_parts<T: AD, P: O3DPose<T>>(pose: &P) -> (Matrix3<T>, Vector3<T>) {
+    let r = Matrix3::from_column_slice(&pose.rotation().rotation_matrix_as_column_major_slice());
+    (r, to_vector3(pose.translation()))
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Isometry3;
+    use crate::optima_3d_rotation::ScaledAxis;
+    use super::*;
+
+    fn test_pose() -> Isometry3<f64> {
+        Isometry3::from_constructors(&[0.5, -1.0, 2.0], &ScaledAxis([0.3, -0.7, 1.1]))
+    }
+
+    fn assert_vector6_eq(a: &Vector6<f64>, b: &Vector6<f64>) {
+        assert!((a - b).amax() < 1e-12, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn transform_matches_the_adjoint_matrix_and_inverts() {
+        let pose = test_pose();
+        let twist = OTwist::new(&[0.1, 0.2, -0.3], &[1.0, -2.0, 0.5]);
+
+        let transformed = twist.transform(&pose);
+        assert_vector6_eq(&transformed.to_vector6(), &(adjoint_matrix(&pose) * twist.to_vector6()));
+        assert_vector6_eq(&transformed.inverse_transform(&pose).to_vector6(), &twist.to_vector6());
+
+        let wrench = OWrench::new(&[0.4, 0.0, -1.0], &[3.0, 1.0, 2.0]);
+        let adjoint_inverse_transpose = adjoint_matrix(&pose).try_inverse().expect("error").transpose();
+        assert_vector6_eq(&wrench.transform(&pose).to_vector6(), &(adjoint_inverse_transpose * wrench.to_vector6()));
+        assert_vector6_eq(&wrench.transform(&pose).inverse_transform(&pose).to_vector6(), &wrench.to_vector6());
+    }
+
+    #[test]
+    fn transforms_preserve_power() {
+        let pose = test_pose();
+        let twist = OTwist::new(&[0.1, 0.2, -0.3], &[1.0, -2.0, 0.5]);
+        let wrench = OWrench::new(&[0.4, 0.0, -1.0], &[3.0, 1.0, 2.0]);
+        assert!((twist.transform(&pose).dot(&wrench.transform(&pose)) - twist.dot(&wrench)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rotation_about_an_offset_axis_moves_the_origin() {
+        let spin = OTwist::new(&[0.0, 0.0, 1.0], &[0.0, 0.0, 0.0]);
+        let offset: Isometry3<f64> = Isometry3::from_constructors(&[1.0, 0.0, 0.0], &ScaledAxis([0.0, 0.0, 0.0]));
+        assert_vector6_eq(&spin.transform(&offset).to_vector6(), &Vector6::new(0.0, 0.0, 1.0, 0.0, -1.0, 0.0));
+        assert_vector6_eq(&spin.at_point(&[1.0, 0.0, 0.0]).to_vector6(), &Vector6::new(0.0, 0.0, 1.0, 0.0, 1.0, 0.0));
+
+        let push = OWrench::new(&[0.0, 0.0, 0.0], &[0.0, 1.0, 0.0]);
+        assert_vector6_eq(&push.at_point(&[1.0, 0.0, 0.0]).to_vector6(), &Vector6::new(0.0, 0.0, -1.0, 0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn cross_products_match_the_ad_matrix() {
+        let a = OTwist::new(&[0.1, 0.2, -0.3], &[1.0, -2.0, 0.5]);
+        let b = OTwist::new(&[-0.5, 0.7, 0.2], &[0.3, 0.1, -1.5]);
+        let wrench = OWrench::new(&[0.4, 0.0, -1.0], &[3.0, 1.0, 2.0]);
+
+        assert_vector6_eq(&a.cross_twist(&b).to_vector6(), &(a.ad_matrix() * b.to_vector6()));
+        assert_vector6_eq(&a.cross_wrench(&wrench).to_vector6(), &(-a.ad_matrix().transpose() * wrench.to_vector6()));
+        assert_vector6_eq(&a.cross_twist(&a).to_vector6(), &Vector6::zeros());
+        // the bracket is antisymmetric.
+        assert_vector6_eq(&a.cross_twist(&b).add(&b.cross_twist(&a)).to_vector6(), &Vector6::zeros());
+    }
+
+    #[test]
+    fn twists_and_wrenches_round_trip_through_vector6_and_serde() {
+        let v = Vector6::new(0.1, 0.2, 0.3, 0.4, 0.5, 0.6);
+        assert_vector6_eq(&OTwist::from_vector6(&v).to_vector6(), &v);
+        assert_vector6_eq(&OWrench::from_vector6(&v).to_vector6(), &v);
+        assert_vector6_eq(&OTwist::from_vector6(&v).scale(2.0).to_vector6(), &(v * 2.0));
+
+        let twist: OTwist<f64> = serde_json::from_str(&serde_json::to_string(&OTwist::from_vector6(&v)).expect("error")).expect("error");
+        assert_vector6_eq(&twist.to_vector6(), &v);
+        let wrench: OWrench<f64> = serde_json::from_str(&serde_json::to_string(&OWrench::from_vector6(&v)).expect("error")).expect("error");
+        assert_vector6_eq(&wrench.to_vector6(), &v);
+    }
+}
@@ -383,9 +383,9 @@ impl<'de, T2: AD, V2: O3DVec<T2>> Visitor<'de> for O3dVecMyVisitor<T2, V2> {
     where
         A: SeqAccess<'de>,
     {
-        let x: f64 = seq.next_element().expect("error").expect("error");
-        let y: f64 = seq.next_element().expect("error").expect("error");
-        let z: f64 = seq.next_element().expect("error").expect("error");
+        let x: f64 = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+        let y: f64 = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+        let z: f64 = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(2, &self))?;
         let xad = T2::constant(x);
         let yad = T2::constant(y);
         let zad = T2::constant(z);
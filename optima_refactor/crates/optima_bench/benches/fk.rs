@@ -0,0 +1,21 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use optima_bench::{obench_load_all_robots, obench_sample_states, OBENCH_NUM_SAMPLES};
+
+fn bench_fk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fk");
+    for (robot_name, robot) in obench_load_all_robots() {
+        let states = obench_sample_states(&robot, OBENCH_NUM_SAMPLES);
+        group.bench_function(robot_name.as_str(), |b| {
+            let mut i = 0;
+            b.iter(|| {
+                let res = robot.forward_kinematics(black_box(&states[i % states.len()]), None);
+                i += 1;
+                res
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_fk);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+use ad_trait::differentiable_function::ForwardADMulti;
+use ad_trait::forward_ad::adfn::adfn;
+use criterion::{criterion_group, criterion_main, Criterion};
+use optima_bench::{obench_ee_link_idx, obench_load_all_robots, obench_sample_ik_goals, OBENCH_NUM_SAMPLES};
+use optima_optimization::DiffBlockOptimizerTrait;
+use optima_optimization::open::SimpleOpEnOptimizer;
+use optima_proximity::pair_group_queries::{OwnedEmptyParryFilter, OwnedEmptyToProximityQry};
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
+use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
+use optima_robotics::robotics_optimization::robotics_optimization_ik_batch::IKBatchSettings;
+
+type FAD = adfn<8>;
+
+/// Latency of a single ik solve from the zero state to a reachable goal.  The block and optimizer
+/// are built once, as they would be in an interactive loop.
+fn bench_ik_solve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ik_solve");
+    for (robot_name, robot) in obench_load_all_robots() {
+        let link_idx = obench_ee_link_idx(&robot);
+        let goals = obench_sample_ik_goals(&robot, link_idx, OBENCH_NUM_SAMPLES);
+        let init_state = vec![0.0; robot.num_dofs()];
+        let db = robot.get_ik_differentiable_block(ForwardADMulti::<FAD>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![link_idx], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0));
+        let o = SimpleOpEnOptimizer::new(robot.get_dof_lower_bounds(), robot.get_dof_upper_bounds(), 0.001);
+
+        group.bench_function(robot_name.as_str(), |b| {
+            let mut i = 0;
+            b.iter(|| {
+                db.update_ik_pose(0, goals[i % goals.len()].clone(), IKGoalUpdateMode::Absolute);
+                db.update_prev_states(init_state.clone());
+                let res = o.optimize_unconstrained(&init_state, &db);
+                i += 1;
+                res
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Throughput of `solve_ik_batch` over OBENCH_NUM_SAMPLES problems from the zero state, on all
+/// available threads.  Includes building each worker's block and optimizer.
+fn bench_ik_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ik_batch");
+    group.sample_size(10);
+    for (robot_name, robot) in obench_load_all_robots() {
+        let link_idx = obench_ee_link_idx(&robot);
+        let goals: Vec<Vec<_>> = obench_sample_ik_goals(&robot, link_idx, OBENCH_NUM_SAMPLES).into_iter().map(|x| vec![x]).collect();
+        let seeds = vec![vec![0.0; robot.num_dofs()]];
+        let settings = IKBatchSettings::default();
+
+        group.bench_function(robot_name.as_str(), |b| {
+            b.iter(|| robot.solve_ik_batch(ForwardADMulti::<FAD>::new(), &[link_idx], &goals, &seeds, &settings).expect("error"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_ik_solve, bench_ik_batch);
+criterion_main!(benches);
@@ -0,0 +1,29 @@
+use std::borrow::Cow;
+use ad_trait::differentiable_function::ForwardADMulti;
+use ad_trait::forward_ad::adfn::adfn;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use optima_bench::{obench_ee_link_idx, obench_load_all_robots, obench_sample_states, OBENCH_NUM_SAMPLES};
+
+type FAD = adfn<8>;
+
+fn bench_jacobian(c: &mut Criterion) {
+    let mut group = c.benchmark_group("jacobian");
+    for (robot_name, robot) in obench_load_all_robots() {
+        let states = obench_sample_states(&robot, OBENCH_NUM_SAMPLES);
+        let link_idx = obench_ee_link_idx(&robot);
+        let ad_robot = robot.to_other_ad_type::<FAD>();
+        let block = robot.get_link_jacobian_differentiable_block(ForwardADMulti::<FAD>::new(), Cow::Borrowed(&ad_robot), link_idx).expect("error");
+        group.bench_function(robot_name.as_str(), |b| {
+            let mut i = 0;
+            b.iter(|| {
+                let res = robot.link_jacobian_from_block(&block, black_box(&states[i % states.len()])).expect("error");
+                i += 1;
+                res
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_jacobian);
+criterion_main!(benches);
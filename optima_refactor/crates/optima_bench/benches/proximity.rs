@@ -0,0 +1,27 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use optima_bench::{obench_load_all_robots, obench_sample_states, OBENCH_NUM_SAMPLES};
+use optima_proximity::pair_group_queries::{OParryDistanceGroupArgs, OParryPairSelector, OwnedParryDistanceGroupQry};
+use optima_proximity::pair_queries::{ParryDisMode, ParryShapeRep};
+
+/// Self distance queries over all shape pairs, once per `ParryShapeRep`.
+fn bench_self_distance(c: &mut Criterion) {
+    let mut group = c.benchmark_group("self_distance");
+    for (robot_name, robot) in obench_load_all_robots() {
+        let states = obench_sample_states(&robot, OBENCH_NUM_SAMPLES);
+        for shape_rep in [ParryShapeRep::BoundingSphere, ParryShapeRep::OBB, ParryShapeRep::Full] {
+            let q = OwnedParryDistanceGroupQry::new(OParryDistanceGroupArgs::new(shape_rep.clone(), shape_rep.clone(), ParryDisMode::ContactDis, true, false, f64::MIN, false));
+            group.bench_function(format!("{}/{:?}", robot_name, shape_rep), |b| {
+                let mut i = 0;
+                b.iter(|| {
+                    let res = robot.parry_shape_scene_self_query(black_box(&states[i % states.len()]), &q, &OParryPairSelector::HalfPairs, false);
+                    i += 1;
+                    res
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_self_distance);
+criterion_main!(benches);
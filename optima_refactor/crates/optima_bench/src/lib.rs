@@ -0,0 +1,58 @@
+//! Shared fixtures for the benchmarks in `benches/`.  Everything is seeded so that runs on
+//! different commits measure the same work.
+//!
+//! Run with `cargo bench -p optima_bench`, or e.g. `cargo bench -p optima_bench --bench fk -- ur5`
+//! to narrow it down.  Criterion keeps the previous run in `target/criterion` and reports the
+//! change against it, so benchmarking before and after a refactor shows any regression.
+
+use nalgebra::Isometry3;
+use optima_file::path::{OAssetLocation, OStemCellPath};
+use optima_robotics::robot::{ORobotDefault, SaveRobot};
+use optima_sampling::SimpleSampler;
+
+/// The robots bundled with the toolbox.
+pub const OBENCH_ROBOTS: [&str; 4] = ["ur5", "sawyer", "fetch", "hubo"];
+
+/// Number of states (or ik problems) each benchmark iterates over.
+pub const OBENCH_NUM_SAMPLES: usize = 64;
+
+/// Loads the preprocessed robot if it has been saved, and otherwise loads it from its urdf and
+/// preprocesses it (saving the result, so this only happens once per machine).  Returns None if
+/// the robot is not in the asset folder.
+pub fn obench_load_robot(robot_name: &str) -> Option<ORobotDefault> {
+    let mut saved_robot_path = OStemCellPath::new_asset_path();
+    saved_robot_path.append_file_location(&OAssetLocation::SavedRobot { robot_name });
+    if saved_robot_path.exists() { return Some(ORobotDefault::load_from_saved_robot_unchecked(robot_name)); }
+
+    let mut urdf_dir = OStemCellPath::new_asset_path();
+    urdf_dir.append_file_location(&OAssetLocation::UrdfRobot { robot_name });
+    if !urdf_dir.exists() { return None; }
+
+    let mut robot = ORobotDefault::from_urdf_unchecked(robot_name);
+    robot.preprocess(SaveRobot::Save(None));
+    Some(robot)
+}
+
+/// All bundled robots that are available, paired with their names.
+pub fn obench_load_all_robots() -> Vec<(String, ORobotDefault)> {
+    OBENCH_ROBOTS.iter().filter_map(|x| obench_load_robot(x).map(|r| (x.to_string(), r))).collect()
+}
+
+/// `num_samples` states drawn uniformly within the robot's joint limits.  Sample i always uses
+/// seed i.
+pub fn obench_sample_states(robot: &ORobotDefault, num_samples: usize) -> Vec<Vec<f64>> {
+    let bounds: Vec<(f64, f64)> = robot.get_dof_lower_bounds().into_iter().zip(robot.get_dof_upper_bounds().into_iter()).collect();
+    (0..num_samples).map(|i| SimpleSampler::uniform_samples(&bounds, Some(i as u64))).collect()
+}
+
+/// The link that ik benchmarks target: the last link in the urdf that is present in the model.
+pub fn obench_ee_link_idx(robot: &ORobotDefault) -> usize {
+    robot.links().iter().rev().find(|x| x.is_present_in_model()).expect("error").link_idx()
+}
+
+/// Reachable ik goals for the given link, found by running fk on sampled states.
+pub fn obench_sample_ik_goals(robot: &ORobotDefault, link_idx: usize, num_samples: usize) -> Vec<Isometry3<f64>> {
+    obench_sample_states(robot, num_samples).iter().map(|state| {
+        robot.forward_kinematics(state, None).get_link_pose_unchecked(link_idx).clone()
+    }).collect()
+}
@@ -4,7 +4,7 @@ use bevy::window::PrimaryWindow;
 use bevy_egui::{EguiContexts, EguiPlugin};
 use bevy_egui::egui::{Color32, Pos2, Visuals};
 use bevy_egui::egui::panel::{Side, TopBottomSide};
-use optima_bevy_egui::{OEguiButton, OEguiContainerTrait, OEguiEngine, OEguiEngineWrapper, OEguiSelector, OEguiSelectorMode, OEguiSidePanel, OEguiSlider, OEguiTextbox, OEguiTextboxResponse, OEguiTopBottomPanel, OEguiWindow};
+use optima_bevy_egui::{OEguiButton, OEguiContainerTrait, OEguiDock, OEguiEngine, OEguiEngineWrapper, OEguiSelector, OEguiSelectorMode, OEguiSidePanel, OEguiSlider, OEguiTabContainer, OEguiTextbox, OEguiTextboxResponse, OEguiTopBottomPanel, OEguiWindow};
 use optima_bevy_egui::OEguiWidgetTrait;
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 
@@ -61,6 +61,14 @@ fn ui_example_system(mut contexts: EguiContexts, egui_engine: Res<OEguiEngineWra
                 .show("button4", ui, &egui_engine, &());
         });
 
+    OEguiTabContainer::new("tabs", &["buttons", "text"], OEguiDock::Right { default_width: 250.0 })
+        .show_tabs("tab_container", contexts.ctx_mut(), &egui_engine, &window_query, |ui, tab| {
+            match tab {
+                "buttons" => { OEguiButton::new("tabbed").show("button5", ui, &egui_engine, &()); }
+                _ => { OEguiTextbox::new(false).show("text2", ui, &egui_engine, &()); }
+            }
+        });
+
     if keys.pressed(KeyCode::B) {
         egui_engine.get_mutex_guard().close_side_panel("side_panel");
     }
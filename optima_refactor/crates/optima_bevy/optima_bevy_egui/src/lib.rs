@@ -1,11 +1,24 @@
-use std::collections::HashMap;
-use std::sync::{Mutex, MutexGuard};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, RwLock};
+use std::time::Duration;
+use ad_trait::AD;
 use bevy::prelude::*;
+use bevy::utils::Instant;
 use bevy::window::PrimaryWindow;
 use bevy_egui::egui;
 use bevy_egui::egui::{Align2, Color32, Context, Id, Pos2, Response, Ui};
 use bevy_egui::egui::panel::{Side, TopBottomSide};
-use optima_file::traits::{FromRonString, ToRonString};
+use bevy_egui::egui::plot::{Legend, Line, Plot, PlotPoints};
+use nalgebra::{Quaternion, UnitQuaternion};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use optima_3d_spatial::optima_3d_pose::O3DPose;
+use optima_3d_spatial::optima_3d_rotation::{O3DRotation, QuatConstructor};
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_error::OptimaError;
+use optima_file::path::{OPath, OSaveFormat};
+use optima_file::traits::OStringEncoding;
 
 #[derive(Resource)]
 pub struct OEguiEngineWrapper(pub Mutex<OEguiEngine>);
@@ -25,12 +38,31 @@ pub struct OEguiEngine {
     window_states: HashMap<String, OEguiWindowState>,
     side_panel_states: HashMap<String, OEguiSidePanelState>,
     top_bottom_panel_states: HashMap<String, OEguiTopBottomPanelState>,
+    tab_container_states: HashMap<String, OEguiTabContainerState>,
     button_responses: HashMap<String, OEguiButtonResponse>,
     slider_responses: HashMap<String, OEguiSliderResponse>,
+    drag_value_responses: HashMap<String, OEguiDragValueResponse>,
     checkbox_responses: HashMap<String, OEguiCheckboxResponse>,
+    color_picker_responses: HashMap<String, OEguiColorPickerResponse>,
     radiobutton_responses: HashMap<String, OEguiRadiobuttonResponse>,
     selector_responses: HashMap<String, OEguiSelectorResponse>,
-    textbox_responses: HashMap<String, OEguiTextboxResponse>
+    textbox_responses: HashMap<String, OEguiTextboxResponse>,
+    plot_responses: HashMap<String, OEguiPlotResponse>,
+    pose_editor_responses: HashMap<String, OEguiPoseEditorResponse>,
+    progress_bar_responses: HashMap<String, OEguiProgressBarResponse>,
+    tree_responses: HashMap<String, OEguiTreeResponse>,
+    table_responses: HashMap<String, OEguiTableResponse>,
+    file_dialog_responses: HashMap<String, OEguiFileDialogResponse>,
+    plot_buffers: HashMap<String, OEguiPlotBuffer>,
+    toasts: OEguiToasts,
+    /// ids of the windows, side panels, top/bottom panels, and tab containers closed by
+    /// `toggle_hide_all_containers`.
+    hidden_containers: Option<[Vec<String>; 4]>,
+    /// time spent showing containers (contents included) since the last `take_container_time`.
+    container_time: Duration,
+    /// number of containers currently being shown, so nested ones are not timed twice.
+    container_depth: usize,
+    restored_state: OEguiEngineState
 }
 impl OEguiEngine {
     pub fn new() -> Self {
@@ -39,12 +71,27 @@ impl OEguiEngine {
             window_states: Default::default(),
             side_panel_states: Default::default(),
             top_bottom_panel_states: Default::default(),
+            tab_container_states: Default::default(),
             button_responses: Default::default(),
             slider_responses: Default::default(),
+            drag_value_responses: Default::default(),
             checkbox_responses: Default::default(),
+            color_picker_responses: Default::default(),
             radiobutton_responses: Default::default(),
             selector_responses: Default::default(),
             textbox_responses: Default::default(),
+            plot_responses: Default::default(),
+            pose_editor_responses: Default::default(),
+            progress_bar_responses: Default::default(),
+            tree_responses: Default::default(),
+            table_responses: Default::default(),
+            file_dialog_responses: Default::default(),
+            plot_buffers: Default::default(),
+            toasts: OEguiToasts::new(),
+            hidden_containers: None,
+            container_time: Duration::ZERO,
+            container_depth: 0,
+            restored_state: Default::default(),
         }
     }
     pub fn reset_on_frame(&mut self) {
@@ -54,6 +101,19 @@ impl OEguiEngine {
     pub fn ui_contains_pointer(&self) -> bool {
         self.ui_contains_pointer
     }
+    /// Time spent showing windows, panels, and tab containers since the last call, including the
+    /// time taken by their contents closures.
+    pub fn take_container_time(&mut self) -> Duration {
+        std::mem::replace(&mut self.container_time, Duration::ZERO)
+    }
+    fn start_container_timer(&mut self) -> Option<Instant> {
+        self.container_depth += 1;
+        if self.container_depth == 1 { Some(Instant::now()) } else { None }
+    }
+    fn stop_container_timer(&mut self, start: Option<Instant>) {
+        self.container_depth -= 1;
+        if let Some(start) = start { self.container_time += start.elapsed(); }
+    }
     pub fn open_window(&mut self, id_str: &str) {
         let window_state = self.window_states.get_mut(id_str);
         match window_state {
@@ -132,12 +192,158 @@ impl OEguiEngine {
             }
         }
     }
+    /// Closes every open window, panel, and tab container, or reopens the ones closed by the previous
+    /// call.  Handy for a clear view of the scene (e.g., before a screenshot).
+    pub fn toggle_hide_all_containers(&mut self) {
+        match self.hidden_containers.take() {
+            None => {
+                let mut hidden: [Vec<String>; 4] = Default::default();
+                self.window_states.iter_mut().filter(|(_, v)| v.open).for_each(|(k, v)| { v.open = false; hidden[0].push(k.clone()); });
+                self.side_panel_states.iter_mut().filter(|(_, v)| v.open).for_each(|(k, v)| { v.open = false; hidden[1].push(k.clone()); });
+                self.top_bottom_panel_states.iter_mut().filter(|(_, v)| v.open).for_each(|(k, v)| { v.open = false; hidden[2].push(k.clone()); });
+                self.tab_container_states.iter_mut().filter(|(_, v)| v.open).for_each(|(k, v)| { v.open = false; hidden[3].push(k.clone()); });
+                self.hidden_containers = Some(hidden);
+            }
+            Some(hidden) => {
+                hidden[0].iter().for_each(|k| { if let Some(v) = self.window_states.get_mut(k) { v.open = true; } });
+                hidden[1].iter().for_each(|k| { if let Some(v) = self.side_panel_states.get_mut(k) { v.open = true; } });
+                hidden[2].iter().for_each(|k| { if let Some(v) = self.top_bottom_panel_states.get_mut(k) { v.open = true; } });
+                hidden[3].iter().for_each(|k| { if let Some(v) = self.tab_container_states.get_mut(k) { v.open = true; } });
+            }
+        }
+    }
+    /// Takes effect the next time the tab container is shown.  Tabs the container does not have are
+    /// ignored.
+    pub fn select_tab(&mut self, id_str: &str, tab: &str) {
+        match self.tab_container_states.get_mut(id_str) {
+            None => { self.restored_state.tab_selections.insert(id_str.to_string(), tab.to_string()); }
+            Some(state) => { state.selected_tab = tab.to_string(); }
+        }
+    }
+    /// Moves a slider that has already been shown.  Otherwise, the value replaces the slider's start
+    /// value the first time it is shown, so the UI can be initialized (e.g., from a saved robot
+    /// state) before any system runs.  The other `set_*` functions work the same way.
+    pub fn set_slider_value(&mut self, id_str: &str, value: f64) {
+        match self.slider_responses.get_mut(id_str) {
+            None => { self.restored_state.slider_values.insert(id_str.to_string(), value); }
+            Some(response) => { response.slider_value = value; }
+        }
+    }
+    /// The value is clamped to the drag value's range when it is shown.
+    pub fn set_drag_value(&mut self, id_str: &str, value: f64) {
+        match self.drag_value_responses.get_mut(id_str) {
+            None => { self.restored_state.drag_values.insert(id_str.to_string(), value); }
+            Some(response) => { response.value = value; }
+        }
+    }
+    pub fn set_checkbox_value(&mut self, id_str: &str, value: bool) {
+        match self.checkbox_responses.get_mut(id_str) {
+            None => { self.restored_state.checkbox_values.insert(id_str.to_string(), value); }
+            Some(response) => { response.currently_selected = value; }
+        }
+    }
+    /// Unmultiplied rgba in [0, 1].
+    pub fn set_color_value(&mut self, id_str: &str, rgba: [f32; 4]) {
+        match self.color_picker_responses.get_mut(id_str) {
+            None => { self.restored_state.color_values.insert(id_str.to_string(), rgba); }
+            Some(response) => { response.rgba = rgba; }
+        }
+    }
+    /// Selections are encoded like the selector's choices.  Selectors that have not been shown yet
+    /// are assumed to use ron (the `OEguiSelector::new` default); use
+    /// `set_selector_selection_with_encoding` for the others.  Selections that are not among the
+    /// choices are dropped when the selector is first shown.
+    pub fn set_selector_selection<S: Serialize>(&mut self, id_str: &str, selections: &[S]) -> Result<(), OptimaError> {
+        let value_encoding = self.selector_responses.get(id_str).map(|x| x.value_encoding).unwrap_or(OStringEncoding::Ron);
+        self.set_selector_selection_with_encoding(id_str, selections, value_encoding)
+    }
+    /// Errors (and changes nothing) if the selector has been shown, only allows one selection, and
+    /// is given more than one.
+    pub fn set_selector_selection_with_encoding<S: Serialize>(&mut self, id_str: &str, selections: &[S], value_encoding: OStringEncoding) -> Result<(), OptimaError> {
+        let selections_as_strings = selections.iter().map(|x| value_encoding.encode(x)).collect::<Result<Vec<String>, OptimaError>>()?;
+        match self.selector_responses.get_mut(id_str) {
+            None => { self.restored_state.selector_selections.insert(id_str.to_string(), selections_as_strings); }
+            Some(response) => {
+                if !response.allow_multiple_selections && selections_as_strings.len() > 1 { return Err(OptimaError::InvalidInput(format!("selector {} only allows one selection, but was given {}.", id_str, selections_as_strings.len()))); }
+                response.current_selections_as_strings = selections_as_strings;
+            }
+        }
+        Ok(())
+    }
+    /// The rolling buffer shown by the `OEguiPlot` with the given `id_str`, created (empty) if it
+    /// does not exist yet, so samples can be pushed before the plot is first shown.
+    pub fn plot_buffer_mut(&mut self, id_str: &str) -> &mut OEguiPlotBuffer {
+        self.plot_buffers.entry(id_str.to_string()).or_insert_with(OEguiPlotBuffer::default)
+    }
+    /// Shows a transient notification in the corner of the screen (see `OEguiToasts`), e.g., when a
+    /// long running action finishes.
+    pub fn push_info(&mut self, text: &str) {
+        self.toasts.push(text, OEguiToastLevel::Info);
+    }
+    pub fn push_warning(&mut self, text: &str) {
+        self.toasts.push(text, OEguiToastLevel::Warning);
+    }
+    pub fn push_error(&mut self, text: &str) {
+        self.toasts.push(text, OEguiToastLevel::Error);
+    }
+    #[inline(always)]
+    pub fn toasts_mut(&mut self) -> &mut OEguiToasts {
+        &mut self.toasts
+    }
+    /// Snapshot of the state that should survive a restart: window positions, which windows and
+    /// panels are open, and the values of sliders, drag values, checkboxes, color pickers, and
+    /// selectors.
+    pub fn state(&self) -> OEguiEngineState {
+        let mut out = self.restored_state.clone();
+
+        self.window_states.iter().for_each(|(k, v)| { out.windows.insert(k.clone(), OEguiSavedWindowState { open: v.open, position: [v.position.x, v.position.y] }); });
+        self.side_panel_states.iter().for_each(|(k, v)| { out.side_panels_open.insert(k.clone(), v.open); });
+        self.top_bottom_panel_states.iter().for_each(|(k, v)| { out.top_bottom_panels_open.insert(k.clone(), v.open); });
+        self.tab_container_states.iter().for_each(|(k, v)| { out.tab_selections.insert(k.clone(), v.selected_tab.clone()); });
+        self.slider_responses.iter().for_each(|(k, v)| { out.slider_values.insert(k.clone(), v.slider_value); });
+        self.drag_value_responses.iter().for_each(|(k, v)| { out.drag_values.insert(k.clone(), v.value); });
+        self.checkbox_responses.iter().for_each(|(k, v)| { out.checkbox_values.insert(k.clone(), v.currently_selected); });
+        self.color_picker_responses.iter().for_each(|(k, v)| { out.color_values.insert(k.clone(), v.rgba); });
+        self.selector_responses.iter().for_each(|(k, v)| { out.selector_selections.insert(k.clone(), v.current_selections_as_strings.clone()); });
+
+        out
+    }
+    /// Windows and panels are updated right away.  Widget values are applied to widgets that have
+    /// already been shown, and otherwise replace the widget's start value the first time it is shown.
+    pub fn set_state(&mut self, state: OEguiEngineState) {
+        state.windows.iter().for_each(|(k, v)| { self.window_states.insert(k.clone(), OEguiWindowState::new(v.open, Pos2::new(v.position[0], v.position[1]), true)); });
+        state.side_panels_open.iter().for_each(|(k, v)| { self.side_panel_states.insert(k.clone(), OEguiSidePanelState { open: *v }); });
+        state.top_bottom_panels_open.iter().for_each(|(k, v)| { self.top_bottom_panel_states.insert(k.clone(), OEguiTopBottomPanelState { open: *v }); });
+        state.tab_selections.iter().for_each(|(k, v)| { if let Some(r) = self.tab_container_states.get_mut(k) { r.selected_tab = v.clone(); } });
+        state.slider_values.iter().for_each(|(k, v)| { if let Some(r) = self.slider_responses.get_mut(k) { r.slider_value = *v; } });
+        state.drag_values.iter().for_each(|(k, v)| { if let Some(r) = self.drag_value_responses.get_mut(k) { r.value = *v; } });
+        state.checkbox_values.iter().for_each(|(k, v)| { if let Some(r) = self.checkbox_responses.get_mut(k) { r.currently_selected = *v; } });
+        state.color_values.iter().for_each(|(k, v)| { if let Some(r) = self.color_picker_responses.get_mut(k) { r.rgba = *v; } });
+        state.selector_selections.iter().for_each(|(k, v)| { if let Some(r) = self.selector_responses.get_mut(k) { r.current_selections_as_strings = v.clone(); } });
+
+        self.restored_state = state;
+    }
+    pub fn save_state_to_ron(&self, path: &OPath) -> Result<(), OptimaError> {
+        path.save_object_to_file(&self.state(), OSaveFormat::Ron).map_err(|e| OptimaError::new_file_io(path.to_string(), e))
+    }
+    pub fn load_state_from_ron(&mut self, path: &OPath) -> Result<(), OptimaError> {
+        let state = path.load_object_from_file::<OEguiEngineState>().map_err(|e| OptimaError::new_file_io(path.to_string(), e))?;
+        self.set_state(state);
+        Ok(())
+    }
+    /// Theme used by every optima egui container from now on.
+    pub fn set_theme(theme: OEguiTheme) {
+        *OEGUI_THEME.write().expect("error") = theme;
+    }
+    pub fn theme() -> OEguiTheme {
+        *OEGUI_THEME.read().expect("error")
+    }
     pub fn set_style(ctx: &Context) {
         let alpha = 130;
         // let alpha2 = 200;
         // let blue = 100;
 
-        catppuccin_egui::set_theme(ctx, catppuccin_egui::MACCHIATO);
+        catppuccin_egui::set_theme(ctx, Self::theme().to_catppuccin_theme());
         let mut style = (*ctx.style()).clone();
         // let c = style.visuals.window_fill.clone();
         // style.visuals.window_fill = Color32::from_rgba_unmultiplied(c.r(), c.g(), c.b(), alpha);
@@ -188,13 +394,167 @@ macro_rules! egui_engine_helpers {
 
 egui_engine_helpers!(get_button_response, get_button_response_mut, button_responses, OEguiButtonResponse);
 egui_engine_helpers!(get_slider_response, get_slider_response_mut, slider_responses, OEguiSliderResponse);
+egui_engine_helpers!(get_drag_value_response, get_drag_value_response_mut, drag_value_responses, OEguiDragValueResponse);
 egui_engine_helpers!(get_checkbox_response, get_checkbox_response_mut, checkbox_responses, OEguiCheckboxResponse);
+egui_engine_helpers!(get_color_picker_response, get_color_picker_response_mut, color_picker_responses, OEguiColorPickerResponse);
 egui_engine_helpers!(get_radiobutton_response, get_radiobutton_response_mut, radiobutton_responses, OEguiRadiobuttonResponse);
 egui_engine_helpers!(get_selector_response, get_selector_response_mut, selector_responses, OEguiSelectorResponse);
 egui_engine_helpers!(get_textbox_response, get_textbox_response_mut, textbox_responses, OEguiTextboxResponse);
+egui_engine_helpers!(get_plot_response, get_plot_response_mut, plot_responses, OEguiPlotResponse);
+egui_engine_helpers!(get_plot_buffer, get_plot_buffer_mut, plot_buffers, OEguiPlotBuffer);
+egui_engine_helpers!(get_pose_editor_response, get_pose_editor_response_mut, pose_editor_responses, OEguiPoseEditorResponse);
+egui_engine_helpers!(get_progress_bar_response, get_progress_bar_response_mut, progress_bar_responses, OEguiProgressBarResponse);
+egui_engine_helpers!(get_tree_response, get_tree_response_mut, tree_responses, OEguiTreeResponse);
+egui_engine_helpers!(get_table_response, get_table_response_mut, table_responses, OEguiTableResponse);
+egui_engine_helpers!(get_file_dialog_response, get_file_dialog_response_mut, file_dialog_responses, OEguiFileDialogResponse);
 egui_engine_helpers!(get_window_state, get_window_state_mut, window_states, OEguiWindowState);
 egui_engine_helpers!(get_side_panel_state, get_side_panel_state_mut, side_panel_states, OEguiSidePanelState);
 egui_engine_helpers!(get_top_bottom_panel_state, get_top_bottom_panel_state_mut, top_bottom_panel_states, OEguiTopBottomPanelState);
+egui_engine_helpers!(get_tab_container_state, get_tab_container_state_mut, tab_container_states, OEguiTabContainerState);
+
+/// The catppuccin flavours, from lightest (`Latte`) to darkest (`Mocha`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OEguiTheme {
+    Latte,
+    Frappe,
+    Macchiato,
+    Mocha
+}
+impl OEguiTheme {
+    pub fn to_catppuccin_theme(&self) -> catppuccin_egui::Theme {
+        match self {
+            OEguiTheme::Latte => { catppuccin_egui::LATTE }
+            OEguiTheme::Frappe => { catppuccin_egui::FRAPPE }
+            OEguiTheme::Macchiato => { catppuccin_egui::MACCHIATO }
+            OEguiTheme::Mocha => { catppuccin_egui::MOCHA }
+        }
+    }
+}
+impl Default for OEguiTheme {
+    fn default() -> Self {
+        Self::Macchiato
+    }
+}
+
+static OEGUI_THEME: RwLock<OEguiTheme> = RwLock::new(OEguiTheme::Macchiato);
+
+/// The part of an `OEguiEngine` that is saved across sessions (see `OEguiEngine::state`).  Every
+/// map is keyed by the widget's or container's `id_str`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OEguiEngineState {
+    pub windows: HashMap<String, OEguiSavedWindowState>,
+    pub side_panels_open: HashMap<String, bool>,
+    pub top_bottom_panels_open: HashMap<String, bool>,
+    /// Selected tab of each `OEguiTabContainer`.
+    pub tab_selections: HashMap<String, String>,
+    pub slider_values: HashMap<String, f64>,
+    pub drag_values: HashMap<String, f64>,
+    pub checkbox_values: HashMap<String, bool>,
+    /// Unmultiplied rgba.
+    pub color_values: HashMap<String, [f32; 4]>,
+    /// Encoded with the selector's `OStringEncoding`.
+    pub selector_selections: HashMap<String, Vec<String>>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OEguiSavedWindowState {
+    pub open: bool,
+    pub position: [f32; 2]
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OEguiToastLevel {
+    Info,
+    Warning,
+    Error
+}
+impl OEguiToastLevel {
+    fn color(&self) -> Color32 {
+        match self {
+            OEguiToastLevel::Info => { Color32::from_rgb(150, 205, 255) }
+            OEguiToastLevel::Warning => { Color32::from_rgb(255, 200, 90) }
+            OEguiToastLevel::Error => { Color32::from_rgb(255, 110, 110) }
+        }
+    }
+}
+
+pub struct OEguiToast {
+    text: String,
+    level: OEguiToastLevel,
+    duration_in_secs: f64,
+    /// egui time at which the toast was first drawn, so toasts pushed while nothing is being drawn
+    /// are not missed.
+    shown_at: Option<f64>
+}
+impl OEguiToast {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    #[inline(always)]
+    pub fn level(&self) -> OEguiToastLevel {
+        self.level
+    }
+}
+
+/// Notifications stacked in the bottom right corner of the screen, oldest on top.  Each one fades
+/// out after `duration_in_secs` (twice that for errors), or goes away when clicked.  Only the newest
+/// `max_num_toasts` are kept.
+pub struct OEguiToasts {
+    toasts: VecDeque<OEguiToast>,
+    pub duration_in_secs: f64,
+    pub max_num_toasts: usize
+}
+impl OEguiToasts {
+    pub fn new() -> Self {
+        Self {
+            toasts: VecDeque::new(),
+            duration_in_secs: 4.0,
+            max_num_toasts: 6,
+        }
+    }
+    pub fn push(&mut self, text: &str, level: OEguiToastLevel) {
+        let duration_in_secs = if level == OEguiToastLevel::Error { 2.0 * self.duration_in_secs } else { self.duration_in_secs };
+        self.toasts.push_back(OEguiToast { text: text.to_string(), level, duration_in_secs, shown_at: None });
+        while self.toasts.len() > self.max_num_toasts { self.toasts.pop_front(); }
+    }
+    pub fn clear(&mut self) {
+        self.toasts.clear();
+    }
+    #[inline(always)]
+    pub fn toasts(&self) -> &VecDeque<OEguiToast> {
+        &self.toasts
+    }
+    /// Has to be called every frame (`optima_bevy_egui` does this).
+    pub fn show(&mut self, ctx: &Context) {
+        if self.toasts.is_empty() { return; }
+
+        let now = ctx.input(|i| i.time);
+        self.toasts.iter_mut().for_each(|x| { x.shown_at.get_or_insert(now); });
+        self.toasts.retain(|x| now - x.shown_at.unwrap() < x.duration_in_secs);
+
+        let mut dismissed = None;
+        egui::Area::new("oegui_toasts")
+            .anchor(Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (i, toast) in self.toasts.iter().enumerate() {
+                    // fades out over the last half second.
+                    let remaining = toast.duration_in_secs - (now - toast.shown_at.unwrap());
+                    let alpha = (remaining / 0.5).min(1.0) as f32;
+                    let response = egui::Frame::popup(ui.style())
+                        .show(ui, |ui| { ui.colored_label(toast.level.color().linear_multiply(alpha), toast.text.as_str()); })
+                        .response
+                        .interact(egui::Sense::click());
+                    if response.clicked() { dismissed = Some(i); }
+                }
+            });
+
+        if let Some(i) = dismissed { self.toasts.remove(i); }
+    }
+}
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -261,7 +621,7 @@ impl OEguiWidgetTrait for OEguiSlider {
         let mut mutex_guard = egui_engine.get_mutex_guard();
         let stored_response = mutex_guard.slider_responses.get(id_str);
         let mut slider_value = match stored_response {
-            None => { self.start_value }
+            None => { mutex_guard.restored_state.slider_values.get(id_str).cloned().unwrap_or(self.start_value) }
             Some(stored_response) => { stored_response.slider_value }
         };
         let response = ui.add(egui::widgets::Slider::new(&mut slider_value, self.lower_range..=self.upper_range));
@@ -282,13 +642,76 @@ impl OEguiSliderResponse {
     }
 }
 
-pub struct OEguiCheckbox { pub text: String }
+/// A numeric entry that is dragged or typed into, for values that need more precision than an
+/// `OEguiSlider` gives.  Dragging changes the value by `step_size` per pixel, and the value is
+/// always clamped to `[lower_range, upper_range]` (infinite bounds are fine).
+pub struct OEguiDragValue {
+    lower_range: f64,
+    upper_range: f64,
+    start_value: f64,
+    step_size: f64,
+    suffix: String
+}
+impl OEguiDragValue {
+    /// Returns an error if `lower_range` is greater than `upper_range` or either is nan.
+    pub fn new(lower_range: f64, upper_range: f64, start_value: f64, step_size: f64, suffix: &str) -> Result<Self, OptimaError> {
+        if !(lower_range <= upper_range) {
+            return Err(OptimaError::InvalidInput(format!("lower_range {} is greater than upper_range {}", lower_range, upper_range)));
+        }
+
+        Ok(Self {
+            lower_range,
+            upper_range,
+            start_value: start_value.clamp(lower_range, upper_range),
+            step_size,
+            suffix: suffix.to_string(),
+        })
+    }
+    pub fn new_unchecked(lower_range: f64, upper_range: f64, start_value: f64, step_size: f64, suffix: &str) -> Self {
+        Self::new(lower_range, upper_range, start_value, step_size, suffix).expect("error")
+    }
+}
+impl OEguiWidgetTrait for OEguiDragValue {
+    type Args = ();
+
+    fn show(&self, id_str: &str, ui: &mut Ui, egui_engine: &Res<OEguiEngineWrapper>, _args: &()) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let stored_response = mutex_guard.drag_value_responses.get(id_str);
+        let mut value = match stored_response {
+            None => { mutex_guard.restored_state.drag_values.get(id_str).map(|x| x.clamp(self.lower_range, self.upper_range)).unwrap_or(self.start_value) }
+            Some(stored_response) => { stored_response.value }
+        };
+        let response = ui.add(egui::widgets::DragValue::new(&mut value).speed(self.step_size).clamp_range(self.lower_range..=self.upper_range).suffix(self.suffix.as_str()));
+        mutex_guard.drag_value_responses.insert(id_str.to_string(), OEguiDragValueResponse { widget_response: response, value });
+    }
+}
+
+pub struct OEguiDragValueResponse {
+    widget_response: Response,
+    pub value: f64
+}
+impl OEguiDragValueResponse {
+    pub fn widget_response(&self) -> &Response {
+        &self.widget_response
+    }
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+pub struct OEguiCheckbox { pub text: String, start_value: bool }
 impl OEguiCheckbox {
     pub fn new(text: &str) -> Self {
         Self {
             text: text.to_string(),
+            start_value: false
         }
     }
+    /// Checked or not the first time the checkbox is shown (false by default).
+    pub fn with_start_value(mut self, start_value: bool) -> Self {
+        self.start_value = start_value;
+        self
+    }
 }
 impl OEguiWidgetTrait for OEguiCheckbox {
     type Args = ();
@@ -297,7 +720,7 @@ impl OEguiWidgetTrait for OEguiCheckbox {
         let mut mutex_guard = egui_engine.get_mutex_guard();
         let stored_response = mutex_guard.checkbox_responses.get_mut(id_str);
         let mut currently_selected = match stored_response {
-            None => { false }
+            None => { mutex_guard.restored_state.checkbox_values.get(id_str).cloned().unwrap_or(self.start_value) }
             Some(stored_response) => { stored_response.currently_selected }
         };
         let response = ui.add(egui::widgets::Checkbox::new(&mut currently_selected, self.text.as_str()));
@@ -318,13 +741,68 @@ impl OEguiCheckboxResponse {
     }
 }
 
-pub struct OEguiRadiobutton { text: String }
+/// A color swatch that opens a picker when clicked.  Colors are unmultiplied rgba in [0, 1].
+pub struct OEguiColorPicker {
+    start_rgba: [f32; 4],
+    show_alpha: bool
+}
+impl OEguiColorPicker {
+    /// Without alpha, the picker only edits rgb, and the alpha of `start_rgba` is kept.
+    pub fn new(start_rgba: [f32; 4], show_alpha: bool) -> Self {
+        Self { start_rgba, show_alpha }
+    }
+}
+impl OEguiWidgetTrait for OEguiColorPicker {
+    type Args = ();
+
+    fn show(&self, id_str: &str, ui: &mut Ui, egui_engine: &Res<OEguiEngineWrapper>, _args: &()) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let stored_response = mutex_guard.color_picker_responses.get(id_str);
+        let mut rgba = match stored_response {
+            None => { mutex_guard.restored_state.color_values.get(id_str).cloned().unwrap_or(self.start_rgba) }
+            Some(stored_response) => { stored_response.rgba }
+        };
+        let response = match self.show_alpha {
+            true => { ui.color_edit_button_rgba_unmultiplied(&mut rgba) }
+            false => {
+                let mut rgb = [rgba[0], rgba[1], rgba[2]];
+                let response = ui.color_edit_button_rgb(&mut rgb);
+                rgba = [rgb[0], rgb[1], rgb[2], rgba[3]];
+                response
+            }
+        };
+        mutex_guard.color_picker_responses.insert(id_str.to_string(), OEguiColorPickerResponse { widget_response: response, rgba });
+    }
+}
+
+pub struct OEguiColorPickerResponse {
+    widget_response: Response,
+    pub rgba: [f32; 4]
+}
+impl OEguiColorPickerResponse {
+    pub fn widget_response(&self) -> &Response {
+        &self.widget_response
+    }
+    pub fn rgba(&self) -> [f32; 4] {
+        self.rgba
+    }
+    pub fn bevy_color(&self) -> Color {
+        Color::rgba(self.rgba[0], self.rgba[1], self.rgba[2], self.rgba[3])
+    }
+}
+
+pub struct OEguiRadiobutton { text: String, start_value: bool }
 impl OEguiRadiobutton {
     pub fn new(text: &str) -> Self {
         Self {
-            text: text.to_string()
+            text: text.to_string(),
+            start_value: false
         }
     }
+    pub fn with_start_value(mut self, start_value: bool) -> Self {
+        self.start_value = start_value;
+        self
+    }
 }
 impl OEguiWidgetTrait for OEguiRadiobutton {
     type Args = ();
@@ -333,7 +811,7 @@ impl OEguiWidgetTrait for OEguiRadiobutton {
         let mut mutex_guard = egui_engine.get_mutex_guard();
         let stored_response = mutex_guard.radiobutton_responses.get_mut(id_str);
         let currently_selected = match stored_response {
-            None => { false }
+            None => { self.start_value }
             Some(stored_response) => { stored_response.currently_selected }
         };
         let response = ui.add(egui::widgets::RadioButton::new(currently_selected, self.text.as_str()));
@@ -356,23 +834,67 @@ impl OEguiRadiobuttonResponse {
 
 pub struct OEguiSelector {
     egui_selector_mode: OEguiSelectorMode,
-    selection_choices_as_ron_strings: Vec<String>,
+    selection_choices_as_strings: Vec<String>,
     initial_selections: Vec<String>,
     selection_display_strings: Option<Vec<String>>,
     allow_multiple_selections: bool,
+    value_encoding: OStringEncoding,
+    num_columns: usize,
+    show_search_box: bool,
+    show_select_all_buttons: bool
 }
 impl OEguiSelector {
-    pub fn new<S: ToRonString>(egui_selection_mode: OEguiSelectorMode,
-                               selection_choices: Vec<S>,
-                               initial_selections: Vec<S>,
-                               selection_display_strings: Option<Vec<String>>,
-                               allow_multiple_selections: bool) -> Self {
+    /// Choices are stored as ron strings; use `new_with_encoding` to pick another encoding.
+    pub fn new<S: Serialize>(egui_selection_mode: OEguiSelectorMode,
+                             selection_choices: Vec<S>,
+                             initial_selections: Vec<S>,
+                             selection_display_strings: Option<Vec<String>>,
+                             allow_multiple_selections: bool) -> Self {
+        Self::new_with_encoding(egui_selection_mode, selection_choices, initial_selections, selection_display_strings, allow_multiple_selections, OStringEncoding::Ron)
+    }
+    /// Without display strings, the encoded choices are shown as is, so a readable encoding (e.g.,
+    /// json for plain strings and numbers) doubles as the label.
+    pub fn new_with_encoding<S: Serialize>(egui_selection_mode: OEguiSelectorMode,
+                                           selection_choices: Vec<S>,
+                                           initial_selections: Vec<S>,
+                                           selection_display_strings: Option<Vec<String>>,
+                                           allow_multiple_selections: bool,
+                                           value_encoding: OStringEncoding) -> Self {
         Self {
             egui_selector_mode: egui_selection_mode,
-            selection_choices_as_ron_strings: selection_choices.iter().map(|x| x.to_ron_string()).collect(),
-            initial_selections: initial_selections.iter().map(|x| x.to_ron_string()).collect(),
+            selection_choices_as_strings: selection_choices.iter().map(|x| value_encoding.encode(x).expect("error")).collect(),
+            initial_selections: initial_selections.iter().map(|x| value_encoding.encode(x).expect("error")).collect(),
             selection_display_strings,
             allow_multiple_selections,
+            value_encoding,
+            num_columns: 1,
+            show_search_box: false,
+            show_select_all_buttons: false
+        }
+    }
+    /// Lays radio buttons, checkboxes, and selection texts out in a grid, filled row by row.  Has no
+    /// effect on combo boxes.
+    pub fn with_columns(mut self, num_columns: usize) -> Self {
+        self.num_columns = num_columns.max(1);
+        self
+    }
+    /// Adds a text box above the choices, so that only choices whose display string contains the
+    /// text (ignoring case) are shown.  Meant for long choice lists.
+    pub fn with_search_box(mut self) -> Self {
+        self.show_search_box = true;
+        self
+    }
+    /// Adds "all" and "none" buttons, which select or deselect the choices currently shown (i.e.,
+    /// the ones that match the search box, if there is one).  Has no effect on selectors that only
+    /// allow one selection.
+    pub fn with_select_all_buttons(mut self) -> Self {
+        self.show_select_all_buttons = true;
+        self
+    }
+    fn display_string(&self, choice_idx: usize) -> &str {
+        match &self.selection_display_strings {
+            None => { &self.selection_choices_as_strings[choice_idx] }
+            Some(d) => { &d[choice_idx] }
         }
     }
 }
@@ -381,110 +903,168 @@ impl OEguiWidgetTrait for OEguiSelector {
 
     fn show(&self, id_str: &str, ui: &mut Ui, egui_engine: &Res<OEguiEngineWrapper>, args: &Self::Args) {
         let mut mutex_guard = egui_engine.get_mutex_guard();
-        let stored_response = mutex_guard.selector_responses.get_mut(id_str);
-        match stored_response {
-            None => { mutex_guard.selector_responses.insert(id_str.to_string(), OEguiSelectorResponse { current_selections_as_ron_strings: self.initial_selections.clone() }); }
-            Some(stored_response) => {
-                let current_selections_as_ron_strings = &mut stored_response.current_selections_as_ron_strings;
-
-                match &self.egui_selector_mode {
-                    OEguiSelectorMode::RadioButtons
-                    | OEguiSelectorMode::Checkboxes
-                    | OEguiSelectorMode::SelectionText => {
-                        self.selection_choices_as_ron_strings.iter().enumerate().for_each(|(i, s)| {
-                            let currently_selected = current_selections_as_ron_strings.contains(s);
-                            let mut currently_selected_copy = currently_selected.clone();
-
-                            let display_string = match &self.selection_display_strings {
-                                None => { s.clone() }
-                                Some(d) => { d[i].clone() }
-                            };
-
-                            let selection_code: i8 = match &self.egui_selector_mode {
-                                OEguiSelectorMode::RadioButtons => {
-                                    if ui.radio(currently_selected_copy, display_string.as_str()).clicked() {
-                                        if !currently_selected { 1 } else { -1 }
-                                    } else { 0 }
-                                }
-                                OEguiSelectorMode::Checkboxes => {
-                                    if ui.checkbox(&mut currently_selected_copy, display_string.as_str()).clicked() {
-                                        if !currently_selected { 1 } else { -1 }
-                                    } else { 0 }
-                                }
-                                OEguiSelectorMode::SelectionText => {
-                                    if ui.selectable_label(currently_selected_copy, display_string.as_str()).clicked() {
-                                        if !currently_selected { 1 } else { -1 }
-                                    } else { 0 }
-                                }
-                                _ => { unreachable!(); }
-                            };
-
-                            let keys = args;
-                            let shift_select = self.allow_multiple_selections & &(keys.pressed(KeyCode::ShiftRight) || keys.pressed(KeyCode::ShiftLeft));
-
-                            if selection_code == -1 && shift_select {
-                                current_selections_as_ron_strings.retain(|x| x != s)
-                            } else if selection_code == -1 {
-                                current_selections_as_ron_strings.clear();
-                                current_selections_as_ron_strings.push(s.clone());
-                            } else if selection_code == 1 && current_selections_as_ron_strings.len() == 0 {
-                                current_selections_as_ron_strings.push(s.clone());
-                            } else if selection_code == 1 && current_selections_as_ron_strings.len() >= 1 && shift_select {
-                                current_selections_as_ron_strings.push(s.clone());
-                            } else if selection_code == 1 && current_selections_as_ron_strings.len() >= 1 {
-                                current_selections_as_ron_strings.clear();
-                                current_selections_as_ron_strings.push(s.clone());
-                            }
-                        })
-                    }
-                    OEguiSelectorMode::ComboBox => {
-                        assert!(!self.allow_multiple_selections, "Combobox cannot handle multiple selections.");
-                        // assert!(self.selection_choices_as_ron_strings.len() > 0);
-                        if current_selections_as_ron_strings.len() == 0 { current_selections_as_ron_strings.push(self.selection_choices_as_ron_strings[0].clone()) }
-                        let selected = current_selections_as_ron_strings[0].clone();
-                        let selected_display = if let Some(selection_display_strings) = &self.selection_display_strings {
-                            let selected_idx = self.selection_choices_as_ron_strings.iter().position(|x| x == &selected).unwrap();
-                            selection_display_strings[selected_idx].clone()
-                        } else {
-                            selected.clone()
+        if !mutex_guard.selector_responses.contains_key(id_str) {
+            // restored selections are dropped if the choices have changed since they were saved, or if
+            // there are several for a selector that only allows one.
+            let current_selections_as_strings = match mutex_guard.restored_state.selector_selections.get(id_str) {
+                Some(restored) if restored.iter().all(|x| self.selection_choices_as_strings.contains(x)) && (self.allow_multiple_selections || restored.len() <= 1) => { restored.clone() }
+                _ => { self.initial_selections.clone() }
+            };
+            mutex_guard.selector_responses.insert(id_str.to_string(), OEguiSelectorResponse {
+                current_selections_as_strings,
+                selection_choices_as_strings: vec![],
+                allow_multiple_selections: self.allow_multiple_selections,
+                search_text: "".to_string(),
+                value_encoding: self.value_encoding
+            });
+        }
+        // the selector is drawn on the frame its response is created as well, so selections can be
+        // read right after the first `show`.
+        let stored_response = mutex_guard.selector_responses.get_mut(id_str).expect("error");
+        stored_response.selection_choices_as_strings = self.selection_choices_as_strings.clone();
+        stored_response.allow_multiple_selections = self.allow_multiple_selections;
+
+        if self.show_search_box {
+            ui.horizontal(|ui| {
+                ui.label("🔍");
+                ui.add(egui::TextEdit::singleline(&mut stored_response.search_text).hint_text("search"));
+            });
+        }
+        let search_text = stored_response.search_text.to_lowercase();
+        let shown_idxs: Vec<usize> = (0..self.selection_choices_as_strings.len()).filter(|i| search_text.is_empty() || self.display_string(*i).to_lowercase().contains(&search_text)).collect();
+
+        let current_selections_as_strings = &mut stored_response.current_selections_as_strings;
+        if self.show_select_all_buttons && self.allow_multiple_selections {
+            ui.horizontal(|ui| {
+                if ui.small_button("all").clicked() {
+                    shown_idxs.iter().for_each(|i| {
+                        let s = &self.selection_choices_as_strings[*i];
+                        if !current_selections_as_strings.contains(s) { current_selections_as_strings.push(s.clone()); }
+                    });
+                }
+                if ui.small_button("none").clicked() {
+                    current_selections_as_strings.retain(|x| !shown_idxs.iter().any(|i| &self.selection_choices_as_strings[*i] == x));
+                }
+            });
+        }
+
+        match &self.egui_selector_mode {
+            OEguiSelectorMode::RadioButtons
+            | OEguiSelectorMode::Checkboxes
+            | OEguiSelectorMode::SelectionText => {
+                let keys = args;
+                let shift_select = self.allow_multiple_selections & &(keys.pressed(KeyCode::ShiftRight) || keys.pressed(KeyCode::ShiftLeft));
+
+                let mut show_choices = |ui: &mut Ui| {
+                    shown_idxs.iter().enumerate().for_each(|(j, i)| {
+                        let s = &self.selection_choices_as_strings[*i];
+                        let currently_selected = current_selections_as_strings.contains(s);
+                        let mut currently_selected_copy = currently_selected.clone();
+                        let display_string = self.display_string(*i);
+
+                        let clicked = match &self.egui_selector_mode {
+                            OEguiSelectorMode::RadioButtons => { ui.radio(currently_selected_copy, display_string).clicked() }
+                            OEguiSelectorMode::Checkboxes => { ui.checkbox(&mut currently_selected_copy, display_string).clicked() }
+                            OEguiSelectorMode::SelectionText => { ui.selectable_label(currently_selected_copy, display_string).clicked() }
+                            _ => { unreachable!(); }
+                        };
+                        let selection_code: i8 = match (clicked, currently_selected) {
+                            (false, _) => { 0 }
+                            (true, false) => { 1 }
+                            (true, true) => { -1 }
                         };
 
-                        egui::ComboBox::new(format!("{}_combobox", id_str), "")
-                            .selected_text(format!("{}", selected_display))
-                            .show_ui(ui, |ui| {
-                                self.selection_choices_as_ron_strings.iter().enumerate().for_each(|(i, s)| {
-                                    let display_string = if let Some(selection_display_strings) = &self.selection_display_strings {
-                                        selection_display_strings[i].clone()
-                                    } else {
-                                        s.clone()
-                                    };
-
-                                    let mut ss = display_string.clone();
-                                    if ui.selectable_value(&mut ss, selected_display.clone(), display_string.as_str()).clicked() {
-                                        current_selections_as_ron_strings.clear();
-                                        current_selections_as_ron_strings.push(s.clone());
-                                    }
-                                });
-                            });
-                    }
-                }
+                        if selection_code == -1 && shift_select {
+                            current_selections_as_strings.retain(|x| x != s)
+                        } else if selection_code == -1 {
+                            current_selections_as_strings.clear();
+                            current_selections_as_strings.push(s.clone());
+                        } else if selection_code == 1 && current_selections_as_strings.len() == 0 {
+                            current_selections_as_strings.push(s.clone());
+                        } else if selection_code == 1 && current_selections_as_strings.len() >= 1 && shift_select {
+                            current_selections_as_strings.push(s.clone());
+                        } else if selection_code == 1 && current_selections_as_strings.len() >= 1 {
+                            current_selections_as_strings.clear();
+                            current_selections_as_strings.push(s.clone());
+                        }
+
+                        if self.num_columns > 1 && (j + 1) % self.num_columns == 0 { ui.end_row(); }
+                    })
+                };
 
-                // egui_engine.selector_responses.lock().unwrap().insert(id_str.to_string(), OEguiSelectorResponse { current_selections_as_ron_strings });
+                if self.num_columns > 1 {
+                    egui::Grid::new(format!("{}_grid", id_str)).show(ui, |ui| show_choices(ui));
+                } else {
+                    show_choices(ui);
+                }
+            }
+            OEguiSelectorMode::ComboBox => {
+                assert!(!self.allow_multiple_selections, "Combobox cannot handle multiple selections.");
+                if self.selection_choices_as_strings.is_empty() { return; }
+                if current_selections_as_strings.len() == 0 { current_selections_as_strings.push(self.selection_choices_as_strings[0].clone()) }
+                let selected = current_selections_as_strings[0].clone();
+                let selected_display = match self.selection_choices_as_strings.iter().position(|x| x == &selected) {
+                    Some(selected_idx) => { self.display_string(selected_idx).to_string() }
+                    None => { selected.clone() }
+                };
+
+                egui::ComboBox::new(format!("{}_combobox", id_str), "")
+                    .selected_text(format!("{}", selected_display))
+                    .show_ui(ui, |ui| {
+                        shown_idxs.iter().for_each(|i| {
+                            let s = &self.selection_choices_as_strings[*i];
+                            let display_string = self.display_string(*i).to_string();
+
+                            let mut ss = display_string.clone();
+                            if ui.selectable_value(&mut ss, selected_display.clone(), display_string.as_str()).clicked() {
+                                current_selections_as_strings.clear();
+                                current_selections_as_strings.push(s.clone());
+                            }
+                        });
+                    });
             }
         }
     }
 }
 
 pub struct OEguiSelectorResponse {
-    pub current_selections_as_ron_strings: Vec<String>
+    pub current_selections_as_strings: Vec<String>,
+    selection_choices_as_strings: Vec<String>,
+    allow_multiple_selections: bool,
+    search_text: String,
+    value_encoding: OStringEncoding
 }
 impl OEguiSelectorResponse {
-    pub fn current_selections<S: FromRonString>(&self) -> Vec<S> {
-        self.current_selections_as_ron_strings.iter().map(|x| S::from_ron_string(x) ).collect()
+    /// Errors if a selection cannot be decoded as an `S`, e.g., when `S` is not the type the
+    /// selector was created with.
+    pub fn current_selections<S: DeserializeOwned>(&self) -> Result<Vec<S>, OptimaError> {
+        self.current_selections_as_strings.iter().map(|x| self.value_encoding.decode(x)).collect()
+    }
+    pub fn current_selections_unchecked<S: DeserializeOwned>(&self) -> Vec<S> {
+        self.current_selections().expect("error")
     }
     #[allow(dead_code)]
-    pub (crate) fn current_selections_as_ron_strings(&self) -> &Vec<String> {
-        &self.current_selections_as_ron_strings
+    pub (crate) fn current_selections_as_strings(&self) -> &Vec<String> {
+        &self.current_selections_as_strings
+    }
+    /// Selects every choice, including ones hidden by the search box.  Errors (and changes nothing)
+    /// if the selector only allows one selection and has more than one choice.
+    pub fn select_all(&mut self) -> Result<(), OptimaError> {
+        if !self.allow_multiple_selections && self.selection_choices_as_strings.len() > 1 { return Err(OptimaError::InvalidInput("select_all needs a selector that allows multiple selections.".to_string())); }
+        self.current_selections_as_strings = self.selection_choices_as_strings.clone();
+        Ok(())
+    }
+    /// A combo box falls back to its first choice the next time it is shown.
+    pub fn select_none(&mut self) {
+        self.current_selections_as_strings.clear();
+    }
+    #[inline(always)]
+    pub fn search_text(&self) -> &str {
+        &self.search_text
+    }
+    #[inline(always)]
+    pub fn value_encoding(&self) -> OStringEncoding {
+        self.value_encoding
     }
 }
 
@@ -493,14 +1073,21 @@ pub enum OEguiSelectorMode {
 }
 
 pub struct OEguiTextbox {
-    multiline: bool
+    multiline: bool,
+    start_text: String
 }
 impl OEguiTextbox {
     pub fn new(multiline: bool) -> Self {
         Self {
-            multiline
+            multiline,
+            start_text: "".to_string()
         }
     }
+    /// Text in the box the first time it is shown.
+    pub fn with_start_text(mut self, start_text: &str) -> Self {
+        self.start_text = start_text.to_string();
+        self
+    }
 }
 impl OEguiWidgetTrait for OEguiTextbox {
     type Args = ();
@@ -510,7 +1097,7 @@ impl OEguiWidgetTrait for OEguiTextbox {
         let mut mutex_guard = egui_engine.get_mutex_guard();
         let stored_response = mutex_guard.textbox_responses.get(id_str);
         let mut curr_string = match stored_response {
-            None => { "".to_string() }
+            None => { self.start_text.clone() }
             Some(stored_response) => { stored_response.text.clone() }
         };
 
@@ -540,6 +1127,819 @@ impl OEguiTextboxResponse {
     }
 }
 
+/// Line plot of the series in the engine's plot buffer with the same `id_str` (see
+/// `OEguiEngine::plot_buffer_mut`), e.g., joint values, distances, or objective values over time.
+/// The buffer keeps the last `max_num_samples` samples of each series.
+pub struct OEguiPlot {
+    height: f32,
+    max_num_samples: usize,
+    show_legend: bool
+}
+impl OEguiPlot {
+    pub fn new(height: f32, max_num_samples: usize, show_legend: bool) -> Self {
+        Self {
+            height,
+            max_num_samples,
+            show_legend,
+        }
+    }
+}
+impl OEguiWidgetTrait for OEguiPlot {
+    type Args = ();
+
+    fn show(&self, id_str: &str, ui: &mut Ui, egui_engine: &Res<OEguiEngineWrapper>, _args: &()) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let buffer = mutex_guard.plot_buffer_mut(id_str);
+        buffer.set_max_num_samples(self.max_num_samples);
+
+        let lines: Vec<Line> = buffer.series.iter().map(|(name, samples)| {
+            Line::new(PlotPoints::new(samples.iter().cloned().collect())).name(name)
+        }).collect();
+
+        let mut plot = Plot::new(id_str).height(self.height);
+        if self.show_legend { plot = plot.legend(Legend::default()); }
+        let response = plot.show(ui, |plot_ui| {
+            lines.into_iter().for_each(|line| plot_ui.line(line));
+        }).response;
+
+        mutex_guard.plot_responses.insert(id_str.to_string(), OEguiPlotResponse { widget_response: response });
+    }
+}
+
+pub struct OEguiPlotResponse {
+    widget_response: Response
+}
+impl OEguiPlotResponse {
+    pub fn widget_response(&self) -> &Response {
+        &self.widget_response
+    }
+}
+
+/// Rolling buffer of `[x, y]` samples for each named series of an `OEguiPlot`.  Series are drawn
+/// in the order they were first pushed.
+pub struct OEguiPlotBuffer {
+    max_num_samples: usize,
+    series: Vec<(String, VecDeque<[f64; 2]>)>
+}
+impl OEguiPlotBuffer {
+    pub fn new(max_num_samples: usize) -> Self {
+        Self {
+            max_num_samples,
+            series: vec![],
+        }
+    }
+    pub fn push_sample(&mut self, series_name: &str, x: f64, y: f64) {
+        let idx = match self.series.iter().position(|(name, _)| name == series_name) {
+            None => {
+                self.series.push((series_name.to_string(), VecDeque::new()));
+                self.series.len() - 1
+            }
+            Some(idx) => { idx }
+        };
+
+        let samples = &mut self.series[idx].1;
+        samples.push_back([x, y]);
+        while samples.len() > self.max_num_samples { samples.pop_front(); }
+    }
+    /// Pushes one sample per series at the same `x`, e.g., every joint value at the current time.
+    pub fn push_samples(&mut self, x: f64, samples: &[(&str, f64)]) {
+        samples.iter().for_each(|(series_name, y)| self.push_sample(series_name, x, *y));
+    }
+    pub fn set_max_num_samples(&mut self, max_num_samples: usize) {
+        self.max_num_samples = max_num_samples;
+        self.series.iter_mut().for_each(|(_, samples)| { while samples.len() > max_num_samples { samples.pop_front(); } });
+    }
+    pub fn clear(&mut self) {
+        self.series.clear();
+    }
+    #[inline(always)]
+    pub fn max_num_samples(&self) -> usize {
+        self.max_num_samples
+    }
+    #[inline(always)]
+    pub fn series(&self) -> &Vec<(String, VecDeque<[f64; 2]>)> {
+        &self.series
+    }
+}
+impl Default for OEguiPlotBuffer {
+    fn default() -> Self {
+        Self::new(1000)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OEguiPoseEditorRotationMode {
+    /// Roll, pitch, yaw in radians.
+    EulerAngles,
+    /// w, x, y, z.  The quaternion is renormalized after every edit, and an edit that makes it
+    /// (nearly) zero is rejected.
+    Quaternion
+}
+
+/// Drag values for the translation and rotation of a pose, so goals or objects can be placed
+/// numerically.  The edited pose is read back with `OEguiPoseEditorResponse::pose`.
+pub struct OEguiPoseEditor {
+    rotation_mode: OEguiPoseEditorRotationMode,
+    translation_step_size: f64,
+    rotation_step_size: f64,
+    start_translation: [f64; 3],
+    start_rotation: UnitQuaternion<f64>
+}
+impl OEguiPoseEditor {
+    pub fn new<T: AD, P: O3DPose<T>>(start_pose: &P, rotation_mode: OEguiPoseEditorRotationMode, translation_step_size: f64, rotation_step_size: f64) -> Self {
+        let (start_translation, start_rotation) = pose_to_translation_and_unit_quaternion(start_pose);
+
+        Self {
+            rotation_mode,
+            translation_step_size,
+            rotation_step_size,
+            start_translation,
+            start_rotation,
+        }
+    }
+}
+impl OEguiWidgetTrait for OEguiPoseEditor {
+    type Args = ();
+
+    fn show(&self, id_str: &str, ui: &mut Ui, egui_engine: &Res<OEguiEngineWrapper>, _args: &()) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let (mut translation, rotation, mut euler_angles) = match mutex_guard.pose_editor_responses.get(id_str) {
+            None => {
+                let e = self.start_rotation.euler_angles();
+                (self.start_translation, self.start_rotation, [e.0, e.1, e.2])
+            }
+            Some(stored_response) => { (stored_response.translation, stored_response.rotation, stored_response.euler_angles) }
+        };
+        let mut wxyz = [rotation.w, rotation.i, rotation.j, rotation.k];
+
+        let drag = |ui: &mut Ui, value: &mut f64, step_size: f64, prefix: &str| {
+            ui.add(egui::widgets::DragValue::new(value).speed(step_size).prefix(prefix))
+        };
+
+        let mut response = ui.horizontal(|ui| {
+            let mut r = drag(ui, &mut translation[0], self.translation_step_size, "x: ");
+            r = r.union(drag(ui, &mut translation[1], self.translation_step_size, "y: "));
+            r.union(drag(ui, &mut translation[2], self.translation_step_size, "z: "))
+        }).inner;
+
+        let mut rotation_changed = false;
+        let rotation = match self.rotation_mode {
+            OEguiPoseEditorRotationMode::EulerAngles => {
+                let r = ui.horizontal(|ui| {
+                    let mut r = drag(ui, &mut euler_angles[0], self.rotation_step_size, "r: ");
+                    r = r.union(drag(ui, &mut euler_angles[1], self.rotation_step_size, "p: "));
+                    r.union(drag(ui, &mut euler_angles[2], self.rotation_step_size, "y: "))
+                }).inner;
+                if r.changed() { rotation_changed = true; }
+                response = response.union(r);
+                if rotation_changed { UnitQuaternion::from_euler_angles(euler_angles[0], euler_angles[1], euler_angles[2]) } else { rotation }
+            }
+            OEguiPoseEditorRotationMode::Quaternion => {
+                let r = ui.horizontal(|ui| {
+                    let mut r = drag(ui, &mut wxyz[0], self.rotation_step_size, "w: ");
+                    r = r.union(drag(ui, &mut wxyz[1], self.rotation_step_size, "x: "));
+                    r = r.union(drag(ui, &mut wxyz[2], self.rotation_step_size, "y: "));
+                    r.union(drag(ui, &mut wxyz[3], self.rotation_step_size, "z: "))
+                }).inner;
+                response = response.union(r.clone());
+                match (r.changed(), unit_quaternion_from_wxyz(wxyz)) {
+                    (true, Some(edited)) => {
+                        rotation_changed = true;
+                        edited
+                    }
+                    _ => { rotation }
+                }
+            }
+        };
+
+        // euler angles are only recomputed from the quaternion when they were not what was edited,
+        // so that dragging through a singularity does not make the displayed angles jump.
+        if rotation_changed && self.rotation_mode == OEguiPoseEditorRotationMode::Quaternion {
+            let e = rotation.euler_angles();
+            euler_angles = [e.0, e.1, e.2];
+        }
+
+        mutex_guard.pose_editor_responses.insert(id_str.to_string(), OEguiPoseEditorResponse { widget_response: response, translation, rotation, euler_angles });
+    }
+}
+
+pub struct OEguiPoseEditorResponse {
+    widget_response: Response,
+    translation: [f64; 3],
+    rotation: UnitQuaternion<f64>,
+    euler_angles: [f64; 3]
+}
+impl OEguiPoseEditorResponse {
+    /// Union of the responses of all drag values, so `changed()` is true if any part of the pose
+    /// was edited this frame.
+    pub fn widget_response(&self) -> &Response {
+        &self.widget_response
+    }
+    pub fn pose<T: AD, P: O3DPose<T>>(&self) -> P {
+        let translation = [T::constant(self.translation[0]), T::constant(self.translation[1]), T::constant(self.translation[2])];
+        let r = &self.rotation;
+        P::from_constructors(&translation, &QuatConstructor::new(T::constant(r.w), T::constant(r.i), T::constant(r.j), T::constant(r.k)))
+    }
+    /// Moves the editor to the given pose, e.g., when the goal it edits was changed elsewhere.
+    pub fn set_pose<T: AD, P: O3DPose<T>>(&mut self, pose: &P) {
+        let (translation, rotation) = pose_to_translation_and_unit_quaternion(pose);
+        let e = rotation.euler_angles();
+        self.translation = translation;
+        self.rotation = rotation;
+        self.euler_angles = [e.0, e.1, e.2];
+    }
+    #[inline(always)]
+    pub fn translation(&self) -> [f64; 3] {
+        self.translation
+    }
+    #[inline(always)]
+    pub fn euler_angles(&self) -> [f64; 3] {
+        self.euler_angles
+    }
+    pub fn quaternion_wxyz(&self) -> [f64; 4] {
+        [self.rotation.w, self.rotation.i, self.rotation.j, self.rotation.k]
+    }
+}
+
+fn pose_to_translation_and_unit_quaternion<T: AD, P: O3DPose<T>>(pose: &P) -> ([f64; 3], UnitQuaternion<f64>) {
+    let t = pose.translation().o3dvec_as_slice();
+    let q = pose.rotation().unit_quaternion_as_wxyz_slice();
+    let translation = [t[0].to_constant(), t[1].to_constant(), t[2].to_constant()];
+    let rotation = unit_quaternion_from_wxyz([q[0].to_constant(), q[1].to_constant(), q[2].to_constant(), q[3].to_constant()]).unwrap_or(UnitQuaternion::identity());
+
+    (translation, rotation)
+}
+
+/// None if the quaternion is (nearly) zero or not finite, since it then has no meaningful
+/// direction to normalize to.
+fn unit_quaternion_from_wxyz(wxyz: [f64; 4]) -> Option<UnitQuaternion<f64>> {
+    if wxyz.iter().any(|x| !x.is_finite()) { return None; }
+    UnitQuaternion::try_new(Quaternion::new(wxyz[0], wxyz[1], wxyz[2], wxyz[3]), 1e-9)
+}
+
+/// Progress of a long running operation (e.g., a background task), given as a fraction in [0, 1].
+/// Clicking the bar is reported through the response, so it can double as a "show details" button.
+pub struct OEguiProgressBar {
+    fraction: f64,
+    text: Option<String>,
+    show_percentage: bool
+}
+impl OEguiProgressBar {
+    pub fn new(fraction: f64, show_percentage: bool) -> Self {
+        Self {
+            fraction: fraction.clamp(0.0, 1.0),
+            text: None,
+            show_percentage,
+        }
+    }
+    /// Shown on the bar, e.g., the name of the current stage.  Goes before the percentage, if that is
+    /// shown too.
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+}
+impl OEguiWidgetTrait for OEguiProgressBar {
+    type Args = ();
+
+    fn show(&self, id_str: &str, ui: &mut Ui, egui_engine: &Res<OEguiEngineWrapper>, _args: &()) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let text = match (&self.text, self.show_percentage) {
+            (None, false) => { None }
+            (None, true) => { Some(format!("{:.1}%", 100.0 * self.fraction)) }
+            (Some(text), false) => { Some(text.clone()) }
+            (Some(text), true) => { Some(format!("{} ({:.1}%)", text, 100.0 * self.fraction)) }
+        };
+
+        let mut progress_bar = egui::widgets::ProgressBar::new(self.fraction as f32);
+        if let Some(text) = text { progress_bar = progress_bar.text(text); }
+        let response = ui.add(progress_bar).interact(egui::Sense::click());
+        mutex_guard.progress_bar_responses.insert(id_str.to_string(), OEguiProgressBarResponse { widget_response: response, fraction: self.fraction });
+    }
+}
+
+pub struct OEguiProgressBarResponse {
+    widget_response: Response,
+    fraction: f64
+}
+impl OEguiProgressBarResponse {
+    pub fn widget_response(&self) -> &Response {
+        &self.widget_response
+    }
+    #[inline(always)]
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OEguiTreeNode {
+    /// Unique within the tree.
+    pub id: String,
+    pub label: String,
+    pub children: Vec<OEguiTreeNode>
+}
+impl OEguiTreeNode {
+    pub fn new(id: &str, label: &str) -> Self {
+        Self { id: id.to_string(), label: label.to_string(), children: vec![] }
+    }
+    pub fn with_children(mut self, children: Vec<OEguiTreeNode>) -> Self {
+        self.children = children;
+        self
+    }
+}
+
+/// Nodes that can be expanded and collapsed, with at most one of them selected (clicking the
+/// selected node again deselects it).
+pub struct OEguiTree {
+    roots: Vec<OEguiTreeNode>,
+    default_open: bool,
+    context_actions: Vec<String>
+}
+impl OEguiTree {
+    pub fn new(roots: Vec<OEguiTreeNode>, default_open: bool) -> Self {
+        Self { roots, default_open, context_actions: vec![] }
+    }
+    /// Right clicking a node opens a menu with these actions.  The one picked is reported through
+    /// `OEguiTreeResponse::context_action` for one frame.
+    pub fn with_context_actions(mut self, context_actions: &[&str]) -> Self {
+        self.context_actions = context_actions.iter().map(|x| x.to_string()).collect();
+        self
+    }
+    fn show_node(&self, node: &OEguiTreeNode, ui: &mut Ui, response: &mut OEguiTreeResponse) {
+        let open = response.is_expanded(&node.id);
+        let mut label_response = None;
+        ui.horizontal(|ui| {
+            if node.children.is_empty() {
+                ui.add_space(ui.spacing().interact_size.y);
+            } else if ui.small_button(if open { "⏷" } else { "⏵" }).clicked() {
+                response.set_expanded(&node.id, !open);
+            }
+            label_response = Some(ui.selectable_label(response.selected.as_ref() == Some(&node.id), node.label.as_str()));
+        });
+
+        let label_response = label_response.expect("error");
+        if label_response.clicked() {
+            response.selected = if response.selected.as_ref() == Some(&node.id) { None } else { Some(node.id.clone()) };
+            response.selection_changed = true;
+        }
+        if !self.context_actions.is_empty() {
+            label_response.context_menu(|ui| {
+                for action in &self.context_actions {
+                    if ui.button(action.as_str()).clicked() {
+                        response.context_action = Some((node.id.clone(), action.clone()));
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
+
+        if open && !node.children.is_empty() {
+            ui.indent(&node.id, |ui| {
+                node.children.iter().for_each(|child| self.show_node(child, ui, response));
+            });
+        }
+    }
+}
+impl OEguiWidgetTrait for OEguiTree {
+    type Args = ();
+
+    fn show(&self, id_str: &str, ui: &mut Ui, egui_engine: &Res<OEguiEngineWrapper>, _args: &()) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let response = mutex_guard.tree_responses.entry(id_str.to_string()).or_insert_with(|| OEguiTreeResponse::new(self.default_open));
+        response.default_open = self.default_open;
+        response.selection_changed = false;
+        response.context_action = None;
+
+        response.parents.clear();
+        let mut stack: Vec<&OEguiTreeNode> = self.roots.iter().collect();
+        while let Some(node) = stack.pop() {
+            node.children.iter().for_each(|child| {
+                response.parents.insert(child.id.clone(), node.id.clone());
+                stack.push(child);
+            });
+        }
+
+        self.roots.iter().for_each(|root| self.show_node(root, ui, response));
+    }
+}
+
+pub struct OEguiTreeResponse {
+    /// Nodes that were expanded or collapsed; all others use `default_open`.
+    expanded: HashMap<String, bool>,
+    default_open: bool,
+    parents: HashMap<String, String>,
+    selected: Option<String>,
+    selection_changed: bool,
+    context_action: Option<(String, String)>
+}
+impl OEguiTreeResponse {
+    fn new(default_open: bool) -> Self {
+        Self { expanded: Default::default(), default_open, parents: Default::default(), selected: None, selection_changed: false, context_action: None }
+    }
+    #[inline(always)]
+    pub fn selected(&self) -> Option<&String> {
+        self.selected.as_ref()
+    }
+    /// True on the frame the user clicked a node.  Selections made through `select` do not count.
+    #[inline(always)]
+    pub fn selection_changed(&self) -> bool {
+        self.selection_changed
+    }
+    /// The node right clicked and the action picked for it, if that happened this frame.
+    #[inline(always)]
+    pub fn context_action(&self) -> Option<&(String, String)> {
+        self.context_action.as_ref()
+    }
+    pub fn select(&mut self, id: Option<&str>) {
+        self.selected = id.map(|x| x.to_string());
+    }
+    pub fn is_expanded(&self, id: &str) -> bool {
+        self.expanded.get(id).cloned().unwrap_or(self.default_open)
+    }
+    pub fn set_expanded(&mut self, id: &str, expanded: bool) {
+        self.expanded.insert(id.to_string(), expanded);
+    }
+    /// Expands every ancestor of the node, so it is visible the next time the tree is shown.
+    pub fn reveal(&mut self, id: &str) {
+        let mut curr = id.to_string();
+        while let Some(parent) = self.parents.get(&curr).cloned() {
+            self.expanded.insert(parent.clone(), true);
+            curr = parent;
+        }
+    }
+    /// Ids of the node's ancestors, from its parent up to its root.
+    pub fn ancestors(&self, id: &str) -> Vec<String> {
+        let mut out = vec![];
+        let mut curr = id.to_string();
+        while let Some(parent) = self.parents.get(&curr) {
+            out.push(parent.clone());
+            curr = parent.clone();
+        }
+        out
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum OEguiTableCell {
+    Text(String),
+    Number(f64)
+}
+impl OEguiTableCell {
+    fn to_display_string(&self, decimals: usize) -> String {
+        match self {
+            OEguiTableCell::Text(x) => { x.clone() }
+            OEguiTableCell::Number(x) => { format!("{:.*}", decimals, x) }
+        }
+    }
+    /// Numbers come before text.
+    fn compare(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (OEguiTableCell::Number(x), OEguiTableCell::Number(y)) => { x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal) }
+            (OEguiTableCell::Text(x), OEguiTableCell::Text(y)) => { x.cmp(y) }
+            (OEguiTableCell::Number(_), OEguiTableCell::Text(_)) => { std::cmp::Ordering::Less }
+            (OEguiTableCell::Text(_), OEguiTableCell::Number(_)) => { std::cmp::Ordering::Greater }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OEguiTableRow {
+    /// Unique within the table, and the same from frame to frame so the selection sticks.
+    pub id: String,
+    pub cells: Vec<OEguiTableCell>
+}
+impl OEguiTableRow {
+    pub fn new(id: &str, cells: Vec<OEguiTableCell>) -> Self {
+        Self { id: id.to_string(), cells }
+    }
+}
+
+/// Rows of cells under clickable column headers.  Clicking a header sorts by that column (clicking
+/// it again flips the order), and clicking a row selects it (clicking the selected row again
+/// deselects it).
+pub struct OEguiTable {
+    headers: Vec<String>,
+    rows: Vec<OEguiTableRow>,
+    default_sort: Option<(usize, bool)>,
+    filter_box: bool,
+    decimals: usize
+}
+impl OEguiTable {
+    pub fn new(headers: &[&str], rows: Vec<OEguiTableRow>) -> Self {
+        assert!(rows.iter().all(|x| x.cells.len() == headers.len()), "every row needs one cell per header.");
+        Self { headers: headers.iter().map(|x| x.to_string()).collect(), rows, default_sort: None, filter_box: false, decimals: 3 }
+    }
+    /// Sort order used until a header is clicked.
+    pub fn with_default_sort(mut self, column: usize, ascending: bool) -> Self {
+        self.default_sort = Some((column, ascending));
+        self
+    }
+    /// Adds a text box above the table.  Only rows with a text cell containing the filter (ignoring
+    /// case) are shown.
+    pub fn with_filter_box(mut self) -> Self {
+        self.filter_box = true;
+        self
+    }
+    /// Decimal places shown for number cells.
+    pub fn with_decimals(mut self, decimals: usize) -> Self {
+        self.decimals = decimals;
+        self
+    }
+}
+impl OEguiWidgetTrait for OEguiTable {
+    type Args = ();
+
+    fn show(&self, id_str: &str, ui: &mut Ui, egui_engine: &Res<OEguiEngineWrapper>, _args: &()) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let response = mutex_guard.table_responses.entry(id_str.to_string()).or_insert_with(|| OEguiTableResponse::new(self.default_sort));
+        response.selection_changed = false;
+
+        if self.filter_box {
+            ui.horizontal(|ui| {
+                ui.label("filter");
+                ui.text_edit_singleline(&mut response.filter_text);
+                if ui.small_button("x").clicked() { response.filter_text.clear(); }
+            });
+        }
+
+        let filter = response.filter_text.to_lowercase();
+        let mut rows: Vec<&OEguiTableRow> = self.rows.iter().filter(|row| {
+            filter.is_empty() || row.cells.iter().any(|cell| match cell {
+                OEguiTableCell::Text(x) => { x.to_lowercase().contains(&filter) }
+                OEguiTableCell::Number(_) => { false }
+            })
+        }).collect();
+        if let Some((column, ascending)) = response.sort {
+            if column < self.headers.len() {
+                rows.sort_by(|a, b| {
+                    let ordering = a.cells[column].compare(&b.cells[column]);
+                    if ascending { ordering } else { ordering.reverse() }
+                });
+            }
+        }
+        response.displayed_row_ids = rows.iter().map(|x| x.id.clone()).collect();
+
+        egui::Grid::new(format!("{}_grid", id_str)).striped(true).show(ui, |ui| {
+            for (column, header) in self.headers.iter().enumerate() {
+                let text = match response.sort {
+                    Some((x, true)) if x == column => { format!("{} ⏶", header) }
+                    Some((x, false)) if x == column => { format!("{} ⏷", header) }
+                    _ => { header.clone() }
+                };
+                if ui.button(text).clicked() {
+                    response.sort = match response.sort {
+                        Some((x, ascending)) if x == column => { Some((column, !ascending)) }
+                        _ => { Some((column, true)) }
+                    };
+                }
+            }
+            ui.end_row();
+
+            for row in &rows {
+                let selected = response.selected.as_ref() == Some(&row.id);
+                let mut clicked = false;
+                for cell in &row.cells {
+                    if ui.selectable_label(selected, cell.to_display_string(self.decimals)).clicked() { clicked = true; }
+                }
+                if clicked {
+                    response.selected = if selected { None } else { Some(row.id.clone()) };
+                    response.selection_changed = true;
+                }
+                ui.end_row();
+            }
+        });
+        if rows.is_empty() { ui.label("no rows."); }
+    }
+}
+
+pub struct OEguiTableResponse {
+    /// column and whether it is sorted in ascending order.
+    sort: Option<(usize, bool)>,
+    filter_text: String,
+    displayed_row_ids: Vec<String>,
+    selected: Option<String>,
+    selection_changed: bool
+}
+impl OEguiTableResponse {
+    fn new(sort: Option<(usize, bool)>) -> Self {
+        Self { sort, filter_text: "".to_string(), displayed_row_ids: vec![], selected: None, selection_changed: false }
+    }
+    #[inline(always)]
+    pub fn selected(&self) -> Option<&String> {
+        self.selected.as_ref()
+    }
+    /// True on the frame the user clicked a row.  Selections made through `select` do not count.
+    #[inline(always)]
+    pub fn selection_changed(&self) -> bool {
+        self.selection_changed
+    }
+    pub fn select(&mut self, id: Option<&str>) {
+        self.selected = id.map(|x| x.to_string());
+    }
+    #[inline(always)]
+    pub fn sort(&self) -> Option<(usize, bool)> {
+        self.sort
+    }
+    #[inline(always)]
+    pub fn filter_text(&self) -> &str {
+        &self.filter_text
+    }
+    /// Ids of the rows that passed the filter, in the order they were shown.
+    #[inline(always)]
+    pub fn displayed_row_ids(&self) -> &Vec<String> {
+        &self.displayed_row_ids
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OEguiFileDialogMode {
+    Open,
+    Save
+}
+
+/// A file browser window, starting in the optima assets folder (or the working directory if that
+/// cannot be found).  Open it with `OEguiFileDialog::open`, call `show` every frame, and read the
+/// path that was picked through `OEguiFileDialogResponse::picked`.
+pub struct OEguiFileDialog {
+    title: String,
+    mode: OEguiFileDialogMode,
+    extensions: Vec<String>,
+    default_filename: String
+}
+impl OEguiFileDialog {
+    pub fn new(title: &str, mode: OEguiFileDialogMode) -> Self {
+        Self { title: title.to_string(), mode, extensions: vec![], default_filename: "".to_string() }
+    }
+    /// Only files with these extensions (without the dot, ignoring case) are listed.  When saving, the
+    /// first one is added to file names that do not have one.
+    pub fn with_extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = extensions.iter().map(|x| x.to_lowercase()).collect();
+        self
+    }
+    pub fn with_default_filename(mut self, default_filename: &str) -> Self {
+        self.default_filename = default_filename.to_string();
+        self
+    }
+    /// The dialog is shown (by `show`) from the next frame on, until a file is picked or it is
+    /// cancelled.  It stays in the folder it was last in.
+    pub fn open(id_str: &str, egui_engine: &Res<OEguiEngineWrapper>) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let response = mutex_guard.file_dialog_responses.entry(id_str.to_string()).or_insert_with(|| OEguiFileDialogResponse::new());
+        response.is_open = true;
+        response.error = None;
+        response.confirm_overwrite = None;
+    }
+    fn has_listed_extension(&self, path: &PathBuf) -> bool {
+        if self.extensions.is_empty() { return true; }
+        path.extension().and_then(|x| x.to_str()).map(|x| self.extensions.contains(&x.to_lowercase())).unwrap_or(false)
+    }
+    /// The file a click on "Open" or "Save" would pick, i.e., the typed file name in the current
+    /// folder, with the first extension added when saving a file name without one.
+    fn target_path(&self, response: &OEguiFileDialogResponse) -> PathBuf {
+        let mut path = response.current_dir.join(response.filename.clone().unwrap_or_default());
+        if self.mode == OEguiFileDialogMode::Save && path.extension().is_none() {
+            if let Some(extension) = self.extensions.first() { path.set_extension(extension); }
+        }
+        path
+    }
+    /// When saving over an existing file, the dialog asks for confirmation first.
+    pub fn show(&self, id_str: &str, ctx: &Context, egui_engine: &Res<OEguiEngineWrapper>) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let response = mutex_guard.file_dialog_responses.entry(id_str.to_string()).or_insert_with(|| OEguiFileDialogResponse::new());
+        response.picked = None;
+        if !response.is_open { return; }
+        if response.filename.is_none() { response.filename = Some(self.default_filename.clone()); }
+
+        let mut directories = vec![];
+        let mut files = vec![];
+        if let Ok(read_dir) = response.current_dir.read_dir() {
+            for entry in read_dir.filter_map(|x| x.ok()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with('.') { continue; }
+                let path = entry.path();
+                if path.is_dir() { directories.push(name); } else if self.has_listed_extension(&path) { files.push(name); }
+            }
+        }
+        directories.sort();
+        files.sort();
+
+        // a pending overwrite confirmation is dropped once a different file name is typed.
+        let target_path = self.target_path(response);
+        if response.confirm_overwrite.as_ref().map(|x| x != &target_path).unwrap_or(false) { response.confirm_overwrite = None; }
+
+        let mut is_open = true;
+        let mut pick = false;
+        let mut overwrite = false;
+        let mut cancel = false;
+        egui::Window::new(&self.title)
+            .id(Id::new(format!("{}_file_dialog", id_str)))
+            .open(&mut is_open)
+            .collapsible(false)
+            .default_size([400.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.small_button("⬆").on_hover_text("parent folder").clicked() {
+                        if let Some(parent) = response.current_dir.parent() { response.current_dir = parent.to_path_buf(); }
+                    }
+                    ui.label(response.current_dir.to_string_lossy().to_string());
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for directory in &directories {
+                        if ui.selectable_label(false, format!("📁 {}", directory)).clicked() { response.current_dir.push(directory); }
+                    }
+                    let filename = response.filename.get_or_insert_with(|| "".to_string());
+                    for file in &files {
+                        let label = ui.selectable_label(filename == file, format!("🗋 {}", file));
+                        if label.clicked() { *filename = file.clone(); }
+                        if label.double_clicked() { pick = true; }
+                    }
+                    if directories.is_empty() && files.is_empty() { ui.label("empty folder."); }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("file");
+                    ui.text_edit_singleline(response.filename.get_or_insert_with(|| "".to_string()));
+                });
+                ui.horizontal(|ui| {
+                    let text = match self.mode {
+                        OEguiFileDialogMode::Open => { "Open" }
+                        OEguiFileDialogMode::Save => { "Save" }
+                    };
+                    if ui.button(text).clicked() { pick = true; }
+                    if ui.button("Cancel").clicked() { cancel = true; }
+                });
+                if let Some(existing) = &response.confirm_overwrite {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(Color32::YELLOW, format!("{} already exists.", existing.file_name().map(|x| x.to_string_lossy().to_string()).unwrap_or_default()));
+                        if ui.button("Overwrite").clicked() { overwrite = true; }
+                    });
+                }
+                if let Some(error) = &response.error { ui.colored_label(Color32::RED, error.as_str()); }
+            });
+
+        if pick || overwrite {
+            let filename = response.filename.clone().unwrap_or_default();
+            let path = self.target_path(response);
+            let confirmed = overwrite && response.confirm_overwrite.as_ref() == Some(&path);
+            if filename.trim().is_empty() {
+                response.error = Some("no file name given.".to_string());
+            } else if self.mode == OEguiFileDialogMode::Open && !path.is_file() {
+                response.error = Some(format!("{} does not exist.", filename));
+            } else if self.mode == OEguiFileDialogMode::Save && path.is_dir() {
+                response.error = Some(format!("{} is a folder.", filename));
+            } else if self.mode == OEguiFileDialogMode::Save && path.exists() && !confirmed {
+                response.error = None;
+                response.confirm_overwrite = Some(path);
+            } else {
+                response.picked = Some(OPath::Path(path));
+                response.is_open = false;
+                response.confirm_overwrite = None;
+            }
+        }
+        if cancel || !is_open {
+            response.is_open = false;
+            response.confirm_overwrite = None;
+        }
+    }
+}
+
+pub struct OEguiFileDialogResponse {
+    is_open: bool,
+    current_dir: PathBuf,
+    /// None until the dialog is first shown, so the dialog's default file name can be filled in.
+    filename: Option<String>,
+    picked: Option<OPath>,
+    error: Option<String>,
+    /// an existing file that "Save" was clicked on, waiting for "Overwrite".
+    confirm_overwrite: Option<PathBuf>
+}
+impl OEguiFileDialogResponse {
+    fn new() -> Self {
+        let current_dir = OPath::asset_root().or_else(|_| std::env::current_dir().map_err(|e| e.to_string())).unwrap_or_default();
+        Self { is_open: false, current_dir, filename: None, picked: None, error: None, confirm_overwrite: None }
+    }
+    #[inline(always)]
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+    /// The path picked this frame, if any.
+    #[inline(always)]
+    pub fn picked(&self) -> Option<&OPath> {
+        self.picked.as_ref()
+    }
+    #[inline(always)]
+    pub fn current_dir(&self) -> &PathBuf {
+        &self.current_dir
+    }
+    pub fn set_current_dir(&mut self, current_dir: PathBuf) {
+        self.current_dir = current_dir;
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 pub trait OEguiContainerTrait {
@@ -629,7 +2029,8 @@ impl OEguiContainerTrait for OEguiWindow {
                     window = window.current_pos(position);
                 }
 
-                window.show(ctx, |ui| {
+                let timer = egui_engine.get_mutex_guard().start_container_timer();
+                let inner_response = window.show(ctx, |ui| {
                         add_contents(ui);
                         let ui_contains_pointer = self.does_ui_contain_cursor(ui, 3.0, 3.0, 32.0, 10.0, window_query);
                         if ui_contains_pointer {
@@ -639,8 +2040,10 @@ impl OEguiContainerTrait for OEguiWindow {
                     });
 
                 let mut egui_engine_mutex = egui_engine.0.lock().unwrap();
+                egui_engine_mutex.stop_container_timer(timer);
                 let state = egui_engine_mutex.window_states.get_mut(id_str).expect("error");
                 state.open = open;
+                if let Some(inner_response) = inner_response { state.position = inner_response.response.rect.min; }
             }
         }
     }
@@ -700,6 +2103,7 @@ impl OEguiContainerTrait for OEguiSidePanel {
             Some(saved_state) => {
                 let open = saved_state.open;
                 drop(mutex_guard);
+                let timer = egui_engine.get_mutex_guard().start_container_timer();
                 egui::SidePanel::new(self.side, id_str.to_string())
                     .default_width(self.default_width)
                     .show_animated(ctx, open, |ui| {
@@ -710,6 +2114,7 @@ impl OEguiContainerTrait for OEguiSidePanel {
                             egui_engine_mutex.ui_contains_pointer = true;
                         }
                     });
+                egui_engine.get_mutex_guard().stop_container_timer(timer);
             }
         }
     }
@@ -754,6 +2159,7 @@ impl OEguiContainerTrait for OEguiTopBottomPanel {
             Some(saved_state) => {
                 let open = saved_state.open;
                 drop(mutex_guard);
+                let timer = egui_engine.get_mutex_guard().start_container_timer();
                 egui::TopBottomPanel::new(self.side, id_str.to_string())
                     .default_height(self.default_height)
                     .show_animated(ctx, open, |ui| {
@@ -764,6 +2170,7 @@ impl OEguiContainerTrait for OEguiTopBottomPanel {
                             egui_engine_mutex.ui_contains_pointer = true;
                         }
                     });
+                egui_engine.get_mutex_guard().stop_container_timer(timer);
             }
         }
     }
@@ -778,4 +2185,98 @@ impl OEguiTopBottomPanelState {
     }
 }
 
+/// Where an `OEguiTabContainer` is shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OEguiDock {
+    Left { default_width: f32 },
+    Right { default_width: f32 },
+    Bottom { default_height: f32 },
+    /// In its own movable, resizable window.
+    Floating
+}
+
+/// A row of tabs over a scrollable area, docked to a side of the screen or floating in a window, so
+/// several panels (e.g., joint sliders, links, collisions, plots) can share one region instead of
+/// being stacked.  The contents closure draws whichever tab is selected; `show_tabs` hands it the
+/// selected tab directly, and with `show` it is read with `OEguiEngine::get_tab_container_state`.
+pub struct OEguiTabContainer {
+    title: String,
+    tabs: Vec<String>,
+    dock: OEguiDock
+}
+impl OEguiTabContainer {
+    /// The first tab is selected until another one is picked.  The title is only shown when the
+    /// container is floating.
+    pub fn new(title: &str, tabs: &[&str], dock: OEguiDock) -> Self {
+        assert!(!tabs.is_empty(), "a tab container needs at least one tab.");
+
+        Self {
+            title: title.to_string(),
+            tabs: tabs.iter().map(|x| x.to_string()).collect(),
+            dock,
+        }
+    }
+    pub fn show_tabs<R, F: FnOnce(&mut Ui, &str) -> R>(&self, id_str: &str, ctx: &Context, egui_engine: &Res<OEguiEngineWrapper>, window_query: &Query<&Window, With<PrimaryWindow>>, add_contents: F) {
+        self.show(id_str, ctx, egui_engine, window_query, &(), |ui| {
+            let selected_tab = egui_engine.get_mutex_guard().get_tab_container_state(id_str).expect("error").selected_tab.clone();
+            add_contents(ui, selected_tab.as_str())
+        });
+    }
+}
+impl OEguiContainerTrait for OEguiTabContainer {
+    type Args = ();
+
+    fn show<R, F: FnOnce(&mut Ui) -> R>(&self, id_str: &str, ctx: &Context, egui_engine: &Res<OEguiEngineWrapper>, window_query: &Query<&Window, With<PrimaryWindow>>, _args: &Self::Args, add_contents: F) {
+        OEguiEngine::set_style(ctx);
+
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        let restored_tab = mutex_guard.restored_state.tab_selections.get(id_str).cloned();
+        let state = mutex_guard.tab_container_states.entry(id_str.to_string()).or_insert_with(|| OEguiTabContainerState { open: true, selected_tab: restored_tab.unwrap_or_default() });
+        if !self.tabs.contains(&state.selected_tab) { state.selected_tab = self.tabs[0].clone(); }
+        let mut open = state.open;
+        let mut selected_tab = state.selected_tab.clone();
+        drop(mutex_guard);
+
+        let contents = |ui: &mut Ui| {
+            ui.horizontal_wrapped(|ui| {
+                for tab in &self.tabs { ui.selectable_value(&mut selected_tab, tab.clone(), tab.as_str()); }
+            });
+            ui.separator();
+            // the contents closure may read the selected tab, so it is stored before it runs.
+            egui_engine.get_mutex_guard().tab_container_states.get_mut(id_str).expect("error").selected_tab = selected_tab.clone();
+            egui::ScrollArea::new([true, true])
+                .id_source(format!("{}_{}", id_str, selected_tab))
+                .show(ui, |ui| { add_contents(ui); });
+            let ui_contains_pointer = self.does_ui_contain_cursor(ui, 3.0, 3.0, 32.0, 10.0, window_query);
+            if ui_contains_pointer {
+                let mut egui_engine_mutex = egui_engine.get_mutex_guard();
+                egui_engine_mutex.ui_contains_pointer = true;
+            }
+        };
+
+        let timer = egui_engine.get_mutex_guard().start_container_timer();
+        match self.dock {
+            OEguiDock::Left { default_width } => { egui::SidePanel::new(Side::Left, id_str.to_string()).default_width(default_width).show_animated(ctx, open, contents); }
+            OEguiDock::Right { default_width } => { egui::SidePanel::new(Side::Right, id_str.to_string()).default_width(default_width).show_animated(ctx, open, contents); }
+            OEguiDock::Bottom { default_height } => { egui::TopBottomPanel::new(TopBottomSide::Bottom, id_str.to_string()).default_height(default_height).show_animated(ctx, open, contents); }
+            OEguiDock::Floating => { egui::Window::new(self.title.as_str()).id(Id::new(id_str)).open(&mut open).resizable(true).show(ctx, contents); }
+        }
+
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        mutex_guard.stop_container_timer(timer);
+        mutex_guard.tab_container_states.get_mut(id_str).expect("error").open = open;
+    }
+}
 
+pub struct OEguiTabContainerState {
+    pub open: bool,
+    pub selected_tab: String
+}
+impl OEguiTabContainerState {
+    pub fn open(&self) -> bool {
+        self.open
+    }
+    pub fn selected_tab(&self) -> &str {
+        &self.selected_tab
+    }
+}
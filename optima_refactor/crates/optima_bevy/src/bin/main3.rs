@@ -2,6 +2,6 @@ use optima_bevy::optima_bevy_utils::robotics::BevyRoboticsTrait;
 use optima_robotics::robot::ORobotDefault;
 
 fn main() {
-    let chain = ORobotDefault::from_urdf("b1");
+    let chain = ORobotDefault::from_urdf_unchecked("b1");
     chain.bevy_display();
 }
\ No newline at end of file
@@ -15,7 +15,7 @@ fn test2(robot: &Arc<ORobotDefault>, a: &Arc<Mutex<i32>>) {
 }
 
 fn main() {
-    let r = ORobotDefault::load_from_saved_robot("ur5");
+    let r = ORobotDefault::load_from_saved_robot_unchecked("ur5");
     let r1 = Arc::new(r);
     let r2 = r1.clone();
     let a = Arc::new(Mutex::new(1));
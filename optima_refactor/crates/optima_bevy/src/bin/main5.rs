@@ -73,7 +73,7 @@ fn main() {
     ];
 
     let scene = OParryGenericShapeScene::new(shapes, poses);
-    let robot = ORobotDefault::from_urdf("panda");
+    let robot = ORobotDefault::from_urdf_unchecked("panda");
 
     let mut app = App::new();
     app.optima_bevy_starter_scene();
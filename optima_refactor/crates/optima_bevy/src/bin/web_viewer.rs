@@ -0,0 +1,21 @@
+use optima_bevy::optima_bevy_utils::robotics::BevyRoboticsTrait;
+use optima_bevy::optima_bevy_utils::viewer_config::OptimaViewerConfig;
+use optima_bevy::optima_bevy_utils::web::{fetch_saved_robot_then, get_url_query_parameter, OptimaBevyWebConfig};
+use optima_robotics::robot::ORobotDefault;
+
+/// Build with `cargo build --bin web_viewer --target wasm32-unknown-unknown` and serve alongside
+/// `web/index.html`.  The robot to display is selected through the url, e.g., `index.html?robot=ur5`,
+/// falling back on the viewer config's `default_robot`.  The saved robot is fetched from the served
+/// asset folder, so it must have been saved as json (the default).
+fn main() {
+    let robot_name = get_url_query_parameter("robot").or(OptimaViewerConfig::load_or_default().default_robot).unwrap_or("ur5".to_string());
+    fetch_saved_robot_then(&robot_name.clone(), &OptimaBevyWebConfig::default(), move |res| {
+        match res {
+            Ok(()) => {
+                let robot = ORobotDefault::load_from_saved_robot_unchecked(&robot_name);
+                robot.bevy_display();
+            }
+            Err(e) => { panic!("could not fetch saved robot {}: {}", robot_name, e); }
+        }
+    });
+}
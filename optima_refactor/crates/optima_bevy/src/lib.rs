@@ -1,28 +1,75 @@
 use std::sync::Arc;
 use ad_trait::AD;
-use bevy::input::common_conditions::input_just_pressed;
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
+use bevy::input::InputSystem;
+use bevy::log::LogPlugin;
 pub use bevy::prelude::*;
-use bevy_egui::EguiPlugin;
+use bevy_egui::{EguiContexts, EguiPlugin};
 use bevy_mod_picking::debug::{DebugPickingMode};
 use bevy_mod_picking::DefaultPickingPlugins;
 use bevy_prototype_debug_lines::{DebugLinesPlugin};
 use bevy_stl::StlPlugin;
 use bevy_transform_gizmo::TransformGizmoPlugin;
+use nalgebra::Isometry3;
 use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
-use optima_bevy_egui::{OEguiEngineWrapper};
+use optima_3d_spatial::optima_3d_transform_tree::OTransformTree;
+use optima_bevy_egui::{OEguiEngine, OEguiEngineWrapper};
+use optima_console::logging::OLogConfig;
+#[cfg(not(target_arch = "wasm32"))]
+use optima_file::path::OPath;
 use optima_interpolation::{InterpolatorTrait};
 use optima_linalg::{OLinalgCategory, OVec, OVecCategoryVec};
 use optima_proximity::shape_scene::{OParryGenericShapeScene};
 use optima_robotics::robot::ORobot;
 use optima_robotics::robotics_traits::AsRobotTrait;
 use optima_universal_hashmap::AnyHashmap;
-use crate::optima_bevy_utils::camera::CameraSystems;
+use crate::optima_bevy_utils::bounding_volumes::{BevyBoundingVolumeDisplay, BoundingVolumeSystems};
+use crate::optima_bevy_utils::camera::{BevyCameraControl, CameraSystems};
+use crate::optima_bevy_utils::diagnostics::{BevyFrameTimings, DiagnosticsSystems};
+use crate::optima_bevy_utils::collision_geometry::{BevyCollisionGeometryDisplay, CollisionGeometrySystems};
+use crate::optima_bevy_utils::environment::{BevyEnvironment, EnvironmentSystems, EnvPreset};
+use crate::optima_bevy_utils::joint_limits::{BevyJointLimitHeatMap, JointLimitSystems};
+use crate::optima_bevy_utils::keyframes::{BevyKeyframeEditor, KeyframeSystems};
+use crate::optima_bevy_utils::kinematic_tree::{BevyKinematicTree, KinematicTreeSystems};
+use crate::optima_bevy_utils::labels::{BevyFrameLabels, FrameLabelSystems};
+use crate::optima_bevy_utils::lidar::{BevyLidars, LidarMount, LidarSystems};
 use crate::optima_bevy_utils::lights::LightSystems;
-use crate::optima_bevy_utils::robotics::{BevyORobot, RoboticsActions, RoboticsSystems, RobotStateEngine};
-use crate::optima_bevy_utils::shape_scene::{ShapeSceneActions, ShapeSceneType};
+use crate::optima_bevy_utils::mesh::BevyLinkMeshCache;
+use crate::optima_bevy_utils::logging::{BevyLogPanelState, LogPanelSystems};
+use crate::optima_bevy_utils::robotics::{BevyORobot, BevyORobotInstances, RoboticsActions, RoboticsSystems, RobotStateEngine};
+use crate::optima_bevy_utils::shortcuts::{shortcut_just_triggered, ShortcutMap, ShortcutSystems, SHORTCUT_TOGGLE_DEBUG_PICKING};
+use crate::optima_bevy_utils::shape_scene::{BevyEnvironmentObjects, EnvironmentObject, ShapeSceneActions, ShapeSceneSystems, ShapeSceneType};
 use crate::optima_bevy_utils::storage::BevyAnyHashmap;
+use crate::optima_bevy_utils::trajectory_trail::TrajectoryTrailPlugin;
+use crate::optima_bevy_utils::transform_tree::{BevyTransformTree, TransformTreeSystems};
 use crate::optima_bevy_utils::transform::TransformUtils;
+use crate::optima_bevy_utils::viewer_config::OptimaViewerConfig;
 use crate::optima_bevy_utils::viewport_visuals::{BevyDrawShape, ViewportVisualsActions, ViewportVisualsSystems};
+use crate::optima_bevy_utils::web::OptimaBevyWebConfig;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::websocket::{BevyWebSocketStateServer, WebSocketSystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::state_bridge::{BevyStateBridge, StateBridgeEncoding, StateBridgeSystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::shared_memory::{BevySharedMemoryStateReader, SharedMemoryStateSource, SharedMemorySystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::hot_reload::{BevyRobotHotReloader, BevySceneHotReloader, HotReloadSystems, RobotHotReloadSource};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::preprocessing::{BevyRobotPreprocessing, BevyRobotPreprocessingJob, PreprocessingSystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::egui_persistence::{BevyEguiStatePersistence, EguiPersistenceSystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::interactive_ik::{BevyInteractiveIK, InteractiveIKSystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::capture::{BevyViewportCapture, CaptureSystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::headless::{HeadlessActions, HeadlessCopyNode, HeadlessRenderTarget, HeadlessSystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::sensors::{BevyCameraSensors, CameraSensorConfig, CameraSensorCopyNode, CameraSensorRenderTargets, SensorActions, SensorSystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::scene_file::{BevySceneFile, SceneFileSystems};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::scene_export::{BevySceneExport, SceneExportSystems};
 
 pub mod scripts;
 pub mod optima_bevy_utils;
@@ -30,17 +77,64 @@ pub mod optima_bevy_utils;
 pub trait OptimaBevyTrait {
     fn optima_bevy_starter_scene(&mut self) -> &mut Self;
     fn optima_bevy_base(&mut self) -> &mut Self;
+    fn optima_bevy_base_with_web_config(&mut self, web_config: OptimaBevyWebConfig) -> &mut Self;
+    fn optima_bevy_base_with_log_config(&mut self, web_config: OptimaBevyWebConfig, log_config: OLogConfig) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_headless(&mut self, width: u32, height: u32) -> &mut Self;
     fn optima_bevy_robotics_base<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static, A: AsRobotTrait<T, C, L>>(&mut self, as_chain: A) -> &mut Self;
     fn optima_bevy_pan_orbit_camera(&mut self) -> &mut Self;
+    fn optima_bevy_camera_control(&mut self) -> &mut Self;
     fn optima_bevy_starter_lights(&mut self) -> &mut Self;
     fn optima_bevy_spawn_robot<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self;
     fn optima_bevy_spawn_robot_in_pose<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static, V: OVec<T>>(&mut self, robot: Arc<ORobot<T, C, L>>, state: V, robot_instance_idx: usize) -> &mut Self;
+    fn optima_bevy_robot_instance<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, robot: ORobot<T, C, L>, base_pose: C::P<T>, robot_instance_idx: usize) -> &mut Self;
     fn optima_bevy_robotics_scene_visuals_starter(&mut self) -> &mut Self;
+    fn optima_bevy_environment(&mut self, preset: EnvPreset) -> &mut Self;
     fn optima_bevy_egui(&mut self) -> &mut Self;
+    fn optima_bevy_log_panel(&mut self) -> &mut Self;
+    fn optima_bevy_shortcuts_panel(&mut self) -> &mut Self;
+    fn optima_bevy_performance_overlay(&mut self) -> &mut Self;
+    fn optima_bevy_robot_state_history_panel(&mut self) -> &mut Self;
     fn optima_bevy_draw_3d_curve<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V> + 'static + Sync + Send>(&mut self, curve: I, num_points: usize, width_in_mm: f32, num_points_per_circle: usize, num_concentric_circles: usize) -> &mut Self;
     fn optima_bevy_draw_shape<T: AD, P: O3DPose<T>>(&mut self, shape: BevyDrawShape<T>, pose: P) -> &mut Self;
     fn optima_bevy_spawn_robot_shape_scene<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static, V: OVec<T>>(&mut self, robot: ORobot<T, C, L>, state: V) -> &mut Self;
     fn optima_bevy_spawn_generic_shape_scene<T: AD, P: O3DPose<T>>(&mut self, scene: OParryGenericShapeScene<T, P>) -> &mut Self;
+    fn optima_bevy_environment_object<T: AD, C: O3DPoseCategory + 'static>(&mut self, object: EnvironmentObject<T, C::P<T>>) -> &mut Self;
+    fn optima_bevy_collision_geometry_display<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self;
+    fn optima_bevy_bounding_volume_display<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self;
+    fn optima_bevy_lidar<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, mount: LidarMount) -> &mut Self;
+    fn optima_bevy_keyframe_editor<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self;
+    fn optima_bevy_frame_labels<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self;
+    fn optima_bevy_transform_tree(&mut self, tree: OTransformTree<f64, Isometry3<f64>>) -> &mut Self;
+    fn optima_bevy_joint_limit_heat_map<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self;
+    fn optima_bevy_kinematic_tree_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_websocket_state_server(&mut self, addr: &str, stream_robot_states: bool) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_state_bridge(&mut self, addr: &str, encoding: StateBridgeEncoding) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_shared_memory_state_reader(&mut self, path: &str, source: SharedMemoryStateSource, robot_instance_idx: usize) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_robot_hot_reload<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, source: RobotHotReloadSource) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_scene_hot_reload<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, path: &str) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_robot_preprocessing<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, save: bool) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_robot_average_distances<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, num_samples: usize, save: bool) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_egui_state_persistence(&mut self, path: OPath) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_interactive_ik<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, link_idx: usize) -> &mut Self;
+    fn optima_bevy_trajectory_trail<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, link_idxs: Vec<usize>) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_viewport_capture(&mut self, output_dir: &str) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_camera_sensor<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, config: CameraSensorConfig) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_scene_file_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, path: &str) -> &mut Self;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_scene_export_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, path: &str) -> &mut Self;
 }
 impl OptimaBevyTrait for App {
     fn optima_bevy_starter_scene(&mut self) -> &mut Self {
@@ -54,27 +148,73 @@ impl OptimaBevyTrait for App {
         self
     }
     fn optima_bevy_base(&mut self) -> &mut Self {
+        self.optima_bevy_base_with_web_config(OptimaBevyWebConfig::default())
+    }
+    fn optima_bevy_base_with_web_config(&mut self, web_config: OptimaBevyWebConfig) -> &mut Self {
+        self.optima_bevy_base_with_log_config(web_config, OLogConfig::default())
+    }
+    /// On native targets, bevy's own log plugin is replaced by the optima subscriber so that events
+    /// also reach the log panel (see `optima_bevy_log_panel`).  On the web, bevy's log plugin is kept
+    /// (it writes to the browser console) and only uses the config's filter.
+    ///
+    /// The window, theme, and background come from the `OptimaViewerConfig` resource if one was
+    /// inserted beforehand, and otherwise from the viewer config file (see `OptimaViewerConfig`).
+    fn optima_bevy_base_with_log_config(&mut self, web_config: OptimaBevyWebConfig, log_config: OLogConfig) -> &mut Self {
+        let viewer_config = match self.world.get_resource::<OptimaViewerConfig>() {
+            None => { OptimaViewerConfig::load_or_default() }
+            Some(viewer_config) => { viewer_config.clone() }
+        };
+        OEguiEngine::set_theme(viewer_config.theme);
+
+        let default_plugins = DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: viewer_config.window.title.clone(),
+                    resolution: (viewer_config.window.width, viewer_config.window.height).into(),
+                    canvas: Some(web_config.canvas_selector.clone()),
+                    fit_canvas_to_parent: web_config.fit_canvas_to_parent,
+                    prevent_default_event_handling: false,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+        #[cfg(target_arch = "wasm32")]
+        let default_plugins = default_plugins
+            .set(AssetPlugin {
+                asset_folder: web_config.asset_root.clone(),
+                ..Default::default()
+            })
+            .set(LogPlugin {
+                filter: log_config.to_filter_directives(),
+                ..Default::default()
+            });
+        #[cfg(not(target_arch = "wasm32"))]
+        let default_plugins = {
+            let log_buffer = match optima_console::logging::init_logging(&log_config) {
+                Ok(log_buffer) => { Some(log_buffer) }
+                Err(_) => { optima_console::logging::OLogBuffer::global() }
+            };
+            if let Some(log_buffer) = log_buffer { self.insert_resource(crate::optima_bevy_utils::logging::BevyLogBuffer(log_buffer)); }
+            default_plugins.disable::<LogPlugin>()
+        };
+
         self
-            .insert_resource(ClearColor(Color::rgb(0.5, 0.5, 0.5)))
+            .insert_resource(ClearColor(Color::rgb(viewer_config.background_color[0], viewer_config.background_color[1], viewer_config.background_color[2])))
+            .insert_resource(viewer_config)
             .insert_resource(Msaa::default())
             .insert_resource(BevyAnyHashmap(AnyHashmap::new()))
-            .add_plugins(DefaultPlugins
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        title: "OPTIMA".to_string(),
-                        ..Default::default()
-                    }),
-                    ..Default::default()
-                })
-            )
+            .insert_resource(ShortcutMap::new_with_defaults())
+            .add_plugins(default_plugins)
             .add_plugins( DefaultPickingPlugins)
+            .add_systems(PreUpdate, ShortcutSystems::system_update_shortcuts.after(InputSystem))
+            .add_systems(Update, (ShortcutSystems::system_toggle_panels_shortcut, ShortcutSystems::system_reset_state_shortcut, ShortcutSystems::system_undo_redo_shortcuts))
             .add_systems(
                 Update,
                 (
                     (|mut next: ResMut<NextState<_>>| next.set(DebugPickingMode::Normal)).run_if(in_state(DebugPickingMode::Disabled)),
                     (|mut next: ResMut<NextState<_>>| next.set(DebugPickingMode::Disabled)).run_if(in_state(DebugPickingMode::Normal)),
                 )
-                    .distributive_run_if(input_just_pressed(KeyCode::F3)),
+                    .distributive_run_if(shortcut_just_triggered(SHORTCUT_TOGGLE_DEBUG_PICKING)),
             )
             .add_systems(
                 Startup,
@@ -87,17 +227,94 @@ impl OptimaBevyTrait for App {
 
         self
     }
+    /// Alternative to `optima_bevy_base` for machines without a display: no window is opened, and
+    /// the scene is rendered into a `width` x `height` image instead, which can be read back or saved
+    /// through the `BevyHeadlessRenderer` resource.  A camera (marked `HeadlessCamera`) is spawned
+    /// on startup, so `optima_bevy_pan_orbit_camera` and `optima_bevy_egui` should not be used.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_headless(&mut self, width: u32, height: u32) -> &mut Self {
+        use bevy::app::ScheduleRunnerPlugin;
+        use bevy::render::{Render, RenderApp, RenderSet};
+        use bevy::render::extract_resource::ExtractResourcePlugin;
+        use bevy::render::main_graph::node::CAMERA_DRIVER;
+        use bevy::render::render_graph::RenderGraph;
+        use bevy::window::ExitCondition;
+        use bevy::winit::WinitPlugin;
+
+        let viewer_config = match self.world.get_resource::<OptimaViewerConfig>() {
+            None => { OptimaViewerConfig::load_or_default() }
+            Some(viewer_config) => { viewer_config.clone() }
+        };
+
+        let default_plugins = DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            })
+            .disable::<WinitPlugin>()
+            .disable::<LogPlugin>();
+        if let Ok(log_buffer) = optima_console::logging::init_logging(&OLogConfig::default()) {
+            self.insert_resource(crate::optima_bevy_utils::logging::BevyLogBuffer(log_buffer));
+        }
+
+        self
+            .insert_resource(ClearColor(Color::rgb(viewer_config.background_color[0], viewer_config.background_color[1], viewer_config.background_color[2])))
+            .insert_resource(viewer_config)
+            .insert_resource(BevyAnyHashmap(AnyHashmap::new()))
+            .add_plugins(default_plugins)
+            .add_plugins(ScheduleRunnerPlugin::run_loop(std::time::Duration::from_secs_f64(1.0 / 60.0)))
+            .add_plugins(StlPlugin)
+            .add_plugins(DebugLinesPlugin::default())
+            .insert_resource(RobotStateEngine::new());
+
+        let (renderer, target) = HeadlessActions::action_init_headless_renderer(&mut self.world, width, height);
+        self
+            .insert_resource(renderer)
+            .insert_resource(target)
+            .add_plugins(ExtractResourcePlugin::<HeadlessRenderTarget>::default())
+            .add_systems(Startup, HeadlessSystems::system_spawn_headless_camera)
+            .add_systems(First, HeadlessSystems::system_advance_headless_frame)
+            .add_systems(First, HeadlessSystems::system_receive_headless_frames.after(HeadlessSystems::system_advance_headless_frame));
+
+        let render_app = self.sub_app_mut(RenderApp);
+        render_app.add_systems(Render, HeadlessSystems::system_read_back_headless_frame.after(RenderSet::Render).before(RenderSet::Cleanup));
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(HeadlessCopyNode::NAME, HeadlessCopyNode::default());
+        graph.add_node_edge(CAMERA_DRIVER, HeadlessCopyNode::NAME);
+
+        self
+    }
     fn optima_bevy_robotics_base<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static, A: AsRobotTrait<T, C, L>>(&mut self, as_robot: A) -> &mut Self {
         self
-            .insert_resource(BevyORobot(as_robot.as_robot().clone(), 0))
-            .add_systems(Last, RoboticsSystems::system_robot_state_updater::<T, C, L>);
+            .insert_resource(BevyORobot(as_robot.as_robot().clone(), 0));
+        if !self.world.contains_resource::<BevyORobotInstances<T, C, L>>() {
+            self.add_systems(Last, RoboticsSystems::system_robot_state_updater::<T, C, L>);
+        }
 
         self
     }
+    /// Press O (or whatever `SHORTCUT_TOGGLE_ORTHOGRAPHIC` is mapped to) to switch between a
+    /// perspective and an orthographic projection.
     fn optima_bevy_pan_orbit_camera(&mut self) -> &mut Self {
         self
             .add_systems(Startup, CameraSystems::system_spawn_pan_orbit_camera)
-            .add_systems(PostUpdate, CameraSystems::system_pan_orbit_camera.in_set(BevySystemSet::Camera));
+            .add_systems(PostUpdate, CameraSystems::system_pan_orbit_camera.in_set(BevySystemSet::Camera))
+            .add_systems(PostUpdate, CameraSystems::system_toggle_orthographic_shortcut.in_set(BevySystemSet::Camera).before(CameraSystems::system_pan_orbit_camera))
+            .add_systems(PostUpdate, CameraSystems::system_sync_orthographic_projection.in_set(BevySystemSet::Camera).after(CameraSystems::system_pan_orbit_camera).after(CameraSystems::system_camera_control));
+
+        self
+    }
+    /// Adds `BevyCameraControl` for camera bookmarks, preset views, and moving the camera from code.
+    /// If `optima_bevy_egui` was called before, the "Camera" window is added as well.  Must be
+    /// called after `optima_bevy_pan_orbit_camera`.
+    fn optima_bevy_camera_control(&mut self) -> &mut Self {
+        self
+            .insert_resource(BevyCameraControl::new())
+            .add_systems(PostUpdate, CameraSystems::system_camera_control.in_set(BevySystemSet::Camera).after(CameraSystems::system_pan_orbit_camera));
+        if self.world.contains_resource::<OEguiEngineWrapper>() {
+            self.add_systems(Update, CameraSystems::system_camera_control_panel.before(BevySystemSet::Camera));
+        }
 
         self
     }
@@ -108,33 +325,111 @@ impl OptimaBevyTrait for App {
         self
     }
     fn optima_bevy_spawn_robot<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self {
-        self.add_systems(Startup, RoboticsSystems::system_spawn_robot_links_as_stl_meshes::<T, C, L>);
+        self
+            .init_resource::<BevyLinkMeshCache>()
+            .add_systems(Startup, RoboticsSystems::system_spawn_robot_links_as_stl_meshes::<T, C, L>);
 
         self
     }
     fn optima_bevy_spawn_robot_in_pose<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static, V: OVec<T>>(&mut self, robot: Arc<ORobot<T, C, L>>, state: V, robot_instance_idx: usize) -> &mut Self {
 
-        self.add_systems(Startup, move |mut commands: Commands, asset_server: Res<AssetServer>, mut materials: ResMut<Assets<StandardMaterial>>| {
+        self.init_resource::<BevyLinkMeshCache>();
+        self.add_systems(Startup, move |mut commands: Commands, asset_server: Res<AssetServer>, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>, mut images: ResMut<Assets<Image>>, mut mesh_cache: ResMut<BevyLinkMeshCache>| {
             let fk_res = robot.forward_kinematics(&state, None);
-            RoboticsActions::action_spawn_robot_as_stl_meshes(&robot, &fk_res, &mut commands, &asset_server, &mut materials, robot_instance_idx);
+            RoboticsActions::action_spawn_robot_as_stl_meshes(&robot, &fk_res, &mut commands, &asset_server, &mut meshes, &mut materials, &mut images, &mut mesh_cache, robot_instance_idx);
         });
 
         self
     }
+    /// Adds a robot instance at its own base pose, which is spawned on startup and set through
+    /// `RobotStateEngine` with the given instance idx.  Each instance gets a window with its own
+    /// joint sliders and link panel (this needs `optima_bevy_egui`).  Instance idx 0 is used by
+    /// `optima_bevy_robotics_base`, so other idxs should be used if both are in the same app.
+    fn optima_bevy_robot_instance<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, robot: ORobot<T, C, L>, base_pose: C::P<T>, robot_instance_idx: usize) -> &mut Self {
+        match self.world.get_resource_mut::<BevyORobotInstances<T, C, L>>() {
+            None => {
+                let mut instances = BevyORobotInstances::<T, C, L>::new();
+                instances.add_instance(robot, base_pose, robot_instance_idx);
+                self
+                    .insert_resource(instances)
+                    .init_resource::<BevyLinkMeshCache>()
+                    .add_systems(Startup, RoboticsSystems::system_spawn_robot_instances::<T, C, L>)
+                    .add_systems(Update, RoboticsSystems::system_robot_instances_panels_egui::<T, C, L>.before(BevySystemSet::Camera));
+                if !self.world.contains_resource::<BevyORobot<T, C, L>>() {
+                    self.add_systems(Last, RoboticsSystems::system_robot_state_updater::<T, C, L>);
+                }
+            }
+            Some(mut instances) => { instances.add_instance(robot, base_pose, robot_instance_idx); }
+        }
+
+        self
+    }
     fn optima_bevy_robotics_scene_visuals_starter(&mut self) -> &mut Self {
         self
             .add_systems(Startup, ViewportVisualsSystems::system_draw_robotics_grid);
 
         self
     }
+    /// Sets up the background, skybox, and ground plane (see `OptimaBevyEnvironment`), e.g., for
+    /// screenshots.  Also adds the "Environment" window for resizing the ground plane if
+    /// `optima_bevy_egui` was called before.
+    fn optima_bevy_environment(&mut self, preset: EnvPreset) -> &mut Self {
+        self
+            .insert_resource(BevyEnvironment::new(preset.to_environment()))
+            .add_systems(Startup, EnvironmentSystems::system_setup_environment)
+            .add_systems(Update, (EnvironmentSystems::system_update_skybox, EnvironmentSystems::system_update_ground_plane));
+        if self.world.contains_resource::<OEguiEngineWrapper>() {
+            self.add_systems(Update, EnvironmentSystems::system_environment_panel.before(BevySystemSet::Camera));
+        }
+
+        self
+    }
     fn optima_bevy_egui(&mut self) -> &mut Self {
         self
             .add_plugins(EguiPlugin)
             .insert_resource(OEguiEngineWrapper::new())
+            .add_systems(Update, |mut contexts: EguiContexts, egui_engine: Res<OEguiEngineWrapper>| { egui_engine.get_mutex_guard().toasts_mut().show(contexts.ctx_mut()) })
+            .add_systems(Update, RoboticsSystems::system_apply_robot_link_material_overrides)
             .add_systems(Last, |egui_engine: Res<OEguiEngineWrapper>| { egui_engine.get_mutex_guard().reset_on_frame() });
 
         self
     }
+    /// Must be called after `optima_bevy_egui`.
+    fn optima_bevy_log_panel(&mut self) -> &mut Self {
+        self
+            .insert_resource(BevyLogPanelState::new())
+            .add_systems(Update, LogPanelSystems::system_log_panel.before(BevySystemSet::Camera));
+
+        self
+    }
+    /// Adds the "Shortcuts" window, which lists the key chords in `ShortcutMap` and lets them be
+    /// remapped.  Must be called after `optima_bevy_egui`.
+    fn optima_bevy_shortcuts_panel(&mut self) -> &mut Self {
+        self.add_systems(Update, ShortcutSystems::system_shortcuts_panel.before(BevySystemSet::Camera));
+
+        self
+    }
+    /// Adds an overlay with the FPS, frame time, and the time per frame spent in forward
+    /// kinematics, proximity queries, and egui (see `BevyFrameTimings`).  The overlay is hidden
+    /// until it is toggled with F2.  Must be called after `optima_bevy_egui`.
+    fn optima_bevy_performance_overlay(&mut self) -> &mut Self {
+        if !self.is_plugin_added::<FrameTimeDiagnosticsPlugin>() { self.add_plugins(FrameTimeDiagnosticsPlugin); }
+        self
+            .insert_resource(BevyFrameTimings::new())
+            .add_systems(First, DiagnosticsSystems::system_end_frame_timings)
+            .add_systems(Update, DiagnosticsSystems::system_toggle_performance_overlay_shortcut)
+            .add_systems(Update, DiagnosticsSystems::system_performance_overlay.before(BevySystemSet::Camera));
+
+        self
+    }
+    /// Adds the "State History" window, for undoing and redoing edits made with the joint sliders,
+    /// interactive ik, and the keyframe editor (ctrl+Z and ctrl+Y work without it).  Must be called
+    /// after `optima_bevy_egui`.
+    fn optima_bevy_robot_state_history_panel(&mut self) -> &mut Self {
+        self.add_systems(Update, RoboticsSystems::system_robot_state_history_panel.before(BevySystemSet::Camera));
+
+        self
+    }
     fn optima_bevy_draw_3d_curve<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V> + 'static + Sync + Send>(&mut self, curve: I, num_points: usize, width_in_mm: f32, num_points_per_circle: usize, num_concentric_circles: usize) -> &mut Self {
         // mut lines: ResMut<DebugLines>
         self.add_systems(Update, move |mut gizmos: Gizmos| {
@@ -183,7 +478,323 @@ impl OptimaBevyTrait for App {
 
         self
     }
+    /// Adds an obstacle to the scene.  All objects are spawned on startup and collected in
+    /// `BevyEnvironmentObjects`, whose shape scene is checked against the robot in the collision
+    /// panel.
+    fn optima_bevy_environment_object<T: AD, C: O3DPoseCategory + 'static>(&mut self, object: EnvironmentObject<T, C::P<T>>) -> &mut Self {
+        match self.world.get_resource_mut::<BevyEnvironmentObjects<T, C>>() {
+            None => {
+                let mut environment_objects = BevyEnvironmentObjects::<T, C>::new();
+                environment_objects.add_object(object);
+                self
+                    .insert_resource(environment_objects)
+                    .add_systems(Startup, ShapeSceneSystems::system_spawn_environment_objects::<T, C>);
+            }
+            Some(mut environment_objects) => { environment_objects.add_object(object); }
+        }
 
+        self
+    }
+    /// Spawns the collision shapes of the robot in `BevyORobot` (hidden at first), keeps them posed
+    /// with the robot, and adds the "Geometry" window to show them instead of or on top of the visual
+    /// meshes.  The robot must be preprocessed, and this must be called after `optima_bevy_egui`.
+    fn optima_bevy_collision_geometry_display<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self {
+        self
+            .insert_resource(BevyCollisionGeometryDisplay::new())
+            .add_systems(Startup, CollisionGeometrySystems::system_spawn_robot_collision_geometry::<T, C, L>)
+            .add_systems(Update, CollisionGeometrySystems::system_collision_geometry_panel.before(BevySystemSet::Camera))
+            .add_systems(Update, CollisionGeometrySystems::system_apply_collision_geometry_display.after(CollisionGeometrySystems::system_collision_geometry_panel))
+            .add_systems(Last, CollisionGeometrySystems::system_update_robot_collision_geometry::<T, C, L>.after(RoboticsSystems::system_robot_state_updater::<T, C, L>));
+
+        self
+    }
+    /// Adds the "Bounding Volumes" window, which draws the bounding spheres and OBBs of the robot's
+    /// shapes (and any environment objects) colored by overlap (see `BevyBoundingVolumeDisplay`).
+    /// The robot must be preprocessed, and this must be called after `optima_bevy_egui`.
+    fn optima_bevy_bounding_volume_display<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self {
+        self
+            .insert_resource(BevyBoundingVolumeDisplay::new())
+            .add_systems(Update, BoundingVolumeSystems::system_bounding_volume_panel.before(BevySystemSet::Camera))
+            .add_systems(Update, BoundingVolumeSystems::system_draw_robot_bounding_volumes::<T, C, L>);
+
+        self
+    }
+    /// Mounts a simulated lidar on a link of the robot in `BevyORobot`; scans are read through the
+    /// `BevyLidars` resource and their beams are drawn in the viewport.  Can be called once per lidar
+    /// (names should be unique).  The robot must be preprocessed.
+    fn optima_bevy_lidar<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, mount: LidarMount) -> &mut Self {
+        match self.world.get_resource_mut::<BevyLidars>() {
+            None => {
+                let mut lidars = BevyLidars::new();
+                lidars.add_lidar(mount);
+                self
+                    .insert_resource(lidars)
+                    .add_systems(Update, LidarSystems::system_update_lidars::<T, C, L>)
+                    .add_systems(Update, LidarSystems::system_draw_lidar_beams.after(LidarSystems::system_update_lidars::<T, C, L>));
+                if self.world.contains_resource::<OEguiEngineWrapper>() {
+                    self.add_systems(Update, LidarSystems::system_lidar_panel.before(BevySystemSet::Camera));
+                }
+            }
+            Some(mut lidars) => { lidars.add_lidar(mount); }
+        }
+
+        self
+    }
+    /// Adds the "Keyframes" window for the robot in `BevyORobot` (see `BevyKeyframeEditor`).  Keyframes
+    /// are posed with the joint sliders of the robot info panel.  Must be called after
+    /// `optima_bevy_egui`.
+    fn optima_bevy_keyframe_editor<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self {
+        let robot_instance_idx = self.world.get_resource::<BevyORobot<T, C, L>>().map(|x| x.1).unwrap_or(0);
+        self
+            .insert_resource(BevyKeyframeEditor::new(robot_instance_idx))
+            // after the joint sliders, so the preview overrides their state update.
+            .add_systems(Update, KeyframeSystems::system_keyframe_editor_panel::<T, C, L>.before(BevySystemSet::Camera).after(RoboticsSystems::system_robot_main_info_panel_egui::<T, C, L>));
+
+        self
+    }
+    /// Draws link name labels (toggled per link in the link panel) and `FrameAnnotation`s over the
+    /// viewport, and adds the "Labels" window.  Must be called after `optima_bevy_egui`.
+    fn optima_bevy_frame_labels<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self {
+        self
+            .insert_resource(BevyFrameLabels::new())
+            .add_systems(Update, FrameLabelSystems::system_frame_labels_panel.before(BevySystemSet::Camera))
+            .add_systems(Update, FrameLabelSystems::system_draw_frame_labels::<T, C, L>.before(BevySystemSet::Camera));
+
+        self
+    }
+    /// Draws the frames of `tree` (see `BevyTransformTree`) as coordinate axes with their names, and
+    /// adds the "Transform Tree" window.  Frames can be added and updated at runtime through the
+    /// `BevyTransformTree` resource.  Must be called after `optima_bevy_egui`.
+    fn optima_bevy_transform_tree(&mut self, tree: OTransformTree<f64, Isometry3<f64>>) -> &mut Self {
+        self
+            .insert_resource(BevyTransformTree::new(tree))
+            .add_systems(Update, TransformTreeSystems::system_draw_transform_tree)
+            .add_systems(Update, TransformTreeSystems::system_draw_transform_tree_names.before(BevySystemSet::Camera))
+            .add_systems(Update, TransformTreeSystems::system_transform_tree_panel.before(BevySystemSet::Camera));
+
+        self
+    }
+    /// Colors links by how close their parent joints are to their position limits (see
+    /// `BevyJointLimitHeatMap`), for the robot in `BevyORobot` and every robot instance.  The "Joint
+    /// Limits" window is added if `optima_bevy_egui` was called first.
+    fn optima_bevy_joint_limit_heat_map<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self {
+        self
+            .insert_resource(BevyJointLimitHeatMap::new())
+            .add_systems(Last, JointLimitSystems::system_update_joint_limit_heat_map::<T, C, L>.after(RoboticsSystems::system_robot_state_updater::<T, C, L>))
+            .add_systems(Last, JointLimitSystems::system_apply_joint_limit_heat_map.after(JointLimitSystems::system_update_joint_limit_heat_map::<T, C, L>));
+        if self.world.contains_resource::<OEguiEngineWrapper>() {
+            self.add_systems(Update, JointLimitSystems::system_joint_limit_panel.before(BevySystemSet::Camera));
+        }
+
+        self
+    }
+    /// Adds the "Kinematic Tree" window, which shows the links and joints of the robot in
+    /// `BevyORobot`.  Link meshes are made pickable, and picking one selects it in the tree (and
+    /// the other way around).  Must be called after `optima_bevy_egui` and
+    /// `optima_bevy_robotics_base`.
+    fn optima_bevy_kinematic_tree_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self) -> &mut Self {
+        self
+            .insert_resource(BevyKinematicTree::new())
+            .add_systems(Update, KinematicTreeSystems::system_make_link_meshes_pickable)
+            .add_systems(Update, KinematicTreeSystems::system_kinematic_tree_panel::<T, C, L>.before(BevySystemSet::Camera));
+
+        self
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_websocket_state_server(&mut self, addr: &str, stream_robot_states: bool) -> &mut Self {
+        self
+            .insert_resource(BevyWebSocketStateServer::new(addr, stream_robot_states))
+            .add_systems(Update, WebSocketSystems::system_websocket_state_server);
+
+        self
+    }
+    /// Listens on a plain tcp socket (e.g., "127.0.0.1:9002") for joint states from an external
+    /// controller or robot driver and applies them through `RobotStateEngine` (see `StateBridge`).
+    /// If the address cannot be bound, a warning is logged and the bridge is not added.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_state_bridge(&mut self, addr: &str, encoding: StateBridgeEncoding) -> &mut Self {
+        match BevyStateBridge::new(addr, encoding) {
+            Ok(state_bridge) => {
+                self
+                    .insert_resource(state_bridge)
+                    .add_systems(Update, StateBridgeSystems::system_state_bridge);
+            }
+            Err(e) => { warn!("could not start state bridge on {} ({}); the state bridge was not added.", addr, e); }
+        }
+
+        self
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    /// If the ring buffer cannot be opened, a warning is logged and the reader is not added.
+    fn optima_bevy_shared_memory_state_reader(&mut self, path: &str, source: SharedMemoryStateSource, robot_instance_idx: usize) -> &mut Self {
+        match BevySharedMemoryStateReader::new(path, source, robot_instance_idx) {
+            Ok(reader) => {
+                self
+                    .insert_resource(reader)
+                    .add_systems(Update, SharedMemorySystems::system_shared_memory_state_reader);
+            }
+            Err(e) => { warn!("could not open shared memory ring buffer {} ({}); the state reader was not added.", path, e); }
+        }
+
+        self
+    }
+    /// Must be called after `optima_bevy_robotics_base`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_robot_hot_reload<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, source: RobotHotReloadSource) -> &mut Self {
+        let robot_name = self.world.get_resource::<BevyORobot<T, C, L>>().expect("optima_bevy_robotics_base must be called before optima_bevy_robot_hot_reload").0.robot_name().to_string();
+        match BevyRobotHotReloader::new(&robot_name, source) {
+            Ok(reloader) => {
+                self
+                    .insert_resource(reloader)
+                    .init_resource::<BevyLinkMeshCache>()
+                    .add_systems(Update, HotReloadSystems::system_robot_hot_reload::<T, C, L>);
+            }
+            Err(e) => { warn!("could not watch robot {} ({}); hot reload was not added.", robot_name, e); }
+        }
+
+        self
+    }
+    /// Applies the scene file at `path` whenever it changes.  If its directory cannot be watched,
+    /// a warning is logged and hot reload is not added.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_scene_hot_reload<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, path: &str) -> &mut Self {
+        match BevySceneHotReloader::new(path) {
+            Ok(reloader) => {
+                self
+                    .insert_resource(reloader)
+                    .add_systems(Update, HotReloadSystems::system_scene_hot_reload::<T, C, L>);
+            }
+            Err(e) => { warn!("could not watch scene {} ({}); hot reload was not added.", path, e); }
+        }
+
+        self
+    }
+    /// Preprocesses the robot in `BevyORobot` in the background, with a progress window that can
+    /// cancel it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_robot_preprocessing<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, save: bool) -> &mut Self {
+        let robot = self.world.get_resource::<BevyORobot<T, C, L>>().expect("optima_bevy_robotics_base must be called before optima_bevy_robot_preprocessing").0.clone();
+        self
+            .insert_resource(BevyRobotPreprocessing::new(robot, save))
+            .add_systems(Update, PreprocessingSystems::system_robot_preprocessing::<T, C, L>)
+            .add_systems(Update, PreprocessingSystems::system_robot_preprocessing_panel::<T, C, L>.before(BevySystemSet::Camera));
+
+        self
+    }
+    /// Same as `optima_bevy_robot_preprocessing`, but only recomputes the average distances between
+    /// the robot's shapes.  The two should not be added to the same app.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_robot_average_distances<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, num_samples: usize, save: bool) -> &mut Self {
+        let robot = self.world.get_resource::<BevyORobot<T, C, L>>().expect("optima_bevy_robotics_base must be called before optima_bevy_robot_average_distances").0.clone();
+        self
+            .insert_resource(BevyRobotPreprocessing::new_with_job(robot, BevyRobotPreprocessingJob::AverageDistances { num_samples }, save))
+            .add_systems(Update, PreprocessingSystems::system_robot_preprocessing::<T, C, L>)
+            .add_systems(Update, PreprocessingSystems::system_robot_preprocessing_panel::<T, C, L>.before(BevySystemSet::Camera));
+
+        self
+    }
+    /// Restores the egui state saved at `path` (if any) on startup, and saves it there on exit, so
+    /// windows and panel settings do not have to be rearranged on every run.  Must be called after
+    /// `optima_bevy_egui`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_egui_state_persistence(&mut self, path: OPath) -> &mut Self {
+        self
+            .insert_resource(BevyEguiStatePersistence::new(path))
+            .add_systems(Startup, EguiPersistenceSystems::system_load_egui_state)
+            .add_systems(Last, EguiPersistenceSystems::system_save_egui_state_on_exit);
+
+        self
+    }
+    /// Spawns a draggable gizmo at the given link and solves ik to follow it (see
+    /// `BevyInteractiveIK`).  Should not be combined with other sources of robot states, e.g., the
+    /// joint sliders.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_interactive_ik<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, link_idx: usize) -> &mut Self {
+        let robot = self.world.get_resource::<BevyORobot<T, C, L>>().expect("optima_bevy_robotics_base must be called before optima_bevy_interactive_ik");
+        match BevyInteractiveIK::new(robot.0.to_other_ad_type::<f64>(), link_idx, robot.1) {
+            Ok(interactive_ik) => {
+                self
+                    .insert_resource(interactive_ik)
+                    .add_systems(Startup, InteractiveIKSystems::system_spawn_interactive_ik_gizmo::<T, C, L>)
+                    .add_systems(Update, InteractiveIKSystems::system_interactive_ik::<T, C, L>);
+            }
+            Err(e) => { warn!("could not set up interactive ik ({}); the gizmo was not added.", e); }
+        }
+
+        self
+    }
+    /// Draws fading trails behind the given links of the robot (see `TrajectoryTrailPlugin`).
+    fn optima_bevy_trajectory_trail<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, link_idxs: Vec<usize>) -> &mut Self {
+        let robot_instance_idx = self.world.get_resource::<BevyORobot<T, C, L>>().expect("optima_bevy_robotics_base must be called before optima_bevy_trajectory_trail").1;
+        self.add_plugins(TrajectoryTrailPlugin::<T, C, L>::new(link_idxs, robot_instance_idx));
+
+        self
+    }
+    /// Adds the "Capture" window for screenshots and recordings (see `BevyViewportCapture`), which
+    /// are saved under `output_dir` unless another path is given.  Must be called after
+    /// `optima_bevy_egui`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_viewport_capture(&mut self, output_dir: &str) -> &mut Self {
+        self
+            .insert_resource(BevyViewportCapture::new(output_dir))
+            .add_systems(Update, CaptureSystems::system_viewport_capture_panel.before(BevySystemSet::Camera))
+            .add_systems(Update, CaptureSystems::system_screenshot_shortcut)
+            .add_systems(Last, CaptureSystems::system_viewport_capture);
+
+        self
+    }
+    /// Mounts a simulated rgb-d camera on a link of the robot in `BevyORobot`; frames are read
+    /// through the `BevyCameraSensors` resource.  Can be called once per sensor (names should be
+    /// unique), after the render plugin is added (i.e., after `optima_bevy_base` or
+    /// `optima_bevy_headless`).
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_camera_sensor<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, config: CameraSensorConfig) -> &mut Self {
+        use bevy::render::{Render, RenderApp, RenderSet};
+        use bevy::render::extract_resource::ExtractResourcePlugin;
+        use bevy::render::main_graph::node::CAMERA_DRIVER;
+        use bevy::render::render_graph::RenderGraph;
+
+        let first_sensor = !self.world.contains_resource::<BevyCameraSensors>();
+        SensorActions::action_add_camera_sensor(&mut self.world, config);
+        if !first_sensor { return self; }
+
+        self
+            .add_plugins(ExtractResourcePlugin::<CameraSensorRenderTargets>::default())
+            .add_systems(First, SensorSystems::system_receive_camera_sensor_frames)
+            .add_systems(Last, SensorSystems::system_update_camera_sensors::<T, C, L>.after(RoboticsSystems::system_robot_state_updater::<T, C, L>));
+
+        let render_app = self.sub_app_mut(RenderApp);
+        render_app.add_systems(Render, SensorSystems::system_read_back_camera_sensor_frames.after(RenderSet::Render).before(RenderSet::Cleanup));
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(CameraSensorCopyNode::NAME, CameraSensorCopyNode::default());
+        graph.add_node_edge(CAMERA_DRIVER, CameraSensorCopyNode::NAME);
+
+        self
+    }
+    /// Adds the "Scene" window, which saves the robots, environment objects, camera, and egui layout
+    /// to a scene file (see `OptimaViewerScene`) at the given path, and loads the joint states and
+    /// camera back from one.  Must be called after `optima_bevy_egui`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_scene_file_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, path: &str) -> &mut Self {
+        self
+            .insert_resource(BevySceneFile::new(path))
+            .add_systems(Update, SceneFileSystems::system_scene_file_panel::<T, C, L>.before(BevySystemSet::Camera));
+
+        self
+    }
+    /// Adds the "Export" window, which writes the robots (at their current states), environment
+    /// objects, and ground plane to a glTF, GLB, or USD file (see `SceneExportActions::action_scene_export`),
+    /// e.g., for Blender, or to a urdf with the environment objects as world-fixed obstacles (see
+    /// `SceneExportActions::action_urdf_snapshot`), e.g., for other simulators.  Must be called after
+    /// `optima_bevy_egui`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn optima_bevy_scene_export_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, path: &str) -> &mut Self {
+        self
+            .insert_resource(BevySceneExport::new(path))
+            .add_systems(Update, SceneExportSystems::system_scene_export_panel::<T, C, L>.before(BevySystemSet::Camera));
+
+        self
+    }
 }
 
 #[derive(Clone, Debug, SystemSet, Hash, PartialEq, Eq)]
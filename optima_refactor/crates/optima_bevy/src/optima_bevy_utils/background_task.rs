@@ -0,0 +1,99 @@
+use std::panic::AssertUnwindSafe;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy_egui::egui;
+use futures_lite::future;
+use optima_bevy_egui::{OEguiEngineWrapper, OEguiProgressBar, OEguiWidgetTrait};
+use optima_console::progress::{OProgressHandle, OProgressState};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BackgroundTaskStatus {
+    Running,
+    Finished,
+    /// The closure returned None, i.e., it stopped because the task was cancelled.
+    Cancelled,
+    /// The closure panicked.
+    Failed
+}
+
+/// A closure running on bevy's `AsyncComputeTaskPool`, so that long computations (e.g., robot
+/// preprocessing) do not freeze the viewer.  The closure reports progress through the
+/// `OProgressHandle` it is given, should return None once that handle is cancelled, and is polled
+/// for its result with `poll` (typically from a system, every frame).
+#[derive(Resource)]
+pub struct BackgroundTask<R: Send + Sync + 'static> {
+    name: String,
+    progress: OProgressHandle,
+    task: Option<Task<Option<Option<R>>>>,
+    status: BackgroundTaskStatus
+}
+impl<R: Send + Sync + 'static> BackgroundTask<R> {
+    pub fn spawn<F: FnOnce(&OProgressHandle) -> Option<R> + Send + 'static>(name: &str, f: F) -> Self {
+        let progress = OProgressHandle::new();
+        let task_progress = progress.clone();
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            std::panic::catch_unwind(AssertUnwindSafe(|| f(&task_progress))).ok()
+        });
+
+        Self { name: name.to_string(), progress, task: Some(task), status: BackgroundTaskStatus::Running }
+    }
+    /// Returns the closure's result on the first call after it is done, and None otherwise.
+    pub fn poll(&mut self) -> Option<R> {
+        let task = self.task.as_mut()?;
+        let res = future::block_on(future::poll_once(task))?;
+        self.task = None;
+
+        match res {
+            Some(Some(out)) => {
+                self.status = BackgroundTaskStatus::Finished;
+                Some(out)
+            }
+            Some(None) => {
+                self.status = BackgroundTaskStatus::Cancelled;
+                None
+            }
+            None => {
+                warn!("background task {} failed.", self.name);
+                self.status = BackgroundTaskStatus::Failed;
+                None
+            }
+        }
+    }
+    pub fn cancel(&self) {
+        self.progress.cancel();
+    }
+    #[inline(always)]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    #[inline(always)]
+    pub fn progress(&self) -> &OProgressHandle {
+        &self.progress
+    }
+    #[inline(always)]
+    pub fn status(&self) -> &BackgroundTaskStatus {
+        &self.status
+    }
+}
+
+pub struct BackgroundTaskActions;
+impl BackgroundTaskActions {
+    /// Progress bar with the current stage, and a cancel button while the task is running.
+    pub fn action_background_task_progress_egui<R: Send + Sync + 'static>(task: &BackgroundTask<R>, id_str: &str, egui_engine: &Res<OEguiEngineWrapper>, ui: &mut egui::Ui) {
+        let state: OProgressState = task.progress().state();
+        match task.status() {
+            BackgroundTaskStatus::Running => {
+                let stage = if state.stage.is_empty() { "starting..." } else { state.stage.as_str() };
+                OEguiProgressBar::new(state.overall_fraction, true).with_text(stage).show(id_str, ui, egui_engine, &());
+                if task.progress().is_cancelled() {
+                    ui.label("cancelling...");
+                } else if ui.button("Cancel").clicked() {
+                    task.cancel();
+                }
+            }
+            BackgroundTaskStatus::Finished => { OEguiProgressBar::new(1.0, true).show(id_str, ui, egui_engine, &()); }
+            BackgroundTaskStatus::Cancelled => { ui.label("Cancelled."); }
+            BackgroundTaskStatus::Failed => { ui.label("Failed (see the log)."); }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
+use parry_ad::shape::TypedShape;
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use optima_linalg::{OLinalgCategory, OVec};
+use optima_proximity::pair_group_queries::{OPairGroupQryTrait, OParryIntersectGroupArgs, OParryIntersectGroupQry, OParryPairIdxs, OParryPairSelector, OSkipReason};
+use optima_proximity::pair_queries::ParryShapeRep;
+use optima_proximity::shape_scene::ShapeSceneTrait;
+use optima_proximity::shapes::{OParryShape, OParryShpGeneric, OParryShpTrait};
+use optima_universal_hashmap::AHashMapWrapper;
+use crate::optima_bevy_utils::robotics::{BevyORobot, RobotStateEngine};
+use crate::optima_bevy_utils::shape_scene::BevyEnvironmentObjects;
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+/// Debug drawing of every robot (and environment) shape's bounding sphere and OBB as wireframes.
+/// Volumes that overlap another volume of the same kind (in a pair that is not skipped) are drawn
+/// in `overlap_color`, all others in `free_color`, which shows how much a bounding volume filter
+/// stage would let through for the current state.
+#[derive(Resource)]
+pub struct BevyBoundingVolumeDisplay {
+    pub show_bounding_spheres: bool,
+    pub show_obbs: bool,
+    pub overlap_color: Color,
+    pub free_color: Color,
+    num_overlapping_sphere_pairs: usize,
+    num_overlapping_obb_pairs: usize,
+    /// state and number of environment shapes the cached overlaps below were computed for.
+    overlap_inputs: Option<(Vec<f64>, usize)>,
+    sphere_overlaps: Option<HashSet<usize>>,
+    obb_overlaps: Option<HashSet<usize>>
+}
+impl BevyBoundingVolumeDisplay {
+    pub fn new() -> Self {
+        Self {
+            show_bounding_spheres: false,
+            show_obbs: false,
+            overlap_color: Color::rgb(1.0, 0.2, 0.2),
+            free_color: Color::rgb(0.2, 0.9, 0.3),
+            num_overlapping_sphere_pairs: 0,
+            num_overlapping_obb_pairs: 0,
+            overlap_inputs: None,
+            sphere_overlaps: None,
+            obb_overlaps: None,
+        }
+    }
+}
+
+pub struct BoundingVolumeSystems;
+impl BoundingVolumeSystems {
+    /// The overlap queries only rerun when the robot state, the robot, or the environment objects
+    /// change (or a volume kind is switched on); the wireframes themselves are redrawn every frame.
+    pub fn system_draw_robot_bounding_volumes<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                                robot_state_engine: Res<RobotStateEngine>,
+                                                                                                                environment_objects: Option<Res<BevyEnvironmentObjects<T, C>>>,
+                                                                                                                mut display: ResMut<BevyBoundingVolumeDisplay>,
+                                                                                                                mut gizmos: Gizmos) {
+        if !display.show_bounding_spheres && !display.show_obbs { return; }
+        let Some(raw_state) = robot_state_engine.get_robot_state(robot.1) else { return; };
+        let state = OVec::ovec_to_other_ad_type::<T>(raw_state);
+        let shapes = robot.get_shapes();
+        let poses = robot.get_shape_poses(&state);
+        let skips = robot.get_pair_skips();
+
+        let empty_shapes = vec![];
+        let empty_poses = vec![];
+        let (environment_shapes, environment_poses) = match &environment_objects {
+            None => { (&empty_shapes, std::borrow::Cow::Borrowed(&empty_poses)) }
+            Some(environment_objects) => { (environment_objects.shape_scene().get_shapes(), environment_objects.shape_scene().get_shape_poses(&())) }
+        };
+
+        let inputs = (raw_state.clone(), environment_shapes.len());
+        let environment_changed = environment_objects.as_ref().map(|x| x.is_changed()).unwrap_or(false);
+        if robot.is_changed() || environment_changed || display.overlap_inputs.as_ref() != Some(&inputs) {
+            display.overlap_inputs = Some(inputs);
+            display.sphere_overlaps = None;
+            display.obb_overlaps = None;
+        }
+
+        for rep in [ParryShapeRep::BoundingSphere, ParryShapeRep::OBB] {
+            let show = match rep { ParryShapeRep::BoundingSphere => { display.show_bounding_spheres } _ => { display.show_obbs } };
+            if !show { continue; }
+
+            let cached = match rep { ParryShapeRep::BoundingSphere => { display.sphere_overlaps.take() } _ => { display.obb_overlaps.take() } };
+            let overlapping = match cached {
+                Some(overlapping) => { overlapping }
+                None => { Self::compute_overlaps(shapes, poses.as_ref(), skips, environment_shapes, environment_poses.as_ref(), &rep, &mut display) }
+            };
+
+            let all_shapes = shapes.iter().zip(poses.iter()).chain(environment_shapes.iter().zip(environment_poses.iter()));
+            for (i, (shape, pose)) in all_shapes.enumerate() {
+                let color = if overlapping.contains(&i) { display.overlap_color } else { display.free_color };
+                draw_bounding_volume(shape, pose, &rep, color, &mut gizmos);
+            }
+
+            match rep {
+                ParryShapeRep::BoundingSphere => { display.sphere_overlaps = Some(overlapping); }
+                _ => { display.obb_overlaps = Some(overlapping); }
+            }
+        }
+    }
+    /// Indices of the volumes that overlap another volume of the same kind; robot shapes are indexed
+    /// first, then environment shapes.
+    fn compute_overlaps<T: AD, P: O3DPose<T>>(shapes: &Vec<OParryShape<T, P>>,
+                                              poses: &Vec<P>,
+                                              skips: &AHashMapWrapper<(u64, u64), Vec<OSkipReason>>,
+                                              environment_shapes: &Vec<OParryShape<T, P>>,
+                                              environment_poses: &Vec<P>,
+                                              rep: &ParryShapeRep,
+                                              display: &mut BevyBoundingVolumeDisplay) -> HashSet<usize> {
+        let mut overlapping = HashSet::new();
+        let args = OParryIntersectGroupArgs::new(rep.clone(), rep.clone(), false, false);
+        let self_res = OParryIntersectGroupQry::query(shapes, shapes, poses, poses, &OParryPairSelector::HalfPairs, skips, &(), false, &args);
+        let mut num_overlapping_pairs = 0;
+        self_res.outputs().iter().filter(|x| x.data().intersect()).for_each(|x| {
+            if let OParryPairIdxs::Shapes(a, b) = x.pair_idxs() { overlapping.insert(*a); overlapping.insert(*b); num_overlapping_pairs += 1; }
+        });
+        if !environment_shapes.is_empty() {
+            let env_res = OParryIntersectGroupQry::query(shapes, environment_shapes, poses, environment_poses, &OParryPairSelector::AllPairs, &(), &(), false, &args);
+            env_res.outputs().iter().filter(|x| x.data().intersect()).for_each(|x| {
+                if let OParryPairIdxs::Shapes(a, b) = x.pair_idxs() { overlapping.insert(*a); overlapping.insert(shapes.len() + *b); num_overlapping_pairs += 1; }
+            });
+        }
+        match rep {
+            ParryShapeRep::BoundingSphere => { display.num_overlapping_sphere_pairs = num_overlapping_pairs; }
+            _ => { display.num_overlapping_obb_pairs = num_overlapping_pairs; }
+        }
+
+        overlapping
+    }
+    pub fn system_bounding_volume_panel(mut display: ResMut<BevyBoundingVolumeDisplay>,
+                                        mut contexts: EguiContexts,
+                                        egui_engine: Res<OEguiEngineWrapper>,
+                                        window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Bounding Volumes", true, true, false, false, false, true)
+            .show("bounding_volumes_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                ui.checkbox(&mut display.show_bounding_spheres, "bounding spheres");
+                if display.show_bounding_spheres { ui.label(format!("overlapping pairs: {}", display.num_overlapping_sphere_pairs)); }
+                ui.checkbox(&mut display.show_obbs, "OBBs");
+                if display.show_obbs { ui.label(format!("overlapping pairs: {}", display.num_overlapping_obb_pairs)); }
+            });
+    }
+}
+
+fn draw_bounding_volume<T: AD, P: O3DPose<T>>(shape: &OParryShape<T, P>, pose: &P, rep: &ParryShapeRep, color: Color, gizmos: &mut Gizmos) {
+    let volume: &OParryShpGeneric<T, P> = match rep {
+        ParryShapeRep::BoundingSphere => { shape.base_shape().bounding_sphere() }
+        _ => { shape.base_shape().obb() }
+    };
+    let transform = TransformUtils::util_convert_3d_pose_to_y_up_bevy_transform(volume.get_isometry3_cow(pose).as_ref());
+
+    match volume.boxed_shape().shape().as_typed_shape() {
+        TypedShape::Ball(ball) => {
+            gizmos.sphere(transform.translation, transform.rotation, ball.radius.to_constant() as f32, color);
+        }
+        TypedShape::Cuboid(c) => {
+            let scale = Vec3::new(c.half_extents[0].to_constant() as f32, c.half_extents[1].to_constant() as f32, c.half_extents[2].to_constant() as f32) * 2.0;
+            gizmos.cuboid(transform.with_scale(scale), color);
+        }
+        _ => { }
+    }
+}
@@ -1,37 +1,201 @@
 use bevy::input::mouse::{MouseMotion, MouseWheel};
 use bevy::math::Vec3;
 use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
 use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
 use bevy_mod_picking::prelude::RaycastPickCamera;
-use optima_bevy_egui::{OEguiEngineWrapper};
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use serde::{Deserialize, Serialize};
+use crate::optima_bevy_utils::shortcuts::{ShortcutMap, SHORTCUT_TOGGLE_ORTHOGRAPHIC};
 use crate::optima_bevy_utils::transform::TransformUtils;
+use crate::optima_bevy_utils::viewer_config::OptimaViewerConfig;
 
 pub struct CameraActions;
 impl CameraActions {
     pub fn action_spawn_pan_orbit_camera(commands: &mut Commands,location: Vec3) {
+        Self::action_spawn_pan_orbit_camera_with_focus(commands, location, Vec3::ZERO);
+    }
+    /// `location` and `focus` are z-up.
+    pub fn action_spawn_pan_orbit_camera_with_focus(commands: &mut Commands, location: Vec3, focus: Vec3) {
 
         let translation = TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(location);
-        let radius = translation.length();
+        let focus = TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(focus);
+        let radius = (translation - focus).length();
 
         commands.spawn((Camera3dBundle {
             transform: Transform::from_translation(translation)
-                .looking_at(Vec3::ZERO, Vec3::Y),
+                .looking_at(focus, Vec3::Y),
             ..Default::default()
         },
                         RaycastPickCamera::default(),
                         bevy_transform_gizmo::GizmoPickSource::default()
 
         )).insert(PanOrbitCamera {
+            focus,
             radius,
             ..Default::default()
         });
     }
+    /// Moves the pan orbit camera to `location`, looking at `focus` (both in bevy space).
+    pub fn action_set_pan_orbit_camera(pan_orbit: &mut PanOrbitCamera, transform: &mut Transform, location: Vec3, focus: Vec3) {
+        let (rotation, radius) = camera_rotation_and_radius(location, focus);
+        Self::action_set_pan_orbit_camera_rotation(pan_orbit, transform, focus, rotation, radius);
+    }
+    /// Switches between a perspective and an orthographic projection.  The orthographic view is
+    /// sized so that things at the focus point keep their on-screen size, and follows the orbit
+    /// radius from then on (see `system_sync_orthographic_projection`), so zooming still works.
+    pub fn action_set_orthographic(pan_orbit: &PanOrbitCamera, projection: &mut Projection, orthographic: bool) {
+        match (orthographic, &*projection) {
+            (true, Projection::Perspective(_)) => {
+                *projection = Projection::Orthographic(OrthographicProjection {
+                    near: -1000.0,
+                    scaling_mode: ScalingMode::FixedVertical(orthographic_height(pan_orbit.radius)),
+                    ..Default::default()
+                });
+            }
+            (false, Projection::Orthographic(_)) => {
+                *projection = Projection::Perspective(PerspectiveProjection::default());
+            }
+            _ => { }
+        }
+    }
+    fn action_set_pan_orbit_camera_rotation(pan_orbit: &mut PanOrbitCamera, transform: &mut Transform, focus: Vec3, rotation: Quat, radius: f32) {
+        pan_orbit.focus = focus;
+        pan_orbit.radius = radius;
+        transform.rotation = rotation;
+        transform.translation = focus + rotation.mul_vec3(Vec3::new(0.0, 0.0, radius));
+    }
+}
+
+/// Height of the orthographic view that matches the default perspective view at distance `radius`.
+fn orthographic_height(radius: f32) -> f32 {
+    2.0 * radius * (PerspectiveProjection::default().fov / 2.0).tan()
+}
+
+/// Rotation of a camera at `location` looking at `focus`, and its distance to `focus`.  Cameras
+/// looking straight up or down keep the optima x axis pointing up on screen.
+fn camera_rotation_and_radius(location: Vec3, focus: Vec3) -> (Quat, f32) {
+    let offset = location - focus;
+    let radius = f32::max(offset.length(), 0.05);
+    let up = if offset.normalize_or_zero().cross(Vec3::Y).length_squared() < 1e-6 { TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(Vec3::X) } else { Vec3::Y };
+    let rotation = Transform::from_translation(focus + offset.normalize_or_zero() * radius).looking_at(focus, up).rotation;
+    (rotation, radius)
 }
 
 pub struct CameraSystems;
 impl CameraSystems {
-    pub fn system_spawn_pan_orbit_camera(mut commands: Commands) {
-        CameraActions::action_spawn_pan_orbit_camera(&mut commands, Vec3::new(5.0, 0.8, 1.5));
+    pub fn system_spawn_pan_orbit_camera(mut commands: Commands, viewer_config: Option<Res<OptimaViewerConfig>>) {
+        let camera_config = viewer_config.map(|x| x.camera.clone()).unwrap_or_default();
+        CameraActions::action_spawn_pan_orbit_camera_with_focus(&mut commands, Vec3::from_array(camera_config.location), Vec3::from_array(camera_config.focus));
+    }
+    /// Applies requests made through `BevyCameraControl`, animating fly-to transitions, and keeps
+    /// its record of the current camera up to date.  Runs after `system_pan_orbit_camera`.
+    pub fn system_camera_control(mut control: ResMut<BevyCameraControl>,
+                                 time: Res<Time>,
+                                 mut query: Query<(&mut PanOrbitCamera, &mut Transform)>) {
+        let Some((mut pan_orbit, mut transform)) = query.iter_mut().next() else { return; };
+        let control = &mut *control;
+
+        if let Some((location, focus)) = control.requested.take() {
+            let location = TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(location);
+            let focus = TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(focus);
+            if control.transition_duration <= 0.0 {
+                control.transition = None;
+                CameraActions::action_set_pan_orbit_camera(&mut pan_orbit, &mut transform, location, focus);
+            } else {
+                let (end_rotation, end_radius) = camera_rotation_and_radius(location, focus);
+                control.transition = Some(CameraTransition {
+                    start_focus: pan_orbit.focus,
+                    start_rotation: transform.rotation,
+                    start_radius: pan_orbit.radius,
+                    end_focus: focus,
+                    end_rotation,
+                    end_radius,
+                    elapsed: 0.0,
+                });
+            }
+        }
+
+        if let Some(transition) = &mut control.transition {
+            transition.elapsed += time.delta_seconds();
+            let t = (transition.elapsed / control.transition_duration).clamp(0.0, 1.0);
+            let t = t * t * (3.0 - 2.0 * t);
+            let focus = transition.start_focus.lerp(transition.end_focus, t);
+            let rotation = transition.start_rotation.slerp(transition.end_rotation, t);
+            let radius = transition.start_radius + (transition.end_radius - transition.start_radius) * t;
+            CameraActions::action_set_pan_orbit_camera_rotation(&mut pan_orbit, &mut transform, focus, rotation, radius);
+            if t >= 1.0 { control.transition = None; }
+        }
+
+        control.current_location = TransformUtils::util_convert_bevy_y_up_vec3_to_z_up_vec3(transform.translation);
+        control.current_focus = TransformUtils::util_convert_bevy_y_up_vec3_to_z_up_vec3(pan_orbit.focus);
+    }
+    pub fn system_camera_control_panel(mut control: ResMut<BevyCameraControl>,
+                                       mut contexts: EguiContexts,
+                                       egui_engine: Res<OEguiEngineWrapper>,
+                                       window_query: Query<&Window, With<PrimaryWindow>>,
+                                       mut projection_query: Query<(&PanOrbitCamera, &mut Projection)>) {
+        OEguiWindow::new("Camera", true, true, false, true, false, true)
+            .show("camera_control_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                if let Some((pan_orbit, mut projection)) = projection_query.iter_mut().next() {
+                    let mut orthographic = matches!(*projection, Projection::Orthographic(_));
+                    if ui.checkbox(&mut orthographic, "orthographic (O)").changed() {
+                        CameraActions::action_set_orthographic(pan_orbit, &mut projection, orthographic);
+                    }
+                }
+                ui.horizontal_wrapped(|ui| {
+                    for view in CameraView::all() {
+                        if ui.button(view.label()).clicked() { control.set_view(view); }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("transition (s): ");
+                    ui.add(egui::DragValue::new(&mut control.transition_duration).speed(0.05).clamp_range(0.0..=5.0));
+                });
+
+                ui.separator();
+
+                ui.heading("Bookmarks");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut control.new_bookmark_name);
+                    if ui.button("Save").clicked() && !control.new_bookmark_name.is_empty() {
+                        let name = std::mem::take(&mut control.new_bookmark_name);
+                        control.save_current_as_bookmark(&name);
+                    }
+                });
+                let mut go_to = None;
+                let mut delete = None;
+                for bookmark in &control.bookmarks {
+                    ui.horizontal(|ui| {
+                        ui.label(&bookmark.name);
+                        if ui.button("Go").clicked() { go_to = Some(bookmark.name.clone()); }
+                        if ui.button("Delete").clicked() { delete = Some(bookmark.name.clone()); }
+                    });
+                }
+                if let Some(name) = go_to { control.restore_bookmark(&name).ok(); }
+                if let Some(name) = delete { control.remove_bookmark(&name); }
+            });
+    }
+    /// Toggles the orthographic projection on the `SHORTCUT_TOGGLE_ORTHOGRAPHIC` shortcut (O by default).
+    pub fn system_toggle_orthographic_shortcut(shortcuts: Res<ShortcutMap>,
+                                               mut query: Query<(&PanOrbitCamera, &mut Projection)>) {
+        if !shortcuts.just_triggered(SHORTCUT_TOGGLE_ORTHOGRAPHIC) { return; }
+        for (pan_orbit, mut projection) in query.iter_mut() {
+            let orthographic = matches!(*projection, Projection::Perspective(_));
+            CameraActions::action_set_orthographic(pan_orbit, &mut projection, orthographic);
+        }
+    }
+    pub fn system_sync_orthographic_projection(mut query: Query<(&PanOrbitCamera, &mut Projection)>) {
+        for (pan_orbit, mut projection) in query.iter_mut() {
+            let height = orthographic_height(pan_orbit.radius);
+            let up_to_date = match &*projection {
+                Projection::Orthographic(projection) => { matches!(projection.scaling_mode, ScalingMode::FixedVertical(h) if h == height) }
+                Projection::Perspective(_) => { true }
+            };
+            if up_to_date { continue; }
+            if let Projection::Orthographic(projection) = &mut *projection { projection.scaling_mode = ScalingMode::FixedVertical(height); }
+        }
     }
     pub fn system_pan_orbit_camera(
         mut ev_motion: EventReader<MouseMotion>,
@@ -98,8 +262,14 @@ impl CameraSystems {
                 any = true;
                 // make panning distance independent of resolution and FOV,
                 // let window = WindowUtils::util_get_primary_window_size(&windows);
-                if let Projection::Perspective(projection) = projection {
-                    pan *= Vec2::new(projection.fov * projection.aspect_ratio, projection.fov) / size;
+                match projection {
+                    Projection::Perspective(projection) => {
+                        pan *= Vec2::new(projection.fov * projection.aspect_ratio, projection.fov) / size;
+                    }
+                    Projection::Orthographic(projection) => {
+                        // the view is orthographic_height(radius) tall, and the translation below is scaled by the radius.
+                        pan *= Vec2::new(projection.area.width(), projection.area.height()) / size / pan_orbit.radius;
+                    }
                 }
                 // translate by local axes
                 let right = transform.rotation * Vec3::X * -pan.x;
@@ -136,4 +306,135 @@ impl Default for PanOrbitCamera {
             upside_down: false,
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraView {
+    /// Looking down the optima -x axis.
+    Front,
+    Back,
+    /// Looking down the optima -y axis, i.e., from the robot's left.
+    Left,
+    Right,
+    Top,
+    Iso
+}
+impl CameraView {
+    pub fn all() -> [CameraView; 6] {
+        [CameraView::Front, CameraView::Back, CameraView::Left, CameraView::Right, CameraView::Top, CameraView::Iso]
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            CameraView::Front => { "Front" }
+            CameraView::Back => { "Back" }
+            CameraView::Left => { "Left" }
+            CameraView::Right => { "Right" }
+            CameraView::Top => { "Top" }
+            CameraView::Iso => { "Iso" }
+        }
+    }
+    /// Unit direction (z up) from the focus to the camera.
+    pub fn direction(&self) -> Vec3 {
+        match self {
+            CameraView::Front => { Vec3::X }
+            CameraView::Back => { -Vec3::X }
+            CameraView::Left => { Vec3::Y }
+            CameraView::Right => { -Vec3::Y }
+            CameraView::Top => { Vec3::Z }
+            CameraView::Iso => { Vec3::ONE.normalize() }
+        }
+    }
+}
+
+/// A saved camera location and focus point, both z up.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub location: [f32; 3],
+    pub focus: [f32; 3]
+}
+
+struct CameraTransition {
+    start_focus: Vec3,
+    start_rotation: Quat,
+    start_radius: f32,
+    end_focus: Vec3,
+    end_rotation: Quat,
+    end_radius: f32,
+    elapsed: f32
+}
+
+/// Moves the pan orbit camera from code (e.g., to line up scripted screenshots) and holds named
+/// camera bookmarks.  All locations are z up.  Moves are animated over `transition_duration`
+/// seconds, or applied on the next frame if it is zero.  Also drives the "Camera" window.
+#[derive(Resource)]
+pub struct BevyCameraControl {
+    bookmarks: Vec<CameraBookmark>,
+    requested: Option<(Vec3, Vec3)>,
+    transition: Option<CameraTransition>,
+    current_location: Vec3,
+    current_focus: Vec3,
+    new_bookmark_name: String,
+    pub transition_duration: f32
+}
+impl BevyCameraControl {
+    pub fn new() -> Self {
+        Self {
+            bookmarks: vec![],
+            requested: None,
+            transition: None,
+            current_location: Vec3::ZERO,
+            current_focus: Vec3::ZERO,
+            new_bookmark_name: "".to_string(),
+            transition_duration: 0.6,
+        }
+    }
+    pub fn fly_to(&mut self, location: Vec3, focus: Vec3) {
+        self.requested = Some((location, focus));
+    }
+    /// Looks at the current focus point from the given direction, keeping the current distance.
+    pub fn set_view(&mut self, view: CameraView) {
+        let radius = (self.current_location - self.current_focus).length().max(0.05);
+        self.fly_to(self.current_focus + view.direction() * radius, self.current_focus);
+    }
+    /// Same as `set_view`, but looks at `focus` from `distance` away.
+    pub fn set_view_of(&mut self, view: CameraView, focus: Vec3, distance: f32) {
+        self.fly_to(focus + view.direction() * distance, focus);
+    }
+    /// Saves the camera as it was at the end of the last frame.  A bookmark with the same name is
+    /// replaced.
+    pub fn save_current_as_bookmark(&mut self, name: &str) {
+        self.add_bookmark(CameraBookmark { name: name.to_string(), location: self.current_location.to_array(), focus: self.current_focus.to_array() });
+    }
+    pub fn add_bookmark(&mut self, bookmark: CameraBookmark) {
+        match self.bookmarks.iter_mut().find(|x| x.name == bookmark.name) {
+            None => { self.bookmarks.push(bookmark); }
+            Some(existing) => { *existing = bookmark; }
+        }
+    }
+    pub fn restore_bookmark(&mut self, name: &str) -> Result<(), String> {
+        let bookmark = self.bookmarks.iter().find(|x| x.name == name).ok_or(format!("no camera bookmark named {}.", name))?;
+        let (location, focus) = (Vec3::from_array(bookmark.location), Vec3::from_array(bookmark.focus));
+        self.fly_to(location, focus);
+        Ok(())
+    }
+    pub fn remove_bookmark(&mut self, name: &str) {
+        self.bookmarks.retain(|x| x.name != name);
+    }
+    #[inline(always)]
+    pub fn bookmarks(&self) -> &Vec<CameraBookmark> {
+        &self.bookmarks
+    }
+    #[inline(always)]
+    pub fn is_transitioning(&self) -> bool {
+        self.requested.is_some() || self.transition.is_some()
+    }
+    #[inline(always)]
+    pub fn current_location(&self) -> Vec3 {
+        self.current_location
+    }
+    #[inline(always)]
+    pub fn current_focus(&self) -> Vec3 {
+        self.current_focus
+    }
+}
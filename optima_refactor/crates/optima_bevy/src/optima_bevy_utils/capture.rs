@@ -0,0 +1,198 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use crate::optima_bevy_utils::shortcuts::{ShortcutMap, SHORTCUT_SCREENSHOT};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureRecordingMode {
+    /// Frames are captured as fast as the viewer renders them, and playback runs on wall clock time.
+    RealTime,
+    /// Interpolator playback advances by exactly `1 / fps` per captured frame, so the recording
+    /// plays back at the right speed regardless of how fast the viewer renders.
+    FrameStepped
+}
+
+struct CaptureRecording {
+    dir: PathBuf,
+    fps: f64,
+    mode: CaptureRecordingMode,
+    encode_mp4: bool,
+    num_frames: usize
+}
+
+/// Saves png screenshots of the viewport, and records it as a numbered image sequence (optionally
+/// encoded to an mp4 with `ffmpeg` once recording stops).  Can be driven from the "Capture" window
+/// or from any system through the methods below.
+#[derive(Resource)]
+pub struct BevyViewportCapture {
+    output_dir: PathBuf,
+    screenshot_requests: Vec<PathBuf>,
+    recording: Option<CaptureRecording>,
+    stop_at_playback_end: bool,
+    frame_captured: bool,
+    fps: f64,
+    mode: CaptureRecordingMode,
+    encode_mp4: bool
+}
+impl BevyViewportCapture {
+    pub fn new<P: AsRef<Path>>(output_dir: P) -> Self {
+        Self {
+            output_dir: output_dir.as_ref().to_path_buf(),
+            screenshot_requests: vec![],
+            recording: None,
+            stop_at_playback_end: false,
+            frame_captured: false,
+            fps: 30.0,
+            mode: CaptureRecordingMode::FrameStepped,
+            encode_mp4: true,
+        }
+    }
+    /// Saves the next rendered frame as a png at `path`.
+    pub fn request_screenshot<P: AsRef<Path>>(&mut self, path: P) {
+        self.screenshot_requests.push(path.as_ref().to_path_buf());
+    }
+    /// Saves the next rendered frame as a timestamped png in the output directory.
+    pub fn request_screenshot_in_output_dir(&mut self) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_millis()).unwrap_or(0);
+        let path = self.output_dir.join(format!("screenshot_{}.png", timestamp));
+        self.request_screenshot(path);
+    }
+    /// Starts saving every frame to `dir` as `frame_000000.png`, `frame_000001.png`, etc.  If
+    /// `encode_mp4` is set, `dir/recording.mp4` is written when recording stops (this needs `ffmpeg`
+    /// on the path).
+    pub fn start_recording<P: AsRef<Path>>(&mut self, dir: P, fps: f64, mode: CaptureRecordingMode, encode_mp4: bool) {
+        if self.recording.is_some() { self.stop_recording(); }
+        let dir = dir.as_ref().to_path_buf();
+        if let Err(e) = std::fs::create_dir_all(&dir) { warn!("could not create recording directory {:?} ({}).", dir, e); return; }
+        self.recording = Some(CaptureRecording { dir, fps, mode, encode_mp4, num_frames: 0 });
+        self.frame_captured = false;
+    }
+    /// Like `start_recording`, but stops automatically once interpolator playback reaches its end.
+    pub fn start_recording_playback<P: AsRef<Path>>(&mut self, dir: P, fps: f64, mode: CaptureRecordingMode, encode_mp4: bool) {
+        self.start_recording(dir, fps, mode, encode_mp4);
+        self.stop_at_playback_end = self.recording.is_some();
+    }
+    pub fn stop_recording(&mut self) {
+        self.stop_at_playback_end = false;
+        let Some(recording) = self.recording.take() else { return; };
+        info!("recorded {} frames to {:?}.", recording.num_frames, recording.dir);
+        if recording.encode_mp4 && recording.num_frames > 0 {
+            std::thread::spawn(move || encode_mp4(recording.dir, recording.fps, recording.num_frames));
+        }
+    }
+    #[inline(always)]
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+    /// If a frame-stepped recording is running, returns how far playback should advance this frame:
+    /// `1 / fps` if the previous frame was captured, and zero if it still has to be.  Returns `None`
+    /// otherwise, in which case playback runs on wall clock time.
+    pub fn frame_stepped_playback_delta(&self) -> Option<f64> {
+        match &self.recording {
+            Some(recording) if recording.mode == CaptureRecordingMode::FrameStepped => {
+                Some(if self.frame_captured { 1.0 / recording.fps } else { 0.0 })
+            }
+            _ => { None }
+        }
+    }
+    /// Called by playback when it wraps around to the start.
+    pub fn notify_playback_end(&mut self) {
+        if self.stop_at_playback_end { self.stop_recording(); }
+    }
+}
+
+fn encode_mp4(dir: PathBuf, fps: f64, num_frames: usize) {
+    // screenshots are written asynchronously, so wait (a bit) for the last frames to land on disk.
+    let start = Instant::now();
+    while !dir.join(format!("frame_{:06}.png", num_frames - 1)).exists() && start.elapsed() < Duration::from_secs(10) {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    let output = dir.join("recording.mp4");
+    let res = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-framerate").arg(format!("{}", fps))
+        .arg("-i").arg(dir.join("frame_%06d.png"))
+        .arg("-c:v").arg("libx264")
+        .arg("-pix_fmt").arg("yuv420p")
+        .arg(&output)
+        .output();
+    match res {
+        Ok(out) if out.status.success() => { info!("saved recording to {:?}.", output); }
+        Ok(out) => { warn!("ffmpeg could not encode {:?}: {}", output, String::from_utf8_lossy(&out.stderr)); }
+        Err(e) => { warn!("could not run ffmpeg to encode {:?} ({}); the image sequence is still in {:?}.", output, e, dir); }
+    }
+}
+
+pub struct CaptureSystems;
+impl CaptureSystems {
+    /// Runs after the frame's state updates, so the frame captured is the one being rendered.
+    pub fn system_viewport_capture(mut capture: ResMut<BevyViewportCapture>,
+                                   mut screenshot_manager: ResMut<ScreenshotManager>,
+                                   window_query: Query<Entity, With<PrimaryWindow>>) {
+        let Ok(window) = window_query.get_single() else { return; };
+        let capture = &mut *capture;
+
+        if let Some(recording) = &mut capture.recording {
+            let path = recording.dir.join(format!("frame_{:06}.png", recording.num_frames));
+            let captured = screenshot_manager.save_screenshot_to_disk(window, path).is_ok();
+            if captured { recording.num_frames += 1; }
+            capture.frame_captured = captured;
+            return;
+        }
+
+        if let Some(path) = capture.screenshot_requests.first().cloned() {
+            if screenshot_manager.save_screenshot_to_disk(window, &path).is_ok() {
+                info!("saved screenshot to {:?}.", path);
+                capture.screenshot_requests.remove(0);
+            }
+        }
+    }
+    pub fn system_screenshot_shortcut(mut capture: ResMut<BevyViewportCapture>, shortcuts: Res<ShortcutMap>) {
+        if shortcuts.just_triggered(SHORTCUT_SCREENSHOT) { capture.request_screenshot_in_output_dir(); }
+    }
+    pub fn system_viewport_capture_panel(mut capture: ResMut<BevyViewportCapture>,
+                                         mut contexts: EguiContexts,
+                                         egui_engine: Res<OEguiEngineWrapper>,
+                                         window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Capture", true, true, false, false, false, true)
+            .show("capture_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                ui.label(format!("output: {:?}", capture.output_dir));
+                if ui.button("Screenshot").clicked() { capture.request_screenshot_in_output_dir(); }
+
+                ui.separator();
+
+                ui.add_enabled_ui(!capture.is_recording(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("fps: ");
+                        ui.add(egui::DragValue::new(&mut capture.fps).speed(1.0).clamp_range(1.0..=240.0));
+                    });
+                    ui.checkbox(&mut capture.encode_mp4, "encode mp4 (ffmpeg)");
+                    let mut frame_stepped = capture.mode == CaptureRecordingMode::FrameStepped;
+                    ui.checkbox(&mut frame_stepped, "frame-stepped playback");
+                    capture.mode = if frame_stepped { CaptureRecordingMode::FrameStepped } else { CaptureRecordingMode::RealTime };
+                });
+
+                match &capture.recording {
+                    None => {
+                        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_millis()).unwrap_or(0);
+                        let dir = capture.output_dir.join(format!("recording_{}", timestamp));
+                        let (fps, mode, encode_mp4) = (capture.fps, capture.mode, capture.encode_mp4);
+                        ui.horizontal(|ui| {
+                            if ui.button("⏺ Record").clicked() { capture.start_recording(&dir, fps, mode, encode_mp4); }
+                            if ui.button("⏺ Record playback").clicked() { capture.start_recording_playback(&dir, fps, mode, encode_mp4); }
+                        });
+                    }
+                    Some(recording) => {
+                        ui.label(format!("recording: {} frames", recording.num_frames));
+                        if ui.button("⏹ Stop").clicked() { capture.stop_recording(); }
+                    }
+                }
+            });
+    }
+}
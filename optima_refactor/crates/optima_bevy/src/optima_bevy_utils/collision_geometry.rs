@@ -0,0 +1,116 @@
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use optima_3d_spatial::optima_3d_pose::O3DPoseCategory;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use optima_linalg::{OLinalgCategory, OVec};
+use optima_proximity::shape_scene::ShapeSceneTrait;
+use optima_proximity::shapes::OParryShpTrait;
+use crate::optima_bevy_utils::robotics::{BevyORobot, LinkMeshID, RobotStateEngine};
+use crate::optima_bevy_utils::shape_scene::{ParryShapeSceneMeshLabel, ShapeSceneActions, ShapeSceneType, ShapeType};
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+#[derive(Clone, Debug)]
+pub struct CollisionGeometryRepDisplay {
+    pub shape_type: ShapeType,
+    pub visible: bool,
+    pub alpha: f32
+}
+
+/// Which of the robot's collision shapes (from its parry shape scene) are drawn, and how
+/// transparent each representation is.  The visual meshes can be hidden to show the collision
+/// shapes on their own.  Set from the "Geometry" window.
+#[derive(Resource)]
+pub struct BevyCollisionGeometryDisplay {
+    pub show_visual_meshes: bool,
+    pub reps: Vec<CollisionGeometryRepDisplay>
+}
+impl BevyCollisionGeometryDisplay {
+    pub fn new() -> Self {
+        let reps = ShapeType::all().iter().map(|shape_type| CollisionGeometryRepDisplay { shape_type: *shape_type, visible: false, alpha: 0.5 }).collect();
+        Self { show_visual_meshes: true, reps }
+    }
+    pub fn get_rep_mut(&mut self, shape_type: ShapeType) -> &mut CollisionGeometryRepDisplay {
+        self.reps.iter_mut().find(|x| x.shape_type == shape_type).expect("error")
+    }
+    fn get_rep(&self, shape_type: ShapeType) -> &CollisionGeometryRepDisplay {
+        self.reps.iter().find(|x| x.shape_type == shape_type).expect("error")
+    }
+}
+
+pub struct CollisionGeometrySystems;
+impl CollisionGeometrySystems {
+    pub fn system_spawn_robot_collision_geometry<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                                   mut commands: Commands,
+                                                                                                                   asset_server: Res<AssetServer>,
+                                                                                                                   mut meshes: ResMut<Assets<Mesh>>,
+                                                                                                                   mut materials: ResMut<Assets<StandardMaterial>>) {
+        let state = vec![T::zero(); robot.0.num_dofs()];
+        ShapeSceneActions::action_spawn_shape_scene(&*robot, state, ShapeSceneType::Robot, &mut commands, &asset_server, &mut meshes, &mut materials);
+    }
+    /// Moves the collision shapes along with the robot whenever its state changes.
+    pub fn system_update_robot_collision_geometry<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                                    robot_state_engine: Res<RobotStateEngine>,
+                                                                                                                    mut last_state: Local<Option<Vec<f64>>>,
+                                                                                                                    mut query: Query<(&ParryShapeSceneMeshLabel, &mut Transform)>) {
+        let Some(state) = robot_state_engine.get_robot_state(robot.1) else { return; };
+        if last_state.as_ref() == Some(state) { return; }
+        *last_state = Some(state.clone());
+
+        let state = OVec::ovec_to_other_ad_type::<T>(state);
+        let shapes = robot.get_shapes();
+        let poses = robot.get_shape_poses(&state);
+        for (label, mut transform) in query.iter_mut() {
+            if label.scene_type != ShapeSceneType::Robot { continue; }
+            let (Some(shape), Some(pose)) = (shapes.get(label.shape_idx), poses.get(label.shape_idx)) else { continue; };
+            if let Some(shape) = label.get_shape(shape) {
+                *transform = TransformUtils::util_convert_3d_pose_to_y_up_bevy_transform(shape.get_isometry3_cow(pose).as_ref());
+            }
+        }
+    }
+    /// Applies visibility and transparency settings whenever they change.
+    pub fn system_apply_collision_geometry_display(display: Res<BevyCollisionGeometryDisplay>,
+                                                   mut materials: ResMut<Assets<StandardMaterial>>,
+                                                   mut shape_query: Query<(&ParryShapeSceneMeshLabel, &Handle<StandardMaterial>, &mut Visibility), Without<LinkMeshID>>,
+                                                   mut link_query: Query<&mut Visibility, With<LinkMeshID>>) {
+        if !display.is_changed() { return; }
+
+        for (label, material, mut visibility) in shape_query.iter_mut() {
+            if label.scene_type != ShapeSceneType::Robot { continue; }
+            let rep = display.get_rep(label.shape_type);
+            *visibility = if rep.visible { Visibility::Inherited } else { Visibility::Hidden };
+            if let Some(material) = materials.get_mut(material) {
+                material.base_color.set_a(rep.alpha);
+                material.alpha_mode = if rep.alpha < 1.0 { AlphaMode::Blend } else { AlphaMode::Opaque };
+            }
+        }
+
+        let link_visibility = if display.show_visual_meshes { Visibility::Inherited } else { Visibility::Hidden };
+        link_query.iter_mut().for_each(|mut visibility| { *visibility = link_visibility; });
+    }
+    pub fn system_collision_geometry_panel(mut display: ResMut<BevyCollisionGeometryDisplay>,
+                                           mut contexts: EguiContexts,
+                                           egui_engine: Res<OEguiEngineWrapper>,
+                                           window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Geometry", true, true, false, false, false, true)
+            .show("collision_geometry_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                // only flag a change when something was edited, so materials are not rewritten every frame.
+                let mut show_visual_meshes = display.show_visual_meshes;
+                let mut reps = display.reps.clone();
+                let mut changed = ui.checkbox(&mut show_visual_meshes, "visual meshes").changed();
+                ui.separator();
+                ui.label("collision shapes");
+                for rep in reps.iter_mut() {
+                    ui.horizontal(|ui| {
+                        changed |= ui.checkbox(&mut rep.visible, rep.shape_type.label()).changed();
+                        changed |= ui.add(egui::Slider::new(&mut rep.alpha, 0.05..=1.0).text("alpha")).changed();
+                    });
+                }
+                if changed {
+                    display.show_visual_meshes = show_visual_meshes;
+                    display.reps = reps;
+                }
+            });
+    }
+}
@@ -0,0 +1,97 @@
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use parry_ad::query::Contact;
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+/// Closest point pair and contact normal of one pair of shapes, in bevy (y up) space.
+#[derive(Clone, Debug)]
+pub struct ContactVis {
+    pub point1: Vec3,
+    pub point2: Vec3,
+    /// outward normal of the first shape at `point1`.
+    pub normal1: Vec3,
+    /// negative if the shapes are penetrating.
+    pub signed_distance: f64,
+    pub label: String
+}
+impl ContactVis {
+    /// `contact` is expected in world space (z up), as returned by parry's contact queries.
+    pub fn from_contact<T: AD>(contact: &Contact<T>, label: String) -> Self {
+        let to_bevy = |x: T, y: T, z: T| TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(Vec3::new(x.to_constant() as f32, y.to_constant() as f32, z.to_constant() as f32));
+        Self {
+            point1: to_bevy(contact.point1.x, contact.point1.y, contact.point1.z),
+            point2: to_bevy(contact.point2.x, contact.point2.y, contact.point2.z),
+            normal1: to_bevy(contact.normal1.x, contact.normal1.y, contact.normal1.z),
+            signed_distance: contact.dist.to_constant(),
+            label,
+        }
+    }
+}
+
+/// Contacts drawn in the viewport by `ContactVisSystems`: each closest point pair is joined by a
+/// line (red if penetrating, orange otherwise), the contact normal is drawn as an arrow from the
+/// first point, and the pair's label and signed distance are drawn next to it.
+#[derive(Resource)]
+pub struct BevyContactVisualization {
+    contacts: Vec<ContactVis>,
+    pub enabled: bool,
+    pub show_labels: bool,
+    pub normal_length: f32
+}
+impl BevyContactVisualization {
+    pub fn new() -> Self {
+        Self { contacts: vec![], enabled: true, show_labels: true, normal_length: 0.05 }
+    }
+    pub fn set_contacts(&mut self, contacts: Vec<ContactVis>) {
+        self.contacts = contacts;
+    }
+    #[inline(always)]
+    pub fn contacts(&self) -> &Vec<ContactVis> {
+        &self.contacts
+    }
+}
+
+pub struct ContactVisSystems;
+impl ContactVisSystems {
+    pub fn system_draw_contacts(contact_vis: Res<BevyContactVisualization>, mut gizmos: Gizmos) {
+        if !contact_vis.enabled { return; }
+
+        contact_vis.contacts.iter().for_each(|contact| {
+            let color = if contact.signed_distance < 0.0 { Color::rgb(1.0, 0.1, 0.1) } else { Color::rgb(1.0, 0.55, 0.0) };
+            gizmos.line(contact.point1, contact.point2, color);
+            gizmos.sphere(contact.point1, Quat::IDENTITY, 0.005, color);
+            gizmos.sphere(contact.point2, Quat::IDENTITY, 0.005, color);
+
+            let tip = contact.point1 + contact.normal1 * contact_vis.normal_length;
+            gizmos.line(contact.point1, tip, Color::CYAN);
+            // arrow head: two short lines back from the tip, in a plane containing the normal.
+            let side = contact.normal1.any_orthonormal_vector() * contact_vis.normal_length * 0.2;
+            let back = contact.normal1 * contact_vis.normal_length * 0.3;
+            gizmos.line(tip, tip - back + side, Color::CYAN);
+            gizmos.line(tip, tip - back - side, Color::CYAN);
+        });
+    }
+    /// Labels are painted on egui's foreground layer at the projected midpoint of each point pair,
+    /// as seen from the camera rendering to the window (not offscreen sensor cameras).
+    pub fn system_draw_contact_labels(contact_vis: Res<BevyContactVisualization>,
+                                      mut contexts: EguiContexts,
+                                      camera_query: Query<(&Camera, &GlobalTransform)>,
+                                      window_query: Query<&Window, With<PrimaryWindow>>) {
+        if !contact_vis.enabled || !contact_vis.show_labels || contact_vis.contacts.is_empty() { return; }
+        if window_query.get_single().is_err() { return; }
+        let Some((camera, camera_transform)) = camera_query.iter().find(|(camera, _)| camera.is_active && matches!(camera.target, RenderTarget::Window(_))) else { return; };
+
+        let painter = contexts.ctx_mut().layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("contact_labels")));
+        contact_vis.contacts.iter().for_each(|contact| {
+            let midpoint = (contact.point1 + contact.point2) / 2.0;
+            if let Some(pos) = camera.world_to_viewport(camera_transform, midpoint) {
+                let text = format!("{}: {:.4}", contact.label, contact.signed_distance);
+                let color = if contact.signed_distance < 0.0 { egui::Color32::from_rgb(255, 80, 80) } else { egui::Color32::from_rgb(255, 170, 0) };
+                painter.text(egui::pos2(pos.x, pos.y), egui::Align2::LEFT_BOTTOM, text, egui::FontId::monospace(12.0), color);
+            }
+        });
+    }
+}
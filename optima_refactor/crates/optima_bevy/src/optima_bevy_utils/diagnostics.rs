@@ -0,0 +1,113 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use bevy_egui::{egui, EguiContexts};
+use optima_bevy_egui::OEguiEngineWrapper;
+use crate::optima_bevy_utils::shortcuts::{ShortcutMap, SHORTCUT_TOGGLE_PERFORMANCE_OVERLAY};
+
+pub const TIMING_FK: &str = "forward kinematics";
+pub const TIMING_PROXIMITY: &str = "proximity queries";
+pub const TIMING_EGUI: &str = "egui";
+
+/// weight of the newest frame in the smoothed timings.
+const SMOOTHING: f64 = 0.1;
+
+/// Per-frame timings of named parts of the viewer (forward kinematics, proximity queries, egui,
+/// ...), shown by the performance overlay.  Code is timed with `BevyFrameTimings::time`, which
+/// also wraps it in a tracing span, so the same parts show up in a trace (e.g., with bevy's
+/// `trace_chrome` feature).  Time from several calls with the same name in one frame adds up.
+#[derive(Resource)]
+pub struct BevyFrameTimings {
+    current_frame: Mutex<Vec<(String, Duration)>>,
+    /// in milliseconds, in the order the names were first timed.
+    averages: Vec<(String, f64)>,
+    /// off by default; toggled with F2.
+    pub show_overlay: bool
+}
+impl BevyFrameTimings {
+    pub fn new() -> Self {
+        Self { current_frame: Mutex::new(vec![]), averages: vec![], show_overlay: false }
+    }
+    /// Runs `f` and adds its duration to `name`'s time for this frame.  Just runs `f` if there is no
+    /// `BevyFrameTimings` resource, so systems can be timed whether or not the overlay was added.
+    pub fn time<R, F: FnOnce() -> R>(timings: &Option<Res<BevyFrameTimings>>, name: &str, f: F) -> R {
+        let Some(timings) = timings else { return f(); };
+        let _span = info_span!("optima_timing", name).entered();
+        let start = Instant::now();
+        let out = f();
+        timings.add(name, start.elapsed());
+        out
+    }
+    pub fn add(&self, name: &str, duration: Duration) {
+        let mut current_frame = self.current_frame.lock().unwrap();
+        match current_frame.iter_mut().find(|(x, _)| x == name) {
+            None => { current_frame.push((name.to_string(), duration)); }
+            Some((_, x)) => { *x += duration; }
+        }
+    }
+    /// Smoothed time of each named part, in milliseconds.
+    #[inline(always)]
+    pub fn averages(&self) -> &Vec<(String, f64)> {
+        &self.averages
+    }
+    fn end_frame(&mut self) {
+        let current_frame = std::mem::take(self.current_frame.get_mut().unwrap());
+        let ms = |name: &str| current_frame.iter().find(|(x, _)| x == name).map(|(_, x)| x.as_secs_f64() * 1000.0);
+
+        // parts that were not timed this frame decay towards zero.
+        for (name, average) in self.averages.iter_mut() {
+            *average += SMOOTHING * (ms(name).unwrap_or(0.0) - *average);
+        }
+        for (name, _) in &current_frame {
+            if !self.averages.iter().any(|(x, _)| x == name) { self.averages.push((name.clone(), ms(name).unwrap_or(0.0))); }
+        }
+    }
+}
+
+pub struct DiagnosticsSystems;
+impl DiagnosticsSystems {
+    /// Runs in `First`, so the previous frame is complete (including systems in `Last`, such as
+    /// the robot state updater).
+    pub fn system_end_frame_timings(mut timings: ResMut<BevyFrameTimings>, egui_engine: Option<Res<OEguiEngineWrapper>>) {
+        if let Some(egui_engine) = egui_engine {
+            let container_time = egui_engine.get_mutex_guard().take_container_time();
+            timings.add(TIMING_EGUI, container_time);
+        }
+        timings.end_frame();
+    }
+    pub fn system_toggle_performance_overlay_shortcut(shortcuts: Res<ShortcutMap>, mut timings: ResMut<BevyFrameTimings>) {
+        if shortcuts.just_triggered(SHORTCUT_TOGGLE_PERFORMANCE_OVERLAY) { timings.show_overlay = !timings.show_overlay; }
+    }
+    /// FPS and frame time in the top right corner, with the smoothed time of each part of the frame
+    /// in `BevyFrameTimings` and its share of the frame time.
+    pub fn system_performance_overlay(timings: Res<BevyFrameTimings>, diagnostics: Res<DiagnosticsStore>, mut contexts: EguiContexts) {
+        if !timings.show_overlay { return; }
+
+        let fps = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS).and_then(|x| x.smoothed());
+        let frame_time = diagnostics.get(FrameTimeDiagnosticsPlugin::FRAME_TIME).and_then(|x| x.smoothed());
+
+        egui::Area::new("performance_overlay")
+            .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+            .show(contexts.ctx_mut(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    match (fps, frame_time) {
+                        (Some(fps), Some(frame_time)) => { ui.monospace(format!("{:.0} fps  {:.2} ms", fps, frame_time)); }
+                        _ => { ui.monospace("measuring..."); }
+                    }
+                    ui.separator();
+                    egui::Grid::new("performance_overlay_grid").show(ui, |ui| {
+                        for (name, ms) in timings.averages() {
+                            let label = ui.monospace(name.as_str());
+                            if name == TIMING_EGUI { label.on_hover_text("includes the work done inside panels, e.g., the self-collision panel's proximity queries."); }
+                            ui.monospace(format!("{:.2} ms", ms));
+                            let fraction = frame_time.map(|x| (*ms / x).clamp(0.0, 1.0) as f32).unwrap_or(0.0);
+                            ui.add(egui::ProgressBar::new(fraction).desired_width(80.0));
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+    }
+}
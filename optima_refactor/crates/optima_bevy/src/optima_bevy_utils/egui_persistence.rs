@@ -0,0 +1,38 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use optima_bevy_egui::OEguiEngineWrapper;
+use optima_file::path::OPath;
+
+/// Where the egui state (window positions, open panels, widget values) is kept between sessions.
+#[derive(Resource)]
+pub struct BevyEguiStatePersistence {
+    path: OPath
+}
+impl BevyEguiStatePersistence {
+    pub fn new(path: OPath) -> Self {
+        Self { path }
+    }
+    #[inline(always)]
+    pub fn path(&self) -> &OPath {
+        &self.path
+    }
+}
+
+pub struct EguiPersistenceSystems;
+impl EguiPersistenceSystems {
+    /// Nothing is restored on the first run, i.e., before the file exists.
+    pub fn system_load_egui_state(persistence: Res<BevyEguiStatePersistence>, egui_engine: Res<OEguiEngineWrapper>) {
+        if !persistence.path.exists() { return; }
+        if let Err(e) = egui_engine.get_mutex_guard().load_state_from_ron(&persistence.path) {
+            warn!("could not restore egui state: {}", e);
+        }
+    }
+    pub fn system_save_egui_state_on_exit(persistence: Res<BevyEguiStatePersistence>, egui_engine: Res<OEguiEngineWrapper>, mut exit_events: EventReader<AppExit>) {
+        if exit_events.is_empty() { return; }
+        exit_events.clear();
+
+        if let Err(e) = egui_engine.get_mutex_guard().save_state_to_ron(&persistence.path) {
+            warn!("could not save egui state: {}", e);
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use bevy::asset::LoadState;
+use bevy::core_pipeline::Skybox;
+use bevy::pbr::EnvironmentMapLight;
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use bevy::render::render_resource::{AddressMode, Extent3d, FilterMode, SamplerDescriptor, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension};
+use bevy::render::texture::{ImageSampler, TextureFormatPixelInfo};
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+
+/// Paths are relative to bevy's assets folder.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SkyboxSource {
+    /// A ktx2 or dds cubemap (e.g., a prefiltered hdri), a single image with the six faces stacked
+    /// vertically in the order +x, -x, +y, -y, +z, -z (bevy's y up frame), or a 2:1 equirectangular
+    /// panorama, which is converted to a cubemap on load (with its center facing -z).
+    Cubemap(String),
+    /// Six images of the same size and format, in the same order as above.
+    Faces([String; 6])
+}
+
+/// Image based lighting from prefiltered ktx2 cubemaps (see bevy's `EnvironmentMapLight`), so
+/// reflections and ambient light match the skybox.  Only used together with a skybox.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnvironmentMapSource {
+    pub diffuse_map: String,
+    pub specular_map: String
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GroundPlaneMaterial {
+    /// rgba in [0, 1].
+    Color([f32; 4]),
+    Checkerboard { color_a: [f32; 4], color_b: [f32; 4], square_size: f32 },
+    /// Image in bevy's assets folder, repeated every `tile_size` meters.
+    Texture { path: String, tile_size: f32 }
+}
+
+/// A square ground plane centered at the origin, just below z = 0 so the grid stays visible.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GroundPlaneConfig {
+    /// side length in meters.
+    pub size: f32,
+    pub material: GroundPlaneMaterial,
+    pub perceptual_roughness: f32
+}
+
+/// Backdrop of the scene: background color, skybox, image based lighting, and ground plane.  Any
+/// field left as None keeps the viewer's default.  Set up with `optima_bevy_environment`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimaBevyEnvironment {
+    /// rgb in [0, 1]; replaces `OptimaViewerConfig::background_color`.  Hidden by a skybox.
+    pub background_color: Option<[f32; 3]>,
+    pub skybox: Option<SkyboxSource>,
+    pub environment_map: Option<EnvironmentMapSource>,
+    pub ground_plane: Option<GroundPlaneConfig>
+}
+
+/// Ready made environments, e.g., for publication figures.  The viewer grid can be turned off
+/// through `OptimaViewerConfig::grid` for a cleaner look.
+#[derive(Clone, Debug)]
+pub enum EnvPreset {
+    /// White background with a light gray checkerboard floor.
+    Studio,
+    /// Dark background with a dark checkerboard floor.
+    Dark,
+    Custom(OptimaBevyEnvironment)
+}
+impl EnvPreset {
+    pub fn to_environment(&self) -> OptimaBevyEnvironment {
+        match self {
+            EnvPreset::Studio => {
+                OptimaBevyEnvironment {
+                    background_color: Some([1.0, 1.0, 1.0]),
+                    ground_plane: Some(GroundPlaneConfig { size: 20.0, material: GroundPlaneMaterial::Checkerboard { color_a: [0.92, 0.92, 0.92, 1.0], color_b: [0.82, 0.82, 0.82, 1.0], square_size: 0.5 }, perceptual_roughness: 0.9 }),
+                    ..Default::default()
+                }
+            }
+            EnvPreset::Dark => {
+                OptimaBevyEnvironment {
+                    background_color: Some([0.08, 0.08, 0.1]),
+                    ground_plane: Some(GroundPlaneConfig { size: 20.0, material: GroundPlaneMaterial::Checkerboard { color_a: [0.2, 0.2, 0.22, 1.0], color_b: [0.14, 0.14, 0.16, 1.0], square_size: 0.5 }, perceptual_roughness: 0.6 }),
+                    ..Default::default()
+                }
+            }
+            EnvPreset::Custom(environment) => { environment.clone() }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct GroundPlane;
+
+#[derive(Resource)]
+pub struct BevyEnvironment {
+    environment: OptimaBevyEnvironment,
+    pub show_skybox: bool,
+    pub show_ground_plane: bool,
+    /// the ground plane is respawned when set, e.g., after its size was changed.
+    pub ground_plane_changed: bool,
+    skybox_faces: Vec<Handle<Image>>,
+    skybox_image: Option<Handle<Image>>,
+    skybox_ready: bool,
+    ground_texture: Option<Handle<Image>>,
+    ground_texture_ready: bool
+}
+impl BevyEnvironment {
+    pub fn new(environment: OptimaBevyEnvironment) -> Self {
+        Self {
+            environment,
+            show_skybox: true,
+            show_ground_plane: true,
+            ground_plane_changed: false,
+            skybox_faces: vec![],
+            skybox_image: None,
+            skybox_ready: false,
+            ground_texture: None,
+            ground_texture_ready: false,
+        }
+    }
+    #[inline(always)]
+    pub fn environment(&self) -> &OptimaBevyEnvironment {
+        &self.environment
+    }
+    pub fn ground_plane_mut(&mut self) -> Option<&mut GroundPlaneConfig> {
+        self.ground_plane_changed = true;
+        self.environment.ground_plane.as_mut()
+    }
+}
+
+pub struct EnvironmentActions;
+impl EnvironmentActions {
+    /// A 2 x 2 image that repeats, so one copy covers two squares in each direction.
+    pub fn action_checkerboard_image(color_a: [f32; 4], color_b: [f32; 4]) -> Image {
+        let a = Color::rgba(color_a[0], color_a[1], color_a[2], color_a[3]).as_rgba_u8();
+        let b = Color::rgba(color_b[0], color_b[1], color_b[2], color_b[3]).as_rgba_u8();
+        let data = [a, b, b, a].concat();
+        let mut image = Image::new(Extent3d { width: 2, height: 2, depth_or_array_layers: 1 }, TextureDimension::D2, data, TextureFormat::Rgba8UnormSrgb);
+        image.sampler_descriptor = repeating_sampler(FilterMode::Nearest);
+        image
+    }
+    pub fn action_spawn_ground_plane(commands: &mut Commands,
+                                     meshes: &mut ResMut<Assets<Mesh>>,
+                                     materials: &mut ResMut<Assets<StandardMaterial>>,
+                                     images: &mut ResMut<Assets<Image>>,
+                                     asset_server: &Res<AssetServer>,
+                                     config: &GroundPlaneConfig) -> (Entity, Option<Handle<Image>>) {
+        let mut mesh = Mesh::from(shape::Plane { size: config.size, subdivisions: 0 });
+        let mut texture = None;
+        let material = match &config.material {
+            GroundPlaneMaterial::Color(color) => {
+                StandardMaterial { base_color: Color::rgba(color[0], color[1], color[2], color[3]), perceptual_roughness: config.perceptual_roughness, ..Default::default() }
+            }
+            GroundPlaneMaterial::Checkerboard { color_a, color_b, square_size } => {
+                scale_uvs(&mut mesh, config.size / (2.0 * square_size.max(0.001)));
+                let image = images.add(Self::action_checkerboard_image(*color_a, *color_b));
+                StandardMaterial { base_color_texture: Some(image), perceptual_roughness: config.perceptual_roughness, ..Default::default() }
+            }
+            GroundPlaneMaterial::Texture { path, tile_size } => {
+                scale_uvs(&mut mesh, config.size / tile_size.max(0.001));
+                let image: Handle<Image> = asset_server.load(path.as_str());
+                texture = Some(image.clone());
+                StandardMaterial { base_color_texture: Some(image), perceptual_roughness: config.perceptual_roughness, ..Default::default() }
+            }
+        };
+
+        let entity = commands.spawn((PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(material),
+            transform: Transform::from_xyz(0.0, -0.001, 0.0),
+            ..Default::default()
+        }, GroundPlane)).id();
+
+        (entity, texture)
+    }
+}
+
+/// Samples an equirectangular panorama (nearest neighbor, so any uncompressed format works) into six
+/// square faces of side `height / 2`, stacked vertically in the order +x, -x, +y, -y, +z, -z.
+fn equirect_to_stacked_cubemap(data: &[u8], width: usize, height: usize, pixel_size: usize) -> Vec<u8> {
+    let n = height / 2;
+    let mut out = Vec::with_capacity(6 * n * n * pixel_size);
+    for face in 0..6 {
+        for row in 0..n {
+            for col in 0..n {
+                // face coordinates in [-1, 1], right and down.
+                let s = 2.0 * (col as f64 + 0.5) / n as f64 - 1.0;
+                let t = 2.0 * (row as f64 + 0.5) / n as f64 - 1.0;
+                let (x, y, z) = match face {
+                    0 => { (1.0, -t, -s) }
+                    1 => { (-1.0, -t, s) }
+                    2 => { (s, 1.0, t) }
+                    3 => { (s, -1.0, -t) }
+                    4 => { (s, -t, 1.0) }
+                    _ => { (-s, -t, -1.0) }
+                };
+                let u = 0.5 + f64::atan2(x, -z) / (2.0 * std::f64::consts::PI);
+                let v = (y / (x * x + y * y + z * z).sqrt()).acos() / std::f64::consts::PI;
+                let px = ((u * width as f64) as usize).min(width - 1);
+                let py = ((v * height as f64) as usize).min(height - 1);
+                let idx = (py * width + px) * pixel_size;
+                out.extend_from_slice(&data[idx..idx + pixel_size]);
+            }
+        }
+    }
+    out
+}
+
+fn repeating_sampler(mag_filter: FilterMode) -> ImageSampler {
+    ImageSampler::Descriptor(SamplerDescriptor {
+        address_mode_u: AddressMode::Repeat,
+        address_mode_v: AddressMode::Repeat,
+        mag_filter,
+        min_filter: FilterMode::Linear,
+        ..Default::default()
+    })
+}
+
+fn scale_uvs(mesh: &mut Mesh, factor: f32) {
+    if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+        uvs.iter_mut().for_each(|uv| { uv[0] *= factor; uv[1] *= factor; });
+    }
+}
+
+pub struct EnvironmentSystems;
+impl EnvironmentSystems {
+    pub fn system_setup_environment(mut commands: Commands,
+                                    mut environment: ResMut<BevyEnvironment>,
+                                    mut clear_color: ResMut<ClearColor>,
+                                    asset_server: Res<AssetServer>,
+                                    mut meshes: ResMut<Assets<Mesh>>,
+                                    mut materials: ResMut<Assets<StandardMaterial>>,
+                                    mut images: ResMut<Assets<Image>>) {
+        if let Some(c) = environment.environment.background_color { clear_color.0 = Color::rgb(c[0], c[1], c[2]); }
+
+        match environment.environment.skybox.clone() {
+            Some(SkyboxSource::Cubemap(path)) => { environment.skybox_image = Some(asset_server.load(path.as_str())); }
+            Some(SkyboxSource::Faces(paths)) => { environment.skybox_faces = paths.iter().map(|x| asset_server.load(x.as_str())).collect(); }
+            None => { }
+        }
+
+        if let Some(config) = environment.environment.ground_plane.clone() {
+            let (_, texture) = EnvironmentActions::action_spawn_ground_plane(&mut commands, &mut meshes, &mut materials, &mut images, &asset_server, &config);
+            environment.ground_texture = texture;
+        }
+    }
+    /// Turns the loaded skybox image(s) into a cubemap, and keeps it (and the environment map) on
+    /// every 3d camera, including ones spawned later.
+    pub fn system_update_skybox(mut commands: Commands,
+                                mut environment: ResMut<BevyEnvironment>,
+                                asset_server: Res<AssetServer>,
+                                mut images: ResMut<Assets<Image>>,
+                                cameras: Query<(Entity, Option<&Skybox>), With<Camera3d>>) {
+        let environment = &mut *environment;
+        if environment.environment.skybox.is_none() { return; }
+
+        if !environment.skybox_ready {
+            if !environment.skybox_faces.is_empty() && environment.skybox_image.is_none() {
+                if environment.skybox_faces.iter().any(|x| asset_server.get_load_state(x) == LoadState::Failed) {
+                    warn!("could not load the skybox faces.");
+                    environment.environment.skybox = None;
+                    return;
+                }
+                if !environment.skybox_faces.iter().all(|x| asset_server.get_load_state(x) == LoadState::Loaded) { return; }
+
+                let faces: Vec<&Image> = environment.skybox_faces.iter().filter_map(|x| images.get(x)).collect();
+                if faces.len() != 6 { return; }
+                let (size, format) = (faces[0].texture_descriptor.size, faces[0].texture_descriptor.format);
+                if faces.iter().any(|x| x.texture_descriptor.size != size || x.texture_descriptor.format != format) {
+                    warn!("skybox faces have to have the same size and format.");
+                    environment.environment.skybox = None;
+                    return;
+                }
+                let data = faces.iter().map(|x| x.data.clone()).collect::<Vec<Vec<u8>>>().concat();
+                let stacked = Image::new(Extent3d { width: size.width, height: 6 * size.height, depth_or_array_layers: 1 }, TextureDimension::D2, data, format);
+                environment.skybox_image = Some(images.add(stacked));
+            }
+
+            let Some(handle) = environment.skybox_image.clone() else { return; };
+            let Some(image) = images.get_mut(&handle) else {
+                if asset_server.get_load_state(&handle) == LoadState::Failed {
+                    warn!("could not load the skybox.");
+                    environment.environment.skybox = None;
+                }
+                return;
+            };
+            // ktx2 and dds cubemaps are already arrays.
+            if image.texture_descriptor.array_layer_count() == 1 {
+                let Extent3d { width, height, .. } = image.texture_descriptor.size;
+                if width > 0 && height == 6 * width {
+                    image.reinterpret_stacked_2d_as_array(6);
+                } else if height > 1 && width == 2 * height && !image.texture_descriptor.format.is_compressed() {
+                    let data = equirect_to_stacked_cubemap(&image.data, width as usize, height as usize, image.texture_descriptor.format.pixel_size());
+                    let mut cubemap = Image::new(Extent3d { width: height / 2, height: 6 * (height / 2), depth_or_array_layers: 1 }, TextureDimension::D2, data, image.texture_descriptor.format);
+                    cubemap.reinterpret_stacked_2d_as_array(6);
+                    *image = cubemap;
+                } else {
+                    warn!("the skybox image is {} x {}, which is neither six stacked square faces (1:6) nor an equirectangular panorama (2:1).", width, height);
+                    environment.environment.skybox = None;
+                    return;
+                }
+            }
+            image.texture_view_descriptor = Some(TextureViewDescriptor { dimension: Some(TextureViewDimension::Cube), ..Default::default() });
+            environment.skybox_ready = true;
+        }
+
+        let Some(skybox_image) = environment.skybox_image.clone() else { return; };
+        for (entity, skybox) in cameras.iter() {
+            match (environment.show_skybox, skybox.is_some()) {
+                (true, false) => {
+                    commands.entity(entity).insert(Skybox(skybox_image.clone()));
+                    if let Some(environment_map) = &environment.environment.environment_map {
+                        commands.entity(entity).insert(EnvironmentMapLight { diffuse_map: asset_server.load(environment_map.diffuse_map.as_str()), specular_map: asset_server.load(environment_map.specular_map.as_str()) });
+                    }
+                }
+                (false, true) => { commands.entity(entity).remove::<(Skybox, EnvironmentMapLight)>(); }
+                _ => { }
+            }
+        }
+    }
+    pub fn system_update_ground_plane(mut commands: Commands,
+                                      mut environment: ResMut<BevyEnvironment>,
+                                      asset_server: Res<AssetServer>,
+                                      mut meshes: ResMut<Assets<Mesh>>,
+                                      mut materials: ResMut<Assets<StandardMaterial>>,
+                                      mut images: ResMut<Assets<Image>>,
+                                      mut query: Query<(Entity, &mut Visibility), With<GroundPlane>>) {
+        let environment = &mut *environment;
+
+        // loaded textures use the default (clamping) sampler, so they would not tile.
+        if let (Some(texture), false) = (&environment.ground_texture, environment.ground_texture_ready) {
+            if let Some(image) = images.get_mut(texture) {
+                image.sampler_descriptor = repeating_sampler(FilterMode::Linear);
+                environment.ground_texture_ready = true;
+            }
+        }
+
+        if environment.ground_plane_changed {
+            environment.ground_plane_changed = false;
+            query.iter().for_each(|(entity, _)| commands.entity(entity).despawn_recursive());
+            if let Some(config) = environment.environment.ground_plane.clone() {
+                let (entity, texture) = EnvironmentActions::action_spawn_ground_plane(&mut commands, &mut meshes, &mut materials, &mut images, &asset_server, &config);
+                if !environment.show_ground_plane { commands.entity(entity).insert(Visibility::Hidden); }
+                environment.ground_texture_ready = texture.is_some() && environment.ground_texture == texture;
+                environment.ground_texture = texture;
+            }
+            return;
+        }
+
+        let visibility = if environment.show_ground_plane { Visibility::Inherited } else { Visibility::Hidden };
+        query.iter_mut().for_each(|(_, mut x)| { if *x != visibility { *x = visibility; } });
+    }
+    pub fn system_environment_panel(mut environment: ResMut<BevyEnvironment>,
+                                    mut contexts: EguiContexts,
+                                    egui_engine: Res<OEguiEngineWrapper>,
+                                    window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Environment", true, true, false, false, false, true)
+            .show("environment_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                if environment.environment.skybox.is_some() { ui.checkbox(&mut environment.show_skybox, "Skybox"); }
+
+                if environment.environment.ground_plane.is_none() {
+                    ui.label("no ground plane.");
+                    return;
+                }
+                ui.checkbox(&mut environment.show_ground_plane, "Ground plane");
+
+                let mut config = environment.environment.ground_plane.clone().expect("error");
+                ui.horizontal(|ui| {
+                    ui.label("size");
+                    ui.add(egui::DragValue::new(&mut config.size).speed(0.1).clamp_range(0.1..=1000.0).suffix(" m"));
+                });
+                match &mut config.material {
+                    GroundPlaneMaterial::Color(_) => { }
+                    GroundPlaneMaterial::Checkerboard { square_size, .. } => {
+                        ui.horizontal(|ui| {
+                            ui.label("square size");
+                            ui.add(egui::DragValue::new(square_size).speed(0.01).clamp_range(0.01..=100.0).suffix(" m"));
+                        });
+                    }
+                    GroundPlaneMaterial::Texture { tile_size, .. } => {
+                        ui.horizontal(|ui| {
+                            ui.label("tile size");
+                            ui.add(egui::DragValue::new(tile_size).speed(0.01).clamp_range(0.01..=100.0).suffix(" m"));
+                        });
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.label("roughness");
+                    ui.add(egui::Slider::new(&mut config.perceptual_roughness, 0.089..=1.0));
+                });
+
+                if environment.environment.ground_plane.as_ref() != Some(&config) {
+                    if let Some(x) = environment.ground_plane_mut() { *x = config; }
+                }
+            });
+    }
+}
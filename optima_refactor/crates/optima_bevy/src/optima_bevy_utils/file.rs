@@ -1,19 +1,17 @@
-use std::path::PathBuf;
 use optima_file::path::OStemCellPath;
 
-// todo: needs to be fixed.  Only works when called from main outside of sub-crates
+/// Bevy's asset server joins the path onto its own asset folder, and joining an absolute path
+/// yields that path unchanged, so the absolute physical path works wherever the optima asset folder
+/// is configured to be (see `OPath::new_asset_physical_path_from_json_file`).
+#[cfg(not(target_arch = "wasm32"))]
 pub fn get_asset_path_str_from_ostemcellpath(p: &OStemCellPath) -> String {
-    let mut path_buf_back_to_optima_assets = PathBuf::new();
-    path_buf_back_to_optima_assets.push("../");
-    path_buf_back_to_optima_assets.push("../");
-    // path_buf_back_to_optima_assets.push("../");
-    // path_buf_back_to_optima_assets.push("../");
-    let string_components = p.split_path_into_string_components_back_to_given_dir("optima_toolbox");
+    p.as_physical_path_unchecked().to_string()
+}
 
-    let mut path_buf_from_optima_toolbox = PathBuf::new();
-    for c in &string_components { path_buf_from_optima_toolbox.push(c); }
-
-    let combined_path_buf = path_buf_back_to_optima_assets.join(path_buf_from_optima_toolbox);
-
-    return combined_path_buf.to_str().expect("error").to_string();
-}
\ No newline at end of file
+/// On wasm32, the asset server fetches paths relative to `OptimaBevyWebConfig::asset_root`, which
+/// is expected to point to a served copy of the optima asset folder.
+#[cfg(target_arch = "wasm32")]
+pub fn get_asset_path_str_from_ostemcellpath(p: &OStemCellPath) -> String {
+    let string_components = p.split_path_into_string_components_back_to_assets_dir();
+    string_components.join("/")
+}
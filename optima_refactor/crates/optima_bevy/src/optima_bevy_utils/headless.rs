@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::mpsc::Receiver;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::extract_resource::ExtractResource;
+use bevy::render::render_graph::{Node, NodeRunError, RenderGraphContext};
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use crate::optima_bevy_utils::readback::ImageReadbackTarget;
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+/// Marks the camera that renders into the headless render target.
+#[derive(Component)]
+pub struct HeadlessCamera;
+
+/// Frames rendered by a headless app (see `optima_bevy_headless`).  Rendered frames are copied back
+/// from the gpu asynchronously (a frame or two late, skipping frames rendered while a copy is still
+/// in flight), so a recent one is always available through `latest_frame_rgba8`, and screenshots
+/// can be requested from any system.
+///
+/// Frames are tagged with the (main world) frame they were rendered on.  A screenshot requested on
+/// some frame is saved from the first frame rendered on or after it, so state changes made before
+/// the request in the same frame are always included.
+#[derive(Resource)]
+pub struct BevyHeadlessRenderer {
+    width: u32,
+    height: u32,
+    image: Handle<Image>,
+    frame: u64,
+    frames: Mutex<Receiver<(u64, Vec<u8>)>>,
+    latest_frame: Option<(u64, Vec<u8>)>,
+    screenshot_requests: Vec<(u64, PathBuf)>
+}
+impl BevyHeadlessRenderer {
+    #[inline(always)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    #[inline(always)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    #[inline(always)]
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+    /// Saves the next rendered frame to `path`.  The image format is inferred from the extension
+    /// (e.g., png or jpg).
+    pub fn request_screenshot<P: AsRef<Path>>(&mut self, path: P) {
+        self.screenshot_requests.push((self.frame, path.as_ref().to_path_buf()));
+    }
+    #[inline(always)]
+    pub fn num_pending_screenshots(&self) -> usize {
+        self.screenshot_requests.len()
+    }
+    /// The most recently rendered frame as tightly packed, row-major rgba8 pixels.
+    pub fn latest_frame_rgba8(&self) -> Option<&Vec<u8>> {
+        self.latest_frame.as_ref().map(|x| &x.1)
+    }
+}
+
+/// Render world side of the headless renderer: the buffer each frame is copied into, and the
+/// channel it is sent back through.
+#[derive(Resource, Clone, ExtractResource)]
+pub struct HeadlessRenderTarget(ImageReadbackTarget);
+
+pub struct HeadlessActions;
+impl HeadlessActions {
+    /// Creates the image that is rendered into, the buffer it is read back through, and the two
+    /// resources above.  Must be called after the render plugin is added.
+    pub fn action_init_headless_renderer(world: &mut World, width: u32, height: u32) -> (BevyHeadlessRenderer, HeadlessRenderTarget) {
+        let (target, frames_rx) = ImageReadbackTarget::new(world, width, height, "optima_headless_buffer");
+        let renderer = BevyHeadlessRenderer {
+            width,
+            height,
+            image: target.image().clone(),
+            frame: 0,
+            frames: Mutex::new(frames_rx),
+            latest_frame: None,
+            screenshot_requests: vec![],
+        };
+
+        (renderer, HeadlessRenderTarget(target))
+    }
+}
+
+pub struct HeadlessSystems;
+impl HeadlessSystems {
+    /// Spawns a camera that renders into the headless image, placed like the default pan orbit
+    /// camera.  Move it by querying for `HeadlessCamera`.
+    pub fn system_spawn_headless_camera(mut commands: Commands, renderer: Res<BevyHeadlessRenderer>) {
+        let translation = TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(Vec3::new(3.0, 0.0, 1.5));
+        let focus = TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(Vec3::new(0.0, 0.0, 0.5));
+
+        commands.spawn((Camera3dBundle {
+            camera: Camera { target: RenderTarget::Image(renderer.image.clone()), ..Default::default() },
+            transform: Transform::from_translation(translation).looking_at(focus, Vec3::Y),
+            ..Default::default()
+        }, HeadlessCamera));
+    }
+    pub fn system_advance_headless_frame(mut renderer: ResMut<BevyHeadlessRenderer>, mut target: ResMut<HeadlessRenderTarget>) {
+        renderer.frame += 1;
+        target.0.frame = renderer.frame;
+    }
+    pub fn system_receive_headless_frames(mut renderer: ResMut<BevyHeadlessRenderer>) {
+        let latest_frame = {
+            let frames = renderer.frames.lock().unwrap_or_else(|e| e.into_inner());
+            let mut out = None;
+            while let Ok(frame) = frames.try_recv() { out = Some(frame); }
+            out
+        };
+        let Some((frame, bytes)) = latest_frame else { return; };
+
+        let (width, height) = (renderer.width, renderer.height);
+        let mut pending = vec![];
+        for (request_frame, path) in renderer.screenshot_requests.drain(..) {
+            if request_frame > frame { pending.push((request_frame, path)); continue; }
+            match image::RgbaImage::from_raw(width, height, bytes.clone()) {
+                None => { warn!("headless frame does not match the render target size."); }
+                Some(img) => {
+                    match img.save(&path) {
+                        Ok(_) => { info!("saved screenshot to {:?}.", path); }
+                        Err(e) => { warn!("could not save screenshot to {:?} ({}).", path, e); }
+                    }
+                }
+            }
+        }
+        renderer.screenshot_requests = pending;
+        renderer.latest_frame = Some((frame, bytes));
+    }
+    /// Runs in the render world after rendering; see `ImageReadbackTarget::read_back`.
+    pub fn system_read_back_headless_frame(target: Option<Res<HeadlessRenderTarget>>, render_device: Res<RenderDevice>) {
+        let Some(target) = target else { return; };
+        target.0.read_back(&render_device);
+    }
+}
+
+/// Render graph node that copies the headless image into the read back buffer once all cameras
+/// have rendered.
+#[derive(Default)]
+pub struct HeadlessCopyNode;
+impl HeadlessCopyNode {
+    pub const NAME: &'static str = "optima_headless_copy";
+}
+impl Node for HeadlessCopyNode {
+    fn run(&self, _graph: &mut RenderGraphContext, render_context: &mut RenderContext, world: &World) -> Result<(), NodeRunError> {
+        let Some(target) = world.get_resource::<HeadlessRenderTarget>() else { return Ok(()) };
+        target.0.copy_image_to_buffer(render_context, world);
+
+        Ok(())
+    }
+}
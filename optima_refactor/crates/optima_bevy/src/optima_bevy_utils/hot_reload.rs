@@ -0,0 +1,214 @@
+use std::fs;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use ad_trait::AD;
+use bevy::prelude::*;
+use optima_3d_spatial::optima_3d_pose::O3DPoseCategory;
+use optima_bevy_egui::OEguiEngineWrapper;
+use optima_file::path::{OAssetLocation, OPath, OStemCellPath};
+use optima_file::watch::OFileWatcher;
+use optima_linalg::OLinalgCategory;
+use optima_robotics::robot::ORobot;
+use crate::optima_bevy_utils::camera::BevyCameraControl;
+use crate::optima_bevy_utils::mesh::BevyLinkMeshCache;
+use crate::optima_bevy_utils::robotics::{BevyORobot, BevyORobotInstances, LinkMeshID, RoboticsActions, RobotStateEngine};
+use crate::optima_bevy_utils::scene_file::{OptimaViewerScene, SceneFileActions};
+use crate::optima_bevy_utils::shape_scene::BevyEnvironmentObjects;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RobotHotReloadSource {
+    /// Reloads with `ORobot::from_urdf` when the urdf or any of its original meshes change.
+    Urdf,
+    /// Reloads with `ORobot::load_from_saved_robot` when the saved robot file changes.
+    SavedRobot
+}
+
+/// Watches the source files of the robot in `BevyORobot` and swaps in a freshly loaded robot when
+/// they change, so edits to a model show up in a running viewer.  If the edited files cannot be
+/// loaded (e.g., a half-written urdf), the current robot is kept and a warning is logged.
+#[derive(Resource)]
+pub struct BevyRobotHotReloader {
+    watcher: OFileWatcher,
+    source: RobotHotReloadSource,
+    robot_name: String
+}
+impl BevyRobotHotReloader {
+    pub fn new(robot_name: &str, source: RobotHotReloadSource) -> Result<Self, String> {
+        let mut watcher = OFileWatcher::new(Duration::from_millis(300))?;
+        match source {
+            RobotHotReloadSource::Urdf => {
+                let mut p = OStemCellPath::new_asset_path();
+                p.append_file_location(&OAssetLocation::UrdfRobot { robot_name });
+                watcher.watch(p.as_physical_path()?, false)?;
+                // downloaded robots keep their meshes in subdirectories (see `download_robot`).
+                let mut p = OStemCellPath::new_asset_path();
+                p.append_file_location(&OAssetLocation::ChainOriginalMeshes { robot_name });
+                if p.exists() { watcher.watch(p.as_physical_path()?, true)?; }
+            }
+            RobotHotReloadSource::SavedRobot => {
+                let mut p = OStemCellPath::new_asset_path();
+                p.append_file_location(&OAssetLocation::SavedRobots);
+                watcher.watch(p.as_physical_path()?, false)?;
+            }
+        }
+
+        Ok(Self { watcher, source, robot_name: robot_name.to_string() })
+    }
+    /// Filters out changes that do not affect the robot (e.g., other saved robots, or files that
+    /// loading the robot writes itself).  Also removes the files derived from changed meshes (stl
+    /// conversions, convex hulls, and convex decompositions) so that they are regenerated on reload.
+    fn relevant_changes(&mut self) -> bool {
+        let changes = self.watcher.poll_changes();
+        let robot_name = self.robot_name.as_str();
+        match self.source {
+            RobotHotReloadSource::Urdf => {
+                let mut original_meshes_dir = OStemCellPath::new_asset_path();
+                original_meshes_dir.append_file_location(&OAssetLocation::ChainOriginalMeshes { robot_name });
+                let original_meshes_dir = PathBuf::from(original_meshes_dir.as_physical_path_unchecked().to_string());
+
+                let mut out = false;
+                for change in &changes {
+                    if change.starts_with(&original_meshes_dir) {
+                        if let Some(stem) = change.file_stem().and_then(|x| x.to_str()) { remove_derived_mesh_files(robot_name, stem); }
+                        out = true;
+                    } else if change.extension().map(|x| x == "urdf").unwrap_or(false) {
+                        out = true;
+                    }
+                }
+                out
+            }
+            RobotHotReloadSource::SavedRobot => {
+                changes.iter().any(|x| x.file_name().map(|x| x == robot_name).unwrap_or(false))
+            }
+        }
+    }
+}
+
+/// Removes everything `ORobot` derives from the original mesh with the given file stem.  Derived
+/// files are named after the stem, and their existence is what marks them as up to date, so they
+/// have to go for the new mesh to be picked up.
+fn remove_derived_mesh_files(robot_name: &str, stem: &str) {
+    let physical = |location: &OAssetLocation| {
+        let mut p = OStemCellPath::new_asset_path();
+        p.append_file_location(location);
+        p.as_physical_path().ok().map(|x| PathBuf::from(x.to_string()))
+    };
+    let remove = |path: &Path| {
+        let res = if path.is_dir() { fs::remove_dir_all(path) } else if path.exists() { fs::remove_file(path) } else { Ok(()) };
+        if let Err(e) = res { warn!("could not remove stale mesh file {:?} ({}).", path, e); }
+    };
+    let stl_file = stem.to_string() + ".stl";
+
+    if let Some(dir) = physical(&OAssetLocation::ChainSTLMeshes { robot_name }) { remove(&dir.join(&stl_file)); }
+    if let Some(dir) = physical(&OAssetLocation::ChainConvexHulls { robot_name }) { remove(&dir.join(&stl_file)); }
+    if let Some(dir) = physical(&OAssetLocation::LinkConvexDecomposition { robot_name, link_mesh_name: stem }) { remove(&dir); }
+    // the levels and hull counts that were computed are only known from what is on disk.
+    let per_setting_dirs = [OAssetLocation::ChainConvexDecompositionLevel { robot_name, level: 0 }, OAssetLocation::ChainConvexDecompositionMaxHulls { robot_name, max_convex_hulls: 0 }];
+    for location in &per_setting_dirs {
+        let Some(parent) = physical(location).and_then(|x| x.parent().map(|x| x.to_path_buf())) else { continue; };
+        let Ok(entries) = fs::read_dir(&parent) else { continue; };
+        entries.filter_map(|x| x.ok()).for_each(|x| remove(&x.path().join(stem)));
+    }
+}
+
+/// Watches a scene file (see `OptimaViewerScene`) and applies it to the running viewer when it
+/// changes.  Only what `SceneFileActions::action_apply_scene` can change at runtime (joint states
+/// and the camera) is applied; a notification explains anything else.
+#[derive(Resource)]
+pub struct BevySceneHotReloader {
+    watcher: OFileWatcher,
+    path: PathBuf
+}
+impl BevySceneHotReloader {
+    /// The file's directory is watched, so the file does not need to exist yet.
+    pub fn new(path: &str) -> Result<Self, String> {
+        let path = PathBuf::from(path);
+        let path = if path.is_absolute() { path } else { std::env::current_dir().map_err(|e| e.to_string())?.join(path) };
+        let dir = path.parent().ok_or(format!("scene file {:?} has no parent directory", path))?.to_path_buf();
+        let mut watcher = OFileWatcher::new(Duration::from_millis(300))?;
+        watcher.watch(&OPath::Path(dir), false)?;
+
+        Ok(Self { watcher, path })
+    }
+    #[inline(always)]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    fn relevant_changes(&mut self) -> bool {
+        let file_name = self.path.file_name();
+        self.watcher.poll_changes().iter().any(|x| x.file_name() == file_name)
+    }
+}
+
+pub struct HotReloadSystems;
+impl HotReloadSystems {
+    pub fn system_robot_hot_reload<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(mut reloader: ResMut<BevyRobotHotReloader>,
+                                                                                                      mut robot: ResMut<BevyORobot<T, C, L>>,
+                                                                                                      mut robot_state_engine: ResMut<RobotStateEngine>,
+                                                                                                      mut commands: Commands,
+                                                                                                      asset_server: Res<AssetServer>,
+                                                                                                      mut meshes: ResMut<Assets<Mesh>>,
+                                                                                                      mut materials: ResMut<Assets<StandardMaterial>>,
+                                                                                                      mut images: ResMut<Assets<Image>>,
+                                                                                                      mut mesh_cache: ResMut<BevyLinkMeshCache>,
+                                                                                                      query: Query<(Entity, &LinkMeshID)>,
+                                                                                                      egui_engine: Option<Res<OEguiEngineWrapper>>) {
+        if !reloader.relevant_changes() { return; }
+
+        let robot_name = reloader.robot_name.clone();
+        let source = reloader.source;
+        let load_res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            match source {
+                RobotHotReloadSource::Urdf => { ORobot::<T, C, L>::from_urdf(&robot_name) }
+                RobotHotReloadSource::SavedRobot => { ORobot::<T, C, L>::load_from_saved_robot(&robot_name) }
+            }
+        }));
+        let new_robot = match load_res {
+            Ok(Ok(new_robot)) => { new_robot }
+            Ok(Err(e)) => {
+                warn!("could not reload robot {} ({}); keeping the current version.", robot_name, e);
+                if let Some(egui_engine) = &egui_engine { egui_engine.get_mutex_guard().push_warning(&format!("could not reload robot {}; keeping the current version.", robot_name)); }
+                return;
+            }
+            Err(_) => {
+                warn!("could not reload robot {}; keeping the current version.", robot_name);
+                if let Some(egui_engine) = &egui_engine { egui_engine.get_mutex_guard().push_warning(&format!("could not reload robot {}; keeping the current version.", robot_name)); }
+                return;
+            }
+        };
+
+        RoboticsActions::action_replace_robot(&mut robot, new_robot, &mut robot_state_engine, &mut commands, &asset_server, &mut meshes, &mut materials, &mut images, &mut mesh_cache, &query);
+        info!("reloaded robot {}.", robot_name);
+        if let Some(egui_engine) = &egui_engine { egui_engine.get_mutex_guard().push_info(&format!("reloaded robot {}.", robot_name)); }
+    }
+    pub fn system_scene_hot_reload<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(mut reloader: ResMut<BevySceneHotReloader>,
+                                                                                                      robot: Option<Res<BevyORobot<T, C, L>>>,
+                                                                                                      robot_instances: Option<Res<BevyORobotInstances<T, C, L>>>,
+                                                                                                      environment_objects: Option<Res<BevyEnvironmentObjects<T, C>>>,
+                                                                                                      camera_control: Option<ResMut<BevyCameraControl>>,
+                                                                                                      egui_engine: Res<OEguiEngineWrapper>) {
+        if !reloader.relevant_changes() { return; }
+        // the file may have been removed, e.g., by an editor that saves by replacing it.
+        if !reloader.path.exists() { return; }
+
+        let path_str = reloader.path.display().to_string();
+        let scene = match OptimaViewerScene::load_from_path(&OPath::Path(reloader.path.clone())) {
+            Ok(scene) => { scene }
+            Err(e) => {
+                warn!("could not reload scene {} ({}); keeping the current scene.", path_str, e);
+                egui_engine.get_mutex_guard().push_warning(&format!("could not reload scene {}.", path_str));
+                return;
+            }
+        };
+
+        let messages = SceneFileActions::action_apply_scene(&scene, robot.as_deref(), robot_instances.as_deref(), environment_objects.as_deref(), camera_control.map(|x| x.into_inner()), &egui_engine);
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        for message in &messages {
+            warn!("{}", message);
+            mutex_guard.push_warning(message);
+        }
+        info!("reloaded scene {}.", path_str);
+        mutex_guard.push_info(&format!("reloaded scene {}.", path_str));
+    }
+}
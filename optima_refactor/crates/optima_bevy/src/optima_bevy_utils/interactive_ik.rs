@@ -0,0 +1,171 @@
+use std::sync::Mutex;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::JoinHandle;
+use ad_trait::AD;
+use ad_trait::differentiable_function::ForwardADMulti;
+use ad_trait::forward_ad::adfn::adfn;
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::{PickableBundle, RaycastPickTarget};
+use bevy_transform_gizmo::GizmoTransformable;
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_error::OptimaError;
+use optima_linalg::OLinalgCategory;
+use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
+use optima_optimization::open::SimpleOpEnOptimizer;
+use optima_proximity::pair_group_queries::{OwnedEmptyParryFilter, OwnedEmptyToProximityQry};
+use optima_robotics::robot::ORobot;
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
+use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
+use crate::optima_bevy_utils::robotics::{BevyORobot, RobotStateEngine};
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+type FAD = adfn<8>;
+
+/// Marks the draggable gizmo whose pose the interactive ik solver tracks.
+#[derive(Component)]
+pub struct InteractiveIKGizmo;
+
+/// Interactive posing: a transform gizmo is spawned at `link_idx`, and whenever it is dragged, ik
+/// is solved (warm started from the current robot state) to bring the link to the gizmo's pose.
+/// Solutions are pushed through the `RobotStateEngine`.
+///
+/// The solver runs on its own thread, so a slow solve never stalls the viewer; if several goals
+/// arrive while it is busy, only the most recent one is solved.
+#[derive(Resource)]
+pub struct BevyInteractiveIK<C: O3DPoseCategory + 'static> {
+    link_idx: usize,
+    robot_instance_idx: usize,
+    goals: Mutex<Sender<(C::P<f64>, Vec<f64>)>>,
+    solutions: Mutex<Receiver<Vec<f64>>>,
+    last_goal_transform: Option<Transform>,
+    dragging: bool,
+    solver_stopped: bool,
+    _solver_thread: JoinHandle<()>
+}
+impl<C: O3DPoseCategory + 'static> BevyInteractiveIK<C> {
+    pub fn new<L: OLinalgCategory + 'static>(robot: ORobot<f64, C, L>, link_idx: usize, robot_instance_idx: usize) -> Result<Self, OptimaError> {
+        OptimaError::check_idx("link", link_idx, robot.links().len())?;
+
+        let (goals_tx, goals_rx) = channel();
+        let (solutions_tx, solutions_rx) = channel();
+        let solver_thread = std::thread::spawn(move || {
+            run_interactive_ik_solver(robot, link_idx, goals_rx, solutions_tx);
+        });
+
+        Ok(Self {
+            link_idx,
+            robot_instance_idx,
+            goals: Mutex::new(goals_tx),
+            solutions: Mutex::new(solutions_rx),
+            last_goal_transform: None,
+            dragging: false,
+            solver_stopped: false,
+            _solver_thread: solver_thread,
+        })
+    }
+    #[inline(always)]
+    pub fn link_idx(&self) -> usize {
+        self.link_idx
+    }
+    #[inline(always)]
+    pub fn robot_instance_idx(&self) -> usize {
+        self.robot_instance_idx
+    }
+    fn try_recv_latest_solution(&self) -> Option<Vec<f64>> {
+        // the receiver has no invariants a panic could break, so a poisoned lock is still usable.
+        let solutions = self.solutions.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = None;
+        while let Ok(solution) = solutions.try_recv() { out = Some(solution); }
+        out
+    }
+}
+
+fn run_interactive_ik_solver<C: O3DPoseCategory, L: OLinalgCategory>(robot: ORobot<f64, C, L>, link_idx: usize, goals_rx: Receiver<(C::P<f64>, Vec<f64>)>, solutions_tx: Sender<Vec<f64>>) {
+    let init_state = vec![0.0; robot.num_dofs()];
+    let db = robot.get_ik_differentiable_block(ForwardADMulti::<FAD>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![link_idx], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.1, 0.0, 0.0));
+    let o = SimpleOpEnOptimizer::new(robot.get_dof_lower_bounds(), robot.get_dof_upper_bounds(), 0.001);
+
+    // blocks until the next goal, and stops once the resource (and with it the sender) is dropped.
+    while let Ok(mut goal) = goals_rx.recv() {
+        while let Ok(newer_goal) = goals_rx.try_recv() { goal = newer_goal; }
+        let (goal_pose, state) = goal;
+
+        db.update_ik_pose(0, goal_pose, IKGoalUpdateMode::Absolute);
+        db.update_prev_states(state.clone());
+        let res = o.optimize_unconstrained(&state, &db);
+
+        if solutions_tx.send(res.x_star().to_vec()).is_err() { return; }
+    }
+}
+
+pub struct InteractiveIKSystems;
+impl InteractiveIKSystems {
+    pub fn system_spawn_interactive_ik_gizmo<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(mut commands: Commands,
+                                                                                                                 robot: Res<BevyORobot<T, C, L>>,
+                                                                                                                 interactive_ik: Res<BevyInteractiveIK<C>>,
+                                                                                                                 robot_state_engine: Res<RobotStateEngine>,
+                                                                                                                 mut meshes: ResMut<Assets<Mesh>>,
+                                                                                                                 mut materials: ResMut<Assets<StandardMaterial>>) {
+        let state = match robot_state_engine.get_robot_state(interactive_ik.robot_instance_idx) {
+            None => { vec![T::zero(); robot.0.num_dofs()] }
+            Some(state) => { state.iter().map(|x| T::constant(*x)).collect() }
+        };
+        let fk_res = robot.0.forward_kinematics(&state, None);
+        let link_pose = match fk_res.get_link_pose(interactive_ik.link_idx) {
+            Ok(link_pose) => { link_pose }
+            Err(e) => {
+                warn!("could not place the interactive ik gizmo ({}); it was not spawned.", e);
+                return;
+            }
+        };
+
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::UVSphere { radius: 0.03, sectors: 16, stacks: 16 })),
+                material: materials.add(StandardMaterial { base_color: Color::rgba(1.0, 0.6, 0.0, 0.7), alpha_mode: AlphaMode::Blend, ..Default::default() }),
+                transform: TransformUtils::util_convert_3d_pose_to_y_up_bevy_transform(link_pose),
+                ..Default::default()
+            },
+            PickableBundle::default(),
+            RaycastPickTarget::default(),
+            GizmoTransformable,
+            InteractiveIKGizmo
+        ));
+    }
+    /// Sends the gizmo's pose to the solver whenever it moves, and forwards solutions to the
+    /// `RobotStateEngine`.  Each drag of the gizmo is recorded as one edit, so it can be undone.
+    pub fn system_interactive_ik<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                     mut interactive_ik: ResMut<BevyInteractiveIK<C>>,
+                                                                                                     mut robot_state_engine: ResMut<RobotStateEngine>,
+                                                                                                     query: Query<&Transform, With<InteractiveIKGizmo>>) {
+        let robot_instance_idx = interactive_ik.robot_instance_idx;
+
+        let mut moved = false;
+        if let Ok(transform) = query.get_single() {
+            if interactive_ik.last_goal_transform.as_ref() != Some(transform) {
+                let goal_pose = TransformUtils::util_convert_y_up_bevy_transform_to_3d_pose::<f64, C::P<f64>>(transform);
+                let state = match robot_state_engine.get_robot_state(robot_instance_idx) {
+                    None => { vec![0.0; robot.0.num_dofs()] }
+                    Some(state) => { state.clone() }
+                };
+                // the first goal is the gizmo's spawn pose, which the robot is already in.
+                if interactive_ik.last_goal_transform.is_some() {
+                    if !interactive_ik.dragging { robot_state_engine.record_edit(robot_instance_idx, "interactive ik"); }
+                    moved = true;
+                    let sent = interactive_ik.goals.lock().unwrap_or_else(|e| e.into_inner()).send((goal_pose, state)).is_ok();
+                    // the solver thread only stops early if it panicked; warn once rather than every frame.
+                    if !sent && !interactive_ik.solver_stopped {
+                        warn!("the interactive ik solver is no longer running; gizmo moves will be ignored.");
+                        interactive_ik.solver_stopped = true;
+                    }
+                }
+                interactive_ik.last_goal_transform = Some(*transform);
+            }
+        }
+        interactive_ik.dragging = moved;
+
+        if let Some(solution) = interactive_ik.try_recv_latest_solution() {
+            robot_state_engine.add_update_request(robot_instance_idx, &solution);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use optima_3d_spatial::optima_3d_pose::O3DPoseCategory;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use optima_linalg::OLinalgCategory;
+use optima_robotics::robot::ORobot;
+use optima_robotics::robotics_components::OJointType;
+use crate::optima_bevy_utils::robotics::{BevyORobot, BevyORobotInstances, LinkMeshID, RobotLinkCollisionHighlights, RobotStateEngine};
+
+const NUM_HEAT_MAP_COLORS: usize = 16;
+
+/// A joint that is close to (or past) one of its position limits.
+#[derive(Clone, Debug)]
+pub struct JointNearLimit {
+    pub robot_instance_idx: usize,
+    pub joint_name: String,
+    /// 0 in the middle of the joint's range, 1 at a limit, and above 1 past it.  For joints with
+    /// several dofs, this is the largest over the dofs.
+    pub closeness: f64
+}
+
+/// Colors every link by how close the joint leading into it is to a position limit, from green (in
+/// the middle of its range) to red (at a limit), with links past a limit drawn magenta.  Links whose
+/// parent joint is fixed or continuous keep their own material.  Links highlighted by
+/// `RobotLinkCollisionHighlights` are left to it, and their heat map color comes back once they are
+/// clear again.  Also drives the "Joint Limits" window.
+#[derive(Resource)]
+pub struct BevyJointLimitHeatMap {
+    pub enabled: bool,
+    /// Joints at least this close to a limit are listed in the window.
+    pub warning_closeness: f64,
+    link_closeness: HashMap<(usize, usize), f64>,
+    joints_near_limits: Vec<JointNearLimit>,
+    heat_map_materials: Vec<Handle<StandardMaterial>>,
+    violation_material: Option<Handle<StandardMaterial>>,
+    original_materials: HashMap<Entity, Handle<StandardMaterial>>
+}
+impl BevyJointLimitHeatMap {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            warning_closeness: 0.9,
+            link_closeness: HashMap::new(),
+            joints_near_limits: vec![],
+            heat_map_materials: vec![],
+            violation_material: None,
+            original_materials: HashMap::new(),
+        }
+    }
+    /// As of the last frame, sorted from closest to a limit.
+    #[inline(always)]
+    pub fn joints_near_limits(&self) -> &Vec<JointNearLimit> {
+        &self.joints_near_limits
+    }
+    /// Closeness of the joint leading into the given link (see `JointNearLimit::closeness`), or None
+    /// if that joint has no limits.
+    pub fn link_closeness(&self, robot_instance_idx: usize, link_idx: usize) -> Option<f64> {
+        self.link_closeness.get(&(robot_instance_idx, link_idx)).cloned()
+    }
+    /// Whether the entity's material is currently swapped for a heat map color.
+    pub fn has_swapped_material(&self, entity: Entity) -> bool {
+        self.original_materials.contains_key(&entity)
+    }
+}
+
+pub struct JointLimitActions;
+impl JointLimitActions {
+    /// Closeness to a limit (see `JointNearLimit::closeness`) of every joint with position limits,
+    /// paired with the joint's child link idx.
+    pub fn action_get_joint_limit_closeness<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(robot: &ORobot<T, C, L>, state: &Vec<f64>) -> Vec<(usize, String, f64)> {
+        let mut out = vec![];
+        let dof_bounds = robot.get_dof_bounds();
+
+        for joint in robot.joints() {
+            if !joint.is_present_in_model() { continue; }
+            // continuous joints are given placeholder limits that are never meant to be reached.
+            if matches!(joint.joint_type(), OJointType::Fixed | OJointType::Continuous) { continue; }
+
+            let mut closeness: Option<f64> = None;
+            for dof_idx in joint.dof_idxs() {
+                let (Some(value), Some((lower, upper))) = (state.get(*dof_idx), dof_bounds.get(*dof_idx)) else { continue; };
+                let (lower, upper) = (lower.to_constant(), upper.to_constant());
+                if upper <= lower { continue; }
+                let half_range = 0.5 * (upper - lower);
+                let middle = lower + half_range;
+                let c = (value - middle).abs() / half_range;
+                closeness = Some(closeness.map(|x| x.max(c)).unwrap_or(c));
+            }
+
+            if let Some(closeness) = closeness { out.push((joint.child_link_idx(), joint.name().to_string(), closeness)); }
+        }
+
+        out
+    }
+}
+
+pub struct JointLimitSystems;
+impl JointLimitSystems {
+    pub fn system_update_joint_limit_heat_map<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Option<Res<BevyORobot<T, C, L>>>,
+                                                                                                                 instances: Option<Res<BevyORobotInstances<T, C, L>>>,
+                                                                                                                 robot_state_engine: Res<RobotStateEngine>,
+                                                                                                                 mut heat_map: ResMut<BevyJointLimitHeatMap>) {
+        let mut robots = vec![];
+        if let Some(robot) = &robot { robots.push((&robot.0, robot.1)); }
+        if let Some(instances) = &instances {
+            instances.instances().iter().for_each(|x| robots.push((&x.robot, x.robot_instance_idx)));
+        }
+
+        let heat_map = &mut *heat_map;
+        heat_map.link_closeness.clear();
+        heat_map.joints_near_limits.clear();
+        for (robot, robot_instance_idx) in robots {
+            let Some(state) = robot_state_engine.get_robot_state(robot_instance_idx) else { continue; };
+            for (child_link_idx, joint_name, closeness) in JointLimitActions::action_get_joint_limit_closeness(robot, state) {
+                heat_map.link_closeness.insert((robot_instance_idx, child_link_idx), closeness);
+                if closeness >= heat_map.warning_closeness {
+                    heat_map.joints_near_limits.push(JointNearLimit { robot_instance_idx, joint_name, closeness });
+                }
+            }
+        }
+        heat_map.joints_near_limits.sort_by(|a, b| b.closeness.total_cmp(&a.closeness));
+    }
+    /// Runs after `system_robot_link_collision_highlighting`.  A link it has highlighted is not
+    /// touched here, so neither system ever records the other's material as the original one.
+    pub fn system_apply_joint_limit_heat_map(mut heat_map: ResMut<BevyJointLimitHeatMap>,
+                                             collision_highlights: Option<Res<RobotLinkCollisionHighlights>>,
+                                             mut materials: ResMut<Assets<StandardMaterial>>,
+                                             mut query: Query<(Entity, &LinkMeshID, &mut Handle<StandardMaterial>)>) {
+        if heat_map.heat_map_materials.is_empty() {
+            heat_map.heat_map_materials = (0..NUM_HEAT_MAP_COLORS).map(|i| {
+                let t = i as f32 / (NUM_HEAT_MAP_COLORS - 1) as f32;
+                // green -> yellow -> red.
+                let color = if t < 0.5 { Color::rgb(2.0 * t, 0.8, 0.1) } else { Color::rgb(1.0, 0.8 * (2.0 - 2.0 * t), 0.1) };
+                materials.add(StandardMaterial::from(color))
+            }).collect();
+        }
+        let violation_material = heat_map.violation_material.get_or_insert_with(|| materials.add(StandardMaterial::from(Color::rgb(1.0, 0.0, 1.0)))).clone();
+
+        // entities that were despawned while colored.
+        heat_map.original_materials.retain(|entity, _| query.contains(*entity));
+
+        for (entity, link_mesh_id, mut material) in query.iter_mut() {
+            if collision_highlights.as_ref().map(|x| x.has_swapped_material(entity)).unwrap_or(false) { continue; }
+            let closeness = if heat_map.enabled { heat_map.link_closeness(link_mesh_id.robot_instance_idx, link_mesh_id.link_idx) } else { None };
+
+            match closeness {
+                None => {
+                    if let Some(original_material) = heat_map.original_materials.remove(&entity) { *material = original_material; }
+                }
+                Some(closeness) => {
+                    let heat_map_material = if closeness > 1.0 {
+                        violation_material.clone()
+                    } else {
+                        heat_map.heat_map_materials[(closeness * (NUM_HEAT_MAP_COLORS - 1) as f64).round() as usize].clone()
+                    };
+                    if *material != heat_map_material {
+                        if !heat_map.original_materials.contains_key(&entity) { heat_map.original_materials.insert(entity, material.clone()); }
+                        *material = heat_map_material;
+                    }
+                }
+            }
+        }
+    }
+    pub fn system_joint_limit_panel(mut heat_map: ResMut<BevyJointLimitHeatMap>,
+                                    mut contexts: EguiContexts,
+                                    egui_engine: Res<OEguiEngineWrapper>,
+                                    window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Joint Limits", true, true, false, false, false, true)
+            .show("joint_limits_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                let heat_map = &mut *heat_map;
+                ui.checkbox(&mut heat_map.enabled, "heat map");
+                ui.add(egui::Slider::new(&mut heat_map.warning_closeness, 0.5..=1.0).text("warn at"));
+                ui.separator();
+                if heat_map.joints_near_limits.is_empty() { ui.label("no joints near their limits."); }
+                for joint in &heat_map.joints_near_limits {
+                    let color = if joint.closeness > 1.0 { egui::Color32::from_rgb(255, 0, 255) } else { egui::Color32::from_rgb(255, 120, 60) };
+                    let text = if joint.closeness > 1.0 { format!("robot {}: {} past its limit", joint.robot_instance_idx, joint.joint_name) } else { format!("robot {}: {} ({:.0}%)", joint.robot_instance_idx, joint.joint_name, 100.0 * joint.closeness) };
+                    ui.colored_label(color, text);
+                }
+            });
+    }
+}
@@ -0,0 +1,433 @@
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use optima_3d_spatial::optima_3d_pose::O3DPoseCategory;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use optima_error::OptimaError;
+use optima_interpolation::{InterpolatorTraitLite, KnotTimedInterpolator};
+use optima_interpolation::splines::PiecewiseHermiteSpline;
+use optima_linalg::{OLinalgCategory, OVec};
+use crate::optima_bevy_utils::robotics::{BevyORobot, RoboticsActions, RobotStateEngine};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// seconds from the first keyframe.
+    pub time: f64,
+    pub state: Vec<f64>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyframeInterpolation {
+    Linear,
+    /// cubic hermite segments with (non-uniform) catmull-rom tangents, easing in and out at the
+    /// first and last keyframes.
+    Smooth
+}
+
+pub type KeyframeInterpolator<T> = KnotTimedInterpolator<T, Vec<T>, PiecewiseHermiteSpline<T, Vec<T>>>;
+
+/// The keyframes as they were before (or, on the redo stack, after) an edit.
+#[derive(Clone, Debug)]
+pub struct KeyframeHistoryEntry {
+    /// what the edit was, e.g., "remove keyframe 2".
+    pub label: String,
+    pub keyframes: Vec<Keyframe>
+}
+
+const MAX_KEYFRAME_HISTORY_LENGTH: usize = 200;
+
+/// Keyframes of a robot's joint states, edited through the "Keyframes" window: the robot is posed
+/// with the joint sliders and added as a keyframe, keyframes can then be retimed, reordered,
+/// overwritten, or removed, and the motion between them previewed.  The result is available as an
+/// optima_interpolation interpolator through `to_interpolator`.
+#[derive(Resource)]
+pub struct BevyKeyframeEditor {
+    keyframes: Vec<Keyframe>,
+    pub robot_instance_idx: usize,
+    pub interpolation: KeyframeInterpolation,
+    /// time between a new keyframe and the one before it.
+    pub default_spacing: f64,
+    /// while previewing, the robot follows the keyframes at `preview_time` instead of the sliders.
+    pub preview: bool,
+    pub playing: bool,
+    pub looping: bool,
+    pub preview_time: f64,
+    undo_stack: Vec<KeyframeHistoryEntry>,
+    redo_stack: Vec<KeyframeHistoryEntry>
+}
+impl BevyKeyframeEditor {
+    pub fn new(robot_instance_idx: usize) -> Self {
+        Self {
+            keyframes: vec![],
+            robot_instance_idx,
+            interpolation: KeyframeInterpolation::Smooth,
+            default_spacing: 1.0,
+            preview: false,
+            playing: false,
+            looping: true,
+            preview_time: 0.0,
+            undo_stack: vec![],
+            redo_stack: vec![],
+        }
+    }
+    #[inline(always)]
+    pub fn keyframes(&self) -> &Vec<Keyframe> {
+        &self.keyframes
+    }
+    pub fn add_keyframe(&mut self, state: Vec<f64>) {
+        let time = match self.keyframes.last() { None => { 0.0 } Some(last) => { last.time + self.default_spacing } };
+        self.keyframes.push(Keyframe { time, state });
+    }
+    pub fn set_keyframes(&mut self, keyframes: Vec<Keyframe>) -> Result<(), String> {
+        if keyframes.first().map(|x| x.time != 0.0).unwrap_or(false) { return Err("the first keyframe has to be at time 0.".to_string()); }
+        if !keyframes.windows(2).all(|x| x[0].time < x[1].time) { return Err("keyframe times have to be strictly increasing.".to_string()); }
+        self.keyframes = keyframes;
+        Ok(())
+    }
+    pub fn remove_keyframe(&mut self, idx: usize) {
+        if idx >= self.keyframes.len() { return; }
+        self.keyframes.remove(idx);
+        // keep the first keyframe at time 0 by shifting everything back.
+        if let Some(first_time) = self.keyframes.first().map(|x| x.time) {
+            self.keyframes.iter_mut().for_each(|x| x.time -= first_time);
+        }
+    }
+    /// Swaps the states of two keyframes; times stay where they are.
+    pub fn swap_keyframes(&mut self, idx1: usize, idx2: usize) {
+        if idx1 >= self.keyframes.len() || idx2 >= self.keyframes.len() { return; }
+        let state1 = self.keyframes[idx1].state.clone();
+        self.keyframes[idx1].state = self.keyframes[idx2].state.clone();
+        self.keyframes[idx2].state = state1;
+    }
+    pub fn set_keyframe_state(&mut self, idx: usize, state: Vec<f64>) {
+        if let Some(keyframe) = self.keyframes.get_mut(idx) { keyframe.state = state; }
+    }
+    /// The time is clamped to lie between the neighboring keyframes; the first keyframe stays at 0.
+    pub fn set_keyframe_time(&mut self, idx: usize, time: f64) {
+        if idx == 0 || idx >= self.keyframes.len() { return; }
+        let lower = self.keyframes[idx - 1].time + 0.01;
+        let upper = self.keyframes.get(idx + 1).map(|x| x.time - 0.01).unwrap_or(f64::INFINITY);
+        self.keyframes[idx].time = time.max(lower).min(upper);
+    }
+    /// Call before an interactive edit (e.g., when a keyframe's time starts being dragged): the
+    /// current keyframes are saved so the edit can be undone.  The editing methods above do not call
+    /// this themselves, so that an edit spanning several frames is undone in one step.
+    pub fn record_edit(&mut self, label: &str) {
+        self.undo_stack.push(KeyframeHistoryEntry { label: label.to_string(), keyframes: self.keyframes.clone() });
+        if self.undo_stack.len() > MAX_KEYFRAME_HISTORY_LENGTH { self.undo_stack.remove(0); }
+        self.redo_stack.clear();
+    }
+    /// Restores the keyframes from before the most recent edit, and returns the entry that was
+    /// restored.  Entries that would not change anything (e.g., a drag that did not move a keyframe)
+    /// are skipped.
+    pub fn undo(&mut self) -> Option<KeyframeHistoryEntry> {
+        loop {
+            let entry = self.undo_stack.pop()?;
+            if entry.keyframes == self.keyframes { continue; }
+            self.redo_stack.push(KeyframeHistoryEntry { label: entry.label.clone(), keyframes: std::mem::replace(&mut self.keyframes, entry.keyframes.clone()) });
+            return Some(entry);
+        }
+    }
+    pub fn redo(&mut self) -> Option<KeyframeHistoryEntry> {
+        loop {
+            let entry = self.redo_stack.pop()?;
+            if entry.keyframes == self.keyframes { continue; }
+            self.undo_stack.push(KeyframeHistoryEntry { label: entry.label.clone(), keyframes: std::mem::replace(&mut self.keyframes, entry.keyframes.clone()) });
+            return Some(entry);
+        }
+    }
+    /// Oldest first.
+    #[inline(always)]
+    pub fn undo_stack(&self) -> &Vec<KeyframeHistoryEntry> {
+        &self.undo_stack
+    }
+    /// Oldest first, i.e., the next entry `redo` restores is the last one.
+    #[inline(always)]
+    pub fn redo_stack(&self) -> &Vec<KeyframeHistoryEntry> {
+        &self.redo_stack
+    }
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map(|x| x.time).unwrap_or(0.0)
+    }
+    /// Interpolator over the keyframes, with `t` in seconds.  Needs at least two keyframes with the
+    /// same number of dofs.
+    ///
+    /// Smooth tangents are the velocities of a non-uniform Catmull-Rom spline, i.e., the difference
+    /// between the neighboring keyframes over the time between them, scaled by each segment's
+    /// duration, so the velocity is continuous across keyframes even when they are unevenly spaced.
+    pub fn to_interpolator<T: AD>(&self) -> Result<KeyframeInterpolator<T>, OptimaError> {
+        if self.keyframes.len() < 2 { return Err(OptimaError::InvalidInput("at least two keyframes are needed.".to_string())); }
+
+        let states: Vec<Vec<T>> = self.keyframes.iter().map(|x| OVec::ovec_to_other_ad_type::<T>(&x.state)).collect();
+        let times: Vec<f64> = self.keyframes.iter().map(|x| x.time).collect();
+        let n = states.len();
+        let velocities: Vec<Vec<T>> = (0..n).map(|i| {
+            match self.interpolation {
+                KeyframeInterpolation::Smooth if i > 0 && i < n - 1 => {
+                    states[i + 1].ovec_sub(&states[i - 1]).ovec_scalar_mul(&T::constant(1.0 / (times[i + 1] - times[i - 1])))
+                }
+                _ => { vec![T::zero(); states[i].len()] }
+            }
+        }).collect();
+
+        let segment_tangents = (0..n - 1).map(|i| {
+            match self.interpolation {
+                // a hermite segment with both tangents equal to the chord is a straight line.
+                KeyframeInterpolation::Linear => {
+                    let chord = states[i + 1].ovec_sub(&states[i]);
+                    (chord.clone(), chord)
+                }
+                KeyframeInterpolation::Smooth => {
+                    let duration = T::constant(times[i + 1] - times[i]);
+                    (velocities[i].ovec_scalar_mul(&duration), velocities[i + 1].ovec_scalar_mul(&duration))
+                }
+            }
+        }).collect();
+
+        let spline = PiecewiseHermiteSpline::new(states, segment_tangents)?;
+        KnotTimedInterpolator::new(spline, times.iter().map(|x| T::constant(*x)).collect())
+    }
+}
+
+enum KeyframeEdit {
+    /// saves the keyframes before an edit that spans several frames, i.e., dragging a time.
+    Record(String),
+    SetTime(usize, f64),
+    Swap(usize, usize),
+    GoTo(usize),
+    Overwrite(usize),
+    Remove(usize)
+}
+
+pub struct KeyframeSystems;
+impl KeyframeSystems {
+    pub fn system_keyframe_editor_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                         mut editor: ResMut<BevyKeyframeEditor>,
+                                                                                                         mut robot_state_engine: ResMut<RobotStateEngine>,
+                                                                                                         mut contexts: EguiContexts,
+                                                                                                         egui_engine: Res<OEguiEngineWrapper>,
+                                                                                                         time: Res<Time>,
+                                                                                                         window_query: Query<&Window, With<PrimaryWindow>>) {
+        let editor = &mut *editor;
+        let current_state = robot_state_engine.get_robot_state(editor.robot_instance_idx).cloned().unwrap_or(vec![0.0; robot.0.num_dofs()]);
+        let mut edits = vec![];
+
+        OEguiWindow::new("Keyframes", true, true, false, false, true, true)
+            .show("keyframes_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Add keyframe").clicked() {
+                        editor.record_edit("add keyframe");
+                        editor.add_keyframe(current_state.clone());
+                    }
+                    ui.selectable_value(&mut editor.interpolation, KeyframeInterpolation::Linear, "linear");
+                    ui.selectable_value(&mut editor.interpolation, KeyframeInterpolation::Smooth, "smooth");
+                    ui.separator();
+                    if ui.add_enabled(!editor.undo_stack.is_empty(), egui::Button::new("Undo")).clicked() { editor.undo(); }
+                    if ui.add_enabled(!editor.redo_stack.is_empty(), egui::Button::new("Redo")).clicked() { editor.redo(); }
+                });
+                ui.add(egui::DragValue::new(&mut editor.default_spacing).clamp_range(0.05..=60.0).speed(0.05).prefix("spacing: ").suffix(" s"));
+
+                ui.separator();
+                draw_timeline(ui, editor);
+                ui.horizontal(|ui| {
+                    let button_str = if editor.playing { "⏸" } else { "⏵" };
+                    if ui.button(button_str).clicked() {
+                        editor.playing = !editor.playing;
+                        if editor.playing { editor.preview = true; }
+                    }
+                    ui.checkbox(&mut editor.preview, "preview");
+                    ui.checkbox(&mut editor.looping, "loop");
+                    let duration = editor.duration();
+                    if ui.add(egui::Slider::new(&mut editor.preview_time, 0.0..=duration).suffix(" s")).changed() { editor.preview = true; }
+                });
+
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    let num_keyframes = editor.keyframes.len();
+                    for (i, keyframe) in editor.keyframes.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}", i));
+                            let mut t = keyframe.time;
+                            let response = ui.add_enabled(i > 0, egui::DragValue::new(&mut t).speed(0.01).suffix(" s"));
+                            // a drag is recorded once, when it starts; a typed time when it changes.
+                            if response.drag_started() || (response.changed() && !response.dragged()) { edits.push(KeyframeEdit::Record(format!("retime keyframe {}", i))); }
+                            if response.changed() { edits.push(KeyframeEdit::SetTime(i, t)); }
+                            if ui.add_enabled(i > 0, egui::Button::new("▲")).clicked() { edits.push(KeyframeEdit::Swap(i, i - 1)); }
+                            if ui.add_enabled(i + 1 < num_keyframes, egui::Button::new("▼")).clicked() { edits.push(KeyframeEdit::Swap(i, i + 1)); }
+                            if ui.button("go to").on_hover_text("move the joint sliders to this keyframe").clicked() { edits.push(KeyframeEdit::GoTo(i)); }
+                            if ui.button("set").on_hover_text("overwrite with the current pose").clicked() { edits.push(KeyframeEdit::Overwrite(i)); }
+                            if ui.button("✖").clicked() { edits.push(KeyframeEdit::Remove(i)); }
+                        });
+                    }
+                });
+            });
+
+        for edit in edits {
+            match edit {
+                KeyframeEdit::Record(label) => { editor.record_edit(&label); }
+                KeyframeEdit::SetTime(idx, t) => { editor.set_keyframe_time(idx, t); }
+                KeyframeEdit::Swap(idx1, idx2) => {
+                    editor.record_edit(&format!("swap keyframes {} and {}", idx1, idx2));
+                    editor.swap_keyframes(idx1, idx2);
+                }
+                KeyframeEdit::GoTo(idx) => {
+                    let state = editor.keyframes[idx].state.clone();
+                    robot_state_engine.record_edit(editor.robot_instance_idx, "keyframe go to");
+                    RoboticsActions::action_set_joint_sliders(&state, editor.robot_instance_idx, &egui_engine);
+                    robot_state_engine.add_update_request(editor.robot_instance_idx, &state);
+                    editor.preview = false;
+                    editor.playing = false;
+                    editor.preview_time = editor.keyframes[idx].time;
+                }
+                KeyframeEdit::Overwrite(idx) => {
+                    editor.record_edit(&format!("overwrite keyframe {}", idx));
+                    editor.set_keyframe_state(idx, current_state.clone());
+                }
+                KeyframeEdit::Remove(idx) => {
+                    editor.record_edit(&format!("remove keyframe {}", idx));
+                    editor.remove_keyframe(idx);
+                }
+            }
+        }
+
+        let duration = editor.duration();
+        if editor.playing {
+            editor.preview_time += time.delta_seconds_f64();
+            if editor.preview_time > duration {
+                if editor.looping { editor.preview_time = 0.0; } else { editor.preview_time = duration; editor.playing = false; }
+            }
+        }
+        editor.preview_time = editor.preview_time.max(0.0).min(duration);
+
+        if editor.preview {
+            if let Ok(interpolator) = editor.to_interpolator::<f64>() {
+                let state = interpolator.interpolate(editor.preview_time);
+                robot_state_engine.add_update_request(editor.robot_instance_idx, &state);
+            }
+        }
+    }
+}
+
+/// Keyframes as ticks on a line, with the preview time as a playhead.  Clicking or dragging on the
+/// timeline scrubs the preview.
+fn draw_timeline(ui: &mut egui::Ui, editor: &mut BevyKeyframeEditor) {
+    let width = ui.available_width().max(100.0);
+    let (response, painter) = ui.allocate_painter(egui::vec2(width, 28.0), egui::Sense::click_and_drag());
+    let rect = response.rect.shrink2(egui::vec2(8.0, 0.0));
+    let duration = editor.duration();
+    let x_of = |t: f64| if duration > 0.0 { rect.left() + rect.width() * (t / duration) as f32 } else { rect.left() };
+
+    painter.line_segment([egui::pos2(rect.left(), rect.center().y), egui::pos2(rect.right(), rect.center().y)], egui::Stroke::new(2.0, egui::Color32::GRAY));
+    for keyframe in editor.keyframes.iter() {
+        painter.circle_filled(egui::pos2(x_of(keyframe.time), rect.center().y), 5.0, egui::Color32::from_rgb(255, 190, 60));
+    }
+    if editor.preview {
+        let x = x_of(editor.preview_time);
+        painter.line_segment([egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())], egui::Stroke::new(2.0, egui::Color32::from_rgb(230, 60, 60)));
+    }
+
+    if let Some(pos) = response.interact_pointer_pos() {
+        if duration > 0.0 {
+            editor.preview_time = (((pos.x - rect.left()) / rect.width()).max(0.0).min(1.0) as f64) * duration;
+            editor.preview = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(interpolation: KeyframeInterpolation, keyframes: &[(f64, f64)]) -> BevyKeyframeEditor {
+        let mut out = BevyKeyframeEditor::new(0);
+        out.interpolation = interpolation;
+        out.set_keyframes(keyframes.iter().map(|(time, x)| Keyframe { time: *time, state: vec![*x] }).collect()).expect("error");
+        out
+    }
+
+    #[test]
+    fn interpolator_passes_through_every_keyframe() {
+        for interpolation in [KeyframeInterpolation::Linear, KeyframeInterpolation::Smooth] {
+            let e = editor(interpolation, &[(0.0, 0.0), (0.5, 1.0), (3.0, -2.0), (3.5, 4.0)]);
+            let interpolator = e.to_interpolator::<f64>().expect("error");
+            for keyframe in e.keyframes() {
+                assert!((interpolator.interpolate(keyframe.time)[0] - keyframe.state[0]).abs() < 1e-9);
+            }
+            assert_eq!(interpolator.max_t(), 3.5);
+        }
+    }
+
+    #[test]
+    fn linear_interpolation_is_linear_in_time() {
+        let interpolator = editor(KeyframeInterpolation::Linear, &[(0.0, 0.0), (2.0, 1.0), (2.5, 0.0)]).to_interpolator::<f64>().expect("error");
+        assert!((interpolator.interpolate(0.5)[0] - 0.25).abs() < 1e-9);
+        assert!((interpolator.interpolate(2.25)[0] - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn smooth_velocity_is_continuous_across_unevenly_spaced_keyframes() {
+        let interpolator = editor(KeyframeInterpolation::Smooth, &[(0.0, 0.0), (0.2, 1.0), (3.0, 3.0), (3.5, 0.0)]).to_interpolator::<f64>().expect("error");
+        let h = 1e-6;
+        for knot in [0.2, 3.0] {
+            let before = (interpolator.interpolate(knot)[0] - interpolator.interpolate(knot - h)[0]) / h;
+            let after = (interpolator.interpolate(knot + h)[0] - interpolator.interpolate(knot)[0]) / h;
+            assert!((before - after).abs() < 1e-3, "knot {}: {} vs {}", knot, before, after);
+        }
+        // the velocity at a keyframe is the slope between its neighbors.
+        let at_first_knot = (interpolator.interpolate(0.2 + h)[0] - interpolator.interpolate(0.2 - h)[0]) / (2.0 * h);
+        assert!((at_first_knot - 1.0).abs() < 1e-3);
+        // and zero at the ends.
+        assert!(((interpolator.interpolate(h)[0] - interpolator.interpolate(0.0)[0]) / h).abs() < 1e-3);
+    }
+
+    #[test]
+    fn to_interpolator_rejects_too_few_keyframes_and_mismatched_dofs() {
+        assert!(editor(KeyframeInterpolation::Smooth, &[(0.0, 0.0)]).to_interpolator::<f64>().is_err());
+
+        let mut e = editor(KeyframeInterpolation::Smooth, &[(0.0, 0.0), (1.0, 1.0)]);
+        e.set_keyframe_state(1, vec![1.0, 2.0]);
+        assert!(e.to_interpolator::<f64>().is_err());
+    }
+
+    #[test]
+    fn undo_and_redo_restore_keyframes() {
+        let mut e = editor(KeyframeInterpolation::Linear, &[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]);
+        let original = e.keyframes().clone();
+
+        e.record_edit("remove keyframe 0");
+        e.remove_keyframe(0);
+        let removed = e.keyframes().clone();
+        e.record_edit("swap keyframes 0 and 1");
+        e.swap_keyframes(0, 1);
+
+        assert_eq!(e.undo().expect("error").label, "swap keyframes 0 and 1");
+        assert_eq!(e.keyframes(), &removed);
+        assert_eq!(e.undo().expect("error").label, "remove keyframe 0");
+        assert_eq!(e.keyframes(), &original);
+        assert!(e.undo().is_none());
+
+        e.redo().expect("error");
+        assert_eq!(e.keyframes(), &removed);
+        // a new edit clears the redo stack.
+        e.record_edit("add keyframe");
+        e.add_keyframe(vec![5.0]);
+        assert!(e.redo().is_none());
+        e.undo().expect("error");
+        assert_eq!(e.keyframes(), &removed);
+    }
+
+    #[test]
+    fn undo_skips_edits_that_changed_nothing() {
+        let mut e = editor(KeyframeInterpolation::Linear, &[(0.0, 0.0), (1.0, 1.0)]);
+        e.record_edit("retime keyframe 1");
+        e.set_keyframe_time(1, 1.5);
+        // e.g., a drag that started but did not move the keyframe.
+        e.record_edit("retime keyframe 1");
+
+        e.undo().expect("error");
+        assert_eq!(e.keyframes()[1].time, 1.0);
+        assert!(e.undo_stack().is_empty());
+    }
+}
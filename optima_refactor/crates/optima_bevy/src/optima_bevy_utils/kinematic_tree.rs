@@ -0,0 +1,164 @@
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use bevy_mod_picking::prelude::{PickableBundle, PickSelection, RaycastPickTarget};
+use optima_3d_spatial::optima_3d_pose::O3DPoseCategory;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiTree, OEguiTreeNode, OEguiWidgetTrait, OEguiWindow};
+use optima_linalg::OLinalgCategory;
+use optima_robotics::robot::ORobot;
+use optima_robotics::robotics_components::OJointType;
+use crate::optima_bevy_utils::robotics::{BevyORobot, LinkMeshID};
+
+const KINEMATIC_TREE_ID: &str = "kinematic_tree";
+const ISOLATE_SUBTREE_ACTION: &str = "isolate this subtree";
+const SHOW_ALL_ACTION: &str = "show all links";
+
+/// State of the "Kinematic Tree" window, which shows the link / joint hierarchy of the robot in
+/// `BevyORobot`.  Selecting a link in the tree selects its meshes in the viewport and vice versa.
+#[derive(Resource)]
+pub struct BevyKinematicTree {
+    /// Only this link and its descendants are shown, if set.
+    isolated_link_idx: Option<usize>,
+    isolated_changed: bool
+}
+impl BevyKinematicTree {
+    pub fn new() -> Self {
+        Self { isolated_link_idx: None, isolated_changed: false }
+    }
+    pub fn isolate_subtree(&mut self, link_idx: Option<usize>) {
+        self.isolated_link_idx = link_idx;
+        self.isolated_changed = true;
+    }
+    #[inline(always)]
+    pub fn isolated_link_idx(&self) -> Option<usize> {
+        self.isolated_link_idx
+    }
+}
+
+pub struct KinematicTreeActions;
+impl KinematicTreeActions {
+    /// Links alternate with the joints that connect them, starting from the base link.  Links of
+    /// robots made of several sub-robots are tagged with their sub-robot idx.
+    pub fn action_kinematic_tree_nodes<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(robot: &ORobot<T, C, L>) -> Vec<OEguiTreeNode> {
+        let mut roots = vec![robot.base_link_idx()];
+        robot.links().iter().filter(|x| x.is_present_in_model() && x.parent_link_idx().is_none() && x.link_idx() != robot.base_link_idx()).for_each(|x| roots.push(x.link_idx()));
+
+        roots.iter().map(|x| Self::link_node(robot, *x)).collect()
+    }
+    /// The link and all links below it.
+    pub fn action_subtree_link_idxs<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(robot: &ORobot<T, C, L>, link_idx: usize) -> Vec<usize> {
+        let mut out = vec![];
+        let mut stack = vec![link_idx];
+        while let Some(link_idx) = stack.pop() {
+            out.push(link_idx);
+            stack.extend(robot.links()[link_idx].children_link_idxs().iter());
+        }
+        out
+    }
+    fn link_node<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(robot: &ORobot<T, C, L>, link_idx: usize) -> OEguiTreeNode {
+        let link = &robot.links()[link_idx];
+        let label = match robot.sub_robots().len() > 1 {
+            true => { format!("🔗 {} [sub-robot {}]", link.name(), link.sub_robot_idx()) }
+            false => { format!("🔗 {}", link.name()) }
+        };
+
+        let children = link.children_joint_idxs().iter().filter_map(|joint_idx| {
+            let joint = &robot.joints()[*joint_idx];
+            if !joint.is_present_in_model() || !robot.links()[joint.child_link_idx()].is_present_in_model() { return None; }
+            let label = match joint.joint_type() {
+                OJointType::Fixed => { format!("⚓ {} (fixed)", joint.name()) }
+                joint_type => { format!("⚙ {} ({:?}, dofs {:?})", joint.name(), joint_type, joint.dof_idxs()) }
+            };
+            Some(OEguiTreeNode::new(&joint_node_id(*joint_idx), &label).with_children(vec![Self::link_node(robot, joint.child_link_idx())]))
+        }).collect();
+
+        OEguiTreeNode::new(&link_node_id(link_idx), &label).with_children(children)
+    }
+}
+
+fn link_node_id(link_idx: usize) -> String {
+    format!("link_{}", link_idx)
+}
+
+fn joint_node_id(joint_idx: usize) -> String {
+    format!("joint_{}", joint_idx)
+}
+
+/// The link a node stands for; a joint stands for its child link.
+fn node_link_idx<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(robot: &ORobot<T, C, L>, node_id: &str) -> Option<usize> {
+    if let Some(link_idx) = node_id.strip_prefix("link_") { return link_idx.parse().ok(); }
+    let joint_idx: usize = node_id.strip_prefix("joint_")?.parse().ok()?;
+    robot.joints().get(joint_idx).map(|x| x.child_link_idx())
+}
+
+pub struct KinematicTreeSystems;
+impl KinematicTreeSystems {
+    /// Link meshes are not pickable otherwise.
+    pub fn system_make_link_meshes_pickable(mut commands: Commands, query: Query<Entity, (With<LinkMeshID>, Without<RaycastPickTarget>)>) {
+        for entity in query.iter() {
+            commands.entity(entity).insert((PickableBundle::default(), RaycastPickTarget::default()));
+        }
+    }
+    pub fn system_kinematic_tree_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                           mut kinematic_tree: ResMut<BevyKinematicTree>,
+                                                                                                           mut contexts: EguiContexts,
+                                                                                                           egui_engine: Res<OEguiEngineWrapper>,
+                                                                                                           window_query: Query<&Window, With<PrimaryWindow>>,
+                                                                                                           mut link_query: Query<(&LinkMeshID, &mut PickSelection, &mut Visibility)>) {
+        let robot_instance_idx = robot.1;
+
+        // viewport selection -> tree.
+        let picked = link_query.iter_mut().filter(|(id, selection, _)| id.robot_instance_idx == robot_instance_idx && selection.is_changed() && selection.is_selected).map(|(id, _, _)| id.link_idx).last();
+        if let Some(link_idx) = picked {
+            if let Some(response) = egui_engine.get_mutex_guard().get_tree_response_mut(KINEMATIC_TREE_ID) {
+                let node_id = link_node_id(link_idx);
+                response.select(Some(&node_id));
+                response.reveal(&node_id);
+            }
+        }
+
+        let nodes = KinematicTreeActions::action_kinematic_tree_nodes(&robot.0);
+        OEguiWindow::new("Kinematic Tree", true, true, false, false, false, true)
+            .show("kinematic_tree_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                ui.label("right click a link or joint for more.");
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(500.0).show(ui, |ui| {
+                    OEguiTree::new(nodes, true)
+                        .with_context_actions(&[ISOLATE_SUBTREE_ACTION, SHOW_ALL_ACTION])
+                        .show(KINEMATIC_TREE_ID, ui, &egui_engine, &());
+                });
+            });
+
+        let mutex_guard = egui_engine.get_mutex_guard();
+        let Some(response) = mutex_guard.get_tree_response(KINEMATIC_TREE_ID) else { return; };
+
+        if let Some((node_id, action)) = response.context_action() {
+            match action.as_str() {
+                ISOLATE_SUBTREE_ACTION => { kinematic_tree.isolate_subtree(node_link_idx(&robot.0, node_id)); }
+                _ => { kinematic_tree.isolate_subtree(None); }
+            }
+        }
+
+        // tree selection -> viewport.
+        if response.selection_changed() {
+            let selected_link_idx = response.selected().and_then(|x| node_link_idx(&robot.0, x));
+            for (id, mut selection, _) in link_query.iter_mut() {
+                if id.robot_instance_idx != robot_instance_idx { continue; }
+                let selected = Some(id.link_idx) == selected_link_idx;
+                if selection.is_selected != selected { selection.is_selected = selected; }
+            }
+        }
+        drop(mutex_guard);
+
+        if kinematic_tree.isolated_changed {
+            kinematic_tree.isolated_changed = false;
+            let shown_link_idxs = kinematic_tree.isolated_link_idx.map(|x| KinematicTreeActions::action_subtree_link_idxs(&robot.0, x));
+            for (id, _, mut visibility) in link_query.iter_mut() {
+                if id.robot_instance_idx != robot_instance_idx { continue; }
+                let shown = shown_link_idxs.as_ref().map(|x| x.contains(&id.link_idx)).unwrap_or(true);
+                *visibility = if shown { Visibility::Inherited } else { Visibility::Hidden };
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use optima_linalg::{OLinalgCategory, OVec};
+use optima_robotics::robot::FKResult;
+use crate::optima_bevy_utils::robotics::{robot_instance_label, BevyORobot, BevyORobotInstances, RobotStateEngine};
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+/// Text anchored to a link frame, e.g., a note on a tool or a sensor name.
+#[derive(Clone, Debug)]
+pub struct FrameAnnotation {
+    pub text: String,
+    pub robot_instance_idx: usize,
+    pub link_idx: usize,
+    /// Anchor point in the link frame.
+    pub offset: [f64; 3],
+    pub color: [u8; 3]
+}
+impl FrameAnnotation {
+    pub fn new(text: &str, robot_instance_idx: usize, link_idx: usize) -> Self {
+        Self { text: text.to_string(), robot_instance_idx, link_idx, offset: [0.0; 3], color: [255, 230, 120] }
+    }
+}
+
+/// Billboard text drawn over the viewport at link frames.  Link names are shown for links whose
+/// "Show Label" box is checked in the link panel (with the name of the joint leading into the link if
+/// `show_joint_names` is set), and annotations are shown at the links they are anchored to.  Also
+/// drives the "Labels" window.
+#[derive(Resource)]
+pub struct BevyFrameLabels {
+    annotations: Vec<FrameAnnotation>,
+    pub show_joint_names: bool,
+    pub show_annotations: bool,
+    pub font_size: f32
+}
+impl BevyFrameLabels {
+    pub fn new() -> Self {
+        Self { annotations: vec![], show_joint_names: false, show_annotations: true, font_size: 12.0 }
+    }
+    pub fn add_annotation(&mut self, annotation: FrameAnnotation) {
+        self.annotations.push(annotation);
+    }
+    pub fn remove_annotations_with_text(&mut self, text: &str) {
+        self.annotations.retain(|x| x.text != text);
+    }
+    pub fn clear_annotations(&mut self) {
+        self.annotations.clear();
+    }
+    #[inline(always)]
+    pub fn annotations(&self) -> &Vec<FrameAnnotation> {
+        &self.annotations
+    }
+}
+
+/// Id of the link panel checkbox that shows the link's label.
+pub (crate) fn link_label_toggle_id(link_name: &str, robot_instance_idx: usize) -> String {
+    robot_instance_label(format!("link_label_toggle_{}", link_name), robot_instance_idx)
+}
+
+pub struct FrameLabelSystems;
+impl FrameLabelSystems {
+    /// Labels are painted on egui's foreground layer, as seen from the camera rendering to the window.
+    /// Frames behind the camera are skipped.
+    pub fn system_draw_frame_labels<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(labels: Res<BevyFrameLabels>,
+                                                                                                       robot: Option<Res<BevyORobot<T, C, L>>>,
+                                                                                                       instances: Option<Res<BevyORobotInstances<T, C, L>>>,
+                                                                                                       robot_state_engine: Res<RobotStateEngine>,
+                                                                                                       egui_engine: Res<OEguiEngineWrapper>,
+                                                                                                       mut contexts: EguiContexts,
+                                                                                                       camera_query: Query<(&Camera, &GlobalTransform)>,
+                                                                                                       window_query: Query<&Window, With<PrimaryWindow>>) {
+        if window_query.get_single().is_err() { return; }
+        let Some((camera, camera_transform)) = camera_query.iter().find(|(camera, _)| camera.is_active && matches!(camera.target, RenderTarget::Window(_))) else { return; };
+
+        let mut robots = vec![];
+        if let Some(robot) = &robot { robots.push((&robot.0, robot.1, None)); }
+        if let Some(instances) = &instances {
+            instances.instances().iter().for_each(|x| robots.push((&x.robot, x.robot_instance_idx, Some(&x.base_pose))));
+        }
+
+        let painter = contexts.ctx_mut().layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("frame_labels")));
+        let font = egui::FontId::proportional(labels.font_size);
+        let to_screen = |p: &C::P<T>| {
+            let t = p.translation();
+            let point = TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(Vec3::new(t.x().to_constant() as f32, t.y().to_constant() as f32, t.z().to_constant() as f32));
+            camera.world_to_viewport(camera_transform, point).map(|x| egui::pos2(x.x, x.y))
+        };
+
+        let egui_engine = egui_engine.get_mutex_guard();
+        for (robot, robot_instance_idx, base_offset) in robots {
+            let Some(state) = robot_state_engine.get_robot_state(robot_instance_idx) else { continue; };
+            let fk_res = robot.forward_kinematics(&OVec::ovec_to_other_ad_type::<T>(state), base_offset);
+
+            for link in robot.links().iter().filter(|x| x.is_present_in_model()) {
+                let shown = egui_engine.get_checkbox_response(&link_label_toggle_id(link.name(), robot_instance_idx)).map(|x| x.currently_selected).unwrap_or(false);
+                if !shown { continue; }
+                let Ok(link_pose) = fk_res.get_link_pose(link.link_idx()) else { continue; };
+                let Some(pos) = to_screen(link_pose) else { continue; };
+
+                let mut text = link.name().to_string();
+                if labels.show_joint_names {
+                    if let Some(joint) = robot.joints().iter().find(|x| x.is_present_in_model() && x.child_link_idx() == link.link_idx()) {
+                        text = format!("{}\n[{}]", text, joint.name());
+                    }
+                }
+                painter.text(pos, egui::Align2::LEFT_BOTTOM, text, font.clone(), egui::Color32::WHITE);
+            }
+
+            if labels.show_annotations {
+                for annotation in labels.annotations.iter().filter(|x| x.robot_instance_idx == robot_instance_idx) {
+                    let Some(anchor) = annotation_pose(&fk_res, annotation) else { continue; };
+                    let Some(pos) = to_screen(&anchor) else { continue; };
+                    let [r, g, b] = annotation.color;
+                    painter.text(pos, egui::Align2::LEFT_BOTTOM, annotation.text.as_str(), font.clone(), egui::Color32::from_rgb(r, g, b));
+                }
+            }
+        }
+    }
+    pub fn system_frame_labels_panel(mut labels: ResMut<BevyFrameLabels>,
+                                     mut contexts: EguiContexts,
+                                     egui_engine: Res<OEguiEngineWrapper>,
+                                     window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Labels", true, true, false, false, false, true)
+            .show("frame_labels_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                let labels = &mut *labels;
+                ui.label("link names are toggled per link in the link panel.");
+                ui.checkbox(&mut labels.show_joint_names, "joint names");
+                ui.checkbox(&mut labels.show_annotations, "annotations");
+                ui.add(egui::Slider::new(&mut labels.font_size, 8.0..=24.0).text("font size"));
+            });
+    }
+}
+
+fn annotation_pose<T: AD, P: O3DPose<T>>(fk_res: &FKResult<T, P>, annotation: &FrameAnnotation) -> Option<P> {
+    let link_pose = fk_res.get_link_pose(annotation.link_idx).ok()?;
+    let offset = P::from_constructors(&[T::constant(annotation.offset[0]), T::constant(annotation.offset[1]), T::constant(annotation.offset[2])], &[T::zero(); 3]);
+    Some(link_pose.mul(&offset))
+}
@@ -0,0 +1,147 @@
+use std::sync::Mutex;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
+use nalgebra::Point3;
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use optima_linalg::{OLinalgCategory, OVec};
+use optima_proximity::ray_casting::{OLidarConfig, OLidarScan, OParryRayCaster};
+use optima_proximity::shape_scene::ShapeSceneTrait;
+use crate::optima_bevy_utils::robotics::{BevyORobot, RobotStateEngine};
+use crate::optima_bevy_utils::shape_scene::BevyEnvironmentObjects;
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+/// Where a lidar is mounted: a frame relative to a robot link (x forward, z up).
+#[derive(Clone, Debug)]
+pub struct LidarMount {
+    pub name: String,
+    pub link_idx: usize,
+    pub offset_translation: [f64; 3],
+    /// roll, pitch, yaw (radians) of the sensor frame relative to the link.
+    pub offset_rpy: [f64; 3],
+    pub config: OLidarConfig
+}
+impl LidarMount {
+    pub fn new(name: &str, link_idx: usize, config: OLidarConfig) -> Self {
+        Self { name: name.to_string(), link_idx, offset_translation: [0.0; 3], offset_rpy: [0.0; 3], config }
+    }
+}
+
+struct BevyLidar {
+    mount: LidarMount,
+    latest_scan: Option<OLidarScan<f64>>,
+    subscribers: Mutex<Vec<Sender<OLidarScan<f64>>>>
+}
+
+/// Lidars added through `optima_bevy_lidar`.  Every frame, each lidar's beams are cast from its link
+/// against the robot's collision shapes (and any `BevyEnvironmentObjects`).  Scans are in world
+/// space (z up), and can be polled with `latest_scan` or streamed over a channel from `subscribe`.
+#[derive(Resource)]
+pub struct BevyLidars {
+    lidars: Vec<BevyLidar>,
+    pub show_beams: bool,
+    pub show_misses: bool,
+    pub show_hit_points: bool
+}
+impl BevyLidars {
+    pub fn new() -> Self {
+        Self { lidars: vec![], show_beams: true, show_misses: false, show_hit_points: true }
+    }
+    pub fn add_lidar(&mut self, mount: LidarMount) {
+        self.lidars.push(BevyLidar { mount, latest_scan: None, subscribers: Mutex::new(vec![]) });
+    }
+    pub fn latest_scan(&self, name: &str) -> Option<&OLidarScan<f64>> {
+        self.lidars.iter().find(|x| x.mount.name == name).and_then(|x| x.latest_scan.as_ref())
+    }
+    /// Every scan of the lidar from now on is also sent through the returned channel.
+    pub fn subscribe(&self, name: &str) -> Result<Receiver<OLidarScan<f64>>, String> {
+        let lidar = self.lidars.iter().find(|x| x.mount.name == name).ok_or(format!("no lidar named {}.", name))?;
+        let (tx, rx) = channel();
+        lidar.subscribers.lock().unwrap().push(tx);
+        Ok(rx)
+    }
+    pub fn lidar_names(&self) -> Vec<String> {
+        self.lidars.iter().map(|x| x.mount.name.clone()).collect()
+    }
+}
+
+pub struct LidarSystems;
+impl LidarSystems {
+    pub fn system_update_lidars<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                 robot_state_engine: Res<RobotStateEngine>,
+                                                                                                 environment_objects: Option<Res<BevyEnvironmentObjects<T, C>>>,
+                                                                                                 mut lidars: ResMut<BevyLidars>) {
+        let state = match robot_state_engine.get_robot_state(robot.1) {
+            None => { vec![T::zero(); robot.0.num_dofs()] }
+            Some(state) => { OVec::ovec_to_other_ad_type::<T>(state) }
+        };
+        let fk_res = robot.0.forward_kinematics(&state, None);
+
+        let robot_poses = robot.get_shape_poses(&state);
+        let environment_poses = environment_objects.as_ref().map(|x| x.shape_scene().get_shape_poses(&()).into_owned());
+        let mut caster = OParryRayCaster::new();
+        if let Err(e) = caster.add_shapes(robot.get_shapes(), robot_poses.as_ref()) { warn!("the robot's shapes were left out of the lidar scans ({}).", e); }
+        if let (Some(environment_objects), Some(environment_poses)) = (&environment_objects, &environment_poses) {
+            if let Err(e) = caster.add_shapes(environment_objects.shape_scene().get_shapes(), environment_poses) { warn!("the environment objects were left out of the lidar scans ({}).", e); }
+        }
+
+        for lidar in lidars.lidars.iter_mut() {
+            let Ok(link_pose) = fk_res.get_link_pose(lidar.mount.link_idx) else { continue; };
+            let m = &lidar.mount;
+            let offset = C::P::<T>::from_constructors(&[T::constant(m.offset_translation[0]), T::constant(m.offset_translation[1]), T::constant(m.offset_translation[2])], &[T::constant(m.offset_rpy[0]), T::constant(m.offset_rpy[1]), T::constant(m.offset_rpy[2])]);
+            let sensor_pose = link_pose.mul(&offset);
+
+            // the link the lidar is mounted on would otherwise block every beam.
+            let mut caster = caster.clone();
+            let t = sensor_pose.translation();
+            caster.remove_shapes_containing_point(&Point3::new(t.x(), t.y(), t.z()));
+
+            let scan = m.config.scan(&caster, &sensor_pose).to_other_ad_type::<f64>();
+            lidar.subscribers.lock().unwrap().retain(|x| x.send(scan.clone()).is_ok());
+            lidar.latest_scan = Some(scan);
+        }
+    }
+    pub fn system_draw_lidar_beams(lidars: Res<BevyLidars>, mut gizmos: Gizmos) {
+        let to_bevy = |x: f64, y: f64, z: f64| TransformUtils::util_convert_z_up_vec3_to_y_up_bevy_vec3(Vec3::new(x as f32, y as f32, z as f32));
+
+        for scan in lidars.lidars.iter().filter_map(|x| x.latest_scan.as_ref()) {
+            let origin = to_bevy(scan.origin.x, scan.origin.y, scan.origin.z);
+            for (dir, hit) in scan.directions.iter().zip(scan.hits.iter()) {
+                match hit {
+                    None => {
+                        if lidars.show_beams && lidars.show_misses {
+                            let end = scan.origin + dir * scan.max_range;
+                            gizmos.line(origin, to_bevy(end.x, end.y, end.z), Color::rgba(0.6, 0.6, 0.6, 0.25));
+                        }
+                    }
+                    Some(hit) => {
+                        let point = to_bevy(hit.point.x, hit.point.y, hit.point.z);
+                        if lidars.show_beams { gizmos.line(origin, point, Color::rgba(0.1, 0.8, 1.0, 0.5)); }
+                        if lidars.show_hit_points { gizmos.sphere(point, Quat::IDENTITY, 0.005, Color::rgb(1.0, 0.2, 0.6)); }
+                    }
+                }
+            }
+        }
+    }
+    pub fn system_lidar_panel(mut lidars: ResMut<BevyLidars>,
+                              mut contexts: EguiContexts,
+                              egui_engine: Res<OEguiEngineWrapper>,
+                              window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Lidar", true, true, false, false, false, true)
+            .show("lidar_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                let lidars = &mut *lidars;
+                ui.checkbox(&mut lidars.show_beams, "beams");
+                ui.checkbox(&mut lidars.show_misses, "misses");
+                ui.checkbox(&mut lidars.show_hit_points, "hit points");
+                ui.separator();
+                for lidar in lidars.lidars.iter() {
+                    let num_hits = lidar.latest_scan.as_ref().map(|x| x.hits.iter().filter(|hit| hit.is_some()).count()).unwrap_or(0);
+                    ui.label(format!("{}: {} / {} beams hit", lidar.mount.name, num_hits, lidar.mount.config.num_beams()));
+                }
+            });
+    }
+}
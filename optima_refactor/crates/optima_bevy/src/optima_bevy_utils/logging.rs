@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use optima_console::logging::{OLogBuffer, OLogLevel};
+
+/// Recent log events from the subscriber installed by `optima_bevy_base_with_log_config`.
+#[derive(Resource, Clone)]
+pub struct BevyLogBuffer(pub OLogBuffer);
+
+#[derive(Resource)]
+pub struct BevyLogPanelState {
+    pub min_level: OLogLevel,
+    pub target_filter: String,
+    pub show_spans: bool
+}
+impl BevyLogPanelState {
+    pub fn new() -> Self {
+        Self { min_level: OLogLevel::Info, target_filter: String::new(), show_spans: false }
+    }
+}
+
+pub struct LogPanelSystems;
+impl LogPanelSystems {
+    pub fn system_log_panel(buffer: Option<Res<BevyLogBuffer>>,
+                            mut state: ResMut<BevyLogPanelState>,
+                            mut contexts: EguiContexts,
+                            egui_engine: Res<OEguiEngineWrapper>,
+                            window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Log", true, true, false, false, true, false)
+            .show("log_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                let buffer = match &buffer {
+                    None => {
+                        ui.label("Logging was not initialized through optima (e.g., logs go to the browser console on the web).");
+                        return;
+                    }
+                    Some(buffer) => { buffer }
+                };
+
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("log_level")
+                        .selected_text(state.min_level.as_str())
+                        .show_ui(ui, |ui| {
+                            for level in [OLogLevel::Error, OLogLevel::Warn, OLogLevel::Info, OLogLevel::Debug, OLogLevel::Trace] {
+                                ui.selectable_value(&mut state.min_level, level, level.as_str());
+                            }
+                        });
+                    ui.label("target: ");
+                    ui.text_edit_singleline(&mut state.target_filter);
+                    ui.checkbox(&mut state.show_spans, "spans");
+                    if ui.button("clear").clicked() { buffer.0.clear(); }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for record in buffer.0.records() {
+                            if record.level > state.min_level { continue; }
+                            if !state.target_filter.is_empty() && !record.target.contains(&state.target_filter) { continue; }
+
+                            let color = match record.level {
+                                OLogLevel::Error => { egui::Color32::from_rgb(230, 80, 80) }
+                                OLogLevel::Warn => { egui::Color32::from_rgb(230, 190, 60) }
+                                OLogLevel::Info => { egui::Color32::LIGHT_GRAY }
+                                _ => { egui::Color32::GRAY }
+                            };
+                            let mut text = format!("{:>5} {}: {}", record.level.as_str().to_uppercase(), record.target, record.message);
+                            if state.show_spans && !record.spans.is_empty() { text = format!("{} [{}]", text, record.spans.join(" > ")); }
+                            ui.colored_label(color, egui::RichText::new(text).monospace());
+                        }
+                    });
+            });
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use bevy::prelude::{warn, Assets, Color, Handle, Image, Mesh, Resource};
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use gltf::image::Format;
+use nalgebra::{Point3, Vector3};
+use optima_3d_mesh::gltf_scene::gltf_triangle_primitives;
+use optima_3d_mesh::OTriMesh;
+use optima_error::OptimaError;
+use optima_file::path::OStemCellPath;
+
+/// The parts of a mesh file's material that carry over to a bevy `StandardMaterial`.
+#[derive(Clone, Debug)]
+pub struct MeshFileMaterial {
+    /// diffuse color, with the material's opacity as alpha.  None if the file does not give one.
+    pub base_color: Option<Color>,
+    pub emissive: Color,
+    /// only given by gltf files.
+    pub metallic: Option<f32>,
+    /// only given by gltf files.
+    pub perceptual_roughness: Option<f32>,
+    /// diffuse texture, as an absolute path if the file gave a relative one.  Always None for meshes
+    /// without texture coordinates.
+    pub texture_path: Option<PathBuf>,
+    /// base color texture of a gltf file, which is decoded along with the file (whether it is embedded
+    /// in the file or not).  Always None for meshes without texture coordinates.
+    pub embedded_texture: Option<Handle<Image>>,
+    pub has_texture_coordinates: bool
+}
+impl Default for MeshFileMaterial {
+    fn default() -> Self {
+        Self { base_color: None, emissive: Color::BLACK, metallic: None, perceptual_roughness: None, texture_path: None, embedded_texture: None, has_texture_coordinates: false }
+    }
+}
+
+/// Meshes loaded from obj, dae, and gltf files, keyed by file path, so every robot instance (and
+/// every link) that uses the same file shares one set of mesh assets instead of parsing the file
+/// again.
+#[derive(Resource, Default)]
+pub struct BevyLinkMeshCache {
+    meshes: HashMap<String, Vec<(Handle<Mesh>, MeshFileMaterial)>>
+}
+impl BevyLinkMeshCache {
+    pub fn get_or_load(&mut self, path: &OStemCellPath, meshes: &mut Assets<Mesh>, images: &mut Assets<Image>) -> Result<Vec<(Handle<Mesh>, MeshFileMaterial)>, OptimaError> {
+        let key = path.to_string();
+        if let Some(cached) = self.meshes.get(&key) { return Ok(cached.clone()); }
+
+        let extension = path.extension().unwrap_or_default().to_lowercase();
+        let loaded = match extension.as_str() {
+            "gltf" | "glb" => { MeshUtils::util_load_gltf_as_bevy_meshes_with_materials(path, images)? }
+            _ => { MeshUtils::util_load_obj_or_dae_as_bevy_meshes_with_materials(path)? }
+        };
+        let loaded: Vec<(Handle<Mesh>, MeshFileMaterial)> = loaded.into_iter().map(|(mesh, material)| (meshes.add(mesh), material)).collect();
+        self.meshes.insert(key, loaded.clone());
+        Ok(loaded)
+    }
+    /// Forgets the meshes of the given file, e.g., after it changed on disk.
+    pub fn remove(&mut self, path: &OStemCellPath) {
+        self.meshes.remove(&path.to_string());
+    }
+}
+
+pub struct MeshUtils;
+impl MeshUtils {
+    /// Loads an obj or dae file as bevy meshes, each paired with the diffuse color of its material
+    /// (white if it has none).  Vertices are left in the file's own (z up) frame, same as the stl
+    /// meshes loaded through the asset server.
+    pub fn util_load_obj_or_dae_as_bevy_meshes(path: &OStemCellPath) -> Result<Vec<(Mesh, Color)>, OptimaError> {
+        Ok(Self::util_load_obj_or_dae_as_bevy_meshes_with_materials(path)?.into_iter().map(|(mesh, material)| (mesh, material.base_color.unwrap_or(Color::WHITE))).collect())
+    }
+    /// Same as `util_load_obj_or_dae_as_bevy_meshes`, but keeps the rest of each material (see
+    /// `MeshFileMaterial`) and the texture coordinates of meshes that have them.
+    pub fn util_load_obj_or_dae_as_bevy_meshes_with_materials(path: &OStemCellPath) -> Result<Vec<(Mesh, MeshFileMaterial)>, OptimaError> {
+        path.verify_extension(&vec!["obj", "OBJ", "dae", "DAE"])?;
+        let scene = path.load_mesh_scene()?;
+        let directory = path.as_physical_path().ok().and_then(|x| PathBuf::from(x.to_string()).parent().map(|x| x.to_path_buf()));
+
+        let mut out = vec![];
+        for (i, mesh) in scene.meshes.iter().enumerate() {
+            if mesh.vertices.is_empty() || mesh.faces.is_empty() { continue; }
+
+            let mut bevy_mesh = Mesh::new(PrimitiveTopology::TriangleList);
+            bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, mesh.vertices.clone());
+            if mesh.texcoords[0].len() == mesh.vertices.len() {
+                bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, mesh.texcoords[0].clone());
+            }
+            bevy_mesh.set_indices(Some(Indices::U32(mesh.faces.iter().flatten().copied().collect())));
+            if mesh.normals.len() == mesh.vertices.len() {
+                bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, mesh.normals.clone());
+            } else {
+                bevy_mesh.duplicate_vertices();
+                bevy_mesh.compute_flat_normals();
+            }
+
+            let mut material = MeshFileMaterial::default();
+            if let Some(m) = scene.materials.get(i) {
+                if let Some(c) = m.color.diffuse { material.base_color = Some(Color::rgba(c[0], c[1], c[2], c[3] * m.opacity.unwrap_or(1.0))); }
+                if let Some(c) = m.color.emissive { material.emissive = Color::rgb(c[0], c[1], c[2]); }
+                material.texture_path = m.texture.diffuse.as_ref().map(|x| match (x.is_relative(), &directory) {
+                    (true, Some(directory)) => { directory.join(x) }
+                    _ => { x.clone() }
+                });
+            }
+            // a texture is of no use without texture coordinates.
+            material.has_texture_coordinates = bevy_mesh.attribute(Mesh::ATTRIBUTE_UV_0).is_some();
+            if !material.has_texture_coordinates { material.texture_path = None; }
+
+            out.push((bevy_mesh, material));
+        }
+
+        Ok(out)
+    }
+    /// Loads a gltf or glb file as bevy meshes, one per triangle primitive, each with its material
+    /// (see `MeshFileMaterial`).  Node transforms are applied, and vertices are rotated from glTF's y
+    /// up frame into the z up frame of the other mesh files (see `gltf_triangle_primitives`).  Base
+    /// color textures are added to `images`.
+    pub fn util_load_gltf_as_bevy_meshes_with_materials(path: &OStemCellPath, images: &mut Assets<Image>) -> Result<Vec<(Mesh, MeshFileMaterial)>, OptimaError> {
+        path.verify_extension(&vec!["gltf", "GLTF", "glb", "GLB"])?;
+        let gltf = path.load_gltf()?;
+        // primitives that share a texture share its image asset.
+        let mut textures: HashMap<usize, Option<Handle<Image>>> = HashMap::new();
+
+        let mut out = vec![];
+        for (primitive, transform) in gltf_triangle_primitives(&gltf) {
+            let reader = primitive.reader(|x| gltf.buffers.get(x.index()).map(|x| &x.0[..]));
+            let Some(positions) = reader.read_positions() else { continue; };
+            let positions: Vec<[f32; 3]> = positions.map(|x| {
+                let p = transform.transform_point(&Point3::from(x));
+                [p.x, p.y, p.z]
+            }).collect();
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => { indices.into_u32().collect() }
+                None => { (0..positions.len() as u32).collect() }
+            };
+            if positions.is_empty() || indices.len() < 3 { continue; }
+            let num_vertices = positions.len();
+
+            let mut bevy_mesh = Mesh::new(PrimitiveTopology::TriangleList);
+            bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+            if let Some(uvs) = reader.read_tex_coords(0).map(|x| x.into_f32().collect::<Vec<[f32; 2]>>()).filter(|x| x.len() == num_vertices) {
+                bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+            }
+            bevy_mesh.set_indices(Some(Indices::U32(indices[..indices.len() - indices.len() % 3].to_vec())));
+            // normals are transformed by the inverse transpose, so they stay normal under non-uniform scales.
+            let normal_transform = transform.fixed_view::<3, 3>(0, 0).into_owned().try_inverse().map(|x| x.transpose());
+            let normals = reader.read_normals().map(|x| x.collect::<Vec<[f32; 3]>>()).filter(|x| x.len() == num_vertices);
+            match (normals, normal_transform) {
+                (Some(normals), Some(normal_transform)) => {
+                    let normals: Vec<[f32; 3]> = normals.iter().map(|x| {
+                        let n = (normal_transform * Vector3::from(*x)).normalize();
+                        [n.x, n.y, n.z]
+                    }).collect();
+                    bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+                }
+                _ => {
+                    bevy_mesh.duplicate_vertices();
+                    bevy_mesh.compute_flat_normals();
+                }
+            }
+
+            let mut material = MeshFileMaterial::default();
+            material.has_texture_coordinates = bevy_mesh.attribute(Mesh::ATTRIBUTE_UV_0).is_some();
+            // primitives without a material get the default gltf material, which is left to the urdf.
+            let m = primitive.material();
+            if m.index().is_some() {
+                let pbr = m.pbr_metallic_roughness();
+                let (c, e) = (pbr.base_color_factor(), m.emissive_factor());
+                material.base_color = Some(Color::rgba(c[0], c[1], c[2], c[3]));
+                material.emissive = Color::rgb(e[0], e[1], e[2]);
+                material.metallic = Some(pbr.metallic_factor());
+                material.perceptual_roughness = Some(pbr.roughness_factor());
+                if let (true, Some(info)) = (material.has_texture_coordinates, pbr.base_color_texture()) {
+                    let image_idx = info.texture().source().index();
+                    material.embedded_texture = textures.entry(image_idx).or_insert_with(|| {
+                        let image = gltf.images.get(image_idx).and_then(gltf_image_to_bevy_image);
+                        if image.is_none() { warn!("texture {} of {} is not an 8 bit image and was left out.", image_idx, path.to_string()); }
+                        image.map(|x| images.add(x))
+                    }).clone();
+                }
+            }
+
+            out.push((bevy_mesh, material));
+        }
+
+        Ok(out)
+    }
+    /// Flat shaded, with vertices left in the mesh's own frame.
+    pub fn util_trimesh_to_bevy_mesh(trimesh: &OTriMesh) -> Mesh {
+        let mut bevy_mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        let positions: Vec<[f32; 3]> = trimesh.points().iter().map(|x| [x[0] as f32, x[1] as f32, x[2] as f32]).collect();
+        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        bevy_mesh.set_indices(Some(Indices::U32(trimesh.indices_as_u32s().iter().flatten().copied().collect())));
+        bevy_mesh.duplicate_vertices();
+        bevy_mesh.compute_flat_normals();
+        bevy_mesh
+    }
+}
+
+/// Converts the 8 bit formats to rgba; other formats are rare for base color textures.
+fn gltf_image_to_bevy_image(image: &gltf::image::Data) -> Option<Image> {
+    let pixels: Vec<u8> = match image.format {
+        Format::R8G8B8A8 => { image.pixels.clone() }
+        Format::R8G8B8 => { image.pixels.chunks_exact(3).flat_map(|x| [x[0], x[1], x[2], 255]).collect() }
+        Format::R8G8 => { image.pixels.chunks_exact(2).flat_map(|x| [x[0], x[0], x[0], x[1]]).collect() }
+        Format::R8 => { image.pixels.iter().flat_map(|x| [*x, *x, *x, 255]).collect() }
+        _ => { return None; }
+    };
+    Some(Image::new(Extent3d { width: image.width, height: image.height, depth_or_array_layers: 1 }, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb))
+}
@@ -1,9 +1,56 @@
 pub mod camera;
+pub mod bounding_volumes;
+pub mod collision_geometry;
+pub mod contacts;
+pub mod joint_limits;
+pub mod keyframes;
+pub mod kinematic_tree;
+pub mod labels;
+pub mod lidar;
 pub mod transform;
+pub mod mesh;
 pub mod file;
 pub mod robotics;
 pub mod lights;
+pub mod environment;
 pub mod viewport_visuals;
 pub mod transform_widget;
 pub mod storage;
-pub mod shape_scene;
\ No newline at end of file
+pub mod shape_scene;
+pub mod web;
+pub mod logging;
+pub mod viewer_config;
+pub mod shortcuts;
+pub mod diagnostics;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod websocket;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod state_bridge;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shared_memory;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod hot_reload;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod background_task;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod preprocessing;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod egui_persistence;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod interactive_ik;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod readback;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sensors;
+pub mod trajectory_trail;
+pub mod transform_tree;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod capture;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod scene_file;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod trajectory_file;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod scene_export;
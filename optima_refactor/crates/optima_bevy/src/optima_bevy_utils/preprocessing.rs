@@ -0,0 +1,133 @@
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
+use optima_3d_spatial::optima_3d_pose::O3DPoseCategory;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use optima_console::progress::OProgressHandle;
+use optima_linalg::OLinalgCategory;
+use optima_robotics::robot::{ORobot, SaveRobot};
+use crate::optima_bevy_utils::background_task::{BackgroundTask, BackgroundTaskActions, BackgroundTaskStatus};
+use crate::optima_bevy_utils::robotics::BevyORobot;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BevyRobotPreprocessingJob {
+    /// Everything `ORobot::preprocess` does.
+    Full,
+    /// Only recomputes the average distances between shapes (see
+    /// `ORobot::parry_shape_scene_compute_average_distances`), e.g., after editing collision
+    /// geometry of a robot whose pair skips are still valid.
+    AverageDistances { num_samples: usize }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BevyRobotPreprocessingStatus {
+    Running,
+    Finished { saved: bool },
+    Cancelled,
+    Failed
+}
+
+/// Preprocesses a robot as a `BackgroundTask` so that the viewer stays responsive, and shows the
+/// progress in a window with a cancel button.  When preprocessing finishes, the preprocessed robot
+/// replaces the one in `BevyORobot` (and is saved, if requested).
+#[derive(Resource)]
+pub struct BevyRobotPreprocessing<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> {
+    task: BackgroundTask<ORobot<T, C, L>>,
+    job: BevyRobotPreprocessingJob,
+    status: BevyRobotPreprocessingStatus,
+    save: bool
+}
+impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> BevyRobotPreprocessing<T, C, L> {
+    /// If `save` is true, the robot is saved under its default name once it is done.
+    pub fn new(robot: ORobot<T, C, L>, save: bool) -> Self {
+        Self::new_with_job(robot, BevyRobotPreprocessingJob::Full, save)
+    }
+    pub fn new_with_job(robot: ORobot<T, C, L>, job: BevyRobotPreprocessingJob, save: bool) -> Self {
+        let task = BackgroundTask::spawn("robot preprocessing", move |progress| {
+            let mut robot = robot;
+            let save_robot = if save { SaveRobot::Save(None) } else { SaveRobot::DoNotSave };
+            let finished = match job {
+                BevyRobotPreprocessingJob::Full => { robot.preprocess_with_progress(save_robot, progress) }
+                BevyRobotPreprocessingJob::AverageDistances { num_samples } => { robot.parry_shape_scene_compute_average_distances_with_progress(save_robot, Some(num_samples), progress) }
+            };
+            if finished { Some(robot) } else { None }
+        });
+
+        Self { task, job, status: BevyRobotPreprocessingStatus::Running, save }
+    }
+    #[inline(always)]
+    pub fn progress(&self) -> &OProgressHandle {
+        self.task.progress()
+    }
+    #[inline(always)]
+    pub fn job(&self) -> BevyRobotPreprocessingJob {
+        self.job
+    }
+    #[inline(always)]
+    pub fn status(&self) -> &BevyRobotPreprocessingStatus {
+        &self.status
+    }
+    pub fn cancel(&self) {
+        self.task.cancel();
+    }
+    /// Returns the preprocessed robot once the task is done, and None while it is still running
+    /// (or if it was cancelled or failed).
+    fn poll(&mut self) -> Option<ORobot<T, C, L>> {
+        let out = self.task.poll();
+        self.status = match self.task.status() {
+            BackgroundTaskStatus::Running => { BevyRobotPreprocessingStatus::Running }
+            BackgroundTaskStatus::Finished => { BevyRobotPreprocessingStatus::Finished { saved: self.save } }
+            BackgroundTaskStatus::Cancelled => { BevyRobotPreprocessingStatus::Cancelled }
+            BackgroundTaskStatus::Failed => { BevyRobotPreprocessingStatus::Failed }
+        };
+        out
+    }
+}
+
+pub struct PreprocessingSystems;
+impl PreprocessingSystems {
+    pub fn system_robot_preprocessing<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(mut preprocessing: ResMut<BevyRobotPreprocessing<T, C, L>>,
+                                                                                                          mut robot: ResMut<BevyORobot<T, C, L>>,
+                                                                                                          egui_engine: Option<Res<OEguiEngineWrapper>>) {
+        let was_running = preprocessing.status == BevyRobotPreprocessingStatus::Running;
+        if let Some(new_robot) = preprocessing.poll() {
+            info!("finished preprocessing robot {}.", new_robot.robot_name());
+            robot.0 = new_robot;
+        }
+
+        let Some(egui_engine) = egui_engine else { return; };
+        if !was_running { return; }
+        let mut egui_engine = egui_engine.get_mutex_guard();
+        match preprocessing.status() {
+            BevyRobotPreprocessingStatus::Running => { }
+            BevyRobotPreprocessingStatus::Finished { saved } => {
+                egui_engine.push_info(&format!("finished preprocessing {}{}", robot.0.robot_name(), if *saved { " (saved)." } else { "." }));
+            }
+            BevyRobotPreprocessingStatus::Cancelled => { egui_engine.push_warning("robot preprocessing was cancelled."); }
+            BevyRobotPreprocessingStatus::Failed => { egui_engine.push_error("robot preprocessing failed (see the log)."); }
+        }
+    }
+
+    pub fn system_robot_preprocessing_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(preprocessing: Res<BevyRobotPreprocessing<T, C, L>>,
+                                                                                                                    mut contexts: EguiContexts,
+                                                                                                                    egui_engine: Res<OEguiEngineWrapper>,
+                                                                                                                    window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Preprocessing", true, true, false, false, false, true)
+            .show("preprocessing_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                match preprocessing.job() {
+                    BevyRobotPreprocessingJob::Full => { ui.label("full preprocessing"); }
+                    BevyRobotPreprocessingJob::AverageDistances { num_samples } => { ui.label(format!("average distances ({} samples)", num_samples)); }
+                }
+                BackgroundTaskActions::action_background_task_progress_egui(&preprocessing.task, "preprocessing_progress_bar", &egui_engine, ui);
+                match preprocessing.status() {
+                    BevyRobotPreprocessingStatus::Running => { }
+                    BevyRobotPreprocessingStatus::Finished { saved } => {
+                        ui.label(if *saved { "Done.  The preprocessed robot has been saved." } else { "Done." });
+                    }
+                    BevyRobotPreprocessingStatus::Cancelled => { ui.label("Nothing was saved."); }
+                    BevyRobotPreprocessingStatus::Failed => { }
+                }
+            });
+    }
+}
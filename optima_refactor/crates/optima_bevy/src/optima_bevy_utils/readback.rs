@@ -0,0 +1,111 @@
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{Buffer, BufferAsyncError, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout, Maintain, MapMode, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::renderer::{RenderContext, RenderDevice};
+
+/// An rgba8 image that cameras render into, together with the gpu buffer it is copied into after
+/// rendering and the channel the pixels are sent back to the main world through.  Lives in the
+/// render world; see `HeadlessRenderTarget` and `CameraSensorRenderTargets` for how it is used.
+///
+/// The buffer is mapped asynchronously, so rendering never waits on the gpu.  While a copy is still
+/// being mapped, newly rendered frames are not copied (i.e., they are dropped rather than queued).
+#[derive(Clone)]
+pub struct ImageReadbackTarget {
+    image: Handle<Image>,
+    buffer: Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    /// main world frame the current contents were rendered on.
+    pub (crate) frame: u64,
+    /// shared between the clones extracted into the render world every frame.
+    state: Arc<Mutex<ReadbackState>>,
+    frames: Arc<Mutex<Sender<(u64, Vec<u8>)>>>
+}
+
+enum ReadbackState {
+    Idle,
+    /// the image of this frame was copied into the buffer, but the copy has not been submitted yet.
+    Copied(u64),
+    /// the buffer holds the image of this frame and is being mapped.
+    Mapping(u64, Receiver<Result<(), BufferAsyncError>>)
+}
+impl ImageReadbackTarget {
+    /// Creates the image that is rendered into and the buffer it is read back through.  Frames are
+    /// received, tagged with the frame they were rendered on, through the returned channel.  Must be
+    /// called after the render plugin is added.
+    pub fn new(world: &mut World, width: u32, height: u32, label: &str) -> (Self, Receiver<(u64, Vec<u8>)>) {
+        let size = Extent3d { width, height, depth_or_array_layers: 1 };
+        let mut image = Image::new_fill(size, TextureDimension::D2, &[0, 0, 0, 255], TextureFormat::Rgba8UnormSrgb);
+        image.texture_descriptor.usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC | TextureUsages::TEXTURE_BINDING;
+        let image = world.resource_mut::<Assets<Image>>().add(image);
+
+        // rows of a texture to buffer copy have to be aligned to 256 bytes.
+        let padded_bytes_per_row = ((4 * width + 255) / 256) * 256;
+        let buffer = world.resource::<RenderDevice>().create_buffer(&BufferDescriptor {
+            label: Some(label),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let (frames_tx, frames_rx) = channel();
+        let target = Self { image, buffer, width, height, padded_bytes_per_row, frame: 0, state: Arc::new(Mutex::new(ReadbackState::Idle)), frames: Arc::new(Mutex::new(frames_tx)) };
+
+        (target, frames_rx)
+    }
+    #[inline(always)]
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+    /// Called from a render graph node once all cameras have rendered.  Does nothing while the
+    /// previous copy is still being read back.
+    pub fn copy_image_to_buffer(&self, render_context: &mut RenderContext, world: &World) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !matches!(*state, ReadbackState::Idle) { return; }
+        let Some(gpu_image) = world.resource::<RenderAssets<Image>>().get(&self.image) else { return; };
+
+        render_context.command_encoder().copy_texture_to_buffer(
+            gpu_image.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: ImageDataLayout { offset: 0, bytes_per_row: Some(self.padded_bytes_per_row), rows_per_image: None },
+            },
+            Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
+        );
+        *state = ReadbackState::Copied(self.frame);
+    }
+    /// Called in the render world after rendering: starts mapping this frame's copy (if one was
+    /// made), and once a mapping has finished, strips the row padding and sends the pixels back to the
+    /// main world.  Never blocks; the gpu is only polled.
+    pub fn read_back(&self, render_device: &RenderDevice) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let ReadbackState::Copied(frame) = *state {
+            let (tx, rx) = channel();
+            self.buffer.slice(..).map_async(MapMode::Read, move |res| { tx.send(res).ok(); });
+            *state = ReadbackState::Mapping(frame, rx);
+        }
+        render_device.wgpu_device().poll(Maintain::Poll);
+
+        let ReadbackState::Mapping(frame, rx) = &*state else { return; };
+        let frame = *frame;
+        match rx.try_recv() {
+            Err(TryRecvError::Empty) => { return; }
+            Ok(Ok(())) => {
+                let unpadded_bytes_per_row = (4 * self.width) as usize;
+                let mut bytes = Vec::with_capacity(unpadded_bytes_per_row * self.height as usize);
+                {
+                    let data = self.buffer.slice(..).get_mapped_range();
+                    data.chunks(self.padded_bytes_per_row as usize).for_each(|row| bytes.extend_from_slice(&row[..unpadded_bytes_per_row]));
+                }
+                self.buffer.unmap();
+                self.frames.lock().unwrap_or_else(|e| e.into_inner()).send((frame, bytes)).ok();
+            }
+            Ok(Err(e)) => { warn!("could not map the read back buffer of frame {} ({}).", frame, e); }
+            Err(TryRecvError::Disconnected) => { warn!("the read back buffer of frame {} was never mapped.", frame); }
+        }
+        *state = ReadbackState::Idle;
+    }
+}
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::path::PathBuf;
 use ad_trait::AD;
 use bevy::pbr::StandardMaterial;
 use bevy::prelude::*;
@@ -12,61 +13,154 @@ use bevy_prototype_debug_lines::DebugLines;
 use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
 use optima_3d_spatial::optima_3d_rotation::O3DRotation;
 use optima_3d_spatial::optima_3d_vec::O3DVec;
-use optima_bevy_egui::{OEguiButton, OEguiCheckbox, OEguiContainerTrait, OEguiEngineWrapper, OEguiSelector, OEguiSelectorMode, OEguiSidePanel, OEguiSlider, OEguiTopBottomPanel, OEguiWidgetTrait};
+#[cfg(not(target_arch = "wasm32"))]
+use optima_error::OptimaError;
+#[cfg(not(target_arch = "wasm32"))]
+use optima_file::path::{OAssetLocation, OPath};
+use optima_file::path::OStemCellPath;
+#[cfg(not(target_arch = "wasm32"))]
+use optima_bevy_egui::{OEguiFileDialog, OEguiFileDialogMode};
+use optima_bevy_egui::{OEguiButton, OEguiCheckbox, OEguiColorPicker, OEguiContainerTrait, OEguiEngineWrapper, OEguiSelector, OEguiSelectorMode, OEguiSidePanel, OEguiSlider, OEguiTable, OEguiTableCell, OEguiTableRow, OEguiTopBottomPanel, OEguiWidgetTrait, OEguiWindow};
 use optima_interpolation::InterpolatorTrait;
 use optima_linalg::{OLinalgCategory, OVec};
-use optima_proximity::pair_group_queries::{OPairGroupQryTrait, OParryDistanceGroupArgs, OParryDistanceGroupQry, OParryIntersectGroupArgs, OParryIntersectGroupQry, OParryPairSelector, OProximityLossFunction, OSkipReason, ToParryProximityOutputTrait};
+use optima_proximity::pair_group_queries::{OPairGroupQryTrait, OParryContactGroupArgs, OParryContactGroupQry, OParryDistanceGroupArgs, OParryDistanceGroupQry, OParryIntersectGroupArgs, OParryIntersectGroupQry, OParryPairIdxs, OParryPairSelector, OProximityLossFunction, OSkipReason, ToParryProximityOutputTrait};
 use optima_proximity::pair_queries::{ParryDisMode, ParryShapeRep};
-use optima_robotics::robot::{FKResult, ORobot, SaveRobot};
+use optima_proximity::shape_queries::IntersectOutputTrait;
+use optima_robotics::robot::{geometry_primitive_trimesh, FKResult, ORobot, SaveRobot};
+use optima_robotics::robotics_components::{OGeometry, OLink};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::capture::BevyViewportCapture;
+use crate::optima_bevy_utils::diagnostics::{BevyFrameTimings, TIMING_FK, TIMING_PROXIMITY};
+use crate::optima_bevy_utils::contacts::{BevyContactVisualization, ContactVis, ContactVisSystems};
 use crate::optima_bevy_utils::file::get_asset_path_str_from_ostemcellpath;
+use crate::optima_bevy_utils::joint_limits::BevyJointLimitHeatMap;
+use crate::optima_bevy_utils::labels::link_label_toggle_id;
+use crate::optima_bevy_utils::mesh::{BevyLinkMeshCache, MeshFileMaterial, MeshUtils};
+use crate::optima_bevy_utils::shape_scene::BevyEnvironmentObjects;
+use crate::optima_bevy_utils::shortcuts::{ShortcutMap, SHORTCUT_PLAY_PAUSE};
 use crate::optima_bevy_utils::transform::TransformUtils;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::optima_bevy_utils::trajectory_file::{BevyTrajectoryFile, RecordedTrajectory, TrajectoryFileSystems};
 use crate::{BevySystemSet, OptimaBevyTrait};
 use crate::optima_bevy_utils::storage::BevyAnyHashmap;
 use crate::optima_bevy_utils::viewport_visuals::ViewportVisualsActions;
+use crate::optima_bevy_utils::viewer_config::OptimaViewerConfig;
 use optima_proximity::shape_scene::ShapeSceneTrait;
 use optima_proximity::shapes::OParryShape;
 use optima_universal_hashmap::AHashMapWrapper;
 
 pub struct RoboticsActions;
 impl RoboticsActions {
+    /// Every visual of every link is spawned.  Visuals whose mesh is an obj, dae, or gltf file are
+    /// spawned from that file, with one entity per sub-mesh.  Other mesh visuals (or ones whose file
+    /// fails to load) use the link's stl mesh if they are the link's first visual, and are otherwise
+    /// loaded through the asset server if they are stl files.  Boxes, spheres, cylinders, and capsules
+    /// are built from their primitive shapes.  Materials are set up by `action_link_material`.
     pub fn action_spawn_robot_as_stl_meshes<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(robot: &ORobot<T, C, L>,
                                                                                                      fk_res: &FKResult<T, C::P<T>>,
                                                                                                      commands: &mut Commands,
                                                                                                      asset_server: &Res<AssetServer>,
+                                                                                                     meshes: &mut ResMut<Assets<Mesh>>,
                                                                                                      materials: &mut ResMut<Assets<StandardMaterial>>,
+                                                                                                     images: &mut ResMut<Assets<Image>>,
+                                                                                                     mesh_cache: &mut BevyLinkMeshCache,
                                                                                                      robot_instance_idx: usize) {
         robot.links().iter().enumerate().for_each(|(link_idx, link)| {
-            if link.is_present_in_model() {
-                let stl_mesh_file_path = link.stl_mesh_file_path();
-                if let Some(stl_mesh_file_path) = stl_mesh_file_path {
-                    let asset_path_str = get_asset_path_str_from_ostemcellpath(&stl_mesh_file_path);
-                    let link_pose = fk_res.get_link_pose(link_idx);
-                    if let Some(link_pose) = link_pose {
-                        let visual_offset = link.visual()[0].origin().pose();
-                        let link_pose = link_pose.mul(visual_offset);
-
-                        let transform = TransformUtils::util_convert_3d_pose_to_y_up_bevy_transform(&link_pose);
-
+            if !link.is_present_in_model() { return; }
+            let Ok(link_pose) = fk_res.get_link_pose(link_idx) else { return; };
+
+            for (visual_idx, visual) in link.visual().iter().enumerate() {
+                let mut transform = TransformUtils::util_convert_3d_pose_to_y_up_bevy_transform(&link_pose.mul(visual.origin().pose()));
+                transform.scale = visual_mesh_scale(visual.geometry());
+                let link_mesh_id = LinkMeshID {
+                    robot_instance_idx,
+                    sub_robot_idx: link.sub_robot_idx(),
+                    link_idx,
+                    visual_idx
+                };
+                let mesh_file_path = robot.link_visual_mesh_file_path(link_idx, visual_idx);
+
+                let file_meshes = mesh_file_path.as_ref().and_then(|x| mesh_cache.get_or_load(x, meshes, images).ok());
+                if let Some(file_meshes) = file_meshes {
+                    for (mesh, file_material) in file_meshes {
                         commands.spawn(PbrBundle {
-                            mesh: asset_server.load(&asset_path_str),
-                            material: materials.add(StandardMaterial::default()),
+                            mesh,
+                            material: materials.add(Self::action_link_material(link, visual_idx, mesh_file_path.as_ref(), Some(&file_material), asset_server)),
                             transform,
                             ..Default::default()
-                        }).insert(LinkMeshID {
-                            robot_instance_idx,
-                            sub_robot_idx: link.sub_robot_idx(),
-                            link_idx,
-                        });
+                        }).insert(link_mesh_id.clone());
                     }
+                    continue;
                 }
+
+                let mesh = match visual.geometry() {
+                    OGeometry::Mesh { .. } => {
+                        let stl_mesh_file_path = match visual_idx {
+                            0 => { link.stl_mesh_file_path().clone() }
+                            _ => { mesh_file_path.clone().filter(|x| x.extension().map(|e| e.to_lowercase() == "stl").unwrap_or(false)) }
+                        };
+                        match stl_mesh_file_path {
+                            Some(stl_mesh_file_path) => { asset_server.load(get_asset_path_str_from_ostemcellpath(&stl_mesh_file_path)) }
+                            None => {
+                                warn!("visual {} of link {} has no mesh that can be shown.", visual_idx, link.name());
+                                continue;
+                            }
+                        }
+                    }
+                    geometry => {
+                        let Some(trimesh) = geometry_primitive_trimesh(geometry) else { continue; };
+                        meshes.add(MeshUtils::util_trimesh_to_bevy_mesh(&trimesh))
+                    }
+                };
+                commands.spawn(PbrBundle {
+                    mesh,
+                    material: materials.add(Self::action_link_material(link, visual_idx, mesh_file_path.as_ref(), None, asset_server)),
+                    transform,
+                    ..Default::default()
+                }).insert(link_mesh_id);
             }
         });
     }
+    /// Material embedded in the visual's mesh file (dae / obj / gltf) if there is one, as in rviz,
+    /// and otherwise the color of the visual's urdf `<material>` (which may name a material defined
+    /// at the top of the urdf).  The urdf texture is used for meshes with texture coordinates whose
+    /// file gives none; it is looked up at the given path, then next to the mesh file (so
+    /// `package://` paths work once the texture is copied there).  Textures are not loaded from
+    /// paths on wasm.  Metallic, roughness, and alpha can be overridden per link in the link panel.
+    pub fn action_link_material<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(link: &OLink<T, C, L>, visual_idx: usize, mesh_file_path: Option<&OStemCellPath>, file_material: Option<&MeshFileMaterial>, asset_server: &Res<AssetServer>) -> StandardMaterial {
+        let urdf_material = link.visual().get(visual_idx).and_then(|x| x.material().as_ref());
+        let urdf_color = urdf_material.and_then(|x| x.color().as_ref()).map(|x| {
+            let c = x.rgba();
+            Color::rgba(c[0] as f32, c[1] as f32, c[2] as f32, c[3] as f32)
+        });
+
+        let mut out = StandardMaterial::default();
+        if let Some(color) = file_material.and_then(|x| x.base_color).or(urdf_color) { out.base_color = color; }
+        if let Some(file_material) = file_material {
+            out.emissive = file_material.emissive;
+            if let Some(metallic) = file_material.metallic { out.metallic = metallic; }
+            if let Some(roughness) = file_material.perceptual_roughness { out.perceptual_roughness = roughness; }
+            let urdf_texture_path = || -> Option<PathBuf> {
+                if !file_material.has_texture_coordinates { return None; }
+                let filename = urdf_material?.texture().as_ref()?.filename().to_string();
+                let mesh_directory = mesh_file_path.and_then(|x| x.as_physical_path().ok()).and_then(|x| PathBuf::from(x.to_string()).parent().map(|x| x.to_path_buf()));
+                resolve_urdf_texture_path(&filename, mesh_directory)
+            };
+            out.base_color_texture = match &file_material.embedded_texture {
+                Some(texture) => { Some(texture.clone()) }
+                None => { file_material.texture_path.clone().or_else(urdf_texture_path).and_then(|x| load_link_texture(x, asset_server)) }
+            };
+        }
+        if out.base_color.a() < 1.0 { out.alpha_mode = AlphaMode::Blend; }
+
+        out
+    }
     pub fn action_set_state_of_robot<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static, V: OVec<T>>(robot: &ORobot<T, C, L>,
                                                                                                           state: &V,
+                                                                                                          base_offset: Option<&C::P<T>>,
                                                                                                           robot_instance_idx: usize,
                                                                                                           query: &mut Query<(&LinkMeshID, &mut Transform)>) {
-        let fk_res = robot.forward_kinematics(state, None);
+        let fk_res = robot.forward_kinematics(state, base_offset);
         for (link_mesh_id, mut transform) in query.iter_mut() {
             let link_mesh_id: &LinkMeshID = &link_mesh_id;
             let transform: &mut Transform = &mut transform;
@@ -74,17 +168,25 @@ impl RoboticsActions {
             if link_mesh_id.robot_instance_idx == robot_instance_idx {
                 let link_idx = link_mesh_id.link_idx;
                 let link = &robot.links()[link_idx];
-                let pose = fk_res.get_link_pose(link_idx).as_ref().unwrap();
-                let visual_offset = link.visual()[0].origin().pose();
+                let pose = fk_res.get_link_pose_unchecked(link_idx);
+                let Some(visual) = link.visual().get(link_mesh_id.visual_idx) else { continue; };
+                let visual_offset = visual.origin().pose();
+                // keeps the mesh scale set when the link was spawned.
+                let scale = transform.scale;
                 *transform = TransformUtils::util_convert_3d_pose_to_y_up_bevy_transform(&(pose.mul(visual_offset)));
+                transform.scale = scale;
             }
         }
     }
+    /// Slider labels of robot instance 0 are the same as before multiple instances were supported, so
+    /// saved egui states still apply to it.
     pub fn action_robot_joint_sliders_egui<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(robot: &ORobot<T, C, L>,
+                                                                                                    robot_instance_idx: usize,
                                                                                                     robot_state_engine: &mut ResMut<RobotStateEngine>,
                                                                                                     egui_engine: &Res<OEguiEngineWrapper>,
                                                                                                     ui: &mut Ui) {
         let mut reset_clicked = false;
+        let mut edit_started = false;
         ui.horizontal(|ui| {
             ui.heading("Joint Sliders");
             reset_clicked = ui.button("Reset").clicked();
@@ -96,7 +198,7 @@ impl RoboticsActions {
                     robot.joints().iter().for_each(|joint| {
                         let dof_idxs = joint.dof_idxs();
                         for (i, dof_idx) in dof_idxs.iter().enumerate() {
-                            let label = format!("joint_slider_dof_{}", dof_idx);
+                            let label = robot_instance_label(format!("joint_slider_dof_{}", dof_idx), robot_instance_idx);
                             let lower = joint.limit().lower()[i];
                             let upper = joint.limit().upper()[i];
 
@@ -111,6 +213,7 @@ impl RoboticsActions {
                             let mut mutex_guard = egui_engine.get_mutex_guard();
                             let response = mutex_guard.get_slider_response_mut(&label).expect("error");
 
+                            let value_before_buttons = response.slider_value;
                             ui.horizontal(|ui| {
                                 if ui.button("0.0").clicked() { response.slider_value = 0.0; }
                                 if ui.button("+0.01").clicked() { response.slider_value += 0.01; }
@@ -118,6 +221,7 @@ impl RoboticsActions {
                                 if ui.button("+0.1").clicked() { response.slider_value += 0.1; }
                                 if ui.button("-0.1").clicked() { response.slider_value -= 0.1; }
                             });
+                            if response.slider_value != value_before_buttons { edit_started = true; }
                         }
                     });
                 });
@@ -128,28 +232,118 @@ impl RoboticsActions {
         let num_dofs = robot.num_dofs();
         let mut curr_state = vec![T::zero(); robot.num_dofs()];
         for i in 0..num_dofs {
-            let label = format!("joint_slider_dof_{}", i);
+            let label = robot_instance_label(format!("joint_slider_dof_{}", i), robot_instance_idx);
             let response = mutex_guard.get_slider_response_mut(&label).expect("error");
             if reset_clicked { response.slider_value = 0.0; }
+            // a drag is recorded once, when it starts.
+            let widget_response = response.widget_response();
+            if widget_response.drag_started() || (widget_response.changed() && !widget_response.dragged()) { edit_started = true; }
             let value = response.slider_value();
             curr_state[i] = T::constant(value);
         }
 
-        robot_state_engine.add_update_request(0, &OVec::ovec_to_other_ad_type::<T>(&curr_state));
+        if edit_started || reset_clicked { robot_state_engine.record_edit(robot_instance_idx, "joint sliders"); }
+        robot_state_engine.add_update_request(robot_instance_idx, &OVec::ovec_to_other_ad_type::<T>(&curr_state));
+    }
+    /// Moves the joint sliders of the given robot instance to `state`, so the robot stays there until
+    /// the sliders are moved again.  Sliders that have not been shown yet are skipped.
+    /// Sliders that have not been shown yet start at the given state.
+    pub fn action_set_joint_sliders(state: &Vec<f64>, robot_instance_idx: usize, egui_engine: &Res<OEguiEngineWrapper>) {
+        let mut mutex_guard = egui_engine.get_mutex_guard();
+        for (i, value) in state.iter().enumerate() {
+            mutex_guard.set_slider_value(&robot_instance_label(format!("joint_slider_dof_{}", i), robot_instance_idx), *value);
+        }
+    }
+    /// Undoes the most recent edit in the `RobotStateEngine`, and moves the joint sliders along with
+    /// the robot.  Returns false if there was nothing to undo.
+    pub fn action_undo_robot_state_edit(robot_state_engine: &mut RobotStateEngine, egui_engine: Option<&Res<OEguiEngineWrapper>>) -> bool {
+        let Some(entry) = robot_state_engine.undo() else { return false; };
+        if let Some(egui_engine) = egui_engine { Self::action_set_joint_sliders(&entry.state, entry.robot_instance_idx, egui_engine); }
+        true
+    }
+    pub fn action_redo_robot_state_edit(robot_state_engine: &mut RobotStateEngine, egui_engine: Option<&Res<OEguiEngineWrapper>>) -> bool {
+        let Some(entry) = robot_state_engine.redo() else { return false; };
+        if let Some(egui_engine) = egui_engine { Self::action_set_joint_sliders(&entry.state, entry.robot_instance_idx, egui_engine); }
+        true
+    }
+    /// Swaps `new_robot` in for the robot in `robot` and respawns its link meshes.  The current state
+    /// is kept if it still fits the new robot; otherwise the robot starts at all zeros and its state
+    /// history is cleared, since none of the recorded states fit anymore.
+    pub fn action_replace_robot<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: &mut BevyORobot<T, C, L>,
+                                                                                                  new_robot: ORobot<T, C, L>,
+                                                                                                  robot_state_engine: &mut RobotStateEngine,
+                                                                                                  commands: &mut Commands,
+                                                                                                  asset_server: &Res<AssetServer>,
+                                                                                                  meshes: &mut ResMut<Assets<Mesh>>,
+                                                                                                  materials: &mut ResMut<Assets<StandardMaterial>>,
+                                                                                                  images: &mut ResMut<Assets<Image>>,
+                                                                                                  mesh_cache: &mut BevyLinkMeshCache,
+                                                                                                  query: &Query<(Entity, &LinkMeshID)>) {
+        let robot_instance_idx = robot.1;
+        for (entity, link_mesh_id) in query.iter() {
+            if link_mesh_id.robot_instance_idx == robot_instance_idx { commands.entity(entity).despawn_recursive(); }
+        }
+        new_robot.links().iter().enumerate().for_each(|(link_idx, link)| {
+            if let Some(stl_mesh_file_path) = link.stl_mesh_file_path() {
+                asset_server.reload_asset(get_asset_path_str_from_ostemcellpath(&stl_mesh_file_path));
+            }
+            for visual_idx in 0..link.visual().len() {
+                if let Some(mesh_file_path) = new_robot.link_visual_mesh_file_path(link_idx, visual_idx) { mesh_cache.remove(&mesh_file_path); }
+            }
+        });
+
+        let num_dofs = new_robot.num_dofs();
+        let state: Vec<T> = match robot_state_engine.get_robot_state(robot_instance_idx) {
+            Some(state) if state.len() == num_dofs => { state.iter().map(|x| T::constant(*x)).collect() }
+            _ => {
+                robot_state_engine.clear_history_of_robot_instance(robot_instance_idx);
+                vec![T::zero(); num_dofs]
+            }
+        };
+        let fk_res = new_robot.forward_kinematics(&state, None);
+        Self::action_spawn_robot_as_stl_meshes(&new_robot, &fk_res, commands, asset_server, meshes, materials, images, mesh_cache, robot_instance_idx);
+        robot_state_engine.add_update_request(robot_instance_idx, &state);
+
+        robot.0 = new_robot;
+    }
+    /// Loads the robot a file (e.g., one picked in a file dialog) belongs to.  A urdf has to be in
+    /// its robot's folder in the asset folder's urdf_robots folder, since it is loaded with
+    /// `ORobot::from_urdf` so that its meshes are found.  Any other file is read as a saved robot
+    /// (see `ORobot::save_robot`).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn action_load_robot_from_path<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(path: &OPath) -> Result<ORobot<T, C, L>, OptimaError> {
+        if !path.extension().map(|x| x.to_lowercase() == "urdf").unwrap_or(false) {
+            return path.load_object_from_file::<ORobot<T, C, L>>().map_err(|e| OptimaError::new_file_io(path.to_string(), e));
+        }
+
+        let urdf_path = PathBuf::from(path.to_string());
+        let not_in_urdf_robots = || OptimaError::InvalidInput(format!("{} is not in a robot folder in the urdf_robots asset folder.", path.to_string()));
+        let robot_dir = urdf_path.parent().ok_or_else(not_in_urdf_robots)?;
+        let robot_name = robot_dir.file_name().and_then(|x| x.to_str()).ok_or_else(not_in_urdf_robots)?;
+        let mut urdf_robots = OStemCellPath::new_asset_path();
+        urdf_robots.append_file_location(&OAssetLocation::UrdfRobots);
+        let urdf_robots = PathBuf::from(urdf_robots.as_physical_path()?.to_string());
+        let same_dir = |a: &std::path::Path, b: &std::path::Path| a.canonicalize().ok().is_some_and(|a| b.canonicalize().ok() == Some(a));
+        if !robot_dir.parent().is_some_and(|x| same_dir(x, &urdf_robots)) { return Err(not_in_urdf_robots()); }
+
+        ORobot::from_urdf(robot_name)
     }
     pub fn action_robot_link_vis_panel_egui<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(robot: &ORobot<T, C, L>,
+                                                                                                     robot_instance_idx: usize,
+                                                                                                     base_offset: Option<&C::P<T>>,
                                                                                                      robot_state_engine: &RobotStateEngine,
                                                                                                      lines: &mut ResMut<DebugLines>,
                                                                                                      egui_engine: &Res<OEguiEngineWrapper>,
                                                                                                      ui: &mut Ui) {
-        let robot_state = robot_state_engine.get_robot_state(0);
+        let robot_state = robot_state_engine.get_robot_state(robot_instance_idx);
         let robot_state = match robot_state {
             None => { return; }
             Some(robot_state) => { robot_state }
         };
         let robot_state = OVec::ovec_to_other_ad_type::<T>(robot_state);
 
-        let fk_res = robot.forward_kinematics(&robot_state, None);
+        let fk_res = robot.forward_kinematics(&robot_state, base_offset);
+        let axis_length_label = robot_instance_label("link_axis_display_length".to_string(), robot_instance_idx);
 
         let mut select_all = false;
         let mut deselect_all = false;
@@ -161,7 +355,14 @@ impl RoboticsActions {
 
         ui.label("link axis display length");
         OEguiSlider::new(0.04, 1.0, 0.1)
-            .show("link_axis_display_length", ui, egui_engine, &());
+            .show(&axis_length_label, ui, egui_engine, &());
+        let axis_color_labels: Vec<String> = ["x", "y", "z"].iter().map(|x| robot_instance_label(format!("link_axis_color_{}", x), robot_instance_idx)).collect();
+        ui.horizontal(|ui| {
+            ui.label("link axis colors");
+            OEguiColorPicker::new([1., 0., 0., 1.], false).show(&axis_color_labels[0], ui, egui_engine, &());
+            OEguiColorPicker::new([0., 1., 0., 1.], false).show(&axis_color_labels[1], ui, egui_engine, &());
+            OEguiColorPicker::new([0., 0., 1., 1.], false).show(&axis_color_labels[2], ui, egui_engine, &());
+        });
 
         ui.group(|ui| {
             egui::ScrollArea::new([true, true])
@@ -171,7 +372,7 @@ impl RoboticsActions {
                     robot.links().iter().enumerate().for_each(|(link_idx, link)| {
                         if link.is_present_in_model() {
 
-                            let pose = fk_res.get_link_pose(link_idx).as_ref().unwrap();
+                            let pose = fk_res.get_link_pose_unchecked(link_idx);
                             let location = pose.translation();
                             let rotation = pose.rotation();
                             let scaled_axis = rotation.scaled_axis_of_rotation();
@@ -179,9 +380,29 @@ impl RoboticsActions {
                             let euler_angles = rotation.euler_angles();
                             ui.label(format!("Link {}", link_idx));
                             ui.label(format!("{}", link.name()));
-                            let toggle_label = format!("link_toggle_{}", link.name());
+                            let toggle_label = robot_instance_label(format!("link_toggle_{}", link.name()), robot_instance_idx);
                             OEguiCheckbox::new("Show Coordinate Frame")
                                 .show(&toggle_label, ui, &egui_engine, &());
+                            OEguiCheckbox::new("Show Label")
+                                .show(&link_label_toggle_id(link.name(), robot_instance_idx), ui, &egui_engine, &());
+                            ui.horizontal(|ui| {
+                                OEguiCheckbox::new("Custom Color")
+                                    .show(&link_color_toggle_id(link_idx, robot_instance_idx), ui, &egui_engine, &());
+                                OEguiColorPicker::new([0.8, 0.8, 0.8, 1.0], true)
+                                    .show(&link_color_id(link_idx, robot_instance_idx), ui, &egui_engine, &());
+                            });
+                            egui::CollapsingHeader::new("Material")
+                                .id_source(link_material_id("header", link_idx, robot_instance_idx))
+                                .show(ui, |ui| {
+                                    OEguiCheckbox::new("Override Material")
+                                        .show(&link_material_id("toggle", link_idx, robot_instance_idx), ui, &egui_engine, &());
+                                    ui.label("metallic");
+                                    OEguiSlider::new(0.0, 1.0, 0.0).show(&link_material_id("metallic", link_idx, robot_instance_idx), ui, &egui_engine, &());
+                                    ui.label("roughness");
+                                    OEguiSlider::new(0.089, 1.0, 0.5).show(&link_material_id("roughness", link_idx, robot_instance_idx), ui, &egui_engine, &());
+                                    ui.label("alpha");
+                                    OEguiSlider::new(0.0, 1.0, 1.0).show(&link_material_id("alpha", link_idx, robot_instance_idx), ui, &egui_engine, &());
+                                });
                             ui.label(format!("Location: {:.2?}", location));
                             ui.label(format!("quaternion wxyz: {:.2?}", unit_quaternion));
                             ui.label(format!("scaled axis: {:.2?}", scaled_axis));
@@ -193,7 +414,7 @@ impl RoboticsActions {
                             if deselect_all { response.currently_selected = false; }
 
                             if response.currently_selected {
-                                let draw_length = mutex_guard.get_slider_response("link_axis_display_length").unwrap().slider_value as f32;
+                                let draw_length = mutex_guard.get_slider_response(&axis_length_label).unwrap().slider_value as f32;
                                 let frame_vectors = rotation.coordinate_frame_vectors();
                                 let x = &frame_vectors[0];
                                 let x_as_vec = draw_length*Vec3::new(x[0].to_constant() as f32, x[1].to_constant() as f32, x[2].to_constant() as f32);
@@ -203,10 +424,11 @@ impl RoboticsActions {
                                 let z_as_vec = draw_length*Vec3::new(z[0].to_constant() as f32, z[1].to_constant() as f32, z[2].to_constant() as f32);
 
                                 let location_as_vec = Vec3::new(location.x().to_constant() as f32, location.y().to_constant() as f32, location.z().to_constant() as f32);
+                                let axis_colors: Vec<Color> = axis_color_labels.iter().zip([Color::rgb(1., 0., 0.), Color::rgb(0., 1., 0.), Color::rgb(0., 0., 1.)]).map(|(x, default)| mutex_guard.get_color_picker_response(x).map(|x| x.bevy_color()).unwrap_or(default)).collect();
 
-                                ViewportVisualsActions::action_draw_gpu_line_optima_space(lines, location_as_vec, location_as_vec + x_as_vec, Color::rgb(1., 0., 0.), 4.0, 10, 1, 0.0);
-                                ViewportVisualsActions::action_draw_gpu_line_optima_space(lines, location_as_vec, location_as_vec + y_as_vec, Color::rgb(0., 1., 0.), 4.0, 10, 1, 0.0);
-                                ViewportVisualsActions::action_draw_gpu_line_optima_space(lines, location_as_vec, location_as_vec + z_as_vec, Color::rgb(0., 0., 1.), 4.0, 10, 1, 0.0);
+                                ViewportVisualsActions::action_draw_gpu_line_optima_space(lines, location_as_vec, location_as_vec + x_as_vec, axis_colors[0], 4.0, 10, 1, 0.0);
+                                ViewportVisualsActions::action_draw_gpu_line_optima_space(lines, location_as_vec, location_as_vec + y_as_vec, axis_colors[1], 4.0, 10, 1, 0.0);
+                                ViewportVisualsActions::action_draw_gpu_line_optima_space(lines, location_as_vec, location_as_vec + z_as_vec, axis_colors[2], 4.0, 10, 1, 0.0);
                             }
 
                             ui.separator();
@@ -224,21 +446,45 @@ impl RoboticsSystems {
     pub fn system_spawn_robot_links_as_stl_meshes<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
                                                                                                                      mut commands: Commands,
                                                                                                                      asset_server: Res<AssetServer>,
-                                                                                                                     mut materials: ResMut<Assets<StandardMaterial>>) {
-        let robot = &robot.0;
-        let num_dofs = robot.num_dofs();
-        let fk_res = robot.forward_kinematics(&vec![T::zero(); num_dofs], None);
-        RoboticsActions::action_spawn_robot_as_stl_meshes(robot, &fk_res, &mut commands, &asset_server, &mut materials, 0);
+                                                                                                                     mut meshes: ResMut<Assets<Mesh>>,
+                                                                                                                     mut materials: ResMut<Assets<StandardMaterial>>,
+                                                                                                                     mut images: ResMut<Assets<Image>>,
+                                                                                                                     mut mesh_cache: ResMut<BevyLinkMeshCache>) {
+        let num_dofs = robot.0.num_dofs();
+        let fk_res = robot.0.forward_kinematics(&vec![T::zero(); num_dofs], None);
+        RoboticsActions::action_spawn_robot_as_stl_meshes(&robot.0, &fk_res, &mut commands, &asset_server, &mut meshes, &mut materials, &mut images, &mut mesh_cache, robot.1);
+    }
+    pub fn system_spawn_robot_instances<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(instances: Res<BevyORobotInstances<T, C, L>>,
+                                                                                                           mut commands: Commands,
+                                                                                                           asset_server: Res<AssetServer>,
+                                                                                                           mut meshes: ResMut<Assets<Mesh>>,
+                                                                                                           mut materials: ResMut<Assets<StandardMaterial>>,
+                                                                                                           mut images: ResMut<Assets<Image>>,
+                                                                                                           mut mesh_cache: ResMut<BevyLinkMeshCache>) {
+        instances.instances.iter().for_each(|instance| {
+            let fk_res = instance.robot.forward_kinematics(&vec![T::zero(); instance.robot.num_dofs()], Some(&instance.base_pose));
+            RoboticsActions::action_spawn_robot_as_stl_meshes(&instance.robot, &fk_res, &mut commands, &asset_server, &mut meshes, &mut materials, &mut images, &mut mesh_cache, instance.robot_instance_idx);
+        });
     }
-    pub fn system_robot_state_updater<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+    /// Requests for instances in `BevyORobotInstances` are applied with that instance's robot and base
+    /// pose; all other requests use the robot in `BevyORobot`.
+    pub fn system_robot_state_updater<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Option<Res<BevyORobot<T, C, L>>>,
+                                                                                                         instances: Option<Res<BevyORobotInstances<T, C, L>>>,
                                                                                                          mut robot_state_engine: ResMut<RobotStateEngine>,
+                                                                                                         timings: Option<Res<BevyFrameTimings>>,
                                                                                                          mut query: Query<(&LinkMeshID, &mut Transform)>) {
+        let _span = trace_span!("robot_state_updater", num_requests = robot_state_engine.robot_state_update_requests.len()).entered();
         while robot_state_engine.robot_state_update_requests.len() > 0 {
-            let robot = &robot.0;
             let request = robot_state_engine.robot_state_update_requests.pop().unwrap();
+            let instance = instances.as_ref().and_then(|x| x.get_instance(request.0));
+            let (robot, base_offset) = match (instance, &robot) {
+                (Some(instance), _) => { (&instance.robot, Some(&instance.base_pose)) }
+                (None, Some(robot)) => { (&robot.0, None) }
+                (None, None) => { continue; }
+            };
             let request_state: Vec<T> = request.1.iter().map(|x| T::constant(*x)).collect();
             robot_state_engine.robot_states.insert(request.0, OVec::ovec_to_other_ad_type::<f64>(&request_state));
-            RoboticsActions::action_set_state_of_robot(robot, &request_state, request.0, &mut query);
+            BevyFrameTimings::time(&timings, TIMING_FK, || RoboticsActions::action_set_state_of_robot(robot, &request_state, base_offset, request.0, &mut query));
         }
     }
     pub fn system_robot_main_info_panel_egui<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
@@ -251,19 +497,127 @@ impl RoboticsSystems {
             .show("joint_sliders_side_panel", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
                 egui::ScrollArea::new([true, true])
                     .show(ui, |ui| {
-                        RoboticsActions::action_robot_joint_sliders_egui(&robot.0, &mut robot_state_engine, &egui_engine, ui);
+                        #[cfg(not(target_arch = "wasm32"))]
+                        ui.horizontal(|ui| {
+                            ui.label(robot.0.robot_name());
+                            if ui.button("Open robot...").clicked() { OEguiFileDialog::open("robot_open_dialog", &egui_engine); }
+                        });
+                        RoboticsActions::action_robot_joint_sliders_egui(&robot.0, robot.1, &mut robot_state_engine, &egui_engine, ui);
                         ui.separator();
-                        RoboticsActions::action_robot_link_vis_panel_egui(&robot.0, & *robot_state_engine, &mut lines, &egui_engine, ui);
+                        RoboticsActions::action_robot_link_vis_panel_egui(&robot.0, robot.1, None, & *robot_state_engine, &mut lines, &egui_engine, ui);
                     });
             });
     }
+    /// Shows the dialog opened by the "Open robot..." button of the main info panel, and swaps in the
+    /// picked robot (see `RoboticsActions::action_load_robot_from_path`).  If it cannot be loaded,
+    /// the current robot is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn system_robot_open_dialog<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(mut robot: ResMut<BevyORobot<T, C, L>>,
+                                                                                                       mut robot_state_engine: ResMut<RobotStateEngine>,
+                                                                                                       mut commands: Commands,
+                                                                                                       asset_server: Res<AssetServer>,
+                                                                                                       mut meshes: ResMut<Assets<Mesh>>,
+                                                                                                       mut materials: ResMut<Assets<StandardMaterial>>,
+                                                                                                       mut images: ResMut<Assets<Image>>,
+                                                                                                       mut mesh_cache: ResMut<BevyLinkMeshCache>,
+                                                                                                       query: Query<(Entity, &LinkMeshID)>,
+                                                                                                       mut contexts: EguiContexts,
+                                                                                                       egui_engine: Res<OEguiEngineWrapper>) {
+        OEguiFileDialog::new("Open Robot", OEguiFileDialogMode::Open)
+            .show("robot_open_dialog", contexts.ctx_mut(), &egui_engine);
+        let Some(path) = egui_engine.get_mutex_guard().get_file_dialog_response("robot_open_dialog").and_then(|x| x.picked().cloned()) else { return; };
+
+        // loading a urdf converts its meshes, which can panic on malformed files.
+        let load_res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| RoboticsActions::action_load_robot_from_path::<T, C, L>(&path)));
+        let new_robot = match load_res {
+            Ok(Ok(new_robot)) => { new_robot }
+            Ok(Err(e)) => {
+                warn!("could not open robot {} ({}); keeping the current robot.", path.to_string(), e);
+                egui_engine.get_mutex_guard().push_error(&format!("could not open robot {}: {}", path.to_string(), e));
+                return;
+            }
+            Err(_) => {
+                warn!("could not open robot {}; keeping the current robot.", path.to_string());
+                egui_engine.get_mutex_guard().push_error(&format!("could not open robot {}.", path.to_string()));
+                return;
+            }
+        };
+
+        let robot_name = new_robot.robot_name().to_string();
+        RoboticsActions::action_replace_robot(&mut robot, new_robot, &mut robot_state_engine, &mut commands, &asset_server, &mut meshes, &mut materials, &mut images, &mut mesh_cache, &query);
+        info!("opened robot {}.", robot_name);
+        egui_engine.get_mutex_guard().push_info(&format!("opened robot {}.", robot_name));
+    }
+    /// Edits that can be undone, newest first.  Clicking an entry undoes (or redoes) every edit up to
+    /// and including it.
+    pub fn system_robot_state_history_panel(mut robot_state_engine: ResMut<RobotStateEngine>,
+                                            mut contexts: EguiContexts,
+                                            egui_engine: Res<OEguiEngineWrapper>,
+                                            window_query: Query<&Window, With<PrimaryWindow>>) {
+        let mut num_undos = 0;
+        let mut num_redos = 0;
+        let mut clear = false;
+
+        OEguiWindow::new("State History", true, true, false, false, false, true)
+            .show("robot_state_history_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                let undo_stack = robot_state_engine.undo_stack();
+                let redo_stack = robot_state_engine.redo_stack();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!undo_stack.is_empty(), egui::Button::new("Undo")).clicked() { num_undos = 1; }
+                    if ui.add_enabled(!redo_stack.is_empty(), egui::Button::new("Redo")).clicked() { num_redos = 1; }
+                    if ui.button("Clear").clicked() { clear = true; }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for (i, entry) in redo_stack.iter().enumerate() {
+                        let text = egui::RichText::new(format!("robot {}: {}", entry.robot_instance_idx, entry.label)).weak();
+                        if ui.selectable_label(false, text).clicked() { num_redos = redo_stack.len() - i; }
+                    }
+                    ui.label("▶ current state");
+                    for (i, entry) in undo_stack.iter().enumerate().rev() {
+                        if ui.selectable_label(false, format!("robot {}: {}", entry.robot_instance_idx, entry.label)).clicked() { num_undos = undo_stack.len() - i; }
+                    }
+                });
+            });
+
+        for _ in 0..num_undos { RoboticsActions::action_undo_robot_state_edit(&mut robot_state_engine, Some(&egui_engine)); }
+        for _ in 0..num_redos { RoboticsActions::action_redo_robot_state_edit(&mut robot_state_engine, Some(&egui_engine)); }
+        if clear { robot_state_engine.clear_history(); }
+    }
+    /// One window per robot instance in `BevyORobotInstances`, each with its own joint sliders and
+    /// link panel.
+    pub fn system_robot_instances_panels_egui<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(instances: Res<BevyORobotInstances<T, C, L>>,
+                                                                                                                 mut lines: ResMut<DebugLines>,
+                                                                                                                 mut contexts: EguiContexts,
+                                                                                                                 mut robot_state_engine: ResMut<RobotStateEngine>,
+                                                                                                                 egui_engine: Res<OEguiEngineWrapper>,
+                                                                                                                 window_query: Query<&Window, With<PrimaryWindow>>) {
+        instances.instances.iter().for_each(|instance| {
+            let title = format!("Robot {} ({})", instance.robot_instance_idx, instance.robot.robot_name());
+            OEguiWindow::new(&title, true, true, false, true, true, true)
+                .show(&format!("robot_instance_window_{}", instance.robot_instance_idx), contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                    RoboticsActions::action_robot_joint_sliders_egui(&instance.robot, instance.robot_instance_idx, &mut robot_state_engine, &egui_engine, ui);
+                    ui.separator();
+                    RoboticsActions::action_robot_link_vis_panel_egui(&instance.robot, instance.robot_instance_idx, Some(&instance.base_pose), & *robot_state_engine, &mut lines, &egui_engine, ui);
+                });
+        });
+    }
     pub fn system_robot_motion_interpolator<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V> + 'static>(interpolator: Res<BevyRobotInterpolator<T, V, I>>,
                                                                                                      mut contexts: EguiContexts,
                                                                                                      mut robot_state_engine: ResMut<RobotStateEngine>,
                                                                                                      mut h: ResMut<BevyAnyHashmap>,
                                                                                                      egui_engine: Res<OEguiEngineWrapper>,
                                                                                                      time: Res<Time>,
+                                                                                                     shortcuts: Option<Res<ShortcutMap>>,
+                                                                                                     #[cfg(not(target_arch = "wasm32"))]
+                                                                                                     mut capture: Option<ResMut<BevyViewportCapture>>,
                                                                                                      window_query: Query<&Window, With<PrimaryWindow>>) {
+        // while a frame-stepped recording runs, playback follows the captured frames instead of the clock.
+        #[cfg(not(target_arch = "wasm32"))]
+        let delta_seconds = capture.as_ref().and_then(|x| x.frame_stepped_playback_delta()).unwrap_or(time.delta_seconds_f64());
+        #[cfg(target_arch = "wasm32")]
+        let delta_seconds = time.delta_seconds_f64();
+
         OEguiTopBottomPanel::new(TopBottomSide::Bottom, 100.0)
             .show("interpolator_bottom_pannel", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
                 ui.horizontal(|ui| {
@@ -284,9 +638,13 @@ impl RoboticsSystems {
                     OEguiSlider::new(0.0, 3.0, 1.0)
                         .show("speed_slider", ui, &egui_engine, &());
 
+                    let mut looping = h.0.get_or_insert(&"looping".to_string(), true).clone();
+                    if ui.checkbox(&mut looping, "loop").changed() { h.0.insert("looping".to_string(), looping); }
+
                     let binding = egui_engine.get_mutex_guard();
                     let response = binding.get_button_response("play_stop").unwrap();
-                    if response.widget_response().clicked() { h.0.insert("playing".to_string(), !playing); }
+                    let shortcut_pressed = shortcuts.as_ref().map(|x| x.just_triggered(SHORTCUT_PLAY_PAUSE)).unwrap_or(false);
+                    if response.widget_response().clicked() || shortcut_pressed { h.0.insert("playing".to_string(), !playing); }
                     drop(binding);
 
                     if playing {
@@ -294,8 +652,17 @@ impl RoboticsSystems {
                         let response2 = binding.get_slider_response("speed_slider").unwrap();
                         let speed = response2.slider_value.clone();
                         let response = binding.get_slider_response_mut("playback_slider").unwrap();
-                        response.slider_value += speed * time.delta_seconds_f64();
-                        if response.slider_value > interpolator.0.max_t().to_constant() { response.slider_value = 0.0; }
+                        response.slider_value += speed * delta_seconds;
+                        if response.slider_value > interpolator.0.max_t().to_constant() {
+                            if looping {
+                                response.slider_value = 0.0;
+                            } else {
+                                response.slider_value = interpolator.0.max_t().to_constant();
+                                h.0.insert("playing".to_string(), false);
+                            }
+                            #[cfg(not(target_arch = "wasm32"))]
+                            if let Some(capture) = &mut capture { capture.notify_playback_end(); }
+                        }
                     }
                 });
             });
@@ -305,7 +672,8 @@ impl RoboticsSystems {
         if let Some(slider_result) = slider_result {
             if slider_result.widget_response().dragged() { h.0.insert("playing".to_string(), false); }
 
-            let slider_value = slider_result.slider_value;
+            // the interpolator may have been swapped for a shorter one (e.g., a trimmed trajectory).
+            let slider_value = slider_result.slider_value.min(interpolator.0.max_t().to_constant());
 
             let state = interpolator.0.interpolate(T::constant(slider_value));
             robot_state_engine.add_update_request(0, &state);
@@ -313,18 +681,29 @@ impl RoboticsSystems {
     }
     pub fn system_robot_self_collision_vis<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(mut robot: ResMut<BevyORobot<T, C, L>>,
                                                                                                               mut robot_state_engine: ResMut<RobotStateEngine>,
+                                                                                                              mut highlights: ResMut<RobotLinkCollisionHighlights>,
+                                                                                                              mut contact_vis: ResMut<BevyContactVisualization>,
+                                                                                                              environment_objects: Option<Res<BevyEnvironmentObjects<T, C>>>,
+                                                                                                              timings: Option<Res<BevyFrameTimings>>,
                                                                                                               mut contexts: EguiContexts,
                                                                                                               egui_engine: Res<OEguiEngineWrapper>,
                                                                                                               keys: Res<Input<KeyCode>>,
                                                                                                               window_query: Query<&Window, With<PrimaryWindow>>) {
+        // cleared every frame and refilled below, so nothing stays highlighted once the queries stop
+        // running (e.g., no state yet or no selection).
+        highlights.robot_instance_idx = robot.1;
+        highlights.in_collision_link_idxs.clear();
+        highlights.near_contact_link_idxs.clear();
+        highlights.selected_pair_link_idxs = None;
+
         OEguiSidePanel::new(Side::Left, 300.0)
             .show("side_panel", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
                 egui::ScrollArea::new([true, true])
                     .show(ui, |ui| {
-                        RoboticsActions::action_robot_joint_sliders_egui(&robot.0, &mut robot_state_engine, &egui_engine, ui);
+                        RoboticsActions::action_robot_joint_sliders_egui(&robot.0, robot.1, &mut robot_state_engine, &egui_engine, ui);
 
                         ui.group(|ui| {
-                            let state = robot_state_engine.get_robot_state(0);
+                            let state = robot_state_engine.get_robot_state(robot.1);
                             if let Some(state) = state {
                                 let state = OVec::ovec_to_other_ad_type::<T>(state);
                                 // let p = robot.0.parry_shape_scene().get_shape_poses(&(&robot.0, &state));
@@ -338,14 +717,14 @@ impl RoboticsSystems {
                                 let parry_shape_rep_response = binding.get_selector_response("selector2");
 
                                 if let (Some(parry_pair_selector_response), Some(parry_shape_rep_response)) = (parry_pair_selector_response, parry_shape_rep_response) {
-                                    let p1 = parry_pair_selector_response.current_selections::<OParryPairSelector>();
-                                    let p2 = parry_shape_rep_response.current_selections::<ParryShapeRep>();
+                                    let p1 = parry_pair_selector_response.current_selections_unchecked::<OParryPairSelector>();
+                                    let p2 = parry_shape_rep_response.current_selections_unchecked::<ParryShapeRep>();
 
                                     // let fr = ParryIntersectGroupSequenceFilter::query(s, s, p.as_ref(), p.as_ref(), &ParryPairSelector::HalfPairs, skips, a, &ParryIntersectGroupSequenceFilterArgs::new(vec![], vec![]));
-                                    let res = OParryIntersectGroupQry::query(s, s, p.as_ref(), p.as_ref(), &p1[0], skips, &(), false, &OParryIntersectGroupArgs::new(p2[0].clone(), p2[0].clone(), false, false));
+                                    let res = BevyFrameTimings::time(&timings, TIMING_PROXIMITY, || OParryIntersectGroupQry::query(s, s, p.as_ref(), p.as_ref(), &p1[0], skips, &(), false, &OParryIntersectGroupArgs::new(p2[0].clone(), p2[0].clone(), false, false)));
 
                                     // let fr = ParryDistanceGroupSequenceFilter::query(s, s, p.as_ref(), p.as_ref(), &ParryPairSelector::HalfPairs, skips, a, &ParryDistanceGroupSequenceFilterArgs::new(vec![], vec![], T::constant(0.6), true, ParryDisMode::ContactDis));
-                                    let res2 = OParryDistanceGroupQry::query(s, s, p.as_ref(), p.as_ref(), &p1[0], skips, a, false, &OParryDistanceGroupArgs::new(p2[0].clone(), p2[0].clone(), ParryDisMode::ContactDis, true, false, T::constant(f64::MIN), true));
+                                    let res2 = BevyFrameTimings::time(&timings, TIMING_PROXIMITY, || OParryDistanceGroupQry::query(s, s, p.as_ref(), p.as_ref(), &p1[0], skips, a, false, &OParryDistanceGroupArgs::new(p2[0].clone(), p2[0].clone(), ParryDisMode::ContactDis, true, false, T::constant(f64::MIN), true)));
 
                                     let proximity_objective_value = res2.get_proximity_objective_value(T::constant(0.6), T::constant(20.0), OProximityLossFunction::Hinge);
 
@@ -357,6 +736,91 @@ impl RoboticsSystems {
                                     ui.separator();
                                     ui.separator();
 
+                                    let shape_idx_to_link_idx = robot.0.parry_shape_scene().get_shape_idx_to_link_idx();
+                                    let link_idxs = |pair_idxs: &OParryPairIdxs| -> (usize, usize) {
+                                        match pair_idxs {
+                                            OParryPairIdxs::Shapes(x, y) => { (shape_idx_to_link_idx[*x], shape_idx_to_link_idx[*y]) }
+                                            OParryPairIdxs::ShapeSubcomponents((x, _), (y, _)) => { (shape_idx_to_link_idx[*x], shape_idx_to_link_idx[*y]) }
+                                        }
+                                    };
+
+                                    let mut in_collision_link_idxs = vec![];
+                                    res.outputs().iter().filter(|x| x.data().intersect()).for_each(|x| {
+                                        let (a, b) = link_idxs(x.pair_idxs());
+                                        in_collision_link_idxs.push(a);
+                                        in_collision_link_idxs.push(b);
+                                    });
+                                    let mut near_contact_link_idxs = vec![];
+                                    res2.outputs().iter().filter(|x| x.data().raw_distance().to_constant() < highlights.near_contact_threshold).for_each(|x| {
+                                        let (a, b) = link_idxs(x.pair_idxs());
+                                        if !in_collision_link_idxs.contains(&a) { near_contact_link_idxs.push(a); }
+                                        if !in_collision_link_idxs.contains(&b) { near_contact_link_idxs.push(b); }
+                                    });
+
+                                    let link_name = |link_idx: usize| robot.0.links()[link_idx].name().to_string();
+                                    let mut distance_rows = vec![];
+                                    let mut distance_row_link_idxs = HashMap::new();
+                                    res2.outputs().iter().for_each(|x| {
+                                        let (a, b) = link_idxs(x.pair_idxs());
+                                        let id = format!("{:?}", x.pair_idxs());
+                                        distance_rows.push(OEguiTableRow::new(&id, vec![OEguiTableCell::Text(link_name(a)), OEguiTableCell::Text(link_name(b)), OEguiTableCell::Number(x.data().raw_distance().to_constant())]));
+                                        distance_row_link_idxs.insert(id, (a, b));
+                                    });
+
+                                    if let Some(environment_objects) = &environment_objects {
+                                        let environment_scene = environment_objects.shape_scene();
+                                        if !environment_scene.get_shapes().is_empty() {
+                                            let es = environment_scene.get_shapes();
+                                            let ep = environment_scene.get_shape_poses(&());
+                                            // robot and environment shapes are separate groups, so every pair has to be checked.
+                                            let environment_pair_selector = match &p1[0] {
+                                                OParryPairSelector::HalfPairs => { OParryPairSelector::AllPairs }
+                                                OParryPairSelector::HalfPairsSubcomponents => { OParryPairSelector::AllPairsSubcomponents }
+                                                x => { x.clone() }
+                                            };
+
+                                            let env_res = BevyFrameTimings::time(&timings, TIMING_PROXIMITY, || OParryIntersectGroupQry::query(s, es, p.as_ref(), ep.as_ref(), &environment_pair_selector, &(), &(), false, &OParryIntersectGroupArgs::new(p2[0].clone(), p2[0].clone(), false, false)));
+                                            let env_res2 = BevyFrameTimings::time(&timings, TIMING_PROXIMITY, || OParryDistanceGroupQry::query(s, es, p.as_ref(), ep.as_ref(), &environment_pair_selector, &(), &(), false, &OParryDistanceGroupArgs::new(p2[0].clone(), p2[0].clone(), ParryDisMode::ContactDis, false, false, T::constant(f64::MIN), true)));
+
+                                            ui.heading(format!("In collision with environment: {:?}", env_res.intersect()));
+                                            if !env_res2.outputs().is_empty() {
+                                                ui.label(format!("Min. dis. to environment: {:.3}", env_res2.min_raw_dis()));
+                                            }
+
+                                            // only the first index of each pair refers to a robot shape.
+                                            let robot_link_idx = |pair_idxs: &OParryPairIdxs| -> usize {
+                                                match pair_idxs {
+                                                    OParryPairIdxs::Shapes(x, _) => { shape_idx_to_link_idx[*x] }
+                                                    OParryPairIdxs::ShapeSubcomponents((x, _), _) => { shape_idx_to_link_idx[*x] }
+                                                }
+                                            };
+                                            env_res.outputs().iter().filter(|x| x.data().intersect()).for_each(|x| {
+                                                in_collision_link_idxs.push(robot_link_idx(x.pair_idxs()));
+                                            });
+                                            env_res2.outputs().iter().filter(|x| x.data().raw_distance().to_constant() < highlights.near_contact_threshold).for_each(|x| {
+                                                let a = robot_link_idx(x.pair_idxs());
+                                                if !in_collision_link_idxs.contains(&a) { near_contact_link_idxs.push(a); }
+                                            });
+
+                                            ui.separator();
+                                            ui.separator();
+                                        }
+                                    }
+                                    highlights.in_collision_link_idxs = in_collision_link_idxs;
+                                    highlights.near_contact_link_idxs = near_contact_link_idxs;
+
+                                    ui.horizontal(|ui| {
+                                        ui.label("Near contact distance: ");
+                                        ui.add(egui::DragValue::new(&mut highlights.near_contact_threshold).speed(0.001).clamp_range(0.0..=1.0));
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.checkbox(&mut contact_vis.enabled, "Show contacts");
+                                        ui.checkbox(&mut contact_vis.show_labels, "Labels");
+                                    });
+
+                                    ui.separator();
+                                    ui.separator();
+
                                     if ui.button("Mark as non-collision state").clicked() {
                                         if intersect {
                                             robot.0.add_non_collision_state(state.clone(), SaveRobot::Save(None));
@@ -399,6 +863,21 @@ impl RoboticsSystems {
 
                                     ui.separator();
                                     ui.separator();
+
+                                    drop(binding);
+                                    ui.label("Pairwise distances (click a row to highlight its links)");
+                                    egui::ScrollArea::vertical().id_source("distance_table_scroll").max_height(300.0).show(ui, |ui| {
+                                        OEguiTable::new(&["link a", "link b", "distance"], distance_rows)
+                                            .with_default_sort(2, true)
+                                            .with_filter_box()
+                                            .with_decimals(4)
+                                            .show("distance_table", ui, &egui_engine, &());
+                                    });
+                                    let selected = egui_engine.get_mutex_guard().get_table_response("distance_table").and_then(|x| x.selected().cloned());
+                                    highlights.selected_pair_link_idxs = selected.and_then(|x| distance_row_link_idxs.get(&x).cloned());
+
+                                    ui.separator();
+                                    ui.separator();
                                 }
                             }
 
@@ -413,6 +892,179 @@ impl RoboticsSystems {
                     });
             });
     }
+    /// Fills `BevyContactVisualization` with the contacts between the robot's links, and between the
+    /// robot and any environment objects, for the pair selector and shape representation chosen in
+    /// the self-collision panel.  Runs on its own (rather than as part of the panel) so the contacts
+    /// follow the robot even while the panel is collapsed or hidden.
+    pub fn system_robot_contact_vis<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                        robot_state_engine: Res<RobotStateEngine>,
+                                                                                                        highlights: Res<RobotLinkCollisionHighlights>,
+                                                                                                        mut contact_vis: ResMut<BevyContactVisualization>,
+                                                                                                        environment_objects: Option<Res<BevyEnvironmentObjects<T, C>>>,
+                                                                                                        timings: Option<Res<BevyFrameTimings>>,
+                                                                                                        egui_engine: Res<OEguiEngineWrapper>) {
+        let selections = {
+            let binding = egui_engine.get_mutex_guard();
+            match (binding.get_selector_response("selector1"), binding.get_selector_response("selector2")) {
+                (Some(a), Some(b)) => { Some((a.current_selections_unchecked::<OParryPairSelector>(), b.current_selections_unchecked::<ParryShapeRep>())) }
+                _ => { None }
+            }
+        };
+        let state = robot_state_engine.get_robot_state(robot.1);
+        let (true, Some(state), Some((pair_selectors, shape_reps))) = (contact_vis.enabled, state, selections) else {
+            contact_vis.set_contacts(vec![]);
+            return;
+        };
+        let (Some(pair_selector), Some(shape_rep)) = (pair_selectors.first(), shape_reps.first()) else {
+            contact_vis.set_contacts(vec![]);
+            return;
+        };
+
+        let state = OVec::ovec_to_other_ad_type::<T>(state);
+        let p = robot.0.get_shape_poses(&state);
+        let s = robot.0.parry_shape_scene().get_shapes();
+        let skips = robot.0.parry_shape_scene().get_pair_skips();
+        let shape_idx_to_link_idx = robot.0.parry_shape_scene().get_shape_idx_to_link_idx();
+        let link_name = |link_idx: usize| robot.0.links()[link_idx].name().to_string();
+        // only the first index of each pair refers to a robot shape for robot / environment pairs.
+        let first_link_idx = |pair_idxs: &OParryPairIdxs| -> usize {
+            match pair_idxs {
+                OParryPairIdxs::Shapes(x, _) => { shape_idx_to_link_idx[*x] }
+                OParryPairIdxs::ShapeSubcomponents((x, _), _) => { shape_idx_to_link_idx[*x] }
+            }
+        };
+        let second_idx = |pair_idxs: &OParryPairIdxs| -> usize {
+            match pair_idxs {
+                OParryPairIdxs::Shapes(_, y) => { *y }
+                OParryPairIdxs::ShapeSubcomponents(_, (y, _)) => { *y }
+            }
+        };
+
+        let contact_args = OParryContactGroupArgs::new(shape_rep.clone(), shape_rep.clone(), T::constant(highlights.near_contact_threshold), false, false, T::constant(f64::MIN));
+        let mut contacts = vec![];
+        let contact_res = BevyFrameTimings::time(&timings, TIMING_PROXIMITY, || OParryContactGroupQry::query(s, s, p.as_ref(), p.as_ref(), pair_selector, skips, &(), false, &contact_args));
+        contact_res.outputs().iter().for_each(|x| {
+            if let Some(contact) = x.data().contact() {
+                let (a, b) = (first_link_idx(x.pair_idxs()), shape_idx_to_link_idx[second_idx(x.pair_idxs())]);
+                contacts.push(ContactVis::from_contact(&contact, format!("{} / {}", link_name(a), link_name(b))));
+            }
+        });
+
+        if let Some(environment_objects) = &environment_objects {
+            let environment_scene = environment_objects.shape_scene();
+            if !environment_scene.get_shapes().is_empty() {
+                let es = environment_scene.get_shapes();
+                let ep = environment_scene.get_shape_poses(&());
+                // robot and environment shapes are separate groups, so every pair has to be checked.
+                let environment_pair_selector = match pair_selector {
+                    OParryPairSelector::HalfPairs => { OParryPairSelector::AllPairs }
+                    OParryPairSelector::HalfPairsSubcomponents => { OParryPairSelector::AllPairsSubcomponents }
+                    x => { x.clone() }
+                };
+                let env_contact_res = BevyFrameTimings::time(&timings, TIMING_PROXIMITY, || OParryContactGroupQry::query(s, es, p.as_ref(), ep.as_ref(), &environment_pair_selector, &(), &(), false, &contact_args));
+                env_contact_res.outputs().iter().for_each(|x| {
+                    if let Some(contact) = x.data().contact() {
+                        let label = format!("{} / {}", link_name(first_link_idx(x.pair_idxs())), environment_objects.objects()[second_idx(x.pair_idxs())].name());
+                        contacts.push(ContactVis::from_contact(&contact, label));
+                    }
+                });
+            }
+        }
+
+        contact_vis.set_contacts(contacts);
+    }
+    /// Applies the link panel's "Custom Color" and "Override Material" settings, and restores the
+    /// original material once they are unchecked.  The changes are made to the link's own material,
+    /// so they are kept underneath highlights that swap the material out (e.g., collision
+    /// highlighting).  An overridden alpha replaces the alpha of the custom color too.
+    ///
+    /// A link's own material is recorded the first time it is seen while no highlight or heat map
+    /// color is swapped in, so a shared highlight material is never mistaken for it.
+    pub fn system_apply_robot_link_material_overrides(egui_engine: Res<OEguiEngineWrapper>,
+                                                      collision_highlights: Option<Res<RobotLinkCollisionHighlights>>,
+                                                      joint_limit_heat_map: Option<Res<BevyJointLimitHeatMap>>,
+                                                      mut materials: ResMut<Assets<StandardMaterial>>,
+                                                      mut own_materials: Local<HashMap<Entity, (Handle<StandardMaterial>, StandardMaterial)>>,
+                                                      query: Query<(Entity, &LinkMeshID, &Handle<StandardMaterial>)>) {
+        let mutex_guard = egui_engine.get_mutex_guard();
+        own_materials.retain(|entity, _| query.contains(*entity));
+        for (entity, link_mesh_id, material) in query.iter() {
+            if !own_materials.contains_key(&entity) {
+                let swapped = collision_highlights.as_ref().map(|x| x.has_swapped_material(entity)).unwrap_or(false) || joint_limit_heat_map.as_ref().map(|x| x.has_swapped_material(entity)).unwrap_or(false);
+                if swapped { continue; }
+                let Some(m) = materials.get(material) else { continue; };
+                own_materials.insert(entity, (material.clone(), m.clone()));
+            }
+            let (own_material, original) = own_materials.get(&entity).expect("error");
+            let (link_idx, robot_instance_idx) = (link_mesh_id.link_idx, link_mesh_id.robot_instance_idx);
+
+            let checked = |id: &str| mutex_guard.get_checkbox_response(id).map(|x| x.currently_selected).unwrap_or(false);
+            let slider = |name: &str| mutex_guard.get_slider_response(&link_material_id(name, link_idx, robot_instance_idx)).map(|x| x.slider_value as f32);
+
+            let mut color = original.base_color;
+            let mut alpha_mode = original.alpha_mode;
+            let (mut metallic, mut roughness) = (original.metallic, original.perceptual_roughness);
+            if checked(&link_color_toggle_id(link_idx, robot_instance_idx)) {
+                if let Some(custom_color) = mutex_guard.get_color_picker_response(&link_color_id(link_idx, robot_instance_idx)).map(|x| x.bevy_color()) { color = custom_color; }
+            }
+            if checked(&link_material_id("toggle", link_idx, robot_instance_idx)) {
+                if let Some(x) = slider("metallic") { metallic = x; }
+                if let Some(x) = slider("roughness") { roughness = x; }
+                if let Some(x) = slider("alpha") { color.set_a(x); }
+            }
+            if color.a() < 1.0 { alpha_mode = AlphaMode::Blend; }
+
+            // only touch the asset when something changed, so it is not re-uploaded every frame.
+            if materials.get(own_material).map(|x| x.base_color == color && x.alpha_mode == alpha_mode && x.metallic == metallic && x.perceptual_roughness == roughness).unwrap_or(true) { continue; }
+            if let Some(m) = materials.get_mut(own_material) {
+                m.base_color = color;
+                m.alpha_mode = alpha_mode;
+                m.metallic = metallic;
+                m.perceptual_roughness = roughness;
+            }
+        }
+    }
+    /// Swaps the material of every link in `RobotLinkCollisionHighlights` for a red (in collision)
+    /// or orange (near contact) one, and the selected pair's for a blue one.  The original material is
+    /// restored once the link is clear again, or once its robot instance is no longer the one being
+    /// highlighted.
+    pub fn system_robot_link_collision_highlighting(mut highlights: ResMut<RobotLinkCollisionHighlights>,
+                                                    mut materials: ResMut<Assets<StandardMaterial>>,
+                                                    mut query: Query<(Entity, &LinkMeshID, &mut Handle<StandardMaterial>)>) {
+        let collision_material = highlights.collision_material.get_or_insert_with(|| materials.add(StandardMaterial::from(Color::rgb(1.0, 0.0, 0.0)))).clone();
+        let near_contact_material = highlights.near_contact_material.get_or_insert_with(|| materials.add(StandardMaterial::from(Color::rgb(1.0, 0.5, 0.0)))).clone();
+        let selected_pair_material = highlights.selected_pair_material.get_or_insert_with(|| materials.add(StandardMaterial::from(Color::rgb(0.0, 0.6, 1.0)))).clone();
+
+        // entities that were despawned while highlighted.
+        highlights.original_materials.retain(|entity, _| query.contains(*entity));
+
+        for (entity, link_mesh_id, mut material) in query.iter_mut() {
+            let in_selected_pair = highlights.selected_pair_link_idxs.map(|(a, b)| a == link_mesh_id.link_idx || b == link_mesh_id.link_idx).unwrap_or(false);
+            let highlight = if link_mesh_id.robot_instance_idx != highlights.robot_instance_idx {
+                None
+            } else if in_selected_pair {
+                Some(selected_pair_material.clone())
+            } else if highlights.in_collision_link_idxs.contains(&link_mesh_id.link_idx) {
+                Some(collision_material.clone())
+            } else if highlights.near_contact_link_idxs.contains(&link_mesh_id.link_idx) {
+                Some(near_contact_material.clone())
+            } else {
+                None
+            };
+
+            match highlight {
+                None => {
+                    if let Some(original_material) = highlights.original_materials.remove(&entity) { *material = original_material; }
+                }
+                Some(highlight) => {
+                    if *material != highlight {
+                        if !highlights.original_materials.contains_key(&entity) { highlights.original_materials.insert(entity, material.clone()); }
+                        *material = highlight;
+                    }
+                }
+            }
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -424,6 +1076,18 @@ pub trait BevyRoboticsTrait<T: AD> {
     fn bevy_get_motion_playback_app<V: OVec<T>, I: InterpolatorTrait<T, V> + 'static>(&self, interpolator: &I) -> App;
     fn bevy_self_collision_visualization(&mut self);
     fn bevy_get_self_collision_visualization_app(&mut self) -> App;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_playback_from_file(&self, path: &OPath) -> Result<(), OptimaError>;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_get_playback_from_file_app(&self, path: &OPath) -> Result<App, OptimaError>;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_preprocess(&self, save: bool);
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_get_preprocess_app(&self, save: bool) -> App;
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_interactive_ik(&self, link_idx: usize);
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_get_interactive_ik_app(&self, link_idx: usize) -> App;
 }
 
 impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> BevyRoboticsTrait<T> for ORobot<T, C, L> {
@@ -440,8 +1104,17 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> BevyRobo
             .optima_bevy_starter_lights()
             .optima_bevy_spawn_robot::<T, C, L>()
             .optima_bevy_robotics_scene_visuals_starter()
-            .optima_bevy_egui()
-            .add_systems(Update, RoboticsSystems::system_robot_main_info_panel_egui::<T, C, L>.before(BevySystemSet::Camera));
+            .optima_bevy_egui();
+
+        let panels = app.world.get_resource::<OptimaViewerConfig>().map(|x| x.panels.clone()).unwrap_or_default();
+        if panels.robot_info { app.add_systems(Update, RoboticsSystems::system_robot_main_info_panel_egui::<T, C, L>.before(BevySystemSet::Camera)); }
+        #[cfg(not(target_arch = "wasm32"))]
+        if panels.robot_info { app.add_systems(Update, RoboticsSystems::system_robot_open_dialog::<T, C, L>.after(RoboticsSystems::system_robot_main_info_panel_egui::<T, C, L>).before(BevySystemSet::Camera)); }
+        if panels.log { app.optima_bevy_log_panel(); }
+        if panels.collision_geometry { app.optima_bevy_collision_geometry_display::<T, C, L>(); }
+        if panels.keyframes { app.optima_bevy_keyframe_editor::<T, C, L>(); }
+        if panels.labels { app.optima_bevy_frame_labels::<T, C, L>(); }
+        if panels.joint_limits { app.optima_bevy_joint_limit_heat_map::<T, C, L>(); }
         app
     }
 
@@ -459,8 +1132,11 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> BevyRobo
             .optima_bevy_spawn_robot::<T, C, L>()
             .optima_bevy_robotics_scene_visuals_starter()
             .optima_bevy_egui()
-            .insert_resource(BevyRobotInterpolator(interpolator.clone(), PhantomData::default()))
+            .insert_resource(BevyRobotInterpolator::new(interpolator.clone()))
             .add_systems(Update, RoboticsSystems::system_robot_motion_interpolator::<T, V, I>.before(BevySystemSet::Camera));
+
+        let panels = app.world.get_resource::<OptimaViewerConfig>().map(|x| x.panels.clone()).unwrap_or_default();
+        if panels.joint_limits { app.optima_bevy_joint_limit_heat_map::<T, C, L>(); }
         app
     }
 
@@ -479,7 +1155,72 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> BevyRobo
             .optima_bevy_spawn_robot::<T, C, L>()
             .optima_bevy_robotics_scene_visuals_starter()
             .optima_bevy_egui()
-            .add_systems(Update, RoboticsSystems::system_robot_self_collision_vis::<T, C, L>.before(BevySystemSet::Camera));
+            .optima_bevy_performance_overlay()
+            .insert_resource(RobotLinkCollisionHighlights::new())
+            .optima_bevy_collision_geometry_display::<T, C, L>()
+            .optima_bevy_bounding_volume_display::<T, C, L>()
+            .insert_resource(BevyContactVisualization::new())
+            .add_systems(Update, RoboticsSystems::system_robot_contact_vis::<T, C, L>.after(RoboticsSystems::system_robot_self_collision_vis::<T, C, L>))
+            .add_systems(Update, ContactVisSystems::system_draw_contacts.after(RoboticsSystems::system_robot_contact_vis::<T, C, L>))
+            .add_systems(Update, ContactVisSystems::system_draw_contact_labels.after(RoboticsSystems::system_robot_contact_vis::<T, C, L>).before(BevySystemSet::Camera))
+            .add_systems(Update, RoboticsSystems::system_robot_self_collision_vis::<T, C, L>.before(BevySystemSet::Camera))
+            .add_systems(Update, RoboticsSystems::system_robot_link_collision_highlighting.after(RoboticsSystems::system_robot_self_collision_vis::<T, C, L>));
+        app
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_playback_from_file(&self, path: &OPath) -> Result<(), OptimaError> {
+        self.bevy_get_playback_from_file_app(path)?.run();
+        Ok(())
+    }
+
+    /// Plays a recorded joint trajectory (see `RecordedTrajectory` for the file formats) with the
+    /// motion playback bottom panel, and adds the "Trajectory" window for trimming, retiming, and
+    /// exporting it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_get_playback_from_file_app(&self, path: &OPath) -> Result<App, OptimaError> {
+        let trajectory = RecordedTrajectory::load_from_path(path)?;
+        let num_dofs = trajectory.samples()[0].state.len();
+        if num_dofs != self.num_dofs() { return Err(OptimaError::InvalidInput(format!("the trajectory has {} dofs, but {} has {}.", num_dofs, self.robot_name(), self.num_dofs()))); }
+
+        let mut app = self.bevy_get_motion_playback_app(&trajectory.to_interpolator::<T>());
+        app
+            .insert_resource(BevyTrajectoryFile::new(&path.to_string(), trajectory, num_dofs))
+            .add_systems(Update, TrajectoryFileSystems::system_trajectory_file_panel::<T>.before(BevySystemSet::Camera));
+        Ok(app)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_preprocess(&self, save: bool) {
+        self.bevy_get_preprocess_app(save).run();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_get_preprocess_app(&self, save: bool) -> App {
+        let mut app = self.bevy_get_display_app();
+        app.optima_bevy_robot_preprocessing::<T, C, L>(save);
+        app
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_interactive_ik(&self, link_idx: usize) {
+        self.bevy_get_interactive_ik_app(link_idx).run();
+    }
+
+    /// Same as the display app, but without the joint sliders, which would otherwise overwrite the
+    /// ik solutions.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn bevy_get_interactive_ik_app(&self, link_idx: usize) -> App {
+        let mut app = App::new();
+        app
+            .optima_bevy_base()
+            .optima_bevy_robotics_base(self.clone())
+            .optima_bevy_pan_orbit_camera()
+            .optima_bevy_starter_lights()
+            .optima_bevy_spawn_robot::<T, C, L>()
+            .optima_bevy_robotics_scene_visuals_starter()
+            .optima_bevy_egui()
+            .optima_bevy_interactive_ik::<T, C, L>(link_idx);
         app
     }
 }
@@ -512,21 +1253,129 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> BevyRobo
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct LinkMeshID {
     pub robot_instance_idx: usize,
     pub sub_robot_idx: usize,
-    pub link_idx: usize
+    pub link_idx: usize,
+    /// which of the link's visuals the mesh belongs to.
+    pub visual_idx: usize
+}
+
+/// Links of robot instance `robot_instance_idx` that are currently in collision or near contact.
+/// Their meshes are drawn red and orange, respectively, by `system_robot_link_collision_highlighting`.
+/// The pair selected in the pairwise distance table is drawn blue on top of that.
+#[derive(Resource)]
+pub struct RobotLinkCollisionHighlights {
+    pub robot_instance_idx: usize,
+    pub in_collision_link_idxs: Vec<usize>,
+    pub near_contact_link_idxs: Vec<usize>,
+    pub selected_pair_link_idxs: Option<(usize, usize)>,
+    /// pairs of links closer than this (but not intersecting) are considered near contact.
+    pub near_contact_threshold: f64,
+    collision_material: Option<Handle<StandardMaterial>>,
+    near_contact_material: Option<Handle<StandardMaterial>>,
+    selected_pair_material: Option<Handle<StandardMaterial>>,
+    original_materials: HashMap<Entity, Handle<StandardMaterial>>
+}
+impl RobotLinkCollisionHighlights {
+    pub fn new() -> Self {
+        Self {
+            robot_instance_idx: 0,
+            in_collision_link_idxs: vec![],
+            near_contact_link_idxs: vec![],
+            selected_pair_link_idxs: None,
+            near_contact_threshold: 0.02,
+            collision_material: None,
+            near_contact_material: None,
+            selected_pair_material: None,
+            original_materials: HashMap::new(),
+        }
+    }
+    /// Whether the entity's material is currently swapped for a highlight.
+    pub fn has_swapped_material(&self, entity: Entity) -> bool {
+        self.original_materials.contains_key(&entity)
+    }
+}
+
+/// A state a robot instance was in before (or, on the redo stack, after) an interactive edit.
+#[derive(Clone, Debug)]
+pub struct RobotStateHistoryEntry {
+    pub robot_instance_idx: usize,
+    /// what made the edit, e.g., "joint sliders".
+    pub label: String,
+    pub state: Vec<f64>
 }
 
+const MAX_ROBOT_STATE_HISTORY_LENGTH: usize = 200;
+
 #[derive(Resource)]
 pub struct RobotStateEngine {
     pub (crate) robot_states: HashMap<usize, Vec<f64>>,
-    pub (crate) robot_state_update_requests: Vec<(usize, Vec<f64>)>
+    pub (crate) robot_state_update_requests: Vec<(usize, Vec<f64>)>,
+    undo_stack: Vec<RobotStateHistoryEntry>,
+    redo_stack: Vec<RobotStateHistoryEntry>
 }
 impl RobotStateEngine {
     pub fn new() -> Self {
-        Self { robot_states: Default::default(), robot_state_update_requests: vec![] }
+        Self { robot_states: Default::default(), robot_state_update_requests: vec![], undo_stack: vec![], redo_stack: vec![] }
+    }
+    /// Call when an interactive edit starts (e.g., a joint slider starts being dragged), before its
+    /// first update request: the current state of the robot instance is saved so the edit can be
+    /// undone.  Updates that are not user edits (playback, streamed states, etc.) should not call
+    /// this.
+    pub fn record_edit(&mut self, robot_instance_idx: usize, label: &str) {
+        let Some(state) = self.robot_states.get(&robot_instance_idx) else { return; };
+        let entry = RobotStateHistoryEntry { robot_instance_idx, label: label.to_string(), state: state.clone() };
+        self.undo_stack.push(entry);
+        if self.undo_stack.len() > MAX_ROBOT_STATE_HISTORY_LENGTH { self.undo_stack.remove(0); }
+        self.redo_stack.clear();
+    }
+    /// Requests the state from before the most recent edit, and returns the entry that was restored.
+    /// Joint sliders are not moved here (see `RoboticsActions::action_set_joint_sliders`).
+    pub fn undo(&mut self) -> Option<RobotStateHistoryEntry> {
+        let entry = self.undo_stack.pop()?;
+        self.restore_history_entry(&entry, true);
+        Some(entry)
+    }
+    pub fn redo(&mut self) -> Option<RobotStateHistoryEntry> {
+        let entry = self.redo_stack.pop()?;
+        self.restore_history_entry(&entry, false);
+        Some(entry)
+    }
+    pub fn clear_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+    pub fn clear_history_of_robot_instance(&mut self, robot_instance_idx: usize) {
+        self.undo_stack.retain(|x| x.robot_instance_idx != robot_instance_idx);
+        self.redo_stack.retain(|x| x.robot_instance_idx != robot_instance_idx);
+    }
+    /// Oldest first.
+    #[inline(always)]
+    pub fn undo_stack(&self) -> &Vec<RobotStateHistoryEntry> {
+        &self.undo_stack
+    }
+    /// Oldest first, i.e., the next entry `redo` restores is the last one.
+    #[inline(always)]
+    pub fn redo_stack(&self) -> &Vec<RobotStateHistoryEntry> {
+        &self.redo_stack
+    }
+    fn restore_history_entry(&mut self, entry: &RobotStateHistoryEntry, undo: bool) {
+        let robot_instance_idx = entry.robot_instance_idx;
+        // with several undos in one frame, the state being left is the one requested by the previous
+        // undo, which is not applied yet.
+        let state = self.robot_state_update_requests.iter().rev().find(|x| x.0 == robot_instance_idx).map(|x| &x.1).or(self.robot_states.get(&robot_instance_idx)).cloned();
+        if let Some(state) = state {
+            let current = RobotStateHistoryEntry { robot_instance_idx, label: entry.label.clone(), state };
+            match undo {
+                true => { self.redo_stack.push(current); }
+                false => { self.undo_stack.push(current); }
+            }
+        }
+        // the restored state overrides anything else requested for this instance this frame.
+        self.robot_state_update_requests.retain(|x| x.0 != robot_instance_idx);
+        self.add_update_request(robot_instance_idx, &entry.state);
     }
     pub fn add_update_request<T: AD, V: OVec<T>>(&mut self, robot_instance_idx: usize, state: &V) {
         let save_state = state.to_constant_vec();
@@ -535,6 +1384,11 @@ impl RobotStateEngine {
     pub fn get_robot_state(&self, robot_instance_idx: usize) -> Option<&Vec<f64>> {
         self.robot_states.get(&robot_instance_idx)
     }
+    pub fn robot_instance_idxs(&self) -> Vec<usize> {
+        let mut out: Vec<usize> = self.robot_states.keys().cloned().collect();
+        out.sort();
+        out
+    }
 }
 
 #[derive(Resource)]
@@ -569,8 +1423,109 @@ impl<T: AD, C: O3DPoseCategory + Send + 'static, L: OLinalgCategory + 'static> S
     }
 }
 
+/// Robot instances that are each placed at their own base pose and driven independently through
+/// `RobotStateEngine` by their instance idx (see `optima_bevy_robot_instance`).
+#[derive(Resource)]
+pub struct BevyORobotInstances<T: AD, C: O3DPoseCategory + Send + 'static, L: OLinalgCategory + 'static> {
+    instances: Vec<BevyORobotInstance<T, C, L>>
+}
+impl<T: AD, C: O3DPoseCategory + Send + 'static, L: OLinalgCategory + 'static> BevyORobotInstances<T, C, L> {
+    pub fn new() -> Self {
+        Self { instances: vec![] }
+    }
+    /// Replaces the instance with the same idx, if there is one.
+    pub fn add_instance(&mut self, robot: ORobot<T, C, L>, base_pose: C::P<T>, robot_instance_idx: usize) {
+        self.instances.retain(|x| x.robot_instance_idx != robot_instance_idx);
+        self.instances.push(BevyORobotInstance { robot, base_pose, robot_instance_idx });
+    }
+    pub fn get_instance(&self, robot_instance_idx: usize) -> Option<&BevyORobotInstance<T, C, L>> {
+        self.instances.iter().find(|x| x.robot_instance_idx == robot_instance_idx)
+    }
+    /// Returns false if there is no instance with the given idx.  The links are only moved to the new
+    /// base pose the next time the instance's state is set through `RobotStateEngine`.
+    pub fn set_base_pose(&mut self, robot_instance_idx: usize, base_pose: C::P<T>) -> bool {
+        match self.instances.iter_mut().find(|x| x.robot_instance_idx == robot_instance_idx) {
+            None => { false }
+            Some(instance) => {
+                instance.base_pose = base_pose;
+                true
+            }
+        }
+    }
+    #[inline(always)]
+    pub fn instances(&self) -> &Vec<BevyORobotInstance<T, C, L>> {
+        &self.instances
+    }
+}
+
+pub struct BevyORobotInstance<T: AD, C: O3DPoseCategory + Send + 'static, L: OLinalgCategory + 'static> {
+    pub robot: ORobot<T, C, L>,
+    pub base_pose: C::P<T>,
+    pub robot_instance_idx: usize
+}
+
+/// Egui labels of robot instance 0 are left as is, those of other instances get the instance idx
+/// appended so that their widgets do not share state.
+pub (crate) fn robot_instance_label(label: String, robot_instance_idx: usize) -> String {
+    if robot_instance_idx == 0 { label } else { format!("{}_robot_{}", label, robot_instance_idx) }
+}
+
+/// Id of the link panel checkbox that turns the link's custom color on.
+pub (crate) fn link_color_toggle_id(link_idx: usize, robot_instance_idx: usize) -> String {
+    robot_instance_label(format!("link_color_toggle_{}", link_idx), robot_instance_idx)
+}
+
+/// Id of the link panel color picker for the link's custom color.
+pub (crate) fn link_color_id(link_idx: usize, robot_instance_idx: usize) -> String {
+    robot_instance_label(format!("link_color_{}", link_idx), robot_instance_idx)
+}
+
+/// Ids of the link panel's material overrides; `name` is one of "header", "toggle", "metallic",
+/// "roughness", or "alpha".
+pub (crate) fn link_material_id(name: &str, link_idx: usize, robot_instance_idx: usize) -> String {
+    robot_instance_label(format!("link_material_{}_{}", name, link_idx), robot_instance_idx)
+}
+
+/// The `scale` of the link's first visual if it is a mesh, in the mesh's own axes (which is what a
+/// bevy `Transform` scales, since scale is applied before rotation).
+/// The urdf scale of a mesh visual (primitive shapes are not scaled).
+fn visual_mesh_scale(geometry: &OGeometry) -> Vec3 {
+    match geometry {
+        OGeometry::Mesh { scale: Some(scale), .. } => { Vec3::new(scale[0] as f32, scale[1] as f32, scale[2] as f32) }
+        _ => { Vec3::ONE }
+    }
+}
+
+fn resolve_urdf_texture_path(filename: &str, mesh_directory: Option<PathBuf>) -> Option<PathBuf> {
+    let path = PathBuf::from(filename.strip_prefix("file://").unwrap_or(filename));
+    if !filename.starts_with("package://") && path.is_file() { return Some(path); }
+    let path = mesh_directory?.join(path.file_name()?);
+    if path.is_file() { Some(path) } else { None }
+}
+
+/// A texture that never loads would keep the whole mesh from being drawn, so missing files are
+/// skipped.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_link_texture(path: PathBuf, asset_server: &Res<AssetServer>) -> Option<Handle<Image>> {
+    if !path.is_file() {
+        warn!("link texture {:?} not found.", path);
+        return None;
+    }
+    Some(asset_server.load(path.to_string_lossy().to_string()))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_link_texture(_path: PathBuf, _asset_server: &Res<AssetServer>) -> Option<Handle<Image>> {
+    None
+}
+
 #[derive(Resource)]
 pub struct BevyRobotInterpolator<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V> + 'static>(pub I, PhantomData<(T, V)>);
+impl<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V> + 'static> BevyRobotInterpolator<T, V, I> {
+    pub fn new(interpolator: I) -> Self {
+        Self(interpolator, PhantomData::default())
+    }
+}
 unsafe impl<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V>> Send for BevyRobotInterpolator<T, V, I> { }
 unsafe impl<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V>> Sync for BevyRobotInterpolator<T, V, I> { }
 
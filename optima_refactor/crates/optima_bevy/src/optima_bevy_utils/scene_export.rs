@@ -0,0 +1,185 @@
+use std::path::PathBuf;
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
+use optima_3d_mesh::OTriMesh;
+use optima_3d_mesh::scene_export::OSceneExport;
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_error::OptimaError;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiFileDialog, OEguiFileDialogMode, OEguiWindow};
+use optima_file::path::{OPath, OStemCellPath};
+use optima_linalg::OLinalgCategory;
+use optima_robotics::robot::ORobot;
+use optima_robotics::robotics_components::OGeometry;
+use optima_robotics::urdf_export::OUrdfSnapshot;
+use crate::optima_bevy_utils::environment::{BevyEnvironment, GroundPlaneMaterial};
+use crate::optima_bevy_utils::robotics::{BevyORobot, BevyORobotInstances, RobotStateEngine};
+use crate::optima_bevy_utils::shape_scene::{BevyEnvironmentObjects, EnvironmentObjectShape};
+
+pub const SCENE_EXPORT_EXTENSIONS: [&str; 4] = ["glb", "gltf", "usda", "urdf"];
+
+#[derive(Resource)]
+pub struct BevySceneExport {
+    pub path: String,
+    /// urdf only: write every robot joint as a fixed joint at its current value.  On by default,
+    /// since a urdf cannot otherwise store the current state (see `OUrdfSnapshot::add_robot`).
+    pub freeze_joints: bool,
+    status: String
+}
+impl BevySceneExport {
+    pub fn new(path: &str) -> Self {
+        Self { path: path.to_string(), freeze_joints: true, status: "".to_string() }
+    }
+}
+
+pub struct SceneExportActions;
+impl SceneExportActions {
+    /// The scene as shown in the viewer: every robot at its current state and base pose (see
+    /// `ORobot::to_scene_export`), the environment objects, and the ground plane if it is shown.
+    /// The ground plane gets a single color (the first checkerboard color for checkerboards).
+    pub fn action_scene_export<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Option<&BevyORobot<T, C, L>>,
+                                                                                                 robot_instances: Option<&BevyORobotInstances<T, C, L>>,
+                                                                                                 robot_state_engine: &RobotStateEngine,
+                                                                                                 environment_objects: Option<&BevyEnvironmentObjects<T, C>>,
+                                                                                                 environment: Option<&BevyEnvironment>) -> Result<OSceneExport, OptimaError> {
+        let mut out = OSceneExport::new();
+
+        for (robot_instance_idx, robot, base_pose) in scene_robots(robot, robot_instances) {
+            let state = robot_state_engine.get_robot_state(robot_instance_idx).cloned().unwrap_or(vec![0.0; robot.num_dofs()]);
+            let robot_export = robot.to_other_ad_type::<f64>().to_scene_export(&state, None)?;
+            let instance_node_idx = out.add_node_from_pose(&format!("robot_instance_{}", robot_instance_idx), None, &base_pose, None)?;
+            out.append(&robot_export, Some(instance_node_idx))?;
+        }
+
+        if let Some(environment_objects) = environment_objects {
+            let environment_node_idx = out.add_node("environment", None, [0.0; 3], [1.0, 0.0, 0.0, 0.0], None)?;
+            environment_objects.shape_scene().add_to_scene_export(&mut out, Some(environment_node_idx), "environment_shape", None)?;
+        }
+
+        let ground_plane = environment.filter(|x| x.show_ground_plane).and_then(|x| x.environment().ground_plane.as_ref());
+        if let Some(ground_plane) = ground_plane {
+            let h = ground_plane.size as f64 / 2.0;
+            let trimesh = OTriMesh::new(vec![[-h, -h, 0.0], [h, -h, 0.0], [h, h, 0.0], [-h, h, 0.0]], vec![[0, 1, 2], [0, 2, 3]]);
+            let color = match &ground_plane.material {
+                GroundPlaneMaterial::Color(color) => { *color }
+                GroundPlaneMaterial::Checkerboard { color_a, .. } => { *color_a }
+                GroundPlaneMaterial::Texture { .. } => { [0.8, 0.8, 0.8, 1.0] }
+            };
+            let mesh_idx = out.add_mesh("ground_plane", trimesh, Some(color.map(|x| x as f64)));
+            out.add_node("ground_plane", None, [0.0, 0.0, -0.001], [1.0, 0.0, 0.0, 0.0], Some(mesh_idx))?;
+        }
+
+        Ok(out)
+    }
+    /// The scene as a single urdf (see `OUrdfSnapshot`), with environment objects as obstacles fixed
+    /// to the world.  Robot instances other than 0 get a `robot_<idx>_` prefix on their link and
+    /// joint names.  Environment objects that were not made from a shape description (see
+    /// `EnvironmentObject::shape_description`) cannot be written and are left out with a warning,
+    /// and the ground plane is left out since simulators bring their own.
+    pub fn action_urdf_snapshot<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Option<&BevyORobot<T, C, L>>,
+                                                                                                  robot_instances: Option<&BevyORobotInstances<T, C, L>>,
+                                                                                                  robot_state_engine: &RobotStateEngine,
+                                                                                                  environment_objects: Option<&BevyEnvironmentObjects<T, C>>,
+                                                                                                  freeze_joints: bool) -> OUrdfSnapshot {
+        let mut out = OUrdfSnapshot::new("optima_scene");
+
+        for (robot_instance_idx, robot, base_pose) in scene_robots(robot, robot_instances) {
+            let state = robot_state_engine.get_robot_state(robot_instance_idx).cloned().unwrap_or(vec![0.0; robot.num_dofs()]);
+            let prefix = if robot_instance_idx == 0 { "".to_string() } else { format!("robot_{}_", robot_instance_idx) };
+            let base_pose = base_pose.o3dpose_to_other_generic_category::<f64, C>();
+            out.add_robot(&robot.to_other_ad_type::<f64>(), &state, Some(&base_pose), &prefix, freeze_joints);
+        }
+
+        if let Some(environment_objects) = environment_objects {
+            for object in environment_objects.objects() {
+                let added = match object.shape_description() {
+                    Some(EnvironmentObjectShape::Box { x_dim, y_dim, z_dim }) => { out.add_obstacle(object.name(), &OGeometry::Box { size: [*x_dim, *y_dim, *z_dim] }, object.pose(), None); true }
+                    Some(EnvironmentObjectShape::Sphere { radius }) => { out.add_obstacle(object.name(), &OGeometry::Sphere { radius: *radius }, object.pose(), None); true }
+                    Some(EnvironmentObjectShape::Cylinder { radius, height }) => { out.add_obstacle(object.name(), &OGeometry::Cylinder { radius: *radius, length: *height }, object.pose(), None); true }
+                    Some(EnvironmentObjectShape::Mesh { asset_path_components }) => {
+                        out.add_mesh_obstacle(object.name(), &OStemCellPath::new_asset_path_from_string_components(asset_path_components), object.pose(), None).is_ok()
+                    }
+                    None => { false }
+                };
+                if !added { warn!("environment object {} cannot be written to a urdf and was left out.", object.name()); }
+            }
+        }
+
+        out
+    }
+}
+
+/// Robots in the order of their instance idxs, with their base poses.
+fn scene_robots<'a, T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Option<&'a BevyORobot<T, C, L>>, robot_instances: Option<&'a BevyORobotInstances<T, C, L>>) -> Vec<(usize, &'a ORobot<T, C, L>, C::P<T>)> {
+    let mut out = vec![];
+    if let Some(robot) = robot { out.push((robot.1, &robot.0, C::P::<T>::identity())); }
+    if let Some(robot_instances) = robot_instances {
+        robot_instances.instances().iter().for_each(|x| out.push((x.robot_instance_idx, &x.robot, x.base_pose.clone())));
+    }
+    out.sort_by_key(|x| x.0);
+    out
+}
+
+pub struct SceneExportSystems;
+impl SceneExportSystems {
+    pub fn system_scene_export_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(mut scene_export: ResMut<BevySceneExport>,
+                                                                                                       robot: Option<Res<BevyORobot<T, C, L>>>,
+                                                                                                       robot_instances: Option<Res<BevyORobotInstances<T, C, L>>>,
+                                                                                                       robot_state_engine: Res<RobotStateEngine>,
+                                                                                                       environment_objects: Option<Res<BevyEnvironmentObjects<T, C>>>,
+                                                                                                       environment: Option<Res<BevyEnvironment>>,
+                                                                                                       mut contexts: EguiContexts,
+                                                                                                       egui_engine: Res<OEguiEngineWrapper>,
+                                                                                                       window_query: Query<&Window, With<PrimaryWindow>>) {
+        let mut export = false;
+        OEguiWindow::new("Export", true, true, false, false, false, false)
+            .show("scene_export_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("file");
+                    ui.text_edit_singleline(&mut scene_export.path);
+                    if ui.button("...").clicked() { OEguiFileDialog::open("scene_export_dialog", &egui_engine); }
+                });
+                ui.label(format!("the format is picked from the extension ({}).", SCENE_EXPORT_EXTENSIONS.join(", ")));
+                ui.checkbox(&mut scene_export.freeze_joints, "urdf: freeze joints at the current state");
+                if ui.button("Export").clicked() { export = true; }
+                if !scene_export.status.is_empty() { ui.label(scene_export.status.as_str()); }
+            });
+
+        OEguiFileDialog::new("Export Scene", OEguiFileDialogMode::Save)
+            .with_extensions(&SCENE_EXPORT_EXTENSIONS)
+            .with_default_filename(&scene_export.path)
+            .show("scene_export_dialog", contexts.ctx_mut(), &egui_engine);
+        let picked = egui_engine.get_mutex_guard().get_file_dialog_response("scene_export_dialog").and_then(|x| x.picked().cloned());
+        if let Some(path) = picked {
+            scene_export.path = path.to_string();
+            export = true;
+        }
+
+        if !export { return; }
+
+        let path = OPath::Path(PathBuf::from(scene_export.path.clone()));
+        let is_urdf = path.extension().map(|x| x.eq_ignore_ascii_case("urdf")).unwrap_or(false);
+        let res = match is_urdf {
+            true => {
+                let urdf = SceneExportActions::action_urdf_snapshot(robot.as_deref(), robot_instances.as_deref(), &robot_state_engine, environment_objects.as_deref(), scene_export.freeze_joints);
+                urdf.save(&path).map(|_| "exported urdf.".to_string()).map_err(|e| e.to_string())
+            }
+            false => {
+                SceneExportActions::action_scene_export(robot.as_deref(), robot_instances.as_deref(), &robot_state_engine, environment_objects.as_deref(), environment.as_deref())
+                    .map_err(|e| e.to_string())
+                    .and_then(|scene| scene.save_from_extension(&path).map(|_| format!("exported {} mesh(es).", scene.meshes().len())))
+            }
+        };
+        scene_export.status = match res {
+            Ok(status) => {
+                egui_engine.get_mutex_guard().push_info(&format!("exported scene to {}.", scene_export.path));
+                status
+            }
+            Err(e) => {
+                egui_engine.get_mutex_guard().push_error(&format!("could not export scene to {}.", scene_export.path));
+                format!("could not export: {}", e)
+            }
+        };
+    }
+}
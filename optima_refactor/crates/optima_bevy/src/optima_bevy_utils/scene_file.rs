@@ -0,0 +1,341 @@
+use std::path::PathBuf;
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
+use nalgebra::Isometry3;
+use serde::{Deserialize, Serialize};
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory, O3DPoseCategoryIsometry3};
+use optima_3d_spatial::optima_3d_rotation::O3DRotation;
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_error::OptimaError;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineState, OEguiEngineWrapper, OEguiFileDialog, OEguiFileDialogMode, OEguiWindow};
+use optima_file::path::{OPath, OSaveFormat};
+use optima_linalg::{OLinalgCategory, OLinalgCategoryNalgebra};
+use optima_robotics::robot::ORobotDefault;
+use crate::optima_bevy_utils::camera::{BevyCameraControl, CameraBookmark};
+use crate::optima_bevy_utils::robotics::{robot_instance_label, BevyORobot, BevyORobotInstances, RobotStateEngine, RoboticsActions};
+use crate::optima_bevy_utils::shape_scene::{BevyEnvironmentObjects, EnvironmentObject, EnvironmentObjectShape};
+use crate::optima_bevy_utils::viewer_config::{OptimaViewerCameraConfig, OptimaViewerConfig};
+use crate::OptimaBevyTrait;
+
+/// Everything needed to bring a viewer back to the same setup: which robots are shown and where,
+/// their joint states, the obstacles, the camera, and the egui layout.  Saved as ron, or as json if
+/// the file has a `.json` extension.  Positions are z up and angles are roll, pitch, yaw in radians.
+/// For example (ron):
+///```text
+/// (
+///     robots: [
+///         (robot_name: "ur5", base_translation: (0.0, -0.5, 0.0)),
+///         (robot_name: "ur5", base_translation: (0.0, 0.5, 0.0), state: Some([0.0, -1.2, 1.0, 0.0, 0.0, 0.0])),
+///     ],
+///     environment_objects: [
+///         (name: "table", shape: Box(x_dim: 1.0, y_dim: 2.0, z_dim: 0.05), translation: (0.6, 0.0, -0.03)),
+///     ],
+///     camera: (location: (3.0, 1.0, 1.5)),
+/// )
+///```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimaViewerScene {
+    pub robots: Vec<OptimaViewerSceneRobot>,
+    pub environment_objects: Vec<OptimaViewerSceneObject>,
+    pub camera: OptimaViewerCameraConfig,
+    pub camera_bookmarks: Vec<CameraBookmark>,
+    pub ui_layout: Option<OEguiEngineState>
+}
+impl OptimaViewerScene {
+    pub fn load_from_path(path: &OPath) -> Result<Self, OptimaError> {
+        path.load_object_from_file().map_err(|e| OptimaError::new_file_io(path.to_string(), e))
+    }
+    pub fn save_to_path(&self, path: &OPath) -> Result<(), OptimaError> {
+        let format = match path.extension().and_then(|x| OSaveFormat::from_extension(&x)) {
+            Some(OSaveFormat::Json) => { OSaveFormat::Json }
+            _ => { OSaveFormat::Ron }
+        };
+        path.save_object_to_file(self, format).map_err(|e| OptimaError::new_file_io(path.to_string(), e))
+    }
+    /// Loads the scene at `path` and displays it, with the "Scene" window pointed at the same file.
+    pub fn bevy_display_from_path(path: &str) -> Result<(), OptimaError> {
+        let scene = Self::load_from_path(&OPath::Path(PathBuf::from(path)))?;
+        scene.bevy_display(path)
+    }
+    /// `scene_path` is the file the "Scene" window saves to and loads from, usually the one the scene
+    /// was loaded from.
+    pub fn bevy_display(&self, scene_path: &str) -> Result<(), OptimaError> {
+        self.bevy_get_display_app(scene_path)?.run();
+        Ok(())
+    }
+    /// Robots are loaded by name from their saved (preprocessed) files and become robot instances
+    /// 0, 1, ... in the order they are listed.  Also adds the "Scene" window, which saves the scene
+    /// as it is at that point to `scene_path` (or loads it back from there).
+    pub fn bevy_get_display_app(&self, scene_path: &str) -> Result<App, OptimaError> {
+        let mut viewer_config = OptimaViewerConfig::load_or_default();
+        viewer_config.camera = self.camera.clone();
+
+        let mut app = App::new();
+        app
+            .insert_resource(viewer_config)
+            .optima_bevy_base()
+            .optima_bevy_pan_orbit_camera()
+            .optima_bevy_starter_lights()
+            .optima_bevy_robotics_scene_visuals_starter()
+            .optima_bevy_egui()
+            .optima_bevy_camera_control();
+
+        for (robot_instance_idx, scene_robot) in self.robots.iter().enumerate() {
+            let robot = ORobotDefault::load_from_saved_robot(&scene_robot.robot_name)?;
+            let base_pose = Isometry3::from_constructors(&scene_robot.base_translation, &scene_robot.base_rpy);
+            app.optima_bevy_robot_instance::<f64, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra>(robot, base_pose, robot_instance_idx);
+        }
+
+        for scene_object in &self.environment_objects {
+            let pose = Isometry3::from_constructors(&scene_object.translation, &scene_object.rpy);
+            let object = EnvironmentObject::new_from_shape_description(&scene_object.name, &scene_object.shape, pose)?;
+            app.optima_bevy_environment_object::<f64, O3DPoseCategoryIsometry3>(object);
+        }
+
+        {
+            let mut camera_control = app.world.resource_mut::<BevyCameraControl>();
+            self.camera_bookmarks.iter().for_each(|x| camera_control.add_bookmark(x.clone()));
+        }
+
+        // joint states are restored through the joint sliders, which would otherwise overwrite them.
+        let mut mutex_guard = app.world.resource::<OEguiEngineWrapper>().get_mutex_guard();
+        mutex_guard.set_state(self.ui_layout.clone().unwrap_or_default());
+        for (robot_instance_idx, scene_robot) in self.robots.iter().enumerate() {
+            let Some(state) = &scene_robot.state else { continue; };
+            for (dof_idx, value) in state.iter().enumerate() {
+                mutex_guard.set_slider_value(&robot_instance_label(format!("joint_slider_dof_{}", dof_idx), robot_instance_idx), *value);
+            }
+        }
+        drop(mutex_guard);
+
+        app
+            .optima_bevy_frame_labels::<f64, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra>()
+            .optima_bevy_scene_file_panel::<f64, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra>(scene_path);
+
+        Ok(app)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimaViewerSceneRobot {
+    pub robot_name: String,
+    pub base_translation: [f64; 3],
+    pub base_rpy: [f64; 3],
+    /// Joint state the robot starts in; all zeros if None.
+    pub state: Option<Vec<f64>>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OptimaViewerSceneObject {
+    pub name: String,
+    pub shape: EnvironmentObjectShape,
+    #[serde(default)]
+    pub translation: [f64; 3],
+    #[serde(default)]
+    pub rpy: [f64; 3]
+}
+
+/// Drives the "Scene" window, which saves the current scene to `path` or loads it back from there.
+/// `path` is relative to the working directory unless absolute.
+#[derive(Resource)]
+pub struct BevySceneFile {
+    pub path: String,
+    status: String
+}
+impl BevySceneFile {
+    pub fn new(path: &str) -> Self {
+        Self { path: path.to_string(), status: "".to_string() }
+    }
+}
+
+pub struct SceneFileActions;
+impl SceneFileActions {
+    /// Robots are listed in the order of their instance idxs.  Environment objects that were not made
+    /// from a shape description (see `EnvironmentObject::shape_description`) cannot be saved and are
+    /// left out with a warning.
+    pub fn action_capture_scene<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Option<&BevyORobot<T, C, L>>,
+                                                                                                  robot_instances: Option<&BevyORobotInstances<T, C, L>>,
+                                                                                                  robot_state_engine: &RobotStateEngine,
+                                                                                                  environment_objects: Option<&BevyEnvironmentObjects<T, C>>,
+                                                                                                  camera_control: Option<&BevyCameraControl>,
+                                                                                                  egui_engine: &OEguiEngineWrapper) -> OptimaViewerScene {
+        let mut robots = vec![];
+        if let Some(robot) = robot {
+            robots.push((robot.1, robot.0.robot_name().to_string(), C::P::<T>::identity()));
+        }
+        if let Some(robot_instances) = robot_instances {
+            robot_instances.instances().iter().for_each(|x| robots.push((x.robot_instance_idx, x.robot.robot_name().to_string(), x.base_pose.clone())));
+        }
+        robots.sort_by_key(|x| x.0);
+
+        let robots = robots.iter().map(|(robot_instance_idx, robot_name, base_pose)| {
+            let (base_translation, base_rpy) = pose_to_translation_and_rpy(base_pose);
+            OptimaViewerSceneRobot { robot_name: robot_name.clone(), base_translation, base_rpy, state: robot_state_engine.get_robot_state(*robot_instance_idx).cloned() }
+        }).collect();
+
+        let mut scene_objects = vec![];
+        if let Some(environment_objects) = environment_objects {
+            for object in environment_objects.objects() {
+                match object.shape_description() {
+                    None => { warn!("environment object {} was not made from a shape description and is left out of the scene file.", object.name()); }
+                    Some(shape) => {
+                        let (translation, rpy) = pose_to_translation_and_rpy(object.pose());
+                        scene_objects.push(OptimaViewerSceneObject { name: object.name().to_string(), shape: shape.clone(), translation, rpy });
+                    }
+                }
+            }
+        }
+
+        let (camera, camera_bookmarks) = match camera_control {
+            None => { (OptimaViewerCameraConfig::default(), vec![]) }
+            Some(camera_control) => {
+                (OptimaViewerCameraConfig { location: camera_control.current_location().to_array(), focus: camera_control.current_focus().to_array() }, camera_control.bookmarks().clone())
+            }
+        };
+
+        OptimaViewerScene {
+            robots,
+            environment_objects: scene_objects,
+            camera,
+            camera_bookmarks,
+            ui_layout: Some(egui_engine.get_mutex_guard().state()),
+        }
+    }
+}
+
+impl SceneFileActions {
+    /// Applies the parts of `scene` that can change while the viewer is running: the joint states,
+    /// the camera, and the camera bookmarks.  Robots and environment objects are only created when
+    /// the viewer starts, so if the scene lists different ones than are shown, they are left as they
+    /// are and a message saying so is returned (the joint states of robots that do match are still
+    /// applied).
+    pub fn action_apply_scene<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(scene: &OptimaViewerScene,
+                                                                                                robot: Option<&BevyORobot<T, C, L>>,
+                                                                                                robot_instances: Option<&BevyORobotInstances<T, C, L>>,
+                                                                                                environment_objects: Option<&BevyEnvironmentObjects<T, C>>,
+                                                                                                camera_control: Option<&mut BevyCameraControl>,
+                                                                                                egui_engine: &Res<OEguiEngineWrapper>) -> Vec<String> {
+        let mut messages = vec![];
+
+        let mut robots = vec![];
+        if let Some(robot) = robot { robots.push((robot.1, robot.0.robot_name().to_string(), robot.0.num_dofs())); }
+        if let Some(robot_instances) = robot_instances { robot_instances.instances().iter().for_each(|x| robots.push((x.robot_instance_idx, x.robot.robot_name().to_string(), x.robot.num_dofs()))); }
+        robots.sort_by_key(|x| x.0);
+        if robots.len() != scene.robots.len() || robots.iter().zip(scene.robots.iter()).any(|(a, b)| a.1 != b.robot_name) {
+            messages.push("the scene lists different robots than are shown; restart the viewer to apply them.".to_string());
+        }
+        for ((robot_instance_idx, robot_name, num_dofs), scene_robot) in robots.iter().zip(scene.robots.iter()) {
+            if robot_name != &scene_robot.robot_name { continue; }
+            let Some(state) = &scene_robot.state else { continue; };
+            if state.len() != *num_dofs {
+                messages.push(format!("the state of robot {} in the scene has {} values, but the robot has {} dofs; it was not applied.", robot_instance_idx, state.len(), num_dofs));
+                continue;
+            }
+            RoboticsActions::action_set_joint_sliders(state, *robot_instance_idx, egui_engine);
+        }
+
+        let object_names: Vec<&str> = environment_objects.map(|x| x.objects().iter().map(|x| x.name()).collect()).unwrap_or_default();
+        if object_names.len() != scene.environment_objects.len() || object_names.iter().zip(scene.environment_objects.iter()).any(|(a, b)| *a != b.name) {
+            messages.push("the scene lists different environment objects than are shown; restart the viewer to apply them.".to_string());
+        }
+
+        if let Some(camera_control) = camera_control {
+            camera_control.fly_to(Vec3::from_array(scene.camera.location), Vec3::from_array(scene.camera.focus));
+            scene.camera_bookmarks.iter().for_each(|x| camera_control.add_bookmark(x.clone()));
+        }
+
+        messages
+    }
+}
+
+fn pose_to_translation_and_rpy<T: AD, P: O3DPose<T>>(pose: &P) -> ([f64; 3], [f64; 3]) {
+    let t = pose.translation();
+    let rpy = pose.rotation().euler_angles();
+    ([t.x().to_constant(), t.y().to_constant(), t.z().to_constant()], [rpy[0].to_constant(), rpy[1].to_constant(), rpy[2].to_constant()])
+}
+
+pub struct SceneFileSystems;
+impl SceneFileSystems {
+    /// Loading applies the scene through `SceneFileActions::action_apply_scene`, so only the joint
+    /// states and camera change; anything else that differs is reported in the window.
+    pub fn system_scene_file_panel<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(mut scene_file: ResMut<BevySceneFile>,
+                                                                                                     robot: Option<Res<BevyORobot<T, C, L>>>,
+                                                                                                     robot_instances: Option<Res<BevyORobotInstances<T, C, L>>>,
+                                                                                                     robot_state_engine: Res<RobotStateEngine>,
+                                                                                                     environment_objects: Option<Res<BevyEnvironmentObjects<T, C>>>,
+                                                                                                     mut camera_control: Option<ResMut<BevyCameraControl>>,
+                                                                                                     mut contexts: EguiContexts,
+                                                                                                     egui_engine: Res<OEguiEngineWrapper>,
+                                                                                                     window_query: Query<&Window, With<PrimaryWindow>>) {
+        let mut save = false;
+        let mut load = false;
+        OEguiWindow::new("Scene", true, true, false, false, false, true)
+            .show("scene_file_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("file");
+                    ui.text_edit_singleline(&mut scene_file.path);
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() { save = true; }
+                    if ui.button("Save as...").clicked() { OEguiFileDialog::open("scene_save_dialog", &egui_engine); }
+                    if ui.button("Load").clicked() { load = true; }
+                    if ui.button("Open...").clicked() { OEguiFileDialog::open("scene_open_dialog", &egui_engine); }
+                });
+                if !scene_file.status.is_empty() { ui.label(scene_file.status.as_str()); }
+            });
+
+        OEguiFileDialog::new("Save Scene", OEguiFileDialogMode::Save)
+            .with_extensions(&["ron", "json"])
+            .with_default_filename(&scene_file.path)
+            .show("scene_save_dialog", contexts.ctx_mut(), &egui_engine);
+        OEguiFileDialog::new("Open Scene", OEguiFileDialogMode::Open)
+            .with_extensions(&["ron", "json"])
+            .show("scene_open_dialog", contexts.ctx_mut(), &egui_engine);
+        let picked = egui_engine.get_mutex_guard().get_file_dialog_response("scene_save_dialog").and_then(|x| x.picked().cloned());
+        if let Some(path) = picked {
+            scene_file.path = path.to_string();
+            save = true;
+        }
+        let opened = egui_engine.get_mutex_guard().get_file_dialog_response("scene_open_dialog").and_then(|x| x.picked().cloned());
+        if let Some(path) = opened {
+            scene_file.path = path.to_string();
+            load = true;
+        }
+
+        if save {
+            let scene = SceneFileActions::action_capture_scene(robot.as_deref(), robot_instances.as_deref(), &robot_state_engine, environment_objects.as_deref(), camera_control.as_deref(), &egui_engine);
+            let path = OPath::Path(PathBuf::from(scene_file.path.clone()));
+            scene_file.status = match scene.save_to_path(&path) {
+                Ok(()) => {
+                    egui_engine.get_mutex_guard().push_info(&format!("saved scene to {}.", scene_file.path));
+                    format!("saved {} robot(s) and {} object(s).", scene.robots.len(), scene.environment_objects.len())
+                }
+                Err(e) => {
+                    egui_engine.get_mutex_guard().push_error(&format!("could not save scene to {}.", scene_file.path));
+                    format!("could not save: {}", e)
+                }
+            };
+        }
+
+        if load {
+            let path = OPath::Path(PathBuf::from(scene_file.path.clone()));
+            scene_file.status = match OptimaViewerScene::load_from_path(&path) {
+                Ok(scene) => {
+                    let messages = SceneFileActions::action_apply_scene(&scene, robot.as_deref(), robot_instances.as_deref(), environment_objects.as_deref(), camera_control.as_deref_mut(), &egui_engine);
+                    let mut mutex_guard = egui_engine.get_mutex_guard();
+                    messages.iter().for_each(|x| mutex_guard.push_warning(x));
+                    mutex_guard.push_info(&format!("loaded scene from {}.", scene_file.path));
+                    if messages.is_empty() { format!("loaded {}.", scene_file.path) } else { messages.join("\n") }
+                }
+                Err(e) => {
+                    egui_engine.get_mutex_guard().push_error(&format!("could not load scene from {}.", scene_file.path));
+                    format!("could not load: {}", e)
+                }
+            };
+        }
+    }
+}
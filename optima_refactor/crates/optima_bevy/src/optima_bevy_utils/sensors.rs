@@ -0,0 +1,300 @@
+use std::sync::Mutex;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::extract_resource::ExtractResource;
+use bevy::render::render_graph::{Node, NodeRunError, RenderGraphContext};
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use nalgebra::{Isometry3, Point3, Vector3};
+use parry_ad::shape::TriMesh;
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_3d_spatial::optima_3d_rotation::O3DRotation;
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_linalg::{OLinalgCategory, OVec};
+use optima_robotics::robot::ORobot;
+use optima_proximity::shape_scene::ShapeSceneTrait;
+use optima_proximity::ray_casting::OParryRayCaster;
+use crate::optima_bevy_utils::readback::ImageReadbackTarget;
+use crate::optima_bevy_utils::robotics::{BevyORobot, RobotStateEngine};
+use crate::optima_bevy_utils::shape_scene::BevyEnvironmentObjects;
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+/// A simulated rgb-d camera mounted on a robot link.  The sensor frame is given relative to the link
+/// and follows the usual robotics camera body convention: the camera looks down its +x axis, with +z
+/// up in the image.
+#[derive(Clone, Debug)]
+pub struct CameraSensorConfig {
+    pub name: String,
+    pub link_idx: usize,
+    pub offset_translation: [f64; 3],
+    /// roll, pitch, yaw (radians) of the sensor frame relative to the link.
+    pub offset_rpy: [f64; 3],
+    pub width: u32,
+    pub height: u32,
+    /// vertical field of view in radians.
+    pub fov_y: f64,
+    /// depth rays that do not hit anything within this distance read as infinity.
+    pub max_depth: f64
+}
+impl CameraSensorConfig {
+    pub fn new(name: &str, link_idx: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            link_idx,
+            offset_translation: [0.0; 3],
+            offset_rpy: [0.0; 3],
+            width: 160,
+            height: 120,
+            fov_y: 60.0_f64.to_radians(),
+            max_depth: 10.0,
+        }
+    }
+}
+
+/// One frame of a camera sensor.  Both buffers are row-major, starting at the top left pixel.
+#[derive(Clone, Debug)]
+pub struct CameraSensorFrame {
+    pub frame: u64,
+    pub width: u32,
+    pub height: u32,
+    /// tightly packed rgba8 pixels, as rendered.
+    pub rgba8: Vec<u8>,
+    /// distance along the optical axis (in meters) to the nearest robot visual mesh or environment
+    /// shape, or infinity if there is none within `max_depth`.
+    pub depth: Vec<f32>,
+    /// world (z up) pose of the sensor frame when the frame was taken.
+    pub translation: [f64; 3],
+    pub rotation_wxyz: [f64; 4]
+}
+
+struct CameraSensor {
+    config: CameraSensorConfig,
+    rgb_frames: Mutex<Receiver<(u64, Vec<u8>)>>,
+    /// depth images (and sensor poses) waiting for the rgb image rendered on the same frame.
+    pending_depth: Vec<(u64, Vec<f32>, [f64; 3], [f64; 4])>,
+    latest_frame: Option<CameraSensorFrame>,
+    subscribers: Mutex<Vec<Sender<CameraSensorFrame>>>
+}
+
+/// Camera sensors added through `optima_bevy_camera_sensor`.  Every frame, each sensor's camera is
+/// moved to its link, renders the scene into an offscreen image, and ray casts a depth image against
+/// the same visual meshes that are rendered (and the shapes of any `BevyEnvironmentObjects`).  Frames can be polled with
+/// `latest_frame`, or streamed over a channel from `subscribe`.
+#[derive(Resource)]
+pub struct BevyCameraSensors {
+    sensors: Vec<CameraSensor>,
+    frame: u64
+}
+impl BevyCameraSensors {
+    pub fn new() -> Self {
+        Self { sensors: vec![], frame: 0 }
+    }
+    pub fn latest_frame(&self, name: &str) -> Option<&CameraSensorFrame> {
+        self.get_sensor(name).and_then(|x| x.latest_frame.as_ref())
+    }
+    /// Every frame of the sensor from now on is also sent through the returned channel.
+    pub fn subscribe(&self, name: &str) -> Result<Receiver<CameraSensorFrame>, String> {
+        let sensor = self.get_sensor(name).ok_or(format!("no camera sensor named {}.", name))?;
+        let (tx, rx) = channel();
+        sensor.subscribers.lock().unwrap_or_else(|e| e.into_inner()).push(tx);
+        Ok(rx)
+    }
+    pub fn sensor_names(&self) -> Vec<String> {
+        self.sensors.iter().map(|x| x.config.name.clone()).collect()
+    }
+    fn get_sensor(&self, name: &str) -> Option<&CameraSensor> {
+        self.sensors.iter().find(|x| x.config.name == name)
+    }
+}
+
+/// Render world side of the camera sensors, in the same order as in `BevyCameraSensors`.
+#[derive(Resource, Clone, ExtractResource)]
+pub struct CameraSensorRenderTargets(pub (crate) Vec<ImageReadbackTarget>);
+
+/// Marks the camera rendering sensor `sensor_idx` (its index in `BevyCameraSensors`).
+#[derive(Component)]
+pub struct CameraSensorCamera {
+    pub sensor_idx: usize
+}
+
+pub struct SensorActions;
+impl SensorActions {
+    /// Creates the sensor's render target, spawns its camera, and registers it in the two resources
+    /// (which are inserted if this is the first sensor).  Must be called after the render plugin is
+    /// added.
+    pub fn action_add_camera_sensor(world: &mut World, config: CameraSensorConfig) {
+        if world.get_resource::<BevyCameraSensors>().is_none() {
+            world.insert_resource(BevyCameraSensors::new());
+            world.insert_resource(CameraSensorRenderTargets(vec![]));
+        }
+        let (target, rgb_frames) = ImageReadbackTarget::new(world, config.width, config.height, &format!("optima_camera_sensor_{}", config.name));
+        let sensor_idx = world.resource::<BevyCameraSensors>().sensors.len();
+
+        world.spawn((Camera3dBundle {
+            camera: Camera { target: RenderTarget::Image(target.image().clone()), order: -1 - sensor_idx as isize, ..Default::default() },
+            projection: Projection::Perspective(PerspectiveProjection {
+                fov: config.fov_y as f32,
+                aspect_ratio: config.width as f32 / config.height as f32,
+                near: 0.01,
+                far: config.max_depth as f32,
+            }),
+            ..Default::default()
+        }, CameraSensorCamera { sensor_idx }));
+
+        world.resource_mut::<CameraSensorRenderTargets>().0.push(target);
+        world.resource_mut::<BevyCameraSensors>().sensors.push(CameraSensor {
+            config,
+            rgb_frames: Mutex::new(rgb_frames),
+            pending_depth: vec![],
+            latest_frame: None,
+            subscribers: Mutex::new(vec![]),
+        });
+    }
+}
+
+pub struct SensorSystems;
+impl SensorSystems {
+    /// Runs after the robot's state is updated: moves each sensor camera to its link and ray casts
+    /// its depth image for the same frame.  The robot's visual meshes are loaded on the first frame
+    /// (and again whenever the robot changes).
+    pub fn system_update_camera_sensors<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                         robot_state_engine: Res<RobotStateEngine>,
+                                                                                                         environment_objects: Option<Res<BevyEnvironmentObjects<T, C>>>,
+                                                                                                         mut sensors: ResMut<BevyCameraSensors>,
+                                                                                                         mut targets: ResMut<CameraSensorRenderTargets>,
+                                                                                                         mut visual_meshes: Local<Option<Vec<(usize, usize, TriMesh<T>)>>>,
+                                                                                                         mut query: Query<(&CameraSensorCamera, &mut Transform)>) {
+        sensors.frame += 1;
+        let frame = sensors.frame;
+        targets.0.iter_mut().for_each(|x| x.frame = frame);
+
+        let state = match robot_state_engine.get_robot_state(robot.1) {
+            None => { vec![T::zero(); robot.0.num_dofs()] }
+            Some(state) => { OVec::ovec_to_other_ad_type::<T>(state) }
+        };
+        let fk_res = robot.0.forward_kinematics(&state, None);
+
+        if robot.is_changed() || visual_meshes.is_none() { *visual_meshes = Some(load_visual_meshes(&robot.0)); }
+        let visual_meshes = visual_meshes.as_ref().expect("error");
+
+        let environment_poses = environment_objects.as_ref().map(|x| x.shape_scene().get_shape_poses(&()).into_owned());
+        let mut caster = OParryRayCaster::new();
+        for (link_idx, visual_idx, trimesh) in visual_meshes.iter() {
+            let Ok(link_pose) = fk_res.get_link_pose(*link_idx) else { continue; };
+            let pose = link_pose.mul(robot.0.links()[*link_idx].visual()[*visual_idx].origin().pose());
+            caster.add_shape(trimesh, pose.o3dpose_downcast_or_convert::<Isometry3<T>>().into_owned());
+        }
+        if let (Some(environment_objects), Some(environment_poses)) = (&environment_objects, &environment_poses) {
+            if let Err(e) = caster.add_shapes(environment_objects.shape_scene().get_shapes(), environment_poses) { warn!("the environment objects were left out of the camera sensor depth images ({}).", e); }
+        }
+
+        for (camera, mut transform) in query.iter_mut() {
+            let Some(sensor) = sensors.sensors.get_mut(camera.sensor_idx) else { continue; };
+            let Ok(link_pose) = fk_res.get_link_pose(sensor.config.link_idx) else { continue; };
+            let c = &sensor.config;
+            let offset = C::P::<T>::from_constructors(&[T::constant(c.offset_translation[0]), T::constant(c.offset_translation[1]), T::constant(c.offset_translation[2])], &[T::constant(c.offset_rpy[0]), T::constant(c.offset_rpy[1]), T::constant(c.offset_rpy[2])]);
+            let sensor_pose = link_pose.mul(&offset);
+
+            // bevy cameras look down -z with +y up, so the sensor's body frame is rotated onto that.
+            let body_to_camera = Quat::from_mat3(&Mat3::from_cols(Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec3::new(-1.0, 0.0, 0.0)));
+            let sensor_transform = TransformUtils::util_convert_3d_pose_to_y_up_bevy_transform(&sensor_pose);
+            *transform = Transform::from_translation(sensor_transform.translation).with_rotation(sensor_transform.rotation * body_to_camera);
+
+            let t = sensor_pose.translation();
+            let r = sensor_pose.rotation().unit_quaternion_as_wxyz_slice();
+            let translation = [t.x().to_constant(), t.y().to_constant(), t.z().to_constant()];
+            let rotation_wxyz = [r[0].to_constant(), r[1].to_constant(), r[2].to_constant(), r[3].to_constant()];
+
+            let depth = ray_cast_depth_image(c, &transform, &caster);
+            sensor.pending_depth.push((frame, depth, translation, rotation_wxyz));
+            // frames that never got an rgb image (e.g., before the first render) are dropped.
+            while sensor.pending_depth.len() > 8 { sensor.pending_depth.remove(0); }
+        }
+    }
+    /// Pairs rendered rgb images with the depth image of the same frame, and publishes the result.
+    pub fn system_receive_camera_sensor_frames(mut sensors: ResMut<BevyCameraSensors>) {
+        for sensor in sensors.sensors.iter_mut() {
+            let rgb_frames: Vec<(u64, Vec<u8>)> = sensor.rgb_frames.get_mut().unwrap_or_else(|e| e.into_inner()).try_iter().collect();
+            for (frame, rgba8) in rgb_frames {
+                let Some(i) = sensor.pending_depth.iter().position(|x| x.0 == frame) else { continue; };
+                let (_, depth, translation, rotation_wxyz) = sensor.pending_depth.remove(i);
+                sensor.pending_depth.retain(|x| x.0 > frame);
+
+                let out = CameraSensorFrame { frame, width: sensor.config.width, height: sensor.config.height, rgba8, depth, translation, rotation_wxyz };
+                sensor.subscribers.get_mut().unwrap_or_else(|e| e.into_inner()).retain(|x| x.send(out.clone()).is_ok());
+                sensor.latest_frame = Some(out);
+            }
+        }
+    }
+    pub fn system_read_back_camera_sensor_frames(targets: Option<Res<CameraSensorRenderTargets>>, render_device: Res<RenderDevice>) {
+        let Some(targets) = targets else { return; };
+        targets.0.iter().for_each(|x| x.read_back(&render_device));
+    }
+}
+
+/// Triangle meshes of the links' visuals (the meshes that are rendered), with the urdf mesh scale
+/// applied, in the frame of the visual (see `ORobot::link_visual_trimesh`).  Visuals whose mesh
+/// cannot be loaded are left out of the depth image.
+fn load_visual_meshes<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static>(robot: &ORobot<T, C, L>) -> Vec<(usize, usize, TriMesh<T>)> {
+    let mut out = vec![];
+    for (link_idx, link) in robot.links().iter().enumerate() {
+        if !link.is_present_in_model() { continue; }
+        for visual_idx in 0..link.visual().len() {
+            let Some(trimesh) = robot.link_visual_trimesh(link_idx, visual_idx) else {
+                warn!("could not load visual {} of link {} for depth ray casting.", visual_idx, link.name());
+                continue;
+            };
+            if trimesh.indices().is_empty() { continue; }
+
+            let points = trimesh.points().iter().map(|p| Point3::new(T::constant(p[0]), T::constant(p[1]), T::constant(p[2]))).collect();
+            out.push((link_idx, visual_idx, TriMesh::new(points, trimesh.indices_as_u32s())));
+        }
+    }
+
+    out
+}
+
+/// Casts one ray per pixel through the sensor camera at `transform` (bevy space).  Shapes that
+/// contain the sensor origin are skipped, so a camera mounted on a link's surface is not blocked by
+/// the link itself.
+fn ray_cast_depth_image<T: AD>(config: &CameraSensorConfig, transform: &Transform, caster: &OParryRayCaster<T>) -> Vec<f32> {
+    let origin = TransformUtils::util_convert_bevy_y_up_vec3_to_z_up_vec3(transform.translation);
+    let origin = Point3::new(T::constant(origin.x as f64), T::constant(origin.y as f64), T::constant(origin.z as f64));
+    let mut caster = caster.clone();
+    caster.remove_shapes_containing_point(&origin);
+
+    let (width, height) = (config.width as usize, config.height as usize);
+    let focal_length = (height as f32 / 2.0) / (config.fov_y as f32 / 2.0).tan();
+    let max_depth = T::constant(config.max_depth);
+
+    let mut out = vec![f32::INFINITY; width * height];
+    for v in 0..height {
+        for u in 0..width {
+            // scaled so that the component along the optical axis is 1, which makes the time of
+            // impact equal to the depth.
+            let dir_camera = Vec3::new((u as f32 + 0.5 - width as f32 / 2.0) / focal_length, -(v as f32 + 0.5 - height as f32 / 2.0) / focal_length, -1.0);
+            let dir = TransformUtils::util_convert_bevy_y_up_vec3_to_z_up_vec3(transform.rotation * dir_camera);
+            let dir = Vector3::new(T::constant(dir.x as f64), T::constant(dir.y as f64), T::constant(dir.z as f64));
+            if let Some(hit) = caster.cast_ray(&origin, &dir, max_depth) { out[v * width + u] = hit.toi.to_constant() as f32; }
+        }
+    }
+
+    out
+}
+
+/// Render graph node that copies every sensor image into its read back buffer once all cameras
+/// have rendered.
+#[derive(Default)]
+pub struct CameraSensorCopyNode;
+impl CameraSensorCopyNode {
+    pub const NAME: &'static str = "optima_camera_sensor_copy";
+}
+impl Node for CameraSensorCopyNode {
+    fn run(&self, _graph: &mut RenderGraphContext, render_context: &mut RenderContext, world: &World) -> Result<(), NodeRunError> {
+        let Some(targets) = world.get_resource::<CameraSensorRenderTargets>() else { return Ok(()) };
+        targets.0.iter().for_each(|x| x.copy_image_to_buffer(render_context, world));
+
+        Ok(())
+    }
+}
@@ -4,11 +4,17 @@ use bevy::asset::AssetServer;
 use bevy::pbr::{AlphaMode, PbrBundle};
 use bevy::prelude::{Assets, Color, Commands, Component, Mesh, Res, ResMut, Resource, shape, StandardMaterial, Visibility};
 use bevy::utils::default;
-use parry_ad::shape::TypedShape;
+use nalgebra::Vector3;
+use optima_3d_mesh::ToTriMesh;
+use parry_ad::shape::{Ball, Cuboid, TypedShape};
+use serde::{Deserialize, Serialize};
 use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_error::OptimaError;
+use optima_file::path::OStemCellPath;
 use optima_proximity::shape_scene::{OParryGenericShapeScene, ShapeSceneTrait};
 use optima_proximity::shapes::{OParryShape, OParryShpGeneric, OParryShpTrait};
 use crate::optima_bevy_utils::file::get_asset_path_str_from_ostemcellpath;
+use crate::optima_bevy_utils::mesh::MeshUtils;
 use crate::optima_bevy_utils::transform::TransformUtils;
 
 pub struct ShapeSceneActions;
@@ -36,18 +42,31 @@ impl ShapeSceneActions {
             Self::action_spawn_parry_shape_generic(&full, pose, ParryShapeSceneMeshLabel::new(scene_type, ShapeType::ConvexShape, i), Visibility::Hidden, commands, asset_server, meshes, materials);
 
             let convex_subcomponents = parry_shape.convex_subcomponents();
-            for convex_subcomponent in convex_subcomponents {
+            for (j, convex_subcomponent) in convex_subcomponents.iter().enumerate() {
                 let bounding_sphere = convex_subcomponent.bounding_sphere();
                 let obb = convex_subcomponent.obb();
                 let full = convex_subcomponent.base_shape();
 
-                Self::action_spawn_parry_shape_generic(&bounding_sphere, pose, ParryShapeSceneMeshLabel::new(scene_type, ShapeType::SubcomponentsBoundingSphere, i), Visibility::Hidden, commands, asset_server, meshes, materials);
-                Self::action_spawn_parry_shape_generic(&obb, pose, ParryShapeSceneMeshLabel::new(scene_type, ShapeType::SubcomponentsOBB, i), Visibility::Hidden, commands, asset_server, meshes, materials);
-                Self::action_spawn_parry_shape_generic(&full, pose, ParryShapeSceneMeshLabel::new(scene_type, ShapeType::SubcomponentsConvexShape, i), Visibility::Visible, commands, asset_server, meshes, materials);
+                Self::action_spawn_parry_shape_generic(&bounding_sphere, pose, ParryShapeSceneMeshLabel::new_subcomponent(scene_type, ShapeType::SubcomponentsBoundingSphere, i, j), Visibility::Hidden, commands, asset_server, meshes, materials);
+                Self::action_spawn_parry_shape_generic(&obb, pose, ParryShapeSceneMeshLabel::new_subcomponent(scene_type, ShapeType::SubcomponentsOBB, i, j), Visibility::Hidden, commands, asset_server, meshes, materials);
+                Self::action_spawn_parry_shape_generic(&full, pose, ParryShapeSceneMeshLabel::new_subcomponent(scene_type, ShapeType::SubcomponentsConvexShape, i, j), Visibility::Visible, commands, asset_server, meshes, materials);
             }
         }
     }
 
+    pub fn action_spawn_environment_objects<T: AD, C: O3DPoseCategory>(environment_objects: &BevyEnvironmentObjects<T, C>,
+                                                                      commands: &mut Commands,
+                                                                      asset_server: &Res<AssetServer>,
+                                                                      meshes: &mut ResMut<Assets<Mesh>>,
+                                                                      materials: &mut ResMut<Assets<StandardMaterial>>) {
+        let shapes = environment_objects.shape_scene.get_shapes();
+        let poses = environment_objects.shape_scene.get_shape_poses(&());
+
+        for (i, (parry_shape, pose)) in shapes.iter().zip(poses.iter()).enumerate() {
+            Self::action_spawn_parry_shape_generic(parry_shape.base_shape().base_shape(), pose, ParryShapeSceneMeshLabel::new(ShapeSceneType::Environment, ShapeType::ConvexShape, i), Visibility::Visible, commands, asset_server, meshes, materials);
+        }
+    }
+
     fn action_spawn_parry_shape_generic<T: AD, P: O3DPose<T>>(shape: &OParryShpGeneric<T, P>,
                                                               pose: &P,
                                                               label: ParryShapeSceneMeshLabel,
@@ -95,6 +114,10 @@ impl ShapeSceneActions {
                     segments: 30,
                 }.into())
             }
+            // capsules and cones may sit anywhere in their own frame, so they take parry's own tessellation.
+            TypedShape::Capsule(_) | TypedShape::Cone(_) => {
+                meshes.add(MeshUtils::util_trimesh_to_bevy_mesh(&shape.to_trimesh()))
+            }
             _ => { return; }
         };
 
@@ -113,10 +136,27 @@ pub struct ParryShapeSceneMeshLabel {
     pub scene_type: ShapeSceneType,
     pub shape_type: ShapeType,
     pub shape_idx: usize,
+    pub subcomponent_idx: Option<usize>
 }
 impl ParryShapeSceneMeshLabel {
     pub fn new(scene_type: ShapeSceneType, shape_type: ShapeType, shape_idx: usize) -> Self {
-        Self { scene_type, shape_type, shape_idx }
+        Self { scene_type, shape_type, shape_idx, subcomponent_idx: None }
+    }
+    pub fn new_subcomponent(scene_type: ShapeSceneType, shape_type: ShapeType, shape_idx: usize, subcomponent_idx: usize) -> Self {
+        Self { scene_type, shape_type, shape_idx, subcomponent_idx: Some(subcomponent_idx) }
+    }
+    /// The shape this mesh was spawned from, within the full `shape`.
+    pub fn get_shape<'a, T: AD, P: O3DPose<T>>(&self, shape: &'a OParryShape<T, P>) -> Option<&'a OParryShpGeneric<T, P>> {
+        let hierarchy = match self.subcomponent_idx {
+            None => { shape.base_shape() }
+            Some(subcomponent_idx) => { shape.convex_subcomponents().get(subcomponent_idx)? }
+        };
+        let out = match self.shape_type {
+            ShapeType::BoundingSphere | ShapeType::SubcomponentsBoundingSphere => { hierarchy.bounding_sphere() }
+            ShapeType::OBB | ShapeType::SubcomponentsOBB => { hierarchy.obb() }
+            ShapeType::ConvexShape | ShapeType::SubcomponentsConvexShape => { hierarchy.base_shape() }
+        };
+        Some(out)
     }
 }
 
@@ -124,6 +164,21 @@ impl ParryShapeSceneMeshLabel {
 pub enum ShapeType {
     BoundingSphere, OBB, ConvexShape, SubcomponentsBoundingSphere, SubcomponentsOBB, SubcomponentsConvexShape
 }
+impl ShapeType {
+    pub fn all() -> [ShapeType; 6] {
+        [ShapeType::ConvexShape, ShapeType::OBB, ShapeType::BoundingSphere, ShapeType::SubcomponentsConvexShape, ShapeType::SubcomponentsOBB, ShapeType::SubcomponentsBoundingSphere]
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            ShapeType::BoundingSphere => { "bounding spheres" }
+            ShapeType::OBB => { "OBBs" }
+            ShapeType::ConvexShape => { "convex hulls" }
+            ShapeType::SubcomponentsBoundingSphere => { "subcomponent bounding spheres" }
+            ShapeType::SubcomponentsOBB => { "subcomponent OBBs" }
+            ShapeType::SubcomponentsConvexShape => { "convex subcomponents" }
+        }
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, Copy)]
 pub enum ShapeSceneType {
@@ -132,6 +187,13 @@ pub enum ShapeSceneType {
 
 pub struct ShapeSceneSystems;
 impl ShapeSceneSystems {
+    pub fn system_spawn_environment_objects<T: AD, C: O3DPoseCategory + 'static>(environment_objects: Res<BevyEnvironmentObjects<T, C>>,
+                                                                               mut commands: Commands,
+                                                                               asset_server: Res<AssetServer>,
+                                                                               mut meshes: ResMut<Assets<Mesh>>,
+                                                                               mut materials: ResMut<Assets<StandardMaterial>>) {
+        ShapeSceneActions::action_spawn_environment_objects(&*environment_objects, &mut commands, &asset_server, &mut meshes, &mut materials);
+    }
     /*
     pub fn system_spawn_shape_scene<T: AD, P: O3DPose<T>, S: ShapeSceneTrait<T, P>>(shape_scene: Res<ShapeSceneBevyWrapper<T, P, S>>) {
 
@@ -165,4 +227,111 @@ impl<T: AD, C: O3DPoseCategory> ShapeSceneTrait<T, C::P<T>> for BevyOParryGeneri
     fn shape_id_to_shape_str(&self, id: u64) -> String {
         self.0.shape_id_to_shape_str(id)
     }
-}
\ No newline at end of file
+}
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// An obstacle placed in the scene at a fixed pose.
+#[derive(Clone)]
+pub struct EnvironmentObject<T: AD, P: O3DPose<T>> {
+    name: String,
+    shape: OParryShape<T, P>,
+    pose: P,
+    shape_description: Option<EnvironmentObjectShape>
+}
+impl<T: AD, P: O3DPose<T>> EnvironmentObject<T, P> {
+    pub fn new_box(name: &str, x_dim: T, y_dim: T, z_dim: T, pose: P) -> Self {
+        let half = T::constant(0.5);
+        let mut out = Self::new_from_parry_shape(name, OParryShape::new_default(Cuboid::new(Vector3::new(half * x_dim, half * y_dim, half * z_dim)), P::identity()), pose);
+        out.shape_description = Some(EnvironmentObjectShape::Box { x_dim: x_dim.to_constant(), y_dim: y_dim.to_constant(), z_dim: z_dim.to_constant() });
+        out
+    }
+    pub fn new_sphere(name: &str, radius: T, pose: P) -> Self {
+        let mut out = Self::new_from_parry_shape(name, OParryShape::new_default(Ball::new(radius), P::identity()), pose);
+        out.shape_description = Some(EnvironmentObjectShape::Sphere { radius: radius.to_constant() });
+        out
+    }
+    /// The cylinder's axis is the local z axis.
+    pub fn new_cylinder(name: &str, radius: T, height: T, pose: P) -> Self {
+        // parry cylinders are aligned with the local y axis, which the offset rotates onto z.
+        let offset = P::from_constructors(&[T::zero(); 3], &[T::constant(std::f64::consts::FRAC_PI_2), T::zero(), T::zero()]);
+        let mut out = Self::new_from_parry_shape(name, OParryShape::new_default_cylinder(T::constant(0.5) * height, radius, offset), pose);
+        out.shape_description = Some(EnvironmentObjectShape::Cylinder { radius: radius.to_constant(), height: height.to_constant() });
+        out
+    }
+    /// Mesh obstacles are represented by the convex hull of the mesh.
+    pub fn new_mesh(name: &str, mesh_path: OStemCellPath, pose: P) -> Result<Self, OptimaError> {
+        Ok(Self::new_from_parry_shape(name, OParryShape::new_default_convex_shape_from_mesh_paths(mesh_path, P::identity(), None)?, pose))
+    }
+    /// Same as `new_mesh`, with the mesh given by its path components within the asset folder.  Unlike
+    /// `new_mesh`, the object can be saved as part of a scene file.
+    pub fn new_mesh_from_asset_path(name: &str, asset_path_components: &Vec<String>, pose: P) -> Result<Self, OptimaError> {
+        let mut out = Self::new_mesh(name, OStemCellPath::new_asset_path_from_string_components(asset_path_components), pose)?;
+        out.shape_description = Some(EnvironmentObjectShape::Mesh { asset_path_components: asset_path_components.clone() });
+        Ok(out)
+    }
+    pub fn new_from_shape_description(name: &str, shape_description: &EnvironmentObjectShape, pose: P) -> Result<Self, OptimaError> {
+        match shape_description {
+            EnvironmentObjectShape::Box { x_dim, y_dim, z_dim } => { Ok(Self::new_box(name, T::constant(*x_dim), T::constant(*y_dim), T::constant(*z_dim), pose)) }
+            EnvironmentObjectShape::Sphere { radius } => { Ok(Self::new_sphere(name, T::constant(*radius), pose)) }
+            EnvironmentObjectShape::Cylinder { radius, height } => { Ok(Self::new_cylinder(name, T::constant(*radius), T::constant(*height), pose)) }
+            EnvironmentObjectShape::Mesh { asset_path_components } => { Self::new_mesh_from_asset_path(name, asset_path_components, pose) }
+        }
+    }
+    pub fn new_from_parry_shape(name: &str, shape: OParryShape<T, P>, pose: P) -> Self {
+        Self { name: name.to_string(), shape, pose, shape_description: None }
+    }
+    #[inline(always)]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    #[inline(always)]
+    pub fn shape(&self) -> &OParryShape<T, P> {
+        &self.shape
+    }
+    #[inline(always)]
+    pub fn pose(&self) -> &P {
+        &self.pose
+    }
+    /// None for objects made from an arbitrary parry shape or an `OStemCellPath`.
+    #[inline(always)]
+    pub fn shape_description(&self) -> &Option<EnvironmentObjectShape> {
+        &self.shape_description
+    }
+}
+
+/// What an `EnvironmentObject` was made from, kept so it can be written to (and rebuilt from) a scene
+/// file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum EnvironmentObjectShape {
+    Box { x_dim: f64, y_dim: f64, z_dim: f64 },
+    Sphere { radius: f64 },
+    Cylinder { radius: f64, height: f64 },
+    Mesh { asset_path_components: Vec<String> }
+}
+
+/// The obstacles in the scene, along with the parry shape scene built from them that robot vs.
+/// environment proximity queries are run against.
+#[derive(Resource)]
+pub struct BevyEnvironmentObjects<T: AD, C: O3DPoseCategory> {
+    objects: Vec<EnvironmentObject<T, C::P<T>>>,
+    shape_scene: OParryGenericShapeScene<T, C::P<T>>
+}
+impl<T: AD, C: O3DPoseCategory> BevyEnvironmentObjects<T, C> {
+    pub fn new() -> Self {
+        Self { objects: vec![], shape_scene: OParryGenericShapeScene::new_empty() }
+    }
+    /// Returns the index of the added object, which is also its index in `shape_scene`.
+    pub fn add_object(&mut self, object: EnvironmentObject<T, C::P<T>>) -> usize {
+        self.shape_scene.add_shape(object.shape.clone(), object.pose.clone());
+        self.objects.push(object);
+        self.objects.len() - 1
+    }
+    #[inline(always)]
+    pub fn objects(&self) -> &Vec<EnvironmentObject<T, C::P<T>>> {
+        &self.objects
+    }
+    #[inline(always)]
+    pub fn shape_scene(&self) -> &OParryGenericShapeScene<T, C::P<T>> {
+        &self.shape_scene
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::Path;
+use bevy::prelude::*;
+use optima_error::OptimaError;
+use optima_shared_memory::OSharedMemoryRingBuffer;
+use crate::optima_bevy_utils::robotics::RobotStateEngine;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SharedMemoryStateSource {
+    /// slots hold exactly one robot state.
+    JointStates,
+    /// slots are laid out as written by `OSharedMemoryIKServer`: [goal write index, cost, state...].
+    IKSolutions
+}
+impl SharedMemoryStateSource {
+    fn state_offset(&self) -> usize {
+        match self {
+            SharedMemoryStateSource::JointStates => { 0 }
+            SharedMemoryStateSource::IKSolutions => { 2 }
+        }
+    }
+}
+
+/// Mirrors the latest state in a shared memory ring buffer onto a robot in the viewer.  Only the
+/// most recent entry is shown each frame, so the writer can run much faster than the frame rate.
+#[derive(Resource)]
+pub struct BevySharedMemoryStateReader {
+    ring_buffer: OSharedMemoryRingBuffer,
+    source: SharedMemoryStateSource,
+    robot_instance_idx: usize,
+    last_write_count: u64,
+    slot_buffer: Vec<f64>
+}
+impl BevySharedMemoryStateReader {
+    pub fn new<P: AsRef<Path>>(path: P, source: SharedMemoryStateSource, robot_instance_idx: usize) -> Result<Self, OptimaError> {
+        let ring_buffer = OSharedMemoryRingBuffer::open(path)?;
+        if ring_buffer.slot_length() < source.state_offset() { return Err(OptimaError::InvalidInput("ring buffer slots are too short for the given source".to_string())); }
+        let slot_buffer = vec![0.0; ring_buffer.slot_length()];
+        Ok(Self { ring_buffer, source, robot_instance_idx, last_write_count: 0, slot_buffer })
+    }
+}
+
+pub struct SharedMemorySystems;
+impl SharedMemorySystems {
+    pub fn system_shared_memory_state_reader(mut reader: ResMut<BevySharedMemoryStateReader>, mut robot_state_engine: ResMut<RobotStateEngine>) {
+        let reader = &mut *reader;
+        if reader.ring_buffer.write_count() == reader.last_write_count { return; }
+
+        if let Ok(Some(write_count)) = reader.ring_buffer.read_latest_into(&mut reader.slot_buffer) {
+            reader.last_write_count = write_count;
+            let state = reader.slot_buffer[reader.source.state_offset()..].to_vec();
+            robot_state_engine.add_update_request(reader.robot_instance_idx, &state);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContext, EguiContexts};
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use crate::optima_bevy_utils::robotics::{RoboticsActions, RobotStateEngine};
+
+pub const SHORTCUT_TOGGLE_PANELS: &str = "toggle panels";
+pub const SHORTCUT_RESET_STATE: &str = "reset state";
+pub const SHORTCUT_PLAY_PAUSE: &str = "play / pause";
+pub const SHORTCUT_SCREENSHOT: &str = "screenshot";
+pub const SHORTCUT_TOGGLE_ORTHOGRAPHIC: &str = "toggle orthographic";
+pub const SHORTCUT_TOGGLE_DEBUG_PICKING: &str = "toggle debug picking";
+pub const SHORTCUT_UNDO: &str = "undo";
+pub const SHORTCUT_REDO: &str = "redo";
+pub const SHORTCUT_TOGGLE_PERFORMANCE_OVERLAY: &str = "toggle performance overlay";
+
+const MODIFIER_KEYS: [KeyCode; 6] = [KeyCode::ControlLeft, KeyCode::ControlRight, KeyCode::ShiftLeft, KeyCode::ShiftRight, KeyCode::AltLeft, KeyCode::AltRight];
+
+/// A key with modifiers.  Modifiers have to match exactly, so e.g. ctrl+S does not trigger a
+/// shortcut bound to S.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool
+}
+impl KeyChord {
+    pub fn new(key: KeyCode) -> Self {
+        Self { key, ctrl: false, shift: false, alt: false }
+    }
+    pub fn with_ctrl(mut self) -> Self {
+        self.ctrl = true;
+        self
+    }
+    pub fn with_shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+    pub fn with_alt(mut self) -> Self {
+        self.alt = true;
+        self
+    }
+    pub fn just_pressed(&self, keys: &Input<KeyCode>) -> bool {
+        let (ctrl, shift, alt) = modifiers(keys);
+        keys.just_pressed(self.key) && self.ctrl == ctrl && self.shift == shift && self.alt == alt
+    }
+    pub fn to_display_string(&self) -> String {
+        let mut out = String::new();
+        if self.ctrl { out += "ctrl+"; }
+        if self.shift { out += "shift+"; }
+        if self.alt { out += "alt+"; }
+        out + &format!("{:?}", self.key)
+    }
+}
+
+fn modifiers(keys: &Input<KeyCode>) -> (bool, bool, bool) {
+    (keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]), keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]), keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]))
+}
+
+/// Key chords bound to named viewer actions.  Systems ask whether their action was triggered this
+/// frame (`just_triggered`, or the `shortcut_just_triggered` run condition) instead of checking
+/// keys themselves, so every shortcut can be looked up and remapped in one place (see the
+/// "Shortcuts" window).  Nothing is triggered while egui is taking keyboard input.
+#[derive(Resource)]
+pub struct ShortcutMap {
+    bindings: Vec<(String, Option<KeyChord>)>,
+    triggered: Vec<String>,
+    remapping: Option<String>
+}
+impl ShortcutMap {
+    pub fn new() -> Self {
+        Self { bindings: vec![], triggered: vec![], remapping: None }
+    }
+    pub fn new_with_defaults() -> Self {
+        let mut out = Self::new();
+        out.bind(SHORTCUT_TOGGLE_PANELS, KeyChord::new(KeyCode::Tab));
+        out.bind(SHORTCUT_RESET_STATE, KeyChord::new(KeyCode::R).with_ctrl());
+        out.bind(SHORTCUT_PLAY_PAUSE, KeyChord::new(KeyCode::Space));
+        out.bind(SHORTCUT_SCREENSHOT, KeyChord::new(KeyCode::F12));
+        out.bind(SHORTCUT_TOGGLE_ORTHOGRAPHIC, KeyChord::new(KeyCode::O));
+        out.bind(SHORTCUT_TOGGLE_DEBUG_PICKING, KeyChord::new(KeyCode::F3));
+        out.bind(SHORTCUT_UNDO, KeyChord::new(KeyCode::Z).with_ctrl());
+        out.bind(SHORTCUT_REDO, KeyChord::new(KeyCode::Y).with_ctrl());
+        out.bind(SHORTCUT_TOGGLE_PERFORMANCE_OVERLAY, KeyChord::new(KeyCode::F2));
+        out
+    }
+    /// Replaces the action's current chord.  Any other action bound to the same chord is unbound.
+    pub fn bind(&mut self, action: &str, chord: KeyChord) {
+        self.bindings.iter_mut().filter(|(_, x)| *x == Some(chord)).for_each(|(_, x)| *x = None);
+        match self.bindings.iter_mut().find(|(x, _)| x == action) {
+            None => { self.bindings.push((action.to_string(), Some(chord))); }
+            Some((_, x)) => { *x = Some(chord); }
+        }
+    }
+    /// The action stays listed (e.g., in the "Shortcuts" window) so it can be bound again.
+    pub fn unbind(&mut self, action: &str) {
+        if let Some((_, x)) = self.bindings.iter_mut().find(|(x, _)| x == action) { *x = None; }
+    }
+    pub fn chord(&self, action: &str) -> Option<KeyChord> {
+        self.bindings.iter().find(|(x, _)| x == action).and_then(|(_, x)| *x)
+    }
+    pub fn just_triggered(&self, action: &str) -> bool {
+        self.triggered.iter().any(|x| x == action)
+    }
+    #[inline(always)]
+    pub fn bindings(&self) -> &Vec<(String, Option<KeyChord>)> {
+        &self.bindings
+    }
+}
+
+/// Run condition for systems that should only run on the frame an action's shortcut is pressed.
+pub fn shortcut_just_triggered(action: &'static str) -> impl FnMut(Res<ShortcutMap>) -> bool + Clone {
+    move |shortcuts: Res<ShortcutMap>| shortcuts.just_triggered(action)
+}
+
+pub struct ShortcutSystems;
+impl ShortcutSystems {
+    pub fn system_update_shortcuts(mut shortcuts: ResMut<ShortcutMap>, keys: Res<Input<KeyCode>>, mut egui_query: Query<&mut EguiContext, With<PrimaryWindow>>) {
+        let shortcuts = &mut *shortcuts;
+        shortcuts.triggered.clear();
+
+        if let Some(action) = shortcuts.remapping.clone() {
+            if keys.just_pressed(KeyCode::Escape) {
+                shortcuts.remapping = None;
+            } else if let Some(key) = keys.get_just_pressed().find(|x| !MODIFIER_KEYS.contains(x)) {
+                let (ctrl, shift, alt) = modifiers(&keys);
+                shortcuts.bind(&action, KeyChord { key: *key, ctrl, shift, alt });
+                shortcuts.remapping = None;
+            }
+            return;
+        }
+
+        if egui_query.iter_mut().any(|mut x| x.get_mut().wants_keyboard_input()) { return; }
+        for (action, chord) in &shortcuts.bindings {
+            if chord.map(|x| x.just_pressed(&keys)).unwrap_or(false) { shortcuts.triggered.push(action.clone()); }
+        }
+    }
+    pub fn system_toggle_panels_shortcut(shortcuts: Res<ShortcutMap>, egui_engine: Option<Res<OEguiEngineWrapper>>) {
+        if !shortcuts.just_triggered(SHORTCUT_TOGGLE_PANELS) { return; }
+        if let Some(egui_engine) = egui_engine { egui_engine.get_mutex_guard().toggle_hide_all_containers(); }
+    }
+    /// Moves every robot back to all zeros, joint sliders included.
+    pub fn system_reset_state_shortcut(shortcuts: Res<ShortcutMap>, mut robot_state_engine: ResMut<RobotStateEngine>, egui_engine: Option<Res<OEguiEngineWrapper>>) {
+        if !shortcuts.just_triggered(SHORTCUT_RESET_STATE) { return; }
+        for robot_instance_idx in robot_state_engine.robot_instance_idxs() {
+            let Some(state) = robot_state_engine.get_robot_state(robot_instance_idx) else { continue; };
+            let zeros = vec![0.0; state.len()];
+            robot_state_engine.record_edit(robot_instance_idx, "reset");
+            if let Some(egui_engine) = &egui_engine { RoboticsActions::action_set_joint_sliders(&zeros, robot_instance_idx, egui_engine); }
+            robot_state_engine.add_update_request(robot_instance_idx, &zeros);
+        }
+    }
+    /// Undo and redo for edits recorded in the `RobotStateEngine` (see `RobotStateEngine::record_edit`).
+    pub fn system_undo_redo_shortcuts(shortcuts: Res<ShortcutMap>, mut robot_state_engine: ResMut<RobotStateEngine>, egui_engine: Option<Res<OEguiEngineWrapper>>) {
+        if shortcuts.just_triggered(SHORTCUT_UNDO) { RoboticsActions::action_undo_robot_state_edit(&mut robot_state_engine, egui_engine.as_ref()); }
+        if shortcuts.just_triggered(SHORTCUT_REDO) { RoboticsActions::action_redo_robot_state_edit(&mut robot_state_engine, egui_engine.as_ref()); }
+    }
+    pub fn system_shortcuts_panel(mut shortcuts: ResMut<ShortcutMap>,
+                                  mut contexts: EguiContexts,
+                                  egui_engine: Res<OEguiEngineWrapper>,
+                                  window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Shortcuts", true, true, false, false, false, false)
+            .show("shortcuts_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                let shortcuts = &mut *shortcuts;
+                let mut unbind = None;
+                egui::Grid::new("shortcuts_grid").striped(true).show(ui, |ui| {
+                    for (action, chord) in &shortcuts.bindings {
+                        ui.label(action.as_str());
+                        if shortcuts.remapping.as_ref() == Some(action) {
+                            ui.label("press a key (esc to cancel)...");
+                        } else if ui.button(chord.map(|x| x.to_display_string()).unwrap_or("unbound".to_string())).clicked() {
+                            shortcuts.remapping = Some(action.clone());
+                        }
+                        if chord.is_some() && ui.small_button("x").clicked() { unbind = Some(action.clone()); }
+                        ui.end_row();
+                    }
+                });
+                if let Some(action) = unbind { shortcuts.unbind(&action); }
+                ui.label("click a shortcut to remap it.");
+            });
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+use std::io::BufReader;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use optima_error::OptimaError;
+use crate::optima_bevy_utils::robotics::RobotStateEngine;
+
+/// How messages are encoded on a `StateBridge` connection.  Either way, messages are written back
+/// to back on the stream with no extra framing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StateBridgeEncoding {
+    /// e.g., `{"type": "robot_state", "robot_instance_idx": 0, "state": [0.0, 0.1, ...]}`, usually
+    /// one per line.
+    Json,
+    /// The same messages as `Json`, encoded with msgpack (e.g., python's `msgpack.packb`).  Messages
+    /// are maps with named fields.
+    MsgPack
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StateBridgeMessage {
+    RobotState { robot_instance_idx: usize, state: Vec<f64> }
+}
+
+/// Plain tcp counterpart to `WebSocketStateServer` for controllers and robot drivers that stream
+/// joint states out: listens on a background thread, and reads each connection on its own thread
+/// until it closes or sends something that cannot be decoded.
+///
+/// `shutdown` (also called on drop) stops listening and closes every open connection.
+pub struct StateBridge {
+    encoding: StateBridgeEncoding,
+    local_addr: SocketAddr,
+    inbound: Mutex<Receiver<StateBridgeMessage>>,
+    num_connections: Arc<AtomicUsize>,
+    num_messages: Arc<AtomicUsize>,
+    shutting_down: Arc<AtomicBool>,
+    /// clones of the open connections by connection id, so `shutdown` can close them.
+    connections: Arc<Mutex<HashMap<usize, TcpStream>>>,
+    listener_thread: Mutex<Option<JoinHandle<()>>>
+}
+impl StateBridge {
+    pub fn start(addr: &str, encoding: StateBridgeEncoding) -> Result<Self, OptimaError> {
+        let listener = TcpListener::bind(addr).map_err(|e| OptimaError::Generic(format!("could not bind state bridge to {}: {}", addr, e)))?;
+        let local_addr = listener.local_addr().map_err(|e| OptimaError::Generic(format!("could not bind state bridge to {}: {}", addr, e)))?;
+        // polled, so that the listener thread notices a shutdown.
+        listener.set_nonblocking(true).map_err(|e| OptimaError::Generic(format!("could not bind state bridge to {}: {}", addr, e)))?;
+        let (inbound_tx, inbound_rx) = channel();
+        let num_connections = Arc::new(AtomicUsize::new(0));
+        let num_messages = Arc::new(AtomicUsize::new(0));
+        let shutting_down = Arc::new(AtomicBool::new(false));
+        let connections = Arc::new(Mutex::new(HashMap::new()));
+
+        let num_connections_ = num_connections.clone();
+        let num_messages_ = num_messages.clone();
+        let shutting_down_ = shutting_down.clone();
+        let connections_ = connections.clone();
+        let listener_thread = std::thread::spawn(move || {
+            let mut next_connection_id = 0;
+            while !shutting_down_.load(Ordering::SeqCst) {
+                let stream = match listener.accept() {
+                    Ok((stream, _)) => { stream }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => { std::thread::sleep(Duration::from_millis(50)); continue; }
+                    Err(e) => { warn!("state bridge could not accept a connection: {}", e); continue; }
+                };
+                // accepted sockets inherit the listener's non-blocking mode on some platforms.
+                if stream.set_nonblocking(false).is_err() { continue; }
+                let connection_id = next_connection_id;
+                next_connection_id += 1;
+                if let Ok(s) = stream.try_clone() { connections_.lock().unwrap_or_else(|e| e.into_inner()).insert(connection_id, s); }
+
+                let inbound_tx = inbound_tx.clone();
+                let num_connections = num_connections_.clone();
+                let num_messages = num_messages_.clone();
+                let shutting_down = shutting_down_.clone();
+                let connections = connections_.clone();
+                std::thread::spawn(move || {
+                    num_connections.fetch_add(1, Ordering::SeqCst);
+                    if let Err(e) = Self::handle_connection(stream, encoding, &inbound_tx, &num_messages) {
+                        if !shutting_down.load(Ordering::SeqCst) { warn!("state bridge connection closed: {}", e); }
+                    }
+                    connections.lock().unwrap_or_else(|e| e.into_inner()).remove(&connection_id);
+                    num_connections.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        Ok(Self { encoding, local_addr, inbound: Mutex::new(inbound_rx), num_connections, num_messages, shutting_down, connections, listener_thread: Mutex::new(Some(listener_thread)) })
+    }
+    pub fn start_unchecked(addr: &str, encoding: StateBridgeEncoding) -> Self {
+        Self::start(addr, encoding).expect("error")
+    }
+    /// Stops listening, closes all connections, and waits for the listener thread to exit.  Does
+    /// nothing if the bridge was already shut down.
+    pub fn shutdown(&self) {
+        if self.shutting_down.swap(true, Ordering::SeqCst) { return; }
+        for (_, connection) in self.connections.lock().unwrap_or_else(|e| e.into_inner()).drain() { connection.shutdown(Shutdown::Both).ok(); }
+        if let Some(listener_thread) = self.listener_thread.lock().unwrap_or_else(|e| e.into_inner()).take() { listener_thread.join().ok(); }
+    }
+    #[inline(always)]
+    pub fn is_shut_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+    /// The address actually bound, e.g., the port chosen when binding to port 0.
+    #[inline(always)]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+    pub fn try_recv_all(&self) -> Vec<StateBridgeMessage> {
+        let inbound = self.inbound.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = vec![];
+        while let Ok(message) = inbound.try_recv() { out.push(message); }
+        out
+    }
+    #[inline(always)]
+    pub fn encoding(&self) -> StateBridgeEncoding {
+        self.encoding
+    }
+    pub fn num_connections(&self) -> usize {
+        self.num_connections.load(Ordering::SeqCst)
+    }
+    /// Total number of messages received since the bridge started.
+    pub fn num_messages(&self) -> usize {
+        self.num_messages.load(Ordering::SeqCst)
+    }
+    /// Returns once the client disconnects (Ok) or sends a message that cannot be decoded (Err).
+    fn handle_connection(stream: TcpStream, encoding: StateBridgeEncoding, inbound_tx: &Sender<StateBridgeMessage>, num_messages: &AtomicUsize) -> Result<(), String> {
+        stream.set_nodelay(true).ok();
+        let mut reader = BufReader::new(stream);
+
+        match encoding {
+            StateBridgeEncoding::Json => {
+                for message in serde_json::Deserializer::from_reader(reader).into_iter::<StateBridgeMessage>() {
+                    let message = message.map_err(|e| e.to_string())?;
+                    num_messages.fetch_add(1, Ordering::SeqCst);
+                    if inbound_tx.send(message).is_err() { return Ok(()); }
+                }
+                Ok(())
+            }
+            StateBridgeEncoding::MsgPack => {
+                loop {
+                    let message = match rmp_serde::from_read::<_, StateBridgeMessage>(&mut reader) {
+                        Ok(message) => { message }
+                        Err(rmp_serde::decode::Error::InvalidMarkerRead(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => { return Ok(()); }
+                        Err(e) => { return Err(e.to_string()); }
+                    };
+                    num_messages.fetch_add(1, Ordering::SeqCst);
+                    if inbound_tx.send(message).is_err() { return Ok(()); }
+                }
+            }
+        }
+    }
+}
+impl Drop for StateBridge {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[derive(Resource)]
+pub struct BevyStateBridge {
+    pub bridge: StateBridge
+}
+impl BevyStateBridge {
+    pub fn new(addr: &str, encoding: StateBridgeEncoding) -> Result<Self, OptimaError> {
+        Ok(Self { bridge: StateBridge::start(addr, encoding)? })
+    }
+}
+
+pub struct StateBridgeSystems;
+impl StateBridgeSystems {
+    /// Drivers often publish faster than the viewer renders, so only the newest state of each robot
+    /// instance received since the last frame is applied.  States for robot instances that do not
+    /// exist, or with the wrong number of dofs, are skipped with a warning (once per instance and
+    /// length, so a misconfigured driver does not flood the log).
+    pub fn system_state_bridge(state_bridge: Res<BevyStateBridge>,
+                               mut robot_state_engine: ResMut<RobotStateEngine>,
+                               mut warned: Local<HashSet<(usize, usize)>>) {
+        let mut latest = HashMap::new();
+        for message in state_bridge.bridge.try_recv_all() {
+            match message {
+                StateBridgeMessage::RobotState { robot_instance_idx, state } => { latest.insert(robot_instance_idx, state); }
+            }
+        }
+
+        for (robot_instance_idx, state) in latest {
+            let num_dofs = match robot_state_engine.get_robot_state(robot_instance_idx) {
+                Some(current) => { current.len() }
+                None => {
+                    if warned.insert((robot_instance_idx, state.len())) { warn!("state bridge received a state for robot instance {}, which does not exist; it was skipped.", robot_instance_idx); }
+                    continue;
+                }
+            };
+            if state.len() != num_dofs {
+                if warned.insert((robot_instance_idx, state.len())) { warn!("state bridge received a state with {} dofs for robot instance {}, which has {}; it was skipped.", state.len(), robot_instance_idx, num_dofs); }
+                continue;
+            }
+            robot_state_engine.add_update_request(robot_instance_idx, &state);
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use std::path::PathBuf;
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use optima_error::OptimaError;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiFileDialog, OEguiFileDialogMode, OEguiWindow};
+use optima_file::path::{OPath, OSaveFormat};
+use optima_interpolation::KnotTimedInterpolator;
+use optima_interpolation::splines::{InterpolatingSpline, InterpolatingSplineType};
+use optima_linalg::OVec;
+use crate::optima_bevy_utils::robotics::BevyRobotInterpolator;
+
+pub type RecordedTrajectoryInterpolator<T> = KnotTimedInterpolator<T, Vec<T>, InterpolatingSpline<T, Vec<T>>>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrajectorySample {
+    /// seconds from the first sample.
+    pub time: f64,
+    pub state: Vec<f64>
+}
+
+/// A joint trajectory as logged by a controller or planner: joint states with timestamps, played
+/// back by linearly interpolating between samples.
+///
+/// Files can be csv, with one sample per row as `time, dof_0, dof_1, ...` (a header row is
+/// skipped), or ron/json, e.g., `(samples: [(time: 0.0, state: [0.0, 0.1]), ...])`.  Timestamps
+/// only have to be increasing; they are shifted so the first sample is at time 0.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedTrajectory {
+    samples: Vec<TrajectorySample>
+}
+impl RecordedTrajectory {
+    pub fn new(mut samples: Vec<TrajectorySample>) -> Result<Self, OptimaError> {
+        if samples.len() < 2 { return Err(OptimaError::InvalidInput("a trajectory needs at least two samples.".to_string())); }
+        if !samples.windows(2).all(|x| x[0].time < x[1].time) { return Err(OptimaError::InvalidInput("sample times have to be strictly increasing.".to_string())); }
+        let num_dofs = samples[0].state.len();
+        if let Some(idx) = samples.iter().position(|x| x.state.len() != num_dofs) { return Err(OptimaError::InvalidInput(format!("sample {} has {} dofs, but the first sample has {}.", idx, samples[idx].state.len(), num_dofs))); }
+
+        let start_time = samples[0].time;
+        samples.iter_mut().for_each(|x| x.time -= start_time);
+        Ok(Self { samples })
+    }
+    pub fn load_from_path(path: &OPath) -> Result<Self, OptimaError> {
+        let out: Self = if is_csv(path) {
+            Self::from_csv_string(&path.read_file_contents_to_string().map_err(|e| OptimaError::new_file_io(path.to_string(), e))?)?
+        } else {
+            path.load_object_from_file().map_err(|e| OptimaError::new_file_io(path.to_string(), e))?
+        };
+        Self::new(out.samples)
+    }
+    /// Saved as csv, json, or ron depending on the extension, and ron if there is none.
+    pub fn save_to_path(&self, path: &OPath) -> Result<(), OptimaError> {
+        let file_io_error = |e: String| OptimaError::new_file_io(path.to_string(), e);
+        if is_csv(path) { return path.write_string_to_file(&self.to_csv_string()).map_err(file_io_error); }
+        let format = match path.extension().and_then(|x| OSaveFormat::from_extension(&x)) {
+            Some(OSaveFormat::Json) => { OSaveFormat::Json }
+            _ => { OSaveFormat::Ron }
+        };
+        path.save_object_to_file(self, format).map_err(file_io_error)
+    }
+    pub fn from_csv_string(s: &str) -> Result<Self, OptimaError> {
+        let mut samples = vec![];
+        for (line_idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() { continue; }
+            let values: Result<Vec<f64>, _> = line.split(',').map(|x| x.trim().parse::<f64>()).collect();
+            match values {
+                Ok(values) => {
+                    if values.is_empty() { continue; }
+                    samples.push(TrajectorySample { time: values[0], state: values[1..].to_vec() });
+                }
+                Err(e) => {
+                    // only the first row may be a header.
+                    if line_idx == 0 { continue; }
+                    return Err(OptimaError::new_serialization::<Self, _>(format!("could not parse row {} of the csv: {}", line_idx + 1, e)));
+                }
+            }
+        }
+        Self::new(samples)
+    }
+    pub fn to_csv_string(&self) -> String {
+        let num_dofs = self.samples[0].state.len();
+        let mut out = "time".to_string();
+        (0..num_dofs).for_each(|i| out += &format!(",dof_{}", i));
+        out += "\n";
+        for sample in &self.samples {
+            out += &sample.time.to_string();
+            sample.state.iter().for_each(|x| out += &format!(",{}", x));
+            out += "\n";
+        }
+        out
+    }
+    #[inline(always)]
+    pub fn samples(&self) -> &Vec<TrajectorySample> {
+        &self.samples
+    }
+    pub fn duration(&self) -> f64 {
+        self.samples.last().map(|x| x.time).unwrap_or(0.0)
+    }
+    /// State at time `t` (clamped to the trajectory), linearly interpolated between samples.
+    pub fn state_at(&self, t: f64) -> Vec<f64> {
+        let t = t.max(0.0).min(self.duration());
+        let idx = self.samples.windows(2).position(|x| t <= x[1].time).unwrap_or(self.samples.len() - 2);
+        let (a, b) = (&self.samples[idx], &self.samples[idx + 1]);
+        let u = (t - a.time) / (b.time - a.time);
+        a.state.iter().zip(b.state.iter()).map(|(x, y)| x + u * (y - x)).collect()
+    }
+    /// The part of the trajectory between `start` and `end`, with samples added at both ends.
+    pub fn trimmed(&self, start: f64, end: f64) -> Result<Self, OptimaError> {
+        let (start, end) = (start.max(0.0), end.min(self.duration()));
+        if end - start <= 0.0 { return Err(OptimaError::InvalidInput("the end of the trimmed trajectory has to come after its start.".to_string())); }
+
+        let mut samples = vec![TrajectorySample { time: start, state: self.state_at(start) }];
+        self.samples.iter().filter(|x| start < x.time && x.time < end).for_each(|x| samples.push(x.clone()));
+        samples.push(TrajectorySample { time: end, state: self.state_at(end) });
+        Self::new(samples)
+    }
+    /// Stretches (`factor` > 1) or compresses (`factor` < 1) the trajectory in time.
+    pub fn time_scaled(&self, factor: f64) -> Result<Self, OptimaError> {
+        if factor <= 0.0 { return Err(OptimaError::InvalidInput("the time scale has to be positive.".to_string())); }
+        Self::new(self.samples.iter().map(|x| TrajectorySample { time: x.time * factor, state: x.state.clone() }).collect())
+    }
+    /// Interpolator with `t` in seconds.
+    pub fn to_interpolator<T: AD>(&self) -> RecordedTrajectoryInterpolator<T> {
+        let states: Vec<Vec<T>> = self.samples.iter().map(|x| OVec::ovec_to_other_ad_type::<T>(&x.state)).collect();
+        let knot_times = self.samples.iter().map(|x| T::constant(x.time)).collect();
+        // `new` already checked that there are at least two samples with strictly increasing times.
+        KnotTimedInterpolator::new_unchecked(InterpolatingSpline::new(states, InterpolatingSplineType::Linear), knot_times)
+    }
+}
+
+fn is_csv(path: &OPath) -> bool {
+    path.extension().map(|x| x.to_lowercase() == "csv").unwrap_or(false)
+}
+
+/// The trajectory played by `bevy_playback_from_file`, as loaded and as edited through the
+/// "Trajectory" window.
+#[derive(Resource)]
+pub struct BevyTrajectoryFile {
+    source_path: String,
+    /// of the robot the trajectory is played on; trajectories opened later have to match it.
+    num_dofs: usize,
+    original: RecordedTrajectory,
+    edited: RecordedTrajectory,
+    trim_start: f64,
+    trim_end: f64,
+    time_scale: f64,
+    export_path: String,
+    status: String
+}
+impl BevyTrajectoryFile {
+    pub fn new(source_path: &str, trajectory: RecordedTrajectory, num_dofs: usize) -> Self {
+        Self {
+            source_path: source_path.to_string(),
+            num_dofs,
+            trim_start: 0.0,
+            trim_end: trajectory.duration(),
+            time_scale: 1.0,
+            original: trajectory.clone(),
+            edited: trajectory,
+            export_path: "edited_trajectory.csv".to_string(),
+            status: "".to_string(),
+        }
+    }
+    #[inline(always)]
+    pub fn edited(&self) -> &RecordedTrajectory {
+        &self.edited
+    }
+}
+
+enum TrajectoryEdit {
+    Trim,
+    Scale,
+    Reset,
+    Open(RecordedTrajectory)
+}
+
+const TRAJECTORY_FILE_EXTENSIONS: [&str; 3] = ["csv", "ron", "json"];
+
+pub struct TrajectoryFileSystems;
+impl TrajectoryFileSystems {
+    /// Edits are applied to the trajectory being played back right away, and the playback slider is
+    /// moved back to the start.  Another trajectory file can be opened in place of the current one.
+    pub fn system_trajectory_file_panel<T: AD>(mut trajectory_file: ResMut<BevyTrajectoryFile>,
+                                               mut interpolator: ResMut<BevyRobotInterpolator<T, Vec<T>, RecordedTrajectoryInterpolator<T>>>,
+                                               mut contexts: EguiContexts,
+                                               egui_engine: Res<OEguiEngineWrapper>,
+                                               window_query: Query<&Window, With<PrimaryWindow>>) {
+        let playback_time = egui_engine.get_mutex_guard().get_slider_response("playback_slider").map(|x| x.slider_value).unwrap_or(0.0);
+        let mut edit = None;
+        let mut export = false;
+
+        OEguiWindow::new("Trajectory", true, true, false, false, false, true)
+            .show("trajectory_file_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                let f = &mut *trajectory_file;
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}: {} samples, {:.2} s", f.source_path, f.edited.samples.len(), f.edited.duration()));
+                    if ui.button("Open...").clicked() { OEguiFileDialog::open("trajectory_open_dialog", &egui_engine); }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("trim");
+                    ui.add(egui::DragValue::new(&mut f.trim_start).speed(0.01).clamp_range(0.0..=f.edited.duration()).suffix(" s"));
+                    if ui.button("⬅ here").clicked() { f.trim_start = playback_time; }
+                    ui.label("to");
+                    ui.add(egui::DragValue::new(&mut f.trim_end).speed(0.01).clamp_range(0.0..=f.edited.duration()).suffix(" s"));
+                    if ui.button("here ➡").clicked() { f.trim_end = playback_time; }
+                    if ui.button("apply").clicked() { edit = Some(TrajectoryEdit::Trim); }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("time scale");
+                    ui.add(egui::DragValue::new(&mut f.time_scale).speed(0.01).clamp_range(0.05..=20.0));
+                    if ui.button("apply").clicked() { edit = Some(TrajectoryEdit::Scale); }
+                });
+                if ui.button("reset to file").clicked() { edit = Some(TrajectoryEdit::Reset); }
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("export to");
+                    ui.text_edit_singleline(&mut f.export_path);
+                    if ui.button("...").clicked() { OEguiFileDialog::open("trajectory_export_dialog", &egui_engine); }
+                    if ui.button("Export").clicked() { export = true; }
+                });
+                if !f.status.is_empty() { ui.label(f.status.as_str()); }
+            });
+
+        OEguiFileDialog::new("Open Trajectory", OEguiFileDialogMode::Open)
+            .with_extensions(&TRAJECTORY_FILE_EXTENSIONS)
+            .show("trajectory_open_dialog", contexts.ctx_mut(), &egui_engine);
+        OEguiFileDialog::new("Export Trajectory", OEguiFileDialogMode::Save)
+            .with_extensions(&TRAJECTORY_FILE_EXTENSIONS)
+            .with_default_filename(&trajectory_file.export_path)
+            .show("trajectory_export_dialog", contexts.ctx_mut(), &egui_engine);
+
+        let f = &mut *trajectory_file;
+        let opened = egui_engine.get_mutex_guard().get_file_dialog_response("trajectory_open_dialog").and_then(|x| x.picked().cloned());
+        if let Some(path) = opened {
+            match RecordedTrajectory::load_from_path(&path) {
+                Ok(trajectory) if trajectory.samples[0].state.len() != f.num_dofs => {
+                    let message = format!("could not open {}: the trajectory has {} dofs, but the robot has {}.", path.to_string(), trajectory.samples[0].state.len(), f.num_dofs);
+                    egui_engine.get_mutex_guard().push_error(&message);
+                    f.status = message;
+                }
+                Ok(trajectory) => {
+                    f.source_path = path.to_string();
+                    edit = Some(TrajectoryEdit::Open(trajectory));
+                }
+                Err(e) => {
+                    egui_engine.get_mutex_guard().push_error(&format!("could not open {}.", path.to_string()));
+                    f.status = format!("could not open {}: {}", path.to_string(), e);
+                }
+            }
+        }
+        let export_path = egui_engine.get_mutex_guard().get_file_dialog_response("trajectory_export_dialog").and_then(|x| x.picked().cloned());
+        if let Some(path) = export_path {
+            f.export_path = path.to_string();
+            export = true;
+        }
+
+        if let Some(edit) = edit {
+            let edited = match edit {
+                TrajectoryEdit::Trim => { f.edited.trimmed(f.trim_start, f.trim_end) }
+                TrajectoryEdit::Scale => { f.edited.time_scaled(f.time_scale) }
+                TrajectoryEdit::Reset => { Ok(f.original.clone()) }
+                TrajectoryEdit::Open(trajectory) => {
+                    f.original = trajectory.clone();
+                    Ok(trajectory)
+                }
+            };
+            match edited {
+                Ok(edited) => {
+                    interpolator.0 = edited.to_interpolator::<T>();
+                    f.trim_start = 0.0;
+                    f.trim_end = edited.duration();
+                    f.time_scale = 1.0;
+                    f.edited = edited;
+                    f.status = "".to_string();
+                    if let Some(response) = egui_engine.get_mutex_guard().get_slider_response_mut("playback_slider") { response.slider_value = 0.0; }
+                }
+                Err(e) => { f.status = e.to_string(); }
+            }
+        }
+
+        if export {
+            f.status = match f.edited.save_to_path(&OPath::Path(PathBuf::from(f.export_path.clone()))) {
+                Ok(()) => {
+                    egui_engine.get_mutex_guard().push_info(&format!("exported trajectory to {}.", f.export_path));
+                    format!("exported to {}.", f.export_path)
+                }
+                Err(e) => {
+                    egui_engine.get_mutex_guard().push_error(&format!("could not export trajectory to {}.", f.export_path));
+                    format!("could not export: {}", e)
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trajectory(samples: &[(f64, f64)]) -> RecordedTrajectory {
+        RecordedTrajectory::new(samples.iter().map(|(time, x)| TrajectorySample { time: *time, state: vec![*x] }).collect()).expect("error")
+    }
+
+    #[test]
+    fn new_rejects_invalid_samples() {
+        assert!(RecordedTrajectory::new(vec![TrajectorySample { time: 0.0, state: vec![0.0] }]).is_err());
+        assert!(RecordedTrajectory::new(vec![TrajectorySample { time: 1.0, state: vec![0.0] }, TrajectorySample { time: 1.0, state: vec![1.0] }]).is_err());
+        assert!(RecordedTrajectory::new(vec![TrajectorySample { time: 0.0, state: vec![0.0] }, TrajectorySample { time: 1.0, state: vec![1.0, 2.0] }]).is_err());
+    }
+
+    #[test]
+    fn new_shifts_times_to_start_at_zero() {
+        let t = trajectory(&[(2.0, 0.0), (3.0, 1.0), (5.0, 2.0)]);
+        assert_eq!(t.samples().iter().map(|x| x.time).collect::<Vec<f64>>(), vec![0.0, 1.0, 3.0]);
+        assert_eq!(t.duration(), 3.0);
+    }
+
+    #[test]
+    fn csv_with_header_parses() {
+        let t = RecordedTrajectory::from_csv_string("time,dof_0,dof_1\n0.5, 1.0, 2.0\n\n1.5, 3.0, 4.0\n").expect("error");
+        assert_eq!(t.samples().len(), 2);
+        assert_eq!(t.samples()[0].time, 0.0);
+        assert_eq!(t.samples()[1].time, 1.0);
+        assert_eq!(t.samples()[1].state, vec![3.0, 4.0]);
+    }
+
+    #[test]
+    fn csv_rejects_unparsable_rows_after_the_first() {
+        assert!(RecordedTrajectory::from_csv_string("0.0,1.0\nnot,a,number\n1.0,2.0\n").is_err());
+    }
+
+    #[test]
+    fn csv_round_trips() {
+        let t = trajectory(&[(0.0, 0.25), (0.5, -1.0), (2.0, 3.5)]);
+        let u = RecordedTrajectory::from_csv_string(&t.to_csv_string()).expect("error");
+        assert_eq!(u.samples().iter().map(|x| (x.time, x.state.clone())).collect::<Vec<_>>(), t.samples().iter().map(|x| (x.time, x.state.clone())).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn state_at_interpolates_and_clamps() {
+        let t = trajectory(&[(0.0, 0.0), (1.0, 2.0), (3.0, 0.0)]);
+        assert_eq!(t.state_at(0.5), vec![1.0]);
+        assert_eq!(t.state_at(1.0), vec![2.0]);
+        assert_eq!(t.state_at(2.0), vec![1.0]);
+        assert_eq!(t.state_at(-1.0), vec![0.0]);
+        assert_eq!(t.state_at(10.0), vec![0.0]);
+    }
+
+    #[test]
+    fn trimmed_adds_samples_at_both_ends() {
+        let t = trajectory(&[(0.0, 0.0), (1.0, 2.0), (3.0, 0.0)]).trimmed(0.5, 2.0).expect("error");
+        assert_eq!(t.samples().len(), 3);
+        assert_eq!(t.duration(), 1.5);
+        assert_eq!(t.state_at(0.0), vec![1.0]);
+        assert_eq!(t.state_at(0.5), vec![2.0]);
+        assert_eq!(t.state_at(1.5), vec![1.0]);
+    }
+
+    #[test]
+    fn trimmed_rejects_empty_ranges() {
+        let t = trajectory(&[(0.0, 0.0), (1.0, 2.0)]);
+        assert!(t.trimmed(0.5, 0.5).is_err());
+        assert!(t.trimmed(2.0, 3.0).is_err());
+    }
+
+    #[test]
+    fn time_scaled_stretches_time() {
+        let t = trajectory(&[(0.0, 0.0), (1.0, 2.0)]);
+        let scaled = t.time_scaled(2.0).expect("error");
+        assert_eq!(scaled.duration(), 2.0);
+        assert_eq!(scaled.state_at(1.0), vec![1.0]);
+        assert!(t.time_scaled(0.0).is_err());
+        assert!(t.time_scaled(-1.0).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use ad_trait::AD;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_bevy_egui::{OEguiColorPicker, OEguiContainerTrait, OEguiEngineWrapper, OEguiWidgetTrait, OEguiWindow};
+use optima_linalg::OLinalgCategory;
+use crate::BevySystemSet;
+use crate::optima_bevy_utils::robotics::{BevyORobot, RobotStateEngine};
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+/// Records the world-space paths of the given links as the robot's state changes, and draws them
+/// as polylines that fade out towards their oldest point.  Trail length and color can be changed,
+/// and the trails cleared, from the "Trajectory Trail" window.
+pub struct TrajectoryTrailPlugin<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> {
+    link_idxs: Vec<usize>,
+    robot_instance_idx: usize,
+    _phantom_data: PhantomData<(T, C, L)>
+}
+impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> TrajectoryTrailPlugin<T, C, L> {
+    pub fn new(link_idxs: Vec<usize>, robot_instance_idx: usize) -> Self {
+        Self { link_idxs, robot_instance_idx, _phantom_data: PhantomData::default() }
+    }
+}
+impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> Plugin for TrajectoryTrailPlugin<T, C, L> {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(BevyTrajectoryTrails::new(self.link_idxs.clone(), self.robot_instance_idx))
+            .add_systems(Update, TrajectoryTrailSystems::system_record_trajectory_trails::<T, C, L>)
+            .add_systems(Update, TrajectoryTrailSystems::system_draw_trajectory_trails)
+            .add_systems(Update, TrajectoryTrailSystems::system_trajectory_trail_panel.before(BevySystemSet::Camera));
+    }
+}
+
+#[derive(Resource)]
+pub struct BevyTrajectoryTrails {
+    link_idxs: Vec<usize>,
+    robot_instance_idx: usize,
+    /// One trail per entry in `link_idxs`, in bevy (y up) space.
+    trails: Vec<VecDeque<Vec3>>,
+    last_state: Option<Vec<f64>>,
+    pub max_num_points: usize,
+    pub color: [f32; 4]
+}
+impl BevyTrajectoryTrails {
+    pub fn new(link_idxs: Vec<usize>, robot_instance_idx: usize) -> Self {
+        let trails = vec![VecDeque::new(); link_idxs.len()];
+        Self { link_idxs, robot_instance_idx, trails, last_state: None, max_num_points: 500, color: [1.0, 0.6, 0.0, 1.0] }
+    }
+    pub fn clear(&mut self) {
+        self.trails.iter_mut().for_each(|x| x.clear());
+    }
+    #[inline(always)]
+    pub fn link_idxs(&self) -> &Vec<usize> {
+        &self.link_idxs
+    }
+    #[inline(always)]
+    pub fn trails(&self) -> &Vec<VecDeque<Vec3>> {
+        &self.trails
+    }
+    fn truncate(&mut self) {
+        let max_num_points = self.max_num_points;
+        self.trails.iter_mut().for_each(|x| { while x.len() > max_num_points { x.pop_front(); } });
+    }
+}
+
+pub struct TrajectoryTrailSystems;
+impl TrajectoryTrailSystems {
+    pub fn system_record_trajectory_trails<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(robot: Res<BevyORobot<T, C, L>>,
+                                                                                                               robot_state_engine: Res<RobotStateEngine>,
+                                                                                                               mut trails: ResMut<BevyTrajectoryTrails>) {
+        let Some(state) = robot_state_engine.get_robot_state(trails.robot_instance_idx) else { return; };
+        if trails.last_state.as_ref() == Some(state) { return; }
+        trails.last_state = Some(state.clone());
+
+        let state: Vec<T> = state.iter().map(|x| T::constant(*x)).collect();
+        let fk_res = robot.0.forward_kinematics(&state, None);
+        let points: Vec<Option<Vec3>> = trails.link_idxs.iter().map(|link_idx| {
+            fk_res.get_link_pose(*link_idx).ok().map(|pose| TransformUtils::util_convert_z_up_ovec3_to_y_up_bevy_vec3(pose.translation()))
+        }).collect();
+
+        trails.trails.iter_mut().zip(points).for_each(|(trail, point)| { if let Some(point) = point { trail.push_back(point); } });
+        trails.truncate();
+    }
+    pub fn system_draw_trajectory_trails(trails: Res<BevyTrajectoryTrails>, mut gizmos: Gizmos) {
+        let c = trails.color;
+        trails.trails.iter().for_each(|trail| {
+            let num_segments = trail.len().saturating_sub(1);
+            for (i, (start, end)) in trail.iter().zip(trail.iter().skip(1)).enumerate() {
+                let alpha = c[3] * (i + 1) as f32 / num_segments as f32;
+                gizmos.line(*start, *end, Color::rgba(c[0], c[1], c[2], alpha));
+            }
+        });
+    }
+    pub fn system_trajectory_trail_panel(mut trails: ResMut<BevyTrajectoryTrails>,
+                                         mut contexts: EguiContexts,
+                                         egui_engine: Res<OEguiEngineWrapper>,
+                                         window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Trajectory Trail", true, true, false, false, false, true)
+            .show("trajectory_trail_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                ui.label(format!("links: {:?}", trails.link_idxs));
+                ui.horizontal(|ui| {
+                    ui.label("length: ");
+                    if ui.add(egui::Slider::new(&mut trails.max_num_points, 2..=5000).logarithmic(true)).changed() { trails.truncate(); }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("color: ");
+                    OEguiColorPicker::new(trails.color, true).show("trajectory_trail_color", ui, &egui_engine, &());
+                });
+                // the picker's value is saved with the rest of the egui state, so it takes precedence.
+                if let Some(response) = egui_engine.get_mutex_guard().get_color_picker_response("trajectory_trail_color") { trails.color = response.rgba(); }
+                if ui.button("Clear").clicked() { trails.clear(); }
+            });
+    }
+}
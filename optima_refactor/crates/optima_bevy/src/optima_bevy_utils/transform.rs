@@ -2,7 +2,7 @@ use ad_trait::AD;
 use bevy::math::Quat;
 use bevy::prelude::{Transform, Vec3};
 use optima_3d_spatial::optima_3d_pose::O3DPose;
-use optima_3d_spatial::optima_3d_rotation::O3DRotation;
+use optima_3d_spatial::optima_3d_rotation::{O3DRotation, QuatConstructor};
 use optima_3d_spatial::optima_3d_vec::O3DVec;
 use optima_linalg::OVec;
 
@@ -21,6 +21,15 @@ impl TransformUtils {
         }
     }
 
+    /// Inverse of `util_convert_3d_pose_to_y_up_bevy_transform`.  Scale is ignored.
+    #[inline(always)]
+    pub fn util_convert_y_up_bevy_transform_to_3d_pose<T: AD, P: O3DPose<T>>(transform: &Transform) -> P {
+        let t = transform.translation;
+        let r = transform.rotation;
+        let pose = P::from_constructors(&[T::constant(t.x as f64), T::constant(t.y as f64), T::constant(t.z as f64)], &QuatConstructor::new(T::constant(r.w as f64), T::constant(r.x as f64), T::constant(r.y as f64), T::constant(r.z as f64)));
+        P::from_constructors(&[T::zero(),T::zero(),T::zero()], &[T::constant(std::f64::consts::FRAC_PI_2), T::zero(), T::zero()]).mul(&pose)
+    }
+
     #[inline(always)]
     pub fn util_convert_z_up_vec3_to_y_up_bevy_vec3(vec: Vec3) -> Vec3 {
         return Vec3::new(vec.x, vec.z, -vec.y);
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+use nalgebra::Isometry3;
+use optima_3d_spatial::optima_3d_pose::O3DPose;
+use optima_3d_spatial::optima_3d_rotation::O3DRotation;
+use optima_3d_spatial::optima_3d_transform_tree::OTransformTree;
+use optima_bevy_egui::{OEguiContainerTrait, OEguiEngineWrapper, OEguiWindow};
+use crate::optima_bevy_utils::transform::TransformUtils;
+
+/// An `OTransformTree` of frames that are not robot links (cameras, fixtures, targets, ...), drawn
+/// as coordinate axes in the viewport and browsable in the "Transform Tree" window, which can also
+/// look up the transform between any two frames.  Poses are in optima's (z up) world space.
+#[derive(Resource)]
+pub struct BevyTransformTree {
+    pub tree: OTransformTree<f64, Isometry3<f64>>,
+    pub show_axes: bool,
+    pub show_names: bool,
+    pub axis_length: f32,
+    lookup_from: String,
+    lookup_to: String
+}
+impl BevyTransformTree {
+    pub fn new(tree: OTransformTree<f64, Isometry3<f64>>) -> Self {
+        Self { tree, show_axes: true, show_names: true, axis_length: 0.1, lookup_from: "".to_string(), lookup_to: "".to_string() }
+    }
+}
+
+pub struct TransformTreeSystems;
+impl TransformTreeSystems {
+    pub fn system_draw_transform_tree(transform_tree: Res<BevyTransformTree>, mut gizmos: Gizmos) {
+        if !transform_tree.show_axes { return; }
+        let l = transform_tree.axis_length as f64;
+        for name in transform_tree.tree.frame_names() {
+            let Ok(pose) = transform_tree.tree.world_transform(name, None) else { continue; };
+            let origin = TransformUtils::util_convert_z_up_ovec3_to_y_up_bevy_vec3(pose.translation());
+            let axes = [([l, 0.0, 0.0], Color::RED), ([0.0, l, 0.0], Color::GREEN), ([0.0, 0.0, l], Color::BLUE)];
+            for (axis, color) in axes {
+                let end = pose.mul_by_point_generic(&axis);
+                gizmos.line(origin, TransformUtils::util_convert_z_up_ovec3_to_y_up_bevy_vec3(&end), color);
+            }
+        }
+    }
+    /// Frame names are painted on egui's foreground layer, as in `FrameLabelSystems::system_draw_frame_labels`.
+    pub fn system_draw_transform_tree_names(transform_tree: Res<BevyTransformTree>,
+                                            mut contexts: EguiContexts,
+                                            camera_query: Query<(&Camera, &GlobalTransform)>,
+                                            window_query: Query<&Window, With<PrimaryWindow>>) {
+        if !transform_tree.show_names || window_query.get_single().is_err() { return; }
+        let Some((camera, camera_transform)) = camera_query.iter().find(|(camera, _)| camera.is_active && matches!(camera.target, RenderTarget::Window(_))) else { return; };
+
+        let painter = contexts.ctx_mut().layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("transform_tree_names")));
+        let font = egui::FontId::proportional(12.0);
+        for name in transform_tree.tree.frame_names() {
+            let Ok(pose) = transform_tree.tree.world_transform(name, None) else { continue; };
+            let point = TransformUtils::util_convert_z_up_ovec3_to_y_up_bevy_vec3(pose.translation());
+            let Some(pos) = camera.world_to_viewport(camera_transform, point) else { continue; };
+            painter.text(egui::pos2(pos.x, pos.y), egui::Align2::LEFT_BOTTOM, name, font.clone(), egui::Color32::from_rgb(200, 220, 255));
+        }
+    }
+    pub fn system_transform_tree_panel(mut transform_tree: ResMut<BevyTransformTree>,
+                                       mut contexts: EguiContexts,
+                                       egui_engine: Res<OEguiEngineWrapper>,
+                                       window_query: Query<&Window, With<PrimaryWindow>>) {
+        OEguiWindow::new("Transform Tree", true, true, false, false, false, true)
+            .show("transform_tree_window", contexts.ctx_mut(), &egui_engine, &window_query, &(), |ui| {
+                let transform_tree = &mut *transform_tree;
+                ui.checkbox(&mut transform_tree.show_axes, "axes");
+                ui.checkbox(&mut transform_tree.show_names, "names");
+                ui.add(egui::Slider::new(&mut transform_tree.axis_length, 0.01..=1.0).text("axis length"));
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    let roots = transform_tree.tree.children(None).unwrap_or_default();
+                    for root in roots { frame_tree_ui(ui, &transform_tree.tree, root); }
+                });
+                ui.separator();
+
+                ui.label("lookup");
+                let names: Vec<String> = transform_tree.tree.frame_names().iter().map(|x| x.to_string()).collect();
+                frame_combo_box(ui, "transform_tree_lookup_from", "from", &mut transform_tree.lookup_from, &names);
+                frame_combo_box(ui, "transform_tree_lookup_to", "to", &mut transform_tree.lookup_to, &names);
+                match transform_tree.tree.lookup(&transform_tree.lookup_from, &transform_tree.lookup_to, None) {
+                    Ok(pose) => { ui.monospace(pose_string(&pose)); }
+                    Err(_) => { ui.label("pick two frames."); }
+                }
+            });
+    }
+}
+
+fn frame_tree_ui(ui: &mut egui::Ui, tree: &OTransformTree<f64, Isometry3<f64>>, name: &str) {
+    egui::CollapsingHeader::new(name).id_source(format!("transform_tree_frame_{}", name)).default_open(true).show(ui, |ui| {
+        if let Ok(pose) = tree.local_transform(name, None) { ui.monospace(pose_string(&pose)); }
+        for child in tree.children(Some(name)).unwrap_or_default() { frame_tree_ui(ui, tree, child); }
+    });
+}
+
+fn frame_combo_box(ui: &mut egui::Ui, id: &str, label: &str, selected: &mut String, names: &Vec<String>) {
+    egui::ComboBox::from_id_source(id).selected_text(format!("{}: {}", label, selected)).show_ui(ui, |ui| {
+        for name in names { ui.selectable_value(selected, name.clone(), name.as_str()); }
+    });
+}
+
+fn pose_string(pose: &Isometry3<f64>) -> String {
+    let t = pose.translation();
+    let rpy = pose.rotation().euler_angles();
+    format!("xyz: [{:.3}, {:.3}, {:.3}]\nrpy: [{:.3}, {:.3}, {:.3}]", t[0], t[1], t[2], rpy[0], rpy[1], rpy[2])
+}
@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use optima_bevy_egui::OEguiTheme;
+use optima_file::path::OPath;
+
+/// Environment variable pointing to a viewer config file.
+pub const OPTIMA_VIEWER_CONFIG_ENV_VAR: &str = "OPTIMA_VIEWER_CONFIG";
+
+/// Viewer settings that can be shared as a file, so a team can standardize its viewer setup without
+/// code changes.  Every field is optional in the file; anything left out keeps its default.
+///
+/// `optima_bevy_base` looks for the file in order at `$OPTIMA_VIEWER_CONFIG`, then
+/// `./optima_viewer_config.ron`, then `./optima_viewer_config.toml`.  For example (ron):
+///```text
+/// (
+///     window: (width: 1600, height: 900),
+///     theme: Mocha,
+///     camera: (location: (3.0, 1.0, 1.5)),
+///     grid: (enabled: false),
+///     panels: (log: true),
+///     default_robot: Some("sawyer"),
+/// )
+///```
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimaViewerConfig {
+    pub window: OptimaViewerWindowConfig,
+    pub theme: OEguiTheme,
+    pub background_color: [f32; 3],
+    pub camera: OptimaViewerCameraConfig,
+    pub grid: OptimaViewerGridConfig,
+    pub panels: OptimaViewerPanelsConfig,
+    /// Robot shown by viewers that are not given one (e.g., the web viewer without a `robot` url
+    /// parameter).
+    pub default_robot: Option<String>
+}
+impl OptimaViewerConfig {
+    pub fn load_from_path(path: &OPath) -> Result<Self, String> {
+        path.load_object_from_file()
+    }
+    /// Returns the first config file found (see the struct docs), or None if there is none.  A file
+    /// that exists but cannot be parsed is an error.
+    pub fn load() -> Result<Option<Self>, String> {
+        let mut candidates = vec![];
+        if let Ok(p) = std::env::var(OPTIMA_VIEWER_CONFIG_ENV_VAR) { candidates.push(PathBuf::from(p)); }
+        if let Ok(cwd) = std::env::current_dir() {
+            candidates.push(cwd.join("optima_viewer_config.ron"));
+            candidates.push(cwd.join("optima_viewer_config.toml"));
+        }
+
+        for candidate in candidates {
+            if !candidate.is_file() { continue; }
+            return Self::load_from_path(&OPath::Path(candidate.clone())).map(Some).map_err(|e| format!("could not load viewer config {:?}: {}", candidate, e));
+        }
+        Ok(None)
+    }
+    /// Same as `load`, but falls back on the default config (with a warning if a file could not be
+    /// parsed).
+    pub fn load_or_default() -> Self {
+        match Self::load() {
+            Ok(config) => { config.unwrap_or_default() }
+            Err(e) => {
+                warn!("{}; using the default viewer config.", e);
+                Self::default()
+            }
+        }
+    }
+}
+impl Default for OptimaViewerConfig {
+    fn default() -> Self {
+        Self {
+            window: OptimaViewerWindowConfig::default(),
+            theme: OEguiTheme::default(),
+            background_color: [0.5, 0.5, 0.5],
+            camera: OptimaViewerCameraConfig::default(),
+            grid: OptimaViewerGridConfig::default(),
+            panels: OptimaViewerPanelsConfig::default(),
+            default_robot: None
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimaViewerWindowConfig {
+    pub title: String,
+    pub width: f32,
+    pub height: f32
+}
+impl Default for OptimaViewerWindowConfig {
+    fn default() -> Self {
+        Self { title: "OPTIMA".to_string(), width: 1280.0, height: 720.0 }
+    }
+}
+
+/// Positions are in optima's z-up frame.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimaViewerCameraConfig {
+    pub location: [f32; 3],
+    pub focus: [f32; 3]
+}
+impl Default for OptimaViewerCameraConfig {
+    fn default() -> Self {
+        Self { location: [5.0, 0.8, 1.5], focus: [0.0; 3] }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimaViewerGridConfig {
+    pub enabled: bool,
+    /// Number of grid lines on each side of each axis.
+    pub half_num_lines: usize,
+    /// Distance between grid lines in meters.
+    pub spacing: f32,
+    pub color: [f32; 4]
+}
+impl Default for OptimaViewerGridConfig {
+    fn default() -> Self {
+        Self { enabled: true, half_num_lines: 10, spacing: 1.0, color: [0.6, 0.6, 0.6, 1.0] }
+    }
+}
+
+/// Which panels the built-in viewers (e.g., `bevy_display`) show.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimaViewerPanelsConfig {
+    pub robot_info: bool,
+    pub log: bool,
+    /// The "Geometry" window for showing collision shapes (needs a preprocessed robot).
+    pub collision_geometry: bool,
+    /// The "Keyframes" window for posing and previewing keyframed motions.
+    pub keyframes: bool,
+    /// Link name labels (toggled per link in the link panel) and the "Labels" window.
+    pub labels: bool,
+    /// Colors links by how close their joints are to their limits, with the "Joint Limits" window.
+    /// Also applies to motion playback.
+    pub joint_limits: bool
+}
+impl Default for OptimaViewerPanelsConfig {
+    fn default() -> Self {
+        Self { robot_info: true, log: false, collision_geometry: false, keyframes: false, labels: false, joint_limits: false }
+    }
+}
@@ -2,12 +2,13 @@ use ad_trait::AD;
 use bevy::asset::{Assets};
 use bevy::math::{Mat3, Quat, Vec3};
 use bevy::pbr::{AlphaMode, PbrBundle};
-use bevy::prelude::{Color, Commands, default, Entity, Gizmos, Mesh, ResMut, shape, StandardMaterial, Transform};
+use bevy::prelude::{Color, Commands, default, Entity, Gizmos, Mesh, Res, ResMut, shape, StandardMaterial, Transform};
 use bevy_prototype_debug_lines::DebugLines;
 use nalgebra::DVector;
 use optima_3d_spatial::optima_3d_pose::O3DPose;
 use optima_geometry::get_points_around_circle;
 use crate::optima_bevy_utils::transform::TransformUtils;
+use crate::optima_bevy_utils::viewer_config::{OptimaViewerConfig, OptimaViewerGridConfig};
 
 pub struct ViewportVisualsActions;
 impl ViewportVisualsActions {
@@ -75,22 +76,32 @@ impl ViewportVisualsActions {
     pub fn action_draw_robotics_grid(commands: &mut Commands,
                                      meshes: &mut ResMut<Assets<Mesh>>,
                                      materials: &mut ResMut<Assets<StandardMaterial>>) {
+        Self::action_draw_robotics_grid_with_config(commands, meshes, materials, &OptimaViewerGridConfig::default());
+    }
+    pub fn action_draw_robotics_grid_with_config(commands: &mut Commands,
+                                                 meshes: &mut ResMut<Assets<Mesh>>,
+                                                 materials: &mut ResMut<Assets<StandardMaterial>>,
+                                                 grid_config: &OptimaViewerGridConfig) {
+        if !grid_config.enabled { return; }
+
         let x_and_y_width = 5.0;
         let normal_width = 2.0;
-        let normal_color = Color::rgba(0.6,0.6,0.6,1.);
+        let normal_color = Color::rgba(grid_config.color[0], grid_config.color[1], grid_config.color[2], grid_config.color[3]);
+        let extent = grid_config.half_num_lines as f32 * grid_config.spacing;
 
-        Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(0., 0., 0.), Vec3::new(10., 0., 0.), Color::rgba(1.,0.,0.,1.), x_and_y_width, true);
-        Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(0., 0., 0.), Vec3::new(-10., 0., 0.), normal_color, normal_width, true);
+        Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(0., 0., 0.), Vec3::new(extent, 0., 0.), Color::rgba(1.,0.,0.,1.), x_and_y_width, true);
+        Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(0., 0., 0.), Vec3::new(-extent, 0., 0.), normal_color, normal_width, true);
 
-        Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(0., 0., 0.), Vec3::new(0., 10., 0.), Color::rgba(0.,1.,0.,1.), x_and_y_width, true);
-        Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(0., 0., 0.), Vec3::new(0., -10., 0.), normal_color.clone(), normal_width, true);
+        Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(0., 0., 0.), Vec3::new(0., extent, 0.), Color::rgba(0.,1.,0.,1.), x_and_y_width, true);
+        Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(0., 0., 0.), Vec3::new(0., -extent, 0.), normal_color.clone(), normal_width, true);
 
-        for i in 0..10 {
-            Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(i as f32, -10.0, 0.), Vec3::new(i as f32, 10.0, 0.), normal_color.clone(), normal_width, true);
-            Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(-i as f32, -10.0, 0.), Vec3::new(-i as f32, 10.0, 0.), normal_color.clone(), normal_width, true);
+        for i in 0..grid_config.half_num_lines {
+            let d = i as f32 * grid_config.spacing;
+            Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(d, -extent, 0.), Vec3::new(d, extent, 0.), normal_color.clone(), normal_width, true);
+            Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(-d, -extent, 0.), Vec3::new(-d, extent, 0.), normal_color.clone(), normal_width, true);
 
-            Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(-10.0, i as f32, 0.), Vec3::new( 10.0, i as f32,0.), normal_color.clone(), normal_width, true);
-            Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new( -10.0, -i as f32,0.), Vec3::new(10.0, -i as f32, 0.), normal_color.clone(), normal_width, true);
+            Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new(-extent, d, 0.), Vec3::new( extent, d,0.), normal_color.clone(), normal_width, true);
+            Self::action_spawn_line_optima_space(commands, meshes, materials, Vec3::new( -extent, -d,0.), Vec3::new(extent, -d, 0.), normal_color.clone(), normal_width, true);
         }
     }
     pub fn action_draw_gpu_line_optima_space_gizmo(gizmos: &mut Gizmos,
@@ -254,8 +265,10 @@ pub struct ViewportVisualsSystems;
 impl ViewportVisualsSystems {
     pub fn system_draw_robotics_grid(mut commands: Commands,
                                      mut meshes: ResMut<Assets<Mesh>>,
-                                     mut materials: ResMut<Assets<StandardMaterial>>) {
-        ViewportVisualsActions::action_draw_robotics_grid(&mut commands, &mut meshes, &mut materials);
+                                     mut materials: ResMut<Assets<StandardMaterial>>,
+                                     viewer_config: Option<Res<OptimaViewerConfig>>) {
+        let grid_config = viewer_config.map(|x| x.grid.clone()).unwrap_or_default();
+        ViewportVisualsActions::action_draw_robotics_grid_with_config(&mut commands, &mut meshes, &mut materials, &grid_config);
     }
 }
 
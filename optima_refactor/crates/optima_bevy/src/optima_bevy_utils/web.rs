@@ -0,0 +1,88 @@
+use optima_error::OptimaError;
+#[cfg(target_arch = "wasm32")]
+use optima_file::path::OAssetLocation;
+#[cfg(target_arch = "wasm32")]
+use optima_file::virtual_assets::OVirtualAssets;
+
+/// Settings that only take effect when targeting wasm32.  The visualization is drawn into the html
+/// canvas matched by `canvas_selector`, and assets (e.g., robot meshes) are fetched over http
+/// relative to `asset_root`, which should be the url of a served copy of the optima asset folder.
+#[derive(Clone, Debug)]
+pub struct OptimaBevyWebConfig {
+    pub canvas_selector: String,
+    pub asset_root: String,
+    pub fit_canvas_to_parent: bool
+}
+impl OptimaBevyWebConfig {
+    pub fn new(canvas_selector: &str, asset_root: &str, fit_canvas_to_parent: bool) -> Self {
+        Self {
+            canvas_selector: canvas_selector.to_string(),
+            asset_root: asset_root.to_string(),
+            fit_canvas_to_parent,
+        }
+    }
+}
+impl Default for OptimaBevyWebConfig {
+    fn default() -> Self {
+        Self::new("#optima-canvas", "optima_toolbox/optima_assets", true)
+    }
+}
+
+/// Returns the value of the given query parameter in the url of the current page, e.g.,
+/// `get_url_query_parameter("robot")` on `.../index.html?robot=ur5` returns `Some("ur5")`.  This
+/// is what allows a visualization to be shared as a link.  Always returns None off of wasm32.
+#[cfg(target_arch = "wasm32")]
+pub fn get_url_query_parameter(key: &str) -> Option<String> {
+    let window = web_sys::window()?;
+    let search = window.location().search().ok()?;
+    let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
+    params.get(key)
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_url_query_parameter(_key: &str) -> Option<String> {
+    None
+}
+
+/// Fetches a saved robot from `asset_root` into the in-memory asset folder (see `OVirtualAssets`),
+/// so that `ORobot::load_from_saved_robot` finds it in the browser, which has no file system to
+/// read from.  Does nothing off of wasm32, where saved robots are read from the asset folder on
+/// disk.  See `fetch_saved_robot_then` for use from a (non-async) `main`.
+#[cfg(target_arch = "wasm32")]
+pub async fn fetch_saved_robot(robot_name: &str, web_config: &OptimaBevyWebConfig) -> Result<(), OptimaError> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let url = format!("{}/saved_robots/{}", web_config.asset_root.trim_end_matches('/'), robot_name);
+    let not_found = |message: String| OptimaError::RobotNotFound { robot_name: robot_name.to_string(), message };
+
+    let window = web_sys::window().ok_or(not_found("there is no browser window".to_string()))?;
+    let response = JsFuture::from(window.fetch_with_str(&url)).await.map_err(|e| not_found(format!("could not fetch {}: {:?}", url, e)))?;
+    let response: web_sys::Response = response.dyn_into().map_err(|e| not_found(format!("{:?}", e)))?;
+    if !response.ok() { return Err(not_found(format!("fetching {} returned http status {}", url, response.status()))); }
+    let text = response.text().map_err(|e| not_found(format!("{:?}", e)))?;
+    let contents = JsFuture::from(text).await.map_err(|e| not_found(format!("could not read {}: {:?}", url, e)))?.as_string().unwrap_or_default();
+
+    let components = OAssetLocation::SavedRobot { robot_name }.get_path_wrt_asset_folder();
+    let components: Vec<&str> = components.iter().map(|x| x.as_str()).collect();
+    OVirtualAssets::add_file(&components, contents.as_bytes())?;
+    Ok(())
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn fetch_saved_robot(_robot_name: &str, _web_config: &OptimaBevyWebConfig) -> Result<(), OptimaError> {
+    Ok(())
+}
+
+/// Runs `fetch_saved_robot` without blocking the browser and calls `f` with its result once the
+/// robot has been fetched.  Off of wasm32, `f` is called right away.
+#[cfg(target_arch = "wasm32")]
+pub fn fetch_saved_robot_then<F: FnOnce(Result<(), OptimaError>) + 'static>(robot_name: &str, web_config: &OptimaBevyWebConfig, f: F) {
+    let robot_name = robot_name.to_string();
+    let web_config = web_config.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        f(fetch_saved_robot(&robot_name, &web_config).await);
+    });
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub fn fetch_saved_robot_then<F: FnOnce(Result<(), OptimaError>) + 'static>(_robot_name: &str, _web_config: &OptimaBevyWebConfig, f: F) {
+    f(Ok(()));
+}
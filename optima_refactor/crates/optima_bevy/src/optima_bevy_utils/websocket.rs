@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::time::Duration;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use tungstenite::{accept, Message};
+use crate::optima_bevy_utils::robotics::RobotStateEngine;
+
+/// Messages accepted from websocket clients.  Text frames are json, e.g.,
+/// `{"type": "robot_state", "robot_instance_idx": 0, "state": [0.0, 0.1, ...]}`.  For lower
+/// overhead, a binary frame can also be used to send a robot state, laid out as a little-endian
+/// u32 robot instance idx followed by little-endian f64 joint values.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebSocketInboundMessage {
+    RobotState { robot_instance_idx: usize, state: Vec<f64> }
+}
+impl WebSocketInboundMessage {
+    pub fn from_binary(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 4 || (bytes.len() - 4) % 8 != 0 { return None; }
+        let robot_instance_idx = u32::from_le_bytes(bytes[0..4].try_into().ok()?) as usize;
+        let state = bytes[4..].chunks_exact(8).map(|x| f64::from_le_bytes(x.try_into().unwrap())).collect();
+        Some(Self::RobotState { robot_instance_idx, state })
+    }
+}
+
+/// Messages broadcast to every connected websocket client as json text frames.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebSocketOutboundMessage {
+    RobotState { robot_instance_idx: usize, state: Vec<f64> },
+    /// orientation is a unit quaternion in [w x y z] format.
+    SelectedGoal { robot_instance_idx: usize, link_idx: usize, position: [f64; 3], orientation: [f64; 4] },
+    RecordedState { robot_instance_idx: usize, state: Vec<f64> },
+    Custom { name: String, payload: serde_json::Value }
+}
+
+pub struct WebSocketStateServer {
+    inbound: Mutex<Receiver<WebSocketInboundMessage>>,
+    clients: Arc<Mutex<Vec<Sender<String>>>>
+}
+impl WebSocketStateServer {
+    /// Starts listening on the given address (e.g., "127.0.0.1:9001") on a background thread.  Each
+    /// client connection is handled on its own thread.
+    pub fn start(addr: &str) -> Self {
+        let listener = TcpListener::bind(addr).expect(&format!("could not bind websocket server to {}", addr));
+        let (inbound_tx, inbound_rx) = channel();
+        let clients: Arc<Mutex<Vec<Sender<String>>>> = Arc::new(Mutex::new(vec![]));
+
+        let clients_ = clients.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if let Ok(stream) = stream {
+                    let (outbound_tx, outbound_rx) = channel();
+                    clients_.lock().unwrap().push(outbound_tx);
+                    let inbound_tx = inbound_tx.clone();
+                    std::thread::spawn(move || { Self::handle_connection(stream, inbound_tx, outbound_rx); });
+                }
+            }
+        });
+
+        Self { inbound: Mutex::new(inbound_rx), clients }
+    }
+    pub fn try_recv_all(&self) -> Vec<WebSocketInboundMessage> {
+        let inbound = self.inbound.lock().unwrap();
+        let mut out = vec![];
+        while let Ok(message) = inbound.try_recv() { out.push(message); }
+        out
+    }
+    pub fn broadcast(&self, message: &WebSocketOutboundMessage) {
+        let text = serde_json::to_string(message).expect("error");
+        self.clients.lock().unwrap().retain(|x| x.send(text.clone()).is_ok());
+    }
+    pub fn num_clients(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+    fn handle_connection(stream: TcpStream, inbound_tx: Sender<WebSocketInboundMessage>, outbound_rx: Receiver<String>) {
+        let mut websocket = match accept(stream) {
+            Ok(websocket) => { websocket }
+            Err(_) => { return; }
+        };
+        // short timeout so that outbound messages are not held up waiting on client input.
+        websocket.get_ref().set_read_timeout(Some(Duration::from_millis(5))).ok();
+
+        loop {
+            match websocket.read() {
+                Ok(Message::Text(text)) => {
+                    if let Ok(message) = serde_json::from_str::<WebSocketInboundMessage>(&text) { inbound_tx.send(message).ok(); }
+                }
+                Ok(Message::Binary(bytes)) => {
+                    if let Some(message) = WebSocketInboundMessage::from_binary(&bytes) { inbound_tx.send(message).ok(); }
+                }
+                Ok(Message::Close(_)) => { return; }
+                Ok(_) => { }
+                Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => { }
+                Err(_) => { return; }
+            }
+
+            loop {
+                match outbound_rx.try_recv() {
+                    Ok(text) => { if websocket.send(Message::Text(text)).is_err() { return; } }
+                    Err(TryRecvError::Empty) => { break; }
+                    Err(TryRecvError::Disconnected) => { return; }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct BevyWebSocketStateServer {
+    pub server: WebSocketStateServer,
+    /// if true, every change in the `RobotStateEngine` is streamed out to clients.
+    pub stream_robot_states: bool,
+    last_streamed_states: HashMap<usize, Vec<f64>>
+}
+impl BevyWebSocketStateServer {
+    pub fn new(addr: &str, stream_robot_states: bool) -> Self {
+        Self { server: WebSocketStateServer::start(addr), stream_robot_states, last_streamed_states: HashMap::new() }
+    }
+    /// Sends a ui event (e.g., a selected goal or a recorded state) to all connected clients.
+    pub fn send_event(&self, message: &WebSocketOutboundMessage) {
+        self.server.broadcast(message);
+    }
+}
+
+pub struct WebSocketSystems;
+impl WebSocketSystems {
+    pub fn system_websocket_state_server(mut websocket_server: ResMut<BevyWebSocketStateServer>, mut robot_state_engine: ResMut<RobotStateEngine>) {
+        for message in websocket_server.server.try_recv_all() {
+            match message {
+                WebSocketInboundMessage::RobotState { robot_instance_idx, state } => {
+                    robot_state_engine.add_update_request(robot_instance_idx, &state);
+                }
+            }
+        }
+
+        if websocket_server.stream_robot_states {
+            let mut changed = vec![];
+            for (robot_instance_idx, state) in robot_state_engine.robot_states.iter() {
+                if websocket_server.last_streamed_states.get(robot_instance_idx) != Some(state) {
+                    changed.push((*robot_instance_idx, state.clone()));
+                }
+            }
+            for (robot_instance_idx, state) in changed {
+                websocket_server.send_event(&WebSocketOutboundMessage::RobotState { robot_instance_idx, state: state.clone() });
+                websocket_server.last_streamed_states.insert(robot_instance_idx, state);
+            }
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use ad_trait::differentiable_function::ForwardADMulti;
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+use std::panic::AssertUnwindSafe;
+use serde::{Deserialize, Serialize};
+use optima_console::progress::OProgressHandle;
+use optima_file::path::{OAssetLocation, OStemCellPath};
+use optima_interpolation::time_parameterization::{resample_timed_waypoints, velocity_limited_waypoint_times};
+use optima_proximity::pair_group_queries::{OParryDistanceGroupArgs, OParryIntersectGroupArgs, OParryPairSelector, OwnedParryDistanceGroupQry, OwnedParryIntersectGroupQry};
+use optima_proximity::pair_queries::{ParryDisMode, ParryShapeRep};
+use optima_proximity::shape_scene::ShapeSceneTrait;
+use optima_robotics::robot::{ORobotDefault, ORobotPreprocessOptions, SaveRobot};
+use optima_robotics::robotics_optimization::robotics_optimization_ik_batch::{IKBatchResult, IKBatchSettings, IKBatchSummary};
+
+/// Implementations of the cli's subcommands.  Each one takes its parsed json input and returns its
+/// json output; malformed inputs, and panics while loading or preprocessing a robot, are reported
+/// as an Err.
+
+type FAD = adfn<8>;
+
+/// A pose as it appears in json.  `orientation` is a unit quaternion in [w x y z] format.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CliPose {
+    pub position: [f64; 3],
+    pub orientation: [f64; 4]
+}
+impl CliPose {
+    pub fn from_isometry(pose: &Isometry3<f64>) -> Self {
+        let t = &pose.translation.vector;
+        let q = pose.rotation.quaternion();
+        Self { position: [t[0], t[1], t[2]], orientation: [q.w, q.i, q.j, q.k] }
+    }
+    pub fn to_isometry(&self) -> Isometry3<f64> {
+        let p = &self.position;
+        let o = &self.orientation;
+        Isometry3::from_parts(Translation3::new(p[0], p[1], p[2]), UnitQuaternion::from_quaternion(Quaternion::new(o[0], o[1], o[2], o[3])))
+    }
+}
+
+/// Loads a robot that has been preprocessed and saved (e.g., with `preprocess-robot`).
+pub fn load_saved_robot(robot_name: &str) -> Result<ORobotDefault, String> {
+    let mut path = OStemCellPath::new_asset_path();
+    path.append_file_location(&OAssetLocation::SavedRobot { robot_name });
+    if !path.exists() { return Err(format!("robot {} has not been preprocessed and saved; run `optima_cli preprocess-robot --robot {}` first", robot_name, robot_name)); }
+    ORobotDefault::load_from_saved_robot(robot_name).map_err(|e| e.to_string())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PreprocessRobotOutput {
+    pub robot: String,
+    pub num_dofs: usize,
+    pub num_shapes: usize,
+    pub num_skipped_pairs: usize,
+    pub saved: bool
+}
+
+/// Builds the robot from its urdf and preprocesses it.  Sampling uses the global rng, so pass
+/// `--seed` to the cli for reproducible pair skips.  Link collision meshes are built as described
+/// by `options`.  Nothing is saved if `progress` is cancelled.
+pub fn preprocess_robot(robot_name: &str, save: bool, options: &ORobotPreprocessOptions, progress: &OProgressHandle) -> Result<PreprocessRobotOutput, String> {
+    let mut urdf_dir = OStemCellPath::new_asset_path();
+    urdf_dir.append_file_location(&OAssetLocation::UrdfRobot { robot_name });
+    if !urdf_dir.exists() { return Err(format!("no urdf found for robot {}", robot_name)); }
+
+    // mesh loading and processing still panic on malformed or missing files.
+    let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut robot = ORobotDefault::from_urdf(robot_name)?;
+        let save_robot = if save { SaveRobot::Save(None) } else { SaveRobot::DoNotSave };
+        let completed = robot.preprocess_with_options(save_robot, options, progress, None);
+        Ok::<_, String>((robot, completed))
+    }));
+    let (robot, completed) = res.map_err(|_| format!("robot {} could not be loaded or preprocessed (see the panic message above)", robot_name))??;
+    if !completed { return Err("preprocessing was cancelled; nothing was saved".to_string()); }
+
+    let scene = robot.parry_shape_scene();
+    Ok(PreprocessRobotOutput {
+        robot: robot_name.to_string(),
+        num_dofs: robot.num_dofs(),
+        num_shapes: scene.get_shapes().len(),
+        num_skipped_pairs: scene.get_pair_skips().hashmap.len(),
+        saved: save
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FKInput {
+    pub states: Vec<Vec<f64>>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FKOutput {
+    /// One entry per input state.
+    pub results: Vec<Vec<FKLinkPose>>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FKLinkPose {
+    pub link_idx: usize,
+    pub link_name: String,
+    pub pose: CliPose
+}
+
+/// Links that are not present in the model are left out.
+pub fn fk(robot: &ORobotDefault, input: &FKInput) -> Result<FKOutput, String> {
+    let mut results = vec![];
+    for state in &input.states {
+        check_state_length(robot, state)?;
+        let fk_res = robot.forward_kinematics(state, None);
+        let mut link_poses = vec![];
+        fk_res.link_poses().iter().enumerate().for_each(|(link_idx, pose)| {
+            if let Some(pose) = pose {
+                link_poses.push(FKLinkPose { link_idx, link_name: robot.links()[link_idx].name().to_string(), pose: CliPose::from_isometry(pose) });
+            }
+        });
+        results.push(link_poses);
+    }
+
+    Ok(FKOutput { results })
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IKInput {
+    /// The links that are given goals, by index.
+    pub goal_link_idxs: Vec<usize>,
+    /// One entry per ik problem, each holding one pose per entry of `goal_link_idxs`.
+    pub goals: Vec<Vec<CliPose>>,
+    /// Either one initial state per problem or a single shared one.  Defaults to the zero state.
+    #[serde(default)]
+    pub seeds: Option<Vec<Vec<f64>>>,
+    #[serde(default)]
+    pub settings: Option<IKBatchSettings>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IKOutput {
+    pub results: Vec<IKBatchResult>,
+    pub summary: IKBatchSummary
+}
+
+pub fn ik(robot: &ORobotDefault, input: &IKInput) -> Result<IKOutput, String> {
+    if input.goal_link_idxs.is_empty() { return Err("at least one goal link is required".to_string()); }
+    for link_idx in &input.goal_link_idxs { check_link_idx(robot, *link_idx)?; }
+    for (i, goals) in input.goals.iter().enumerate() {
+        if goals.len() != input.goal_link_idxs.len() { return Err(format!("problem {} has {} goals, expected {}", i, goals.len(), input.goal_link_idxs.len())); }
+    }
+    let seeds = input.seeds.clone().unwrap_or(vec![vec![0.0; robot.num_dofs()]]);
+    if seeds.len() != 1 && seeds.len() != input.goals.len() { return Err(format!("expected 1 or {} seeds, got {}", input.goals.len(), seeds.len())); }
+    for seed in &seeds { check_state_length(robot, seed)?; }
+    let settings = input.settings.clone().unwrap_or_default();
+
+    let goals: Vec<Vec<Isometry3<f64>>> = input.goals.iter().map(|x| x.iter().map(|y| y.to_isometry()).collect()).collect();
+    let results = robot.solve_ik_batch(ForwardADMulti::<FAD>::new(), &input.goal_link_idxs, &goals, &seeds, &settings)?;
+    let summary = IKBatchSummary::new(&results);
+
+    Ok(IKOutput { results, summary })
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckCollisionInput {
+    pub states: Vec<Vec<f64>>,
+    /// Defaults to `Full`.
+    #[serde(default)]
+    pub shape_rep: Option<ParryShapeRep>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckCollisionOutput {
+    /// One entry per input state.
+    pub results: Vec<CheckCollisionResult>,
+    pub num_in_collision: usize
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckCollisionResult {
+    pub in_collision: bool,
+    /// Descriptions of the shapes in each colliding pair.
+    pub colliding_pairs: Vec<(String, String)>,
+    /// Smallest signed distance between any pair that is not skipped.
+    pub min_distance: f64
+}
+
+/// Self-collision check for each state, honoring the pair skips found during preprocessing.
+pub fn check_collision(robot: &ORobotDefault, input: &CheckCollisionInput) -> Result<CheckCollisionOutput, String> {
+    let shape_rep = input.shape_rep.clone().unwrap_or(ParryShapeRep::Full);
+    let intersect_q = OwnedParryIntersectGroupQry::new(OParryIntersectGroupArgs::new(shape_rep.clone(), shape_rep.clone(), false, false));
+    let distance_q = OwnedParryDistanceGroupQry::new(OParryDistanceGroupArgs::new(shape_rep.clone(), shape_rep.clone(), ParryDisMode::ContactDis, false, false, f64::MIN, true));
+    let scene = robot.parry_shape_scene();
+
+    let mut results = vec![];
+    for state in &input.states {
+        check_state_length(robot, state)?;
+        let fk_res = robot.forward_kinematics(state, None);
+        let intersect_res = robot.parry_shape_scene_self_query_from_fk_res(&fk_res, &intersect_q, &OParryPairSelector::HalfPairs, false);
+        let distance_res = robot.parry_shape_scene_self_query_from_fk_res(&fk_res, &distance_q, &OParryPairSelector::HalfPairs, false);
+
+        let colliding_pairs = intersect_res.outputs().iter().filter(|x| x.data().intersect()).map(|x| {
+            let ids = x.pair_ids();
+            (scene.shape_id_to_shape_str(ids.0), scene.shape_id_to_shape_str(ids.1))
+        }).collect();
+
+        results.push(CheckCollisionResult { in_collision: intersect_res.intersect(), colliding_pairs, min_distance: *distance_res.min_raw_dis() });
+    }
+    let num_in_collision = results.iter().filter(|x| x.in_collision).count();
+
+    Ok(CheckCollisionOutput { results, num_in_collision })
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimeParameterizeInput {
+    pub waypoints: Vec<Vec<f64>>,
+    /// One per dof.  If left out, the robot's joint velocity limits are used.
+    #[serde(default)]
+    pub max_velocities: Option<Vec<f64>>,
+    /// If given, the path is also resampled at this interval (in seconds).
+    #[serde(default)]
+    pub time_step: Option<f64>
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimeParameterizeOutput {
+    /// Time at which each waypoint is reached.
+    pub waypoint_times: Vec<f64>,
+    pub duration: f64,
+    /// (time, state) pairs, present if a time step was given.
+    pub samples: Option<Vec<(f64, Vec<f64>)>>
+}
+
+/// Velocity-limited timing for the piecewise linear path through the waypoints.
+pub fn time_parameterize(robot: Option<&ORobotDefault>, input: &TimeParameterizeInput) -> Result<TimeParameterizeOutput, String> {
+    let max_velocities = match (&input.max_velocities, robot) {
+        (Some(max_velocities), _) => { max_velocities.clone() }
+        (None, Some(robot)) => { joint_velocity_limits(robot)? }
+        (None, None) => { return Err("either give max_velocities in the input or pass --robot to use its joint velocity limits".to_string()); }
+    };
+
+    let waypoint_times = velocity_limited_waypoint_times(&input.waypoints, &max_velocities)?;
+    let duration = waypoint_times.last().cloned().unwrap_or(0.0);
+    let samples = match input.time_step {
+        None => { None }
+        Some(time_step) => { Some(resample_timed_waypoints(&input.waypoints, &waypoint_times, time_step)?) }
+    };
+
+    Ok(TimeParameterizeOutput { waypoint_times, duration, samples })
+}
+
+fn joint_velocity_limits(robot: &ORobotDefault) -> Result<Vec<f64>, String> {
+    let mut out = vec![0.0; robot.num_dofs()];
+    robot.joints().iter().filter(|x| x.is_present_in_model()).for_each(|joint| {
+        let velocity = joint.limit().velocity();
+        // joints with several dofs may give a single limit for all of them; dofs without a limit
+        // stay at 0 and are reported below.
+        joint.dof_idxs().iter().enumerate().for_each(|(i, dof_idx)| {
+            if let Some(v) = velocity.get(i).or(velocity.first()) { out[*dof_idx] = *v; }
+        });
+    });
+    if let Some(dof_idx) = out.iter().position(|x| *x <= 0.0) { return Err(format!("robot {} has no velocity limit for dof {}; give max_velocities in the input", robot.robot_name(), dof_idx)); }
+
+    Ok(out)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn check_state_length(robot: &ORobotDefault, state: &[f64]) -> Result<(), String> {
+    if state.len() != robot.num_dofs() { return Err(format!("expected a state of length {}, got {}", robot.num_dofs(), state.len())); }
+    Ok(())
+}
+
+fn check_link_idx(robot: &ORobotDefault, link_idx: usize) -> Result<(), String> {
+    if link_idx >= robot.links().len() { return Err(format!("link idx {} is out of range", link_idx)); }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use optima_robotics::robotics_components::{OInertial, OJoint, OJointLimit, OJointType, OLink};
+    use super::*;
+
+    /// One revolute joint about z with the given velocity limits.
+    fn one_joint_robot(velocity: Vec<f64>) -> ORobotDefault {
+        let link = |name: &str| OLink::new_manual(name, vec![], vec![], OInertial::new_zeros());
+        let joint = OJoint::new_manual("joint", OJointType::Revolute, Isometry3::identity(), [0.0, 0.0, 1.0], "base", "tip", OJointLimit::new_manual(vec![1.0], vec![-1.0], vec![1.0], velocity), None, None, None);
+        ORobotDefault::from_manual_unchecked("one_joint", vec![link("base"), link("tip")], vec![joint])
+    }
+
+    #[test]
+    fn cli_pose_round_trips_through_isometry() {
+        let pose = CliPose { position: [0.1, -0.2, 0.3], orientation: [0.5, 0.5, 0.5, 0.5] };
+        let round_trip = CliPose::from_isometry(&pose.to_isometry());
+        for i in 0..3 { assert!((round_trip.position[i] - pose.position[i]).abs() < 1e-12); }
+        for i in 0..4 { assert!((round_trip.orientation[i] - pose.orientation[i]).abs() < 1e-12); }
+    }
+
+    #[test]
+    fn fk_and_ik_reject_malformed_input() {
+        let robot = one_joint_robot(vec![1.0]);
+        assert!(fk(&robot, &FKInput { states: vec![vec![0.0, 0.0]] }).is_err());
+        assert_eq!(fk(&robot, &FKInput { states: vec![vec![0.0]] }).expect("error").results.len(), 1);
+
+        let goal = CliPose { position: [0.0; 3], orientation: [1.0, 0.0, 0.0, 0.0] };
+        let ik_input = |goal_link_idxs: Vec<usize>, goals: Vec<Vec<CliPose>>| IKInput { goal_link_idxs, goals, seeds: None, settings: None };
+        assert!(ik(&robot, &ik_input(vec![], vec![])).is_err());
+        assert!(ik(&robot, &ik_input(vec![5], vec![vec![goal.clone()]])).is_err());
+        assert!(ik(&robot, &ik_input(vec![1], vec![vec![goal.clone(), goal.clone()]])).is_err());
+    }
+
+    #[test]
+    fn time_parameterize_uses_the_robot_velocity_limits() {
+        let robot = one_joint_robot(vec![2.0]);
+        let input = TimeParameterizeInput { waypoints: vec![vec![0.0], vec![1.0]], max_velocities: None, time_step: None };
+        let output = time_parameterize(Some(&robot), &input).expect("error");
+        assert!((output.duration - 0.5).abs() < 1e-9, "{}", output.duration);
+        assert!(time_parameterize(None, &input).is_err());
+    }
+
+    #[test]
+    fn time_parameterize_rejects_a_robot_without_velocity_limits() {
+        let robot = one_joint_robot(vec![]);
+        let input = TimeParameterizeInput { waypoints: vec![vec![0.0], vec![1.0]], max_velocities: None, time_step: None };
+        assert!(time_parameterize(Some(&robot), &input).is_err());
+    }
+}
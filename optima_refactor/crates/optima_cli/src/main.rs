@@ -0,0 +1,254 @@
+mod commands;
+
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use clap::{Args, Parser, Subcommand};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use optima_console::progress::{OProgressHandle, OProgressState};
+use optima_sampling::OGlobalRng;
+use optima_3d_mesh::decimation::OMeshDecimation;
+use optima_robotics::robot::{ORobotPreprocessOptions, DEFAULT_LINK_MAX_CONVEX_HULLS};
+use crate::commands::*;
+
+/// Command line access to the toolbox for batch workflows and ci robot validation.  Inputs and
+/// outputs are json, read from stdin and written to stdout unless `--input` / `--output` are given.
+/// Errors are written to stderr with a nonzero exit code.
+///
+/// e.g., `echo '{"states": [[0,0,0,0,0,0]]}' | optima_cli fk --robot ur5`
+#[derive(Parser)]
+#[command(name = "optima_cli", version, about = "optima toolbox command line interface")]
+struct OptimaCli {
+    /// Seeds all sampling (preprocessing, random initial conditions, etc.) so that runs are
+    /// reproducible.
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    #[command(subcommand)]
+    command: OptimaCliCommand
+}
+
+#[derive(Subcommand)]
+enum OptimaCliCommand {
+    /// Builds a robot from its urdf, computes its self-collision pair skips and average distances,
+    /// and saves it.  Takes no input.  Progress is printed to stderr, and ctrl-c cancels cleanly
+    /// (without saving).
+    PreprocessRobot {
+        #[arg(long)]
+        robot: String,
+        /// Preprocess without saving the result (e.g., to check that a urdf loads in ci).
+        #[arg(long)]
+        no_save: bool,
+        /// Upper bound on the number of convex pieces each link mesh is split into.  1 keeps a
+        /// single convex hull per link.
+        #[arg(long, default_value_t = DEFAULT_LINK_MAX_CONVEX_HULLS)]
+        max_convex_hulls: u32,
+        /// Decimates each link's collision meshes down to at most this many triangles.
+        #[arg(long, conflicts_with = "max_decimation_error")]
+        target_triangle_count: Option<usize>,
+        /// Decimates each link's collision meshes as far as possible while keeping vertices within
+        /// this distance (in meters) of the original surface.
+        #[arg(long)]
+        max_decimation_error: Option<f64>,
+        /// Do not print progress to stderr.
+        #[arg(long)]
+        quiet: bool,
+        #[command(flatten)]
+        io: CliIOArgs
+    },
+    /// Forward kinematics.  Input: `{"states": [[...], ...]}`.
+    Fk {
+        #[arg(long)]
+        robot: String,
+        #[command(flatten)]
+        io: CliIOArgs
+    },
+    /// Batch inverse kinematics.  Input: `{"goal_link_idxs": [...], "goals": [[{"position": [x, y, z],
+    /// "orientation": [w, x, y, z]}, ...], ...], "seeds": optional, "settings": optional}`.
+    Ik {
+        #[arg(long)]
+        robot: String,
+        #[command(flatten)]
+        io: CliIOArgs
+    },
+    /// Self-collision check.  Input: `{"states": [[...], ...], "shape_rep": optional}`.
+    CheckCollision {
+        #[arg(long)]
+        robot: String,
+        /// Exit with a nonzero code if any state is in collision.
+        #[arg(long)]
+        fail_on_collision: bool,
+        #[command(flatten)]
+        io: CliIOArgs
+    },
+    /// Velocity-limited timing of a waypoint path.  Input: `{"waypoints": [[...], ...],
+    /// "max_velocities": optional, "time_step": optional}`.
+    TimeParameterize {
+        /// Supplies joint velocity limits when the input has no `max_velocities`.
+        #[arg(long)]
+        robot: Option<String>,
+        #[command(flatten)]
+        io: CliIOArgs
+    },
+    /// Opens the viewer on the robot.  If an input with `{"states": [[...], ...]}` is given, the
+    /// states are played back as a motion.
+    View {
+        #[arg(long)]
+        robot: String,
+        #[arg(long, short)]
+        input: Option<PathBuf>,
+        /// Builds the robot from its urdf and preprocesses it in the viewer, with a progress bar
+        /// and a cancel button.  The result is saved unless `--no-save` is given.
+        #[arg(long, conflicts_with = "input")]
+        preprocess: bool,
+        #[arg(long, requires = "preprocess")]
+        no_save: bool
+    }
+}
+
+#[derive(Args)]
+struct CliIOArgs {
+    /// Json input file (stdin if not given).
+    #[arg(long, short)]
+    input: Option<PathBuf>,
+    /// Json output file (stdout if not given).
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+    #[arg(long)]
+    pretty: bool
+}
+impl CliIOArgs {
+    fn read<I: DeserializeOwned>(&self) -> Result<I, String> {
+        let mut s = String::new();
+        match &self.input {
+            None => { std::io::stdin().read_to_string(&mut s).map_err(|e| format!("could not read stdin: {}", e))?; }
+            Some(path) => { s = std::fs::read_to_string(path).map_err(|e| format!("could not read {:?}: {}", path, e))?; }
+        }
+        serde_json::from_str(&s).map_err(|e| format!("invalid input: {}", e))
+    }
+    fn write<O: Serialize>(&self, output: &O) -> Result<(), String> {
+        let s = if self.pretty { serde_json::to_string_pretty(output) } else { serde_json::to_string(output) }.map_err(|e| e.to_string())?;
+        match &self.output {
+            None => { writeln!(std::io::stdout(), "{}", s).map_err(|e| format!("could not write stdout: {}", e)) }
+            Some(path) => { std::fs::write(path, s).map_err(|e| format!("could not write {:?}: {}", path, e)) }
+        }
+    }
+}
+
+fn main() {
+    let cli = OptimaCli::parse();
+    if let Some(seed) = cli.seed { OGlobalRng::set_seed(Some(seed)); }
+
+    match run(cli.command) {
+        Ok(exit_code) => { std::process::exit(exit_code); }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run(command: OptimaCliCommand) -> Result<i32, String> {
+    match command {
+        OptimaCliCommand::PreprocessRobot { robot, no_save, max_convex_hulls, target_triangle_count, max_decimation_error, quiet, io } => {
+            let decimation = match (target_triangle_count, max_decimation_error) {
+                (Some(n), _) => { Some(OMeshDecimation::TargetTriangleCount(n)) }
+                (None, Some(e)) => { Some(OMeshDecimation::MaxError(e)) }
+                (None, None) => { None }
+            };
+            let options = ORobotPreprocessOptions { max_convex_hulls, decimation };
+
+            let progress = OProgressHandle::new();
+            // the workers' own bars would go to stdout, which is reserved for the json output.
+            progress.set_terminal_bars(false);
+            let ctrlc_progress = progress.clone();
+            ctrlc::set_handler(move || ctrlc_progress.cancel()).map_err(|e| e.to_string())?;
+
+            let worker_progress = progress.clone();
+            let worker = std::thread::spawn(move || preprocess_robot(&robot, !no_save, &options, &worker_progress));
+            while !worker.is_finished() {
+                if !quiet { print_progress(&progress.state()); }
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            if !quiet { print_progress(&progress.state()); eprintln!(); }
+
+            io.write(&worker.join().map_err(|_| "preprocessing panicked".to_string())??)?;
+        }
+        OptimaCliCommand::Fk { robot, io } => {
+            let robot = load_saved_robot(&robot)?;
+            io.write(&fk(&robot, &io.read()?)?)?;
+        }
+        OptimaCliCommand::Ik { robot, io } => {
+            let robot = load_saved_robot(&robot)?;
+            io.write(&ik(&robot, &io.read()?)?)?;
+        }
+        OptimaCliCommand::CheckCollision { robot, fail_on_collision, io } => {
+            let robot = load_saved_robot(&robot)?;
+            let output = check_collision(&robot, &io.read()?)?;
+            io.write(&output)?;
+            if fail_on_collision && output.num_in_collision > 0 { return Ok(2); }
+        }
+        OptimaCliCommand::TimeParameterize { robot, io } => {
+            let robot = match robot {
+                None => { None }
+                Some(robot) => { Some(load_saved_robot(&robot)?) }
+            };
+            io.write(&time_parameterize(robot.as_ref(), &io.read()?)?)?;
+        }
+        OptimaCliCommand::View { robot, input, preprocess, no_save } => {
+            if preprocess {
+                view_preprocess(&robot, !no_save)?;
+            } else {
+                let robot = load_saved_robot(&robot)?;
+                view(robot, input)?;
+            }
+        }
+    }
+
+    Ok(0)
+}
+
+fn print_progress(state: &OProgressState) {
+    eprint!("\r\x1b[2K[{:>5.1}%] {}", state.percentage(), state.stage);
+    let _ = std::io::stderr().flush();
+}
+
+#[cfg(feature = "view")]
+fn view(robot: optima_robotics::robot::ORobotDefault, input: Option<PathBuf>) -> Result<(), String> {
+    use optima_bevy::optima_bevy_utils::robotics::BevyRoboticsTrait;
+    use optima_interpolation::splines::{InterpolatingSpline, InterpolatingSplineType};
+
+    match input {
+        None => { robot.bevy_display(); }
+        Some(input) => {
+            let io = CliIOArgs { input: Some(input), output: None, pretty: false };
+            let states: FKInput = io.read()?;
+            if states.states.len() < 2 { return Err("at least two states are needed for playback".to_string()); }
+            if let Some(state) = states.states.iter().find(|x| x.len() != robot.num_dofs()) { return Err(format!("expected states of length {}, got {}", robot.num_dofs(), state.len())); }
+            let spline = InterpolatingSpline::new(states.states, InterpolatingSplineType::Linear);
+            robot.bevy_motion_playback(&spline);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "view")]
+fn view_preprocess(robot_name: &str, save: bool) -> Result<(), String> {
+    use optima_bevy::optima_bevy_utils::robotics::BevyRoboticsTrait;
+
+    let robot = std::panic::catch_unwind(|| optima_robotics::robot::ORobotDefault::from_urdf(robot_name)).map_err(|_| format!("robot {} could not be loaded (see the panic message above)", robot_name))??;
+    robot.bevy_preprocess(save);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "view"))]
+fn view(_robot: optima_robotics::robot::ORobotDefault, _input: Option<PathBuf>) -> Result<(), String> {
+    Err("optima_cli was built without the `view` feature".to_string())
+}
+
+#[cfg(not(feature = "view"))]
+fn view_preprocess(_robot_name: &str, _save: bool) -> Result<(), String> {
+    Err("optima_cli was built without the `view` feature".to_string())
+}
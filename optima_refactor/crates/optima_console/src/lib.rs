@@ -1,3 +1,5 @@
 
 pub mod input;
 pub mod output;
+pub mod progress;
+pub mod logging;
@@ -0,0 +1,195 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, OnceLock};
+use serde::{Deserialize, Serialize};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Environment variable that, when set, replaces the filter of an `OLogConfig` (same syntax as
+/// `RUST_LOG`, e.g., `warn,optima_robotics=debug`).
+pub const OPTIMA_LOG_ENV_VAR: &str = "OPTIMA_LOG";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum OLogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace
+}
+impl OLogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OLogLevel::Error => { "error" }
+            OLogLevel::Warn => { "warn" }
+            OLogLevel::Info => { "info" }
+            OLogLevel::Debug => { "debug" }
+            OLogLevel::Trace => { "trace" }
+        }
+    }
+    pub fn from_tracing_level(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => { OLogLevel::Error }
+            Level::WARN => { OLogLevel::Warn }
+            Level::INFO => { OLogLevel::Info }
+            Level::DEBUG => { OLogLevel::Debug }
+            _ => { OLogLevel::Trace }
+        }
+    }
+}
+
+/// Which log events are kept, per crate (or any other tracing target prefix), and where they go.
+///
+/// # Example
+///```ignore
+/// use optima_console::logging::{init_logging, OLogConfig, OLogLevel};
+///
+/// let buffer = init_logging(&OLogConfig::new(OLogLevel::Warn).with_crate_level("optima_robotics", OLogLevel::Debug)).expect("error");
+///```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OLogConfig {
+    pub default_level: OLogLevel,
+    pub crate_levels: Vec<(String, OLogLevel)>,
+    /// Print events to stdout.
+    pub to_stdout: bool,
+    /// Number of recent events kept in the `OLogBuffer` (e.g., for the viewer's log panel).
+    pub buffer_capacity: usize
+}
+impl OLogConfig {
+    pub fn new(default_level: OLogLevel) -> Self {
+        Self { default_level, crate_levels: vec![], to_stdout: true, buffer_capacity: 2000 }
+    }
+    pub fn with_crate_level(mut self, crate_name: &str, level: OLogLevel) -> Self {
+        self.crate_levels.retain(|x| x.0 != crate_name);
+        self.crate_levels.push((crate_name.to_string(), level));
+        self
+    }
+    pub fn with_stdout(mut self, to_stdout: bool) -> Self {
+        self.to_stdout = to_stdout;
+        self
+    }
+    pub fn with_buffer_capacity(mut self, buffer_capacity: usize) -> Self {
+        self.buffer_capacity = buffer_capacity;
+        self
+    }
+    /// The config as filter directives, e.g., `warn,optima_robotics=debug`.
+    pub fn to_filter_directives(&self) -> String {
+        let mut out = self.default_level.as_str().to_string();
+        self.crate_levels.iter().for_each(|(crate_name, level)| out += &format!(",{}={}", crate_name, level.as_str()));
+        out
+    }
+}
+impl Default for OLogConfig {
+    /// Info for the optima crates, warn for everything else.
+    fn default() -> Self {
+        let mut out = Self::new(OLogLevel::Warn);
+        for crate_name in ["optima_robotics", "optima_proximity", "optima_optimization", "optima_file", "optima_bevy"] {
+            out = out.with_crate_level(crate_name, OLogLevel::Info);
+        }
+        out
+    }
+}
+
+/// Installs the global tracing subscriber described by `config` (overridden by the `OPTIMA_LOG`
+/// environment variable, if set).  Returns the buffer that recent events are collected in.  Can
+/// only succeed once per process.
+pub fn init_logging(config: &OLogConfig) -> Result<OLogBuffer, String> {
+    let directives = std::env::var(OPTIMA_LOG_ENV_VAR).unwrap_or(config.to_filter_directives());
+    let filter = EnvFilter::try_new(&directives).map_err(|e| format!("invalid log filter {:?}: {}", directives, e))?;
+    let buffer = OLogBuffer::new(config.buffer_capacity);
+    let stdout_layer = if config.to_stdout { Some(tracing_subscriber::fmt::layer()) } else { None };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(stdout_layer)
+        .with(buffer.clone())
+        .try_init()
+        .map_err(|e| e.to_string())?;
+
+    let _ = OLOG_GLOBAL_BUFFER.set(buffer.clone());
+    Ok(buffer)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OLogRecord {
+    /// Increases by one with every event, so consumers can tell which records they have seen.
+    pub idx: u64,
+    pub level: OLogLevel,
+    pub target: String,
+    /// Names of the spans the event happened in, outermost first.
+    pub spans: Vec<String>,
+    pub message: String
+}
+
+/// A bounded buffer of recent log events, filled by the subscriber that `init_logging` installs.
+/// Clones share the same buffer.
+#[derive(Clone)]
+pub struct OLogBuffer {
+    records: Arc<Mutex<VecDeque<OLogRecord>>>,
+    next_idx: Arc<Mutex<u64>>,
+    capacity: usize
+}
+impl OLogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self { records: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))), next_idx: Arc::new(Mutex::new(0)), capacity }
+    }
+    /// The buffer installed by `init_logging`, if it has been called.
+    pub fn global() -> Option<OLogBuffer> {
+        OLOG_GLOBAL_BUFFER.get().cloned()
+    }
+    pub fn records(&self) -> Vec<OLogRecord> {
+        self.records.lock().expect("error").iter().cloned().collect()
+    }
+    pub fn records_since(&self, idx: u64) -> Vec<OLogRecord> {
+        self.records.lock().expect("error").iter().filter(|x| x.idx >= idx).cloned().collect()
+    }
+    pub fn clear(&self) {
+        self.records.lock().expect("error").clear();
+    }
+    fn push(&self, level: OLogLevel, target: String, spans: Vec<String>, message: String) {
+        let mut next_idx = self.next_idx.lock().expect("error");
+        let mut records = self.records.lock().expect("error");
+        if self.capacity == 0 { return; }
+        while records.len() >= self.capacity { records.pop_front(); }
+        records.push_back(OLogRecord { idx: *next_idx, level, target, spans, message });
+        *next_idx += 1;
+    }
+}
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for OLogBuffer {
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = OLogMessageVisitor { message: String::new(), fields: vec![] };
+        event.record(&mut visitor);
+        let mut message = visitor.message;
+        if !visitor.fields.is_empty() {
+            if !message.is_empty() { message += " "; }
+            message += &visitor.fields.join(" ");
+        }
+
+        let spans = match ctx.event_scope(event) {
+            None => { vec![] }
+            Some(scope) => { scope.from_root().map(|x| x.name().to_string()).collect() }
+        };
+
+        let metadata = event.metadata();
+        self.push(OLogLevel::from_tracing_level(metadata.level()), metadata.target().to_string(), spans, message);
+    }
+}
+
+struct OLogMessageVisitor {
+    message: String,
+    fields: Vec<String>
+}
+impl Visit for OLogMessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" { self.message = value.to_string(); } else { self.fields.push(format!("{}={}", field.name(), value)); }
+    }
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" { self.message = format!("{:?}", value); } else { self.fields.push(format!("{}={:?}", field.name(), value)); }
+    }
+}
+
+static OLOG_GLOBAL_BUFFER: OnceLock<OLogBuffer> = OnceLock::new();
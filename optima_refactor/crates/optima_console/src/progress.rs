@@ -0,0 +1,223 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Stdout;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use pbr::ProgressBar;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::output::get_default_progress_bar;
+
+/// Called with a description of the current stage and the fraction of that stage that has been
+/// completed (in [0, 1]).  Returning false requests cancellation.
+pub type OProgressCallback = dyn FnMut(&str, f64) -> bool + Send;
+
+/// Lets long running operations (robot preprocessing, optimization, etc.) report progress and
+/// check whether they should stop early.  Clones share the same callback, cancel flag, and
+/// progress state, so one clone can be handed to the worker while another is kept by whoever may
+/// want to poll or cancel it (e.g., a ui thread).
+#[derive(Clone)]
+pub struct OProgressHandle {
+    callback: Arc<Mutex<Option<Box<OProgressCallback>>>>,
+    cancelled: Arc<AtomicBool>,
+    state: Arc<Mutex<OProgressState>>,
+    range: (f64, f64),
+    terminal_bars: Arc<AtomicBool>
+}
+impl OProgressHandle {
+    pub fn new() -> Self {
+        Self { callback: Arc::new(Mutex::new(None)), cancelled: Arc::new(AtomicBool::new(false)), state: Arc::new(Mutex::new(OProgressState::new())), range: (0.0, 1.0), terminal_bars: Arc::new(AtomicBool::new(true)) }
+    }
+    pub fn new_with_callback<F: FnMut(&str, f64) -> bool + Send + 'static>(callback: F) -> Self {
+        let out = Self::new();
+        out.set_callback(callback);
+        out
+    }
+    pub fn set_callback<F: FnMut(&str, f64) -> bool + Send + 'static>(&self, callback: F) {
+        *self.callback.lock().expect("error") = Some(Box::new(callback));
+    }
+    pub fn clear_callback(&self) {
+        *self.callback.lock().expect("error") = None;
+    }
+    /// Whether workers draw their own progress bars in the terminal (on by default).  Turn this off
+    /// when presenting progress some other way, e.g., when stdout is reserved for json.
+    pub fn set_terminal_bars(&self, enabled: bool) {
+        self.terminal_bars.store(enabled, Ordering::SeqCst);
+    }
+    /// A handle that shares this one's callback, cancel flag, and state, but whose fractions only
+    /// cover [start, end] of this handle's share of the overall progress.  Used to split an
+    /// operation into stages, e.g., `progress.sub_range(0.0, 0.3)` for a first stage that takes
+    /// about 30% of the time.
+    pub fn sub_range(&self, start: f64, end: f64) -> Self {
+        let width = self.range.1 - self.range.0;
+        let mut out = self.clone();
+        out.range = (self.range.0 + width * start.clamp(0.0, 1.0), self.range.0 + width * end.clamp(0.0, 1.0));
+        out
+    }
+    /// Same as `sub_range`, for the i-th of num_parts equal parts.
+    pub fn sub_range_part(&self, i: usize, num_parts: usize) -> Self {
+        let num_parts = num_parts.max(1) as f64;
+        self.sub_range(i as f64 / num_parts, (i + 1) as f64 / num_parts)
+    }
+    /// Returns true if the operation should keep going.
+    pub fn report(&self, stage: &str, fraction: f64) -> bool {
+        if self.is_cancelled() { return false; }
+        let fraction = fraction.clamp(0.0, 1.0);
+
+        {
+            let mut state = self.state.lock().expect("error");
+            state.stage = stage.to_string();
+            state.stage_fraction = fraction;
+            // stages may restart their count (e.g., when a sampling loop has to keep going), so
+            // the overall fraction only ever moves forward.
+            state.overall_fraction = state.overall_fraction.max(self.range.0 + (self.range.1 - self.range.0) * fraction);
+        }
+
+        let mut binding = self.callback.lock().expect("error");
+        if let Some(callback) = binding.as_mut() {
+            if !callback(stage, fraction) { self.cancel(); }
+        }
+
+        !self.is_cancelled()
+    }
+    /// The most recently reported progress.
+    pub fn state(&self) -> OProgressState {
+        self.state.lock().expect("error").clone()
+    }
+    /// Overall progress in [0, 1].
+    pub fn overall_fraction(&self) -> f64 {
+        self.state.lock().expect("error").overall_fraction
+    }
+    /// Marks the whole operation as done.
+    pub fn finish(&self, stage: &str) {
+        let mut state = self.state.lock().expect("error");
+        state.stage = stage.to_string();
+        state.stage_fraction = 1.0;
+        state.overall_fraction = 1.0;
+    }
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+    /// Clears the cancel flag and the progress state so that the handle can be reused for another
+    /// operation.
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::SeqCst);
+        *self.state.lock().expect("error") = OProgressState::new();
+    }
+    #[inline(always)]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+    /// A terminal progress bar for a loop of `total` steps, or a no-op if terminal bars are turned
+    /// off (see `set_terminal_bars`).
+    pub fn terminal_bar(&self, total: usize) -> OTerminalProgressBar {
+        #[cfg(not(target_arch = "wasm32"))]
+        return OTerminalProgressBar { bar: if self.terminal_bars.load(Ordering::SeqCst) { Some(get_default_progress_bar(total)) } else { None } };
+        #[cfg(target_arch = "wasm32")]
+        { let _ = total; return OTerminalProgressBar; }
+    }
+}
+impl Default for OProgressHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OProgressState {
+    pub stage: String,
+    /// Fraction of the current stage that has been completed.
+    pub stage_fraction: f64,
+    /// Fraction of the whole operation that has been completed.
+    pub overall_fraction: f64
+}
+impl OProgressState {
+    pub fn new() -> Self {
+        Self { stage: String::new(), stage_fraction: 0.0, overall_fraction: 0.0 }
+    }
+    pub fn percentage(&self) -> f64 {
+        self.overall_fraction * 100.0
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct OTerminalProgressBar {
+    bar: Option<ProgressBar<Stdout>>
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl OTerminalProgressBar {
+    pub fn message(&mut self, message: &str) {
+        if let Some(bar) = &mut self.bar { bar.message(message); }
+    }
+    pub fn set(&mut self, i: u64) {
+        if let Some(bar) = &mut self.bar { bar.set(i); }
+    }
+    /// Leaves the finished bar on its own line, so later output does not overwrite it.
+    pub fn finish(&mut self) {
+        if let Some(bar) = &mut self.bar { bar.finish_println(""); }
+    }
+}
+
+/// There is no terminal on the web, so the bar does nothing.
+#[cfg(target_arch = "wasm32")]
+pub struct OTerminalProgressBar;
+#[cfg(target_arch = "wasm32")]
+impl OTerminalProgressBar {
+    pub fn message(&mut self, _message: &str) { }
+    pub fn set(&mut self, _i: u64) { }
+    pub fn finish(&mut self) { }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sub_range_maps_fractions_into_the_parent_range() {
+        let progress = OProgressHandle::new();
+        let stage = progress.sub_range(0.2, 0.6);
+        stage.report("stage", 0.5);
+        assert!((progress.overall_fraction() - 0.4).abs() < 1e-12);
+
+        // nested ranges compose, and bounds outside [0, 1] are clamped.
+        let nested = stage.sub_range(0.5, 2.0);
+        nested.report("nested", 1.0);
+        assert!((progress.overall_fraction() - 0.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sub_range_part_splits_into_equal_parts() {
+        let progress = OProgressHandle::new();
+        progress.sub_range_part(2, 4).report("part", 0.5);
+        assert!((progress.overall_fraction() - 0.625).abs() < 1e-12);
+
+        // zero parts is treated as one part.
+        let progress = OProgressHandle::new();
+        progress.sub_range_part(0, 0).report("part", 0.25);
+        assert!((progress.overall_fraction() - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn overall_fraction_only_moves_forward() {
+        let progress = OProgressHandle::new();
+        progress.report("sampling", 0.7);
+        progress.report("sampling", 0.1);
+        let state = progress.state();
+        assert!((state.overall_fraction - 0.7).abs() < 1e-12);
+        assert!((state.stage_fraction - 0.1).abs() < 1e-12);
+
+        progress.finish("done");
+        assert_eq!(progress.overall_fraction(), 1.0);
+        progress.reset();
+        assert_eq!(progress.overall_fraction(), 0.0);
+    }
+
+    #[test]
+    fn cancelling_from_the_callback_stops_every_clone() {
+        let progress = OProgressHandle::new_with_callback(|_, fraction| fraction < 0.5);
+        let worker = progress.sub_range(0.0, 0.5);
+        assert!(worker.report("work", 0.25));
+        assert!(!worker.report("work", 0.75));
+        assert!(progress.is_cancelled());
+        assert!(!progress.report("work", 0.9));
+    }
+}
@@ -0,0 +1,85 @@
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+
+/// The error type returned by the fallible public apis of optima_file, optima_proximity, and
+/// optima_robotics.  Those apis also have panicking `*_unchecked` counterparts for hot loops and
+/// scripts where the input is known to be good.
+///
+/// Converts to and from `String`, so it can be used with `?` in functions that still return
+/// `Result<_, String>`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OptimaError {
+    /// A file could not be found, read, or written.  `path` is empty when there was no single path
+    /// to blame (e.g., when all candidate paths of an `OStemCellPath` failed).
+    FileIO { path: String, message: String },
+    /// A string or byte buffer could not be parsed into (or written from) the requested type.
+    Serialization { type_name: String, message: String },
+    /// A robot could not be found under the given name (e.g., it has no urdf, or was never saved).
+    RobotNotFound { robot_name: String, message: String },
+    LinkNotFound { link_name: String },
+    JointNotFound { joint_name: String },
+    /// `kind` names what was being indexed, e.g., "link".
+    IdxOutOfBounds { kind: String, idx: usize, len: usize },
+    /// The link is in the robot, but forward kinematics did not compute a pose for it (e.g., it is
+    /// past a dead end link).
+    LinkHasNoPose { link_idx: usize },
+    InvalidInput(String),
+    Generic(String)
+}
+impl OptimaError {
+    pub fn new_file_io<P: Display, M: Display>(path: P, message: M) -> Self {
+        Self::FileIO { path: path.to_string(), message: message.to_string() }
+    }
+    /// `T` is the type that was being (de)serialized.
+    pub fn new_serialization<T: ?Sized, M: Display>(message: M) -> Self {
+        Self::Serialization { type_name: std::any::type_name::<T>().to_string(), message: message.to_string() }
+    }
+    pub fn new_idx_out_of_bounds(kind: &str, idx: usize, len: usize) -> Self {
+        Self::IdxOutOfBounds { kind: kind.to_string(), idx, len }
+    }
+    /// Returns `IdxOutOfBounds` if `idx` is not less than `len`.
+    #[inline(always)]
+    pub fn check_idx(kind: &str, idx: usize, len: usize) -> Result<(), Self> {
+        if idx < len { Ok(()) } else { Err(Self::new_idx_out_of_bounds(kind, idx, len)) }
+    }
+}
+impl Display for OptimaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptimaError::FileIO { path, message } => {
+                if path.is_empty() { write!(f, "file error: {}", message) } else { write!(f, "file error at {}: {}", path, message) }
+            }
+            OptimaError::Serialization { type_name, message } => { write!(f, "could not (de)serialize {}: {}", type_name, message) }
+            OptimaError::RobotNotFound { robot_name, message } => { write!(f, "robot {} not found: {}", robot_name, message) }
+            OptimaError::LinkNotFound { link_name } => { write!(f, "link {} not found", link_name) }
+            OptimaError::JointNotFound { joint_name } => { write!(f, "joint {} not found", joint_name) }
+            OptimaError::IdxOutOfBounds { kind, idx, len } => { write!(f, "{} idx {} is out of bounds (there are {})", kind, idx, len) }
+            OptimaError::LinkHasNoPose { link_idx } => { write!(f, "link {} does not have a pose", link_idx) }
+            OptimaError::InvalidInput(s) => { write!(f, "invalid input: {}", s) }
+            OptimaError::Generic(s) => { write!(f, "{}", s) }
+        }
+    }
+}
+impl std::error::Error for OptimaError { }
+impl From<String> for OptimaError {
+    fn from(value: String) -> Self {
+        Self::Generic(value)
+    }
+}
+impl From<&str> for OptimaError {
+    fn from(value: &str) -> Self {
+        Self::Generic(value.to_string())
+    }
+}
+impl From<OptimaError> for String {
+    fn from(value: OptimaError) -> Self {
+        value.to_string()
+    }
+}
+impl From<std::io::Error> for OptimaError {
+    fn from(value: std::io::Error) -> Self {
+        Self::new_file_io("", value)
+    }
+}
+
+pub type OptimaResult<T> = Result<T, OptimaError>;
@@ -0,0 +1,331 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use optima_error::OptimaError;
+use crate::path::{load_object_from_json_string, OStemCellPath};
+use crate::traits::ToJsonString;
+
+/// Bump whenever the layout of cached artifacts changes so that stale entries are never read back.
+pub const OASSET_CACHE_FORMAT_VERSION: u32 = 1;
+
+/// Identifies a derived artifact by the hash of everything it was computed from: the kind of
+/// artifact, the parameters used to compute it, and the contents of its sources.  Because the key
+/// changes whenever a source file changes, entries never need to be invalidated by hand.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OAssetCacheKey {
+    kind: String,
+    hash: String
+}
+impl OAssetCacheKey {
+    /// `kind` groups entries on disk (e.g., "convex_hull") and `params` should describe any settings
+    /// that affect the result (e.g., "max_convex_hulls=5").
+    pub fn new(kind: &str, params: &str, sources: &[&[u8]]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(OASSET_CACHE_FORMAT_VERSION.to_le_bytes());
+        hash_field(&mut hasher, kind.as_bytes());
+        hash_field(&mut hasher, params.as_bytes());
+        for source in sources { hash_field(&mut hasher, source); }
+
+        Self { kind: sanitize_kind(kind), hash: to_hex(&hasher.finalize()) }
+    }
+    pub fn new_from_files(kind: &str, params: &str, sources: &[&OStemCellPath]) -> Result<Self, OptimaError> {
+        let contents: Vec<Vec<u8>> = sources.iter().map(|x| x.read_file_contents_to_bytes()).collect::<Result<_, _>>()?;
+        let contents: Vec<&[u8]> = contents.iter().map(|x| x.as_slice()).collect();
+        Ok(Self::new(kind, params, &contents))
+    }
+    pub fn new_from_files_unchecked(kind: &str, params: &str, sources: &[&OStemCellPath]) -> Self {
+        Self::new_from_files(kind, params, sources).expect("error")
+    }
+    pub fn new_from_object<T: Serialize>(kind: &str, params: &str, source: &T) -> Self {
+        Self::new(kind, params, &[source.to_json_string().as_bytes()])
+    }
+    #[inline(always)]
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+    /// Hex encoded sha256.
+    #[inline(always)]
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+/// On-disk, content-addressed store for expensive derived data (converted meshes, convex hulls,
+/// decompositions, preprocessing results).  Each entry is stored next to a checksum of its
+/// contents, and an entry whose checksum does not match (e.g., from an interrupted write) is
+/// treated as missing and removed.
+///
+/// A cache without a root (e.g., on wasm) never stores anything, so callers do not need to special
+/// case it.
+#[derive(Clone, Debug)]
+pub struct OAssetCache {
+    root: Option<PathBuf>
+}
+impl OAssetCache {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self { root: Some(root.as_ref().to_path_buf()) }
+    }
+    /// Uses `$OPTIMA_ASSET_CACHE_DIR` if set, otherwise `optima/asset_cache` inside the user's
+    /// cache directory.
+    pub fn new_default() -> Self {
+        if cfg!(target_arch = "wasm32") { return Self::new_disabled(); }
+        if let Ok(dir) = std::env::var("OPTIMA_ASSET_CACHE_DIR") { return Self::new(dir); }
+        match dirs::cache_dir() {
+            None => { Self::new_disabled() }
+            Some(dir) => { Self::new(dir.join("optima").join("asset_cache")) }
+        }
+    }
+    pub fn new_disabled() -> Self {
+        Self { root: None }
+    }
+    #[inline(always)]
+    pub fn root(&self) -> Option<&PathBuf> {
+        self.root.as_ref()
+    }
+    #[inline(always)]
+    pub fn is_enabled(&self) -> bool {
+        self.root.is_some()
+    }
+    pub fn contains(&self, key: &OAssetCacheKey) -> bool {
+        self.get_bytes(key).is_some()
+    }
+    /// The entry's checksum is verified on every call, not just the first time it is read, so an
+    /// entry that was truncated or modified on disk is never returned.  Entries whose checksum does
+    /// not match are removed.
+    pub fn get_bytes(&self, key: &OAssetCacheKey) -> Option<Vec<u8>> {
+        let path = self.entry_path(key)?;
+        let contents = fs::read(&path).ok()?;
+        // a missing checksum means the entry is still being written (see `put_bytes`).
+        let checksum = fs::read_to_string(checksum_path(&path)).ok()?;
+        if checksum.trim() != to_hex(&Sha256::digest(&contents)) {
+            self.remove(key);
+            return None;
+        }
+        Some(contents)
+    }
+    /// The entry is written to a temporary file and renamed into place, and only then is its
+    /// checksum written (also via a rename).  A reader therefore never finds a checksum next to a
+    /// partial entry, and an entry whose checksum has not been written yet is treated as missing.
+    pub fn put_bytes(&self, key: &OAssetCacheKey, contents: &[u8]) -> Result<(), OptimaError> {
+        let path = match self.entry_path(key) {
+            None => { return Ok(()); }
+            Some(path) => { path }
+        };
+        let file_io_error = |e: std::io::Error| OptimaError::new_file_io(path.display(), e);
+        let parent = path.parent().ok_or_else(|| OptimaError::new_file_io(path.display(), "cache entry has no parent directory"))?;
+        fs::create_dir_all(parent).map_err(file_io_error)?;
+
+        let tmp_path = path.with_extension(format!("tmp{}", std::process::id()));
+        let mut f = fs::File::create(&tmp_path).map_err(file_io_error)?;
+        f.write_all(contents).map_err(file_io_error)?;
+        f.sync_all().map_err(file_io_error)?;
+        fs::rename(&tmp_path, &path).map_err(file_io_error)?;
+
+        let tmp_checksum_path = path.with_extension(format!("sha256.tmp{}", std::process::id()));
+        fs::write(&tmp_checksum_path, to_hex(&Sha256::digest(contents))).map_err(file_io_error)?;
+        fs::rename(&tmp_checksum_path, checksum_path(&path)).map_err(file_io_error)?;
+        Ok(())
+    }
+    /// An entry that passes its checksum but no longer deserializes as `T` (e.g., after the type
+    /// changed without a bump of `OASSET_CACHE_FORMAT_VERSION`) is removed and treated as missing.
+    pub fn get_object<T: DeserializeOwned>(&self, key: &OAssetCacheKey) -> Option<T> {
+        let contents = self.get_bytes(key)?;
+        let object = String::from_utf8(contents).ok().and_then(|s| load_object_from_json_string(&s).ok());
+        if object.is_none() { self.remove(key); }
+        object
+    }
+    pub fn put_object<T: Serialize>(&self, key: &OAssetCacheKey, object: &T) -> Result<(), OptimaError> {
+        self.put_bytes(key, object.to_json_string().as_bytes())
+    }
+    /// Returns the cached object if present, otherwise computes it with `f` and caches the result.
+    /// Failing to write the cache is not an error; the computed object is returned either way.
+    pub fn get_or_insert_with<T: Serialize + DeserializeOwned, F: FnOnce() -> T>(&self, key: &OAssetCacheKey, f: F) -> T {
+        if let Some(object) = self.get_object(key) { return object; }
+        let object = f();
+        let _ = self.put_object(key, &object);
+        object
+    }
+    /// Same as `get_or_insert_with`, but an error from `f` is returned and nothing is cached.
+    pub fn try_get_or_insert_with<T: Serialize + DeserializeOwned, F: FnOnce() -> Result<T, OptimaError>>(&self, key: &OAssetCacheKey, f: F) -> Result<T, OptimaError> {
+        if let Some(object) = self.get_object(key) { return Ok(object); }
+        let object = f()?;
+        let _ = self.put_object(key, &object);
+        Ok(object)
+    }
+    pub fn remove(&self, key: &OAssetCacheKey) {
+        if let Some(path) = self.entry_path(key) {
+            let _ = fs::remove_file(checksum_path(&path));
+            let _ = fs::remove_file(&path);
+        }
+    }
+    /// Removes every entry of the given kind, or all entries if `kind` is None.
+    pub fn clear(&self, kind: Option<&str>) -> Result<(), OptimaError> {
+        let dir = match (&self.root, kind) {
+            (None, _) => { return Ok(()); }
+            (Some(root), None) => { root.clone() }
+            (Some(root), Some(kind)) => { root.join(sanitize_kind(kind)) }
+        };
+        if dir.exists() { fs::remove_dir_all(&dir).map_err(|e| OptimaError::new_file_io(dir.display(), e))?; }
+        Ok(())
+    }
+    /// Total size of all entries, in bytes.
+    pub fn size_on_disk(&self) -> u64 {
+        match &self.root {
+            None => { 0 }
+            Some(root) => {
+                walkdir::WalkDir::new(root).into_iter().filter_map(|x| x.ok()).filter(|x| x.file_type().is_file()).filter_map(|x| x.metadata().ok()).map(|x| x.len()).sum()
+            }
+        }
+    }
+    fn entry_path(&self, key: &OAssetCacheKey) -> Option<PathBuf> {
+        self.root.as_ref().map(|root| root.join(&key.kind).join(&key.hash[0..2]).join(&key.hash))
+    }
+}
+impl Default for OAssetCache {
+    fn default() -> Self {
+        Self::new_default()
+    }
+}
+
+fn hash_field(hasher: &mut Sha256, field: &[u8]) {
+    hasher.update((field.len() as u64).to_le_bytes());
+    hasher.update(field);
+}
+
+fn checksum_path(entry_path: &Path) -> PathBuf {
+    entry_path.with_extension("sha256")
+}
+
+fn sanitize_kind(kind: &str) -> String {
+    kind.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' }).collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cache in a fresh directory under the system temp dir, removed again when dropped.
+    struct TempCache {
+        cache: OAssetCache
+    }
+    impl TempCache {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("optima_asset_cache_test_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            Self { cache: OAssetCache::new(root) }
+        }
+    }
+    impl Drop for TempCache {
+        fn drop(&mut self) {
+            let _ = self.cache.clear(None);
+        }
+    }
+
+    #[test]
+    fn key_depends_on_kind_params_and_sources() {
+        let a = OAssetCacheKey::new("convex_hull", "", &[b"mesh"]);
+        assert_eq!(a, OAssetCacheKey::new("convex_hull", "", &[b"mesh"]));
+        assert_ne!(a, OAssetCacheKey::new("convex_hull", "max_convex_hulls=5", &[b"mesh"]));
+        assert_ne!(a, OAssetCacheKey::new("convex_hull", "", &[b"mesh2"]));
+        assert_ne!(a, OAssetCacheKey::new("decimation", "", &[b"mesh"]));
+        // fields are length prefixed, so moving bytes between sources changes the key.
+        assert_ne!(OAssetCacheKey::new("k", "", &[b"ab", b"c"]), OAssetCacheKey::new("k", "", &[b"a", b"bc"]));
+    }
+
+    #[test]
+    fn put_then_get_round_trips() {
+        let t = TempCache::new("round_trip");
+        let key = OAssetCacheKey::new("test", "", &[b"source"]);
+        assert!(!t.cache.contains(&key));
+        t.cache.put_bytes(&key, b"derived").expect("error");
+        assert_eq!(t.cache.get_bytes(&key).expect("error"), b"derived".to_vec());
+
+        let object_key = OAssetCacheKey::new("test", "object", &[b"source"]);
+        t.cache.put_object(&object_key, &vec![1.0, 2.0, 3.0]).expect("error");
+        assert_eq!(t.cache.get_object::<Vec<f64>>(&object_key).expect("error"), vec![1.0, 2.0, 3.0]);
+        assert!(t.cache.size_on_disk() > 0);
+    }
+
+    #[test]
+    fn corrupted_entry_is_detected_on_every_load() {
+        let t = TempCache::new("corrupted");
+        let key = OAssetCacheKey::new("test", "", &[b"source"]);
+        t.cache.put_bytes(&key, b"derived").expect("error");
+        assert!(t.cache.get_bytes(&key).is_some());
+
+        // modify the entry after it has already been read once.
+        fs::write(t.cache.entry_path(&key).expect("error"), b"tampered").expect("error");
+        assert!(t.cache.get_bytes(&key).is_none());
+        // and the corrupted entry is removed.
+        assert!(!t.cache.entry_path(&key).expect("error").exists());
+    }
+
+    #[test]
+    fn entry_without_checksum_is_missing_but_kept() {
+        let t = TempCache::new("no_checksum");
+        let key = OAssetCacheKey::new("test", "", &[b"source"]);
+        t.cache.put_bytes(&key, b"derived").expect("error");
+        let path = t.cache.entry_path(&key).expect("error");
+        fs::remove_file(checksum_path(&path)).expect("error");
+
+        assert!(t.cache.get_bytes(&key).is_none());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn undeserializable_object_is_removed() {
+        let t = TempCache::new("undeserializable");
+        let key = OAssetCacheKey::new("test", "", &[b"source"]);
+        t.cache.put_bytes(&key, b"not json").expect("error");
+        assert!(t.cache.get_object::<Vec<f64>>(&key).is_none());
+        assert!(!t.cache.contains(&key));
+    }
+
+    #[test]
+    fn get_or_insert_with_only_computes_once() {
+        let t = TempCache::new("get_or_insert");
+        let key = OAssetCacheKey::new("test", "", &[b"source"]);
+        let mut num_calls = 0;
+        assert_eq!(t.cache.get_or_insert_with(&key, || { num_calls += 1; 5usize }), 5);
+        assert_eq!(t.cache.get_or_insert_with(&key, || { num_calls += 1; 6usize }), 5);
+        assert_eq!(num_calls, 1);
+    }
+
+    #[test]
+    fn try_get_or_insert_with_does_not_cache_errors() {
+        let t = TempCache::new("try_get_or_insert");
+        let key = OAssetCacheKey::new("test", "", &[b"source"]);
+        assert!(t.cache.try_get_or_insert_with::<usize, _>(&key, || Err(OptimaError::Generic("failed".to_string()))).is_err());
+        assert!(!t.cache.contains(&key));
+        assert_eq!(t.cache.try_get_or_insert_with(&key, || Ok(5usize)).expect("error"), 5);
+        assert_eq!(t.cache.try_get_or_insert_with(&key, || Ok(6usize)).expect("error"), 5);
+    }
+
+    #[test]
+    fn clear_by_kind() {
+        let t = TempCache::new("clear");
+        let a = OAssetCacheKey::new("a", "", &[b"source"]);
+        let b = OAssetCacheKey::new("b", "", &[b"source"]);
+        t.cache.put_bytes(&a, b"a").expect("error");
+        t.cache.put_bytes(&b, b"b").expect("error");
+        t.cache.clear(Some("a")).expect("error");
+        assert!(!t.cache.contains(&a));
+        assert!(t.cache.contains(&b));
+    }
+
+    #[test]
+    fn disabled_cache_stores_nothing() {
+        let cache = OAssetCache::new_disabled();
+        let key = OAssetCacheKey::new("test", "", &[b"source"]);
+        cache.put_bytes(&key, b"derived").expect("error");
+        assert!(cache.get_bytes(&key).is_none());
+        assert_eq!(cache.size_on_disk(), 0);
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use urdf_rs::Geometry;
+use optima_error::OptimaError;
+use crate::path::{load_object_from_json_string, path_buf_from_string_components, urdf_mesh_relative_path_components, OAssetLocation, OPath, OStemCellPath};
+use crate::traits::ToJsonString;
+
+/// Where to fetch a robot description from.  Mesh filenames in the urdf are resolved against
+/// `mesh_base_url`: `package://<package>/<rest>` becomes `<mesh_base_url>/<rest>`, relative paths
+/// are appended as is, and absolute http(s) urls are used directly.
+///
+/// If checksums are given (hex encoded sha256, keyed by the mesh filename as written in the urdf),
+/// downloads that do not match are rejected.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ORobotDownloadSource {
+    pub urdf_url: String,
+    pub mesh_base_url: Option<String>,
+    #[serde(default)]
+    pub urdf_sha256: Option<String>,
+    #[serde(default)]
+    pub mesh_sha256: HashMap<String, String>
+}
+impl ORobotDownloadSource {
+    pub fn new(urdf_url: &str, mesh_base_url: Option<&str>) -> Self {
+        Self { urdf_url: urdf_url.to_string(), mesh_base_url: mesh_base_url.map(|x| x.to_string()), urdf_sha256: None, mesh_sha256: HashMap::new() }
+    }
+    /// Raw file urls in a github repository at the given revision (branch, tag, or commit; a commit
+    /// is recommended so that the download is reproducible).  `package_root` is the directory in
+    /// the repository that `package://<package>/` refers to.
+    pub fn new_github(owner: &str, repo: &str, rev: &str, urdf_path: &str, package_root: &str) -> Self {
+        let base = format!("https://raw.githubusercontent.com/{}/{}/{}", owner, repo, rev);
+        Self::new(&join_url(&base, urdf_path), Some(&join_url(&base, package_root)))
+    }
+    pub fn with_urdf_sha256(mut self, sha256: &str) -> Self {
+        self.urdf_sha256 = Some(sha256.to_lowercase());
+        self
+    }
+    pub fn with_mesh_sha256(mut self, mesh_filename: &str, sha256: &str) -> Self {
+        self.mesh_sha256.insert(mesh_filename.to_string(), sha256.to_lowercase());
+        self
+    }
+    fn resolve_mesh_url(&self, mesh_filename: &str) -> Result<String, String> {
+        if mesh_filename.starts_with("http://") || mesh_filename.starts_with("https://") { return Ok(mesh_filename.to_string()); }
+        let base = self.mesh_base_url.as_ref().ok_or(format!("no mesh_base_url to resolve mesh {:?} against", mesh_filename))?;
+        let relative = match mesh_filename.strip_prefix("package://") {
+            None => { mesh_filename.trim_start_matches("file://").trim_start_matches('/') }
+            Some(rest) => { rest.split_once('/').map(|x| x.1).unwrap_or(rest) }
+        };
+        Ok(join_url(base, relative))
+    }
+}
+
+/// A named set of download sources, e.g., a json file shared by a team:
+/// `{"robots": {"ur5": {"urdf_url": "...", "mesh_base_url": "..."}}}`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ORobotRegistry {
+    pub robots: HashMap<String, ORobotDownloadSource>
+}
+impl ORobotRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn from_json_string(json_str: &str) -> Result<Self, OptimaError> {
+        load_object_from_json_string(json_str).map_err(|e| OptimaError::new_serialization::<Self, _>(e))
+    }
+    pub fn from_file(path: &OStemCellPath) -> Result<Self, OptimaError> {
+        path.as_physical_path()?.load_object_from_json_file().map_err(|e| OptimaError::new_file_io(path.to_string(), e))
+    }
+    pub fn from_url(url: &str) -> Result<Self, OptimaError> {
+        let bytes = fetch_bytes(url)?;
+        Self::from_json_string(&String::from_utf8(bytes).map_err(|e| e.to_string())?)
+    }
+    pub fn with_robot(mut self, robot_name: &str, source: ORobotDownloadSource) -> Self {
+        self.robots.insert(robot_name.to_string(), source);
+        self
+    }
+    pub fn get(&self, robot_name: &str) -> Option<&ORobotDownloadSource> {
+        self.robots.get(robot_name)
+    }
+}
+
+/// Record of a completed download, written next to the urdf as `download_manifest.json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ORobotDownloadManifest {
+    pub source: ORobotDownloadSource,
+    /// (file path relative to the robot's urdf directory, sha256) for every downloaded file.
+    pub files: Vec<(String, String)>
+}
+
+/// Downloads the urdf and every mesh it references into the robot's urdf directory in the asset
+/// folder, laid out the way `ORobot::from_urdf` expects.  Meshes go in `original_meshes`, keyed by
+/// their path in the package (see `urdf_mesh_relative_path_components`), so meshes that share a file
+/// name do not overwrite each other.  Returns the path to the saved urdf.
+///
+/// All files are fetched and verified before anything is written.  On a physical asset folder, the
+/// files are then written to a staging directory next to the robot's directory, which is renamed
+/// into place if the robot's directory does not exist yet.  If it does exist (e.g., it holds files
+/// from an earlier attempt), the staged files are moved into it one at a time with the urdf last,
+/// so `ensure_robot_downloaded` never sees a urdf without its meshes.  A failure while staging
+/// leaves the asset folder untouched.
+pub fn download_robot(robot_name: &str, source: &ORobotDownloadSource) -> Result<OStemCellPath, OptimaError> {
+    tracing::info!(robot = robot_name, url = %source.urdf_url, "downloading urdf");
+    let urdf_bytes = fetch_bytes(&source.urdf_url)?;
+    verify_sha256(&source.urdf_url, &urdf_bytes, source.urdf_sha256.as_ref())?;
+    let urdf_str = String::from_utf8(urdf_bytes.clone()).map_err(|e| e.to_string())?;
+    let urdf = urdf_rs::read_from_string(&urdf_str).map_err(|e| e.to_string())?;
+
+    let mut mesh_filenames = vec![];
+    for link in &urdf.links {
+        let geometries = link.visual.iter().map(|x| &x.geometry).chain(link.collision.iter().map(|x| &x.geometry));
+        for geometry in geometries {
+            if let Geometry::Mesh { filename, .. } = geometry {
+                if !mesh_filenames.contains(filename) { mesh_filenames.push(filename.clone()); }
+            }
+        }
+    }
+
+    // the urdf goes first here and is installed last (see `install_files`).
+    let mut files = vec![(vec![format!("{}.urdf", robot_name)], urdf_bytes)];
+    for (i, mesh_filename) in mesh_filenames.iter().enumerate() {
+        let url = source.resolve_mesh_url(mesh_filename)?;
+        tracing::info!(mesh = i + 1, num_meshes = mesh_filenames.len(), %url, "downloading mesh");
+        let bytes = fetch_bytes(&url)?;
+        verify_sha256(&url, &bytes, source.mesh_sha256.get(mesh_filename))?;
+        let mut relative_path = vec!["original_meshes".to_string()];
+        let components = urdf_mesh_relative_path_components(mesh_filename);
+        if components.is_empty() { return Err(OptimaError::InvalidInput(format!("mesh filename {:?} does not name a file", mesh_filename))); }
+        relative_path.extend(components);
+        if let Some(other) = files.iter().find(|x| x.0 == relative_path) {
+            if other.1 != bytes { return Err(OptimaError::InvalidInput(format!("two different meshes map to {}", relative_path.join("/")))); }
+            continue;
+        }
+        files.push((relative_path, bytes));
+    }
+
+    let manifest = ORobotDownloadManifest { source: source.clone(), files: files.iter().map(|(relative_path, bytes)| (relative_path.join("/"), sha256_hex(bytes))).collect() };
+    files.insert(1, (vec!["download_manifest.json".to_string()], manifest.to_json_string().into_bytes()));
+
+    let mut robot_dir = OStemCellPath::new_asset_path();
+    robot_dir.append_file_location(&OAssetLocation::UrdfRobot { robot_name });
+    match robot_dir.as_physical_path()? {
+        OPath::Path(dir) => { install_files(dir, &files)?; }
+        OPath::VfsPath(_) => {
+            // the in-memory asset folder cannot be observed half written by another process.
+            for (relative_path, bytes) in &files {
+                let mut path = robot_dir.clone();
+                path.append_vec(relative_path);
+                path.as_physical_path()?.write_bytes_to_file(bytes)?;
+            }
+        }
+    }
+
+    let mut urdf_path = robot_dir;
+    urdf_path.append(&format!("{}.urdf", robot_name));
+    tracing::info!(robot = robot_name, num_meshes = mesh_filenames.len(), "downloaded robot");
+    Ok(urdf_path)
+}
+
+/// Writes `files` (relative path components, contents) under `dir` by way of a staging directory.
+/// The first file is moved into place last.
+fn install_files(dir: &Path, files: &[(Vec<String>, Vec<u8>)]) -> Result<(), OptimaError> {
+    let dir_name = dir.file_name().ok_or_else(|| OptimaError::new_file_io(dir.display(), "robot directory has no name"))?;
+    let staging_dir = dir.with_file_name(format!(".{}.download{}", dir_name.to_string_lossy(), std::process::id()));
+    let file_io_error = |path: &Path, e: std::io::Error| OptimaError::new_file_io(path.display(), e);
+
+    if staging_dir.exists() { fs::remove_dir_all(&staging_dir).map_err(|e| file_io_error(&staging_dir, e))?; }
+    let staged = files.iter().try_for_each(|(relative_path, bytes)| {
+        let path = staging_dir.join(path_buf_from_string_components(relative_path));
+        if let Some(parent) = path.parent() { fs::create_dir_all(parent).map_err(|e| file_io_error(parent, e))?; }
+        fs::write(&path, bytes).map_err(|e| file_io_error(&path, e))
+    });
+    if let Err(e) = staged {
+        let _ = fs::remove_dir_all(&staging_dir);
+        return Err(e);
+    }
+
+    if !dir.exists() {
+        if let Some(parent) = dir.parent() { fs::create_dir_all(parent).map_err(|e| file_io_error(parent, e))?; }
+        fs::rename(&staging_dir, dir).map_err(|e| file_io_error(dir, e))?;
+        return Ok(());
+    }
+
+    for (relative_path, _) in files.iter().skip(1).chain(files.iter().take(1)) {
+        let from = staging_dir.join(path_buf_from_string_components(relative_path));
+        let to = dir.join(path_buf_from_string_components(relative_path));
+        if let Some(parent) = to.parent() { fs::create_dir_all(parent).map_err(|e| file_io_error(parent, e))?; }
+        fs::rename(&from, &to).map_err(|e| file_io_error(&to, e))?;
+    }
+    let _ = fs::remove_dir_all(&staging_dir);
+    Ok(())
+}
+
+/// Downloads the robot from the registry unless its urdf is already in the asset folder.
+pub fn ensure_robot_downloaded(robot_name: &str, registry: &ORobotRegistry) -> Result<(), OptimaError> {
+    let mut robot_dir = OStemCellPath::new_asset_path();
+    robot_dir.append_file_location(&OAssetLocation::UrdfRobot { robot_name });
+    if robot_dir.exists() && robot_dir.get_all_items_in_directory(false, false).iter().any(|x| x.ends_with(".urdf")) { return Ok(()); }
+
+    let source = registry.get(robot_name).ok_or(OptimaError::RobotNotFound { robot_name: robot_name.to_string(), message: "it is not in the asset folder or the registry".to_string() })?;
+    download_robot(robot_name, source).map(|_| ())
+}
+
+/// Re-hashes every file listed in the robot's download manifest.  Returns the files that are
+/// missing or have changed since they were downloaded.
+pub fn verify_downloaded_robot(robot_name: &str) -> Result<Vec<String>, OptimaError> {
+    let mut robot_dir = OStemCellPath::new_asset_path();
+    robot_dir.append_file_location(&OAssetLocation::UrdfRobot { robot_name });
+    let mut manifest_path = robot_dir.clone();
+    manifest_path.append("download_manifest.json");
+    let manifest: ORobotDownloadManifest = manifest_path.as_physical_path()?.load_object_from_json_file()?;
+
+    let mut out = vec![];
+    for (relative_path, sha256) in &manifest.files {
+        let mut path = robot_dir.clone();
+        relative_path.split('/').for_each(|x| path.append(x));
+        match path.as_physical_path()?.read_file_contents_to_bytes() {
+            Ok(bytes) if &sha256_hex(&bytes) == sha256 => { }
+            _ => { out.push(relative_path.clone()); }
+        }
+    }
+    Ok(out)
+}
+
+fn fetch_bytes(url: &str) -> Result<Vec<u8>, String> {
+    let response = ureq::get(url).call().map_err(|e| format!("could not download {}: {}", url, e))?;
+    read_to_end_limited(response.into_reader(), OROBOT_DOWNLOAD_MAX_FILE_SIZE, url)
+}
+
+/// Errors instead of silently truncating if the reader holds more than `max_size` bytes.
+fn read_to_end_limited<R: Read>(reader: R, max_size: u64, url: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    reader.take(max_size + 1).read_to_end(&mut bytes).map_err(|e| format!("could not download {}: {}", url, e))?;
+    if bytes.len() as u64 > max_size { return Err(format!("could not download {}: it is larger than the {} byte limit", url, max_size)); }
+    Ok(bytes)
+}
+
+fn verify_sha256(url: &str, bytes: &[u8], expected: Option<&String>) -> Result<(), String> {
+    if let Some(expected) = expected {
+        let actual = sha256_hex(bytes);
+        if &actual != expected { return Err(format!("checksum mismatch for {}: expected {}, got {}", url, expected, actual)); }
+    }
+    Ok(())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn join_url(base: &str, relative: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), relative.trim_start_matches('/'))
+}
+
+const OROBOT_DOWNLOAD_MAX_FILE_SIZE: u64 = 512 * 1024 * 1024;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::path::PathBuf;
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let out = std::env::temp_dir().join(format!("optima_download_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&out);
+        out
+    }
+
+    #[test]
+    fn mesh_urls_resolve_against_the_base_url() {
+        let source = ORobotDownloadSource::new("https://example.com/ur5.urdf", Some("https://example.com/ur_description/"));
+        assert_eq!(source.resolve_mesh_url("package://ur_description/meshes/base.dae").expect("error"), "https://example.com/ur_description/meshes/base.dae");
+        assert_eq!(source.resolve_mesh_url("meshes/base.dae").expect("error"), "https://example.com/ur_description/meshes/base.dae");
+        assert_eq!(source.resolve_mesh_url("https://other.com/base.dae").expect("error"), "https://other.com/base.dae");
+        assert!(ORobotDownloadSource::new("https://example.com/ur5.urdf", None).resolve_mesh_url("meshes/base.dae").is_err());
+    }
+
+    #[test]
+    fn mesh_relative_paths_keep_directories_and_stay_inside() {
+        assert_eq!(urdf_mesh_relative_path_components("package://ur_description/meshes/visual/base.dae"), vec!["meshes", "visual", "base.dae"]);
+        assert_eq!(urdf_mesh_relative_path_components("https://example.com/meshes/base.dae"), vec!["meshes", "base.dae"]);
+        assert_eq!(urdf_mesh_relative_path_components("file:///meshes/base.dae"), vec!["meshes", "base.dae"]);
+        assert_eq!(urdf_mesh_relative_path_components("package://pkg/../../etc/./passwd"), vec!["etc", "passwd"]);
+        assert_ne!(urdf_mesh_relative_path_components("package://pkg/visual/link.dae"), urdf_mesh_relative_path_components("package://pkg/collision/link.dae"));
+    }
+
+    #[test]
+    fn oversized_downloads_are_rejected() {
+        assert_eq!(read_to_end_limited(Cursor::new(vec![1u8; 8]), 8, "url").expect("error").len(), 8);
+        assert!(read_to_end_limited(Cursor::new(vec![1u8; 9]), 8, "url").is_err());
+    }
+
+    #[test]
+    fn checksums_are_verified_when_given() {
+        let expected = sha256_hex(b"mesh");
+        assert!(verify_sha256("url", b"mesh", Some(&expected)).is_ok());
+        assert!(verify_sha256("url", b"other", Some(&expected)).is_err());
+        assert!(verify_sha256("url", b"other", None).is_ok());
+    }
+
+    #[test]
+    fn install_files_into_a_new_directory() {
+        let dir = temp_dir("new").join("ur5");
+        let files = vec![(vec!["ur5.urdf".to_string()], b"urdf".to_vec()), (vec!["original_meshes".to_string(), "visual".to_string(), "link.dae".to_string()], b"visual".to_vec()), (vec!["original_meshes".to_string(), "collision".to_string(), "link.dae".to_string()], b"collision".to_vec())];
+        install_files(&dir, &files).expect("error");
+
+        assert_eq!(fs::read(dir.join("ur5.urdf")).expect("error"), b"urdf");
+        assert_eq!(fs::read(dir.join("original_meshes/visual/link.dae")).expect("error"), b"visual");
+        assert_eq!(fs::read(dir.join("original_meshes/collision/link.dae")).expect("error"), b"collision");
+        // nothing is left behind next to the robot's directory.
+        assert_eq!(fs::read_dir(dir.parent().expect("error")).expect("error").count(), 1);
+        let _ = fs::remove_dir_all(dir.parent().expect("error"));
+    }
+
+    #[test]
+    fn install_files_into_an_existing_directory() {
+        let dir = temp_dir("existing").join("ur5");
+        fs::create_dir_all(&dir).expect("error");
+        fs::write(dir.join("notes.txt"), b"keep me").expect("error");
+        let files = vec![(vec!["ur5.urdf".to_string()], b"urdf".to_vec()), (vec!["original_meshes".to_string(), "base.dae".to_string()], b"mesh".to_vec())];
+        install_files(&dir, &files).expect("error");
+
+        assert_eq!(fs::read(dir.join("ur5.urdf")).expect("error"), b"urdf");
+        assert_eq!(fs::read(dir.join("original_meshes/base.dae")).expect("error"), b"mesh");
+        assert_eq!(fs::read(dir.join("notes.txt")).expect("error"), b"keep me");
+        assert_eq!(fs::read_dir(dir.parent().expect("error")).expect("error").count(), 1);
+        let _ = fs::remove_dir_all(dir.parent().expect("error"));
+    }
+
+    #[test]
+    fn registry_round_trips_through_json() {
+        let registry = ORobotRegistry::new().with_robot("ur5", ORobotDownloadSource::new_github("owner", "repo", "abc123", "urdf/ur5.urdf", "ur_description").with_urdf_sha256("ABC"));
+        let loaded = ORobotRegistry::from_json_string(&registry.to_json_string()).expect("error");
+        let source = loaded.get("ur5").expect("error");
+        assert_eq!(source.urdf_url, "https://raw.githubusercontent.com/owner/repo/abc123/urdf/ur5.urdf");
+        assert_eq!(source.urdf_sha256.as_deref(), Some("abc"));
+        assert!(ORobotRegistry::from_json_string("not json").is_err());
+    }
+}
@@ -1,2 +1,8 @@
 pub mod path;
 pub mod traits;
+pub mod cache;
+pub mod virtual_assets;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod download;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod watch;
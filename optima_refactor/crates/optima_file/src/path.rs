@@ -1,7 +1,8 @@
 use std::fs;
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 use std::str::FromStr;
 use dae_parser::Document;
 use vfs::*;
@@ -12,7 +13,8 @@ use serde::de::DeserializeOwned;
 use stl_io::IndexedMesh;
 use walkdir::WalkDir;
 use crate::traits::{ToJsonString};
-use optima_console::output::{oprint_full, PrintColor, PrintMode};
+use crate::virtual_assets::OVirtualAssets;
+use optima_error::OptimaError;
 use urdf_rs::Robot;
 
 /// excludes have higher priority than includes.  Includes work based on union of sets, so if you use
@@ -84,6 +86,8 @@ impl OStemCellPath {
             if let Err(p) = &p_res2 { error_strings.push(p.clone()); }
         }
 
+        if !OVirtualAssets::is_empty() { optima_file_paths.push(OPath::new_asset_in_memory_path()); }
+
         if optima_file_paths.len() == 0 {
             panic!("OptimaStemCellPath has zero valid paths for the following reasons: \n{:?}", error_strings);
         }
@@ -92,6 +96,12 @@ impl OStemCellPath {
             optima_file_paths
         }
     }
+    /// A stem cell path that only tries the given path (e.g., a file outside the asset folder).
+    pub fn new_from_path(path: OPath) -> Self {
+        Self {
+            optima_file_paths: vec![path]
+        }
+    }
     pub fn new_asset_path_from_string_components(components: &Vec<String>) -> Self {
         let mut out_path = Self::new_asset_path();
         for s in components { out_path.append(s); }
@@ -110,18 +120,33 @@ impl OStemCellPath {
             p.append_file_location(location);
         }
     }
-    pub fn read_file_contents_to_string(&self) -> String {
+    pub fn read_file_contents_to_string(&self) -> Result<String, OptimaError> {
         self.try_function_on_all_optima_file_paths(OPath::read_file_contents_to_string, "read_file_contents_to_string")
     }
-    pub fn write_string_to_file(&self, s: &String) {
+    pub fn read_file_contents_to_string_unchecked(&self) -> String {
+        self.read_file_contents_to_string().expect("error")
+    }
+    pub fn read_file_contents_to_bytes(&self) -> Result<Vec<u8>, OptimaError> {
+        self.try_function_on_all_optima_file_paths(OPath::read_file_contents_to_bytes, "read_file_contents_to_bytes")
+    }
+    pub fn read_file_contents_to_bytes_unchecked(&self) -> Vec<u8> {
+        self.read_file_contents_to_bytes().expect("error")
+    }
+    pub fn write_string_to_file(&self, s: &String) -> Result<(), OptimaError> {
         self.try_function_on_all_optima_file_paths_with_one_param(OPath::write_string_to_file, s, "write_string_to_file")
     }
+    pub fn write_string_to_file_unchecked(&self, s: &String) {
+        self.write_string_to_file(s).expect("error")
+    }
     pub fn exists(&self) -> bool {
-        return self.optima_file_paths[0].exists();
+        return self.optima_file_paths.iter().any(|x| x.exists());
     }
-    pub fn get_file_for_writing(&self) -> File {
+    pub fn get_file_for_writing(&self) -> Result<File, OptimaError> {
         self.try_function_on_all_optima_file_paths(OPath::get_file_for_writing, "get_file_for_writing")
     }
+    pub fn get_file_for_writing_unchecked(&self) -> File {
+        self.get_file_for_writing().expect("error")
+    }
     pub fn to_string(&self) -> String {
         return self.optima_file_paths[0].to_string();
     }
@@ -148,18 +173,30 @@ impl OStemCellPath {
     pub fn split_path_into_string_components_back_to_given_dir(&self, dir: &str) -> Vec<String> {
         return self.optima_file_paths[0].split_path_into_string_components_back_to_given_dir(dir);
     }
-    pub fn delete_file(&self) {
+    pub fn delete_file(&self) -> Result<(), OptimaError> {
         self.try_function_on_all_optima_file_paths(OPath::delete_file, "delete_file")
     }
-    pub fn delete_all_items_in_directory(&self) {
+    pub fn delete_file_unchecked(&self) {
+        self.delete_file().expect("error")
+    }
+    pub fn delete_all_items_in_directory(&self) -> Result<(), OptimaError> {
         self.try_function_on_all_optima_file_paths(OPath::delete_all_items_in_directory, "delete_all_items_in_directory")
     }
-    pub fn copy_file_to_destination(&self, destination: &OPath) {
+    pub fn delete_all_items_in_directory_unchecked(&self) {
+        self.delete_all_items_in_directory().expect("error")
+    }
+    pub fn copy_file_to_destination(&self, destination: &OPath) -> Result<(), OptimaError> {
         self.try_function_on_all_optima_file_paths_with_one_param(OPath::copy_file_to_destination, destination, "copy_file_to_destination")
     }
-    pub fn verify_extension(&self, extensions: &Vec<&str>) {
+    pub fn copy_file_to_destination_unchecked(&self, destination: &OPath) {
+        self.copy_file_to_destination(destination).expect("error")
+    }
+    pub fn verify_extension(&self, extensions: &Vec<&str>) -> Result<(), OptimaError> {
         self.try_function_on_all_optima_file_paths_with_one_param(OPath::verify_extension, extensions, "verify_extension")
     }
+    pub fn verify_extension_unchecked(&self, extensions: &Vec<&str>) {
+        self.verify_extension(extensions).expect("error")
+    }
     pub fn get_all_items_in_directory(&self, include_directories: bool, include_hidden_files: bool) -> Vec<String> {
         for p in &self.optima_file_paths {
             let items = p.get_all_items_in_directory(include_directories, include_hidden_files);
@@ -216,12 +253,30 @@ impl OStemCellPath {
 
         out
     }
-    pub fn save_object_to_file_as_json<T: Serialize + DeserializeOwned>(&self, object: &T) {
+    pub fn save_object_to_file_as_json<T: Serialize + DeserializeOwned>(&self, object: &T) -> Result<(), OptimaError> {
         self.try_function_on_all_optima_file_paths_with_one_param(OPath::save_object_to_file_as_json, object, "save_object_to_file_as_json")
     }
-    pub fn load_object_from_json_file<T: DeserializeOwned>(&self) -> T {
+    pub fn save_object_to_file_as_json_unchecked<T: Serialize + DeserializeOwned>(&self, object: &T) {
+        self.save_object_to_file_as_json(object).expect("error")
+    }
+    pub fn load_object_from_json_file<T: DeserializeOwned>(&self) -> Result<T, OptimaError> {
         self.try_function_on_all_optima_file_paths(OPath::load_object_from_json_file, "load_object_from_json_file")
     }
+    pub fn load_object_from_json_file_unchecked<T: DeserializeOwned>(&self) -> T {
+        self.load_object_from_json_file().expect("error")
+    }
+    pub fn save_object_to_file<T: Serialize + DeserializeOwned>(&self, object: &T, format: OSaveFormat) -> Result<(), OptimaError> {
+        self.as_physical_path()?.save_object_to_file(object, format).map_err(|e| OptimaError::new_file_io(self.to_string(), e))
+    }
+    pub fn save_object_to_file_unchecked<T: Serialize + DeserializeOwned>(&self, object: &T, format: OSaveFormat) {
+        self.save_object_to_file(object, format).expect("error")
+    }
+    pub fn load_object_from_file<T: DeserializeOwned>(&self) -> Result<T, OptimaError> {
+        self.try_function_on_all_optima_file_paths(OPath::load_object_from_file, "load_object_from_file")
+    }
+    pub fn load_object_from_file_unchecked<T: DeserializeOwned>(&self) -> T {
+        self.load_object_from_file().expect("error")
+    }
     pub fn walk_directory_and_match(&self, pattern: OPathMatchingPattern, stop_condition: OPathMatchingStopCondition) -> Vec<OPath> {
         for p in &self.optima_file_paths {
             let res = p.walk_directory_and_match(pattern.clone(), stop_condition.clone());
@@ -229,16 +284,16 @@ impl OStemCellPath {
         }
         return vec![];
     }
-    pub fn try_function_on_all_optima_file_paths<T>(&self, f: fn(&OPath) -> Result<T, String>, function_name: &str) -> T {
+    pub fn try_function_on_all_optima_file_paths<T>(&self, f: fn(&OPath) -> Result<T, String>, function_name: &str) -> Result<T, OptimaError> {
         let mut error_strings = vec![];
         for p in &self.optima_file_paths {
             let res = f(p);
             match res {
-                Ok(a) => { return a }
+                Ok(a) => { return Ok(a) }
                 Err(s) => { error_strings.push(s) }
             }
         }
-        panic!("No valid optima_path {:?} in function {:?} with error strings {:?}", self, function_name, error_strings);
+        Err(OptimaError::new_file_io(self.to_string(), format!("no valid optima_path in function {:?} with error strings {:?}", function_name, error_strings)))
     }
     pub fn try_function_on_all_optima_file_paths_return_option<T>(&self, f: fn(&OPath) -> Result<T, String>) -> Option<T> {
         for p in &self.optima_file_paths {
@@ -250,54 +305,81 @@ impl OStemCellPath {
         }
         None
     }
-    pub fn try_function_on_all_optima_file_paths_with_one_param<T, P>(&self, f: fn(&OPath, &P) -> Result<T, String>, param: &P, function_name: &str) -> T {
+    pub fn try_function_on_all_optima_file_paths_with_one_param<T, P>(&self, f: fn(&OPath, &P) -> Result<T, String>, param: &P, function_name: &str) -> Result<T, OptimaError> {
         let mut error_strings = vec![];
         for p in &self.optima_file_paths {
             let res = f(p, param);
             match res {
-                Ok(a) => { return a }
+                Ok(a) => { return Ok(a) }
                 Err(s) => { error_strings.push(s) }
             }
         }
-        panic!("No valid optima_path in function {:?} with error strings {:?}", function_name, error_strings);
+        Err(OptimaError::new_file_io(self.to_string(), format!("no valid optima_path in function {:?} with error strings {:?}", function_name, error_strings)))
     }
     pub fn optima_file_paths(&self) -> &Vec<OPath> {
         &self.optima_file_paths
     }
-    pub fn as_physical_path(&self) -> &OPath {
+    pub fn as_physical_path(&self) -> Result<&OPath, OptimaError> {
         for x in self.optima_file_paths() {
             match x {
-                OPath::Path(_) => { return x; }
+                OPath::Path(_) => { return Ok(x); }
                 OPath::VfsPath(_) => {}
             }
         }
-        panic!("physical path not found");
+        Err(OptimaError::new_file_io(self.to_string(), "physical path not found"))
     }
-    pub fn as_virtual_path(&self) -> &OPath {
+    pub fn as_physical_path_unchecked(&self) -> &OPath {
+        self.as_physical_path().expect("error")
+    }
+    pub fn as_virtual_path(&self) -> Result<&OPath, OptimaError> {
         for x in self.optima_file_paths() {
             match x {
                 OPath::Path(_) => { }
-                OPath::VfsPath(_) => { return x; }
+                OPath::VfsPath(_) => { return Ok(x); }
             }
         }
-        panic!("virtual path not found");
+        Err(OptimaError::new_file_io(self.to_string(), "virtual path not found"))
+    }
+    pub fn as_virtual_path_unchecked(&self) -> &OPath {
+        self.as_virtual_path().expect("error")
     }
 }
 impl OStemCellPath {
-    pub fn load_urdf(&self) -> Robot {
+    pub fn load_urdf(&self) -> Result<Robot, OptimaError> {
         return self.try_function_on_all_optima_file_paths(OPath::load_urdf, "load_urdf");
     }
+    pub fn load_urdf_unchecked(&self) -> Robot {
+        self.load_urdf().expect("error")
+    }
     /*
     pub fn load_dae(&self) -> Scene {
         return self.try_function_on_all_optima_file_paths(OPath::load_dae, "load_dae");
     }
     */
-    pub fn load_dae(&self) -> Document {
+    pub fn load_dae(&self) -> Result<Document, OptimaError> {
         return self.try_function_on_all_optima_file_paths(OPath::load_dae, "load_dae");
     }
-    pub fn load_stl(&self) -> IndexedMesh {
+    pub fn load_dae_unchecked(&self) -> Document {
+        self.load_dae().expect("error")
+    }
+    pub fn load_stl(&self) -> Result<IndexedMesh, OptimaError> {
         return self.try_function_on_all_optima_file_paths(OPath::load_stl, "load_stl");
     }
+    pub fn load_stl_unchecked(&self) -> IndexedMesh {
+        self.load_stl().expect("error")
+    }
+    pub fn load_mesh_scene(&self) -> Result<mesh_loader::Scene, OptimaError> {
+        return self.try_function_on_all_optima_file_paths(OPath::load_mesh_scene, "load_mesh_scene");
+    }
+    pub fn load_mesh_scene_unchecked(&self) -> mesh_loader::Scene {
+        self.load_mesh_scene().expect("error")
+    }
+    pub fn load_gltf(&self) -> Result<OGltfImport, OptimaError> {
+        return self.try_function_on_all_optima_file_paths(OPath::load_gltf, "load_gltf");
+    }
+    pub fn load_gltf_unchecked(&self) -> OGltfImport {
+        self.load_gltf().expect("error")
+    }
 }
 
 impl Serialize for OStemCellPath {
@@ -339,10 +421,10 @@ impl<'de> Deserialize<'de> for OStemCellPath {
 /// let d = p.load_dae();
 /// println!("{:?}", d);
 /// ```
-/// Note that the virtual file system (VfsPath) variant does not support any
-/// writing operations; if tried, an `UnsupportedOperationError` will be returned.  Also, in order
-/// for the VFS options to work, the --no-default-features flag must be used at compile time (because
-/// the do_not_embed_assets feature is on by default).
+/// Note that the embedded virtual file system (VfsPath) does not support any writing operations;
+/// if tried, an error will be returned.  The in-memory asset folder (see `OVirtualAssets`) does
+/// support writes.  Also, in order for the embedded VFS options to work, the --no-default-features
+/// flag must be used at compile time (because the do_not_embed_assets feature is on by default).
 #[derive(Clone, Debug)]
 pub enum OPath {
     Path(PathBuf),
@@ -354,20 +436,85 @@ impl OPath {
 
         Self::Path(dirs::home_dir().unwrap().to_path_buf())
     }
+    /// Resolves the physical asset folder.  In order of priority: `OPath::set_asset_root`, the
+    /// `OPTIMA_ASSET_ROOT` environment variable, an `optima_asset_path.JSON` file in the current
+    /// working directory (relative paths are taken relative to that directory), and finally
+    /// `~/.optima_asset_path.JSON`, which is created by searching the home directory if needed.
+    /// The error lists every location that was tried.
     pub fn new_asset_physical_path_from_json_file() -> Result<Self, String> {
         if cfg!(target_arch = "wasm32") { return Err("Not supported by wasm32.".to_string()) }
 
+        let mut searched = vec![];
+
+        if let Some(root) = OASSET_ROOT_OVERRIDE.read().expect("error").clone() {
+            if root.is_dir() { return Ok(Self::Path(root)); }
+            searched.push(format!("OPath::set_asset_root: {:?} is not a directory", root));
+        }
+
+        if let Ok(root) = std::env::var(OPTIMA_ASSET_ROOT_ENV_VAR) {
+            let root = PathBuf::from(root);
+            if root.is_dir() { return Ok(Self::Path(root)); }
+            searched.push(format!("${}: {:?} is not a directory", OPTIMA_ASSET_ROOT_ENV_VAR, root));
+        }
+
+        if let Ok(cwd) = std::env::current_dir() {
+            let config_path = Self::Path(cwd.join("optima_asset_path.JSON"));
+            if config_path.exists() {
+                match config_path.load_object_from_json_file::<PathToAssetsDir>() {
+                    Ok(path_to_assets_dir) => {
+                        let root = cwd.join(path_to_assets_dir.path_to_assets_dir);
+                        if root.is_dir() { return Ok(Self::Path(root)); }
+                        searched.push(format!("{:?}: {:?} is not a directory", config_path, root));
+                    }
+                    Err(e) => { searched.push(format!("{:?}: could not be read ({})", config_path, e)); }
+                }
+            } else {
+                searched.push(format!("{:?}: not found", config_path));
+            }
+        }
+
+        if dirs::home_dir().is_none() {
+            searched.push("~/.optima_asset_path.JSON: no home directory".to_string());
+        } else {
+            match Self::new_asset_physical_path_from_home_json_file() {
+                Ok(p) => { return Ok(p); }
+                Err(e) => { searched.push(format!("~/.optima_asset_path.JSON: {}", e)); }
+            }
+        }
+
+        Err(format!("optima_assets folder not found.  Set it with OPath::set_asset_root or ${}.  Searched:\n  {}", OPTIMA_ASSET_ROOT_ENV_VAR, searched.join("\n  ")))
+    }
+    /// Overrides the physical asset folder for the rest of the process (see
+    /// `new_asset_physical_path_from_json_file`).
+    pub fn set_asset_root<P: AsRef<Path>>(path: P) -> Result<(), String> {
+        let path = path.as_ref().to_path_buf();
+        if !path.is_dir() { return Err(format!("{:?} is not a directory", path)); }
+        *OASSET_ROOT_OVERRIDE.write().expect("error") = Some(path);
+        Ok(())
+    }
+    pub fn clear_asset_root() {
+        *OASSET_ROOT_OVERRIDE.write().expect("error") = None;
+    }
+    /// The physical asset folder that asset paths currently resolve to.
+    pub fn asset_root() -> Result<PathBuf, String> {
+        match Self::new_asset_physical_path_from_json_file()? {
+            OPath::Path(p) => { Ok(p) }
+            OPath::VfsPath(_) => { unreachable!() }
+        }
+    }
+    fn new_asset_physical_path_from_home_json_file() -> Result<Self, String> {
         let mut check_path = Self::new_home_path();
         check_path.append(".optima_asset_path.JSON");
         if check_path.exists() {
             let path_to_assets_dir_res = check_path.load_object_from_json_file::<PathToAssetsDir>();
             return match path_to_assets_dir_res {
                 Ok(path_to_asset_dir) => {
+                    if !path_to_asset_dir.path_to_assets_dir.is_dir() { return Err(format!("{:?} points to {:?}, which no longer exists.  Delete it to search again.", check_path, path_to_asset_dir.path_to_assets_dir)); }
                     Ok(Self::Path(path_to_asset_dir.path_to_assets_dir))
                 }
                 Err(_) => {
                     let found = Self::auto_create_optima_asset_path_json_file();
-                    if !found { Err("optima_asset folder not found on computer.".to_string()) } else { Self::new_asset_physical_path_from_json_file() }
+                    if !found { Err("optima_asset folder not found on computer.".to_string()) } else { Self::new_asset_physical_path_from_home_json_file() }
                 }
             }
         } else {
@@ -381,7 +528,7 @@ impl OPath {
             return if !found {
                 // panic!("optima_asset folder not found on computer.")
                 Err("optima_asset folder not found on computer.".to_string())
-            } else { Self::new_asset_physical_path_from_json_file() }
+            } else { Self::new_asset_physical_path_from_home_json_file() }
         }
     }
     #[cfg(not(feature = "do_not_embed_assets"))]
@@ -390,6 +537,10 @@ impl OPath {
         let root_path = VfsPath::new(e);
         return Ok(Self::VfsPath(root_path));
     }
+    /// Root of the in-memory asset folder (see `OVirtualAssets`).
+    pub fn new_asset_in_memory_path() -> Self {
+        Self::VfsPath(OVirtualAssets::root())
+    }
     pub fn new_asset_physical_path_from_string_components(components: &Vec<String>) -> Self {
         if cfg!(target_arch = "wasm32") { panic!("Not supported by wasm32.") }
 
@@ -465,7 +616,24 @@ impl OPath {
             }
         }
     }
+    pub fn read_file_contents_to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut contents = vec![];
+        match self {
+            OPath::Path(p) => {
+                let mut f = File::open(p).map_err(|e| e.to_string())?;
+                f.read_to_end(&mut contents).map_err(|e| e.to_string())?;
+            }
+            OPath::VfsPath(p) => {
+                let mut f = p.open_file().map_err(|e| e.to_string())?;
+                f.read_to_end(&mut contents).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(contents)
+    }
     pub fn write_string_to_file(&self, s: &String) -> Result<(), String> {
+        self.write_bytes_to_file(s.as_bytes())
+    }
+    pub fn write_bytes_to_file(&self, bytes: &[u8]) -> Result<(), String> {
         match self {
             OPath::Path(p) => {
                 let parent_option = p.parent();
@@ -487,7 +655,7 @@ impl OPath {
 
                 match &mut file_res {
                     Ok(f) => {
-                        f.write(s.as_bytes()).expect("error");
+                        f.write_all(bytes).expect("error");
                         Ok(())
                     }
                     Err(e) => {
@@ -496,9 +664,12 @@ impl OPath {
                     }
                 }
             }
-            OPath::VfsPath(_) => {
-                // panic!("Writing is not supported by VfsPath.  Try using a Path variant instead.");
-                Err("Writing is not supported by VfsPath.  Try using a Path variant instead.".to_string())
+            OPath::VfsPath(p) => {
+                // only the in-memory asset folder accepts writes; the embedded one returns an error here.
+                p.parent().create_dir_all().map_err(|e| e.to_string())?;
+                let mut f = p.create_file().map_err(|e| e.to_string())?;
+                f.write_all(bytes).map_err(|e| e.to_string())?;
+                Ok(())
             }
         }
     }
@@ -608,6 +779,38 @@ impl OPath {
         let contents = self.read_file_contents_to_string()?;
         return load_object_from_json_string::<T>(&contents);
     }
+    pub fn save_object_to_file_as_binary<T: Serialize>(&self, object: &T) -> Result<(), String> {
+        self.write_bytes_to_file(&object_to_binary_bytes(object)?)
+    }
+    pub fn save_object_to_file<T: Serialize + DeserializeOwned>(&self, object: &T, format: OSaveFormat) -> Result<(), String> {
+        match format {
+            OSaveFormat::Json => { self.save_object_to_file_as_json(object) }
+            OSaveFormat::Ron => { self.write_string_to_file(&ron::to_string(object).map_err(|e| e.to_string())?) }
+            OSaveFormat::Yaml => { self.write_string_to_file(&serde_yaml::to_string(object).map_err(|e| e.to_string())?) }
+            OSaveFormat::Toml => { self.write_string_to_file(&toml::to_string(object).map_err(|e| e.to_string())?) }
+            OSaveFormat::Binary => { self.save_object_to_file_as_binary(object) }
+        }
+    }
+    /// Detects the format of the file (binary, json, or ron) from its contents.  Files with a .yaml,
+    /// .yml, or .toml extension are read as yaml or toml.
+    pub fn load_object_from_file<T: DeserializeOwned>(&self) -> Result<T, String> {
+        let contents = self.read_file_contents_to_bytes()?;
+        let format = match self.extension().and_then(|x| OSaveFormat::from_extension(&x)) {
+            Some(format) if format == OSaveFormat::Yaml || format == OSaveFormat::Toml => { format }
+            _ => { OSaveFormat::detect(&contents) }
+        };
+        match format {
+            OSaveFormat::Binary => { load_object_from_binary_bytes(&contents) }
+            OSaveFormat::Yaml => { load_object_from_yaml_string(&String::from_utf8(contents).map_err(|e| e.to_string())?) }
+            OSaveFormat::Toml => { load_object_from_toml_string(&String::from_utf8(contents).map_err(|e| e.to_string())?) }
+            format => {
+                let s = String::from_utf8(contents).map_err(|e| e.to_string())?;
+                // ron lists and json arrays look alike, so fall back on the other text format.
+                if format == OSaveFormat::Json { load_object_from_json_string(&s).or_else(|e| load_object_from_ron_string(&s).map_err(|_| e)) }
+                else { load_object_from_ron_string(&s).or_else(|e| load_object_from_json_string(&s).map_err(|_| e)) }
+            }
+        }
+    }
     pub fn walk_directory_and_match(&self, pattern: OPathMatchingPattern, stop_condition: OPathMatchingStopCondition) -> Vec<OPath> {
         let mut out_vec = vec![];
 
@@ -683,7 +886,14 @@ impl OPath {
     }
     pub fn split_path_into_string_components_back_to_asset_dir(&self) -> Vec<String> {
         return match self {
-            OPath::Path(_) => {
+            OPath::Path(p) => {
+                // the asset folder does not have to be named optima_assets if it was configured.
+                if let Ok(root) = Self::asset_root() {
+                    if let Ok(relative) = p.strip_prefix(&root) {
+                        return relative.iter().map(|x| x.to_str().unwrap().to_string()).collect();
+                    }
+                }
+
                 let string_components = self.split_path_into_string_components();
                 let mut optima_assets_idx: Option<usize> = None;
                 for (i, s) in string_components.iter().enumerate() {
@@ -989,16 +1199,14 @@ impl OPath {
         return matched;
     }
     fn auto_create_optima_asset_path_json_file() -> bool {
-        oprint_full("Searching for Optima assets folder...", PrintMode::Println, PrintColor::Cyan, true, 0, None, vec![]);
+        tracing::info!("searching for the optima_assets folder");
         let mut home_dir = Self::new_home_path();
         let walk_vec = home_dir.walk_directory_and_match(OPathMatchingPattern::FileOrDirName("optima_assets".to_string()), OPathMatchingStopCondition::All);
         return if walk_vec.is_empty() {
-            oprint_full("WARNING: optima_assets folder not found on your computer.", PrintMode::Println, PrintColor::Yellow, true, 0, None, vec![]);
             let mut lock_path = Self::new_home_path();
             lock_path.append(".optima_asset_path.lock");
             lock_path.write_string_to_file(&"".to_string()).expect("error");
-            oprint_full(&format!("Adding a lock file here: {:?}", lock_path), PrintMode::Println, PrintColor::Yellow, true, 0, None, vec![]);
-            oprint_full(&format!("If you would like to use a local optima_assets directory on your computer, please delete the lock file once the assets directory is on your computer"), PrintMode::Println, PrintColor::Yellow, true, 0, None, vec![]);
+            tracing::warn!(lock_file = ?lock_path, "optima_assets folder not found; added a lock file.  To use a local optima_assets folder, delete the lock file once the folder is on your computer");
             false
         } else {
             let mut found_path = walk_vec[0].clone();
@@ -1013,9 +1221,8 @@ impl OPath {
 
             match &found_path {
                 OPath::Path(p) => {
-                    oprint_full(&format!("Optima assets folder found at {:?}", p), PrintMode::Println, PrintColor::Green, true, 0, None, vec![]);
                     home_dir.append(".optima_asset_path.JSON");
-                    oprint_full(&format!("Saved found path at {:?}", home_dir), PrintMode::Println, PrintColor::Green, true, 0, None, vec![]);
+                    tracing::info!(assets = ?p, saved_to = ?home_dir, "found the optima_assets folder");
                     let path_to_assets_dir = PathToAssetsDir { path_to_assets_dir: p.clone() };
                     home_dir.save_object_to_file_as_json(&path_to_assets_dir).expect("error");
                     true
@@ -1046,6 +1253,32 @@ impl OPath {
         let contents = self.read_file_contents_to_string()?;
         Ok( Document::from_str(&contents).expect(&format!("there was an error loading dae. {:?}", self)) )
     }
+    /// Loads an obj, dae, or stl file as a list of meshes along with their materials.  Material
+    /// libraries referenced by obj files are looked up next to the file, so they are only found for
+    /// physical paths.
+    pub fn load_mesh_scene(&self) -> Result<mesh_loader::Scene, String> {
+        self.verify_extension(&vec!["obj", "OBJ", "dae", "DAE", "stl", "STL"])?;
+        let loader = mesh_loader::Loader::default();
+        let res = match self {
+            OPath::Path(p) => { loader.load(p) }
+            OPath::VfsPath(p) => {
+                let bytes = self.read_file_contents_to_bytes()?;
+                loader.load_from_slice(&bytes, p.as_str())
+            }
+        };
+        res.map_err(|e| format!("there was an error loading mesh scene {:?}: {}", self, e))
+    }
+    /// Loads a gltf or glb file along with its buffers and images.  Buffers and images kept in
+    /// separate files are looked up next to the file, so they are only found for physical paths.
+    pub fn load_gltf(&self) -> Result<OGltfImport, String> {
+        self.verify_extension(&vec!["gltf", "GLTF", "glb", "GLB"])?;
+        let res = match self {
+            OPath::Path(p) => { gltf::import(p) }
+            OPath::VfsPath(_) => { gltf::import_slice(&self.read_file_contents_to_bytes()?) }
+        };
+        let (document, buffers, images) = res.map_err(|e| format!("there was an error loading gltf {:?}: {}", self, e))?;
+        Ok(OGltfImport { document, buffers, images })
+    }
     pub fn load_stl(&self) -> Result<IndexedMesh, String> {
         self.verify_extension(&vec!["stl", "STL"])?;
         return match self {
@@ -1105,12 +1338,120 @@ pub fn load_object_from_ron_string<T: DeserializeOwned>(ron_str: &str) -> Result
     }
 }
 
+/// Loads an object that implements the `Deserialize` trait from a deserialized yaml string.
+pub fn load_object_from_yaml_string<T: DeserializeOwned>(yaml_str: &str) -> Result<T, String> {
+    serde_yaml::from_str::<T>(yaml_str).map_err(|e| e.to_string())
+}
+
+/// Loads an object that implements the `Deserialize` trait from a deserialized toml string.
+pub fn load_object_from_toml_string<T: DeserializeOwned>(toml_str: &str) -> Result<T, String> {
+    toml::from_str::<T>(toml_str).map_err(|e| e.to_string())
+}
+
+const OBINARY_MAGIC: &[u8; 8] = b"OPTIMAB\x01";
+
+/// Serializes an object into Optima's binary format (see `ToBinaryBytes`).
+pub fn object_to_binary_bytes<T: Serialize + ?Sized>(object: &T) -> Result<Vec<u8>, String> {
+    let packed = rmp_serde::to_vec_named(object).map_err(|e| e.to_string())?;
+    let mut out = OBINARY_MAGIC.to_vec();
+    out.extend(compress_bytes(&packed)?);
+    Ok(out)
+}
+
+/// Loads an object that implements the `Deserialize` trait from bytes written by
+/// `object_to_binary_bytes`.
+pub fn load_object_from_binary_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    if !bytes.starts_with(OBINARY_MAGIC) { return Err("bytes are not in the optima binary format".to_string()); }
+    let packed = decompress_bytes(&bytes[OBINARY_MAGIC.len()..])?;
+    rmp_serde::from_slice(&packed).map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn compress_bytes(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    zstd::encode_all(bytes, 3).map_err(|e| e.to_string())
+}
+#[cfg(target_arch = "wasm32")]
+fn compress_bytes(_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    Err("the optima binary format is not supported by wasm32.".to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn decompress_bytes(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    zstd::decode_all(bytes).map_err(|e| e.to_string())
+}
+#[cfg(target_arch = "wasm32")]
+fn decompress_bytes(_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    Err("the optima binary format is not supported by wasm32.".to_string())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OSaveFormat {
+    Json,
+    Ron,
+    Yaml,
+    Toml,
+    /// zstd-compressed MessagePack; see `ToBinaryBytes`.
+    Binary
+}
+impl OSaveFormat {
+    /// Anything that is not in the binary format and does not look like json is treated as ron.
+    /// Yaml and toml cannot be told apart from ron by their contents; see `from_extension`.
+    pub fn detect(contents: &[u8]) -> Self {
+        if contents.starts_with(OBINARY_MAGIC) { return Self::Binary; }
+        let first = contents.iter().find(|x| !x.is_ascii_whitespace());
+        match first {
+            Some(b'{') | Some(b'[') | Some(b'"') => { Self::Json }
+            _ => { Self::Ron }
+        }
+    }
+}
+impl OSaveFormat {
+    /// Only the text formats have a conventional extension.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "json" => { Some(Self::Json) }
+            "ron" => { Some(Self::Ron) }
+            "yaml" | "yml" => { Some(Self::Yaml) }
+            "toml" => { Some(Self::Toml) }
+            _ => { None }
+        }
+    }
+}
+/// Json, which every target can write and read back.  `Binary` is smaller and faster to load, but
+/// is not available on wasm32.
+impl Default for OSaveFormat {
+    fn default() -> Self {
+        Self::Json
+    }
+}
+
 pub fn path_buf_from_string_components(components: &Vec<String>) -> PathBuf {
     let mut out = PathBuf::new();
     for c in components { out.push(c); }
     out
 }
 
+/// Where a mesh referenced in a urdf as `mesh_filename` lives relative to the robot's
+/// `original_meshes` directory when it was fetched by `download_robot`: `package://<package>/<rest>`
+/// maps to `<rest>`, the path of an http(s) url is used as is, and `file://` and leading slashes are
+/// dropped.  Empty, `.`, and `..` components are removed so the result can never leave the
+/// directory.
+pub fn urdf_mesh_relative_path_components(mesh_filename: &str) -> Vec<String> {
+    let relative = if let Some(rest) = mesh_filename.strip_prefix("package://") {
+        rest.split_once('/').map(|x| x.1).unwrap_or(rest)
+    } else if let Some(rest) = mesh_filename.strip_prefix("http://").or(mesh_filename.strip_prefix("https://")) {
+        rest.split_once('/').map(|x| x.1).unwrap_or(rest)
+    } else {
+        mesh_filename.trim_start_matches("file://")
+    };
+    relative.split('/').filter(|x| !x.is_empty() && *x != "." && *x != "..").map(|x| x.to_string()).collect()
+}
+
+/// Environment variable that sets the physical asset folder (see `OPath::new_asset_physical_path_from_json_file`).
+pub const OPTIMA_ASSET_ROOT_ENV_VAR: &str = "OPTIMA_ASSET_ROOT";
+
+static OASSET_ROOT_OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
 /// Convenience class that will be used for path_to_assets_dir.JSON file.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct PathToAssetsDir {
@@ -1150,6 +1491,9 @@ pub enum OAssetLocation<'a> {
     LinkConvexDecomposition { robot_name: &'a str, link_mesh_name: &'a str },
     ChainConvexDecompositionLevel { robot_name: &'a str, level: usize },
     LinkConvexDecompositionLevel { robot_name: &'a str, level: usize, link_mesh_name: &'a str },
+    ChainConvexDecompositionMaxHulls { robot_name: &'a str, max_convex_hulls: u32 },
+    LinkConvexDecompositionMaxHulls { robot_name: &'a str, max_convex_hulls: u32, link_mesh_name: &'a str },
+    ChainDecimatedCollisionMeshes { robot_name: &'a str },
     SavedRobots,
     SavedRobot { robot_name: &'a str }
 }
@@ -1292,6 +1636,22 @@ impl<'a> OAssetLocation<'a> {
                 v.push(link_mesh_name.to_string());
                 v
             }
+            OAssetLocation::ChainConvexDecompositionMaxHulls { robot_name, max_convex_hulls } => {
+                let mut v = Self::UrdfRobot { robot_name: robot_name }.get_path_wrt_asset_folder();
+                v.push("convex_decomposition_max_hulls".to_string());
+                v.push(format!("max_hulls_{}", max_convex_hulls));
+                v
+            }
+            OAssetLocation::LinkConvexDecompositionMaxHulls { robot_name, max_convex_hulls, link_mesh_name } => {
+                let mut v = Self::ChainConvexDecompositionMaxHulls { robot_name, max_convex_hulls: *max_convex_hulls }.get_path_wrt_asset_folder();
+                v.push(link_mesh_name.to_string());
+                v
+            }
+            OAssetLocation::ChainDecimatedCollisionMeshes { robot_name } => {
+                let mut v = Self::UrdfRobot { robot_name: robot_name }.get_path_wrt_asset_folder();
+                v.push("decimated_collision_meshes".to_string());
+                v
+            }
             OAssetLocation::SavedRobots => {
                 vec!["saved_robots".to_string()]
             }
@@ -1304,6 +1664,14 @@ impl<'a> OAssetLocation<'a> {
     }
 }
 
+/// A gltf or glb file loaded by `OPath::load_gltf`, along with the buffers and (decoded) images it
+/// references.
+pub struct OGltfImport {
+    pub document: gltf::Document,
+    pub buffers: Vec<gltf::buffer::Data>,
+    pub images: Vec<gltf::image::Data>
+}
+
 /// An Enum used to specify a particular patten that should be matched during a directory walk.
 #[derive(Debug, Clone)]
 pub enum OPathMatchingPattern {
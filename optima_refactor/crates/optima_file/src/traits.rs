@@ -1,7 +1,8 @@
 
 use serde::de::DeserializeOwned;
-use serde::{Serialize};
-use crate::path::{load_object_from_json_string, OAssetLocation, OStemCellPath};
+use serde::{Deserialize, Serialize};
+use optima_error::OptimaError;
+use crate::path::{load_object_from_binary_bytes, load_object_from_json_string, load_object_from_ron_string, load_object_from_toml_string, load_object_from_yaml_string, object_to_binary_bytes, OAssetLocation, OStemCellPath};
 
 pub trait SaveAndLoadable {
     type SaveType: Serialize + DeserializeOwned;
@@ -10,14 +11,23 @@ pub trait SaveAndLoadable {
     fn get_serialization_string(&self) -> String {
         serde_json::to_string(&self.get_save_serialization_object()).expect("error")
     }
-    fn save_to_path(&self, path: &OStemCellPath) {
+    fn save_to_path(&self, path: &OStemCellPath) -> Result<(), OptimaError> {
         path.save_object_to_file_as_json(&self.get_save_serialization_object())
     }
-    fn load_from_path(path: &OStemCellPath) -> Self where Self: Sized {
-        let s = path.read_file_contents_to_string();
+    fn save_to_path_unchecked(&self, path: &OStemCellPath) {
+        self.save_to_path(path).expect("error")
+    }
+    fn load_from_path(path: &OStemCellPath) -> Result<Self, OptimaError> where Self: Sized {
+        let s = path.read_file_contents_to_string()?;
         return Self::load_from_json_string(&s);
     }
-    fn load_from_json_string(json_str: &str) -> Self where Self: Sized;
+    fn load_from_path_unchecked(path: &OStemCellPath) -> Self where Self: Sized {
+        Self::load_from_path(path).expect("error")
+    }
+    fn load_from_json_string(json_str: &str) -> Result<Self, OptimaError> where Self: Sized;
+    fn load_from_json_string_unchecked(json_str: &str) -> Self where Self: Sized {
+        Self::load_from_json_string(json_str).expect("error")
+    }
 }
 impl <T> SaveAndLoadable for Vec<T> where T: SaveAndLoadable{
     type SaveType = Vec<String>;
@@ -32,25 +42,25 @@ impl <T> SaveAndLoadable for Vec<T> where T: SaveAndLoadable{
         out_vec
     }
 
-    fn load_from_json_string(json_str: &str) -> Self where Self: Sized {
-        let load: Self::SaveType = load_object_from_json_string(json_str).expect("error");
+    fn load_from_json_string(json_str: &str) -> Result<Self, OptimaError> where Self: Sized {
+        let load: Self::SaveType = load_object_from_json_string(json_str).map_err(|e| OptimaError::new_serialization::<Self, _>(e))?;
 
         let mut out_vec = vec![];
         for s in &load {
-            out_vec.push(T::load_from_json_string(s));
+            out_vec.push(T::load_from_json_string(s)?);
         }
 
-        out_vec
+        Ok(out_vec)
     }
 }
 
 pub trait AssetSaveAndLoadable: SaveAndLoadable {
-    fn save_as_asset(&self, location: OAssetLocation) {
+    fn save_as_asset(&self, location: OAssetLocation) -> Result<(), OptimaError> {
         let mut path = OStemCellPath::new_asset_path();
         path.append_file_location(&location);
         self.save_to_path(&path)
     }
-    fn load_as_asset(location: OAssetLocation) -> Self where Self: Sized {
+    fn load_as_asset(location: OAssetLocation) -> Result<Self, OptimaError> where Self: Sized {
         let mut path = OStemCellPath::new_asset_path();
         path.append_file_location(&location);
         Self::load_from_path(&path)
@@ -65,11 +75,11 @@ pub trait ToRonString: Serialize {
 }
 impl<T> ToRonString for T where T: Serialize { }
 pub trait FromRonString: ToRonString + DeserializeOwned {
-    fn from_ron_string(ron_str: &str) -> Self where Self: Sized {
-        let load: Result<Self, _> = ron::from_str(ron_str);
-        return if let Ok(load) = load { load } else {
-            panic!("Could not load ron string {:?} into correct type.", ron_str)
-        }
+    fn from_ron_string(ron_str: &str) -> Result<Self, OptimaError> where Self: Sized {
+        load_object_from_ron_string(ron_str).map_err(|e| OptimaError::new_serialization::<Self, _>(e))
+    }
+    fn from_ron_string_unchecked(ron_str: &str) -> Self where Self: Sized {
+        Self::from_ron_string(ron_str).expect("error")
     }
 }
 impl<T> FromRonString for T where T: ToRonString + DeserializeOwned { }
@@ -81,12 +91,11 @@ pub trait ToJsonString: Serialize {
 }
 impl<T> ToJsonString for T where T: Serialize { }
 pub trait FromJsonString: ToJsonString + DeserializeOwned {
-    fn from_json_string(json_str: &str) -> Self where Self: Sized {
-        let load: Result<Self, _> = serde_json::from_str(json_str);
-        return if let Ok(load) = load { load } else {
-            // Err(OptimaError::new_generic_error_str(&format!(), file!(), line!()))
-            panic!("Could not load json string {:?} into correct type.", json_str);
-        }
+    fn from_json_string(json_str: &str) -> Result<Self, OptimaError> where Self: Sized {
+        load_object_from_json_string(json_str).map_err(|e| OptimaError::new_serialization::<Self, _>(e))
+    }
+    fn from_json_string_unchecked(json_str: &str) -> Self where Self: Sized {
+        Self::from_json_string(json_str).expect("error")
     }
 }
 impl<T> FromJsonString for T where T: ToJsonString + DeserializeOwned { }
@@ -98,13 +107,83 @@ pub trait ToTomlString: Serialize {
 }
 impl<T> ToTomlString for T where T: Serialize { }
 pub trait FromTomlString: ToTomlString + DeserializeOwned {
-    fn from_toml_string(toml_string: &str) -> Self where Self: Sized {
-        let load: Result<Self, _> = toml::from_str(toml_string);
-        return if let Ok(load) = load { load } else {
-            // Err(OptimaError::new_generic_error_str(&format!("Could not load toml string {:?} into correct type.", toml_string), file!(), line!()))
-            panic!("Could not load toml string {:?} into correct type.", toml_string);
-        }
+    fn from_toml_string(toml_str: &str) -> Result<Self, OptimaError> where Self: Sized {
+        load_object_from_toml_string(toml_str).map_err(|e| OptimaError::new_serialization::<Self, _>(e))
+    }
+    fn from_toml_string_unchecked(toml_str: &str) -> Self where Self: Sized {
+        Self::from_toml_string(toml_str).expect("error")
     }
 }
 impl<T> FromTomlString for T where T: ToTomlString + DeserializeOwned { }
 
+pub trait ToYamlString: Serialize {
+    fn to_yaml_string(&self) -> String {
+        serde_yaml::to_string(self).expect("error")
+    }
+}
+impl<T> ToYamlString for T where T: Serialize { }
+pub trait FromYamlString: ToYamlString + DeserializeOwned {
+    fn from_yaml_string(yaml_str: &str) -> Result<Self, OptimaError> where Self: Sized {
+        load_object_from_yaml_string(yaml_str).map_err(|e| OptimaError::new_serialization::<Self, _>(e))
+    }
+    fn from_yaml_string_unchecked(yaml_str: &str) -> Self where Self: Sized {
+        Self::from_yaml_string(yaml_str).expect("error")
+    }
+}
+impl<T> FromYamlString for T where T: ToYamlString + DeserializeOwned { }
+
+/// A text encoding for serializable values, for places that store values as strings (e.g., the
+/// choices of an egui selector) and should not be tied to one format.  Note that toml can only
+/// encode structs and maps at the top level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OStringEncoding {
+    Json,
+    Ron,
+    Yaml,
+    Toml
+}
+impl OStringEncoding {
+    pub fn encode<T: Serialize + ?Sized>(&self, object: &T) -> Result<String, OptimaError> {
+        let res = match self {
+            OStringEncoding::Json => { serde_json::to_string(object).map_err(|e| e.to_string()) }
+            OStringEncoding::Ron => { ron::to_string(object).map_err(|e| e.to_string()) }
+            OStringEncoding::Yaml => { serde_yaml::to_string(object).map_err(|e| e.to_string()) }
+            OStringEncoding::Toml => { toml::to_string(object).map_err(|e| e.to_string()) }
+        };
+        res.map_err(|e| OptimaError::new_serialization::<T, _>(e))
+    }
+    pub fn decode<T: DeserializeOwned>(&self, s: &str) -> Result<T, OptimaError> {
+        let res = match self {
+            OStringEncoding::Json => { load_object_from_json_string(s) }
+            OStringEncoding::Ron => { load_object_from_ron_string(s) }
+            OStringEncoding::Yaml => { load_object_from_yaml_string(s) }
+            OStringEncoding::Toml => { load_object_from_toml_string(s) }
+        };
+        res.map_err(|e| OptimaError::new_serialization::<T, _>(e))
+    }
+}
+impl Default for OStringEncoding {
+    fn default() -> Self {
+        Self::Ron
+    }
+}
+
+/// Compact binary encoding (zstd-compressed MessagePack behind a short header), for large objects
+/// such as preprocessed robots where json is slow to write and parse.  MessagePack is used rather
+/// than a schema-less format like bincode because it is self-describing, which the custom
+/// deserializers throughout Optima rely on.
+pub trait ToBinaryBytes: Serialize {
+    fn to_binary_bytes(&self) -> Vec<u8> {
+        object_to_binary_bytes(self).expect("error")
+    }
+}
+impl<T> ToBinaryBytes for T where T: Serialize { }
+pub trait FromBinaryBytes: ToBinaryBytes + DeserializeOwned {
+    fn from_binary_bytes(bytes: &[u8]) -> Result<Self, OptimaError> where Self: Sized {
+        load_object_from_binary_bytes(bytes).map_err(|e| OptimaError::new_serialization::<Self, _>(e))
+    }
+    fn from_binary_bytes_unchecked(bytes: &[u8]) -> Self where Self: Sized {
+        Self::from_binary_bytes(bytes).expect("error")
+    }
+}
+impl<T> FromBinaryBytes for T where T: ToBinaryBytes + DeserializeOwned { }
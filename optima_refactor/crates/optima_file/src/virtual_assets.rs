@@ -0,0 +1,70 @@
+use std::io::Write;
+use std::sync::OnceLock;
+use vfs::{MemoryFS, VfsPath};
+use crate::path::OAssetLocation;
+
+/// An in-memory asset folder that is consulted by every `OStemCellPath` after the physical and
+/// embedded asset folders.  Files are added at runtime, so an executable can carry just the assets
+/// it needs (e.g., via `include_bytes!`) or fetch them over the network, which makes single-file
+/// demos and wasm builds work without an `optima_assets` folder on disk.
+///
+/// Paths mirror the layout of the asset folder, so anything that loads through `OAssetLocation`
+/// finds these files without changes.
+///
+/// # Example
+///```ignore
+/// use optima_file::path::OAssetLocation;
+/// use optima_file::virtual_assets::OVirtualAssets;
+///
+/// OVirtualAssets::add_file_at_location(&OAssetLocation::UrdfRobot { robot_name: "ur5" }, "ur5.urdf", include_bytes!("../assets/ur5.urdf")).expect("error");
+///```
+pub struct OVirtualAssets;
+impl OVirtualAssets {
+    /// The root of the in-memory asset folder.
+    pub fn root() -> VfsPath {
+        OVIRTUAL_ASSETS_ROOT.get_or_init(|| VfsPath::new(MemoryFS::new())).clone()
+    }
+    /// `components` is the file's path relative to the asset folder, e.g., `["optima_robots", "ur5", "ur5.urdf"]`.
+    /// Replaces the file if it already exists.
+    pub fn add_file(components: &[&str], contents: &[u8]) -> Result<(), String> {
+        let (filename, dirs) = components.split_last().ok_or("add_file needs at least one path component.".to_string())?;
+        let mut dir = Self::root();
+        for s in dirs { dir = dir.join(s).map_err(|e| e.to_string())?; }
+        dir.create_dir_all().map_err(|e| e.to_string())?;
+
+        let file = dir.join(filename).map_err(|e| e.to_string())?;
+        let mut f = file.create_file().map_err(|e| e.to_string())?;
+        f.write_all(contents).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+    pub fn add_file_at_location(location: &OAssetLocation, filename: &str, contents: &[u8]) -> Result<(), String> {
+        let mut components = location.get_path_wrt_asset_folder();
+        components.push(filename.to_string());
+        let components: Vec<&str> = components.iter().map(|x| x.as_str()).collect();
+        Self::add_file(&components, contents)
+    }
+    /// Copies every file of a `rust_embed::RustEmbed` folder into the in-memory asset folder.  The
+    /// embed's folder should be laid out like (a subset of) the asset folder.
+    pub fn add_embedded_folder<E: rust_embed::RustEmbed>() -> Result<(), String> {
+        for filename in E::iter() {
+            let file = E::get(&filename).ok_or(format!("embedded file {} not found", filename))?;
+            let components: Vec<&str> = filename.split('/').filter(|x| !x.is_empty()).collect();
+            Self::add_file(&components, &file.data)?;
+        }
+        Ok(())
+    }
+    pub fn remove_file(components: &[&str]) -> Result<(), String> {
+        let mut p = Self::root();
+        for s in components { p = p.join(s).map_err(|e| e.to_string())?; }
+        p.remove_file().map_err(|e| e.to_string())
+    }
+    /// True if no files have been added.
+    pub fn is_empty() -> bool {
+        match OVIRTUAL_ASSETS_ROOT.get() {
+            None => { true }
+            Some(root) => { root.read_dir().map(|mut x| x.next().is_none()).unwrap_or(true) }
+        }
+    }
+}
+
+static OVIRTUAL_ASSETS_ROOT: OnceLock<VfsPath> = OnceLock::new();
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use crate::path::OPath;
+
+/// Watches files and directories for changes.  Editors tend to save in several steps (truncate,
+/// write, rename), so changes are collected until no new event has arrived for `debounce` and then
+/// reported together by `poll_changes`.
+///
+/// Watching a directory rather than a single file is more robust, since editors that save by
+/// replacing the file would otherwise end the watch.
+pub struct OFileWatcher {
+    watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    pending: HashSet<PathBuf>,
+    last_event: Option<Instant>,
+    debounce: Duration
+}
+impl OFileWatcher {
+    pub fn new(debounce: Duration) -> Result<Self, String> {
+        let (sender, receiver) = channel();
+        let watcher = notify::recommended_watcher(move |res| { let _ = sender.send(res); }).map_err(|e| e.to_string())?;
+
+        Ok(Self { watcher, receiver, pending: HashSet::new(), last_event: None, debounce })
+    }
+    /// Only physical paths can be watched.
+    pub fn watch(&mut self, path: &OPath, recursive: bool) -> Result<(), String> {
+        let p = match path {
+            OPath::Path(p) => { p }
+            OPath::VfsPath(_) => { return Err("Watching is not supported by VfsPath.  Try using a Path variant instead.".to_string()); }
+        };
+        let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        self.watcher.watch(p, mode).map_err(|e| e.to_string())
+    }
+    pub fn unwatch(&mut self, path: &OPath) -> Result<(), String> {
+        match path {
+            OPath::Path(p) => { self.watcher.unwatch(p).map_err(|e| e.to_string()) }
+            OPath::VfsPath(_) => { Ok(()) }
+        }
+    }
+    /// Non-blocking.  Returns the paths that changed (were created, modified, or removed) once the
+    /// debounce window has passed, and an empty vec otherwise.
+    pub fn poll_changes(&mut self) -> Vec<PathBuf> {
+        while let Ok(res) = self.receiver.try_recv() {
+            if let Ok(event) = res {
+                match event.kind {
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
+                        self.pending.extend(event.paths);
+                        self.last_event = Some(Instant::now());
+                    }
+                    _ => { }
+                }
+            }
+        }
+
+        match self.last_event {
+            Some(last_event) if last_event.elapsed() >= self.debounce => {
+                self.last_event = None;
+                self.pending.drain().collect()
+            }
+            _ => { vec![] }
+        }
+    }
+}
@@ -1,7 +1,9 @@
 pub mod splines;
+pub mod time_parameterization;
 
 use std::marker::PhantomData;
 use ad_trait::AD;
+use optima_error::OptimaError;
 use optima_linalg::OVec;
 
 pub trait InterpolatorTraitLite<T: AD, V: OVec<T>> {
@@ -69,7 +71,6 @@ impl<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V>> ArclengthParameterizedInterp
 
         let mut arclength_markers = vec![];
 
-        let mut t = T::zero();
         let max_allowable_t_value = interpolator.max_t();
         let step_size = max_allowable_t_value / T::constant(num_arclength_markers as f64);
         let mut accumulated_distance = T::zero();
@@ -77,7 +78,10 @@ impl<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V>> ArclengthParameterizedInterp
         let mut prev_point = interpolator.interpolate(T::zero());
 
         let mut passed_m = false;
+        let mut i = 0;
         while !passed_m {
+            // computed from the step index rather than accumulated, so rounding does not drift in f32.
+            let mut t = step_size * T::constant(i as f64);
             if t >= max_allowable_t_value {
                 passed_m = true;
                 t = max_allowable_t_value;
@@ -90,7 +94,7 @@ impl<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V>> ArclengthParameterizedInterp
             arclength_markers.push((accumulated_distance, t));
 
             prev_point = curr_point;
-            t += step_size;
+            i += 1;
         }
 
         Self { interpolator, arclength_markers, total_arclength: accumulated_distance, phantom_data: PhantomData::default() }
@@ -173,6 +177,63 @@ impl<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V>> InterpolatorTraitLite<T, V>
     }
 }
 
+/// Plays an interpolator whose segments are parameterized by t in [0, num_segments] (e.g., an
+/// `InterpolatingSpline`) such that segment i runs from `knot_times[i]` to `knot_times[i + 1]`.
+/// Useful for keyframes that are not evenly spaced in time.
+#[derive(Clone)]
+pub struct KnotTimedInterpolator<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V>> {
+    interpolator: I,
+    knot_times: Vec<T>,
+    phantom_data: PhantomData<V>
+}
+impl<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V>> KnotTimedInterpolator<T, V, I> {
+    /// `knot_times` must start at zero, be strictly increasing, and have one more entry than the
+    /// interpolator has segments.
+    pub fn new(interpolator: I, knot_times: Vec<T>) -> Result<Self, OptimaError> {
+        if knot_times.len() < 2 { return Err(OptimaError::InvalidInput(format!("at least two knot times are needed, got {}.", knot_times.len()))); }
+        let num_segments = interpolator.max_t().to_constant().round() as usize;
+        if knot_times.len() - 1 != num_segments { return Err(OptimaError::InvalidInput(format!("got {} knot times for an interpolator with {} segments.", knot_times.len(), num_segments))); }
+        if knot_times[0] != T::zero() { return Err(OptimaError::InvalidInput(format!("the first knot time has to be 0, got {}.", knot_times[0]))); }
+        if !knot_times.windows(2).all(|x| x[0] < x[1]) { return Err(OptimaError::InvalidInput("knot times have to be strictly increasing.".to_string())); }
+        Ok(Self { interpolator, knot_times, phantom_data: PhantomData::default() })
+    }
+    pub fn new_unchecked(interpolator: I, knot_times: Vec<T>) -> Self {
+        Self::new(interpolator, knot_times).expect("error")
+    }
+    /// Like `interpolate`, but returns an error for a `t` outside of [0, `max_t`] (or nan) instead of
+    /// clamping it.
+    pub fn try_interpolate(&self, t: T) -> Result<V, OptimaError> {
+        if !(T::zero() <= t && t <= self.max_t()) { return Err(OptimaError::InvalidInput(format!("t = {} is outside of [0, {}].", t, self.max_t()))); }
+        Ok(self.interpolate_in_range(t))
+    }
+    fn interpolate_in_range(&self, t: T) -> V {
+        let segment_idx = self.knot_times.windows(2).position(|x| t <= x[1]).unwrap_or(self.knot_times.len() - 2);
+        let (start, stop) = (self.knot_times[segment_idx], self.knot_times[segment_idx + 1]);
+        let u = (t - start) / (stop - start);
+        self.interpolator.interpolate(T::constant(segment_idx as f64) + u)
+    }
+    #[inline(always)]
+    pub fn knot_times(&self) -> &Vec<T> {
+        &self.knot_times
+    }
+    #[inline(always)]
+    pub fn interpolator(&self) -> &I {
+        &self.interpolator
+    }
+}
+impl<T: AD, V: OVec<T>, I: InterpolatorTrait<T, V>> InterpolatorTraitLite<T, V> for KnotTimedInterpolator<T, V, I> {
+    /// `t` is clamped to [0, `max_t`] (and nan is read as 0); see `try_interpolate`.
+    fn interpolate(&self, t: T) -> V {
+        let t = if t > self.max_t() { self.max_t() } else if t >= T::zero() { t } else { T::zero() };
+        self.interpolate_in_range(t)
+    }
+
+    fn max_t(&self) -> T {
+        // `new` guarantees at least two knot times.
+        self.knot_times[self.knot_times.len() - 1]
+    }
+}
+
 /*
 pub struct SpacetimeInterpolator<T: AD, V: OVec<T>, SI: InterpolatorTrait<T, V>, TI: InterpolatorTrait<T, V>> {
     space_interpolator: SI,
@@ -224,10 +285,15 @@ pub fn get_interpolation_range<T: AD>(range_start: T, range_stop: T, step_size:
     // out_range.push(range_stop);
     */
 
+    // values are computed from the step index rather than accumulated, so that the loop still ends
+    // when step_size is below the precision of T (e.g., in f32).
+    let mut i = 0;
     'l: loop {
         out_range.push(curr_val);
-        curr_val += step_size;
-        if curr_val > range_stop { break 'l; }
+        i += 1;
+        let next_val = range_start + step_size * T::constant(i as f64);
+        if next_val > range_stop || next_val <= curr_val { break 'l; }
+        curr_val = next_val;
     }
 
     let diff =  (range_stop - *out_range.last().unwrap()).abs();
@@ -236,9 +302,11 @@ pub fn get_interpolation_range<T: AD>(range_start: T, range_stop: T, step_size:
     out_range
 }
 
+/// Exactly `num_steps` values, the last of which is exactly `range_stop` (accumulated rounding
+/// could otherwise drop or duplicate the final step, especially in f32).
 pub fn get_interpolation_range_num_steps<T: AD>(range_start: T, range_stop: T, num_steps: usize) -> Vec<T> {
     let step_size = (range_stop - range_start) / T::constant(num_steps as f64 - 1.0);
-    get_interpolation_range(range_start, range_stop, step_size)
+    (0..num_steps).map(|i| if i == num_steps - 1 { range_stop } else { range_start + step_size * T::constant(i as f64) }).collect()
 }
 
 pub fn linearly_interpolate_points<T: AD, V: OVec<T>>(start_point: V, end_point: V, num_points: usize) -> Vec<V> {
@@ -249,4 +317,44 @@ pub fn linearly_interpolate_points<T: AD, V: OVec<T>>(start_point: V, end_point:
         out.push( p );
     }
     out
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::splines::{InterpolatingSpline, InterpolatingSplineType};
+    use super::*;
+
+    fn linear(points: &[f64]) -> InterpolatingSpline<f64, Vec<f64>> {
+        InterpolatingSpline::new(points.iter().map(|x| vec![*x]).collect(), InterpolatingSplineType::Linear)
+    }
+
+    #[test]
+    fn knot_timed_interpolator_maps_time_onto_segments() {
+        let interpolator = KnotTimedInterpolator::new(linear(&[0.0, 1.0, 3.0]), vec![0.0, 2.0, 2.5]).expect("error");
+        assert_eq!(interpolator.max_t(), 2.5);
+        assert!((interpolator.interpolate(1.0)[0] - 0.5).abs() < 1e-9);
+        assert!((interpolator.interpolate(2.0)[0] - 1.0).abs() < 1e-9);
+        assert!((interpolator.interpolate(2.25)[0] - 2.0).abs() < 1e-9);
+        assert!((interpolator.interpolate(2.5)[0] - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn knot_timed_interpolator_rejects_bad_knot_times() {
+        assert!(KnotTimedInterpolator::new(linear(&[0.0, 1.0, 3.0]), vec![0.0, 1.0]).is_err());
+        assert!(KnotTimedInterpolator::new(linear(&[0.0, 1.0, 3.0]), vec![0.5, 1.0, 2.0]).is_err());
+        assert!(KnotTimedInterpolator::new(linear(&[0.0, 1.0, 3.0]), vec![0.0, 1.0, 1.0]).is_err());
+        assert!(KnotTimedInterpolator::new(linear(&[0.0]), vec![0.0]).is_err());
+    }
+
+    #[test]
+    fn knot_timed_interpolator_clamps_but_try_interpolate_rejects_out_of_range_times() {
+        let interpolator = KnotTimedInterpolator::new(linear(&[0.0, 1.0]), vec![0.0, 2.0]).expect("error");
+        assert_eq!(interpolator.interpolate(-1.0), vec![0.0]);
+        assert_eq!(interpolator.interpolate(5.0), vec![1.0]);
+        assert_eq!(interpolator.interpolate(f64::NAN), vec![0.0]);
+
+        assert!(interpolator.try_interpolate(-1.0).is_err());
+        assert!(interpolator.try_interpolate(5.0).is_err());
+        assert!(interpolator.try_interpolate(f64::NAN).is_err());
+        assert!((interpolator.try_interpolate(1.0).expect("error")[0] - 0.5).abs() < 1e-9);
+    }
+}
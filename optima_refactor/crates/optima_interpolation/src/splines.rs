@@ -1,4 +1,5 @@
 use ad_trait::AD;
+use optima_error::OptimaError;
 use optima_linalg::OVec;
 use crate::{InterpolatorTrait, InterpolatorTraitLite, linearly_interpolate_points};
 
@@ -210,11 +211,10 @@ impl<T: AD, V: OVec<T>> InterpolatingSpline<T, V> {
     }
     #[inline]
     fn interpolating_spline_interpolate(&self, t: T) -> V {
-        if t == self.max_allowable_t_value() { return self.interpolating_spline_interpolate(t - T::constant(0.00000001)); }
-
         assert!(t >= T::zero());
-        let rt = t.fract();
-        let spline_segment_idx = t.floor().to_constant() as usize;
+        // t at the very end is the end of the last segment.  (Nudging t down instead does nothing in
+        // f32 once t is large enough.)
+        let (spline_segment_idx, rt) = if t == self.max_allowable_t_value() { (self.num_spline_segments - 1, T::one()) } else { (t.floor().to_constant() as usize, t.fract()) };
         assert!(spline_segment_idx < self.num_spline_segments, "t: {}", t);
 
         let a_vecs = &self.spline_segment_a_coefficients[spline_segment_idx];
@@ -368,6 +368,48 @@ impl<T: AD, V: OVec<T>> InterpolatorTraitLite<T, V> for InterpolatingSpline<T, V
     }
 }
 
+/// Cubic hermite segments that each have their own start and end tangent, unlike a `HermiteCubic`
+/// `InterpolatingSpline`, where neighboring segments share the tangent control point between them.
+/// This is what tangents scaled per segment need, e.g., non-uniform Catmull-Rom tangents scaled by
+/// segment durations.  Segment i is parameterized by t in [i, i + 1].
+#[derive(Clone, Debug)]
+pub struct PiecewiseHermiteSpline<T: AD, V: OVec<T>> {
+    segments: Vec<InterpolatingSpline<T, V>>
+}
+impl<T: AD, V: OVec<T>> PiecewiseHermiteSpline<T, V> {
+    /// Segment i runs from `points[i]` to `points[i + 1]`, with the start and end tangents in
+    /// `segment_tangents[i]` (with respect to the segment's own parameter in [0, 1]).
+    pub fn new(points: Vec<V>, segment_tangents: Vec<(V, V)>) -> Result<Self, OptimaError> {
+        if points.len() < 2 { return Err(OptimaError::InvalidInput(format!("at least two points are needed, got {}.", points.len()))); }
+        if segment_tangents.len() != points.len() - 1 { return Err(OptimaError::InvalidInput(format!("got {} tangent pairs for {} segments.", segment_tangents.len(), points.len() - 1))); }
+        let dim = points[0].len();
+        let dims_match = points.iter().all(|x| x.len() == dim) && segment_tangents.iter().all(|(a, b)| a.len() == dim && b.len() == dim);
+        if !dims_match { return Err(OptimaError::InvalidInput(format!("all points and tangents have to have {} elements.", dim))); }
+
+        let segments = segment_tangents.into_iter().enumerate().map(|(i, (start_tangent, end_tangent))| {
+            InterpolatingSpline::new(vec![points[i].clone(), start_tangent, points[i + 1].clone(), end_tangent], InterpolatingSplineType::HermiteCubic)
+        }).collect();
+
+        Ok(Self { segments })
+    }
+    #[inline(always)]
+    pub fn num_segments(&self) -> usize {
+        self.segments.len()
+    }
+}
+impl<T: AD, V: OVec<T>> InterpolatorTraitLite<T, V> for PiecewiseHermiteSpline<T, V> {
+    fn interpolate(&self, t: T) -> V {
+        assert!(t >= T::zero() && t <= self.max_t(), "t: {}", t);
+        let segment_idx = (t.floor().to_constant() as usize).min(self.segments.len() - 1);
+        self.segments[segment_idx].interpolate(t - T::constant(segment_idx as f64))
+    }
+
+    #[inline(always)]
+    fn max_t(&self) -> T {
+        T::constant(self.segments.len() as f64)
+    }
+}
+
 fn calculate_a_vector_coefficients_generic<T: AD, V: OVec<T>>(p: &Vec<&V>, basis_matrix: &Vec<Vec<T>>) -> Vec<V> {
     assert!(p.len() > 0);
     let mut out_vec = vec![];
@@ -500,4 +542,40 @@ impl<T: AD, V: OVec<T>> InterpolatorTraitLite<T, V> for BSpline<T, V> {
     fn max_t(&self) -> T {
         self.max_allowable_t_value()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piecewise_hermite_spline_interpolates_its_points_with_per_segment_tangents() {
+        let spline = PiecewiseHermiteSpline::new(vec![vec![0.0], vec![1.0], vec![1.0]], vec![(vec![0.0], vec![2.0]), (vec![0.5], vec![0.0])]).expect("error");
+        assert_eq!(spline.num_segments(), 2);
+        assert_eq!(spline.max_t(), 2.0);
+        for (t, expected) in [(0.0, 0.0), (1.0, 1.0), (2.0, 1.0)] {
+            assert!((spline.interpolate(t)[0] - expected).abs() < 1e-9);
+        }
+
+        // the end tangent of the first segment and the start tangent of the second are independent.
+        let h = 1e-6;
+        let before = (spline.interpolate(1.0)[0] - spline.interpolate(1.0 - h)[0]) / h;
+        let after = (spline.interpolate(1.0 + h)[0] - spline.interpolate(1.0)[0]) / h;
+        assert!((before - 2.0).abs() < 1e-3);
+        assert!((after - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn piecewise_hermite_spline_with_chord_tangents_is_linear() {
+        let spline = PiecewiseHermiteSpline::new(vec![vec![0.0, 1.0], vec![2.0, -1.0]], vec![(vec![2.0, -2.0], vec![2.0, -2.0])]).expect("error");
+        let p = spline.interpolate(0.25);
+        assert!((p[0] - 0.5).abs() < 1e-9);
+        assert!((p[1] - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn piecewise_hermite_spline_rejects_bad_input() {
+        assert!(PiecewiseHermiteSpline::<f64, Vec<f64>>::new(vec![vec![0.0]], vec![]).is_err());
+        assert!(PiecewiseHermiteSpline::new(vec![vec![0.0], vec![1.0]], vec![]).is_err());
+        assert!(PiecewiseHermiteSpline::new(vec![vec![0.0], vec![1.0, 2.0]], vec![(vec![0.0], vec![0.0])]).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use ad_trait::AD;
+use optima_error::OptimaError;
+use optima_linalg::OVec;
+
+/// Times at which a piecewise linear path through `waypoints` reaches each waypoint when every
+/// segment is traversed as fast as `max_velocities` (one per dimension) allows, i.e., each segment
+/// takes as long as its slowest dimension needs.  The first time is zero.
+pub fn velocity_limited_waypoint_times<T: AD, V: OVec<T>>(waypoints: &Vec<V>, max_velocities: &[T]) -> Result<Vec<T>, String> {
+    if waypoints.is_empty() { return Ok(vec![]); }
+    let dim = waypoints[0].len();
+    if waypoints.iter().any(|x| x.len() != dim) { return Err("all waypoints must have the same length".to_string()); }
+    if max_velocities.len() != dim { return Err(format!("expected {} max velocities, got {}", dim, max_velocities.len())); }
+    if max_velocities.iter().any(|x| *x <= T::zero()) { return Err("max velocities must be positive".to_string()); }
+
+    let mut out = vec![T::zero()];
+    for i in 1..waypoints.len() {
+        let delta = waypoints[i].ovec_sub(&waypoints[i - 1]);
+        let distances: Vec<T> = (0..dim).map(|j| delta.ovec_get_element(j).abs()).collect();
+        let duration = velocity_limited_segment_duration(&distances, max_velocities)?;
+        let last = *out.last().unwrap();
+        out.push(last + duration);
+    }
+
+    Ok(out)
+}
+
+/// Time needed to cover each of `distances` (e.g., a pose's translation and rotation angle) at
+/// the matching `max_velocities` together, i.e., the time of the slowest one.
+pub fn velocity_limited_segment_duration<T: AD>(distances: &[T], max_velocities: &[T]) -> Result<T, OptimaError> {
+    if distances.len() != max_velocities.len() { return Err(OptimaError::InvalidInput(format!("expected {} max velocities, got {}.", distances.len(), max_velocities.len()))); }
+    if max_velocities.iter().any(|x| *x <= T::zero()) { return Err(OptimaError::InvalidInput("max velocities must be positive.".to_string())); }
+
+    let mut out = T::zero();
+    for (distance, max_velocity) in distances.iter().zip(max_velocities.iter()) {
+        out = out.max(distance.abs() / *max_velocity);
+    }
+    Ok(out)
+}
+
+/// Samples the piecewise linear path through `waypoints`, reached at `times`, every `time_step`
+/// seconds.  The last waypoint is always included.  Returns (time, point) pairs.
+pub fn resample_timed_waypoints<T: AD, V: OVec<T>>(waypoints: &Vec<V>, times: &[T], time_step: T) -> Result<Vec<(T, V)>, String> {
+    if waypoints.len() != times.len() { return Err(format!("expected {} times, got {}", waypoints.len(), times.len())); }
+    if time_step <= T::zero() { return Err("time step must be positive".to_string()); }
+    if waypoints.is_empty() { return Ok(vec![]); }
+    if waypoints.len() == 1 { return Ok(vec![(times[0], waypoints[0].clone())]); }
+
+    let mut out = vec![];
+    let mut segment = 0;
+    for t in crate::get_interpolation_range(times[0], *times.last().unwrap(), time_step) {
+        while segment < times.len() - 2 && t > times[segment + 1] { segment += 1; }
+
+        let duration = times[segment + 1] - times[segment];
+        let ratio = if duration > T::zero() { ((t - times[segment]) / duration).min(T::one()) } else { T::one() };
+        let point = waypoints[segment].ovec_scalar_mul(&(T::one() - ratio)).ovec_add(&waypoints[segment + 1].ovec_scalar_mul(&ratio));
+        out.push((t, point));
+    }
+    // the range ends within a small tolerance of the final time, so pin the last sample to it.
+    *out.last_mut().unwrap() = (*times.last().unwrap(), waypoints.last().unwrap().clone());
+
+    Ok(out)
+}
@@ -37,7 +37,7 @@ impl UdpClient {
 
         self.socket.send(&message_data)?;
 
-        println!("Message sent: {:?}", message);
+        tracing::debug!(?message, "message sent");
 
         Ok(())
     }
@@ -0,0 +1,191 @@
+use ad_trait::differentiable_block::DifferentiableBlock;
+use ad_trait::differentiable_function::{DerivativeMethodTrait, DifferentiableFunctionClass};
+use nalgebra::{DMatrix, DVector};
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use optima_sampling::get_rng;
+use crate::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
+
+/// Covariance Matrix Adaptation Evolution Strategy.  Only calls the objective function (never its
+/// derivative), so it is suited to objectives where automatic differentiation is impractical.
+pub struct CMAESOptimizer {
+    initial_sigma: f64,
+    population_size: Option<usize>,
+    max_generations: usize,
+    tolerance: f64,
+    lower_bounds: Option<Vec<f64>>,
+    upper_bounds: Option<Vec<f64>>,
+    seed: Option<u64>
+}
+impl CMAESOptimizer {
+    /// If `population_size` is None, the standard default of 4 + floor(3 ln(n)) is used.
+    pub fn new(initial_sigma: f64, population_size: Option<usize>, max_generations: usize, tolerance: f64, lower_bounds: Option<Vec<f64>>, upper_bounds: Option<Vec<f64>>, seed: Option<u64>) -> Self {
+        assert!(initial_sigma > 0.0);
+        if let (Some(l), Some(u)) = (&lower_bounds, &upper_bounds) { assert_eq!(l.len(), u.len()); }
+        Self { initial_sigma, population_size, max_generations, tolerance, lower_bounds, upper_bounds, seed }
+    }
+    /// Same as `optimize_unconstrained`, but each population is evaluated in parallel.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn optimize_parallel<'a, DC1, E1>(&self, initial_condition: &[f64], objective_function: &DifferentiableBlock<'a, DC1, E1>) -> CMAESOptimizerOutput
+        where DC1: DifferentiableFunctionClass,
+              E1: DerivativeMethodTrait,
+              DifferentiableBlock<'a, DC1, E1>: Sync
+    {
+        self.cmaes_optimize(initial_condition, |population| {
+            population.par_iter().map(|x| {
+                let res = objective_function.call(x);
+                assert_eq!(res.len(), 1);
+                res[0]
+            }).collect()
+        })
+    }
+    fn cmaes_optimize<F>(&self, initial_condition: &[f64], evaluate_population: F) -> CMAESOptimizerOutput
+        where F: Fn(&Vec<Vec<f64>>) -> Vec<f64>
+    {
+        let n = initial_condition.len();
+        assert!(n > 0);
+        let nf = n as f64;
+
+        let lambda = match self.population_size {
+            None => { 4 + (3.0 * nf.ln()).floor() as usize }
+            Some(population_size) => { population_size.max(2) }
+        };
+        let mu = (lambda / 2).max(1);
+
+        let mut weights: Vec<f64> = (0..mu).map(|i| (mu as f64 + 0.5).ln() - ((i + 1) as f64).ln()).collect();
+        let weights_sum: f64 = weights.iter().sum();
+        weights.iter_mut().for_each(|w| *w /= weights_sum);
+        let mueff = 1.0 / weights.iter().map(|w| w * w).sum::<f64>();
+
+        let cc = (4.0 + mueff / nf) / (nf + 4.0 + 2.0 * mueff / nf);
+        let cs = (mueff + 2.0) / (nf + mueff + 5.0);
+        let c1 = 2.0 / ((nf + 1.3).powi(2) + mueff);
+        let cmu = (1.0 - c1).min(2.0 * (mueff - 2.0 + 1.0 / mueff) / ((nf + 2.0).powi(2) + mueff));
+        let damps = 1.0 + 2.0 * (((mueff - 1.0) / (nf + 1.0)).sqrt() - 1.0).max(0.0) + cs;
+        let chi_n = nf.sqrt() * (1.0 - 1.0 / (4.0 * nf) + 1.0 / (21.0 * nf * nf));
+
+        let mut rng = get_rng(self.seed);
+
+        let mut mean = DVector::from_column_slice(&self.clamp_to_bounds(initial_condition.to_vec()));
+        let mut sigma = self.initial_sigma;
+        let mut pc = DVector::<f64>::zeros(n);
+        let mut ps = DVector::<f64>::zeros(n);
+        let mut c = DMatrix::<f64>::identity(n, n);
+
+        let mut x_star = mean.as_slice().to_vec();
+        let mut f_star = evaluate_population(&vec![x_star.clone()])[0];
+        let mut num_function_evaluations = 1;
+        let mut num_generations = 0;
+
+        for generation in 0..self.max_generations {
+            num_generations = generation + 1;
+
+            let eigen = c.clone().symmetric_eigen();
+            let b = eigen.eigenvectors;
+            let d = eigen.eigenvalues.map(|x| x.max(1e-20).sqrt());
+            let inv_sqrt_c = &b * DMatrix::from_diagonal(&d.map(|x| 1.0 / x)) * b.transpose();
+
+            let mut population = vec![];
+            for _ in 0..lambda {
+                let z = DVector::<f64>::from_fn(n, |_, _| StandardNormal.sample(&mut rng));
+                let y = &b * d.component_mul(&z);
+                let x = &mean + sigma * y;
+                population.push(self.clamp_to_bounds(x.as_slice().to_vec()));
+            }
+
+            let fitnesses = evaluate_population(&population);
+            assert_eq!(fitnesses.len(), lambda);
+            num_function_evaluations += lambda;
+
+            let mut idxs: Vec<usize> = (0..lambda).collect();
+            idxs.sort_by(|a, b| fitnesses[*a].partial_cmp(&fitnesses[*b]).unwrap_or(std::cmp::Ordering::Equal));
+
+            if fitnesses[idxs[0]] < f_star {
+                f_star = fitnesses[idxs[0]];
+                x_star = population[idxs[0]].clone();
+            }
+
+            let old_mean = mean.clone();
+            mean = DVector::<f64>::zeros(n);
+            for i in 0..mu {
+                mean += weights[i] * DVector::from_column_slice(&population[idxs[i]]);
+            }
+
+            let y_w = (&mean - &old_mean) / sigma;
+            ps = (1.0 - cs) * &ps + (cs * (2.0 - cs) * mueff).sqrt() * (&inv_sqrt_c * &y_w);
+            let ps_norm = ps.norm();
+            let hsig = ps_norm / (1.0 - (1.0 - cs).powi(2 * (generation as i32 + 1))).sqrt() / chi_n < 1.4 + 2.0 / (nf + 1.0);
+            let hsig_f = if hsig { 1.0 } else { 0.0 };
+            pc = (1.0 - cc) * &pc + hsig_f * (cc * (2.0 - cc) * mueff).sqrt() * &y_w;
+
+            let mut rank_mu = DMatrix::<f64>::zeros(n, n);
+            for i in 0..mu {
+                let artmp = (DVector::from_column_slice(&population[idxs[i]]) - &old_mean) / sigma;
+                rank_mu += weights[i] * &artmp * artmp.transpose();
+            }
+            c = (1.0 - c1 - cmu) * &c + c1 * (&pc * pc.transpose() + (1.0 - hsig_f) * cc * (2.0 - cc) * &c) + cmu * rank_mu;
+            c = 0.5 * (&c + c.transpose());
+
+            sigma *= ((cs / damps) * (ps_norm / chi_n - 1.0)).exp();
+
+            let f_range = fitnesses[idxs[lambda - 1]] - fitnesses[idxs[0]];
+            if sigma * d.max() < self.tolerance || f_range.abs() < self.tolerance { break; }
+        }
+
+        CMAESOptimizerOutput { x_star, f_star, num_generations, num_function_evaluations }
+    }
+    fn clamp_to_bounds(&self, mut x: Vec<f64>) -> Vec<f64> {
+        if let Some(lower_bounds) = &self.lower_bounds {
+            x.iter_mut().zip(lower_bounds.iter()).for_each(|(x, l)| *x = x.max(*l));
+        }
+        if let Some(upper_bounds) = &self.upper_bounds {
+            x.iter_mut().zip(upper_bounds.iter()).for_each(|(x, u)| *x = x.min(*u));
+        }
+        x
+    }
+}
+impl DiffBlockOptimizerTrait for CMAESOptimizer {
+    type OutputType = CMAESOptimizerOutput;
+
+    fn optimize<'a, DC1, E1, DC2, E2, DC3, E3>(&self, initial_condition: &[f64], objective_function: &DifferentiableBlock<'a, DC1, E1>, _equality_constraint_function: &DifferentiableBlock<'a, DC2, E2>, _inequality_constraint_function: &DifferentiableBlock<'a, DC3, E3>) -> Self::OutputType where DC1: DifferentiableFunctionClass, DC2: DifferentiableFunctionClass, DC3: DifferentiableFunctionClass, E1: DerivativeMethodTrait, E2: DerivativeMethodTrait, E3: DerivativeMethodTrait {
+        self.cmaes_optimize(initial_condition, |population| {
+            population.iter().map(|x| {
+                let res = objective_function.call(x);
+                assert_eq!(res.len(), 1);
+                res[0]
+            }).collect()
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CMAESOptimizerOutput {
+    x_star: Vec<f64>,
+    f_star: f64,
+    num_generations: usize,
+    num_function_evaluations: usize
+}
+impl CMAESOptimizerOutput {
+    #[inline(always)]
+    pub fn num_generations(&self) -> usize {
+        self.num_generations
+    }
+    #[inline(always)]
+    pub fn num_function_evaluations(&self) -> usize {
+        self.num_function_evaluations
+    }
+}
+impl OptimizerOutputTrait for CMAESOptimizerOutput {
+    type DataType = f64;
+
+    #[inline(always)]
+    fn x_star(&self) -> &[Self::DataType] {
+        self.x_star.as_slice()
+    }
+
+    #[inline(always)]
+    fn f_star(&self) -> Self::DataType {
+        self.f_star
+    }
+}
@@ -0,0 +1,75 @@
+use ad_trait::differentiable_block::DifferentiableBlock;
+use ad_trait::differentiable_function::{DerivativeMethodTrait, DifferentiableFunctionClass};
+
+/// Compares the derivative computed by the given block against central finite differences with
+/// step size `eps`.  Each (output, input) entry of the Jacobian is reported separately.
+pub fn check_gradients<'a, DC, E>(block: &DifferentiableBlock<'a, DC, E>, x: &[f64], eps: f64) -> GradientCheckReport
+    where DC: DifferentiableFunctionClass,
+          E: DerivativeMethodTrait
+{
+    assert!(eps > 0.0);
+    assert_eq!(x.len(), block.num_inputs());
+
+    let (_, jacobian) = block.derivative(x);
+    assert_eq!(jacobian.nrows(), block.num_outputs());
+    assert_eq!(jacobian.ncols(), x.len());
+
+    let mut entries = vec![];
+    for input_idx in 0..x.len() {
+        let mut x_plus = x.to_vec();
+        let mut x_minus = x.to_vec();
+        x_plus[input_idx] += eps;
+        x_minus[input_idx] -= eps;
+        let f_plus = block.call(&x_plus);
+        let f_minus = block.call(&x_minus);
+
+        for output_idx in 0..f_plus.len() {
+            let finite_difference_value = (f_plus[output_idx] - f_minus[output_idx]) / (2.0 * eps);
+            let derivative_value = jacobian[(output_idx, input_idx)];
+            let absolute_error = (derivative_value - finite_difference_value).abs();
+            let relative_error = absolute_error / derivative_value.abs().max(finite_difference_value.abs()).max(1e-12);
+            entries.push(GradientCheckEntry { output_idx, input_idx, derivative_value, finite_difference_value, absolute_error, relative_error });
+        }
+    }
+
+    GradientCheckReport { eps, entries }
+}
+
+#[derive(Clone, Debug)]
+pub struct GradientCheckEntry {
+    pub output_idx: usize,
+    pub input_idx: usize,
+    pub derivative_value: f64,
+    pub finite_difference_value: f64,
+    pub absolute_error: f64,
+    pub relative_error: f64
+}
+
+#[derive(Clone, Debug)]
+pub struct GradientCheckReport {
+    eps: f64,
+    entries: Vec<GradientCheckEntry>
+}
+impl GradientCheckReport {
+    #[inline(always)]
+    pub fn eps(&self) -> f64 {
+        self.eps
+    }
+    #[inline(always)]
+    pub fn entries(&self) -> &Vec<GradientCheckEntry> {
+        &self.entries
+    }
+    pub fn max_absolute_error(&self) -> f64 {
+        self.entries.iter().map(|x| x.absolute_error).fold(0.0, f64::max)
+    }
+    pub fn max_relative_error(&self) -> f64 {
+        self.entries.iter().map(|x| x.relative_error).fold(0.0, f64::max)
+    }
+    /// Entries whose relative error exceeds the given tolerance.
+    pub fn discrepancies(&self, relative_tolerance: f64) -> Vec<&GradientCheckEntry> {
+        self.entries.iter().filter(|x| x.relative_error > relative_tolerance).collect()
+    }
+    pub fn passes(&self, relative_tolerance: f64) -> bool {
+        self.discrepancies(relative_tolerance).is_empty()
+    }
+}
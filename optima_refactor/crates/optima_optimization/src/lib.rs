@@ -1,6 +1,9 @@
 pub mod open;
 pub mod argmin;
 pub mod loss_functions;
+pub mod cmaes;
+pub mod gradient_checking;
+pub mod projections;
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(not(target_arch = "wasm64"))]
 #[cfg(feature = "include_nlopt")]
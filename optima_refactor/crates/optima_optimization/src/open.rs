@@ -1,9 +1,12 @@
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 use ad_trait::differentiable_block::DifferentiableBlock;
 use ad_trait::differentiable_function::{DerivativeMethodTrait, DifferentiableFunctionClass};
-use optimization_engine::core::SolverStatus;
+use optimization_engine::core::{ExitStatus, SolverStatus};
 use optimization_engine::panoc::{PANOCCache, PANOCOptimizer};
 use optimization_engine::{constraints, Optimizer, Problem, SolverError};
+use optima_console::progress::OProgressHandle;
 use optima_linalg::OVec;
 use optima_sampling::SimpleSampler;
 use crate::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
@@ -12,24 +15,42 @@ pub struct SimpleOpEnOptimizer {
     lower_bounds: Vec<f64>,
     upper_bounds: Vec<f64>,
     panoc_cache: Mutex<PANOCCache>,
+    progress: Option<OProgressHandle>
 }
 impl SimpleOpEnOptimizer {
     pub fn new(lower_bounds: Vec<f64>, upper_bounds: Vec<f64>, tolerance: f64) -> Self {
         assert_eq!(lower_bounds.len(), upper_bounds.len());
         let problem_size = lower_bounds.len();
-        Self { lower_bounds, upper_bounds, panoc_cache: Mutex::new(PANOCCache::new(problem_size, tolerance, 5)) }
+        Self { lower_bounds, upper_bounds, panoc_cache: Mutex::new(PANOCCache::new(problem_size, tolerance, 5)), progress: None }
+    }
+    /// Progress is reported once per gradient evaluation, as a fraction of PANOC's default
+    /// iteration limit.  If the handle is cancelled, the solve stops early and returns the most
+    /// recent iterate with an `ExitStatus::NotConvergedOutOfTime` status.
+    pub fn set_progress_handle(&mut self, progress: Option<OProgressHandle>) {
+        self.progress = progress;
+    }
+    #[inline(always)]
+    pub fn progress_handle(&self) -> &Option<OProgressHandle> {
+        &self.progress
     }
 }
 impl DiffBlockOptimizerTrait for SimpleOpEnOptimizer {
     type OutputType = Box<SimpleOpEnEngineOptimizerOutput>;
 
     fn optimize<'a, DC1, E1, DC2, E2, DC3, E3>(&self, initial_condition: &[f64], objective_function: &DifferentiableBlock<'a, DC1, E1>, _equality_constraint_function: &DifferentiableBlock<'a, DC2, E2>, _inequality_constraint_function: &DifferentiableBlock<'a, DC3, E3>) -> Self::OutputType where DC1: DifferentiableFunctionClass, DC2: DifferentiableFunctionClass, DC3: DifferentiableFunctionClass, E1: DerivativeMethodTrait, E2: DerivativeMethodTrait, E3: DerivativeMethodTrait {
-        simple_open_optimize(objective_function, initial_condition, &self.lower_bounds, &self.upper_bounds, &self.panoc_cache)
+        simple_open_optimize(objective_function, initial_condition, &self.lower_bounds, &self.upper_bounds, &self.panoc_cache, &self.progress)
     }
 }
 
-fn simple_open_optimize<'a, DC, E>(objective_function: &DifferentiableBlock<'a, DC, E>, init_condition: &[f64], lower_bounds: &Vec<f64>, upper_bounds: &Vec<f64>, cache: &Mutex<PANOCCache>) -> Box<SimpleOpEnEngineOptimizerOutput> where DC: DifferentiableFunctionClass, E: DerivativeMethodTrait {
+fn simple_open_optimize<'a, DC, E>(objective_function: &DifferentiableBlock<'a, DC, E>, init_condition: &[f64], lower_bounds: &Vec<f64>, upper_bounds: &Vec<f64>, cache: &Mutex<PANOCCache>, progress: &Option<OProgressHandle>) -> Box<SimpleOpEnEngineOptimizerOutput> where DC: DifferentiableFunctionClass, E: DerivativeMethodTrait {
+    let _span = tracing::debug_span!("open_optimize", num_dofs = init_condition.len()).entered();
+    let start = Instant::now();
+    let num_gradient_evaluations = AtomicUsize::new(0);
     let df = |u: &[f64], grad: &mut [f64]| -> Result<(), SolverError> {
+        if let Some(progress) = progress {
+            let n = num_gradient_evaluations.fetch_add(1, Ordering::Relaxed);
+            if !progress.report("optimization", n as f64 / OPEN_DEFAULT_MAX_ITERATIONS as f64) { return Err(SolverError::Cost); }
+        }
         let res = objective_function.derivative(u);
         let grad_as_slice = res.1.as_slice();
         assert_eq!(grad_as_slice.len(), grad.len());
@@ -54,7 +75,16 @@ fn simple_open_optimize<'a, DC, E>(objective_function: &DifferentiableBlock<'a,
     let s = SimpleSampler::uniform_samples(&vec![(-0.000001, 0.000001); init_condition.len()], None);
     let mut x = init_condition.to_vec().ovec_add(&s);
     // let mut x = init_condition.to_vec();
-    let solver_status = panoc.solve(x.as_mut_slice()).expect("error");
+    let solver_status = match panoc.solve(x.as_mut_slice()) {
+        Ok(solver_status) => { solver_status }
+        Err(SolverError::Cost) if progress.as_ref().map(|p| p.is_cancelled()).unwrap_or(false) => {
+            let cost = objective_function.call(&x)[0];
+            tracing::info!(iterations = num_gradient_evaluations.load(Ordering::Relaxed), cost, "optimization cancelled");
+            SolverStatus::new(ExitStatus::NotConvergedOutOfTime, num_gradient_evaluations.load(Ordering::Relaxed), start.elapsed(), f64::INFINITY, cost)
+        }
+        Err(e) => { panic!("error: {:?}", e) }
+    };
+    tracing::debug!(exit_status = ?solver_status.exit_status(), iterations = solver_status.iterations(), cost = solver_status.cost_value(), solve_time = ?solver_status.solve_time(), "optimization finished");
 
     Box::new(SimpleOpEnEngineOptimizerOutput {
         x_star: x,
@@ -62,6 +92,8 @@ fn simple_open_optimize<'a, DC, E>(objective_function: &DifferentiableBlock<'a,
     })
 }
 
+const OPEN_DEFAULT_MAX_ITERATIONS: usize = 100;
+
 #[derive(Clone, Debug)]
 pub struct SimpleOpEnEngineOptimizerOutput {
     x_star: Vec<f64>,
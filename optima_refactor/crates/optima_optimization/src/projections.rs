@@ -0,0 +1,95 @@
+use nalgebra::{DMatrix, DVector};
+
+/// Projects x onto the box defined by the given lower and upper bounds (element-wise clamping).
+pub fn project_onto_box(x: &[f64], lower_bounds: &[f64], upper_bounds: &[f64]) -> Vec<f64> {
+    let mut out = x.to_vec();
+    project_onto_box_in_place(&mut out, lower_bounds, upper_bounds);
+    out
+}
+
+pub fn project_onto_box_in_place(x: &mut [f64], lower_bounds: &[f64], upper_bounds: &[f64]) {
+    assert_eq!(x.len(), lower_bounds.len());
+    assert_eq!(x.len(), upper_bounds.len());
+    x.iter_mut().zip(lower_bounds.iter().zip(upper_bounds.iter())).for_each(|(x, (l, u))| {
+        *x = x.max(*l).min(*u);
+    });
+}
+
+/// Euclidean projection onto the affine set { x | A x = b }.  The factorization of A A^T is
+/// computed once on construction, so repeated projections (e.g., inside a projected gradient loop)
+/// only cost a few matrix-vector products.
+#[derive(Clone, Debug)]
+pub struct LinearEqualityProjector {
+    a: DMatrix<f64>,
+    b: DVector<f64>,
+    a_t_aat_inv: DMatrix<f64>
+}
+impl LinearEqualityProjector {
+    pub fn new(a: DMatrix<f64>, b: DVector<f64>) -> Self {
+        assert_eq!(a.nrows(), b.len());
+        let aat = &a * a.transpose();
+        let aat_inv = aat.clone().try_inverse().unwrap_or_else(|| aat.pseudo_inverse(1e-12).expect("error"));
+        let a_t_aat_inv = a.transpose() * aat_inv;
+        Self { a, b, a_t_aat_inv }
+    }
+    pub fn new_from_slices(a_row_major: &[f64], num_rows: usize, b: &[f64]) -> Self {
+        assert_eq!(a_row_major.len() % num_rows, 0);
+        let num_cols = a_row_major.len() / num_rows;
+        Self::new(DMatrix::from_row_slice(num_rows, num_cols, a_row_major), DVector::from_column_slice(b))
+    }
+    pub fn project(&self, x: &[f64]) -> Vec<f64> {
+        let x = DVector::from_column_slice(x);
+        let residual = &self.a * &x - &self.b;
+        let out = x - &self.a_t_aat_inv * residual;
+        out.as_slice().to_vec()
+    }
+    /// Projects a direction (e.g., a gradient) onto the null space of A so that moving along it
+    /// keeps A x = b satisfied.
+    pub fn project_direction(&self, d: &[f64]) -> Vec<f64> {
+        let d = DVector::from_column_slice(d);
+        let out = &d - &self.a_t_aat_inv * (&self.a * &d);
+        out.as_slice().to_vec()
+    }
+    pub fn residual_norm(&self, x: &[f64]) -> f64 {
+        let x = DVector::from_column_slice(x);
+        (&self.a * x - &self.b).norm()
+    }
+    #[inline(always)]
+    pub fn a(&self) -> &DMatrix<f64> {
+        &self.a
+    }
+    #[inline(always)]
+    pub fn b(&self) -> &DVector<f64> {
+        &self.b
+    }
+}
+
+pub fn project_onto_linear_equality(x: &[f64], a: &DMatrix<f64>, b: &DVector<f64>) -> Vec<f64> {
+    LinearEqualityProjector::new(a.clone(), b.clone()).project(x)
+}
+
+/// Projects onto the intersection of a box and an affine set using Dykstra's alternating projection
+/// algorithm.  Stops when successive iterates move less than `tolerance`.
+pub fn project_onto_box_and_linear_equality(x: &[f64], lower_bounds: &[f64], upper_bounds: &[f64], projector: &LinearEqualityProjector, max_iter: usize, tolerance: f64) -> Vec<f64> {
+    let n = x.len();
+    let mut curr = x.to_vec();
+    let mut p = vec![0.0; n];
+    let mut q = vec![0.0; n];
+
+    for _ in 0..max_iter {
+        let prev = curr.clone();
+
+        let y_in: Vec<f64> = curr.iter().zip(p.iter()).map(|(c, p)| c + p).collect();
+        let y = projector.project(&y_in);
+        p = y_in.iter().zip(y.iter()).map(|(a, b)| a - b).collect();
+
+        let z_in: Vec<f64> = y.iter().zip(q.iter()).map(|(y, q)| y + q).collect();
+        curr = project_onto_box(&z_in, lower_bounds, upper_bounds);
+        q = z_in.iter().zip(curr.iter()).map(|(a, b)| a - b).collect();
+
+        let change = curr.iter().zip(prev.iter()).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+        if change < tolerance { break; }
+    }
+
+    curr
+}
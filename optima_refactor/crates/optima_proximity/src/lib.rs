@@ -6,5 +6,6 @@ pub mod shapes;
 pub mod pair_group_queries;
 pub mod shape_scene;
 pub mod proxima;
+pub mod ray_casting;
 
 pub extern crate parry_ad;
\ No newline at end of file
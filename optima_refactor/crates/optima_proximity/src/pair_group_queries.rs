@@ -70,11 +70,12 @@ impl<'a, T: AD, Q: OPairGroupQryTrait> OwnedPairGroupQry<'a, T, Q> {
         Self { args }
     }
     pub fn query<S: OPairSkipsTrait, A: OPairAverageDistanceTrait<T>, P: O3DPose<T>>(&self, shape_group_a: &Vec<<Q::ShapeCategory as ShapeCategoryTrait>::ShapeType<T, P>>, shape_group_b: &Vec<<Q::ShapeCategory as ShapeCategoryTrait>::ShapeType<T, P>>, poses_a: &Vec<P>, poses_b: &Vec<P>, pair_selector: &Q::SelectorType, pair_skips: &S, pair_average_distances: &A, freeze: bool) -> <Q::OutputCategory as OPairGroupQryOutputCategoryTrait>::Output<T, P> {
+        let _span = tracing::trace_span!("pair_group_query", query = std::any::type_name::<Q>(), num_shapes_a = shape_group_a.len(), num_shapes_b = shape_group_b.len()).entered();
         Q::query(shape_group_a, shape_group_b, poses_a, poses_b, pair_selector, pair_skips, pair_average_distances, freeze, &self.args)
     }
     pub fn to_other_ad_type<T1: AD>(&self) -> OwnedPairGroupQry<'a, T1, Q> {
         let json_str = self.to_json_string();
-        OwnedPairGroupQry::<'a, T1, Q>::from_json_string(&json_str)
+        OwnedPairGroupQry::<'a, T1, Q>::from_json_string_unchecked(&json_str)
         // let new_args: <Q::ArgsCategory as PairGroupQryArgsCategory>::Args<'a, T1> = <Q::ArgsCategory as PairGroupQryArgsCategory>::ArgsConverterType::convert_to_other_ad_type::<T, T1>(&self.args);
         // OwnedPairGroupQry::<'a, T1, Q>::new(new_args)
     }
@@ -437,7 +438,7 @@ impl ADConvertableTrait for PairGroupQryArgsCategoryParryDistanceConverter {
 
     fn convert_to_other_ad_type<T1: AD, T2: AD>(input: &Self::ConvertableType<T1>) -> Self::ConvertableType<T2> {
         let json_str = input.to_json_string();
-        Self::ConvertableType::<T2>::from_json_string(&json_str)
+        Self::ConvertableType::<T2>::from_json_string_unchecked(&json_str)
     }
 }
 */
@@ -592,7 +593,7 @@ impl ADConvertableTrait for PairGroupQryArgsCategoryParryContactConverter {
 
     fn convert_to_other_ad_type<T1: AD, T2: AD>(input: &Self::ConvertableType<T1>) -> Self::ConvertableType<T2> {
         let json_str = input.to_json_string();
-        Self::ConvertableType::<T2>::from_json_string(&json_str)
+        Self::ConvertableType::<T2>::from_json_string_unchecked(&json_str)
     }
 }
 */
@@ -955,7 +956,7 @@ impl ADConvertableTrait for PairGroupQryArgsCategoryParryDistanceFilterConverter
 
     fn convert_to_other_ad_type<T1: AD, T2: AD>(input: &Self::ConvertableType<T1>) -> Self::ConvertableType<T2> {
         let json_str = input.to_json_string();
-        Self::ConvertableType::<T2>::from_json_string(&json_str)
+        Self::ConvertableType::<T2>::from_json_string_unchecked(&json_str)
     }
 }
 */
@@ -1266,7 +1267,7 @@ impl ADConvertableTrait for PairGroupQryArgsCategoryParryDistanceSequenceFilterC
 
     fn convert_to_other_ad_type<T1: AD, T2: AD>(input: &Self::ConvertableType<T1>) -> Self::ConvertableType<T2> {
         let json_str = input.to_json_string();
-        Self::ConvertableType::<T2>::from_json_string(&json_str)
+        Self::ConvertableType::<T2>::from_json_string_unchecked(&json_str)
     }
 }
 */
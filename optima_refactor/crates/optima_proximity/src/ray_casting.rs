@@ -0,0 +1,305 @@
+use ad_trait::AD;
+use parry_ad::na::{Isometry3, Point3, Vector3};
+use parry_ad::query::{PointQuery, Ray, RayCast};
+use parry_ad::shape::Shape;
+use optima_3d_spatial::optima_3d_pose::O3DPose;
+use optima_3d_spatial::optima_3d_rotation::O3DRotation;
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_error::OptimaError;
+use crate::shapes::{OParryShape, OParryShpTrait};
+
+#[derive(Clone, Debug)]
+pub struct ORayHit<T: AD> {
+    /// index of the hit shape, in the order shapes were added to the `OParryRayCaster`.
+    pub shape_idx: usize,
+    pub toi: T,
+    pub point: Point3<T>,
+    pub normal: Vector3<T>
+}
+impl<T: AD> ORayHit<T> {
+    pub fn to_other_ad_type<T1: AD>(&self) -> ORayHit<T1> {
+        ORayHit { shape_idx: self.shape_idx, toi: T1::constant(self.toi.to_constant()), point: point_to_other_ad_type(&self.point), normal: vector_to_other_ad_type(&self.normal) }
+    }
+}
+
+/// Casts rays against the base shapes of one or more shape scenes at fixed poses.  Shape isometries
+/// are computed once when shapes are added, so many rays can be cast cheaply against the same
+/// configuration.
+#[derive(Clone)]
+pub struct OParryRayCaster<'a, T: AD> {
+    shapes: Vec<(usize, &'a dyn Shape<T>, Isometry3<T>)>,
+    num_shapes: usize
+}
+impl<'a, T: AD> OParryRayCaster<'a, T> {
+    pub fn new() -> Self {
+        Self { shapes: vec![], num_shapes: 0 }
+    }
+    /// Adds every shape at its pose.  Returns an error (and adds nothing) if there is not exactly one
+    /// pose per shape.
+    pub fn add_shapes<P: O3DPose<T>>(&mut self, shapes: &'a Vec<OParryShape<T, P>>, poses: &Vec<P>) -> Result<(), OptimaError> {
+        if shapes.len() != poses.len() {
+            return Err(OptimaError::InvalidInput(format!("got {} poses for {} shapes.", poses.len(), shapes.len())));
+        }
+        for (shape, pose) in shapes.iter().zip(poses.iter()) {
+            let base_shape = shape.base_shape().base_shape();
+            self.shapes.push((self.num_shapes, base_shape.boxed_shape().shape().as_ref(), base_shape.get_isometry3_cow(pose).into_owned()));
+            self.num_shapes += 1;
+        }
+        Ok(())
+    }
+    /// Adds a single parry shape at `pose`, e.g., a triangle mesh that is not part of any shape scene.
+    /// Returns the shape index reported in its hits.
+    pub fn add_shape(&mut self, shape: &'a dyn Shape<T>, pose: Isometry3<T>) -> usize {
+        let shape_idx = self.num_shapes;
+        self.shapes.push((shape_idx, shape, pose));
+        self.num_shapes += 1;
+        shape_idx
+    }
+    /// Stops casting against shapes that contain `point`, e.g., so a sensor mounted on the surface of
+    /// a link is not blocked by the link itself.  Shape indices of the remaining shapes do not change.
+    pub fn remove_shapes_containing_point(&mut self, point: &Point3<T>) {
+        self.shapes.retain(|(_, shape, iso)| !shape.contains_point(iso, point));
+    }
+    /// Closest hit along the ray within `max_toi`.  `toi` is in units of `dir`'s length.
+    pub fn cast_ray(&self, origin: &Point3<T>, dir: &Vector3<T>, max_toi: T) -> Option<ORayHit<T>> {
+        self.cast_ray_in_range(origin, dir, T::zero(), max_toi)
+    }
+    /// Closest hit along the ray with `min_toi <= toi <= max_toi`, i.e., surfaces closer than
+    /// `min_toi` are ignored and the ray continues past them.  A ray that is inside a shape at
+    /// `min_toi` hits it there.
+    pub fn cast_ray_in_range(&self, origin: &Point3<T>, dir: &Vector3<T>, min_toi: T, max_toi: T) -> Option<ORayHit<T>> {
+        if min_toi > max_toi { return None; }
+        let ray = Ray::new(origin.clone(), dir.clone());
+        // the ray is restarted at min_toi, so tois along it are offset by min_toi.
+        let shifted_ray = Ray::new(ray.point_at(min_toi), dir.clone());
+        let mut out: Option<ORayHit<T>> = None;
+        for (shape_idx, shape, iso) in self.shapes.iter() {
+            let max_toi = match &out { None => { max_toi } Some(hit) => { hit.toi } };
+            if let Some(intersection) = shape.cast_ray_and_get_normal(iso, &shifted_ray, max_toi - min_toi, true) {
+                let toi = intersection.toi + min_toi;
+                out = Some(ORayHit { shape_idx: *shape_idx, toi, point: ray.point_at(toi), normal: intersection.normal });
+            }
+        }
+        out
+    }
+}
+
+/// A lidar's beam pattern, in the sensor frame (x forward, z up).  With a single vertical beam this
+/// is a planar (2D) scanner; otherwise beams are spread over `vertical_fov` as well.  Angles are in
+/// radians.
+#[derive(Clone, Debug)]
+pub struct OLidarConfig {
+    pub horizontal_fov: f64,
+    pub num_horizontal_beams: usize,
+    pub vertical_fov: f64,
+    pub num_vertical_beams: usize,
+    pub min_range: f64,
+    pub max_range: f64
+}
+impl OLidarConfig {
+    pub fn new_2d(horizontal_fov: f64, num_horizontal_beams: usize, max_range: f64) -> Self {
+        Self { horizontal_fov, num_horizontal_beams, vertical_fov: 0.0, num_vertical_beams: 1, min_range: 0.0, max_range }
+    }
+    pub fn new_3d(horizontal_fov: f64, num_horizontal_beams: usize, vertical_fov: f64, num_vertical_beams: usize, max_range: f64) -> Self {
+        Self { horizontal_fov, num_horizontal_beams, vertical_fov, num_vertical_beams, min_range: 0.0, max_range }
+    }
+    #[inline(always)]
+    pub fn num_beams(&self) -> usize {
+        self.num_horizontal_beams * self.num_vertical_beams
+    }
+    /// Unit beam directions in the sensor frame, row by row from the lowest elevation up, each row
+    /// sweeping counterclockwise (i.e., from right to left).
+    pub fn beam_directions<T: AD>(&self) -> Vec<Vector3<T>> {
+        let mut out = vec![];
+        let yaws = beam_angles(self.horizontal_fov, self.num_horizontal_beams, true);
+        let pitches = beam_angles(self.vertical_fov, self.num_vertical_beams, false);
+        for pitch in &pitches {
+            for yaw in &yaws {
+                out.push(Vector3::new(T::constant(pitch.cos() * yaw.cos()), T::constant(pitch.cos() * yaw.sin()), T::constant(pitch.sin())));
+            }
+        }
+        out
+    }
+    /// Casts every beam from the sensor at `sensor_pose` (in the same frame as the caster's shapes).
+    pub fn scan<T: AD, P: O3DPose<T>>(&self, caster: &OParryRayCaster<T>, sensor_pose: &P) -> OLidarScan<T> {
+        let t = sensor_pose.translation();
+        let origin = Point3::new(t.x(), t.y(), t.z());
+        let min_range = T::constant(self.min_range);
+
+        let mut directions = vec![];
+        let mut hits = vec![];
+        for dir in self.beam_directions::<T>() {
+            let dir = sensor_pose.rotation().mul_by_point_generic(&dir);
+            // surfaces closer than min_range are not seen (e.g., the sensor's own housing), so the beam
+            // reports the next surface behind them.
+            let hit = caster.cast_ray_in_range(&origin, &dir, min_range, T::constant(self.max_range));
+            directions.push(dir);
+            hits.push(hit);
+        }
+
+        OLidarScan { origin, directions, hits, max_range: T::constant(self.max_range) }
+    }
+}
+
+fn beam_angles(fov: f64, num_beams: usize, wrap_around: bool) -> Vec<f64> {
+    if num_beams <= 1 { return vec![0.0]; }
+    // a full circle would otherwise cast its first and last beams in the same direction.
+    let full_circle = wrap_around && fov >= 2.0 * std::f64::consts::PI - 1e-9;
+    let step = if full_circle { fov / num_beams as f64 } else { fov / (num_beams - 1) as f64 };
+    (0..num_beams).map(|i| -fov / 2.0 + step * i as f64).collect()
+}
+
+/// One lidar sweep, in world space.  `directions` and `hits` are in the order of
+/// `OLidarConfig::beam_directions`.
+#[derive(Clone, Debug)]
+pub struct OLidarScan<T: AD> {
+    pub origin: Point3<T>,
+    pub directions: Vec<Vector3<T>>,
+    pub hits: Vec<Option<ORayHit<T>>>,
+    pub max_range: T
+}
+impl<T: AD> OLidarScan<T> {
+    /// Range of every beam, where beams that hit nothing read as `max_range`.
+    pub fn ranges(&self) -> Vec<T> {
+        self.hits.iter().map(|x| match x { None => { self.max_range } Some(hit) => { hit.toi } }).collect()
+    }
+    pub fn hit_points(&self) -> Vec<Point3<T>> {
+        self.hits.iter().filter_map(|x| x.as_ref().map(|hit| hit.point)).collect()
+    }
+    pub fn to_other_ad_type<T1: AD>(&self) -> OLidarScan<T1> {
+        OLidarScan {
+            origin: point_to_other_ad_type(&self.origin),
+            directions: self.directions.iter().map(|x| vector_to_other_ad_type(x)).collect(),
+            hits: self.hits.iter().map(|x| x.as_ref().map(|hit| hit.to_other_ad_type())).collect(),
+            max_range: T1::constant(self.max_range.to_constant()),
+        }
+    }
+}
+
+fn point_to_other_ad_type<T: AD, T1: AD>(p: &Point3<T>) -> Point3<T1> {
+    Point3::new(T1::constant(p.x.to_constant()), T1::constant(p.y.to_constant()), T1::constant(p.z.to_constant()))
+}
+
+fn vector_to_other_ad_type<T: AD, T1: AD>(v: &Vector3<T>) -> Vector3<T1> {
+    Vector3::new(T1::constant(v.x.to_constant()), T1::constant(v.y.to_constant()), T1::constant(v.z.to_constant()))
+}
+
+#[cfg(test)]
+mod tests {
+    use parry_ad::shape::{Ball, Cuboid};
+    use super::*;
+
+    fn at(x: f64, y: f64, z: f64) -> Isometry3<f64> {
+        Isometry3::translation(x, y, z)
+    }
+
+    fn two_boxes() -> Vec<OParryShape<f64, Isometry3<f64>>> {
+        vec![OParryShape::new_default(Cuboid::new(Vector3::new(0.5, 0.5, 0.5)), Isometry3::identity()),
+             OParryShape::new_default(Cuboid::new(Vector3::new(0.5, 0.5, 0.5)), Isometry3::identity())]
+    }
+
+    #[test]
+    fn cast_ray_returns_the_closest_hit() {
+        let shapes = two_boxes();
+        let mut caster = OParryRayCaster::new();
+        caster.add_shapes(&shapes, &vec![at(5.0, 0.0, 0.0), at(2.0, 0.0, 0.0)]).expect("error");
+
+        let hit = caster.cast_ray(&Point3::origin(), &Vector3::x(), 10.0).expect("error");
+        assert_eq!(hit.shape_idx, 1);
+        assert!((hit.toi - 1.5).abs() < 1e-9);
+        assert!((hit.normal - Vector3::new(-1.0, 0.0, 0.0)).norm() < 1e-9);
+        assert!(caster.cast_ray(&Point3::origin(), &Vector3::x(), 1.0).is_none());
+        assert!(caster.cast_ray(&Point3::origin(), &Vector3::y(), 10.0).is_none());
+    }
+
+    #[test]
+    fn add_shapes_rejects_mismatched_poses() {
+        let shapes = two_boxes();
+        let mut caster = OParryRayCaster::new();
+        assert!(caster.add_shapes(&shapes, &vec![at(2.0, 0.0, 0.0)]).is_err());
+        assert!(caster.cast_ray(&Point3::origin(), &Vector3::x(), 10.0).is_none());
+    }
+
+    #[test]
+    fn add_shape_indices_continue_after_scenes() {
+        let shapes = two_boxes();
+        let ball = Ball::new(0.5);
+        let mut caster = OParryRayCaster::new();
+        caster.add_shapes(&shapes, &vec![at(5.0, 0.0, 0.0), at(8.0, 0.0, 0.0)]).expect("error");
+        assert_eq!(caster.add_shape(&ball, at(2.0, 0.0, 0.0)), 2);
+        assert_eq!(caster.cast_ray(&Point3::origin(), &Vector3::x(), 10.0).expect("error").shape_idx, 2);
+    }
+
+    #[test]
+    fn remove_shapes_containing_point_keeps_shape_indices() {
+        let shapes = two_boxes();
+        let mut caster = OParryRayCaster::new();
+        caster.add_shapes(&shapes, &vec![at(0.0, 0.0, 0.0), at(3.0, 0.0, 0.0)]).expect("error");
+        caster.remove_shapes_containing_point(&Point3::origin());
+
+        let hit = caster.cast_ray(&Point3::origin(), &Vector3::x(), 10.0).expect("error");
+        assert_eq!(hit.shape_idx, 1);
+        assert!((hit.toi - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scan_sees_past_surfaces_closer_than_min_range() {
+        let shapes = two_boxes();
+        let mut caster = OParryRayCaster::new();
+        // a box right in front of the sensor (closer than min_range) and one further away.
+        caster.add_shapes(&shapes, &vec![at(1.0, 0.0, 0.0), at(5.0, 0.0, 0.0)]).expect("error");
+
+        let mut config = OLidarConfig::new_2d(0.0, 1, 10.0);
+        config.min_range = 2.0;
+        let scan = config.scan(&caster, &Isometry3::identity());
+        let hit = scan.hits[0].as_ref().expect("error");
+        assert_eq!(hit.shape_idx, 1);
+        assert!((hit.toi - 4.5).abs() < 1e-9);
+
+        config.min_range = 0.0;
+        let scan = config.scan(&caster, &Isometry3::identity());
+        assert_eq!(scan.hits[0].as_ref().expect("error").shape_idx, 0);
+    }
+
+    #[test]
+    fn scan_min_range_inside_a_shape_hits_it_at_min_range() {
+        let shapes = two_boxes();
+        let mut caster = OParryRayCaster::new();
+        caster.add_shapes(&shapes, &vec![at(2.0, 0.0, 0.0), at(5.0, 0.0, 0.0)]).expect("error");
+
+        let mut config = OLidarConfig::new_2d(0.0, 1, 10.0);
+        config.min_range = 2.0;
+        let hit = config.scan(&caster, &Isometry3::identity()).hits[0].clone().expect("error");
+        assert_eq!(hit.shape_idx, 0);
+        assert!((hit.toi - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn beam_directions_cover_the_fov() {
+        let config = OLidarConfig::new_3d(std::f64::consts::PI, 3, 0.2, 2, 10.0);
+        let directions = config.beam_directions::<f64>();
+        assert_eq!(directions.len(), config.num_beams());
+        directions.iter().for_each(|x| assert!((x.norm() - 1.0).abs() < 1e-9));
+        // lowest row first, sweeping from right (-y) to left (+y).
+        assert!(directions[0].z < 0.0 && directions[0].y < 0.0);
+        assert!(directions[2].y > 0.0);
+        assert!(directions[3].z > 0.0);
+
+        let full_circle = OLidarConfig::new_2d(2.0 * std::f64::consts::PI, 4, 10.0).beam_directions::<f64>();
+        assert!((full_circle[0] - full_circle[3]).norm() > 1e-3);
+    }
+
+    #[test]
+    fn ranges_report_max_range_for_misses() {
+        let shapes = two_boxes();
+        let mut caster = OParryRayCaster::new();
+        caster.add_shapes(&shapes, &vec![at(3.0, 0.0, 0.0), at(30.0, 0.0, 0.0)]).expect("error");
+
+        let scan = OLidarConfig::new_2d(std::f64::consts::PI, 3, 10.0).scan(&caster, &Isometry3::identity());
+        let ranges = scan.ranges();
+        assert_eq!(ranges.len(), 3);
+        assert!((ranges[1] - 2.5).abs() < 1e-9);
+        assert_eq!(ranges[0], 10.0);
+        assert_eq!(scan.hit_points().len(), 1);
+    }
+}
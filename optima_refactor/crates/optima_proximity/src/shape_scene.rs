@@ -3,7 +3,10 @@ use ad_trait::AD;
 use as_any::AsAny;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
+use optima_3d_mesh::ToTriMesh;
+use optima_3d_mesh::scene_export::OSceneExport;
 use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_error::OptimaError;
 use crate::pair_group_queries::{OPairSkipsTrait, OSkipReason};
 use crate::shapes::OParryShape;
 use optima_3d_spatial::optima_3d_pose::SerdeO3DPose;
@@ -56,7 +59,22 @@ impl<T: AD, P: O3DPose<T>> OParryGenericShapeScene<T, P> {
     }
     pub fn to_other_generic_types<T1: AD, C1: O3DPoseCategory>(&self) -> OParryGenericShapeScene<T1, C1::P<T1>> {
         let json_str = self.to_json_string();
-        OParryGenericShapeScene::<T1, C1::P<T1>>::from_json_string(&json_str)
+        OParryGenericShapeScene::<T1, C1::P<T1>>::from_json_string_unchecked(&json_str)
+    }
+    /// Adds each shape as a node (named `<name_prefix>_<idx>`) under `parent` at its current pose.
+    /// Returns the indices of the added nodes, or `IdxOutOfBounds` if `parent` is not in `scene`.
+    pub fn add_to_scene_export(&self, scene: &mut OSceneExport, parent: Option<usize>, name_prefix: &str, color: Option<[f64; 4]>) -> Result<Vec<usize>, OptimaError> {
+        if let Some(parent) = parent { OptimaError::check_idx("node", parent, scene.nodes().len())?; }
+        let mut out = vec![];
+        for (i, (shape, pose)) in self.shapes.iter().zip(self.poses.iter()).enumerate() {
+            let name = format!("{}_{}", name_prefix, i);
+            let base_shape = shape.base_shape().base_shape();
+            let mesh_idx = scene.add_mesh(&name, base_shape.to_trimesh(), color);
+            let node_idx = scene.add_node_from_pose(&name, parent, pose, None)?;
+            scene.add_node_from_pose(&format!("{}_mesh", name), Some(node_idx), base_shape.offset(), Some(mesh_idx))?;
+            out.push(node_idx);
+        }
+        Ok(out)
     }
 }
 impl<T: AD, P: O3DPose<T>> ShapeSceneTrait<T, P> for OParryGenericShapeScene<T, P> {
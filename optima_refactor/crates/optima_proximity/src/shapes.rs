@@ -5,12 +5,12 @@ use std::ops::{Mul};
 use std::time::{Instant};
 use ad_trait::AD;
 use parry_ad::na::{Isometry3, Point3, Vector3};
-use parry_ad::shape::{Ball, ConvexPolyhedron, Cuboid, Shape, TypedShape};
+use parry_ad::shape::{Ball, Capsule, Cone, ConvexPolyhedron, Cuboid, Cylinder, Shape, TypedShape};
 use parry_ad::transformation::vhacd::{VHACD, VHACDParameters};
 use serde::ser::SerializeTuple;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::{SeqAccess, Visitor};
-use optima_3d_mesh::{OTriMesh};
+use optima_3d_mesh::{OTriMesh, ToTriMesh};
 use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
 use optima_3d_spatial::optima_3d_vec::{O3DVec};
 use optima_linalg::OVec;
@@ -18,6 +18,8 @@ use optima_sampling::SimpleSampler;
 use serde_with::*;
 use ad_trait::SerdeAD;
 use optima_3d_spatial::optima_3d_pose::SerdeO3DPose;
+use optima_file::cache::{OAssetCache, OAssetCacheKey};
+use optima_error::OptimaError;
 use optima_file::path::OStemCellPath;
 use optima_file::traits::{FromJsonString, ToJsonString};
 use crate::pair_queries::{ParryContactOutput, ParryDisMode, ParryDistanceOutput, ParryIntersectOutput, ParryOutputAuxData, ParryQryShapeType, ParryShapeRep};
@@ -166,41 +168,65 @@ impl<T: AD, P: O3DPose<T>> OParryShape<T, P> {
     pub fn new_default_with_path_option<S: Shape<T>>(shape: S, offset: P, path: Option<OStemCellPath>) -> Self {
         Self::new_with_path_option(shape, offset, path, true, true)
     }
-    pub fn new_convex_shape_from_mesh_paths(trimesh_path: OStemCellPath, offset: P, convex_subcomponents_paths: Option<Vec<OStemCellPath>>, compute_max_dis_from_origin_to_point_on_shape: bool, compute_bounding_shape_errors: bool) -> Self {
-        let trimesh = OTriMesh::try_to_get_trimesh_from_path(&trimesh_path).expect("error");
-
-        let points = trimesh.points_to_point3s::<T>();
-        // let indices = trimesh.indices_as_u32s();
-
-        // let t = TriMesh::new(points, indices);
-        let convex_polyhedron = ConvexPolyhedron::from_convex_hull(&points).expect("error");
+    /// Errors if a mesh cannot be loaded or does not have a convex hull (e.g., it is flat).
+    pub fn new_convex_shape_from_mesh_paths(trimesh_path: OStemCellPath, offset: P, convex_subcomponents_paths: Option<Vec<OStemCellPath>>, compute_max_dis_from_origin_to_point_on_shape: bool, compute_bounding_shape_errors: bool) -> Result<Self, OptimaError> {
+        let convex_polyhedron = load_convex_polyhedron_from_mesh_path::<T>(&trimesh_path)?;
 
         match &convex_subcomponents_paths {
             None => {
-                Self::new_default_with_path_option(convex_polyhedron, offset, Some(trimesh_path.clone()))
+                Ok(Self::new_default_with_path_option(convex_polyhedron, offset, Some(trimesh_path.clone())))
             }
             Some(convex_subcomponents) => {
-                // let convex_polyhedron = ConvexPolyhedron::from_convex_hull(&trimesh.to_convex_hull().points_to_point3s()).expect("error");
-
                 let mut s = vec![];
 
-                convex_subcomponents.iter().for_each(|x| {
-                    let trimesh = OTriMesh::try_to_get_trimesh_from_path(x).expect("error");
-                    let points = trimesh.points_to_point3s::<T>();
-                    let convex_polyhedron_subcomponent = ConvexPolyhedron::from_convex_hull(&points).expect("error");
+                for x in convex_subcomponents {
+                    let convex_polyhedron_subcomponent = load_convex_polyhedron_from_mesh_path::<T>(x)?;
                     s.push(OParryShpGenericHierarchy::new(convex_polyhedron_subcomponent, offset.clone(), Some(x.clone()), compute_max_dis_from_origin_to_point_on_shape, compute_bounding_shape_errors));
-                });
+                }
 
-                Self {
+                Ok(Self {
                     base_shape: OParryShpGenericHierarchy::new(convex_polyhedron, offset, Some(trimesh_path.clone()), compute_max_dis_from_origin_to_point_on_shape, compute_bounding_shape_errors),
                     convex_subcomponents: s,
-                }
+                })
             }
         }
     }
-    pub fn new_default_convex_shape_from_mesh_paths(trimesh_path: OStemCellPath, offset: P, convex_subcomponents_paths: Option<Vec<OStemCellPath>>) -> Self {
+    pub fn new_convex_shape_from_mesh_paths_unchecked(trimesh_path: OStemCellPath, offset: P, convex_subcomponents_paths: Option<Vec<OStemCellPath>>, compute_max_dis_from_origin_to_point_on_shape: bool, compute_bounding_shape_errors: bool) -> Self {
+        Self::new_convex_shape_from_mesh_paths(trimesh_path, offset, convex_subcomponents_paths, compute_max_dis_from_origin_to_point_on_shape, compute_bounding_shape_errors).expect("error")
+    }
+    pub fn new_default_convex_shape_from_mesh_paths(trimesh_path: OStemCellPath, offset: P, convex_subcomponents_paths: Option<Vec<OStemCellPath>>) -> Result<Self, OptimaError> {
         Self::new_convex_shape_from_mesh_paths(trimesh_path, offset, convex_subcomponents_paths, true, true)
     }
+    /// Same as `new_convex_shape_from_mesh_paths`, but reuses the bounding shapes and error bounds
+    /// computed for identical meshes on a previous run, which is most of the construction time.
+    /// Shape ids are resampled on every call, so a cached entry can be shared by several links.
+    pub fn new_convex_shape_from_mesh_paths_cached(trimesh_path: OStemCellPath, offset: P, convex_subcomponents_paths: Option<Vec<OStemCellPath>>, compute_max_dis_from_origin_to_point_on_shape: bool, compute_bounding_shape_errors: bool, cache: &OAssetCache) -> Result<Self, OptimaError> {
+        let mut sources = vec![&trimesh_path];
+        if let Some(convex_subcomponents_paths) = &convex_subcomponents_paths { sources.extend(convex_subcomponents_paths.iter()); }
+        let params = format!("subcomponents={},max_dis={},errors={},offset={}", convex_subcomponents_paths.is_some(), compute_max_dis_from_origin_to_point_on_shape, compute_bounding_shape_errors, offset.to_json_string());
+        let key = OAssetCacheKey::new_from_files("parry_convex_shape", &params, &sources)?;
+
+        let mut out = match cache.get_object::<Self>(&key) {
+            Some(out) => { out }
+            None => {
+                let out = Self::new_convex_shape_from_mesh_paths(trimesh_path.clone(), offset.clone(), convex_subcomponents_paths.clone(), compute_max_dis_from_origin_to_point_on_shape, compute_bounding_shape_errors)?;
+                let _ = cache.put_object(&key, &out);
+                out
+            }
+        };
+
+        // the entry may have been written for a different file with the same contents.
+        out.base_shape.base_shape.shape.path = Some(trimesh_path);
+        if let Some(convex_subcomponents_paths) = convex_subcomponents_paths {
+            out.convex_subcomponents.iter_mut().zip(convex_subcomponents_paths).for_each(|(x, path)| { x.base_shape.shape.path = Some(path); });
+        }
+        out.resample_all_ids();
+
+        Ok(out)
+    }
+    pub fn new_default_convex_shape_from_mesh_paths_cached(trimesh_path: OStemCellPath, offset: P, convex_subcomponents_paths: Option<Vec<OStemCellPath>>, cache: &OAssetCache) -> Result<Self, OptimaError> {
+        Self::new_convex_shape_from_mesh_paths_cached(trimesh_path, offset, convex_subcomponents_paths, true, true, cache)
+    }
     pub fn new_convex_shape_from_trimesh(trimesh: OTriMesh, offset: P, convex_subcomponents: Option<Vec<OTriMesh>>, compute_max_dis_from_origin_to_point_on_shape: bool, compute_bounding_shape_errors: bool) -> Self {
         let points = trimesh.points_to_point3s::<T>();
         // let indices = trimesh.indices_as_u32s();
@@ -232,6 +258,28 @@ impl<T: AD, P: O3DPose<T>> OParryShape<T, P> {
     pub fn new_default_convex_shape_from_trimesh(trimesh: OTriMesh, offset: P, convex_subcomponents: Option<Vec<OTriMesh>>) -> Self {
         Self::new_convex_shape_from_trimesh(trimesh, offset, convex_subcomponents, true, true)
     }
+    /// Capsule whose segment runs along the local y axis from `-half_height` to `half_height`, as
+    /// with parry's primitives; use `offset` to align it with another axis.
+    pub fn new_capsule(half_height: T, radius: T, offset: P, compute_max_dis_from_origin_to_point_on_shape: bool, compute_bounding_shape_errors: bool) -> Self {
+        Self::new(Capsule::new_y(half_height, radius), offset, compute_max_dis_from_origin_to_point_on_shape, compute_bounding_shape_errors)
+    }
+    pub fn new_default_capsule(half_height: T, radius: T, offset: P) -> Self {
+        Self::new_capsule(half_height, radius, offset, true, true)
+    }
+    /// Cylinder along the local y axis, centered at the origin.
+    pub fn new_cylinder(half_height: T, radius: T, offset: P, compute_max_dis_from_origin_to_point_on_shape: bool, compute_bounding_shape_errors: bool) -> Self {
+        Self::new(Cylinder::new(half_height, radius), offset, compute_max_dis_from_origin_to_point_on_shape, compute_bounding_shape_errors)
+    }
+    pub fn new_default_cylinder(half_height: T, radius: T, offset: P) -> Self {
+        Self::new_cylinder(half_height, radius, offset, true, true)
+    }
+    /// Cone along the local y axis with its base at `-half_height` and its apex at `half_height`.
+    pub fn new_cone(half_height: T, radius: T, offset: P, compute_max_dis_from_origin_to_point_on_shape: bool, compute_bounding_shape_errors: bool) -> Self {
+        Self::new(Cone::new(half_height, radius), offset, compute_max_dis_from_origin_to_point_on_shape, compute_bounding_shape_errors)
+    }
+    pub fn new_default_cone(half_height: T, radius: T, offset: P) -> Self {
+        Self::new_cone(half_height, radius, offset, true, true)
+    }
     #[inline(always)]
     pub fn base_shape(&self) -> &OParryShpGenericHierarchy<T, P> {
         &self.base_shape
@@ -258,12 +306,12 @@ impl<T: AD, P: O3DPose<T>> OParryShape<T, P> {
     #[inline]
     pub fn to_other_ad_type<T1: AD>(&self) -> OParryShape<T1, <P::Category as O3DPoseCategory>::P<T1>> {
         let json_str = self.to_json_string();
-        OParryShape::<T1, <P::Category as O3DPoseCategory>::P<T1>>::from_json_string(&json_str)
+        OParryShape::<T1, <P::Category as O3DPoseCategory>::P<T1>>::from_json_string_unchecked(&json_str)
     }
     #[inline]
     pub fn to_other_generic_category<T1: AD, C1: O3DPoseCategory>(&self) -> OParryShape<T1, C1::P<T1>> {
         let json_str = self.to_json_string();
-        OParryShape::<T1, C1::P<T1>>::from_json_string(&json_str)
+        OParryShape::<T1, C1::P<T1>>::from_json_string_unchecked(&json_str)
     }
 }
 impl<T: AD, P: O3DPose<T>> OShpQryIntersectTrait<T, P, OParryShape<T, P>> for OParryShape<T, P> {
@@ -498,12 +546,12 @@ impl<T: AD, P: O3DPose<T>> OParryShpGenericHierarchy<T, P> {
     #[inline]
     pub fn to_other_ad_type<T1: AD>(&self) -> OParryShpGenericHierarchy<T1, <P::Category as O3DPoseCategory>::P<T1>> {
         let json_str = self.to_json_string();
-        OParryShpGenericHierarchy::<T1, <P::Category as O3DPoseCategory>::P<T1>>::from_json_string(&json_str)
+        OParryShpGenericHierarchy::<T1, <P::Category as O3DPoseCategory>::P<T1>>::from_json_string_unchecked(&json_str)
     }
     #[inline]
     pub fn to_other_generic_category<T1: AD, C1: O3DPoseCategory>(&self) -> OParryShpGenericHierarchy<T1, C1::P<T1>> {
         let json_str = self.to_json_string();
-        OParryShpGenericHierarchy::<T1, C1::P<T1>>::from_json_string(&json_str)
+        OParryShpGenericHierarchy::<T1, C1::P<T1>>::from_json_string_unchecked(&json_str)
     }
 }
 impl<T: AD, P: O3DPose<T>> OShpQryIntersectTrait<T, P,OParryShpGenericHierarchy<T, P>> for OParryShpGenericHierarchy<T, P> {
@@ -655,12 +703,21 @@ impl<T: AD, P: O3DPose<T>> OParryShpGeneric<T, P> {
     #[inline]
     pub fn to_other_ad_type<T1: AD>(&self) -> OParryShpGeneric<T1, <P::Category as O3DPoseCategory>::P<T1>> {
         let json_str = self.to_json_string();
-        OParryShpGeneric::<T1, <P::Category as O3DPoseCategory>::P<T1>>::from_json_string(&json_str)
+        OParryShpGeneric::<T1, <P::Category as O3DPoseCategory>::P<T1>>::from_json_string_unchecked(&json_str)
     }
     #[inline]
     pub fn to_other_generic_category<T1: AD, C1: O3DPoseCategory>(&self) -> OParryShpGeneric<T1, C1::P<T1>> {
         let json_str = self.to_json_string();
-        OParryShpGeneric::<T1, C1::P<T1>>::from_json_string(&json_str)
+        OParryShpGeneric::<T1, C1::P<T1>>::from_json_string_unchecked(&json_str)
+    }
+}
+/// The mesh is in the shape's own frame, i.e., `offset` is not applied.
+impl<T: AD, P: O3DPose<T>> ToTriMesh for OParryShpGeneric<T, P> {
+    fn to_trimesh(&self) -> OTriMesh {
+        let (points, indices) = get_vertices_and_indices_from_typed_shape(&self.shape.shape.as_typed_shape(), 20);
+        let points = points.iter().map(|x| [x.x.to_constant(), x.y.to_constant(), x.z.to_constant()]).collect();
+        let indices = indices.iter().map(|x| [x[0] as usize, x[1] as usize, x[2] as usize]).collect();
+        OTriMesh::new(points, indices)
     }
 }
 impl<T: AD, P: O3DPose<T>> Clone for OParryShpGeneric<T, P> {
@@ -793,7 +850,7 @@ impl<T: AD> BoxedShape<T> {
     #[inline]
     pub fn to_other_ad_type<T1: AD>(&self) -> BoxedShape<T1> {
         let json_str = self.to_json_string();
-        BoxedShape::<T1>::from_json_string(&json_str)
+        BoxedShape::<T1>::from_json_string_unchecked(&json_str)
     }
 }
 impl<T: AD> Clone for BoxedShape<T> {
@@ -818,7 +875,14 @@ impl<T: AD> Serialize for BoxedShape<T> {
                 tuple.serialize_element(&OVec::ovec_to_other_ad_type::<f64>(&s.half_extents))?;
                 tuple
             }
-            TypedShape::Capsule(_) => { panic!("shape not handled here") }
+            TypedShape::Capsule(s) => {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(&"capsule".to_string())?;
+                let a = OVec::ovec_to_other_ad_type::<f64>(&s.segment.a.coords);
+                let b = OVec::ovec_to_other_ad_type::<f64>(&s.segment.b.coords);
+                tuple.serialize_element(&(a, b, s.radius.to_constant()))?;
+                tuple
+            }
             TypedShape::Segment(_) => { panic!("shape not handled here") }
             TypedShape::Triangle(_) => { panic!("shape not handled here") }
             TypedShape::TriMesh(_) => { panic!("shape not handled here") }
@@ -845,8 +909,18 @@ impl<T: AD> Serialize for BoxedShape<T> {
                     }
                 }
             }
-            TypedShape::Cylinder(_) => { panic!("shape not handled here") }
-            TypedShape::Cone(_) => { panic!("shape not handled here") }
+            TypedShape::Cylinder(s) => {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(&"cylinder".to_string())?;
+                tuple.serialize_element(&[s.half_height.to_constant(), s.radius.to_constant()])?;
+                tuple
+            }
+            TypedShape::Cone(s) => {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(&"cone".to_string())?;
+                tuple.serialize_element(&[s.half_height.to_constant(), s.radius.to_constant()])?;
+                tuple
+            }
             TypedShape::RoundCuboid(_) => { panic!("shape not handled here") }
             TypedShape::RoundTriangle(_) => { panic!("shape not handled here") }
             TypedShape::RoundCylinder(_) => { panic!("shape not handled here") }
@@ -895,10 +969,10 @@ impl<'de, T: AD> Visitor<'de> for BoxedShapeVisitor<T> {
     }
 
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error> where A: SeqAccess<'de> {
-        let shape_type_str = seq.next_element::<String>().expect("error").expect("error");
+        let shape_type_str = seq.next_element::<String>()?.ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
 
         if shape_type_str == "ball" {
-            let radius = seq.next_element::<f64>().expect("error").expect("error");
+            let radius = seq.next_element::<f64>()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
             // let _path = seq.next_element::<Option<OStemCellPath>>().expect("error").expect("error");
             let ret = Ok(BoxedShape {
                 shape: Box::new(Ball::new(T::constant(radius))),
@@ -906,34 +980,53 @@ impl<'de, T: AD> Visitor<'de> for BoxedShapeVisitor<T> {
             });
             return ret;
         } else if shape_type_str == "cuboid" {
-            let half_extents = seq.next_element::<[f64; 3]>().expect("error").expect("error");
+            let half_extents = seq.next_element::<[f64; 3]>()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
             // let _path = seq.next_element::<Option<OStemCellPath>>().expect("error").expect("error");
             let half_extents = OVec::ovec_to_other_ad_type::<T>(&half_extents);
             return Ok(BoxedShape{
                 shape: Box::new(Cuboid::new(Vector3::new(half_extents[0], half_extents[1], half_extents[2]))),
                 path: None,
             })
+        } else if shape_type_str == "capsule" {
+            let (a, b, radius) = seq.next_element::<([f64; 3], [f64; 3], f64)>()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+            let a = OVec::ovec_to_other_ad_type::<T>(&a);
+            let b = OVec::ovec_to_other_ad_type::<T>(&b);
+            return Ok(BoxedShape{
+                shape: Box::new(Capsule::new(Point3::new(a[0], a[1], a[2]), Point3::new(b[0], b[1], b[2]), T::constant(radius))),
+                path: None,
+            })
+        } else if shape_type_str == "cylinder" {
+            let [half_height, radius] = seq.next_element::<[f64; 2]>()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+            return Ok(BoxedShape{
+                shape: Box::new(Cylinder::new(T::constant(half_height), T::constant(radius))),
+                path: None,
+            })
+        } else if shape_type_str == "cone" {
+            let [half_height, radius] = seq.next_element::<[f64; 2]>()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+            return Ok(BoxedShape{
+                shape: Box::new(Cone::new(T::constant(half_height), T::constant(radius))),
+                path: None,
+            })
         } else if shape_type_str == "convex_polyhedron_raw" {
             // let (points, _indices) = seq.next_element::<(Vec<[f64; 3]>, Vec<[u32; 3]>)>().expect("error").expect("error");
-            let points = seq.next_element::<Vec<[f64; 3]>>().expect("error").expect("error");
+            let points = seq.next_element::<Vec<[f64; 3]>>()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
             // let _path = seq.next_element::<Option<OStemCellPath>>().expect("error").expect("error");
             let points: Vec<Point3<T>> = points.iter().map(|x| Point3::new(T::constant(x[0]), T::constant(x[1]), T::constant(x[2]))).collect();
             // let convex_polyhedron = ConvexPolyhedron::from_convex_mesh(points, &indices).expect("error");
-            let convex_polyhedron = ConvexPolyhedron::from_convex_hull(&points).expect("error");
+            let convex_polyhedron = ConvexPolyhedron::from_convex_hull(&points).ok_or_else(|| serde::de::Error::custom("points do not have a convex hull"))?;
             return Ok(BoxedShape{
                 shape: Box::new(convex_polyhedron),
                 path: None,
             })
         } else if shape_type_str == "convex_polyhedron_from_file" {
-            let path = seq.next_element::<Option<OStemCellPath>>().expect("error").expect("error").unwrap();
-            let trimesh = OTriMesh::try_to_get_trimesh_from_path(&path).expect("error");
-            let convex_polyhedron = ConvexPolyhedron::from_convex_hull(&trimesh.points_to_point3s()).expect("error");
+            let path = seq.next_element::<Option<OStemCellPath>>()?.flatten().ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+            let convex_polyhedron = load_convex_polyhedron_from_mesh_path::<T>(&path).map_err(serde::de::Error::custom)?;
             return Ok(BoxedShape{
                 shape: Box::new(convex_polyhedron),
                 path: Some(path.clone()),
             })
         } else {
-            panic!("shape not supported");
+            return Err(serde::de::Error::unknown_variant(&shape_type_str, &["ball", "cuboid", "capsule", "cylinder", "cone", "convex_polyhedron_raw", "convex_polyhedron_from_file"]));
         }
     }
 }
@@ -1006,18 +1099,28 @@ pub (crate) fn get_bounding_sphere_from_shape<T: AD, S: Shape<T> + ?Sized, P: O3
     // OParryShpGeneric::new(sphere, offset, None)
 
     let ts = shape.as_typed_shape();
-    let (vertices, _) = get_vertices_and_indices_from_typed_shape(&ts, 30);
 
     let aabb = shape.compute_local_aabb();
     let mins = aabb.mins;
     let maxs = aabb.maxs;
     let center = mins.o3dvec_add(&maxs).o3dvec_scalar_mul(T::constant(0.5));
 
-    let mut radius = T::zero();
-    vertices.iter().for_each(|x| {
-        let dis = (x - center).norm();
-        if radius < dis { radius = dis; }
-    });
+    // primitives get exact radii about the aabb center; a tessellation can only underestimate them.
+    let radius = match &ts {
+        TypedShape::Ball(s) => { s.radius }
+        TypedShape::Capsule(s) => { s.half_height() + s.radius }
+        TypedShape::Cylinder(s) => { (s.half_height * s.half_height + s.radius * s.radius).sqrt() }
+        TypedShape::Cone(s) => { (s.half_height * s.half_height + s.radius * s.radius).sqrt() }
+        _ => {
+            let (vertices, _) = get_vertices_and_indices_from_typed_shape(&ts, 30);
+            let mut radius = T::zero();
+            vertices.iter().for_each(|x| {
+                let dis = (x - center).norm();
+                if radius < dis { radius = dis; }
+            });
+            radius
+        }
+    };
 
     let offset = offset.mul(&P::from_constructors(&center, &[T::zero();3]));
     let sphere = Ball::new(radius);
@@ -1164,3 +1267,9 @@ pub (crate) fn calculate_max_dis_from_origin_to_point_on_shape<T: AD, S: Shape<T
     max
 }
 
+
+fn load_convex_polyhedron_from_mesh_path<T: AD>(path: &OStemCellPath) -> Result<ConvexPolyhedron<T>, OptimaError> {
+    let trimesh = OTriMesh::try_to_get_trimesh_from_path(path).ok_or_else(|| OptimaError::new_file_io(path.to_string(), "could not load mesh"))?;
+    let points = trimesh.points_to_point3s::<T>();
+    ConvexPolyhedron::from_convex_hull(&points).ok_or_else(|| OptimaError::InvalidInput(format!("mesh at {} does not have a convex hull", path.to_string())))
+}
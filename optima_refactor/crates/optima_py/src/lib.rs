@@ -0,0 +1,188 @@
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use ad_trait::differentiable_function::ForwardADMulti;
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{DMatrix, Isometry3, Quaternion, UnitQuaternion, Vector3};
+use numpy::{PyArray1, PyArray2, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategoryIsometry3};
+use optima_interpolation::InterpolatorTraitLite;
+use optima_interpolation::splines::{InterpolatingSpline, InterpolatingSplineType};
+use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
+use optima_optimization::open::SimpleOpEnOptimizer;
+use optima_proximity::pair_group_queries::{OParryIntersectGroupArgs, OParryPairSelector, OwnedEmptyParryFilter, OwnedEmptyToProximityQry, OwnedParryIntersectGroupQry};
+use optima_proximity::pair_queries::ParryShapeRep;
+use optima_linalg::OLinalgCategoryNalgebra;
+use optima_robotics::robot::{ORobot, ORobotDefault};
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
+use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
+use optima_robotics::robotics_optimization::robotics_optimization_jacobian::manipulability_from_jacobian;
+
+type FAD = adfn<8>;
+
+/// A link given from python either by index or by name.
+#[derive(FromPyObject)]
+pub enum PyLink {
+    Idx(usize),
+    Name(String)
+}
+
+#[pyclass(name = "ORobot", unsendable)]
+pub struct PyORobot {
+    robot: ORobotDefault,
+    /// AD copy of `robot` for jacobians, made on first use since converting is slow.
+    ad_robot: OnceCell<ORobot<FAD, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra>>
+}
+#[pymethods]
+impl PyORobot {
+    #[staticmethod]
+    pub fn load_from_saved_robot(robot_name: &str) -> PyResult<Self> {
+        Ok(Self::new(ORobotDefault::load_from_saved_robot(robot_name).map_err(|e| PyValueError::new_err(e.to_string()))?))
+    }
+    #[staticmethod]
+    pub fn from_urdf(robot_name: &str) -> PyResult<Self> {
+        Ok(Self::new(ORobotDefault::from_urdf(robot_name).map_err(|e| PyValueError::new_err(e.to_string()))?))
+    }
+    pub fn num_dofs(&self) -> usize {
+        self.robot.num_dofs()
+    }
+    pub fn link_names(&self) -> Vec<String> {
+        self.robot.links().iter().map(|x| x.name().to_string()).collect()
+    }
+    pub fn get_link_idx_from_link_name(&self, link_name: &str) -> PyResult<usize> {
+        self.robot.get_link_idx_from_link_name(link_name).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+    pub fn get_dof_bounds(&self) -> Vec<(f64, f64)> {
+        self.robot.get_dof_bounds()
+    }
+    /// Returns a list with one entry per link: either None or a 4x4 homogeneous transform.
+    pub fn forward_kinematics<'py>(&self, py: Python<'py>, state: PyReadonlyArray1<f64>) -> PyResult<Vec<Option<&'py PyArray2<f64>>>> {
+        let state = self.state_from_array(&state)?;
+        let fk_res = self.robot.forward_kinematics(&state, None);
+        let mut out = vec![];
+        for pose in fk_res.link_poses() {
+            match pose {
+                None => { out.push(None); }
+                Some(pose) => { out.push(Some(isometry_to_pyarray(py, pose)?)); }
+            }
+        }
+        Ok(out)
+    }
+    /// `link` is a link index or name.
+    pub fn link_pose<'py>(&self, py: Python<'py>, state: PyReadonlyArray1<f64>, link: PyLink) -> PyResult<&'py PyArray2<f64>> {
+        let state = self.state_from_array(&state)?;
+        let link_idx = self.link_idx(link)?;
+        let fk_res = self.robot.forward_kinematics(&state, None);
+        match fk_res.get_link_pose(link_idx) {
+            Err(e) => { Err(PyValueError::new_err(e.to_string())) }
+            Ok(pose) => { isometry_to_pyarray(py, pose) }
+        }
+    }
+    /// 6 x num_dofs geometric jacobian of the given link (an index or name).
+    pub fn jacobian<'py>(&self, py: Python<'py>, state: PyReadonlyArray1<f64>, link: PyLink) -> PyResult<&'py PyArray2<f64>> {
+        let jacobian = self.jacobian_matrix(&state, link)?;
+        let rows: Vec<Vec<f64>> = jacobian.row_iter().map(|r| r.iter().cloned().collect()).collect();
+        PyArray2::from_vec2(py, &rows).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+    /// Yoshikawa manipulability of the given link (an index or name).
+    pub fn manipulability(&self, state: PyReadonlyArray1<f64>, link: PyLink) -> PyResult<f64> {
+        Ok(manipulability_from_jacobian(&self.jacobian_matrix(&state, link)?))
+    }
+    pub fn in_self_collision(&self, state: PyReadonlyArray1<f64>) -> PyResult<bool> {
+        let state = self.state_from_array(&state)?;
+        let q = OwnedParryIntersectGroupQry::new(OParryIntersectGroupArgs::new(ParryShapeRep::Full, ParryShapeRep::Full, true, false));
+        let res = self.robot.parry_shape_scene_self_query(&state, &q, &OParryPairSelector::HalfPairs, false);
+        Ok(res.intersect())
+    }
+    /// goal_orientation should be a unit quaternion in format [w x y z]
+    pub fn solve_ik<'py>(&self, py: Python<'py>, init_state: PyReadonlyArray1<f64>, link: PyLink, goal_position: PyReadonlyArray1<f64>, goal_orientation: PyReadonlyArray1<f64>) -> PyResult<&'py PyArray1<f64>> {
+        let x = self.state_from_array(&init_state)?;
+        let link_idx = self.link_idx(link)?;
+        let position = as_slice_with_len(&goal_position, 3)?;
+        let orientation = as_slice_with_len(&goal_orientation, 4)?;
+        let pos = Vector3::new(position[0], position[1], position[2]);
+        let quat = UnitQuaternion::from_quaternion(Quaternion::new(orientation[0], orientation[1], orientation[2], orientation[3]));
+
+        let db = self.robot.get_ik_differentiable_block(ForwardADMulti::<FAD>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &x, vec![link_idx], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0));
+        db.update_ik_pose(0, Isometry3::from_translation_and_rotation(&pos, &quat), IKGoalUpdateMode::Absolute);
+
+        let o = SimpleOpEnOptimizer::new(self.robot.get_dof_lower_bounds(), self.robot.get_dof_upper_bounds(), 0.001);
+        let res = o.optimize_unconstrained(&x, &db);
+
+        Ok(PyArray1::from_vec(py, res.x_star().to_vec()))
+    }
+    fn __repr__(&self) -> String {
+        format!("ORobot(num_dofs={})", self.robot.num_dofs())
+    }
+}
+impl PyORobot {
+    fn new(robot: ORobotDefault) -> Self {
+        Self { robot, ad_robot: OnceCell::new() }
+    }
+    fn state_from_array(&self, state: &PyReadonlyArray1<f64>) -> PyResult<Vec<f64>> {
+        as_slice_with_len(state, self.robot.num_dofs()).map(|x| x.to_vec())
+    }
+    /// Errors if the link does not exist or is not present in the model (and so has no pose).
+    fn link_idx(&self, link: PyLink) -> PyResult<usize> {
+        let link_idx = match link {
+            PyLink::Idx(idx) => { idx }
+            PyLink::Name(name) => { self.robot.get_link_idx_from_link_name(&name).map_err(|e| PyValueError::new_err(e.to_string()))? }
+        };
+        match self.robot.links().get(link_idx) {
+            None => { Err(PyValueError::new_err(format!("link idx {} is out of range", link_idx))) }
+            Some(l) if !l.is_present_in_model() => { Err(PyValueError::new_err(format!("link {} is not present in the model", link_idx))) }
+            Some(_) => { Ok(link_idx) }
+        }
+    }
+    fn jacobian_matrix(&self, state: &PyReadonlyArray1<f64>, link: PyLink) -> PyResult<DMatrix<f64>> {
+        let state = self.state_from_array(state)?;
+        let link_idx = self.link_idx(link)?;
+        let ad_robot = self.ad_robot.get_or_init(|| self.robot.to_other_ad_type::<FAD>());
+        let block = self.robot.get_link_jacobian_differentiable_block(ForwardADMulti::<FAD>::new(), Cow::Borrowed(ad_robot), link_idx).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        self.robot.link_jacobian_from_block(&block, &state).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+/// Interpolates through the rows of `waypoints` (num_waypoints x dim) and returns a
+/// num_points x dim array.  `spline_type` is one of "linear", "quadratic", "hermite_cubic",
+/// "natural_cubic", or "bezier_cubic".
+#[pyfunction]
+pub fn interpolate_trajectory<'py>(py: Python<'py>, waypoints: PyReadonlyArray2<f64>, num_points: usize, spline_type: &str) -> PyResult<&'py PyArray2<f64>> {
+    let spline_type = match spline_type {
+        "linear" => { InterpolatingSplineType::Linear }
+        "quadratic" => { InterpolatingSplineType::Quadratic }
+        "hermite_cubic" => { InterpolatingSplineType::HermiteCubic }
+        "natural_cubic" => { InterpolatingSplineType::NaturalCubic }
+        "bezier_cubic" => { InterpolatingSplineType::BezierCubic }
+        _ => { return Err(PyValueError::new_err(format!("unsupported spline type {}", spline_type))); }
+    };
+
+    let waypoints = waypoints.as_array();
+    let control_points: Vec<Vec<f64>> = waypoints.rows().into_iter().map(|r| r.to_vec()).collect();
+    let spline = InterpolatingSpline::new(control_points, spline_type);
+    let points = spline.interpolate_points_by_num_points(num_points);
+
+    PyArray2::from_vec2(py, &points).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn isometry_to_pyarray<'py>(py: Python<'py>, pose: &Isometry3<f64>) -> PyResult<&'py PyArray2<f64>> {
+    let m = pose.to_homogeneous();
+    let rows: Vec<Vec<f64>> = m.row_iter().map(|r| r.iter().cloned().collect()).collect();
+    PyArray2::from_vec2(py, &rows).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn as_slice_with_len<'a>(arr: &'a PyReadonlyArray1<f64>, len: usize) -> PyResult<&'a [f64]> {
+    let s = arr.as_slice().map_err(|e| PyValueError::new_err(e.to_string()))?;
+    if s.len() != len {
+        return Err(PyValueError::new_err(format!("expected an array of length {}, got {}", len, s.len())));
+    }
+    Ok(s)
+}
+
+#[pymodule]
+fn optima_py(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyORobot>()?;
+    m.add_function(wrap_pyfunction!(interpolate_trajectory, m)?)?;
+    Ok(())
+}
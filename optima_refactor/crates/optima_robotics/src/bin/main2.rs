@@ -1,7 +1,7 @@
 use optima_robotics::robot::ORobotDefault;
 
 fn main() {
-    let c = ORobotDefault::from_urdf("ur5");
+    let c = ORobotDefault::from_urdf_unchecked("ur5");
 
     println!("{:?}", c);
 }
\ No newline at end of file
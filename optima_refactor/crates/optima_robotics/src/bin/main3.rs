@@ -14,9 +14,9 @@ fn test<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory, AsChain: AsRobo
 
 fn main() {
     let mut r = ORobotSetDefault::<f64>::new_empty();
-    r.add_robot(ORobot::from_urdf("ur5"), 0, 0, &Isometry3::identity(), [0.0; 3], OJointType::Fixed, OJointLimit::default());
-    r.add_robot(ORobot::from_urdf("ur5"), 0, 0, &Isometry3::identity(), [0.0; 3], OJointType::Fixed, OJointLimit::default());
-    r.add_robot(ORobot::from_urdf("ur5"), 0, 0, &Isometry3::identity(), [0.0; 3], OJointType::Fixed, OJointLimit::default());
+    r.add_robot(ORobot::from_urdf_unchecked("ur5"), 0, 0, &Isometry3::identity(), [0.0; 3], OJointType::Fixed, OJointLimit::default());
+    r.add_robot(ORobot::from_urdf_unchecked("ur5"), 0, 0, &Isometry3::identity(), [0.0; 3], OJointType::Fixed, OJointLimit::default());
+    r.add_robot(ORobot::from_urdf_unchecked("ur5"), 0, 0, &Isometry3::identity(), [0.0; 3], OJointType::Fixed, OJointLimit::default());
 
     let chain = r.as_robot();
     let chain = chain.to_other_ad_type::<adfn<1>>();
@@ -7,8 +7,8 @@ use optima_robotics::robot_set::ORobotSetDefault;
 use optima_robotics::robotics_components::{OJointLimit, OJointType};
 
 fn main() {
-    let b1_chain = ORobotDefault::from_urdf("b1");
-    let z1_chain = ORobotDefault::from_urdf("z1");
+    let b1_chain = ORobotDefault::from_urdf_unchecked("b1");
+    let z1_chain = ORobotDefault::from_urdf_unchecked("z1");
 
     let mut robot = ORobotSetDefault::new_empty();
     robot.add_robot(b1_chain, 0, 0, &Isometry3::identity(), [0.0; 3], OJointType::Fixed, OJointLimit::default());
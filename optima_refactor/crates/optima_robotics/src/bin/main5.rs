@@ -8,8 +8,8 @@ use optima_robotics::robotics_components::{OJointLimit, OJointType};
 use optima_robotics::robotics_traits::AsRobotTrait;
 
 fn main()  {
-    let r1 = ORobotDefault::from_urdf("ur5");
-    let r2 = ORobotDefault::from_urdf("lite6");
+    let r1 = ORobotDefault::from_urdf_unchecked("ur5");
+    let r2 = ORobotDefault::from_urdf_unchecked("lite6");
 
     let mut robot_set = ORobotSetDefault::new_empty();
     robot_set.add_robot(r1, 0, 0, &Isometry3::identity(), [0.0; 3], OJointType::Fixed, OJointLimit::default());
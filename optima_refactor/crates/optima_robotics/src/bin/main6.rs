@@ -5,7 +5,7 @@ use optima_robotics::robot::{ORobotDefault};
 
 
 fn main() {
-    let mut r = ORobotDefault::load_from_saved_robot("ur5");
+    let mut r = ORobotDefault::load_from_saved_robot_unchecked("ur5");
     let s = r.parry_shape_scene().get_shapes();
     let state = vec![0.,0.,2.85,0.,0.,0.];
     let p = r.parry_shape_scene().get_shape_poses(&(&r, &state));
@@ -12,7 +12,7 @@ use optima_robotics::robotics_optimization::robotics_optimization_utils::Robotic
 
 type DerivativeMethod = ForwardADMulti<adf_f32x8>;
 fn main() {
-    let robot = ORobotDefault::load_from_saved_robot("ur5");
+    let robot = ORobotDefault::load_from_saved_robot_unchecked("ur5");
     let mut diff_block = robot.spawn_ik_differentiable_block::<DerivativeMethod>(());
     diff_block.add_initial_ik_goals(vec![6]);
     diff_block.update_ik_goal_pose(0, &Isometry3::from_constructors(&[0.06,0.1,0.5], &[0.,0.,0.]));
@@ -7,5 +7,6 @@ pub mod robotics_components;
 pub mod utils;
 pub mod robotics_functions;
 pub mod robot_shape_scene;
+pub mod urdf_export;
 pub mod robotics_diffblock_spawners;
 pub mod robotics_optimization;
@@ -1,21 +1,33 @@
+use std::any::TypeId;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
 use std::sync::{Arc, RwLock};
+use std::time::Instant;
 use ad_trait::*;
 use ad_trait::differentiable_block::DifferentiableBlock;
-use ad_trait::differentiable_function::DerivativeMethodTrait;
+use ad_trait::differentiable_function::{DerivativeMethodTrait, ForwardADMulti};
+use ad_trait::forward_ad::adfn::adfn;
+use ad_trait::reverse_ad::adr::adr;
 use serde::{Serialize, Deserialize};
+use nalgebra::{DMatrix, Isometry3};
+use rayon::prelude::*;
 use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategoryIsometry3, O3DPoseCategory};
 use crate::utils::get_urdf_path_from_chain_name;
 use serde_with::*;
-use optima_3d_mesh::{SaveToSTL, ToTriMesh};
+use optima_3d_mesh::{OTriMesh, ToTriMesh};
+use optima_3d_mesh::decimation::OMeshDecimation;
+use optima_3d_mesh::scene_export::OSceneExport;
 use optima_3d_spatial::optima_3d_rotation::O3DRotation;
 use optima_3d_spatial::optima_3d_vec::{O3DVec, O3DVecCategoryArr};
-use optima_console::output::{oprint, PrintColor, PrintMode};
+use optima_console::progress::OProgressHandle;
 use optima_console::tab;
-use optima_file::path::{OAssetLocation, OPath, OPathMatchingPattern, OPathMatchingStopCondition, OStemCellPath};
+use optima_error::OptimaError;
+use optima_file::cache::{OAssetCache, OAssetCacheKey};
+#[cfg(not(target_arch = "wasm32"))]
+use optima_file::download::{ensure_robot_downloaded, ORobotRegistry};
+use optima_file::path::{urdf_mesh_relative_path_components, OAssetLocation, OPath, OPathMatchingPattern, OPathMatchingStopCondition, OSaveFormat, OStemCellPath};
 use optima_file::traits::{FromJsonString, ToJsonString};
 use optima_linalg::{OLinalgCategoryNalgebra, OLinalgCategory, OVec, OVecCategoryVec};
 use crate::robotics_components::*;
@@ -23,17 +35,29 @@ use crate::robotics_functions::compute_chain_info;
 use crate::robotics_traits::{AsRobotTrait, JointTrait};
 use optima_misc::arr_storage::MutArrTraitRaw;
 use optima_misc::arr_storage::ImmutArrTraitRaw;
-use optima_proximity::pair_group_queries::{OPairGroupQryTrait, OwnedPairGroupQry, OParryFilterOutputCategory, OPairGroupQryOutputCategoryTrait, OParryFilterOutput, OParryPairSelector, ToParryProximityOutputCategory, OSkipReason};
+use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
+use optima_optimization::open::SimpleOpEnOptimizer;
+use optima_proximity::pair_group_queries::{OPairGroupQryTrait, OwnedEmptyParryFilter, OwnedEmptyToProximityQry, OwnedPairGroupQry, OParryFilterOutputCategory, OPairGroupQryOutputCategoryTrait, OParryFilterOutput, OParryPairSelector, ToParryProximityOutputCategory, OSkipReason};
 use optima_proximity::shape_scene::{OParryGenericShapeScene, ShapeSceneTrait};
 use optima_proximity::shapes::{OParryShape, ShapeCategoryOParryShape};
-use optima_sampling::SimpleSampler;
+use parry_ad::na::Vector3;
+use parry_ad::shape::{Ball, Cuboid};
+use optima_sampling::{get_rng, Rng, RngCore, SimpleSampler};
 use optima_universal_hashmap::AHashMapWrapper;
 use crate::robot_shape_scene::{ORobotParryShapeScene};
 use crate::robotics_optimization::robotics_optimization_functions::{AxisDirection, LookAtTarget};
-use crate::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjective, DifferentiableFunctionClassIKObjective, DifferentiableFunctionIKObjective, IKGoal, IKGoalVecTrait};
+use crate::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjective, DifferentiableBlockIKObjectiveTrait, DifferentiableFunctionClassIKObjective, DifferentiableFunctionIKObjective, IKGoal, IKGoalUpdateMode, IKGoalVecTrait};
+use crate::robotics_optimization::robotics_optimization_ik_batch::{IKBatchResult, IKBatchSettings};
 use crate::robotics_optimization::robotics_optimization_look_at::{DifferentiableFunctionClassLookAt, DifferentiableFunctionLookAt};
+use crate::robotics_optimization::robotics_optimization_composite::CompositeObjective;
+use crate::robotics_optimization::robotics_optimization_jacobian::{DifferentiableBlockLinkJacobian, DifferentiableFunctionLinkJacobian, manipulability_from_jacobian};
+use crate::robotics_optimization::robotics_optimization_trajectory::{DifferentiableBlockTrajectoryObjective, DifferentiableFunctionTrajectoryObjective, TrajectoryObjective};
 
 pub type ORobotDefault = ORobot<f64, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra>;
+/// Single precision counterpart of `ORobotDefault` (e.g., for embedded targets).  Preprocess and
+/// save robots in f64, then load them with `load_from_saved_robot` or convert them with
+/// `to_other_ad_type::<f32>()`.
+pub type ORobotF32 = ORobot<f32, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra>;
 #[serde_as]
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ORobot<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory> {
@@ -58,16 +82,39 @@ pub struct ORobot<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory> {
     has_been_preprocessed: bool,
     phantom_data: PhantomData<(T, C)>
 }
+/// Upper bound on the number of convex pieces each link mesh is split into by `ORobot::preprocess`.
+pub const DEFAULT_LINK_MAX_CONVEX_HULLS: u32 = 8;
+
+/// How `ORobot::preprocess_with_options` builds the collision meshes of each link.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ORobotPreprocessOptions {
+    /// Upper bound on the number of convex pieces (from VHACD) each link mesh is split into, which
+    /// become the convex subcomponents of the link's shape.  Non-convex links are approximated much
+    /// more closely than by their single convex hull, and subcomponent queries can skip pieces that
+    /// are far apart.  1 keeps the single convex hull per link.
+    pub max_convex_hulls: u32,
+    /// Simplifies each link's convex hull and convex subcomponents, which cuts query times on
+    /// robots with dense meshes.  The simplified shapes still contain the originals (see
+    /// `OTriMesh::decimated_convex_hull`).  None keeps every vertex.
+    pub decimation: Option<OMeshDecimation>
+}
+impl Default for ORobotPreprocessOptions {
+    fn default() -> Self {
+        Self { max_convex_hulls: DEFAULT_LINK_MAX_CONVEX_HULLS, decimation: None }
+    }
+}
+
 impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T, C, L> {
-    pub fn from_urdf(robot_name: &str) -> Self {
+    pub fn from_urdf(robot_name: &str) -> Result<Self, OptimaError> {
         let urdf_path = get_urdf_path_from_chain_name(robot_name);
-        let urdf = urdf_path.load_urdf();
+        let urdf = urdf_path.load_urdf().map_err(|e| OptimaError::RobotNotFound { robot_name: robot_name.to_string(), message: e.to_string() })?;
 
         let mut links = vec![];
         let mut joints = vec![];
 
+        let named_materials = OMaterial::named_materials_from_urdf(&urdf);
         urdf.links.iter().for_each(|x| {
-            links.push(OLink::from_link(x));
+            links.push(OLink::from_link(x, &named_materials));
         });
 
         urdf.joints.iter().for_each(|x| {
@@ -76,7 +123,19 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
 
         Self::from_manual(robot_name, links, joints)
     }
-    pub fn from_manual(robot_name: &str, links: Vec<OLink<T, C, L>>, joints: Vec<OJoint<T, C>>) -> Self {
+    pub fn from_urdf_unchecked(robot_name: &str) -> Self {
+        Self::from_urdf(robot_name).expect("error")
+    }
+    /// Same as `from_urdf`, but first downloads the robot's urdf and meshes from the registry if
+    /// they are not already in the asset folder.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_urdf_or_download(robot_name: &str, registry: &ORobotRegistry) -> Result<Self, OptimaError> {
+        ensure_robot_downloaded(robot_name, registry)?;
+        Self::from_urdf(robot_name)
+    }
+    /// Returns an error if a joint names a parent, child, or mimicked joint that is not in the
+    /// robot, or if a link mesh cannot be found or converted.
+    pub fn from_manual(robot_name: &str, links: Vec<OLink<T, C, L>>, joints: Vec<OJoint<T, C>>) -> Result<Self, OptimaError> {
         let mut link_name_to_link_idx_map = HashMap::new();
         let mut joint_name_to_joint_idx_map = HashMap::new();
 
@@ -106,18 +165,36 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
             phantom_data: Default::default(),
         };
 
-        out.setup();
+        out.setup()?;
 
-        out
+        Ok(out)
     }
-    pub fn load_from_saved_robot(robot_name: &str) -> Self {
+    pub fn from_manual_unchecked(robot_name: &str, links: Vec<OLink<T, C, L>>, joints: Vec<OJoint<T, C>>) -> Self {
+        Self::from_manual(robot_name, links, joints).expect("error")
+    }
+    pub fn load_from_saved_robot(robot_name: &str) -> Result<Self, OptimaError> {
         let mut p = OStemCellPath::new_asset_path();
         p.append_file_location(&OAssetLocation::SavedRobot { robot_name });
-        p.load_object_from_json_file::<ORobot<T, C, L>>()
+        if !p.exists() { return Err(OptimaError::RobotNotFound { robot_name: robot_name.to_string(), message: "it has not been preprocessed and saved".to_string() }); }
+        p.load_object_from_file::<ORobot<T, C, L>>()
+    }
+    pub fn load_from_saved_robot_unchecked(robot_name: &str) -> Self {
+        Self::load_from_saved_robot(robot_name).expect("error")
     }
-    pub fn save_robot(&mut self, name: Option<&str>) {
+    /// Saves as json (see `OSaveFormat::default`).  Use `save_robot_with_format` with
+    /// `OSaveFormat::Binary` for smaller files on native targets; `load_from_saved_robot` reads
+    /// either format.
+    pub fn save_robot(&mut self, name: Option<&str>) -> Result<(), OptimaError> {
+        self.save_robot_with_format(name, OSaveFormat::default())
+    }
+    pub fn save_robot_unchecked(&mut self, name: Option<&str>) {
+        self.save_robot(name).expect("error")
+    }
+    /// Returns an error, leaving the robot's name unchanged, if the name is reserved or the file
+    /// cannot be written.
+    pub fn save_robot_with_format(&mut self, name: Option<&str>, format: OSaveFormat) -> Result<(), OptimaError> {
         if !self.has_been_preprocessed {
-            oprint("cannot save a non-preprocessed robot.  returning.", PrintMode::Println, PrintColor::Yellow);
+            tracing::warn!(robot = %self.robot_name, "saving a robot that has not been preprocessed");
         }
         let name = match name {
             None => {
@@ -128,12 +205,24 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
             }
             Some(name) => { name.to_string() }
         };
-        if name == "robot_set_default" { panic!("cannot save robot with name robot_set_default"); }
+        if name == "robot_set_default" { return Err(OptimaError::InvalidInput("cannot save robot with name robot_set_default".to_string())); }
 
-        self.robot_name = name.clone();
+        let previous_name = std::mem::replace(&mut self.robot_name, name.clone());
         let mut p = OStemCellPath::new_asset_path();
         p.append_file_location(&OAssetLocation::SavedRobot { robot_name: &name });
-        p.save_object_to_file_as_json(self);
+        let res = p.save_object_to_file(self, format);
+        if res.is_err() { self.robot_name = previous_name; }
+        res
+    }
+    pub fn save_robot_with_format_unchecked(&mut self, name: Option<&str>, format: OSaveFormat) {
+        self.save_robot_with_format(name, format).expect("error")
+    }
+    /// Saving after preprocessing should not throw away the preprocessing, so a failed save is
+    /// logged rather than returned.
+    fn save_robot_or_warn(&mut self, name: Option<&str>) {
+        if let Err(e) = self.save_robot(name) {
+            tracing::warn!(robot = %self.robot_name, error = %e, "could not save robot");
+        }
     }
     pub (crate) fn from_manual_internal(robot_name: &str, links: Vec<OLink<T, C, L>>, joints: Vec<OJoint<T, C>>, robot_type: RobotType) -> Self {
         let mut link_name_to_link_idx_map = HashMap::new();
@@ -165,9 +254,10 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
             phantom_data: Default::default(),
         };
 
+        // robot set link and joint names are generated from the sub robots, so they always resolve.
         out.set_link_and_joint_idxs();
-        out.assign_joint_connection_indices();
-        out.set_mimic_joint_idxs();
+        out.assign_joint_connection_indices().expect("error");
+        out.set_mimic_joint_idxs().expect("error");
         out.set_chain_info();
         out.set_num_dofs();
         out.set_all_sub_dof_idxs();
@@ -195,7 +285,7 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
     }
     pub fn to_other_generic_types<T2: AD, C2: O3DPoseCategory, L2: OLinalgCategory>(&self) -> ORobot<T2, C2, L2> {
         let json_str = self.to_json_string();
-        ORobot::<T2, C2, L2>::from_json_string(&json_str)
+        ORobot::<T2, C2, L2>::from_json_string_unchecked(&json_str)
     }
     pub fn to_other_ad_type<T2: AD>(&self) -> ORobot<T2, C, L> {
         self.to_other_generic_types::<T2, C, L>()
@@ -213,11 +303,19 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
         &self.sub_robots
     }
     #[inline(always)]
-    pub fn get_link_idx_from_link_name(&self, link_name: &str) -> usize {
+    pub fn get_link_idx_from_link_name(&self, link_name: &str) -> Result<usize, OptimaError> {
+        self.link_name_to_link_idx_map.get(link_name).copied().ok_or(OptimaError::LinkNotFound { link_name: link_name.to_string() })
+    }
+    #[inline(always)]
+    pub fn get_link_idx_from_link_name_unchecked(&self, link_name: &str) -> usize {
         *self.link_name_to_link_idx_map.get(link_name).expect(&format!("link name {} not found", link_name))
     }
     #[inline(always)]
-    pub fn get_joint_idx_from_joint_name(&self, joint_name: &str) -> usize {
+    pub fn get_joint_idx_from_joint_name(&self, joint_name: &str) -> Result<usize, OptimaError> {
+        self.joint_name_to_joint_idx_map.get(joint_name).copied().ok_or(OptimaError::JointNotFound { joint_name: joint_name.to_string() })
+    }
+    #[inline(always)]
+    pub fn get_joint_idx_from_joint_name_unchecked(&self, joint_name: &str) -> usize {
         *self.joint_name_to_joint_idx_map.get(joint_name).expect(&format!("tried to find joint {}, could not find it", joint_name))
     }
     #[inline(always)]
@@ -295,6 +393,7 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
         self.base_link_idx
     }
     pub fn forward_kinematics<V: OVec<T>>(&self, state: &V, base_offset: Option<&C::P<T>>) -> FKResult<T, C::P<T>> {
+        let _span = tracing::trace_span!("forward_kinematics", robot = %self.robot_name).entered();
         let mut out = vec![ None; self.links.len() ];
 
         let base_pose = match base_offset {
@@ -319,6 +418,7 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
         FKResult { link_poses: out, _phantom_data: Default::default() }
     }
     pub fn forward_kinematics_floating_chain<V: OVec<T>>(&self, state: &V, start_link_idx: usize, end_link_idx: usize, base_offset: Option<&C::P<T>>) -> FKResult<T, C::P<T>> {
+        let _span = tracing::trace_span!("forward_kinematics_floating_chain", robot = %self.robot_name, start_link_idx, end_link_idx).entered();
         let mut out = vec![ None; self.links.len() ];
 
         let base_pose = match base_offset {
@@ -417,7 +517,7 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
         fk_res.link_poses.iter().enumerate().for_each(|(i, x)| {
             if let Some(pose) = x {
                 let link = &self.links()[i];
-                if link.is_present_in_model && link.convex_hull_file_path.is_some() {
+                if link.is_present_in_model && (link.primitive_collision().is_some() || link.convex_hull_file_path.is_some()) {
                     out.push(pose.clone());
                 }
             }
@@ -502,61 +602,121 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
         dof_bounds.iter().for_each(|x| out.push(x.1));
         out
     }
+    /// Uniform sample within the dof bounds, drawn from the global rng (see `OGlobalRng`).
     #[inline(always)]
     pub fn sample_pseudorandom_state(&self) -> Vec<T> {
         let bounds = self.get_dof_bounds();
         SimpleSampler::uniform_samples(&bounds, None)
     }
+    #[inline(always)]
+    pub fn sample_pseudorandom_state_with_seed(&self, seed: u64) -> Vec<T> {
+        let bounds = self.get_dof_bounds();
+        SimpleSampler::uniform_samples(&bounds, Some(seed))
+    }
+    #[inline(always)]
+    pub fn sample_pseudorandom_state_with_rng<R: Rng>(&self, rng: &mut R) -> Vec<T> {
+        let bounds = self.get_dof_bounds();
+        SimpleSampler::uniform_samples_with_rng(&bounds, rng)
+    }
     pub fn preprocess(&mut self, save: SaveRobot) {
-        self.preprocess_robot_parry_shape_scene();
+        self.preprocess_with_progress(save, &OProgressHandle::new());
+    }
+    /// Same as `preprocess`, but reports progress through (and can be cancelled with) the given
+    /// handle.  Returns false if preprocessing was cancelled, in which case the robot is left
+    /// unchanged and is not saved.
+    pub fn preprocess_with_progress(&mut self, save: SaveRobot, progress: &OProgressHandle) -> bool {
+        self.preprocess_with_progress_and_seed(save, progress, None)
+    }
+    /// Same as `preprocess_with_progress`, but all state sampling is drawn from the given seed, so
+    /// the resulting pair skips and average distances are the same on every run.  With None, the
+    /// global rng is used (see `OGlobalRng`).
+    pub fn preprocess_with_progress_and_seed(&mut self, save: SaveRobot, progress: &OProgressHandle, seed: Option<u64>) -> bool {
+        self.preprocess_with_options(save, &ORobotPreprocessOptions::default(), progress, seed)
+    }
+    /// Same as `preprocess_with_progress_and_seed`, but first rebuilds each link's collision meshes
+    /// as described by `options`.  New meshes are stored with the robot's other meshes and reused on
+    /// later runs.  If cancelled, the robot is left unchanged.
+    pub fn preprocess_with_options(&mut self, save: SaveRobot, options: &ORobotPreprocessOptions, progress: &OProgressHandle, seed: Option<u64>) -> bool {
+        let previous_collision_mesh_file_paths: Vec<(Option<OStemCellPath>, Vec<OStemCellPath>)> = self.links.iter().map(|x| (x.convex_hull_file_path.clone(), x.convex_decomposition_file_paths.clone())).collect();
+        let restore_collision_mesh_file_paths = |robot: &mut Self| {
+            robot.links.iter_mut().zip(previous_collision_mesh_file_paths.iter()).for_each(|(link, (convex_hull_file_path, convex_decomposition_file_paths))| {
+                link.convex_hull_file_path = convex_hull_file_path.clone();
+                link.convex_decomposition_file_paths = convex_decomposition_file_paths.clone();
+            });
+        };
+
+        if !self.set_link_convex_decomposition_mesh_file_paths(options.max_convex_hulls, &progress.sub_range(0.0, 0.1)) { return false; }
+        if let Some(decimation) = &options.decimation {
+            if !self.set_link_decimated_collision_mesh_file_paths(decimation, &progress.sub_range(0.1, 0.15)) {
+                restore_collision_mesh_file_paths(self);
+                return false;
+            }
+        }
+        if !self.preprocess_robot_parry_shape_scene(&progress.sub_range(0.15, 1.0), seed) {
+            restore_collision_mesh_file_paths(self);
+            return false;
+        }
         self.has_been_preprocessed = true;
 
         match save {
             SaveRobot::Save(name) => {
-                self.save_robot(name);
+                self.save_robot_or_warn(name);
             }
             SaveRobot::DoNotSave => {  }
         }
+        progress.finish("done");
+
+        true
     }
     pub fn parry_shape_scene_compute_average_distances(&mut self, save: SaveRobot, shape_average_dis_num_samples: Option<usize>) {
+        self.parry_shape_scene_compute_average_distances_with_progress(save, shape_average_dis_num_samples, &OProgressHandle::new());
+    }
+    /// Same as `parry_shape_scene_compute_average_distances`, but reports progress through (and can
+    /// be cancelled with) the given handle.  Returns false if it was cancelled, in which case the
+    /// robot is left unchanged and is not saved.
+    pub fn parry_shape_scene_compute_average_distances_with_progress(&mut self, save: SaveRobot, shape_average_dis_num_samples: Option<usize>, progress: &OProgressHandle) -> bool {
         let num_samples = match shape_average_dis_num_samples {
             None => { 1000 }
             Some(s) => { s }
         };
         let mut parry_shape_scene = self.parry_shape_scene.clone();
-        parry_shape_scene.preprocess_shape_average_distances(Arc::new(self.clone()), num_samples);
+        parry_shape_scene.preprocess_shape_average_distances(Arc::new(self.clone()), num_samples, progress, None);
+        if progress.is_cancelled() { return false; }
 
         self.parry_shape_scene = parry_shape_scene;
 
         match save {
             SaveRobot::Save(name) => {
-                self.save_robot(name);
+                self.save_robot_or_warn(name);
             }
             SaveRobot::DoNotSave => {  }
         }
+        progress.finish("done");
+
+        true
     }
     pub fn parry_shape_scene_compute_always_collision_pairs(&mut self, save: SaveRobot) {
         let mut parry_shape_scene = self.parry_shape_scene.clone();
-        parry_shape_scene.preprocess_always_in_collision_states_pair_skips(Arc::new(self.clone()), 5000);
+        parry_shape_scene.preprocess_always_in_collision_states_pair_skips(Arc::new(self.clone()), 5000, &OProgressHandle::new(), None);
 
         self.parry_shape_scene = parry_shape_scene;
 
         match save {
             SaveRobot::Save(name) => {
-                self.save_robot(name);
+                self.save_robot_or_warn(name);
             }
             SaveRobot::DoNotSave => {  }
         }
     }
     pub fn parry_shape_scene_compute_never_collision_pairs(&mut self, save: SaveRobot) {
         let mut parry_shape_scene = self.parry_shape_scene.clone();
-        parry_shape_scene.preprocess_never_in_collision_states_pair_skips(Arc::new(self.clone()), 5000);
+        parry_shape_scene.preprocess_never_in_collision_states_pair_skips(Arc::new(self.clone()), 5000, &OProgressHandle::new(), None);
 
         self.parry_shape_scene = parry_shape_scene;
 
         match save {
             SaveRobot::Save(name) => {
-                self.save_robot(name);
+                self.save_robot_or_warn(name);
             }
             SaveRobot::DoNotSave => {  }
         }
@@ -575,7 +735,7 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
         let r = Arc::new(self.clone());
         self.parry_shape_scene.add_close_proximity_states_pair_skips(r.clone(), state.clone(), threshold );
         match save_robot {
-            SaveRobot::Save(s) => { self.save_robot(s) }
+            SaveRobot::Save(s) => { self.save_robot_or_warn(s) }
             SaveRobot::DoNotSave => {}
         }
     }
@@ -588,14 +748,14 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
     pub fn reset_close_proximity_states(&mut self, save_robot: SaveRobot) {
         self.parry_shape_scene.clear_close_proximity_states_pair_skips();
         match save_robot {
-            SaveRobot::Save(s) => { self.save_robot(s) }
+            SaveRobot::Save(s) => { self.save_robot_or_warn(s) }
             SaveRobot::DoNotSave => {}
         }
     }
     #[inline]
     pub fn get_ik_goal<V: OVec<T>>(&self, state: &V, link_idx: usize, ik_goal_mode: IKGoalMode<T, C>) -> C::P<T> {
         let fk_res = self.forward_kinematics(state, None);
-        let pose = fk_res.get_link_pose(link_idx).as_ref().expect("error");
+        let pose = fk_res.get_link_pose_unchecked(link_idx);
         return match ik_goal_mode {
             IKGoalMode::Absolute => { pose.clone() }
             IKGoalMode::LocalRelativeCombined { offset } => { pose.mul(&offset) }
@@ -687,29 +847,31 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobot<T
     */
     fn set_non_collision_states_internal(&mut self, save_robot: SaveRobot) {
         let mut parry_shape_scene = self.parry_shape_scene.clone();
-        parry_shape_scene.preprocess_non_collision_states_pair_skips(Arc::new(self.clone()), &self.non_collision_states);
+        parry_shape_scene.preprocess_non_collision_states_pair_skips(Arc::new(self.clone()), &self.non_collision_states, &OProgressHandle::new());
 
         self.parry_shape_scene = parry_shape_scene;
 
         match save_robot {
-            SaveRobot::Save(s) => { self.save_robot(s) }
+            SaveRobot::Save(s) => { self.save_robot_or_warn(s) }
             SaveRobot::DoNotSave => {}
         }
     }
-    fn setup(&mut self) {
+    fn setup(&mut self) -> Result<(), OptimaError> {
         self.set_link_and_joint_idxs();
-        self.assign_joint_connection_indices();
-        self.set_mimic_joint_idxs();
+        self.assign_joint_connection_indices()?;
+        self.set_mimic_joint_idxs()?;
         self.set_chain_info();
         self.set_num_dofs();
         self.set_all_sub_dof_idxs();
         self.set_dof_to_joint_and_sub_dof_idxs();
-        self.set_link_original_mesh_file_paths();
-        self.set_link_stl_mesh_file_paths();
-        self.set_link_convex_hull_mesh_file_paths();
-        self.set_link_convex_decomposition_mesh_file_paths();
-        // self.set_link_convex_decomposition_levels_mesh_file_paths();
+        self.set_link_original_mesh_file_paths()?;
+        self.set_link_stl_mesh_file_paths()?;
+        self.set_link_convex_hull_mesh_file_paths()?;
+        // a single hull per link needs no decomposition, so this cannot fail or be cancelled.
+        self.set_link_convex_decomposition_mesh_file_paths(1, &OProgressHandle::new());
+        // self.set_link_convex_decomposition_levels_mesh_file_paths()?;
         self.set_robot_parry_shape_scene();
+        Ok(())
     }
 }
 impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static> ORobot<T, C, L> {
@@ -723,19 +885,21 @@ impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static> ORobot<T, C, L> {
            x.joint_idx = i;
         });
     }
-    fn assign_joint_connection_indices(&mut self) {
+    fn assign_joint_connection_indices(&mut self) -> Result<(), OptimaError> {
         for joint_idx in 0..self.joints.len() {
-            self.joints.get_element_mut(joint_idx).parent_link_idx = self.get_link_idx_from_link_name(&self.joints.get_element(joint_idx).parent_link());
-            self.joints.get_element_mut(joint_idx).child_link_idx = self.get_link_idx_from_link_name(&self.joints.get_element(joint_idx).child_link())
+            self.joints.get_element_mut(joint_idx).parent_link_idx = self.get_link_idx_from_link_name(&self.joints.get_element(joint_idx).parent_link())?;
+            self.joints.get_element_mut(joint_idx).child_link_idx = self.get_link_idx_from_link_name(&self.joints.get_element(joint_idx).child_link())?;
         }
+        Ok(())
     }
-    fn set_mimic_joint_idxs(&mut self) {
+    fn set_mimic_joint_idxs(&mut self) -> Result<(), OptimaError> {
         for joint_idx in 0..self.joints.len() {
             if let Some(mimic) = &self.joints[joint_idx].mimic() {
-                let idx = self.get_joint_idx_from_joint_name(&mimic.joint());
+                let idx = self.get_joint_idx_from_joint_name(&mimic.joint())?;
                 self.joints[joint_idx].mimic.as_mut().unwrap().joint_idx = idx;
             }
         }
+        Ok(())
     }
     fn set_chain_info(&mut self) {
         let chain_info = compute_chain_info(&self.links, &self.joints);
@@ -821,74 +985,64 @@ impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static> ORobot<T, C, L> {
 
         self.dof_to_joint_and_sub_dof_idxs = dof_to_joint_and_sub_dof_idxs;
     }
-    fn set_link_original_mesh_file_paths(&mut self) {
-        self.links.iter_mut().for_each(|link| {
-            if link.visual().len() > 0 {
-                let geometry = link.visual()[0].geometry().clone();
-                match geometry {
-                    OGeometry::Mesh { filename, .. } => {
-                        let split = filename.split("//");
-                        let split: Vec<String> = split.map(|x| x.to_string()).collect();
-                        let filepath = split.last().unwrap().to_owned();
-                        let split = filepath.split("/");
-                        let split: Vec<String> = split.map(|x| x.to_string()).collect();
-
-                        let file_check = split.last().unwrap().to_owned();
-                        let mut target_path = OStemCellPath::new_asset_path();
-                        target_path.append_file_location(&OAssetLocation::ChainOriginalMeshes { robot_name: &self.robot_name });
-                        target_path.append(&file_check);
-                        let exists = target_path.exists();
-
-                        if !exists {
-                            let asset_path = OPath::new_home_path();
-                            oprint(&format!("searching for mesh {:?}", filepath), PrintMode::Println, PrintColor::Green);
-                            let found_paths = asset_path.walk_directory_and_match(OPathMatchingPattern::PathComponents(split), OPathMatchingStopCondition::First);
-                            if found_paths.is_empty() {
-                                panic!("could not find filepath for link mesh: {:?}", filename);
-                            }
-
-                            let found_path = found_paths[0].clone();
-                            found_path.copy_file_to_destination(target_path.as_physical_path()).expect("error: file could not be copied.");
-                        }
-
-                        link.original_mesh_file_path = Some(target_path.clone());
-                    }
-                    _ => {}
+    /// Copies the meshes of every visual into the robot's original mesh folder.  The first visual's
+    /// mesh becomes the link's original mesh (from which its stl, convex hull, and convex
+    /// decomposition are made) and must be found; the meshes of other visuals are only rendered, so
+    /// they are skipped with a warning if they cannot be.
+    fn set_link_original_mesh_file_paths(&mut self) -> Result<(), OptimaError> {
+        for link in self.links.iter_mut() {
+            let mesh_filenames: Vec<(usize, String)> = link.visual().iter().enumerate().filter_map(|(i, x)| match x.geometry() {
+                OGeometry::Mesh { filename, .. } => { Some((i, filename.clone())) }
+                _ => { None }
+            }).collect();
+            for (visual_idx, filename) in mesh_filenames {
+                match copy_original_mesh_file(&self.robot_name, link.name(), &filename) {
+                    Ok(target_path) => { if visual_idx == 0 { link.original_mesh_file_path = Some(target_path); } }
+                    Err(e) if visual_idx == 0 => { return Err(e); }
+                    Err(e) => { tracing::warn!(link = %link.name(), visual_idx, error = %e, "skipping the mesh of a visual"); }
                 }
             }
-        });
+        }
+        Ok(())
     }
-    fn set_link_stl_mesh_file_paths(&mut self) {
-        self.links.iter_mut().for_each(|link| {
+    fn set_link_stl_mesh_file_paths(&mut self) -> Result<(), OptimaError> {
+        let cache = OAssetCache::new_default();
+        for link in self.links.iter_mut() {
             let original_mesh_file_path = &link.original_mesh_file_path;
             if let Some(original_mesh_file_path) = original_mesh_file_path {
-                let extension = original_mesh_file_path.extension().expect("must have extension");
-                let filename = original_mesh_file_path.filename_without_extension().expect("must have filename");
+                let extension = original_mesh_file_path.extension().ok_or(OptimaError::new_file_io(original_mesh_file_path.to_string(), "mesh file has no extension"))?;
+                let filename = original_mesh_file_path.filename_without_extension().ok_or(OptimaError::new_file_io(original_mesh_file_path.to_string(), "mesh file has no file name"))?;
                 let mut target_path = OStemCellPath::new_asset_path();
                 target_path.append_file_location(&OAssetLocation::ChainSTLMeshes { robot_name: &self.robot_name });
                 target_path.append(&(filename.clone() + ".stl"));
                 let exists = target_path.exists();
 
                 if !exists {
-                    oprint(&format!("saving stl version of {:?}", original_mesh_file_path.filename().unwrap()), PrintMode::Println, PrintColor::Green);
-                    if extension.as_str() == "stl" || extension.as_str() == "STL" {
-                        original_mesh_file_path.load_stl().save_to_stl(&target_path);
-                    } else if extension.as_str() == "dae" || extension.as_str() == "DAE" {
-                        original_mesh_file_path.load_dae().save_to_stl(&target_path);
-                    } else {
-                        panic!("extension {} is unsupported.", extension);
-                    };
+                    tracing::info!(mesh = ?original_mesh_file_path.filename(), "saving stl version of mesh");
+                    let key = OAssetCacheKey::new_from_files("stl_conversion", &extension.to_lowercase(), &[original_mesh_file_path])?;
+                    let trimesh = cache.try_get_or_insert_with(&key, || {
+                        match extension.to_lowercase().as_str() {
+                            "stl" => { Ok(original_mesh_file_path.load_stl()?.to_trimesh()) }
+                            "dae" => { Ok(original_mesh_file_path.load_dae()?.to_trimesh()) }
+                            "obj" => { Ok(original_mesh_file_path.load_mesh_scene()?.to_trimesh()) }
+                            "gltf" | "glb" => { Ok(original_mesh_file_path.load_gltf()?.to_trimesh()) }
+                            _ => { Err(OptimaError::new_file_io(original_mesh_file_path.to_string(), format!("mesh extension {} is unsupported", extension))) }
+                        }
+                    })?;
+                    trimesh.save_to_stl(&target_path);
                 }
 
                 link.stl_mesh_file_path = Some(target_path.clone());
             }
-        });
+        }
+        Ok(())
     }
-    fn set_link_convex_hull_mesh_file_paths(&mut self) {
-        self.links.iter_mut().for_each(|link| {
+    fn set_link_convex_hull_mesh_file_paths(&mut self) -> Result<(), OptimaError> {
+        let cache = OAssetCache::new_default();
+        for link in self.links.iter_mut() {
             let stl_mesh_file = &link.stl_mesh_file_path;
             if let Some(stl_mesh_file) = stl_mesh_file {
-                let filename = stl_mesh_file.filename().unwrap();
+                let filename = stl_mesh_file.filename().ok_or(OptimaError::new_file_io(stl_mesh_file.to_string(), "mesh file has no file name"))?;
 
                 let mut target_path = OStemCellPath::new_asset_path();
                 target_path.append_file_location(&OAssetLocation::ChainConvexHulls { robot_name: &self.robot_name });
@@ -897,92 +1051,272 @@ impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static> ORobot<T, C, L> {
                 let exists = target_path.exists();
 
                 if !exists {
-                    oprint(&format!("computing convex hull of {:?}", filename), PrintMode::Println, PrintColor::Green);
-                    let convex_hull = stl_mesh_file.load_stl().to_trimesh().to_convex_hull();
+                    tracing::info!(mesh = %filename, "computing convex hull");
+                    let convex_hull = stl_mesh_file.load_stl()?.to_trimesh().to_convex_hull_cached(&cache);
                     convex_hull.save_to_stl(&target_path);
                 }
 
                 link.convex_hull_file_path = Some(target_path.clone());
             }
-        });
+        }
+        Ok(())
     }
-    fn set_link_convex_decomposition_mesh_file_paths(&mut self) {
-        self.links.iter_mut().for_each(|link| {
-            let stl_mesh_file = &link.stl_mesh_file_path;
-            if let Some(stl_mesh_file) = stl_mesh_file {
-                let filename = stl_mesh_file.filename_without_extension().unwrap();
-
-                let mut target_path_stub = OStemCellPath::new_asset_path();
-                target_path_stub.append_file_location(&OAssetLocation::LinkConvexDecomposition { robot_name: &self.robot_name, link_mesh_name: &filename });
-
-                let exists = target_path_stub.exists();
+    /// Sets each link's convex subcomponents to its mesh split into at most `max_convex_hulls` convex
+    /// pieces (see `convex_decomposition_mesh_files`).  With a `max_convex_hulls` of 1 the single
+    /// piece is the link's convex hull, so nothing is computed.  Links whose meshes cannot be
+    /// decomposed keep their previous subcomponents (and a warning is logged).  Returns false,
+    /// leaving the links unchanged, if cancelled.
+    fn set_link_convex_decomposition_mesh_file_paths(&mut self, max_convex_hulls: u32, progress: &OProgressHandle) -> bool {
+        let cache = OAssetCache::new_default();
+        let num_links = self.links.len();
+        let mut convex_decomposition_file_paths = vec![];
+        for (i, link) in self.links.iter().enumerate() {
+            if !progress.report("convex decomposition of link meshes", i as f64 / num_links as f64) { return false; }
+
+            let Some(stl_mesh_file) = &link.stl_mesh_file_path else {
+                convex_decomposition_file_paths.push(link.convex_decomposition_file_paths.clone());
+                continue;
+            };
+            if max_convex_hulls <= 1 {
+                convex_decomposition_file_paths.push(link.convex_hull_file_path.iter().cloned().collect());
+                continue;
+            }
+            if !link.is_present_in_model {
+                convex_decomposition_file_paths.push(link.convex_decomposition_file_paths.clone());
+                continue;
+            }
 
-                if !exists {
-                    let convex_decomposition = stl_mesh_file.load_stl().to_trimesh().to_convex_decomposition(1);
-                    oprint(&format!("computing convex decomposition of {:?}.  {:?} convex subcomponents found.", filename, convex_decomposition.len()), PrintMode::Println, PrintColor::Green);
-
-                    convex_decomposition.iter().enumerate().for_each(|(i, trimesh)| {
-                        let mut target_path = target_path_stub.clone();
-                        target_path.append(&(filename.clone() + "_" + i.to_string().as_str() + ".stl"));
-                        trimesh.save_to_stl(&target_path);
-                    });
+            let res = stl_mesh_file.filename_without_extension().ok_or(OptimaError::new_file_io(stl_mesh_file.to_string(), "mesh file has no file name")).and_then(|filename| {
+                let mut directory = OStemCellPath::new_asset_path();
+                directory.append_file_location(&OAssetLocation::LinkConvexDecompositionMaxHulls { robot_name: &self.robot_name, max_convex_hulls, link_mesh_name: &filename });
+                convex_decomposition_mesh_files(stl_mesh_file, max_convex_hulls, &directory, &cache)
+            });
+            match res {
+                Ok(paths) => { convex_decomposition_file_paths.push(paths); }
+                Err(e) => {
+                    tracing::warn!(link = %link.name, error = %e, "could not decompose link mesh; keeping its previous convex subcomponents");
+                    convex_decomposition_file_paths.push(link.convex_decomposition_file_paths.clone());
                 }
-
-                let files = target_path_stub.get_all_items_in_directory_as_paths(false, false);
-                link.convex_decomposition_file_paths = files.clone();
             }
-        });
+        }
+
+        self.links.iter_mut().zip(convex_decomposition_file_paths).for_each(|(link, paths)| { link.convex_decomposition_file_paths = paths; });
+        true
     }
     #[allow(dead_code)]
-    fn set_link_convex_decomposition_levels_mesh_file_paths(&mut self) {
+    fn set_link_convex_decomposition_levels_mesh_file_paths(&mut self) -> Result<(), OptimaError> {
         let max_num_convex_hulls = vec![1, 2, 5, 10, 20, 10000];
+        let cache = OAssetCache::new_default();
         for (level, max_num) in max_num_convex_hulls.iter().enumerate() {
-            self.links.iter_mut().for_each(|link| {
-                let stl_mesh_file = &link.stl_mesh_file_path;
-                if let Some(stl_mesh_file) = stl_mesh_file {
-                    let filename = stl_mesh_file.filename_without_extension().unwrap();
-
-                    let mut target_path_stub = OStemCellPath::new_asset_path();
-                    target_path_stub.append_file_location(&OAssetLocation::LinkConvexDecompositionLevel { robot_name: &self.robot_name, level, link_mesh_name: &filename });
-
-                    let exists = target_path_stub.exists();
+            for link in self.links.iter_mut() {
+                if let Some(stl_mesh_file) = &link.stl_mesh_file_path {
+                    let filename = stl_mesh_file.filename_without_extension().ok_or(OptimaError::new_file_io(stl_mesh_file.to_string(), "mesh file has no file name"))?;
 
-                    if !exists {
-                        let convex_decomposition = stl_mesh_file.load_stl().to_trimesh().to_convex_decomposition(*max_num);
-                        oprint(&format!("computing convex decomposition of {:?} at level {:?}.  {:?} convex subcomponents found.", filename, level, convex_decomposition.len()), PrintMode::Println, PrintColor::Green);
-
-                        convex_decomposition.iter().enumerate().for_each(|(i, trimesh)| {
-                            let mut target_path = target_path_stub.clone();
-                            target_path.append(&(filename.clone() + "_" + i.to_string().as_str() + ".stl"));
-                            trimesh.save_to_stl(&target_path);
-                        });
-                    }
+                    let mut directory = OStemCellPath::new_asset_path();
+                    directory.append_file_location(&OAssetLocation::LinkConvexDecompositionLevel { robot_name: &self.robot_name, level, link_mesh_name: &filename });
 
-                    let files = target_path_stub.get_all_items_in_directory_as_paths(false, false);
-                    // link.convex_decomposition_file_paths = files.clone();
-                    link.convex_decomposition_levels_file_paths.push(files.clone());
+                    let files = convex_decomposition_mesh_files(stl_mesh_file, *max_num, &directory, &cache)?;
+                    link.convex_decomposition_levels_file_paths.push(files);
                 }
-            });
+            }
         }
+        Ok(())
+    }
+    /// Replaces each link's convex hull with the decimated convex hull of its mesh, and each of its
+    /// convex subcomponents with the decimated convex hull of that subcomponent (see
+    /// `OTriMesh::decimated_convex_hull`), so the simplified shapes still contain the originals.
+    /// Links whose meshes cannot be loaded keep their original meshes.  Returns false, leaving the
+    /// links unchanged, if cancelled.
+    fn set_link_decimated_collision_mesh_file_paths(&mut self, decimation: &OMeshDecimation, progress: &OProgressHandle) -> bool {
+        let mut directory = OStemCellPath::new_asset_path();
+        directory.append_file_location(&OAssetLocation::ChainDecimatedCollisionMeshes { robot_name: &self.robot_name });
+
+        let decimate = |path: &OStemCellPath| {
+            decimated_convex_hull_file(path, decimation, &directory).map_err(|e| {
+                tracing::warn!(mesh = %path.to_string(), error = %e, "could not decimate mesh; keeping the original mesh");
+            }).ok()
+        };
+
+        let num_links = self.links.len();
+        let mut collision_mesh_file_paths = vec![];
+        for (i, link) in self.links.iter().enumerate() {
+            if !progress.report("decimation of collision meshes", i as f64 / num_links as f64) { return false; }
+            if !link.is_present_in_model {
+                collision_mesh_file_paths.push((link.convex_hull_file_path.clone(), link.convex_decomposition_file_paths.clone()));
+                continue;
+            }
+            let convex_hull_file_path = match &link.stl_mesh_file_path {
+                Some(stl_mesh_file_path) if link.convex_hull_file_path.is_some() => { decimate(stl_mesh_file_path).or_else(|| link.convex_hull_file_path.clone()) }
+                _ => { link.convex_hull_file_path.clone() }
+            };
+            let convex_decomposition_file_paths = link.convex_decomposition_file_paths.iter().map(|x| decimate(x).unwrap_or_else(|| x.clone())).collect();
+            collision_mesh_file_paths.push((convex_hull_file_path, convex_decomposition_file_paths));
+        }
+
+        self.links.iter_mut().zip(collision_mesh_file_paths).for_each(|(link, (convex_hull_file_path, convex_decomposition_file_paths))| {
+            link.convex_hull_file_path = convex_hull_file_path;
+            link.convex_decomposition_file_paths = convex_decomposition_file_paths;
+        });
+        true
     }
     fn set_robot_parry_shape_scene(&mut self) {
         self.parry_shape_scene = ORobotParryShapeScene::new(self);
     }
-    fn preprocess_robot_parry_shape_scene(&mut self) {
+    fn preprocess_robot_parry_shape_scene(&mut self, progress: &OProgressHandle, seed: Option<u64>) -> bool {
         let mut parry_shape_scene = ORobotParryShapeScene::new(self);
 
+        // each sampling stage gets its own seed from one stream so the stages do not repeat each
+        // other's samples.
+        let mut seeds = get_rng(seed);
+
         // parry_shape_scene.add_non_collision_states_pair_skips::<Vec<T>>(self, &self.non_collision_states);
         let r = Arc::new(self.clone());
-        parry_shape_scene.preprocess_non_collision_states_pair_skips(r.clone(), &self.non_collision_states);
-        parry_shape_scene.preprocess_always_in_collision_states_pair_skips(r.clone(), 5000);
-        parry_shape_scene.preprocess_never_in_collision_states_pair_skips(r.clone(), 5000);
-        parry_shape_scene.preprocess_shape_average_distances(r.clone(), 1000);
+        // shares of the overall progress are rough estimates of how long each stage takes.
+        parry_shape_scene.preprocess_non_collision_states_pair_skips(r.clone(), &self.non_collision_states, &progress.sub_range(0.0, 0.02));
+        if progress.is_cancelled() { return false; }
+        parry_shape_scene.preprocess_always_in_collision_states_pair_skips(r.clone(), 5000, &progress.sub_range(0.02, 0.36), Some(seeds.next_u64()));
+        if progress.is_cancelled() { return false; }
+        parry_shape_scene.preprocess_never_in_collision_states_pair_skips(r.clone(), 5000, &progress.sub_range(0.36, 0.70), Some(seeds.next_u64()));
+        if progress.is_cancelled() { return false; }
+        parry_shape_scene.preprocess_shape_average_distances(r.clone(), 1000, &progress.sub_range(0.70, 1.0), Some(seeds.next_u64()));
+        if progress.is_cancelled() { return false; }
 
         self.parry_shape_scene = parry_shape_scene;
+        true
+    }
+    /// The geometry of the given visual in the visual's frame, with the urdf mesh scale applied.
+    /// The first visual's mesh is the link's stl mesh; other meshes are looked up among the
+    /// robot's original meshes.
+    pub fn link_visual_trimesh(&self, link_idx: usize, visual_idx: usize) -> Option<OTriMesh> {
+        let link = self.links.get(link_idx)?;
+        let visual = link.visual().get(visual_idx)?;
+        match visual.geometry() {
+            OGeometry::Mesh { filename, scale } => {
+                let path = match visual_idx {
+                    0 => { link.stl_mesh_file_path().clone()? }
+                    _ => { self.original_mesh_file_path(filename)? }
+                };
+                let trimesh = OTriMesh::try_to_get_trimesh_from_path(&path)?;
+                match scale {
+                    None => { Some(trimesh) }
+                    Some(scale) => {
+                        let points = trimesh.points().iter().map(|p| [p[0] * scale[0], p[1] * scale[1], p[2] * scale[2]]).collect();
+                        Some(OTriMesh::new(points, trimesh.indices().clone()))
+                    }
+                }
+            }
+            geometry => { geometry_primitive_trimesh(geometry) }
+        }
+    }
+    /// The mesh file of the given visual as it was given in the urdf (e.g., a dae file, with its
+    /// materials), or None if the visual is not a mesh or its file was not found.
+    pub fn link_visual_mesh_file_path(&self, link_idx: usize, visual_idx: usize) -> Option<OStemCellPath> {
+        let link = self.links.get(link_idx)?;
+        match (visual_idx, link.visual().get(visual_idx)?.geometry()) {
+            (0, OGeometry::Mesh { .. }) => { link.original_mesh_file_path().clone() }
+            (_, OGeometry::Mesh { filename, .. }) => { self.original_mesh_file_path(filename) }
+            _ => { None }
+        }
+    }
+    /// Where `set_link_original_mesh_file_paths` keeps a urdf mesh, if it is there.
+    fn original_mesh_file_path(&self, mesh_filename: &str) -> Option<OStemCellPath> {
+        let mut target_path = OStemCellPath::new_asset_path();
+        target_path.append_file_location(&OAssetLocation::ChainOriginalMeshes { robot_name: &self.robot_name });
+        let mut downloaded_path = target_path.clone();
+        downloaded_path.append_vec(&urdf_mesh_relative_path_components(mesh_filename));
+        if downloaded_path.exists() { return Some(downloaded_path); }
+        target_path.append(urdf_mesh_relative_path_components(mesh_filename).last()?);
+        if target_path.exists() { Some(target_path) } else { None }
+    }
+}
+/// Jacobians
+impl<C: O3DPoseCategory, L: OLinalgCategory + 'static> ORobot<f64, C, L> {
+    /// Geometric jacobian of the given link (6 x num_dofs, translational rows first, then
+    /// rotational rows expressed in the world frame), computed with forward mode AD.  The robot is
+    /// converted to an AD type on every call; to compute many jacobians, convert it once and use
+    /// `get_link_jacobian_differentiable_block`.
+    pub fn jacobian(&self, state: &[f64], link_idx: usize) -> Result<DMatrix<f64>, OptimaError> {
+        let ad_robot = self.to_other_ad_type::<adfn<8>>();
+        let block = self.get_link_jacobian_differentiable_block(ForwardADMulti::<adfn<8>>::new(), Cow::Borrowed(&ad_robot), link_idx)?;
+        self.link_jacobian_from_block(&block, state)
+    }
+    /// Block whose derivative at a state is the geometric jacobian of the given link (see
+    /// `DifferentiableFunctionLinkJacobian`).  `ad_robot` is this robot converted with
+    /// `to_other_ad_type::<E::T>()`; `derivative_method` must be an AD method.
+    pub fn get_link_jacobian_differentiable_block<'a, E: DerivativeMethodTrait>(&'a self, derivative_method: E, ad_robot: Cow<'a, ORobot<E::T, C, L>>, link_idx: usize) -> Result<DifferentiableBlockLinkJacobian<'a, C, L, E>, OptimaError> {
+        self.forward_kinematics(&vec![0.0; self.num_dofs()], None).get_link_pose(link_idx)?;
+        let f1 = DifferentiableFunctionLinkJacobian::new(Cow::Borrowed(self), link_idx);
+        let f2 = DifferentiableFunctionLinkJacobian::new(ad_robot, link_idx);
+        Ok(DifferentiableBlockLinkJacobian::new(derivative_method, f1, f2))
+    }
+    /// The jacobian from a block made by `get_link_jacobian_differentiable_block`.
+    pub fn link_jacobian_from_block<E: DerivativeMethodTrait>(&self, block: &DifferentiableBlockLinkJacobian<C, L, E>, state: &[f64]) -> Result<DMatrix<f64>, OptimaError> {
+        if state.len() != self.num_dofs() { return Err(OptimaError::InvalidInput(format!("expected a state of length {}, got {}", self.num_dofs(), state.len()))); }
+        Ok(block.derivative(state).1)
+    }
+    /// Yoshikawa manipulability measure of the given link (see `manipulability_from_jacobian`).
+    pub fn manipulability(&self, state: &[f64], link_idx: usize) -> Result<f64, OptimaError> {
+        Ok(manipulability_from_jacobian(&self.jacobian(state, link_idx)?))
+    }
+    /// The robot at the given state as a scene that can be saved to glTF or USD.  Each link that is
+    /// present in the model becomes a node (poses are relative to the parent link), and each of the
+    /// link's visuals is a child of its link node.  Visual meshes other than a link's first are
+    /// looked up among the robot's original meshes and are left out if they cannot be found.
+    /// Shapes in `environment` are added at their current poses.
+    pub fn to_scene_export(&self, state: &[f64], environment: Option<&OParryGenericShapeScene<f64, C::P<f64>>>) -> Result<OSceneExport, OptimaError> {
+        if state.len() != self.num_dofs() { return Err(OptimaError::InvalidInput(format!("expected a state of length {}, got {}", self.num_dofs(), state.len()))); }
+        let mut out = OSceneExport::new();
+        let fk_res = self.forward_kinematics(&state.to_vec(), None);
+        let mut link_node_idxs: Vec<Option<usize>> = vec![None; self.links.len()];
+
+        let robot_node_idx = out.add_node(&self.robot_name, None, [0.0; 3], [1.0, 0.0, 0.0, 0.0], None)?;
+        for link_idx in self.kinematic_hierarchy.iter().flatten() {
+            let link = &self.links[*link_idx];
+            if !link.is_present_in_model() { continue; }
+            let pose = match fk_res.get_link_pose(*link_idx) {
+                Err(_) => { continue; }
+                Ok(pose) => { pose }
+            };
+
+            let parent = link.parent_link_idx.and_then(|x| link_node_idxs[x].map(|y| (x, y)));
+            let node_idx = match parent {
+                None => { out.add_node_from_pose(link.name(), Some(robot_node_idx), pose, None)? }
+                Some((parent_link_idx, parent_node_idx)) => {
+                    let parent_pose = fk_res.get_link_pose(parent_link_idx)?;
+                    out.add_node_from_pose(link.name(), Some(parent_node_idx), &parent_pose.inverse().mul(pose), None)?
+                }
+            };
+            link_node_idxs[*link_idx] = Some(node_idx);
+
+            for (visual_idx, visual) in link.visual().iter().enumerate() {
+                let Some(trimesh) = self.link_visual_trimesh(*link_idx, visual_idx) else { continue; };
+                let color = visual.material().as_ref().and_then(|x| x.color().as_ref()).map(|x| *x.rgba());
+                let mesh_name = if visual_idx == 0 { format!("{}_visual", link.name()) } else { format!("{}_visual_{}", link.name(), visual_idx) };
+                let mesh_idx = out.add_mesh(&mesh_name, trimesh, color);
+                out.add_node_from_pose(&mesh_name, Some(node_idx), visual.origin().pose(), Some(mesh_idx))?;
+            }
+        }
+
+        if let Some(environment) = environment {
+            let environment_node_idx = out.add_node("environment", None, [0.0; 3], [1.0, 0.0, 0.0, 0.0], None)?;
+            environment.add_to_scene_export(&mut out, Some(environment_node_idx), "environment_shape", None)?;
+        }
+
+        Ok(out)
+    }
+    /// Saves `to_scene_export` to the given path; the format (`gltf`, `glb`, or `usda`) is picked
+    /// from the file extension.
+    pub fn export_scene(&self, state: &[f64], environment: Option<&OParryGenericShapeScene<f64, C::P<f64>>>, path: &OPath) -> Result<(), String> {
+        self.to_scene_export(state, environment)?.save_from_extension(path)
     }
 }
 impl<C: O3DPoseCategory, L: OLinalgCategory> ORobot<f64, C, L> {
-    pub fn get_ik_differentiable_block<'a, E, FQ, Q>(&'a self, derivative_method: E, filter_query: OwnedPairGroupQry<'a, f64, FQ>, distance_query: OwnedPairGroupQry<'a, f64, Q>, constant_selector: Option<OParryPairSelector>, init_state: &[f64], ik_goal_link_idxs: Vec<usize>, linf_dis_cutoff: f64, dis_filter_cutoff: f64, ee_matching_weight: f64, self_collision_avoidance_weight: f64, min_vel_weight: f64, min_acc_weight: f64, min_jerk_weight: f64) -> DifferentiableBlock<'a, DifferentiableFunctionClassIKObjective<C, L, FQ, Q>, E>
+    /// `derivative_method` picks how gradients are computed.  `ForwardADMulti<adfn<N>>` is usually
+    /// fastest for up to ~16 dofs, but its cost grows with the number of inputs (one pass per N of
+    /// them); `ReverseAD` gets the whole gradient of the (scalar) objective from a single backward
+    /// pass, so it is the better choice for high-dof robots.  Reverse mode records onto ad_trait's
+    /// global tape, so a `ReverseAD` block should only be evaluated on one thread at a time.
+    pub fn get_ik_differentiable_block<'a, E, FQ, Q>(&'a self, derivative_method: E, filter_query: OwnedPairGroupQry<'a, f64, FQ>, distance_query: OwnedPairGroupQry<'a, f64, Q>, constant_selector: Option<OParryPairSelector>, init_state: &[f64], ik_goal_link_idxs: Vec<usize>, linf_dis_cutoff: f64, dis_filter_cutoff: f64, objective: CompositeObjective) -> DifferentiableBlock<'a, DifferentiableFunctionClassIKObjective<C, L, FQ, Q>, E>
         where E: DerivativeMethodTrait,
               FQ: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=OParryFilterOutputCategory>,
               Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory> {
@@ -990,11 +1324,77 @@ impl<C: O3DPoseCategory, L: OLinalgCategory> ORobot<f64, C, L> {
         let last_proximity_filter_state: Arc<RwLock<Option<Vec<f64>>>> = Arc::new(RwLock::new(None));
         let filter_output: Arc<RwLock<Option<OParryFilterOutput>>> = Arc::new(RwLock::new(None));
 
-        let f2 = self.get_ik_objective_function(Cow::Owned(self.to_other_ad_type::<E::T>()), filter_query.clone(), distance_query.clone(), constant_selector.clone(), init_state, ik_goal_link_idxs.clone(), linf_dis_cutoff, dis_filter_cutoff, ee_matching_weight, self_collision_avoidance_weight, min_vel_weight, min_acc_weight, min_jerk_weight, last_proximity_filter_state.clone(), filter_output.clone());
-        let f1= self.get_ik_objective_function(Cow::Borrowed(self), filter_query, distance_query, constant_selector, init_state, ik_goal_link_idxs, linf_dis_cutoff, dis_filter_cutoff, ee_matching_weight, self_collision_avoidance_weight, min_vel_weight, min_acc_weight, min_jerk_weight, last_proximity_filter_state.clone(), filter_output.clone());
+        let f2 = self.get_ik_objective_function(Cow::Owned(self.to_other_ad_type::<E::T>()), filter_query.clone(), distance_query.clone(), constant_selector.clone(), init_state, ik_goal_link_idxs.clone(), linf_dis_cutoff, dis_filter_cutoff, objective.clone(), last_proximity_filter_state.clone(), filter_output.clone()).without_term_value_recording();
+        let f1= self.get_ik_objective_function(Cow::Borrowed(self), filter_query, distance_query, constant_selector, init_state, ik_goal_link_idxs, linf_dis_cutoff, dis_filter_cutoff, objective, last_proximity_filter_state.clone(), filter_output.clone());
 
         DifferentiableBlockIKObjective::new(derivative_method, f1, f2)
     }
+    /// Solves many independent ik problems in parallel (e.g., for workspace studies or solver
+    /// benchmarking).  `goals[i]` holds one pose per entry of `goal_link_idxs`, and `seeds` holds
+    /// either one initial state per problem or a single state shared by all of them.  A block and
+    /// optimizer are built for each chunk of problems that rayon hands to a worker (via `map_init`),
+    /// so they are reused across the problems in that chunk but are not strictly one per thread.
+    /// With `ReverseAD` the problems are solved on a single thread (see
+    /// `get_ik_differentiable_block`).  Returns an error if the goals or seeds do not have the
+    /// expected shape, if `settings` holds an invalid objective weight, or if the thread pool cannot
+    /// be built.
+    pub fn solve_ik_batch<E>(&self, derivative_method: E, goal_link_idxs: &[usize], goals: &[Vec<C::P<f64>>], seeds: &[Vec<f64>], settings: &IKBatchSettings) -> Result<Vec<IKBatchResult>, OptimaError>
+        where C: 'static,
+              L: 'static,
+              E: DerivativeMethodTrait + Clone + Send + Sync,
+              E::T: 'static,
+              C::P<f64>: Sync,
+              Self: Sync {
+        let objective = settings.to_composite_objective()?;
+        if goals.is_empty() { return Ok(vec![]); }
+        if seeds.len() != 1 && seeds.len() != goals.len() { return Err(OptimaError::InvalidInput(format!("expected one seed per problem ({}) or a single shared seed, got {}", goals.len(), seeds.len()))); }
+        if let Some(seed) = seeds.iter().find(|x| x.len() != self.num_dofs) { return Err(OptimaError::InvalidInput(format!("every seed must have {} values, got one with {}", self.num_dofs, seed.len()))); }
+        if let Some(problem_idx) = goals.iter().position(|x| x.len() != goal_link_idxs.len()) { return Err(OptimaError::InvalidInput(format!("problem {} has {} goals, expected {}", problem_idx, goals[problem_idx].len(), goal_link_idxs.len()))); }
+        for link_idx in goal_link_idxs { OptimaError::check_idx("link", *link_idx, self.links.len())?; }
+        let _span = tracing::info_span!("solve_ik_batch", robot = %self.robot_name, num_problems = goals.len()).entered();
+
+        let solve = || -> Vec<IKBatchResult> {
+            goals.par_iter().enumerate().map_init(|| {
+                let db = self.get_ik_differentiable_block(derivative_method.clone(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &seeds[0], goal_link_idxs.to_vec(), 0.0, 0.0, objective.clone());
+                let o = SimpleOpEnOptimizer::new(self.get_dof_lower_bounds(), self.get_dof_upper_bounds(), settings.optimizer_tolerance);
+                (db, o)
+            }, |(db, o), (problem_idx, problem_goals)| {
+                let _span = tracing::debug_span!("ik_solve", problem_idx).entered();
+                let seed = if seeds.len() == 1 { &seeds[0] } else { &seeds[problem_idx] };
+                problem_goals.iter().enumerate().for_each(|(goal_idx, pose)| db.update_ik_pose(goal_idx, pose.clone(), IKGoalUpdateMode::Absolute));
+                db.update_prev_states(seed.clone());
+
+                let start = Instant::now();
+                let res = o.optimize_unconstrained(seed, db);
+                let solve_time = start.elapsed();
+
+                let fk_res = self.forward_kinematics(&res.x_star().to_vec(), None);
+                let mut position_error = 0.0;
+                let mut orientation_error = 0.0;
+                problem_goals.iter().enumerate().for_each(|(goal_idx, pose)| {
+                    let link_pose = fk_res.get_link_pose_unchecked(goal_link_idxs[goal_idx]);
+                    position_error = f64::max(position_error, link_pose.translation().dis(pose.translation()));
+                    orientation_error = f64::max(orientation_error, link_pose.rotation().dis(pose.rotation()));
+                });
+                let success = position_error <= settings.success_position_tolerance && orientation_error <= settings.success_orientation_tolerance;
+                tracing::debug!(success, position_error, orientation_error, cost = res.f_star(), ?solve_time, "ik solve finished");
+
+                IKBatchResult { solution: res.x_star().to_vec(), cost: res.f_star(), success, position_error, orientation_error, solve_time }
+            }).collect()
+        };
+
+        let num_threads = if TypeId::of::<E::T>() == TypeId::of::<adr>() { Some(1) } else { settings.num_threads };
+        let out = match num_threads {
+            None => { solve() }
+            Some(num_threads) => {
+                let pool = rayon::ThreadPoolBuilder::new().num_threads(num_threads).build().map_err(|e| OptimaError::Generic(format!("could not build the ik batch thread pool: {}", e)))?;
+                pool.install(solve)
+            }
+        };
+        tracing::info!(num_successes = out.iter().filter(|x| x.success).count(), num_problems = out.len(), "ik batch finished");
+
+        Ok(out)
+    }
     pub fn get_look_at_differentiable_block<'a, E, FQ, Q>(&'a self, derivative_method: E, filter_query: OwnedPairGroupQry<'a, f64, FQ>, distance_query: OwnedPairGroupQry<'a, f64, Q>, constant_selector: Option<OParryPairSelector>, init_state: &[f64], ik_goal_link_idxs: Vec<usize>, looker_link: usize, looker_forward_axis: AxisDirection, looker_side_axis: AxisDirection, look_at_target: LookAtTarget<f64, O3DVecCategoryArr>, linf_dis_cutoff: f64, dis_filter_cutoff: f64, ee_matching_weight: f64, self_collision_avoidance_weight: f64, min_vel_weight: f64, min_acc_weight: f64, min_jerk_weight: f64, look_at_weight: f64, roll_prevention_weight: f64) -> DifferentiableBlock<'a, DifferentiableFunctionClassLookAt<C, L, FQ, Q>, E>
         where E: DerivativeMethodTrait,
               FQ: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=OParryFilterOutputCategory>,
@@ -1007,10 +1407,20 @@ impl<C: O3DPoseCategory, L: OLinalgCategory> ORobot<f64, C, L> {
 
         DifferentiableBlock::new(derivative_method, f1, f2)
     }
+    /// Trajectory objectives have num_waypoints * num_dofs inputs, so `ReverseAD` is typically much
+    /// faster here than forward mode once there are more than a handful of waypoints.
+    pub fn get_trajectory_differentiable_block<'a, E, Q>(&'a self, derivative_method: E, distance_query: OwnedPairGroupQry<'a, f64, Q>, selector: OParryPairSelector, trajectory_objective: TrajectoryObjective<C>) -> DifferentiableBlockTrajectoryObjective<'a, C, L, Q, E>
+        where E: DerivativeMethodTrait,
+              Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory> {
+        let f1 = DifferentiableFunctionTrajectoryObjective::new(Cow::Borrowed(self), trajectory_objective.num_waypoints(), trajectory_objective.terms().clone(), distance_query, selector);
+        let f2 = f1.to_other_ad_type::<E::T>();
+
+        DifferentiableBlockTrajectoryObjective::new(derivative_method, f1, f2)
+    }
 }
 /// Objective Functions
 impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory> ORobot<T, C, L > {
-    pub fn get_ik_objective_function<'a, T1, FQ, Q>(&'a self, robot: Cow<'a, ORobot<T1, C, L>>, filter_query: OwnedPairGroupQry<'a, f64, FQ>, distance_query: OwnedPairGroupQry<'a, f64, Q>, constant_selector: Option<OParryPairSelector>, init_state: &[f64], ik_goal_link_idxs: Vec<usize>, linf_dis_cutoff: f64, dis_filter_cutoff: f64, ee_matching_weight: f64, self_collision_avoidance_weight: f64, min_vel_weight: f64, min_acc_weight: f64, min_jerk_weight: f64, last_proximity_filter_state: Arc<RwLock<Option<Vec<f64>>>>, filter_output: Arc<RwLock<Option<OParryFilterOutput>>>) -> DifferentiableFunctionIKObjective<T1, C, L, FQ, Q>
+    pub fn get_ik_objective_function<'a, T1, FQ, Q>(&'a self, robot: Cow<'a, ORobot<T1, C, L>>, filter_query: OwnedPairGroupQry<'a, f64, FQ>, distance_query: OwnedPairGroupQry<'a, f64, Q>, constant_selector: Option<OParryPairSelector>, init_state: &[f64], ik_goal_link_idxs: Vec<usize>, linf_dis_cutoff: f64, dis_filter_cutoff: f64, objective: CompositeObjective, last_proximity_filter_state: Arc<RwLock<Option<Vec<f64>>>>, filter_output: Arc<RwLock<Option<OParryFilterOutput>>>) -> DifferentiableFunctionIKObjective<T1, C, L, FQ, Q>
         where T1: AD,
               FQ: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=OParryFilterOutputCategory>,
               Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory>
@@ -1018,9 +1428,9 @@ impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory> ORobot<T, C, L > {
         let fk_res = self.forward_kinematics(&init_state.to_vec().ovec_to_other_ad_type::<T>(), None);
 
         let mut ik_goals: Vec<IKGoal<T, C::P<T>>> = vec![];
-        ik_goal_link_idxs.iter().for_each(|x| { ik_goals.push(IKGoal::new(*x, fk_res.get_link_pose(*x).as_ref().expect("error").clone(), T::constant(1.0))); });
+        ik_goal_link_idxs.iter().for_each(|x| { ik_goals.push(IKGoal::new(*x, fk_res.get_link_pose_unchecked(*x).clone(), T::constant(1.0))); });
 
-        let f = DifferentiableFunctionIKObjective::new(robot, ik_goals.to_other_generic_types::<T1, C>(), init_state.to_vec().ovec_to_other_ad_type::<T1>(), filter_query.to_other_ad_type::<T1>(), distance_query.to_other_ad_type::<T1>(), constant_selector, T1::constant(dis_filter_cutoff), linf_dis_cutoff, last_proximity_filter_state.clone(), filter_output.clone(), T1::constant(ee_matching_weight), T1::constant(self_collision_avoidance_weight), T1::constant(min_vel_weight), T1::constant(min_acc_weight), T1::constant(min_jerk_weight));
+        let f = DifferentiableFunctionIKObjective::new(robot, ik_goals.to_other_generic_types::<T1, C>(), init_state.to_vec().ovec_to_other_ad_type::<T1>(), filter_query.to_other_ad_type::<T1>(), distance_query.to_other_ad_type::<T1>(), constant_selector, T1::constant(dis_filter_cutoff), linf_dis_cutoff, last_proximity_filter_state.clone(), filter_output.clone(), objective);
 
         f
     }
@@ -1028,7 +1438,7 @@ impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory> ORobot<T, C, L > {
         where T1: AD,
               FQ: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=OParryFilterOutputCategory>,
               Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory> {
-        let ik_objective = self.get_ik_objective_function(robot, filter_query, distance_query, constant_selector, init_state, ik_goal_link_idxs, linf_dis_cutoff, dis_filter_cutoff, ee_matching_weight, self_collision_avoidance_weight, min_vel_weight, min_acc_weight, min_jerk_weight, last_proximity_filter_state, filter_output);
+        let ik_objective = self.get_ik_objective_function(robot, filter_query, distance_query, constant_selector, init_state, ik_goal_link_idxs, linf_dis_cutoff, dis_filter_cutoff, CompositeObjective::new_ik_unchecked(ee_matching_weight, self_collision_avoidance_weight, min_vel_weight, min_acc_weight, min_jerk_weight), last_proximity_filter_state, filter_output).without_term_value_recording();
 
         DifferentiableFunctionLookAt::new(ik_objective, looker_link, looker_forward_axis, looker_side_axis, look_at_target.to_other_ad_type::<T1>(), T1::constant(look_at_weight), T1::constant(roll_prevention_weight))
     }
@@ -1112,12 +1522,108 @@ impl<T: AD, P: O3DPose<T>> FKResult<T, P> {
     pub fn link_poses(&self) -> &Vec<Option<P>> {
         &self.link_poses
     }
+    /// Errors if `link_idx` is out of bounds or the link does not have a pose (e.g., it is past a
+    /// dead end link).
     #[inline]
-    pub fn get_link_pose(&self, link_idx: usize) -> &Option<P> {
-        &self.link_poses[link_idx]
+    pub fn get_link_pose(&self, link_idx: usize) -> Result<&P, OptimaError> {
+        OptimaError::check_idx("link", link_idx, self.link_poses.len())?;
+        self.link_poses[link_idx].as_ref().ok_or(OptimaError::LinkHasNoPose { link_idx })
+    }
+    #[inline(always)]
+    pub fn get_link_pose_unchecked(&self, link_idx: usize) -> &P {
+        self.link_poses[link_idx].as_ref().expect("link does not have a pose")
     }
 }
 
+/// Copies a urdf mesh into the robot's original mesh folder (searching the home directory for it if
+/// it is not already there) and returns where it is kept.
+fn copy_original_mesh_file(robot_name: &str, link_name: &str, filename: &str) -> Result<OStemCellPath, OptimaError> {
+    // `split` always yields at least one item.
+    let filepath = filename.split("//").last().unwrap_or_default().to_string();
+    let split: Vec<String> = filepath.split("/").map(|x| x.to_string()).collect();
+
+    let file_check = split.last().cloned().unwrap_or_default();
+    if file_check.is_empty() {
+        return Err(OptimaError::InvalidInput(format!("link {} has a mesh with no file name: {:?}", link_name, filename)));
+    }
+    let mut target_path = OStemCellPath::new_asset_path();
+    target_path.append_file_location(&OAssetLocation::ChainOriginalMeshes { robot_name });
+    // downloaded robots keep each mesh at its path in the package (see
+    // `download_robot`); otherwise meshes are stored by file name.
+    let mut downloaded_path = target_path.clone();
+    downloaded_path.append_vec(&urdf_mesh_relative_path_components(filename));
+    target_path.append(&file_check);
+    if downloaded_path.exists() { target_path = downloaded_path; }
+    let exists = target_path.exists();
+
+    if !exists {
+        let asset_path = OPath::new_home_path();
+        tracing::info!(mesh = %filepath, "searching for mesh");
+        let found_paths = asset_path.walk_directory_and_match(OPathMatchingPattern::PathComponents(split), OPathMatchingStopCondition::First);
+        let found_path = found_paths.first().ok_or(OptimaError::new_file_io(filepath.clone(), format!("could not find the mesh of link {}", link_name)))?;
+        found_path.copy_file_to_destination(target_path.as_physical_path()?)?;
+    }
+
+    Ok(target_path)
+}
+
+/// Triangle mesh of a primitive urdf geometry in the geometry's frame (cylinders and capsules run
+/// along z, as in urdf); `None` for meshes.
+pub fn geometry_primitive_trimesh(geometry: &OGeometry) -> Option<OTriMesh> {
+    let y_up_shape = |shape: OParryShape<f64, Isometry3<f64>>| {
+        let trimesh = shape.base_shape().base_shape().to_trimesh();
+        // parry's cylinders and capsules run along y; rotating by 90 degrees about x takes y to z.
+        OTriMesh::new(trimesh.points().iter().map(|p| [p[0], -p[2], p[1]]).collect(), trimesh.indices().clone())
+    };
+    match geometry {
+        OGeometry::Box { size } => { Some(OParryShape::new(Cuboid::new(Vector3::new(size[0] / 2.0, size[1] / 2.0, size[2] / 2.0)), Isometry3::identity(), false, false).base_shape().base_shape().to_trimesh()) }
+        OGeometry::Sphere { radius } => { Some(OParryShape::new(Ball::new(*radius), Isometry3::identity(), false, false).base_shape().base_shape().to_trimesh()) }
+        OGeometry::Cylinder { radius, length } => { Some(y_up_shape(OParryShape::new_cylinder(length / 2.0, *radius, Isometry3::identity(), false, false))) }
+        OGeometry::Capsule { radius, length } => { Some(y_up_shape(OParryShape::new_capsule(length / 2.0, *radius, Isometry3::identity(), false, false))) }
+        OGeometry::Mesh { .. } => { None }
+    }
+}
+
+/// Splits the stl mesh at `path` into at most `max_convex_hulls` convex pieces, saved in a
+/// directory inside `directory` named after the hash of the mesh and `max_convex_hulls`.  As with
+/// `decimated_convex_hull_file`, a changed mesh gets a new directory, so stale pieces are never
+/// picked up; the pieces of earlier versions of the mesh are removed.
+fn convex_decomposition_mesh_files(path: &OStemCellPath, max_convex_hulls: u32, directory: &OStemCellPath, cache: &OAssetCache) -> Result<Vec<OStemCellPath>, OptimaError> {
+    let key = OAssetCacheKey::new_from_files("convex_decomposition_files", &format!("max_convex_hulls={}", max_convex_hulls), &[path])?;
+    let mut target_directory = directory.clone();
+    target_directory.append(key.hash());
+
+    if !target_directory.exists() {
+        if directory.exists() { directory.delete_all_items_in_directory()?; }
+        let convex_decomposition = path.load_stl()?.to_trimesh().to_convex_decomposition_cached(max_convex_hulls, cache);
+        tracing::info!(mesh = %path.to_string(), max_convex_hulls, num_subcomponents = convex_decomposition.len(), "computed convex decomposition");
+
+        convex_decomposition.iter().enumerate().for_each(|(i, trimesh)| {
+            let mut target_path = target_directory.clone();
+            target_path.append(&format!("{}.stl", i));
+            trimesh.save_to_stl(&target_path);
+        });
+    }
+
+    Ok(target_directory.get_all_items_in_directory_as_paths(false, false))
+}
+
+/// Decimated convex hull of the mesh at `path` (see `OTriMesh::decimated_convex_hull`), saved in
+/// `directory` under a name derived from the mesh's contents and `decimation`, so each mesh is only
+/// decimated once per setting.
+fn decimated_convex_hull_file(path: &OStemCellPath, decimation: &OMeshDecimation, directory: &OStemCellPath) -> Result<OStemCellPath, OptimaError> {
+    let key = OAssetCacheKey::new_from_files("decimated_convex_hull", &decimation.params_string(), &[path])?;
+    let mut target_path = directory.clone();
+    target_path.append(&format!("{}.stl", key.hash()));
+
+    if !target_path.exists() {
+        let trimesh = OTriMesh::try_to_get_trimesh_from_path(path).ok_or_else(|| OptimaError::new_file_io(path.to_string(), "could not load mesh"))?;
+        trimesh.decimated_convex_hull(decimation).save_to_stl(&target_path);
+    }
+
+    Ok(target_path)
+}
+
 #[derive(Clone, Debug)]
 pub enum SaveRobot<'a> {
     Save(Option<&'a str>),
@@ -1132,3 +1638,307 @@ pub enum IKGoalMode<T: AD, C: O3DPoseCategory> {
     GlobalRelativeSeparate { offset: C::P<T> }
 }
 
+
+#[cfg(test)]
+pub (crate) mod tests {
+    use nalgebra::Isometry3;
+    use optima_proximity::pair_group_queries::{OParryDistanceGroupArgs, OwnedParryDistanceGroupQry};
+    use optima_proximity::pair_queries::{ParryDisMode, ParryShapeRep};
+    use crate::robotics_optimization::robotics_optimization_composite::CompositeObjectiveTerm;
+    use super::*;
+
+    /// A planar arm with two revolute joints about z and a fixed end effector, so tests do not need
+    /// anything from the asset folder.  The end effector is at (cos q0 + cos(q0 + q1), sin q0 +
+    /// sin(q0 + q1), 0).
+    pub (crate) fn two_link_arm() -> ORobotDefault {
+        two_link_arm_with_forearm_visuals(vec![])
+    }
+
+    pub (crate) fn two_link_arm_with_forearm_visuals(forearm_visuals: Vec<OVisual<f64, O3DPoseCategoryIsometry3>>) -> ORobotDefault {
+        let link = |name: &str| OLink::new_manual(name, vec![], vec![], OInertial::new_zeros());
+        let revolute_limit = || OJointLimit::new_manual(vec![10.0], vec![-3.0], vec![3.0], vec![2.0]);
+        let links = vec![link("base"), link("upper_arm"), OLink::new_manual("forearm", vec![], forearm_visuals, OInertial::new_zeros()), link("ee")];
+        let joints = vec![
+            OJoint::new_manual("shoulder", OJointType::Revolute, Isometry3::identity(), [0.0, 0.0, 1.0], "base", "upper_arm", revolute_limit(), None, None, None),
+            OJoint::new_manual("elbow", OJointType::Revolute, Isometry3::translation(1.0, 0.0, 0.0), [0.0, 0.0, 1.0], "upper_arm", "forearm", revolute_limit(), None, None, None),
+            OJoint::new_manual("wrist", OJointType::Fixed, Isometry3::translation(1.0, 0.0, 0.0), [0.0, 0.0, 1.0], "forearm", "ee", OJointLimit::new_manual(vec![0.0], vec![0.0], vec![0.0], vec![0.0]), None, None, None)
+        ];
+
+        ORobotDefault::from_manual_unchecked("two_link_arm", links, joints)
+    }
+
+    type FAD = adfn<2>;
+
+    #[test]
+    fn visual_materials_given_only_by_name_use_the_robot_level_material() {
+        let urdf = urdf_rs::read_from_string(r#"<robot name="r">
+            <material name="blue"><color rgba="0 0 1 1"/></material>
+            <link name="a">
+                <visual><geometry><box size="1 1 1"/></geometry><material name="blue"/></visual>
+                <visual><geometry><box size="1 1 1"/></geometry><material name="blue"><color rgba="1 0 0 1"/></material></visual>
+                <visual><geometry><box size="1 1 1"/></geometry><material name="green"/></visual>
+            </link>
+        </robot>"#).expect("error");
+        let link = OLink::<f64, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra>::from_link(&urdf.links[0], &OMaterial::named_materials_from_urdf(&urdf));
+        let color = |visual_idx: usize| link.visual()[visual_idx].material().as_ref().and_then(|x| x.color().as_ref()).map(|x| *x.rgba());
+
+        assert_eq!(color(0), Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(color(1), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(color(2), None);
+    }
+
+    #[test]
+    fn solve_ik_batch_solves_reachable_goals() {
+        let robot = two_link_arm();
+        let ee = robot.get_link_idx_from_link_name_unchecked("ee");
+        let states = vec![vec![0.3, 0.5], vec![-0.4, 1.0], vec![1.0, -0.7]];
+        let goals: Vec<Vec<Isometry3<f64>>> = states.iter().map(|x| vec![robot.forward_kinematics(x, None).get_link_pose_unchecked(ee).clone()]).collect();
+        let seeds: Vec<Vec<f64>> = states.iter().map(|x| vec![x[0] + 0.05, x[1] - 0.05]).collect();
+        let settings = IKBatchSettings { num_threads: Some(2), ..IKBatchSettings::default() };
+
+        let results = robot.solve_ik_batch(ForwardADMulti::<FAD>::new(), &[ee], &goals, &seeds, &settings).expect("error");
+        assert_eq!(results.len(), 3);
+        for res in &results {
+            assert!(res.success, "{:?}", res);
+            assert_eq!(res.solution.len(), 2);
+        }
+    }
+
+    #[test]
+    fn solve_ik_batch_accepts_a_shared_seed_and_no_problems() {
+        let robot = two_link_arm();
+        let ee = robot.get_link_idx_from_link_name_unchecked("ee");
+        let goal = robot.forward_kinematics(&vec![0.2, 0.2], None).get_link_pose_unchecked(ee).clone();
+
+        let results = robot.solve_ik_batch(ForwardADMulti::<FAD>::new(), &[ee], &vec![vec![goal.clone()], vec![goal]], &[vec![0.25, 0.15]], &IKBatchSettings::default()).expect("error");
+        assert_eq!(results.len(), 2);
+        assert!(robot.solve_ik_batch(ForwardADMulti::<FAD>::new(), &[ee], &[], &[], &IKBatchSettings::default()).expect("error").is_empty());
+    }
+
+    #[test]
+    fn solve_ik_batch_rejects_malformed_input() {
+        let robot = two_link_arm();
+        let ee = robot.get_link_idx_from_link_name_unchecked("ee");
+        let goal = robot.forward_kinematics(&vec![0.2, 0.2], None).get_link_pose_unchecked(ee).clone();
+        let goals = vec![vec![goal.clone()], vec![goal.clone()], vec![goal.clone()]];
+        let settings = IKBatchSettings::default();
+        let fd = || ForwardADMulti::<FAD>::new();
+
+        // two seeds for three problems.
+        assert!(robot.solve_ik_batch(fd(), &[ee], &goals, &[vec![0.0, 0.0], vec![0.0, 0.0]], &settings).is_err());
+        // seed with the wrong number of dofs.
+        assert!(robot.solve_ik_batch(fd(), &[ee], &goals, &[vec![0.0]], &settings).is_err());
+        // a problem with two goals when one goal link was given.
+        assert!(robot.solve_ik_batch(fd(), &[ee], &vec![vec![goal.clone(), goal.clone()]], &[vec![0.0, 0.0]], &settings).is_err());
+        // goal link out of bounds.
+        assert!(robot.solve_ik_batch(fd(), &[100], &goals, &[vec![0.0, 0.0]], &settings).is_err());
+        // negative objective weight.
+        let mut bad_settings = IKBatchSettings::default();
+        bad_settings.objective_weights.push((CompositeObjectiveTerm::PoseMatching, -1.0));
+        assert!(robot.solve_ik_batch(fd(), &[ee], &goals, &[vec![0.0, 0.0]], &bad_settings).is_err());
+    }
+
+    #[test]
+    fn to_scene_export_exports_every_visual_of_a_link() {
+        let robot = two_link_arm_with_forearm_visuals(vec![
+            OVisual::new_manual(None, None, Isometry3::translation(0.5, 0.0, 0.0), OGeometry::Box { size: [1.0, 0.2, 0.2] }),
+            OVisual::new_manual(None, None, Isometry3::identity(), OGeometry::Cylinder { radius: 0.1, length: 0.4 })
+        ]);
+
+        let scene = robot.to_scene_export(&[0.3, -0.2], None).expect("error");
+        let names: Vec<&str> = scene.meshes().iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, vec!["forearm_visual", "forearm_visual_1"]);
+
+        let forearm_node = scene.nodes().iter().position(|x| x.name == "forearm").expect("error");
+        let box_node = scene.nodes().iter().find(|x| x.mesh == Some(0)).expect("error");
+        assert_eq!(box_node.parent, Some(forearm_node));
+        assert!((box_node.translation[0] - 0.5).abs() < 1e-9);
+
+        // the cylinder runs along z, as in urdf.
+        let cylinder_points = scene.meshes()[1].trimesh.points();
+        let z_extent = cylinder_points.iter().map(|p| p[2].abs()).fold(0.0, f64::max);
+        let y_extent = cylinder_points.iter().map(|p| p[1].abs()).fold(0.0, f64::max);
+        assert!((z_extent - 0.2).abs() < 1e-9, "{}", z_extent);
+        assert!((y_extent - 0.1).abs() < 1e-6, "{}", y_extent);
+    }
+
+    #[test]
+    fn to_scene_export_rejects_a_state_of_the_wrong_length() {
+        let robot = two_link_arm();
+        assert!(matches!(robot.to_scene_export(&[0.0], None), Err(OptimaError::InvalidInput(_))));
+    }
+
+    /// `two_link_arm` with a box along each arm link and a ball at the end effector, so proximity
+    /// queries have shapes to work with.
+    fn two_link_arm_with_shapes() -> ORobotDefault {
+        let mut robot = two_link_arm();
+        let link_shapes = vec![
+            ("upper_arm", OParryShape::new_default(Cuboid::new(Vector3::new(0.4, 0.05, 0.05)), Isometry3::translation(0.5, 0.0, 0.0))),
+            ("forearm", OParryShape::new_default(Cuboid::new(Vector3::new(0.4, 0.05, 0.05)), Isometry3::translation(0.5, 0.0, 0.0))),
+            ("ee", OParryShape::new_default(Ball::new(0.1), Isometry3::identity()))
+        ];
+        for (link_name, shape) in link_shapes {
+            let link_idx = robot.get_link_idx_from_link_name_unchecked(link_name);
+            robot.parry_shape_scene.shapes.push(shape);
+            robot.parry_shape_scene.shape_idx_to_link_idx.push(link_idx);
+        }
+        robot
+    }
+
+    fn sample_states(robot: &ORobotDefault, num_states: u64) -> Vec<Vec<f64>> {
+        let bounds: Vec<(f64, f64)> = robot.get_dof_lower_bounds().into_iter().zip(robot.get_dof_upper_bounds().into_iter()).collect();
+        (0..num_states).map(|i| SimpleSampler::uniform_samples(&bounds, Some(i))).collect()
+    }
+
+    #[test]
+    fn f32_forward_kinematics_matches_f64() {
+        let robot64 = two_link_arm();
+        let robot32: ORobotF32 = robot64.to_other_ad_type::<f32>();
+
+        for state64 in sample_states(&robot64, 50) {
+            let fk64 = robot64.forward_kinematics(&state64, None);
+            let fk32 = robot32.forward_kinematics(&state64.ovec_to_other_ad_type::<f32>(), None);
+            for (p64, p32) in fk64.link_poses().iter().zip(fk32.link_poses().iter()) {
+                let (Some(p64), Some(p32)) = (p64, p32) else { panic!("a link has a pose in one precision only"); };
+                let p32 = p32.o3dpose_to_other_generic_category::<f64, O3DPoseCategoryIsometry3>();
+                assert!(p64.translation().dis(p32.translation()) < 1e-5, "{:?} vs {:?}", p64, p32);
+                assert!(p64.rotation().dis(p32.rotation()) < 1e-5, "{:?} vs {:?}", p64, p32);
+            }
+        }
+    }
+
+    #[test]
+    fn f32_jacobian_matches_f64() {
+        // the jacobian is only computed with AD in f64, so the f32 one is a central difference of
+        // f32 forward kinematics; the step trades truncation error against f32 rounding error.
+        let robot64 = two_link_arm();
+        let robot32: ORobotF32 = robot64.to_other_ad_type::<f32>();
+        let ee = robot64.get_link_idx_from_link_name_unchecked("ee");
+        let h = 1e-2_f32;
+
+        for state64 in sample_states(&robot64, 20) {
+            let jacobian64 = robot64.jacobian(&state64, ee).expect("error");
+            let state32 = state64.ovec_to_other_ad_type::<f32>();
+            for dof in 0..robot64.num_dofs() {
+                let (mut plus, mut minus) = (state32.clone(), state32.clone());
+                plus[dof] += h;
+                minus[dof] -= h;
+                let t_plus = robot32.forward_kinematics(&plus, None).get_link_pose_unchecked(ee).translation().clone();
+                let t_minus = robot32.forward_kinematics(&minus, None).get_link_pose_unchecked(ee).translation().clone();
+                for row in 0..3 {
+                    let column32 = (t_plus[row] - t_minus[row]) / (2.0 * h);
+                    assert!((jacobian64[(row, dof)] - column32 as f64).abs() < 1e-3, "row {}, dof {}: {} vs {}", row, dof, jacobian64[(row, dof)], column32);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn f32_self_distance_matches_f64() {
+        let robot64 = two_link_arm_with_shapes();
+        let robot32: ORobotF32 = robot64.to_other_ad_type::<f32>();
+        assert_eq!(robot32.parry_shape_scene.get_shapes().len(), 3);
+
+        for shape_rep in [ParryShapeRep::BoundingSphere, ParryShapeRep::OBB, ParryShapeRep::Full] {
+            let q64 = OwnedParryDistanceGroupQry::new(OParryDistanceGroupArgs::new(shape_rep.clone(), shape_rep.clone(), ParryDisMode::ContactDis, false, false, f64::MIN, false));
+            let q32 = q64.to_other_ad_type::<f32>();
+            for state64 in sample_states(&robot64, 50) {
+                let res64 = robot64.parry_shape_scene_self_query(&state64, &q64, &OParryPairSelector::HalfPairs, false);
+                let res32 = robot32.parry_shape_scene_self_query(&state64.ovec_to_other_ad_type::<f32>(), &q32, &OParryPairSelector::HalfPairs, false);
+                assert!((*res64.min_raw_dis() - *res32.min_raw_dis() as f64).abs() < 1e-4, "{:?}: {} vs {}", shape_rep, res64.min_raw_dis(), res32.min_raw_dis());
+            }
+        }
+    }
+
+    #[test]
+    fn from_manual_rejects_a_joint_with_an_unknown_link() {
+        let link = |name: &str| OLink::new_manual(name, vec![], vec![], OInertial::new_zeros());
+        let limit = OJointLimit::new_manual(vec![1.0], vec![-1.0], vec![1.0], vec![1.0]);
+        let joints = vec![OJoint::new_manual("joint", OJointType::Revolute, Isometry3::identity(), [0.0, 0.0, 1.0], "base", "missing", limit, None, None, None)];
+        let res = ORobotDefault::from_manual("unknown_link", vec![link("base"), link("tip")], joints);
+        assert_eq!(res.err(), Some(OptimaError::LinkNotFound { link_name: "missing".to_string() }));
+    }
+
+    #[test]
+    fn from_manual_rejects_a_mesh_without_a_file_name() {
+        let visual = OVisual::new_manual(None, None, Isometry3::identity(), OGeometry::Mesh { filename: "package://robot/meshes/".to_string(), scale: None });
+        let link = |name: &str| OLink::new_manual(name, vec![], vec![], OInertial::new_zeros());
+        let links = vec![link("base"), OLink::new_manual("tip", vec![], vec![visual], OInertial::new_zeros())];
+        let joints = vec![OJoint::new_manual("joint", OJointType::Fixed, Isometry3::identity(), [0.0, 0.0, 1.0], "base", "tip", OJointLimit::new_manual(vec![0.0], vec![0.0], vec![0.0], vec![0.0]), None, None, None)];
+        assert!(matches!(ORobotDefault::from_manual("no_mesh_file_name", links, joints), Err(OptimaError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn save_robot_rejects_the_reserved_name_and_keeps_the_robot_name() {
+        let mut robot = two_link_arm();
+        assert!(matches!(robot.save_robot(Some("robot_set_default")), Err(OptimaError::InvalidInput(_))));
+        assert_eq!(robot.robot_name(), "two_link_arm");
+    }
+
+    fn temp_directory(name: &str) -> OPath {
+        let dir = std::env::temp_dir().join(format!("optima_robot_test_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("error");
+        OPath::Path(dir)
+    }
+
+    fn temp_file(dir: &OPath, name: &str) -> OStemCellPath {
+        let mut out = OStemCellPath::new_from_path(dir.clone());
+        out.append(name);
+        out
+    }
+
+    /// Closed box about the origin with outward facing triangles.
+    fn box_trimesh(half_extents: [f64; 3]) -> OTriMesh {
+        let [x, y, z] = half_extents;
+        let points = vec![[-x, -y, -z], [x, -y, -z], [x, y, -z], [-x, y, -z], [-x, -y, z], [x, -y, z], [x, y, z], [-x, y, z]];
+        let indices = vec![[0, 2, 1], [0, 3, 2], [4, 5, 6], [4, 6, 7], [0, 1, 5], [0, 5, 4], [1, 2, 6], [1, 6, 5], [2, 3, 7], [2, 7, 6], [3, 0, 4], [3, 4, 7]];
+        OTriMesh::new(points, indices)
+    }
+
+    fn sorted_strings(paths: &[OStemCellPath]) -> Vec<String> {
+        let mut out: Vec<String> = paths.iter().map(|x| x.to_string()).collect();
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn convex_decomposition_mesh_files_are_reused_until_the_mesh_changes() {
+        let dir = temp_directory("convex_decomposition");
+        let mesh = temp_file(&dir, "mesh.stl");
+        let pieces_directory = temp_file(&dir, "pieces");
+        let cache = OAssetCache::new_disabled();
+
+        assert!(convex_decomposition_mesh_files(&mesh, 4, &pieces_directory, &cache).is_err());
+
+        box_trimesh([1.0, 0.5, 0.25]).save_to_stl(&mesh);
+        let pieces = convex_decomposition_mesh_files(&mesh, 4, &pieces_directory, &cache).expect("error");
+        assert!(!pieces.is_empty() && pieces.len() <= 4, "{}", pieces.len());
+        assert_eq!(sorted_strings(&convex_decomposition_mesh_files(&mesh, 4, &pieces_directory, &cache).expect("error")), sorted_strings(&pieces));
+
+        box_trimesh([2.0, 0.5, 0.25]).save_to_stl(&mesh);
+        let new_pieces = convex_decomposition_mesh_files(&mesh, 4, &pieces_directory, &cache).expect("error");
+        assert!(pieces.iter().all(|x| !x.exists()), "pieces of the old mesh were kept");
+        let max_x = new_pieces.iter().flat_map(|x| x.load_stl().expect("error").to_trimesh().points().clone()).map(|x| x[0]).fold(f64::MIN, f64::max);
+        assert!((max_x - 2.0).abs() < 0.1, "{}", max_x);
+    }
+
+    #[test]
+    fn a_single_convex_hull_per_link_is_the_links_convex_hull() {
+        let mut robot = two_link_arm();
+        let upper_arm = robot.get_link_idx_from_link_name_unchecked("upper_arm");
+        let dir = temp_directory("single_convex_hull");
+        robot.links[upper_arm].stl_mesh_file_path = Some(temp_file(&dir, "upper_arm.stl"));
+        robot.links[upper_arm].convex_hull_file_path = Some(temp_file(&dir, "upper_arm_convex_hull.stl"));
+
+        let cancelled = OProgressHandle::new();
+        cancelled.cancel();
+        assert!(!robot.set_link_convex_decomposition_mesh_file_paths(1, &cancelled));
+        assert!(robot.links[upper_arm].convex_decomposition_file_paths.is_empty());
+
+        // the meshes do not exist, so the subcomponent cannot have come from a decomposition.
+        assert!(robot.set_link_convex_decomposition_mesh_file_paths(1, &OProgressHandle::new()));
+        assert_eq!(sorted_strings(&robot.links[upper_arm].convex_decomposition_file_paths), vec![temp_file(&dir, "upper_arm_convex_hull.stl").to_string()]);
+        robot.links.iter().filter(|x| x.link_idx != upper_arm).for_each(|x| assert!(x.convex_decomposition_file_paths.is_empty()));
+    }
+}
@@ -46,11 +46,11 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory> ORobotSet<T, C, L>
         out
     }
     pub fn new_from_single_robot_name(robot_name: &str) -> Self {
-        Self::new_from_single_robot(ORobot::from_urdf(robot_name))
+        Self::new_from_single_robot(ORobot::from_urdf_unchecked(robot_name))
     }
     pub fn to_new_generic_types<T2: AD, C2: O3DPoseCategory, L2: OLinalgCategory>(&self) -> ORobotSet<T2, C2, L2> {
         let json_str = self.to_json_string();
-        ORobotSet::<T2, C2, L2>::from_json_string(&json_str)
+        ORobotSet::<T2, C2, L2>::from_json_string_unchecked(&json_str)
     }
     pub fn to_new_ad_type<T2: AD>(&self) -> ORobotSet<T2, C, L> {
         self.to_new_generic_types::<T2, C, L>()
@@ -404,7 +404,7 @@ impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory> ORobotWrapper<T, C, L> {
 }
 impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory + 'static> ORobot<T, C, L> {
     pub(crate) fn new_world_robot() -> Self {
-        Self::from_manual("world", vec![OLink::new_manual("world_link", vec![], vec![], OInertial::new_zeros())], vec![])
+        Self::from_manual_unchecked("world", vec![OLink::new_manual("world_link", vec![], vec![], OInertial::new_zeros())], vec![])
     }
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
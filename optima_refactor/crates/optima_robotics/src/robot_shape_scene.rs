@@ -1,19 +1,23 @@
 use std::borrow::Cow;
+use std::f64::consts::FRAC_PI_2;
 use std::marker::PhantomData;
 use std::sync::Arc;
 use ad_trait::AD;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
-use optima_console::output::{get_default_progress_bar};
+use optima_console::progress::OProgressHandle;
+use optima_file::cache::OAssetCache;
 use optima_linalg::{OLinalgCategory, OVec, OVecCategoryVec};
 use optima_proximity::pair_group_queries::{AHashMapWrapperSkipsWithReasonsTrait, OPairGroupQryTrait, OParryDistanceGroupArgs, OParryDistanceGroupQry, OParryIntersectGroupArgs, OParryIntersectGroupQry, OParryPairIdxs, OParryPairSelector, OSkipReason};
 use optima_proximity::pair_queries::{ParryDisMode, ParryShapeRep};
 use optima_proximity::shape_queries::{DistanceOutputTrait, IntersectOutputTrait};
 use optima_proximity::shape_scene::ShapeSceneTrait;
 use optima_proximity::shapes::OParryShape;
+use optima_sampling::get_rng;
 use optima_universal_hashmap::AHashMapWrapper;
 use crate::robot::{ORobot};
+use crate::robotics_components::{OCollision, OGeometry};
 
 /*
 #[serde_as]
@@ -135,7 +139,7 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
         }
 
         // h.keys().for_each(|x| { self.pair_skips.hashmap.insert(*x, ()); });
-        println!("found {} new non collision state pairs to skip", h.hashmap.len());
+        tracing::info!("found {} new non collision state pairs to skip", h.hashmap.len());
         self.non_collision_states_pair_skips = h;
         self.combine_all_skips();
     }
@@ -650,33 +654,48 @@ pub struct ORobotParryShapeScene<T: AD, C: O3DPoseCategory + 'static, L: OLinalg
     phantom_data: PhantomData<(C, L)>
 }
 impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotParryShapeScene<T, C, L> {
+    /// Links whose only collision geometry is a urdf cylinder or capsule get that parry primitive
+    /// (see `OLink::primitive_collision`); other links get their convex hull.  Links whose collision
+    /// meshes cannot be loaded are left without a shape (and a warning is logged) rather than failing
+    /// the whole scene.
     pub fn new(robot: &ORobot<T, C, L>) -> Self {
         let mut shapes = vec![];
         let mut shape_idx_to_link_idx = vec![];
         let mut id_to_string = AHashMapWrapper::new();
+        let cache = OAssetCache::new_default();
 
         robot.links().iter().for_each(|link| {
             if link.is_present_in_model {
-                if let Some(convex_hull_file_path) = &link.convex_hull_file_path {
+                let shape = if let Some(collision) = link.primitive_collision() {
+                    Self::primitive_collision_shape(collision)
+                } else if let Some(convex_hull_file_path) = &link.convex_hull_file_path {
                     let mut convex_shape_subcomponents_trimesh = vec![];
                     link.convex_decomposition_file_paths.iter().for_each(|x| {
                         convex_shape_subcomponents_trimesh.push(x.clone());
                     });
 
-                    let shape = OParryShape::new_default_convex_shape_from_mesh_paths(convex_hull_file_path.clone(), C::P::identity(), Some(convex_shape_subcomponents_trimesh));
+                    match OParryShape::new_default_convex_shape_from_mesh_paths_cached(convex_hull_file_path.clone(), C::P::identity(), Some(convex_shape_subcomponents_trimesh), &cache) {
+                        Ok(shape) => { shape }
+                        Err(e) => {
+                            tracing::warn!(link = %link.name, error = %e, "could not load the collision meshes of link; it will have no collision shape");
+                            return;
+                        }
+                    }
+                } else {
+                    return;
+                };
 
-                    id_to_string.hashmap.insert(shape.base_shape().base_shape().id(), format!("convex shape for link {} ({})", link.link_idx, link.name));
-                    id_to_string.hashmap.insert(shape.base_shape().obb().id(), format!("obb for link {} ({})", link.link_idx, link.name));
-                    id_to_string.hashmap.insert(shape.base_shape().bounding_sphere().id(), format!("bounding sphere for link {} ({})", link.link_idx, link.name));
-                    shape.convex_subcomponents().iter().enumerate().for_each(|(i, x)| {
-                        id_to_string.hashmap.insert(x.base_shape().id(), format!("convex shape for link {} ({}) subcomponent {}", link.link_idx, link.name, i));
-                        id_to_string.hashmap.insert(x.obb().id(), format!("obb for link {} ({}) subcomponent {}", link.link_idx, link.name, i));
-                        id_to_string.hashmap.insert(x.bounding_sphere().id(), format!("bounding sphere for link {} ({}) subcomponent {}", link.link_idx, link.name, i));
-                    });
+                id_to_string.hashmap.insert(shape.base_shape().base_shape().id(), format!("convex shape for link {} ({})", link.link_idx, link.name));
+                id_to_string.hashmap.insert(shape.base_shape().obb().id(), format!("obb for link {} ({})", link.link_idx, link.name));
+                id_to_string.hashmap.insert(shape.base_shape().bounding_sphere().id(), format!("bounding sphere for link {} ({})", link.link_idx, link.name));
+                shape.convex_subcomponents().iter().enumerate().for_each(|(i, x)| {
+                    id_to_string.hashmap.insert(x.base_shape().id(), format!("convex shape for link {} ({}) subcomponent {}", link.link_idx, link.name, i));
+                    id_to_string.hashmap.insert(x.obb().id(), format!("obb for link {} ({}) subcomponent {}", link.link_idx, link.name, i));
+                    id_to_string.hashmap.insert(x.bounding_sphere().id(), format!("bounding sphere for link {} ({}) subcomponent {}", link.link_idx, link.name, i));
+                });
 
-                    shapes.push(shape);
-                    shape_idx_to_link_idx.push(link.link_idx);
-                }
+                shapes.push(shape);
+                shape_idx_to_link_idx.push(link.link_idx);
             }
         });
 
@@ -689,14 +708,26 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
             phantom_data: Default::default(),
         }
     }
-    pub fn preprocess_non_collision_states_pair_skips<V: OVec<T>>(&mut self, robot: Arc<ORobot<T, C, L>>, non_collision_states: &Vec<V>) {
+    /// Urdf cylinders and capsules run along z while parry's run along y, so the collision origin is
+    /// followed by a quarter turn about x (as with the cylinders of bevy environment objects).
+    fn primitive_collision_shape(collision: &OCollision<T, C>) -> OParryShape<T, C::P<T>> {
+        let z_to_y: C::P<T> = C::P::from_constructors(&[T::zero(); 3], &[T::constant(FRAC_PI_2), T::zero(), T::zero()]);
+        let offset = collision.origin().pose().mul(&z_to_y);
+        match collision.geometry() {
+            OGeometry::Cylinder { radius, length } => { OParryShape::new_default_cylinder(T::constant(0.5 * *length), T::constant(*radius), offset) }
+            OGeometry::Capsule { radius, length } => { OParryShape::new_default_capsule(T::constant(0.5 * *length), T::constant(*radius), offset) }
+            _ => { unreachable!("primitive collisions are cylinders or capsules") }
+        }
+    }
+    pub fn preprocess_non_collision_states_pair_skips<V: OVec<T>>(&mut self, robot: Arc<ORobot<T, C, L>>, non_collision_states: &Vec<V>, progress: &OProgressHandle) {
         self.pair_skips.clear_skip_reason_type(OSkipReason::FromNonCollisionExample);
 
         let shape_reps = vec![ ParryShapeRep::BoundingSphere, ParryShapeRep::OBB, ParryShapeRep::Full ];
         let selectors = vec![OParryPairSelector::HalfPairs, OParryPairSelector::HalfPairsSubcomponents];
 
         let shapes = &self.shapes;
-        for state in non_collision_states {
+        for (i, state) in non_collision_states.iter().enumerate() {
+            if !progress.report("non collision state pair skips", i as f64 / non_collision_states.len() as f64) { tracing::info!("non collision state pair skips cancelled"); return; }
             let input = (robot.clone(), state.ovec_to_other_generic_category::<T, OVecCategoryVec>());
             let poses = self.get_shape_poses(&input);
             let binding = poses.as_ref();
@@ -744,7 +775,9 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
     pub fn clear_close_proximity_states_pair_skips(&mut self) {
         self.pair_skips.clear_skip_reason_type(OSkipReason::CloseProximityWrtAverageExample);
     }
-    pub fn preprocess_always_in_collision_states_pair_skips(&mut self, robot: Arc<ORobot<T, C, L>>, num_same: usize) {
+    /// `seed` makes the sampled states reproducible; with None they are drawn from the global rng.
+    pub fn preprocess_always_in_collision_states_pair_skips(&mut self, robot: Arc<ORobot<T, C, L>>, num_same: usize, progress: &OProgressHandle, seed: Option<u64>) {
+        let mut rng = get_rng(seed);
         self.pair_skips.clear_skip_reason_type(OSkipReason::AlwaysInCollision);
 
         let shape_reps = vec![ ParryShapeRep::BoundingSphere, ParryShapeRep::OBB, ParryShapeRep::Full ];
@@ -752,9 +785,11 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
 
         let shapes = &self.shapes;
 
-        for shape_rep in &shape_reps {
-            for selector in &selectors {
-                let mut progress_bar = get_default_progress_bar(num_same);
+        let num_parts = shape_reps.len() * selectors.len();
+        for (rep_idx, shape_rep) in shape_reps.iter().enumerate() {
+            for (selector_idx, selector) in selectors.iter().enumerate() {
+                let part_progress = progress.sub_range_part(rep_idx * selectors.len() + selector_idx, num_parts);
+                let mut progress_bar = progress.terminal_bar(num_same);
 
                 let mut h = AHashMapWrapper::new();
 
@@ -763,8 +798,9 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
                 'l: loop {
                     progress_bar.message(&format!("shape rep {:?}, selector {:?}: always collision {} of {}", shape_rep, selector, count, num_same));
                     progress_bar.set(count as u64);
+                    if !part_progress.report(&format!("always in collision pair skips ({:?}, {:?})", shape_rep, selector), count as f64 / num_same as f64) { progress_bar.finish(); tracing::info!(?shape_rep, ?selector, "always in collision pair skips cancelled"); return; }
 
-                    let sample = robot.sample_pseudorandom_state();
+                    let sample = robot.sample_pseudorandom_state_with_rng(&mut rng);
                     let binding = (robot.clone(), sample);
                     let poses = self.get_shape_poses(&binding);
                     let poses = poses.as_ref();
@@ -782,7 +818,7 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
                         }
                     });
                     first_loop = false;
-                    if count > num_same { progress_bar.finish(); println!(); break 'l; }
+                    if count > num_same { progress_bar.finish(); break 'l; }
                     else { count += 1; }
                 }
 
@@ -792,7 +828,9 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
             }
         }
     }
-    pub fn preprocess_never_in_collision_states_pair_skips(&mut self, robot: Arc<ORobot<T, C, L>>, num_same: usize) {
+    /// `seed` makes the sampled states reproducible; with None they are drawn from the global rng.
+    pub fn preprocess_never_in_collision_states_pair_skips(&mut self, robot: Arc<ORobot<T, C, L>>, num_same: usize, progress: &OProgressHandle, seed: Option<u64>) {
+        let mut rng = get_rng(seed);
         self.pair_skips.clear_skip_reason_type(OSkipReason::NeverInCollision);
 
         let shape_reps = vec![ ParryShapeRep::BoundingSphere, ParryShapeRep::OBB, ParryShapeRep::Full ];
@@ -800,9 +838,11 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
 
         let shapes = &self.shapes;
 
-        for shape_rep in &shape_reps {
-            for selector in &selectors {
-                let mut progress_bar = get_default_progress_bar(num_same);
+        let num_parts = shape_reps.len() * selectors.len();
+        for (rep_idx, shape_rep) in shape_reps.iter().enumerate() {
+            for (selector_idx, selector) in selectors.iter().enumerate() {
+                let part_progress = progress.sub_range_part(rep_idx * selectors.len() + selector_idx, num_parts);
+                let mut progress_bar = progress.terminal_bar(num_same);
 
                 let mut h = AHashMapWrapper::new();
 
@@ -811,8 +851,9 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
                 'l: loop {
                     progress_bar.message(&format!("shape rep {:?}, selector {:?}: never collision {} of {}", shape_rep, selector, count, num_same));
                     progress_bar.set(count as u64);
+                    if !part_progress.report(&format!("never in collision pair skips ({:?}, {:?})", shape_rep, selector), count as f64 / num_same as f64) { progress_bar.finish(); tracing::info!(?shape_rep, ?selector, "never in collision pair skips cancelled"); return; }
 
-                    let sample = robot.sample_pseudorandom_state();
+                    let sample = robot.sample_pseudorandom_state_with_rng(&mut rng);
                     let binding = (robot.clone(), sample);
                     let poses = self.get_shape_poses(&binding);
                     let poses = poses.as_ref();
@@ -830,7 +871,7 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
                         }
                     });
                     first_loop = false;
-                    if count > num_same { progress_bar.finish(); println!(); break 'l; }
+                    if count > num_same { progress_bar.finish(); break 'l; }
                     else { count += 1; }
                 }
 
@@ -840,7 +881,9 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
             }
         }
     }
-    pub fn preprocess_shape_average_distances(&mut self, robot: Arc<ORobot<T, C, L>>, num_samples: usize) {
+    /// `seed` makes the sampled states reproducible; with None they are drawn from the global rng.
+    pub fn preprocess_shape_average_distances(&mut self, robot: Arc<ORobot<T, C, L>>, num_samples: usize, progress: &OProgressHandle, seed: Option<u64>) {
+        let mut rng = get_rng(seed);
         self.pair_average_distances.hashmap.clear();
 
         let shape_reps = vec![ ParryShapeRep::Full ];
@@ -848,16 +891,19 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
 
         let shapes = &self.shapes;
 
-        for shape_rep in &shape_reps {
-            for selector in &selectors {
-                let mut progress_bar = get_default_progress_bar(num_samples);
+        let num_parts = shape_reps.len() * selectors.len();
+        for (rep_idx, shape_rep) in shape_reps.iter().enumerate() {
+            for (selector_idx, selector) in selectors.iter().enumerate() {
+                let part_progress = progress.sub_range_part(rep_idx * selectors.len() + selector_idx, num_parts);
+                let mut progress_bar = progress.terminal_bar(num_samples);
                 for i in 0..num_samples {
-                    let state = robot.sample_pseudorandom_state();
+                    let state = robot.sample_pseudorandom_state_with_rng(&mut rng);
                     let binding = (robot.clone(), state);
                     let poses = self.get_shape_poses(&binding);
                     let poses = poses.as_ref();
                     progress_bar.message(&format!("shape rep {:?}, selector {:?}: average distance sample {} of {}", shape_rep, selector, i, num_samples));
                     progress_bar.set(i as u64);
+                    if !part_progress.report(&format!("average distances ({:?}, {:?})", shape_rep, selector), i as f64 / num_samples as f64) { progress_bar.finish(); tracing::info!(?shape_rep, ?selector, "average distances cancelled"); return; }
 
                     let res = OParryDistanceGroupQry::query(shapes, shapes, poses, poses, selector, &(), &(), false, &OParryDistanceGroupArgs::new(shape_rep.clone(), shape_rep.clone(), ParryDisMode::ContactDis, false, false, T::constant(f64::MIN), false));
                     res.outputs().iter().for_each(|output| {
@@ -902,7 +948,6 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
                     });
                 }
                 progress_bar.finish();
-                println!();
             }
         }
 
@@ -917,6 +962,11 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ORobotPa
     pub fn get_pair_average_distances(&self) -> &AHashMapWrapper<(u64, u64), T> {
         &self.pair_average_distances
     }
+    /// The link that each shape in `get_shapes` belongs to.
+    #[inline(always)]
+    pub fn get_shape_idx_to_link_idx(&self) -> &Vec<usize> {
+        &self.shape_idx_to_link_idx
+    }
     pub (crate) fn resample_ids(&mut self) {
         let mut h = AHashMapWrapper::new();
 
@@ -996,3 +1046,68 @@ impl<T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> ShapeSce
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use nalgebra::Isometry3;
+    use parry_ad::shape::Ball;
+    use crate::robot::ORobotDefault;
+    use crate::robotics_components::{OInertial, OJoint, OJointLimit, OJointType, OLink};
+    use super::*;
+
+    /// The planar arm of `robot::tests::two_link_arm` whose upper arm collides as a cylinder and whose
+    /// forearm collides as a capsule, both of radius 0.1 and length 0.6 about the middle of the link.
+    fn two_link_arm_with_primitive_collisions() -> ORobotDefault {
+        let collision = |geometry: OGeometry| vec![OCollision::new_manual(None, Isometry3::translation(0.5, 0.0, 0.0), geometry)];
+        let revolute_limit = || OJointLimit::new_manual(vec![10.0], vec![-3.0], vec![3.0], vec![2.0]);
+        let links = vec![
+            OLink::new_manual("base", vec![], vec![], OInertial::new_zeros()),
+            OLink::new_manual("upper_arm", collision(OGeometry::Cylinder { radius: 0.1, length: 0.6 }), vec![], OInertial::new_zeros()),
+            OLink::new_manual("forearm", collision(OGeometry::Capsule { radius: 0.1, length: 0.6 }), vec![], OInertial::new_zeros())
+        ];
+        let joints = vec![
+            OJoint::new_manual("shoulder", OJointType::Revolute, Isometry3::identity(), [0.0, 0.0, 1.0], "base", "upper_arm", revolute_limit(), None, None, None),
+            OJoint::new_manual("elbow", OJointType::Revolute, Isometry3::translation(1.0, 0.0, 0.0), [0.0, 0.0, 1.0], "upper_arm", "forearm", revolute_limit(), None, None, None)
+        ];
+
+        ORobotDefault::from_manual_unchecked("two_link_arm", links, joints)
+    }
+
+    /// Distance from the shape of each link (posed at the zero state) to a world point.
+    fn distances_to_point(robot: &ORobotDefault, point: [f64; 3]) -> Vec<f64> {
+        let scene = ORobotParryShapeScene::new(robot);
+        let poses = robot.get_shape_poses_internal(&vec![0.0, 0.0]);
+        let ball = Ball::new(0.0);
+        scene.shapes.iter().zip(poses.iter()).map(|(shape, pose)| {
+            let shape = shape.base_shape().base_shape();
+            parry_ad::query::distance(&(pose * shape.offset()), &**shape.shape(), &Isometry3::translation(point[0], point[1], point[2]), &ball).expect("error")
+        }).collect()
+    }
+
+    #[test]
+    fn cylinder_and_capsule_collisions_become_primitive_shapes() {
+        let robot = two_link_arm_with_primitive_collisions();
+        let scene = ORobotParryShapeScene::new(&robot);
+
+        assert_eq!(scene.shape_idx_to_link_idx, vec![robot.get_link_idx_from_link_name_unchecked("upper_arm"), robot.get_link_idx_from_link_name_unchecked("forearm")]);
+        assert_eq!(robot.get_shape_poses_internal(&vec![0.0, 0.0]).len(), 2);
+        assert!(scene.shapes[0].base_shape().base_shape().shape().as_cylinder().is_some());
+        assert!(scene.shapes[1].base_shape().base_shape().shape().as_capsule().is_some());
+    }
+
+    #[test]
+    fn primitive_collisions_run_along_the_urdf_z_axis() {
+        let robot = two_link_arm_with_primitive_collisions();
+
+        // above the middle of each link: past the cylinder's flat end and the capsule's rounded end.
+        let d = distances_to_point(&robot, [0.5, 0.0, 0.35]);
+        assert!((d[0] - 0.05).abs() < 1e-6, "{:?}", d);
+        let d = distances_to_point(&robot, [1.5, 0.0, 0.5]);
+        assert!((d[1] - 0.1).abs() < 1e-6, "{:?}", d);
+
+        // beside the middle of each link, where only the radius counts.
+        let d = distances_to_point(&robot, [0.5, 0.35, 0.0]);
+        assert!((d[0] - 0.25).abs() < 1e-6, "{:?}", d);
+        let d = distances_to_point(&robot, [1.5, 0.35, 0.0]);
+        assert!((d[1] - 0.25).abs() < 1e-6, "{:?}", d);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
 use serde::{Serialize, Deserialize};
@@ -82,7 +83,9 @@ pub struct OLink<T: AD, C: O3DPoseCategory, L: OLinalgCategory> {
     pub (crate) convex_decomposition_levels_file_paths: Vec<Vec<OStemCellPath>>
 }
 impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory> OLink<T, C, L> {
-    pub (crate) fn from_link(link: &Link) -> Self {
+    /// `named_materials` are the materials defined at the top level of the urdf (see
+    /// `OMaterial::from_material`).
+    pub (crate) fn from_link(link: &Link, named_materials: &HashMap<String, OMaterial>) -> Self {
         Self {
             is_present_in_model: true,
             link_idx: usize::default(),
@@ -95,7 +98,7 @@ impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory> OLink<T, C, L> {
             children_link_idxs: vec![],
             link_connection_paths: vec![],
             collision: link.collision.iter().map(|x| OCollision::from_collision(x)).collect(),
-            visual: link.visual.iter().map(|x| OVisual::from_visual(x)).collect(),
+            visual: link.visual.iter().map(|x| OVisual::from_visual(x, named_materials)).collect(),
             inertial: OInertial::from_inertial(&link.inertial),
             original_mesh_file_path: None,
             stl_mesh_file_path: None,
@@ -143,12 +146,41 @@ impl<T: AD, C: O3DPoseCategory, L: OLinalgCategory> OLink<T, C, L> {
         self.link_idx_in_sub_robot
     }
     #[inline(always)]
+    pub fn parent_joint_idx(&self) -> &Option<usize> {
+        &self.parent_joint_idx
+    }
+    #[inline(always)]
+    pub fn children_joint_idxs(&self) -> &Vec<usize> {
+        &self.children_joint_idxs
+    }
+    #[inline(always)]
+    pub fn parent_link_idx(&self) -> &Option<usize> {
+        &self.parent_link_idx
+    }
+    #[inline(always)]
+    pub fn children_link_idxs(&self) -> &Vec<usize> {
+        &self.children_link_idxs
+    }
+    #[inline(always)]
     pub fn name(&self) -> &str {
         &self.name
     }
     pub fn collision(&self) -> &Vec<OCollision<T, C>> {
         &self.collision
     }
+    /// The link's collision geometry if it is a single urdf cylinder or capsule, which the parry
+    /// shape scene uses in place of the link's convex hull.
+    pub fn primitive_collision(&self) -> Option<&OCollision<T, C>> {
+        match self.collision.as_slice() {
+            [collision] => {
+                match collision.geometry() {
+                    OGeometry::Cylinder { .. } | OGeometry::Capsule { .. } => { Some(collision) }
+                    _ => { None }
+                }
+            }
+            _ => { None }
+        }
+    }
     pub fn visual(&self) -> &Vec<OVisual<T, C>> {
         &self.visual
     }
@@ -707,6 +739,13 @@ impl<T: AD, C: O3DPoseCategory> OCollision<T, C> {
             origin: OPose::from_pose(&collision.origin)
         }
     }
+    pub fn new_manual(name: Option<&str>, origin: C::P<T>, geometry: OGeometry) -> Self {
+        Self {
+            geometry,
+            name: name.map(|x| x.to_string()),
+            origin: OPose::from_o3d_pose(&origin)
+        }
+    }
     pub fn geometry(&self) -> &OGeometry {
         &self.geometry
     }
@@ -728,7 +767,7 @@ pub struct OVisual<T: AD, C: O3DPoseCategory> {
     geometry: OGeometry
 }
 impl<T: AD, C: O3DPoseCategory> OVisual<T, C> {
-    pub (crate) fn from_visual(visual: &Visual) -> Self {
+    pub (crate) fn from_visual(visual: &Visual, named_materials: &HashMap<String, OMaterial>) -> Self {
         Self {
             name: match &visual.name {
                 None => { None }
@@ -736,12 +775,20 @@ impl<T: AD, C: O3DPoseCategory> OVisual<T, C> {
             },
             material: match &visual.material {
                 None => { None }
-                Some(material) => { Some(OMaterial::from_material(material)) }
+                Some(material) => { Some(OMaterial::from_material(material, named_materials)) }
             },
             origin: OPose::from_pose(&visual.origin),
             geometry: OGeometry::from_geometry(&visual.geometry)
         }
     }
+    pub fn new_manual(name: Option<&str>, material: Option<OMaterial>, origin: C::P<T>, geometry: OGeometry) -> Self {
+        Self {
+            name: name.map(|x| x.to_string()),
+            material,
+            origin: OPose::from_o3d_pose(&origin),
+            geometry
+        }
+    }
     pub fn name(&self) -> &Option<String> {
         &self.name
     }
@@ -764,8 +811,10 @@ pub struct OMaterial {
     color: Option<OColor>
 }
 impl OMaterial {
-    pub (crate) fn from_material(material: &Material) -> Self {
-        Self {
+    /// A visual's material may give only a name, referring to a material defined at the top level of
+    /// the urdf; such materials are resolved through `named_materials`.
+    pub (crate) fn from_material(material: &Material, named_materials: &HashMap<String, OMaterial>) -> Self {
+        let out = Self {
             texture: match &material.texture {
                 None => { None }
                 Some(texture) => { Some(OTexture::from_texture(texture)) }
@@ -775,8 +824,25 @@ impl OMaterial {
                 None => { None }
                 Some(color) => { Some(OColor::from_color(color)) }
             }
+        };
+        match named_materials.get(&out.name) {
+            Some(named_material) if out.color.is_none() && out.texture.is_none() => { named_material.clone() }
+            _ => { out }
         }
     }
+    /// The materials defined at the top level of the urdf, by name.
+    pub (crate) fn named_materials_from_urdf(urdf: &urdf_rs::Robot) -> HashMap<String, OMaterial> {
+        urdf.materials.iter().map(|x| (x.name.clone(), OMaterial::from_material(x, &HashMap::new()))).collect()
+    }
+    pub fn texture(&self) -> &Option<OTexture> {
+        &self.texture
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn color(&self) -> &Option<OColor> {
+        &self.color
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
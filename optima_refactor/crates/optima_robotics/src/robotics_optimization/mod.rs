@@ -2,4 +2,9 @@ pub mod robotics_optimization_ik;
 pub mod robotics_optimization_functions;
 pub mod robotics_optimization_look_at;
 pub mod path_optimization;
-pub mod robotics_collision_state_resolver;
\ No newline at end of file
+pub mod robotics_collision_state_resolver;
+pub mod robotics_optimization_composite;
+pub mod robotics_optimization_trajectory;
+pub mod robotics_optimization_custom;
+pub mod robotics_optimization_ik_batch;
+pub mod robotics_optimization_jacobian;
\ No newline at end of file
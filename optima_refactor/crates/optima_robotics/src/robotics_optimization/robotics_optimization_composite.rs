@@ -0,0 +1,236 @@
+use std::sync::{Arc, RwLock};
+use serde::{Deserialize, Serialize};
+use optima_error::OptimaError;
+use crate::robotics_optimization::robotics_optimization_custom::CustomObjectiveTermHandle;
+
+/// The individual differentiable terms that can be combined in a `CompositeObjective`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CompositeObjectiveTerm {
+    PoseMatching,
+    SelfProximity,
+    JointLimits,
+    Velocity,
+    Acceleration,
+    Jerk,
+    /// A `CustomObjectiveTerm`, identified by its handle's id.
+    Custom(u32)
+}
+impl CompositeObjectiveTerm {
+    /// All built-in terms.
+    pub fn all() -> Vec<CompositeObjectiveTerm> {
+        vec![CompositeObjectiveTerm::PoseMatching, CompositeObjectiveTerm::SelfProximity, CompositeObjectiveTerm::JointLimits, CompositeObjectiveTerm::Velocity, CompositeObjectiveTerm::Acceleration, CompositeObjectiveTerm::Jerk]
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompositeObjectiveTermValue {
+    pub term: CompositeObjectiveTerm,
+    pub weight: f64,
+    pub raw_value: f64,
+    pub weighted_value: f64
+}
+
+/// Weighted combination of differentiable objective terms.  `CompositeObjective` is a cheap handle
+/// to shared state, so a clone kept by the caller can be used to read back the per-term values
+/// recorded during the most recent evaluation.  Weights are read at the start of every evaluation,
+/// so changing them through any clone of the handle (or through the block, e.g.,
+/// `update_objective_weight`) affects the next evaluation of blocks built with it.
+#[derive(Clone, Debug)]
+pub struct CompositeObjective {
+    weights: Arc<RwLock<Vec<(CompositeObjectiveTerm, f64)>>>,
+    custom_terms: Arc<RwLock<Vec<CustomObjectiveTermHandle>>>,
+    term_values: Arc<RwLock<Vec<CompositeObjectiveTermValue>>>
+}
+impl CompositeObjective {
+    pub fn new() -> Self {
+        Self { weights: Arc::new(RwLock::new(vec![])), custom_terms: Arc::new(RwLock::new(vec![])), term_values: Arc::new(RwLock::new(vec![])) }
+    }
+    /// Mirrors the weight arguments previously taken by `get_ik_differentiable_block`.  Returns an
+    /// error if any weight is invalid (see `set_weight`).
+    pub fn new_ik(ee_matching_weight: f64, self_collision_avoidance_weight: f64, min_vel_weight: f64, min_acc_weight: f64, min_jerk_weight: f64) -> Result<Self, OptimaError> {
+        Self::new()
+            .with_term(CompositeObjectiveTerm::PoseMatching, ee_matching_weight)?
+            .with_term(CompositeObjectiveTerm::SelfProximity, self_collision_avoidance_weight)?
+            .with_term(CompositeObjectiveTerm::Velocity, min_vel_weight)?
+            .with_term(CompositeObjectiveTerm::Acceleration, min_acc_weight)?
+            .with_term(CompositeObjectiveTerm::Jerk, min_jerk_weight)
+    }
+    pub fn new_ik_unchecked(ee_matching_weight: f64, self_collision_avoidance_weight: f64, min_vel_weight: f64, min_acc_weight: f64, min_jerk_weight: f64) -> Self {
+        Self::new_ik(ee_matching_weight, self_collision_avoidance_weight, min_vel_weight, min_acc_weight, min_jerk_weight).expect("error")
+    }
+    pub fn with_term(self, term: CompositeObjectiveTerm, weight: f64) -> Result<Self, OptimaError> {
+        self.set_weight(term, weight)?;
+        Ok(self)
+    }
+    pub fn with_custom_term(self, term: CustomObjectiveTermHandle, weight: f64) -> Result<Self, OptimaError> {
+        self.set_custom_term(term, weight)?;
+        Ok(self)
+    }
+    /// Registers the term (or updates its weight if it is already registered).  Its weight can
+    /// later be changed through `set_weight(CompositeObjectiveTerm::Custom(term.id()), ..)`.
+    pub fn set_custom_term(&self, term: CustomObjectiveTermHandle, weight: f64) -> Result<(), OptimaError> {
+        let id = term.id();
+        Self::check_weight(CompositeObjectiveTerm::Custom(id), weight)?;
+        self.insert_custom_term(term, weight);
+        Ok(())
+    }
+    /// Returns an error if `weight` is negative, infinite, or nan; a term is turned off with a
+    /// weight of zero.
+    pub fn set_weight(&self, term: CompositeObjectiveTerm, weight: f64) -> Result<(), OptimaError> {
+        Self::check_weight(term, weight)?;
+        self.insert_weight(term, weight);
+        Ok(())
+    }
+    pub fn check_weight(term: CompositeObjectiveTerm, weight: f64) -> Result<(), OptimaError> {
+        if weight.is_finite() && weight >= 0.0 { Ok(()) } else { Err(OptimaError::InvalidInput(format!("objective weight for {:?} must be finite and non-negative, got {}", term, weight))) }
+    }
+    /// `set_weight` without the check, for callers that already ran `check_weight`.
+    pub (crate) fn insert_weight(&self, term: CompositeObjectiveTerm, weight: f64) {
+        let mut binding = self.weights.write().expect("error");
+        match binding.iter_mut().find(|x| x.0 == term) {
+            None => { binding.push((term, weight)); }
+            Some(x) => { x.1 = weight; }
+        }
+    }
+    /// `set_custom_term` without the check, for callers that already ran `check_weight`.
+    pub (crate) fn insert_custom_term(&self, term: CustomObjectiveTermHandle, weight: f64) {
+        let id = term.id();
+        {
+            let mut binding = self.custom_terms.write().expect("error");
+            if !binding.iter().any(|x| x.id() == id) { binding.push(term); }
+        }
+        self.insert_weight(CompositeObjectiveTerm::Custom(id), weight);
+    }
+    pub fn remove_term(&self, term: CompositeObjectiveTerm) {
+        self.weights.write().expect("error").retain(|x| x.0 != term);
+        if let CompositeObjectiveTerm::Custom(id) = term { self.custom_terms.write().expect("error").retain(|x| x.id() != id); }
+    }
+    #[inline(always)]
+    pub fn weight(&self, term: CompositeObjectiveTerm) -> f64 {
+        let binding = self.weights.read().expect("error");
+        match binding.iter().find(|x| x.0 == term) {
+            None => { 0.0 }
+            Some(x) => { x.1 }
+        }
+    }
+    #[inline(always)]
+    pub fn is_active(&self, term: CompositeObjectiveTerm) -> bool {
+        self.weight(term) > 0.0
+    }
+    pub fn weights(&self) -> Vec<(CompositeObjectiveTerm, f64)> {
+        self.weights.read().expect("error").clone()
+    }
+    pub fn custom_terms(&self) -> Vec<CustomObjectiveTermHandle> {
+        self.custom_terms.read().expect("error").clone()
+    }
+    /// Per-term values from the most recent evaluation of the objective.
+    pub fn term_values(&self) -> Vec<CompositeObjectiveTermValue> {
+        self.term_values.read().expect("error").clone()
+    }
+    pub fn term_value(&self, term: CompositeObjectiveTerm) -> Option<CompositeObjectiveTermValue> {
+        self.term_values.read().expect("error").iter().find(|x| x.term == term).cloned()
+    }
+    /// Copies the current weights and custom terms out of the shared state.  Both locks are only
+    /// held while copying.
+    pub fn weights_snapshot(&self) -> CompositeObjectiveWeights {
+        let mut out = CompositeObjectiveWeights::default();
+        let weights = self.weights();
+        for (term, weight) in weights.iter() {
+            match term {
+                CompositeObjectiveTerm::PoseMatching => { out.pose_matching = *weight; }
+                CompositeObjectiveTerm::SelfProximity => { out.self_proximity = *weight; }
+                CompositeObjectiveTerm::JointLimits => { out.joint_limits = *weight; }
+                CompositeObjectiveTerm::Velocity => { out.velocity = *weight; }
+                CompositeObjectiveTerm::Acceleration => { out.acceleration = *weight; }
+                CompositeObjectiveTerm::Jerk => { out.jerk = *weight; }
+                CompositeObjectiveTerm::Custom(_) => { }
+            }
+        }
+        out.custom_terms = self.custom_terms().into_iter().filter_map(|x| {
+            weights.iter().find(|y| y.0 == CompositeObjectiveTerm::Custom(x.id())).map(|y| (x, y.1))
+        }).collect();
+        out
+    }
+    /// Replaces the values returned by `term_values`.  Evaluations collect their values locally
+    /// and only take the write lock here, so readers are never blocked for a whole evaluation.
+    pub (crate) fn set_term_values(&self, term_values: Vec<CompositeObjectiveTermValue>) {
+        *self.term_values.write().expect("error") = term_values;
+    }
+}
+impl Default for CompositeObjective {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Plain copy of a `CompositeObjective`'s weights, taken at the start of each evaluation so that
+/// the handle's locks are not held while the terms are computed.
+#[derive(Clone, Debug, Default)]
+pub struct CompositeObjectiveWeights {
+    pub pose_matching: f64,
+    pub self_proximity: f64,
+    pub joint_limits: f64,
+    pub velocity: f64,
+    pub acceleration: f64,
+    pub jerk: f64,
+    pub custom_terms: Vec<(CustomObjectiveTermHandle, f64)>
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::robotics_optimization::robotics_optimization_custom::{CustomObjectiveTerm, CustomObjectiveTermHandle};
+    use super::*;
+
+    struct SumTerm;
+    impl CustomObjectiveTerm for SumTerm {
+        fn name(&self) -> String { "sum".to_string() }
+        fn value(&self, inputs: &[f64]) -> f64 { inputs.iter().sum() }
+    }
+
+    #[test]
+    fn invalid_weights_are_rejected() {
+        let objective = CompositeObjective::new_ik(1.0, 0.0, 0.0, 0.0, 0.0).expect("error");
+        for weight in [-1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(objective.set_weight(CompositeObjectiveTerm::PoseMatching, weight), Err(OptimaError::InvalidInput(_))));
+            assert!(objective.set_custom_term(CustomObjectiveTermHandle::new(SumTerm), weight).is_err());
+        }
+        assert_eq!(objective.weight(CompositeObjectiveTerm::PoseMatching), 1.0);
+        assert!(objective.custom_terms().is_empty());
+        assert!(CompositeObjective::new_ik(1.0, -0.1, 0.0, 0.0, 0.0).is_err());
+        assert!(CompositeObjective::new().with_term(CompositeObjectiveTerm::Jerk, 0.0).is_ok());
+    }
+
+    #[test]
+    fn snapshot_sees_weights_set_through_any_clone() {
+        let objective = CompositeObjective::new_ik(1.0, 0.0, 0.0, 0.0, 0.0).expect("error");
+        let handle = objective.clone();
+        handle.set_weight(CompositeObjectiveTerm::Velocity, 0.5).expect("error");
+        let term = CustomObjectiveTermHandle::new(SumTerm);
+        handle.set_custom_term(term.clone(), 2.0).expect("error");
+
+        let snapshot = objective.weights_snapshot();
+        assert_eq!(snapshot.pose_matching, 1.0);
+        assert_eq!(snapshot.velocity, 0.5);
+        assert_eq!(snapshot.custom_terms.len(), 1);
+        assert_eq!(snapshot.custom_terms[0].0.id(), term.id());
+        assert_eq!(snapshot.custom_terms[0].1, 2.0);
+
+        handle.remove_term(CompositeObjectiveTerm::Custom(term.id()));
+        handle.set_weight(CompositeObjectiveTerm::PoseMatching, 0.0).expect("error");
+        let snapshot = objective.weights_snapshot();
+        assert_eq!(snapshot.pose_matching, 0.0);
+        assert!(snapshot.custom_terms.is_empty());
+        assert!(!objective.is_active(CompositeObjectiveTerm::PoseMatching));
+    }
+
+    #[test]
+    fn term_values_are_replaced_on_each_evaluation() {
+        let objective = CompositeObjective::new();
+        let reader = objective.clone();
+        objective.set_term_values(vec![CompositeObjectiveTermValue { term: CompositeObjectiveTerm::PoseMatching, weight: 2.0, raw_value: 0.5, weighted_value: 1.0 }]);
+        assert_eq!(reader.term_value(CompositeObjectiveTerm::PoseMatching).expect("error").weighted_value, 1.0);
+        objective.set_term_values(vec![CompositeObjectiveTermValue { term: CompositeObjectiveTerm::Jerk, weight: 1.0, raw_value: 0.25, weighted_value: 0.25 }]);
+        assert!(reader.term_value(CompositeObjectiveTerm::PoseMatching).is_none());
+        assert_eq!(reader.term_values().len(), 1);
+    }
+}
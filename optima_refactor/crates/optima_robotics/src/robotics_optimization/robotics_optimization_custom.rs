@@ -0,0 +1,84 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use ad_trait::AD;
+
+/// A user-defined cost term that can be added to the ik objective (see
+/// `CompositeObjective::with_custom_term`) or to a trajectory objective (see
+/// `TrajectoryObjective::with_custom_term`) without touching this module.
+///
+/// Terms are evaluated on plain f64 inputs: the robot state for ik, or the stacked waypoint vector
+/// for trajectories.  Derivatives are spliced into whatever AD type the block uses, so only a value
+/// and a gradient are required.
+pub trait CustomObjectiveTerm: Send + Sync {
+    fn name(&self) -> String;
+    fn value(&self, inputs: &[f64]) -> f64;
+    /// Defaults to central finite differences over `value`.  Override when an analytical gradient
+    /// is available, since this is called on every evaluation of the objective.
+    fn value_and_gradient(&self, inputs: &[f64]) -> (f64, Vec<f64>) {
+        (self.value(inputs), finite_difference_gradient(|x| self.value(x), inputs))
+    }
+}
+
+/// Central finite differences.
+pub fn finite_difference_gradient<F: Fn(&[f64]) -> f64>(f: F, inputs: &[f64]) -> Vec<f64> {
+    let mut x = inputs.to_vec();
+    let mut gradient = vec![0.0; inputs.len()];
+    for i in 0..inputs.len() {
+        let xi = x[i];
+        x[i] = xi + CUSTOM_OBJECTIVE_TERM_FD_EPSILON;
+        let plus = f(&x);
+        x[i] = xi - CUSTOM_OBJECTIVE_TERM_FD_EPSILON;
+        let minus = f(&x);
+        x[i] = xi;
+        gradient[i] = (plus - minus) / (2.0 * CUSTOM_OBJECTIVE_TERM_FD_EPSILON);
+    }
+    gradient
+}
+
+const CUSTOM_OBJECTIVE_TERM_FD_EPSILON: f64 = 1e-6;
+
+static NEXT_CUSTOM_OBJECTIVE_TERM_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Shared handle to a registered custom term.  Every handle gets a process-wide unique id, which
+/// is what `CompositeObjectiveTerm::Custom` refers to.
+#[derive(Clone)]
+pub struct CustomObjectiveTermHandle {
+    id: u32,
+    term: Arc<dyn CustomObjectiveTerm>
+}
+impl CustomObjectiveTermHandle {
+    pub fn new<Term: CustomObjectiveTerm + 'static>(term: Term) -> Self {
+        Self::new_from_arc(Arc::new(term))
+    }
+    pub fn new_from_arc(term: Arc<dyn CustomObjectiveTerm>) -> Self {
+        Self { id: NEXT_CUSTOM_OBJECTIVE_TERM_ID.fetch_add(1, Ordering::Relaxed), term }
+    }
+    #[inline(always)]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    #[inline(always)]
+    pub fn term(&self) -> &Arc<dyn CustomObjectiveTerm> {
+        &self.term
+    }
+    /// Evaluates the term on AD inputs.  The output is the first order expansion
+    /// `f(x0) + grad f(x0) . (x - x0)` around the current value of the inputs, which has the same
+    /// value and first derivatives as the term itself.
+    pub fn evaluate<T: AD>(&self, inputs: &[T]) -> T {
+        let x0: Vec<f64> = inputs.iter().map(|x| x.to_constant()).collect();
+        let (value, gradient) = self.term.value_and_gradient(&x0);
+        assert_eq!(gradient.len(), inputs.len(), "custom objective term {} returned a gradient of the wrong length", self.term.name());
+
+        let mut out = T::constant(value);
+        for (i, g) in gradient.iter().enumerate() {
+            if *g != 0.0 { out += T::constant(*g) * (inputs[i] - T::constant(x0[i])); }
+        }
+        out
+    }
+}
+impl Debug for CustomObjectiveTermHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomObjectiveTermHandle").field("id", &self.id).field("name", &self.term.name()).finish()
+    }
+}
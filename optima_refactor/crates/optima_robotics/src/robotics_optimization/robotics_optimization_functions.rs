@@ -53,9 +53,21 @@ pub fn robot_ik_goals_objective<'a, T, C>(fk_res: &FKResult<T, C::P<T>>, ik_goal
     if ik_goals.len() == 0 { return out; }
 
     ik_goals.iter().for_each(|ik_goal| {
-        let pose = fk_res.get_link_pose(ik_goal.goal_link_idx).as_ref().expect("error");
+        let pose = fk_res.get_link_pose_unchecked(ik_goal.goal_link_idx);
         // let interpolated_ik_goal = pose.interpolate_with_max_translation_and_rotation(&ik_goal.goal_pose, max_translation, max_rotation);
-        let dis = pose.dis(&ik_goal.goal_pose);
+        let tolerance = &ik_goal.tolerance;
+        let dis = if tolerance.is_exact() { pose.dis(&ik_goal.goal_pose) } else {
+            let mut dis = T::zero();
+            if tolerance.match_position {
+                let d = pose.translation().dis(ik_goal.goal_pose.translation()) - T::constant(tolerance.position_tolerance);
+                if d > T::zero() { dis += d; }
+            }
+            if tolerance.match_orientation {
+                let d = pose.rotation().dis(ik_goal.goal_pose.rotation()) - T::constant(tolerance.orientation_tolerance);
+                if d > T::zero() { dis += d; }
+            }
+            dis
+        };
         out += ik_goal.weight * dis;
     });
 
@@ -64,11 +76,27 @@ pub fn robot_ik_goals_objective<'a, T, C>(fk_res: &FKResult<T, C::P<T>>, ik_goal
     out
 }
 
+pub fn robot_joint_limits_objective<T, C, L>(robot: &ORobot<T, C, L>, inputs: &[T]) -> T
+    where T: AD,
+          C: O3DPoseCategory + 'static,
+          L: OLinalgCategory + 'static
+{
+    let mut out = T::zero();
+
+    let bounds = robot.get_dof_bounds();
+    inputs.iter().zip(bounds.iter()).for_each(|(x, (lower, upper))| {
+        if *x < *lower { out += (*lower - *x).powi(2); }
+        else if *x > *upper { out += (*x - *upper).powi(2); }
+    });
+
+    out
+}
+
 pub fn robot_link_look_at_objective<'a, T, C>(fk_res: &FKResult<T, C::P<T>>, looker_link: usize, looker_link_forward_axis: &AxisDirection, look_at_target: &LookAtTarget<T, O3DVecCategoryArr>) -> T
     where T: AD,
           C: O3DPoseCategory + 'static
 {
-    let looker_pose = fk_res.get_link_pose(looker_link).as_ref().expect("error");
+    let looker_pose = fk_res.get_link_pose_unchecked(looker_link);
     let looker_location = looker_pose.translation().o3dvec_to_other_generic_category::<T, O3DVecCategoryArr>();
     let axes = looker_pose.rotation().coordinate_frame_vectors();
     let axis = match looker_link_forward_axis {
@@ -86,7 +114,7 @@ pub fn robot_link_look_at_objective<'a, T, C>(fk_res: &FKResult<T, C::P<T>>, loo
     let look_at_target_location = match look_at_target {
         LookAtTarget::Absolute(position) => { position.o3dvec_to_other_generic_category::<T, O3DVecCategoryArr>() }
         LookAtTarget::RobotLink(idx) => {
-            let look_at_target_link = fk_res.get_link_pose(*idx).as_ref().expect("error");
+            let look_at_target_link = fk_res.get_link_pose_unchecked(*idx);
             look_at_target_link.translation().o3dvec_to_other_generic_category::<T, O3DVecCategoryArr>()
         }
         LookAtTarget::PhantomData(_) => { unreachable!() }
@@ -100,7 +128,7 @@ pub fn robot_link_look_at_objective<'a, T, C>(fk_res: &FKResult<T, C::P<T>>, loo
 pub fn robot_link_look_at_roll_prevention_objective<'a, T, C>(fk_res: &FKResult<T, C::P<T>>, looker_link: usize, looker_link_side_axis: &AxisDirection) -> T
     where T: AD,
           C: O3DPoseCategory + 'static {
-    let looker_link_pose = fk_res.get_link_pose(looker_link).as_ref().expect("error");
+    let looker_link_pose = fk_res.get_link_pose_unchecked(looker_link);
     let looker_link_pose_coordinate_frame_vectors = looker_link_pose.rotation().coordinate_frame_vectors();
     let looker_link_side_vector = match looker_link_side_axis {
         AxisDirection::X => { looker_link_pose_coordinate_frame_vectors[0] }
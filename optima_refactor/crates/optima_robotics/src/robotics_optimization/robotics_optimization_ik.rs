@@ -11,11 +11,14 @@ use optima_linalg::{OLinalgCategory, OVec, OVecCategoryVec};
 use optima_proximity::pair_group_queries::{OPairGroupQryTrait, OwnedPairGroupQry, OParryFilterOutputCategory, OParryFilterOutput, OParryPairSelector, OProximityLossFunction, ToParryProximityOutputCategory};
 use optima_proximity::shapes::ShapeCategoryOParryShape;
 use crate::robot::{FKResult, ORobot};
-use crate::robotics_optimization::robotics_optimization_functions::{robot_ik_goals_objective, robot_per_instant_velocity_acceleration_and_jerk_objectives, robot_self_proximity_objective, robot_self_proximity_refilter_check};
+use crate::robotics_optimization::robotics_optimization_functions::{robot_ik_goals_objective, robot_joint_limits_objective, robot_per_instant_velocity_acceleration_and_jerk_objectives, robot_self_proximity_objective, robot_self_proximity_refilter_check};
+use crate::robotics_optimization::robotics_optimization_composite::{CompositeObjective, CompositeObjectiveTerm, CompositeObjectiveTermValue};
+use crate::robotics_optimization::robotics_optimization_custom::CustomObjectiveTermHandle;
 use optima_3d_spatial::optima_3d_pose::SerdeO3DPose;
 use ad_trait::SerdeAD;
 use serde_with::*;
 use optima_file::traits::{FromJsonString, ToJsonString};
+use optima_error::OptimaError;
 use optima_optimization::loss_functions::{GrooveLossGaussianDirection, OptimizationLossFunctionTrait, OptimizationLossGroove};
 
 pub struct DifferentiableFunctionClassIKObjective<C, L, FQ, Q>(PhantomData<(C, L, FQ, Q)>)
@@ -55,37 +58,54 @@ pub struct DifferentiableFunctionIKObjective<'a, T, C, L, FQ, Q>
     linf_dis_cutoff: f64,
     last_proximity_filter_state: Arc<RwLock<Option<Vec<f64>>>>,
     filter_output: Arc<RwLock<Option<OParryFilterOutput>>>,
-    ee_matching_weight: T,
-    collision_avoidance_weight: T,
-    min_vel_weight: T,
-    min_acc_weight: T,
-    min_jerk_weight: T
+    objective: CompositeObjective,
+    records_term_values: bool
 }
 impl<'a, T, C, L, FQ, Q> DifferentiableFunctionIKObjective<'a, T, C, L, FQ, Q> where T: AD,
                                                                                      C: O3DPoseCategory + 'static,
                                                                                      L: OLinalgCategory + 'static,
                                                                                      FQ: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=OParryFilterOutputCategory>,
                                                                                      Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory> {
-    pub fn new(robot: Cow<'a, ORobot<T, C, L>>, ik_goals: Vec<IKGoal<T, C::P<T>>>, init_state: Vec<T>, filter_query: OwnedPairGroupQry<'a, T, FQ>, distance_query: OwnedPairGroupQry<'a, T, Q>, constant_selector: Option<OParryPairSelector>, dis_filter_cutoff: T, linf_dis_cutoff: f64, last_proximity_filter_state: Arc<RwLock<Option<Vec<f64>>>>, filter_output: Arc<RwLock<Option<OParryFilterOutput>>>, ee_matching_weight: T, collision_avoidance_weight: T, min_vel_weight: T, min_acc_weight: T, min_jerk_weight: T) -> Self {
+    pub fn new(robot: Cow<'a, ORobot<T, C, L>>, ik_goals: Vec<IKGoal<T, C::P<T>>>, init_state: Vec<T>, filter_query: OwnedPairGroupQry<'a, T, FQ>, distance_query: OwnedPairGroupQry<'a, T, Q>, constant_selector: Option<OParryPairSelector>, dis_filter_cutoff: T, linf_dis_cutoff: f64, last_proximity_filter_state: Arc<RwLock<Option<Vec<f64>>>>, filter_output: Arc<RwLock<Option<OParryFilterOutput>>>, objective: CompositeObjective) -> Self {
         let prev_states = IKPrevStates::new(init_state.clone());
-        Self { robot, ik_goals: RwLock::new(ik_goals), prev_states: RwLock::new(prev_states), filter_query, distance_query, constant_selector, dis_filter_cutoff, linf_dis_cutoff, last_proximity_filter_state, filter_output, ee_matching_weight, collision_avoidance_weight, min_vel_weight, min_acc_weight, min_jerk_weight }
+        Self { robot, ik_goals: RwLock::new(ik_goals), prev_states: RwLock::new(prev_states), filter_query, distance_query, constant_selector, dis_filter_cutoff, linf_dis_cutoff, last_proximity_filter_state, filter_output, objective, records_term_values: true }
+    }
+    /// Stops this function from writing per-term values to the objective's handle.  Used for the
+    /// derivative copy in a block, which shares the handle with the f64 function.
+    pub fn without_term_value_recording(mut self) -> Self {
+        self.records_term_values = false;
+        self
     }
     pub fn call_and_return_fk_res(&self, inputs: &[T], freeze: bool) -> (Vec<T>, FKResult<T, C::P<T>>) {
         let inputs_as_vec = inputs.to_vec();
         let fk_res = self.robot.forward_kinematics(&inputs_as_vec, None);
 
-        if self.collision_avoidance_weight > T::zero() && self.constant_selector.is_none() {
+        let weights = self.objective.weights_snapshot();
+        let ee_matching_weight = weights.pose_matching;
+        let collision_avoidance_weight = weights.self_proximity;
+        let joint_limits_weight = weights.joint_limits;
+        let min_vel_weight = weights.velocity;
+        let min_acc_weight = weights.acceleration;
+        let min_jerk_weight = weights.jerk;
+
+        if collision_avoidance_weight > 0.0 && self.constant_selector.is_none() {
             robot_self_proximity_refilter_check(&self.robot, &self.filter_query, inputs, &fk_res, &self.last_proximity_filter_state, &self.filter_output, self.linf_dis_cutoff);
         }
 
         let mut out_val = T::zero();
+        let mut term_values = vec![];
+        let mut add_term = |term: CompositeObjectiveTerm, weight: f64, raw_value: T| {
+            let weighted_value = T::constant(weight) * raw_value;
+            if self.records_term_values { term_values.push(CompositeObjectiveTermValue { term, weight, raw_value: raw_value.to_constant(), weighted_value: weighted_value.to_constant() }); }
+            out_val += weighted_value;
+        };
 
-        if self.ee_matching_weight > T::zero() {
+        if ee_matching_weight > 0.0 {
             let loss = OptimizationLossGroove::new(GrooveLossGaussianDirection::BowlUp, T::zero(), T::constant(2.0), T::constant(0.2), T::constant(1.0), T::constant(2.0));
-            out_val += self.ee_matching_weight * loss.loss(robot_ik_goals_objective::<T, C>(&fk_res, &self.ik_goals.read().unwrap()));
+            add_term(CompositeObjectiveTerm::PoseMatching, ee_matching_weight, loss.loss(robot_ik_goals_objective::<T, C>(&fk_res, &self.ik_goals.read().unwrap())));
         }
 
-        if self.collision_avoidance_weight > T::zero() {
+        if collision_avoidance_weight > 0.0 {
             let loss = OptimizationLossGroove::new(GrooveLossGaussianDirection::BowlUp, T::zero(), T::constant(6.0), T::constant(0.4), T::constant(2.0), T::constant(4.0));
             let tmp = match &self.constant_selector {
                 None => {
@@ -97,20 +117,27 @@ impl<'a, T, C, L, FQ, Q> DifferentiableFunctionIKObjective<'a, T, C, L, FQ, Q> w
                     robot_self_proximity_objective(&self.robot, &fk_res, &self.distance_query, selector, self.dis_filter_cutoff, T::constant(15.0), OProximityLossFunction::Hinge, freeze)
                 }
             }.powi(2);
-            // println!("{:?}", tmp);
-            let tmp = self.collision_avoidance_weight * loss.loss(tmp);
-            // println!("...{:?}", tmp);
-            out_val += tmp;
+            add_term(CompositeObjectiveTerm::SelfProximity, collision_avoidance_weight, loss.loss(tmp));
+        }
+
+        if joint_limits_weight > 0.0 {
+            let loss = OptimizationLossGroove::new(GrooveLossGaussianDirection::BowlUp, T::zero(), T::constant(2.0), T::constant(0.2), T::constant(1.0), T::constant(2.0));
+            add_term(CompositeObjectiveTerm::JointLimits, joint_limits_weight, loss.loss(robot_joint_limits_objective(&self.robot, inputs)));
         }
 
-        if self.min_vel_weight + self.min_acc_weight + self.min_jerk_weight > T::zero() {
+        if min_vel_weight + min_acc_weight + min_jerk_weight > 0.0 {
             let loss = OptimizationLossGroove::new(GrooveLossGaussianDirection::BowlUp, T::zero(), T::constant(2.0), T::constant(0.2), T::constant(2.0), T::constant(2.0));
             let (v, a, j) = robot_per_instant_velocity_acceleration_and_jerk_objectives(inputs, &self.prev_states.read().unwrap(), T::constant(12.0));
 
-            out_val += self.min_vel_weight * loss.loss(v);
-            out_val += self.min_acc_weight * loss.loss(a);
-            out_val += self.min_jerk_weight * loss.loss(j);
+            if min_vel_weight > 0.0 { add_term(CompositeObjectiveTerm::Velocity, min_vel_weight, loss.loss(v)); }
+            if min_acc_weight > 0.0 { add_term(CompositeObjectiveTerm::Acceleration, min_acc_weight, loss.loss(a)); }
+            if min_jerk_weight > 0.0 { add_term(CompositeObjectiveTerm::Jerk, min_jerk_weight, loss.loss(j)); }
+        }
+
+        for (custom_term, weight) in weights.custom_terms.iter() {
+            if *weight > 0.0 { add_term(CompositeObjectiveTerm::Custom(custom_term.id()), *weight, custom_term.evaluate(inputs)); }
         }
+        if self.records_term_values { self.objective.set_term_values(term_values); }
 
         (vec![out_val], fk_res)
     }
@@ -123,6 +150,9 @@ impl<'a, T, C, L, FQ, Q> DifferentiableFunctionIKObjective<'a, T, C, L, FQ, Q> w
     pub fn prev_states(&self) -> &RwLock<IKPrevStates<T>> {
         &self.prev_states
     }
+    pub fn objective(&self) -> &CompositeObjective {
+        &self.objective
+    }
     pub fn to_other_ad_type<T1: AD>(&self) -> DifferentiableFunctionIKObjective<'a, T1, C, L, FQ, Q> {
         DifferentiableFunctionIKObjective {
             robot: Cow::Owned(self.robot.to_other_ad_type::<T1>()),
@@ -135,11 +165,8 @@ impl<'a, T, C, L, FQ, Q> DifferentiableFunctionIKObjective<'a, T, C, L, FQ, Q> w
             linf_dis_cutoff: self.linf_dis_cutoff.clone(),
             last_proximity_filter_state: self.last_proximity_filter_state.clone(),
             filter_output: self.filter_output.clone(),
-            ee_matching_weight: self.ee_matching_weight.to_other_ad_type::<T1>(),
-            collision_avoidance_weight: self.collision_avoidance_weight.to_other_ad_type::<T1>(),
-            min_vel_weight: self.min_vel_weight.to_other_ad_type::<T1>(),
-            min_acc_weight: self.min_acc_weight.to_other_ad_type::<T1>(),
-            min_jerk_weight: self.min_jerk_weight.to_other_ad_type::<T1>(),
+            objective: self.objective.clone(),
+            records_term_values: false
         }
     }
 }
@@ -163,6 +190,11 @@ pub type DifferentiableBlockIKObjective<'a, C, L, FQ, Q, E> = DifferentiableBloc
 pub trait DifferentiableBlockIKObjectiveTrait<'a, C: O3DPoseCategory> {
     fn update_ik_pose(&self, idx: usize, pose: C::P<f64>, update_mode: IKGoalUpdateMode);
     fn update_prev_states(&self, state: Vec<f64>);
+    fn update_objective_weight(&self, term: CompositeObjectiveTerm, weight: f64) -> Result<(), OptimaError>;
+    fn update_ik_goal_weight(&self, idx: usize, weight: f64);
+    fn update_ik_goal_tolerance(&self, idx: usize, tolerance: IKGoalTolerance);
+    fn update_custom_objective_term(&self, term: CustomObjectiveTermHandle, weight: f64) -> Result<(), OptimaError>;
+    fn remove_objective_term(&self, term: CompositeObjectiveTerm);
 }
 impl<'a, C, L, FQ, Q, E> DifferentiableBlockIKObjectiveTrait<'a, C> for DifferentiableBlock<'a, DifferentiableFunctionClassIKObjective<C, L, FQ, Q>, E>
     where C: O3DPoseCategory + 'static,
@@ -188,6 +220,48 @@ impl<'a, C, L, FQ, Q, E> DifferentiableBlockIKObjectiveTrait<'a, C> for Differen
             // y.prev_states.update(state.ovec_to_other_ad_type::<E::T>());
         });
     }
+
+    #[inline]
+    fn update_objective_weight(&self, term: CompositeObjectiveTerm, weight: f64) -> Result<(), OptimaError> {
+        CompositeObjective::check_weight(term, weight)?;
+        // both functions share the objective's handle, so it only has to be set once.
+        self.update_function(|x, _| {
+            x.objective.insert_weight(term, weight);
+        });
+        Ok(())
+    }
+
+    #[inline]
+    fn update_ik_goal_weight(&self, idx: usize, weight: f64) {
+        self.update_function(|x, y| {
+            x.ik_goals.write().expect("error")[idx].weight = weight;
+            y.ik_goals.write().expect("error")[idx].weight = E::T::constant(weight);
+        });
+    }
+
+    #[inline]
+    fn update_ik_goal_tolerance(&self, idx: usize, tolerance: IKGoalTolerance) {
+        self.update_function(|x, y| {
+            x.ik_goals.write().expect("error")[idx].tolerance = tolerance;
+            y.ik_goals.write().expect("error")[idx].tolerance = tolerance;
+        });
+    }
+
+    #[inline]
+    fn update_custom_objective_term(&self, term: CustomObjectiveTermHandle, weight: f64) -> Result<(), OptimaError> {
+        CompositeObjective::check_weight(CompositeObjectiveTerm::Custom(term.id()), weight)?;
+        self.update_function(|x, _| {
+            x.objective.insert_custom_term(term.clone(), weight);
+        });
+        Ok(())
+    }
+
+    #[inline]
+    fn remove_objective_term(&self, term: CompositeObjectiveTerm) {
+        self.update_function(|x, _| {
+            x.objective.remove_term(term);
+        });
+    }
 }
 
 #[serde_as]
@@ -198,18 +272,39 @@ pub struct IKGoal<T: AD, P: O3DPose<T>> {
     pub (crate) goal_pose: P,
     #[serde_as(as = "SerdeAD<T>")]
     pub (crate) weight: T,
+    #[serde(default)]
+    pub (crate) tolerance: IKGoalTolerance
 }
 impl<T: AD, P: O3DPose<T>> IKGoal<T, P> {
     pub fn new(goal_link_idx: usize, goal_pose: P, weight: T) -> Self {
-        Self { goal_link_idx, goal_pose, weight }
+        Self::new_with_tolerance(goal_link_idx, goal_pose, weight, IKGoalTolerance::default())
+    }
+    pub fn new_with_tolerance(goal_link_idx: usize, goal_pose: P, weight: T, tolerance: IKGoalTolerance) -> Self {
+        Self { goal_link_idx, goal_pose, weight, tolerance }
+    }
+    #[inline(always)]
+    pub fn goal_link_idx(&self) -> usize {
+        self.goal_link_idx
+    }
+    #[inline(always)]
+    pub fn goal_pose(&self) -> &P {
+        &self.goal_pose
+    }
+    #[inline(always)]
+    pub fn weight(&self) -> T {
+        self.weight
+    }
+    #[inline(always)]
+    pub fn tolerance(&self) -> &IKGoalTolerance {
+        &self.tolerance
     }
     pub fn to_new_ad_type<T1: AD>(&self) -> IKGoal<T1, <P::Category as O3DPoseCategory>::P<T1>> {
         let json_str = self.to_json_string();
-        IKGoal::<T1, <P::Category as O3DPoseCategory>::P<T1>>::from_json_string(&json_str)
+        IKGoal::<T1, <P::Category as O3DPoseCategory>::P<T1>>::from_json_string_unchecked(&json_str)
     }
     pub fn to_new_generic_types<T1: AD, C: O3DPoseCategory>(&self) -> IKGoal<T1, C::P<T1>> {
         let json_str = self.to_json_string();
-        IKGoal::<T1, C::P<T1>>::from_json_string(&json_str)
+        IKGoal::<T1, C::P<T1>>::from_json_string_unchecked(&json_str)
     }
     #[inline]
     pub fn update_goal_pose(&mut self, pose: P, update_mode: IKGoalUpdateMode) {
@@ -227,12 +322,40 @@ impl<T: AD, P: O3DPose<T>> IKGoal<T, P> {
     }
     #[inline]
     pub fn update_goal_pose_interpolated(&mut self, pose: &P, fk_res: &FKResult<T, P>, max_translation: T, max_rotation: T) {
-        let curr_pose = fk_res.get_link_pose(self.goal_link_idx).as_ref().expect("error");
+        let curr_pose = fk_res.get_link_pose_unchecked(self.goal_link_idx);
         let interpolated_pose = curr_pose.interpolate_with_separate_max_translation_and_rotation(pose, max_translation, max_rotation);
         self.update_goal_pose(interpolated_pose, IKGoalUpdateMode::Absolute);
     }
 }
 
+/// Which parts of an ik goal's pose must be matched, and by how much.  Errors (position in meters,
+/// orientation in radians) within the tolerance incur no cost.  The default matches the full pose
+/// exactly.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IKGoalTolerance {
+    pub match_position: bool,
+    pub match_orientation: bool,
+    pub position_tolerance: f64,
+    pub orientation_tolerance: f64
+}
+impl IKGoalTolerance {
+    pub fn new(match_position: bool, match_orientation: bool, position_tolerance: f64, orientation_tolerance: f64) -> Self {
+        Self { match_position, match_orientation, position_tolerance, orientation_tolerance }
+    }
+    pub fn new_position_only(position_tolerance: f64) -> Self {
+        Self::new(true, false, position_tolerance, 0.0)
+    }
+    #[inline(always)]
+    pub fn is_exact(&self) -> bool {
+        self.match_position && self.match_orientation && self.position_tolerance <= 0.0 && self.orientation_tolerance <= 0.0
+    }
+}
+impl Default for IKGoalTolerance {
+    fn default() -> Self {
+        Self::new(true, true, 0.0, 0.0)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum IKGoalUpdateMode {
     LocalRelative, GlobalRelative, Absolute
@@ -0,0 +1,72 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use optima_error::OptimaError;
+use crate::robotics_optimization::robotics_optimization_composite::{CompositeObjective, CompositeObjectiveTerm};
+
+/// Settings for `ORobot::solve_ik_batch`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IKBatchSettings {
+    /// Objective term weights used for every problem.  Only terms that do not need proximity
+    /// queries (i.e., everything but `SelfProximity`) have an effect in batch solves.
+    pub objective_weights: Vec<(CompositeObjectiveTerm, f64)>,
+    /// Convergence tolerance passed to the optimizer.
+    pub optimizer_tolerance: f64,
+    /// A problem counts as solved if every goal's position error (in meters) is within this.
+    pub success_position_tolerance: f64,
+    /// A problem counts as solved if every goal's orientation error (in radians) is within this.
+    pub success_orientation_tolerance: f64,
+    /// Uses all available threads if None.
+    pub num_threads: Option<usize>
+}
+impl IKBatchSettings {
+    pub fn new(objective: &CompositeObjective, optimizer_tolerance: f64, success_position_tolerance: f64, success_orientation_tolerance: f64, num_threads: Option<usize>) -> Self {
+        Self { objective_weights: objective.weights(), optimizer_tolerance, success_position_tolerance, success_orientation_tolerance, num_threads }
+    }
+    /// Returns an error if any of `objective_weights` is invalid (see `CompositeObjective::set_weight`).
+    pub fn to_composite_objective(&self) -> Result<CompositeObjective, OptimaError> {
+        let mut out = CompositeObjective::new();
+        for (term, weight) in &self.objective_weights { out = out.with_term(*term, *weight)?; }
+        Ok(out)
+    }
+}
+impl Default for IKBatchSettings {
+    fn default() -> Self {
+        Self::new(&CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0), 0.001, 0.001, 0.01, None)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IKBatchResult {
+    pub solution: Vec<f64>,
+    pub cost: f64,
+    pub success: bool,
+    /// Largest position error over the problem's goals.
+    pub position_error: f64,
+    /// Largest orientation error over the problem's goals.
+    pub orientation_error: f64,
+    /// Time spent in the optimizer (excludes building the block and checking the solution).
+    pub solve_time: Duration
+}
+
+/// Aggregate statistics over a batch, for benchmarking.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IKBatchSummary {
+    pub num_problems: usize,
+    pub num_successes: usize,
+    pub success_rate: f64,
+    pub mean_solve_time: Duration,
+    pub max_solve_time: Duration,
+    pub mean_cost: f64
+}
+impl IKBatchSummary {
+    pub fn new(results: &[IKBatchResult]) -> Self {
+        let num_problems = results.len();
+        let num_successes = results.iter().filter(|x| x.success).count();
+        let denominator = num_problems.max(1);
+        let total_solve_time: Duration = results.iter().map(|x| x.solve_time).sum();
+        let max_solve_time = results.iter().map(|x| x.solve_time).max().unwrap_or_default();
+        let mean_cost = results.iter().map(|x| x.cost).sum::<f64>() / denominator as f64;
+
+        Self { num_problems, num_successes, success_rate: num_successes as f64 / denominator as f64, mean_solve_time: total_solve_time / denominator as u32, max_solve_time, mean_cost }
+    }
+}
@@ -0,0 +1,67 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use ad_trait::AD;
+use ad_trait::differentiable_block::DifferentiableBlock;
+use ad_trait::differentiable_function::{DifferentiableFunctionClass, DifferentiableFunctionTrait};
+use nalgebra::DMatrix;
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_3d_spatial::optima_3d_rotation::O3DRotation;
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_linalg::OLinalgCategory;
+use crate::robot::ORobot;
+
+pub struct DifferentiableFunctionClassLinkJacobian<C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(PhantomData<(C, L)>);
+impl<C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> DifferentiableFunctionClass for DifferentiableFunctionClassLinkJacobian<C, L> {
+    type FunctionType<'a, T: AD> = DifferentiableFunctionLinkJacobian<'a, T, C, L>;
+}
+
+/// Maps a robot state to six outputs whose derivative with respect to the state is the geometric
+/// jacobian of a link: the link's position, then three outputs that are zero at every state but
+/// whose derivative is the link's angular velocity in the world frame.
+///
+/// The angular outputs are twice the vector part of the link rotation times the inverse of a
+/// constant copy of itself.  That product is the identity, so its derivative is exactly the angular
+/// velocity, but a copy is only constant for AD types; with finite differencing the angular rows
+/// would come out as zero, so use an AD derivative method.
+pub struct DifferentiableFunctionLinkJacobian<'a, T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> {
+    robot: Cow<'a, ORobot<T, C, L>>,
+    link_idx: usize
+}
+impl<'a, T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> DifferentiableFunctionLinkJacobian<'a, T, C, L> {
+    pub fn new(robot: Cow<'a, ORobot<T, C, L>>, link_idx: usize) -> Self {
+        Self { robot, link_idx }
+    }
+    #[inline(always)]
+    pub fn link_idx(&self) -> usize {
+        self.link_idx
+    }
+}
+impl<'a, T: AD, C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static> DifferentiableFunctionTrait<'a, T> for DifferentiableFunctionLinkJacobian<'a, T, C, L> {
+    fn call(&self, inputs: &[T], _freeze: bool) -> Vec<T> {
+        let fk_res = self.robot.forward_kinematics(&inputs.to_vec(), None);
+        let pose = fk_res.get_link_pose_unchecked(self.link_idx);
+
+        let translation = pose.translation().o3dvec_as_slice();
+        let rotation = pose.rotation();
+        let q = rotation.mul(&rotation.o3drot_to_constant_ads().inverse()).unit_quaternion_as_wxyz_slice();
+        let two = T::constant(2.0);
+
+        vec![translation[0], translation[1], translation[2], two * q[1], two * q[2], two * q[3]]
+    }
+
+    fn num_inputs(&self) -> usize {
+        self.robot.num_dofs()
+    }
+
+    fn num_outputs(&self) -> usize { 6 }
+}
+
+pub type DifferentiableBlockLinkJacobian<'a, C, L, E> = DifferentiableBlock<'a, DifferentiableFunctionClassLinkJacobian<C, L>, E>;
+
+/// Yoshikawa manipulability of a jacobian, sqrt(det(J J^T)).  With fewer dofs than task
+/// dimensions, J J^T is always singular, so sqrt(det(J^T J)) is used instead, which measures how
+/// well the dofs move the link within the directions they can reach.
+pub fn manipulability_from_jacobian(jacobian: &DMatrix<f64>) -> f64 {
+    let m = if jacobian.ncols() < jacobian.nrows() { jacobian.transpose() * jacobian } else { jacobian * jacobian.transpose() };
+    m.determinant().max(0.0).sqrt()
+}
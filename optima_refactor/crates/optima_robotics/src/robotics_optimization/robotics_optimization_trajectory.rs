@@ -0,0 +1,231 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use ad_trait::AD;
+use ad_trait::differentiable_block::DifferentiableBlock;
+use ad_trait::differentiable_function::{DifferentiableFunctionClass, DifferentiableFunctionTrait};
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_linalg::{OLinalgCategory, OVec};
+use optima_optimization::loss_functions::{GrooveLossGaussianDirection, OptimizationLossFunctionTrait, OptimizationLossGroove};
+use optima_proximity::pair_group_queries::{OPairGroupQryTrait, OwnedPairGroupQry, OParryPairSelector, OProximityLossFunction, ToParryProximityOutputCategory};
+use optima_proximity::shapes::ShapeCategoryOParryShape;
+use crate::robot::{FKResult, ORobot};
+use crate::robotics_optimization::robotics_optimization_custom::CustomObjectiveTermHandle;
+use crate::robotics_optimization::robotics_optimization_functions::{min_acceleration_over_path_objective, min_jerk_over_path_objective, min_velocity_over_path_objective, robot_self_proximity_objective};
+
+pub struct DifferentiableFunctionClassTrajectoryObjective<C, L, Q>(PhantomData<(C, L, Q)>)
+    where C: O3DPoseCategory + 'static,
+          L: OLinalgCategory + 'static,
+          Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory>;
+impl<C, L, Q> DifferentiableFunctionClass for DifferentiableFunctionClassTrajectoryObjective<C, L, Q>
+    where C: O3DPoseCategory + 'static,
+          L: OLinalgCategory + 'static,
+          Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory> {
+    type FunctionType<'a, T: AD> = DifferentiableFunctionTrajectoryObjective<'a, T, C, L, Q>;
+}
+
+/// Whole-trajectory objective over a stacked waypoint vector, i.e., the inputs are
+/// `[q_0, q_1, ..., q_{n-1}]` concatenated, where each `q_i` is a full robot state.
+pub struct DifferentiableFunctionTrajectoryObjective<'a, T, C, L, Q>
+    where T: AD,
+          C: O3DPoseCategory + 'static,
+          L: OLinalgCategory + 'static,
+          Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory> {
+    robot: Cow<'a, ORobot<T, C, L>>,
+    num_waypoints: usize,
+    terms: Vec<TrajectoryObjectiveTerm<T, C::P<T>>>,
+    distance_query: OwnedPairGroupQry<'a, T, Q>,
+    selector: OParryPairSelector
+}
+impl<'a, T, C, L, Q> DifferentiableFunctionTrajectoryObjective<'a, T, C, L, Q> where T: AD,
+                                                                                    C: O3DPoseCategory + 'static,
+                                                                                    L: OLinalgCategory + 'static,
+                                                                                    Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory> {
+    pub fn new(robot: Cow<'a, ORobot<T, C, L>>, num_waypoints: usize, terms: Vec<TrajectoryObjectiveTerm<T, C::P<T>>>, distance_query: OwnedPairGroupQry<'a, T, Q>, selector: OParryPairSelector) -> Self {
+        assert!(num_waypoints >= 2);
+        Self { robot, num_waypoints, terms, distance_query, selector }
+    }
+    #[inline(always)]
+    pub fn num_waypoints(&self) -> usize {
+        self.num_waypoints
+    }
+    #[inline(always)]
+    pub fn terms(&self) -> &Vec<TrajectoryObjectiveTerm<T, C::P<T>>> {
+        &self.terms
+    }
+    pub fn to_other_ad_type<T1: AD>(&self) -> DifferentiableFunctionTrajectoryObjective<'a, T1, C, L, Q> {
+        DifferentiableFunctionTrajectoryObjective {
+            robot: Cow::Owned(self.robot.to_other_ad_type::<T1>()),
+            num_waypoints: self.num_waypoints,
+            terms: self.terms.iter().map(|x| x.to_other_generic_types::<T1, C>()).collect(),
+            distance_query: self.distance_query.to_other_ad_type::<T1>(),
+            selector: self.selector.clone(),
+        }
+    }
+}
+impl<'a, T, C, L, Q> DifferentiableFunctionTrait<'a, T> for DifferentiableFunctionTrajectoryObjective<'a, T, C, L, Q> where T: AD,
+                                                                                                                            C: O3DPoseCategory + 'static,
+                                                                                                                            L: OLinalgCategory + 'static,
+                                                                                                                            Q: OPairGroupQryTrait<ShapeCategory=ShapeCategoryOParryShape, SelectorType=OParryPairSelector, OutputCategory=ToParryProximityOutputCategory> {
+    fn call(&self, inputs: &[T], freeze: bool) -> Vec<T> {
+        let waypoints = inputs.to_vec().ovec_split_into_sub_vecs_owned(self.robot.num_dofs());
+        assert_eq!(waypoints.len(), self.num_waypoints);
+
+        let mut fk_results: Vec<Option<FKResult<T, C::P<T>>>> = vec![None; self.num_waypoints];
+        let mut get_fk_res = |idx: usize| -> FKResult<T, C::P<T>> {
+            if fk_results[idx].is_none() { fk_results[idx] = Some(self.robot.forward_kinematics(&waypoints[idx], None)); }
+            fk_results[idx].as_ref().unwrap().clone()
+        };
+
+        let mut out = T::zero();
+
+        for term in &self.terms {
+            match term {
+                TrajectoryObjectiveTerm::Smoothness { order, weight } => {
+                    let loss = OptimizationLossGroove::new(GrooveLossGaussianDirection::BowlUp, T::zero(), T::constant(2.0), T::constant(0.2), T::constant(2.0), T::constant(2.0));
+                    let value = match order {
+                        TrajectorySmoothnessOrder::Velocity => { min_velocity_over_path_objective(&waypoints, T::constant(10.0)) }
+                        TrajectorySmoothnessOrder::Acceleration => {
+                            if self.num_waypoints < 3 { continue; }
+                            min_acceleration_over_path_objective(&waypoints, T::constant(10.0))
+                        }
+                        TrajectorySmoothnessOrder::Jerk => {
+                            if self.num_waypoints < 4 { continue; }
+                            min_jerk_over_path_objective(&waypoints, T::constant(10.0))
+                        }
+                    };
+                    out += *weight * loss.loss(value);
+                }
+                TrajectoryObjectiveTerm::EndpointPoseGoal { endpoint, link_idx, pose, weight } => {
+                    let loss = OptimizationLossGroove::new(GrooveLossGaussianDirection::BowlUp, T::zero(), T::constant(2.0), T::constant(0.2), T::constant(1.0), T::constant(2.0));
+                    let waypoint_idx = match endpoint {
+                        TrajectoryEndpoint::Start => { 0 }
+                        TrajectoryEndpoint::End => { self.num_waypoints - 1 }
+                    };
+                    let fk_res = get_fk_res(waypoint_idx);
+                    let link_pose = fk_res.get_link_pose_unchecked(*link_idx);
+                    out += *weight * loss.loss(link_pose.dis(pose));
+                }
+                TrajectoryObjectiveTerm::WaypointSelfProximity { cutoff, weight } => {
+                    let loss = OptimizationLossGroove::new(GrooveLossGaussianDirection::BowlUp, T::zero(), T::constant(6.0), T::constant(0.4), T::constant(2.0), T::constant(4.0));
+                    let mut value = T::zero();
+                    for waypoint_idx in 0..self.num_waypoints {
+                        let fk_res = get_fk_res(waypoint_idx);
+                        value += robot_self_proximity_objective(&self.robot, &fk_res, &self.distance_query, &self.selector, *cutoff, T::constant(15.0), OProximityLossFunction::Hinge, freeze).powi(2);
+                    }
+                    value /= T::constant(self.num_waypoints as f64);
+                    out += *weight * loss.loss(value);
+                }
+                TrajectoryObjectiveTerm::Custom { term, weight } => {
+                    out += *weight * term.evaluate(inputs);
+                }
+            }
+        }
+
+        vec![out]
+    }
+
+    fn num_inputs(&self) -> usize {
+        self.num_waypoints * self.robot.num_dofs()
+    }
+
+    fn num_outputs(&self) -> usize { 1 }
+}
+
+pub type DifferentiableBlockTrajectoryObjective<'a, C, L, Q, E> = DifferentiableBlock<'a, DifferentiableFunctionClassTrajectoryObjective<C, L, Q>, E>;
+
+#[derive(Clone, Debug)]
+pub enum TrajectorySmoothnessOrder {
+    Velocity, Acceleration, Jerk
+}
+
+#[derive(Clone, Debug)]
+pub enum TrajectoryEndpoint {
+    Start, End
+}
+
+#[derive(Clone, Debug)]
+pub enum TrajectoryObjectiveTerm<T: AD, P: O3DPose<T>> {
+    Smoothness { order: TrajectorySmoothnessOrder, weight: T },
+    EndpointPoseGoal { endpoint: TrajectoryEndpoint, link_idx: usize, pose: P, weight: T },
+    WaypointSelfProximity { cutoff: T, weight: T },
+    /// Evaluated on the full stacked waypoint vector.
+    Custom { term: CustomObjectiveTermHandle, weight: T }
+}
+impl<T: AD, P: O3DPose<T>> TrajectoryObjectiveTerm<T, P> {
+    pub fn to_other_generic_types<T1: AD, C1: O3DPoseCategory>(&self) -> TrajectoryObjectiveTerm<T1, C1::P<T1>> {
+        match self {
+            TrajectoryObjectiveTerm::Smoothness { order, weight } => {
+                TrajectoryObjectiveTerm::Smoothness { order: order.clone(), weight: weight.to_other_ad_type::<T1>() }
+            }
+            TrajectoryObjectiveTerm::EndpointPoseGoal { endpoint, link_idx, pose, weight } => {
+                TrajectoryObjectiveTerm::EndpointPoseGoal { endpoint: endpoint.clone(), link_idx: *link_idx, pose: pose.o3dpose_to_other_generic_category::<T1, C1>(), weight: weight.to_other_ad_type::<T1>() }
+            }
+            TrajectoryObjectiveTerm::WaypointSelfProximity { cutoff, weight } => {
+                TrajectoryObjectiveTerm::WaypointSelfProximity { cutoff: cutoff.to_other_ad_type::<T1>(), weight: weight.to_other_ad_type::<T1>() }
+            }
+            TrajectoryObjectiveTerm::Custom { term, weight } => {
+                TrajectoryObjectiveTerm::Custom { term: term.clone(), weight: weight.to_other_ad_type::<T1>() }
+            }
+        }
+    }
+}
+
+/// Builder for a trajectory objective.  Terms are added one at a time and the whole objective is
+/// turned into a differentiable block via `ORobot::get_trajectory_differentiable_block`.
+#[derive(Clone, Debug)]
+pub struct TrajectoryObjective<C: O3DPoseCategory + 'static> {
+    num_waypoints: usize,
+    terms: Vec<TrajectoryObjectiveTerm<f64, C::P<f64>>>
+}
+impl<C: O3DPoseCategory + 'static> TrajectoryObjective<C> {
+    pub fn new(num_waypoints: usize) -> Self {
+        assert!(num_waypoints >= 2);
+        Self { num_waypoints, terms: vec![] }
+    }
+    pub fn with_smoothness(mut self, order: TrajectorySmoothnessOrder, weight: f64) -> Self {
+        self.terms.push(TrajectoryObjectiveTerm::Smoothness { order, weight });
+        self
+    }
+    pub fn with_endpoint_pose_goal(mut self, endpoint: TrajectoryEndpoint, link_idx: usize, pose: C::P<f64>, weight: f64) -> Self {
+        self.terms.push(TrajectoryObjectiveTerm::EndpointPoseGoal { endpoint, link_idx, pose, weight });
+        self
+    }
+    pub fn with_waypoint_self_proximity(mut self, cutoff: f64, weight: f64) -> Self {
+        self.terms.push(TrajectoryObjectiveTerm::WaypointSelfProximity { cutoff, weight });
+        self
+    }
+    pub fn with_custom_term(mut self, term: CustomObjectiveTermHandle, weight: f64) -> Self {
+        self.terms.push(TrajectoryObjectiveTerm::Custom { term, weight });
+        self
+    }
+    #[inline(always)]
+    pub fn num_waypoints(&self) -> usize {
+        self.num_waypoints
+    }
+    #[inline(always)]
+    pub fn terms(&self) -> &Vec<TrajectoryObjectiveTerm<f64, C::P<f64>>> {
+        &self.terms
+    }
+    /// Linearly interpolates between the two given states to produce a stacked initial condition.
+    pub fn initial_condition_from_endpoints(&self, start_state: &[f64], end_state: &[f64]) -> Vec<f64> {
+        assert_eq!(start_state.len(), end_state.len());
+        let mut waypoints = vec![];
+        for i in 0..self.num_waypoints {
+            let t = i as f64 / (self.num_waypoints - 1) as f64;
+            let waypoint = start_state.to_vec().ovec_scalar_mul(&(1.0 - t)).ovec_add(&end_state.to_vec().ovec_scalar_mul(&t));
+            waypoints.push(waypoint);
+        }
+        stack_waypoints(&waypoints)
+    }
+}
+
+pub fn stack_waypoints<T: AD>(waypoints: &Vec<Vec<T>>) -> Vec<T> {
+    let mut out = vec![];
+    waypoints.iter().for_each(|x| out.extend(x.iter()));
+    out
+}
+
+pub fn unstack_waypoints<T: AD>(stacked: &[T], num_dofs: usize) -> Vec<Vec<T>> {
+    assert_eq!(stacked.len() % num_dofs, 0);
+    stacked.to_vec().ovec_split_into_sub_vecs_owned(num_dofs)
+}
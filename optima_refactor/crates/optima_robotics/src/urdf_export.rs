@@ -0,0 +1,344 @@
+use std::collections::HashSet;
+use ad_trait::AD;
+use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategory};
+use optima_3d_spatial::optima_3d_rotation::O3DRotation;
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_error::OptimaError;
+use optima_file::path::{OPath, OStemCellPath};
+use optima_linalg::OLinalgCategory;
+use crate::robot::ORobot;
+use crate::robotics_components::{OGeometry, OJointType};
+
+pub const URDF_WORLD_LINK: &str = "world";
+
+/// A single urdf with a `world` root link, built from robots at given states and static obstacles,
+/// so a scene composed in the viewer (or in code) can be loaded by other simulators.  Robot root
+/// links and obstacles are attached to `world` with fixed joints.
+///
+/// Meshes found locally (original visual meshes, and the computed convex hulls for collision) are
+/// referenced by paths relative to the urdf, under `meshes/`, and copied there by `save`; so the
+/// urdf and its meshes can be moved together and do not depend on ROS package paths.  Other meshes
+/// keep their filenames from the original urdf.  `ORobot` does not keep link masses, so links are
+/// written without `<inertial>` tags, and capsules (not part of the urdf spec) are written as
+/// cylinders of the same total length.
+pub struct OUrdfSnapshot {
+    name: String,
+    links: Vec<String>,
+    joints: Vec<String>,
+    used_names: HashSet<String>,
+    mesh_files: Vec<(OStemCellPath, String)>
+}
+impl OUrdfSnapshot {
+    pub const MESH_DIRECTORY: &'static str = "meshes";
+
+    pub fn new(name: &str) -> Self {
+        let mut used_names = HashSet::new();
+        used_names.insert(URDF_WORLD_LINK.to_string());
+        Self { name: name.to_string(), links: vec![format!("  <link name=\"{}\"/>", URDF_WORLD_LINK)], joints: vec![], used_names, mesh_files: vec![] }
+    }
+    /// `prefix` is put in front of every link and joint name, e.g., to tell several instances of
+    /// the same robot apart.  With `freeze_joints`, every joint is written as a fixed joint at its
+    /// value in `state`.  Otherwise joints keep their types and limits, and since urdf has no way to
+    /// store joint values, the robot loads at its zero state: `state` only shows in the joints that
+    /// are fixed anyway.  Spherical joints (which urdf does not have) and joints fixed with
+    /// `ORobot::set_joint_as_fixed` are always written as fixed at their values.  A mimic joint only
+    /// keeps its `<mimic>` tag if the joint it follows is not fixed.
+    pub fn add_robot<C: O3DPoseCategory + 'static, L: OLinalgCategory + 'static>(&mut self, robot: &ORobot<f64, C, L>, state: &[f64], base_pose: Option<&C::P<f64>>, prefix: &str, freeze_joints: bool) {
+        let state = state.to_vec();
+        let fk_res = robot.forward_kinematics(&state, base_pose);
+
+        let link_names: Vec<Option<String>> = robot.links().iter().map(|link| {
+            if !link.is_present_in_model() { return None; }
+            Some(self.unique_name(&format!("{}{}", prefix, link.name())))
+        }).collect();
+
+        for (link, link_name) in robot.links().iter().zip(link_names.iter()) {
+            let Some(link_name) = link_name else { continue; };
+            let mut tag = format!("  <link name=\"{}\">\n", xml_escape(link_name));
+
+            // the local meshes stand for a link's whole visual (or collision), so they only replace
+            // mesh filenames of links with a single visual (or collision).
+            let visual_mesh = if link.visual().len() == 1 { link.original_mesh_file_path().as_ref().and_then(|x| self.add_mesh_file(x, &format!("{}_visual", link_name))) } else { None };
+            for (i, visual) in link.visual().iter().enumerate() {
+                tag += "    <visual>\n";
+                tag += &format!("      {}\n", origin_tag(visual.origin().pose()));
+                tag += &format!("      <geometry>{}</geometry>\n", geometry_tag(visual.geometry(), visual_mesh.as_deref()));
+                let color = visual.material().as_ref().and_then(|x| x.color().as_ref()).map(|x| *x.rgba());
+                if let Some(c) = color {
+                    tag += &format!("      <material name=\"{}_material_{}\"><color rgba=\"{} {} {} {}\"/></material>\n", xml_escape(link_name), i, c[0], c[1], c[2], c[3]);
+                }
+                tag += "    </visual>\n";
+            }
+
+            let collision_mesh = if link.collision().len() == 1 { link.convex_hull_file_path().as_ref().and_then(|x| self.add_mesh_file(x, &format!("{}_collision", link_name))) } else { None };
+            for collision in link.collision() {
+                tag += "    <collision>\n";
+                tag += &format!("      {}\n", origin_tag(collision.origin().pose()));
+                tag += &format!("      <geometry>{}</geometry>\n", geometry_tag(collision.geometry(), collision_mesh.as_deref()));
+                tag += "    </collision>\n";
+            }
+            tag += "  </link>";
+            self.links.push(tag);
+
+            let has_parent_in_model = link.parent_joint_idx().map(|x| robot.joints()[x].is_present_in_model() && link_names[robot.joints()[x].parent_link_idx()].is_some()).unwrap_or(false);
+            if !has_parent_in_model {
+                if let Ok(pose) = fk_res.get_link_pose(link.link_idx()) {
+                    let joint_name = self.unique_name(&format!("{}world_to_{}", prefix, link.name()));
+                    self.joints.push(fixed_joint_tag(&joint_name, URDF_WORLD_LINK, link_name, pose));
+                }
+            }
+        }
+
+        // names and types (None for fixed) are settled before any joint is written, so mimic tags
+        // can refer to the name their joint was actually given.
+        let written_joints: Vec<Option<(String, Option<&str>)>> = robot.joints().iter().map(|joint| {
+            if !joint.is_present_in_model() { return None; }
+            link_names[joint.parent_link_idx()].as_ref()?;
+            link_names[joint.child_link_idx()].as_ref()?;
+            let joint_name = self.unique_name(&format!("{}{}", prefix, joint.name()));
+
+            let joint_type = match joint.joint_type() {
+                OJointType::Revolute => { Some("revolute") }
+                OJointType::Continuous => { Some("continuous") }
+                OJointType::Prismatic => { Some("prismatic") }
+                OJointType::Floating => { Some("floating") }
+                OJointType::Planar => { Some("planar") }
+                OJointType::Fixed | OJointType::Spherical => { None }
+            };
+            let joint_type = if freeze_joints || joint.fixed_values().is_some() { None } else { joint_type };
+            Some((joint_name, joint_type))
+        }).collect();
+
+        for (joint, written_joint) in robot.joints().iter().zip(written_joints.iter()) {
+            let Some((joint_name, joint_type)) = written_joint else { continue; };
+            let (Some(parent), Some(child)) = (&link_names[joint.parent_link_idx()], &link_names[joint.child_link_idx()]) else { continue; };
+
+            match joint_type {
+                None => {
+                    let pose = robot.get_joint_fixed_offset_transform(joint.joint_idx()).mul(&robot.get_joint_variable_transform(&state, joint.joint_idx()));
+                    self.joints.push(fixed_joint_tag(&joint_name, parent, child, &pose));
+                }
+                Some(joint_type) => {
+                    let mut tag = format!("  <joint name=\"{}\" type=\"{}\">\n", xml_escape(&joint_name), joint_type);
+                    tag += &format!("    <parent link=\"{}\"/>\n    <child link=\"{}\"/>\n", xml_escape(parent), xml_escape(child));
+                    tag += &format!("    {}\n", origin_tag(joint.origin().pose()));
+                    let axis = joint.axis();
+                    tag += &format!("    <axis xyz=\"{} {} {}\"/>\n", axis[0], axis[1], axis[2]);
+                    let limit = joint.limit();
+                    let first = |x: &Vec<f64>| x.get(0).cloned().unwrap_or(0.0);
+                    tag += &format!("    <limit lower=\"{}\" upper=\"{}\" effort=\"{}\" velocity=\"{}\"/>\n", first(limit.lower()), first(limit.upper()), first(limit.effort()), first(limit.velocity()));
+                    let mimic = joint.mimic().as_ref().and_then(|mimic| match written_joints.get(mimic.joint_idx()) {
+                        Some(Some((mimic_joint, Some(_)))) => { Some((mimic_joint, mimic)) }
+                        _ => { None }
+                    });
+                    if let Some((mimic_joint, mimic)) = mimic {
+                        tag += &format!("    <mimic joint=\"{}\" multiplier=\"{}\" offset=\"{}\"/>\n", xml_escape(mimic_joint), mimic.multiplier().unwrap_or(1.0), mimic.offset().unwrap_or(0.0));
+                    }
+                    tag += "  </joint>";
+                    self.joints.push(tag);
+                }
+            }
+        }
+    }
+    /// A static obstacle, fixed to `world` at `pose`.  The geometry is used for both its visual and
+    /// its collision.
+    pub fn add_obstacle<T: AD, P: O3DPose<T>>(&mut self, name: &str, geometry: &OGeometry, pose: &P, color: Option<[f64; 4]>) {
+        self.add_obstacle_with_mesh_file(name, geometry, None, pose, color);
+    }
+    /// A static obstacle whose geometry is the mesh in `mesh_file`; the mesh is copied next to the
+    /// urdf by `save` like the robot meshes.  Returns an error if `mesh_file` is not found locally.
+    pub fn add_mesh_obstacle<T: AD, P: O3DPose<T>>(&mut self, name: &str, mesh_file: &OStemCellPath, pose: &P, color: Option<[f64; 4]>) -> Result<(), OptimaError> {
+        mesh_file.as_physical_path()?;
+        let geometry = OGeometry::Mesh { filename: mesh_file.to_string(), scale: None };
+        self.add_obstacle_with_mesh_file(name, &geometry, Some(mesh_file), pose, color);
+        Ok(())
+    }
+    fn add_obstacle_with_mesh_file<T: AD, P: O3DPose<T>>(&mut self, name: &str, geometry: &OGeometry, mesh_file: Option<&OStemCellPath>, pose: &P, color: Option<[f64; 4]>) {
+        let link_name = self.unique_name(name);
+        let mesh_uri = mesh_file.and_then(|x| self.add_mesh_file(x, &format!("{}_visual", link_name)));
+        let geometry_tag = geometry_tag(geometry, mesh_uri.as_deref());
+        let mut tag = format!("  <link name=\"{}\">\n", xml_escape(&link_name));
+        tag += &format!("    <visual>\n      <geometry>{}</geometry>\n", geometry_tag);
+        if let Some(c) = color {
+            tag += &format!("      <material name=\"{}_material\"><color rgba=\"{} {} {} {}\"/></material>\n", xml_escape(&link_name), c[0], c[1], c[2], c[3]);
+        }
+        tag += "    </visual>\n";
+        tag += &format!("    <collision>\n      <geometry>{}</geometry>\n    </collision>\n", geometry_tag);
+        tag += "  </link>";
+        self.links.push(tag);
+
+        let joint_name = self.unique_name(&format!("world_to_{}", name));
+        self.joints.push(fixed_joint_tag(&joint_name, URDF_WORLD_LINK, &link_name, pose));
+    }
+    pub fn to_urdf_string(&self) -> String {
+        let mut out = format!("<?xml version=\"1.0\"?>\n<robot name=\"{}\">\n", xml_escape(&self.name));
+        self.links.iter().chain(self.joints.iter()).for_each(|x| { out += x; out += "\n"; });
+        out += "</robot>\n";
+        out
+    }
+    /// The local meshes the urdf refers to, each with its path relative to the urdf.
+    #[inline(always)]
+    pub fn mesh_files(&self) -> &Vec<(OStemCellPath, String)> {
+        &self.mesh_files
+    }
+    /// Writes the urdf and copies its meshes (see `mesh_files`) next to it.
+    pub fn save(&self, path: &OPath) -> Result<(), OptimaError> {
+        path.write_string_to_file(&self.to_urdf_string()).map_err(|e| OptimaError::new_file_io(path.to_string(), e))?;
+        if self.mesh_files.is_empty() { return Ok(()); }
+
+        let directory = match path {
+            OPath::Path(p) => { p.parent().map(|x| x.to_path_buf()).unwrap_or_default() }
+            OPath::VfsPath(_) => { return Err(OptimaError::new_file_io(path.to_string(), "meshes can only be copied next to a physical path")); }
+        };
+        for (mesh_file, relative_path) in &self.mesh_files {
+            mesh_file.copy_file_to_destination(&OPath::Path(directory.join(relative_path)))?;
+        }
+        Ok(())
+    }
+    /// Records a local mesh to be copied by `save`, and returns the path the urdf refers to it by.
+    /// `name` must be unique in the urdf (e.g., derived from a link name).
+    fn add_mesh_file(&mut self, path: &OStemCellPath, name: &str) -> Option<String> {
+        path.as_physical_path().ok()?;
+        let extension = path.extension().map(|x| format!(".{}", x)).unwrap_or_default();
+        let relative_path = format!("{}/{}{}", Self::MESH_DIRECTORY, name, extension);
+        self.mesh_files.push((path.clone(), relative_path.clone()));
+        Some(relative_path)
+    }
+    /// Urdf names have to be unique across the file, so repeated names get a numbered suffix.
+    fn unique_name(&mut self, name: &str) -> String {
+        let mut out = name.to_string();
+        let mut i = 1;
+        while self.used_names.contains(&out) {
+            out = format!("{}_{}", name, i);
+            i += 1;
+        }
+        self.used_names.insert(out.clone());
+        out
+    }
+}
+
+fn origin_tag<T: AD, P: O3DPose<T>>(pose: &P) -> String {
+    let t = pose.translation();
+    let rpy = pose.rotation().euler_angles();
+    format!("<origin xyz=\"{} {} {}\" rpy=\"{} {} {}\"/>", t.x().to_constant(), t.y().to_constant(), t.z().to_constant(), rpy[0].to_constant(), rpy[1].to_constant(), rpy[2].to_constant())
+}
+
+fn fixed_joint_tag<T: AD, P: O3DPose<T>>(name: &str, parent: &str, child: &str, pose: &P) -> String {
+    format!("  <joint name=\"{}\" type=\"fixed\">\n    <parent link=\"{}\"/>\n    <child link=\"{}\"/>\n    {}\n  </joint>", xml_escape(name), xml_escape(parent), xml_escape(child), origin_tag(pose))
+}
+
+/// `mesh_uri` replaces the filename of mesh geometries.
+fn geometry_tag(geometry: &OGeometry, mesh_uri: Option<&str>) -> String {
+    match geometry {
+        OGeometry::Box { size } => { format!("<box size=\"{} {} {}\"/>", size[0], size[1], size[2]) }
+        OGeometry::Cylinder { radius, length } => { format!("<cylinder radius=\"{}\" length=\"{}\"/>", radius, length) }
+        OGeometry::Capsule { radius, length } => { format!("<cylinder radius=\"{}\" length=\"{}\"/>", radius, length + 2.0 * radius) }
+        OGeometry::Sphere { radius } => { format!("<sphere radius=\"{}\"/>", radius) }
+        OGeometry::Mesh { filename, scale } => {
+            let scale = scale.map(|s| format!(" scale=\"{} {} {}\"", s[0], s[1], s[2])).unwrap_or_default();
+            format!("<mesh filename=\"{}\"{}/>", xml_escape(mesh_uri.unwrap_or(filename)), scale)
+        }
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Isometry3;
+    use crate::robot::ORobotDefault;
+    use crate::robot::tests::two_link_arm;
+    use crate::robotics_components::{OInertial, OJoint, OJointLimit, OLink, OMimic};
+    use super::*;
+
+    fn joint<'a>(robot: &'a urdf_rs::Robot, name: &str) -> &'a urdf_rs::Joint {
+        robot.joints.iter().find(|x| x.name == name).expect("error")
+    }
+
+    fn arm_with_mimic_elbow() -> ORobotDefault {
+        let link = |name: &str| OLink::new_manual(name, vec![], vec![], OInertial::new_zeros());
+        let revolute_limit = || OJointLimit::new_manual(vec![10.0], vec![-3.0], vec![3.0], vec![2.0]);
+        let mimic = OMimic::from_mimic(&urdf_rs::Mimic { joint: "shoulder".to_string(), multiplier: Some(2.0), offset: Some(0.5) });
+        let joints = vec![
+            OJoint::new_manual("shoulder", OJointType::Revolute, Isometry3::identity(), [0.0, 0.0, 1.0], "base", "upper_arm", revolute_limit(), None, None, None),
+            OJoint::new_manual("elbow", OJointType::Revolute, Isometry3::translation(1.0, 0.0, 0.0), [0.0, 0.0, 1.0], "upper_arm", "forearm", revolute_limit(), None, Some(mimic), None)
+        ];
+
+        ORobotDefault::from_manual_unchecked("mimic_arm", vec![link("base"), link("upper_arm"), link("forearm")], joints)
+    }
+
+    #[test]
+    fn robot_is_attached_to_world_and_keeps_its_joints() {
+        let mut urdf = OUrdfSnapshot::new("scene");
+        urdf.add_robot(&two_link_arm(), &[0.3, -0.2], None, "", false);
+        let robot = urdf_rs::read_from_string(&urdf.to_urdf_string()).expect("error");
+
+        assert_eq!(robot.name, "scene");
+        let links: Vec<&str> = robot.links.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(links, vec![URDF_WORLD_LINK, "base", "upper_arm", "forearm", "ee"]);
+
+        let world_to_base = joint(&robot, "world_to_base");
+        assert!(matches!(world_to_base.joint_type, urdf_rs::JointType::Fixed));
+        assert_eq!(world_to_base.parent.link, URDF_WORLD_LINK);
+        assert_eq!(world_to_base.child.link, "base");
+
+        let shoulder = joint(&robot, "shoulder");
+        assert!(matches!(shoulder.joint_type, urdf_rs::JointType::Revolute));
+        assert_eq!((shoulder.limit.lower, shoulder.limit.upper, shoulder.limit.effort, shoulder.limit.velocity), (-3.0, 3.0, 10.0, 2.0));
+        assert!(matches!(joint(&robot, "wrist").joint_type, urdf_rs::JointType::Fixed));
+        assert!(urdf.mesh_files().is_empty());
+    }
+
+    #[test]
+    fn frozen_joints_are_fixed_at_their_state() {
+        let mut urdf = OUrdfSnapshot::new("scene");
+        urdf.add_robot(&two_link_arm(), &[0.5, 0.0], None, "", true);
+        let robot = urdf_rs::read_from_string(&urdf.to_urdf_string()).expect("error");
+
+        assert!(robot.joints.iter().all(|x| matches!(x.joint_type, urdf_rs::JointType::Fixed)));
+        let shoulder = joint(&robot, "shoulder");
+        assert!((shoulder.origin.rpy[2] - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn repeated_names_get_numbered_suffixes() {
+        let mut urdf = OUrdfSnapshot::new("scene");
+        urdf.add_robot(&two_link_arm(), &[0.0, 0.0], None, "", false);
+        urdf.add_robot(&two_link_arm(), &[0.0, 0.0], None, "", false);
+        let robot = urdf_rs::read_from_string(&urdf.to_urdf_string()).expect("error");
+
+        assert_eq!(robot.links.len(), 9);
+        let shoulder = joint(&robot, "shoulder_1");
+        assert_eq!((shoulder.parent.link.as_str(), shoulder.child.link.as_str()), ("base_1", "upper_arm_1"));
+        assert_eq!(joint(&robot, "world_to_base_1").child.link, "base_1");
+    }
+
+    #[test]
+    fn mimic_tags_refer_to_the_renamed_joint() {
+        let mut urdf = OUrdfSnapshot::new("scene");
+        urdf.add_robot(&arm_with_mimic_elbow(), &[0.0], None, "", false);
+        urdf.add_robot(&arm_with_mimic_elbow(), &[0.0], None, "", false);
+        let robot = urdf_rs::read_from_string(&urdf.to_urdf_string()).expect("error");
+
+        let mimic = joint(&robot, "elbow_1").mimic.as_ref().expect("error");
+        assert_eq!(mimic.joint, "shoulder_1");
+        assert_eq!((mimic.multiplier, mimic.offset), (Some(2.0), Some(0.5)));
+        assert_eq!(joint(&robot, "elbow").mimic.as_ref().expect("error").joint, "shoulder");
+    }
+
+    #[test]
+    fn obstacles_are_fixed_to_world() {
+        let mut urdf = OUrdfSnapshot::new("scene");
+        urdf.add_obstacle("table", &OGeometry::Box { size: [1.0, 2.0, 0.1] }, &Isometry3::translation(0.0, 0.0, 0.5), Some([0.5, 0.5, 0.5, 1.0]));
+        let robot = urdf_rs::read_from_string(&urdf.to_urdf_string()).expect("error");
+
+        let table = robot.links.iter().find(|x| x.name == "table").expect("error");
+        assert_eq!(table.visual.len(), 1);
+        assert_eq!(table.collision.len(), 1);
+        let world_to_table = joint(&robot, "world_to_table");
+        assert!(matches!(world_to_table.joint_type, urdf_rs::JointType::Fixed));
+        assert_eq!(world_to_table.origin.xyz[2], 0.5);
+    }
+}
@@ -0,0 +1,94 @@
+use optima_robotics::robot::ORobotDefault;
+
+#[cfg(feature = "ros2")]
+pub mod ros2_bridge;
+#[cfg(feature = "ros2")]
+pub mod ros2_conversions;
+
+#[derive(Clone, Debug)]
+pub struct OptimaRos2BridgeConfig {
+    pub node_name: String,
+    pub namespace: String,
+    /// robot states from the viewer's `RobotStateEngine` are published here as sensor_msgs/JointState.
+    pub joint_state_publish_topic: String,
+    /// sensor_msgs/JointState messages received here drive the visualization.
+    pub joint_state_subscribe_topic: String,
+    pub tf_topic: String,
+    pub tf_static_topic: String,
+    pub world_frame: String,
+    /// If true, the robot's base pose is followed from the transform between `world_frame` and the
+    /// robot's base link on the tf topics (e.g., as broadcast by a mobile base driver), and the bridge
+    /// stops publishing link transforms itself so that it does not compete with the robot's own
+    /// robot_state_publisher.
+    pub subscribe_to_tf: bool,
+    /// moveit_msgs/GetPositionIK service.
+    pub ik_service_name: String,
+    pub robot_instance_idx: usize,
+    pub spin_period_in_ms: u64
+}
+impl OptimaRos2BridgeConfig {
+    pub fn new(node_name: &str, namespace: &str) -> Self {
+        Self {
+            node_name: node_name.to_string(),
+            namespace: namespace.to_string(),
+            joint_state_publish_topic: "/optima/joint_states".to_string(),
+            joint_state_subscribe_topic: "/joint_states".to_string(),
+            tf_topic: "/tf".to_string(),
+            tf_static_topic: "/tf_static".to_string(),
+            world_frame: "world".to_string(),
+            subscribe_to_tf: false,
+            ik_service_name: "/optima/compute_ik".to_string(),
+            robot_instance_idx: 0,
+            spin_period_in_ms: 10,
+        }
+    }
+}
+impl Default for OptimaRos2BridgeConfig {
+    fn default() -> Self {
+        Self::new("optima_bridge", "")
+    }
+}
+
+/// Names used for each degree of freedom in JointState messages.  A joint with a single dof keeps
+/// its urdf name, while a joint with multiple dofs gets one name per dof, i.e., `<name>_<i>`.
+pub fn robot_dof_names(robot: &ORobotDefault) -> Vec<String> {
+    let mut out = vec![String::new(); robot.num_dofs()];
+    robot.joints().iter().for_each(|joint| {
+        let dof_idxs = joint.dof_idxs();
+        for (i, dof_idx) in dof_idxs.iter().enumerate() {
+            out[*dof_idx] = if dof_idxs.len() == 1 { joint.name().to_string() } else { format!("{}_{}", joint.name(), i) };
+        }
+    });
+    out
+}
+
+/// Maps named joint positions onto a full robot state.  If `names` is empty, `positions` is
+/// assumed to already be in dof order.  Returns None if not every dof is covered.
+pub fn named_positions_to_robot_state(names: &Vec<String>, positions: &Vec<f64>, dof_names: &Vec<String>) -> Option<Vec<f64>> {
+    if names.is_empty() {
+        return if positions.len() == dof_names.len() { Some(positions.clone()) } else { None };
+    }
+
+    let mut out = vec![None; dof_names.len()];
+    names.iter().zip(positions.iter()).for_each(|(name, position)| {
+        if let Some(dof_idx) = dof_names.iter().position(|x| x == name) { out[dof_idx] = Some(*position); }
+    });
+
+    out.into_iter().collect()
+}
+
+/// Same as `named_positions_to_robot_state`, but for messages that only cover some of the dofs
+/// (e.g., an arm and its gripper published by separate drivers).  Positions are merged into
+/// `partial_state`, and the full state is returned once every dof has been received at least once.
+pub fn merge_named_positions_into_robot_state(names: &Vec<String>, positions: &Vec<f64>, dof_names: &Vec<String>, partial_state: &mut Vec<Option<f64>>) -> Option<Vec<f64>> {
+    if names.is_empty() {
+        if positions.len() != dof_names.len() { return None; }
+        *partial_state = positions.iter().map(|x| Some(*x)).collect();
+    } else {
+        names.iter().zip(positions.iter()).for_each(|(name, position)| {
+            if let Some(dof_idx) = dof_names.iter().position(|x| x == name) { partial_state[dof_idx] = Some(*position); }
+        });
+    }
+
+    partial_state.iter().cloned().collect()
+}
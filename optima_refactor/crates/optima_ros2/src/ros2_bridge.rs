@@ -0,0 +1,357 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use ad_trait::differentiable_function::ForwardADMulti;
+use ad_trait::forward_ad::adfn::adfn;
+#[cfg(feature = "visualization")]
+use bevy::prelude::*;
+use futures::executor::LocalPool;
+use futures::future;
+use futures::task::LocalSpawnExt;
+use futures::StreamExt;
+use nalgebra::Isometry3;
+use r2r::builtin_interfaces::msg::Time;
+use r2r::geometry_msgs::msg::TransformStamped;
+use r2r::moveit_msgs::srv::GetPositionIK;
+use r2r::sensor_msgs::msg::JointState;
+use r2r::std_msgs::msg::Header;
+use r2r::tf2_msgs::msg::TFMessage;
+use r2r::QosProfile;
+#[cfg(feature = "visualization")]
+use optima_3d_spatial::optima_3d_pose::O3DPoseCategoryIsometry3;
+#[cfg(feature = "visualization")]
+use optima_bevy::optima_bevy_utils::robotics::{BevyORobotInstances, RobotStateEngine};
+#[cfg(feature = "visualization")]
+use optima_linalg::OLinalgCategoryNalgebra;
+use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
+use optima_optimization::open::SimpleOpEnOptimizer;
+use optima_proximity::pair_group_queries::{OwnedEmptyParryFilter, OwnedEmptyToProximityQry};
+use optima_robotics::robot::ORobotDefault;
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
+use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
+use crate::ros2_conversions::{o3dpose_to_ros_transform, ros_pose_to_o3dpose, ros_transform_to_o3dpose};
+use crate::{merge_named_positions_into_robot_state, named_positions_to_robot_state, robot_dof_names, OptimaRos2BridgeConfig};
+
+type FAD = adfn<8>;
+
+/// moveit_msgs/MoveItErrorCodes values used by the IK service.
+const MOVEIT_SUCCESS: i32 = 1;
+const MOVEIT_NO_IK_SOLUTION: i32 = -31;
+const MOVEIT_INVALID_LINK_NAME: i32 = -24;
+
+/// Longest chain of transforms followed when looking up the robot's base pose in the tf tree.
+const MAX_TF_CHAIN_LENGTH: usize = 32;
+
+/// Connection to a running bridge node.  The node itself lives on its own thread (r2r nodes have to
+/// be spun continuously), and robot states are passed back and forth through channels.
+pub struct OptimaRos2BridgeHandle {
+    outgoing: Mutex<Sender<Vec<f64>>>,
+    incoming: Mutex<Receiver<Vec<f64>>>,
+    incoming_base_poses: Mutex<Receiver<Isometry3<f64>>>,
+    _node_thread: JoinHandle<()>
+}
+impl OptimaRos2BridgeHandle {
+    /// Spawns the bridge node.  Publishing, subscribing, and the IK service all start immediately.
+    pub fn spawn(robot: ORobotDefault, config: OptimaRos2BridgeConfig) -> Self {
+        let (outgoing_tx, outgoing_rx) = channel();
+        let (incoming_tx, incoming_rx) = channel();
+        let (incoming_base_poses_tx, incoming_base_poses_rx) = channel();
+
+        let node_thread = std::thread::spawn(move || {
+            run_bridge_node(robot, config, outgoing_rx, incoming_tx, incoming_base_poses_tx);
+        });
+
+        Self {
+            outgoing: Mutex::new(outgoing_tx),
+            incoming: Mutex::new(incoming_rx),
+            incoming_base_poses: Mutex::new(incoming_base_poses_rx),
+            _node_thread: node_thread,
+        }
+    }
+    /// Publishes the given state as a JointState message, along with the corresponding link
+    /// transforms on the tf topic.
+    pub fn publish_state(&self, state: &Vec<f64>) {
+        self.outgoing.lock().unwrap().send(state.clone()).expect("bridge node is no longer running");
+    }
+    /// Returns the most recent state received on the subscribed JointState topic since the last
+    /// call, if any.  Messages that only name some of the dofs are merged into the previous ones, and
+    /// nothing is returned until every dof has been named at least once.
+    pub fn try_recv_latest_state(&self) -> Option<Vec<f64>> {
+        let incoming = self.incoming.lock().unwrap();
+        let mut out = None;
+        while let Ok(state) = incoming.try_recv() { out = Some(state); }
+        out
+    }
+    /// Returns the most recent pose of the robot's base link in `world_frame` looked up from the tf
+    /// topics since the last call, if any.  Always None unless `subscribe_to_tf` is set.
+    pub fn try_recv_latest_base_pose(&self) -> Option<Isometry3<f64>> {
+        let incoming_base_poses = self.incoming_base_poses.lock().unwrap();
+        let mut out = None;
+        while let Ok(base_pose) = incoming_base_poses.try_recv() { out = Some(base_pose); }
+        out
+    }
+}
+
+fn run_bridge_node(robot: ORobotDefault, config: OptimaRos2BridgeConfig, outgoing_rx: Receiver<Vec<f64>>, incoming_tx: Sender<Vec<f64>>, incoming_base_poses_tx: Sender<Isometry3<f64>>) {
+    let ctx = r2r::Context::create().expect("error");
+    let mut node = r2r::Node::create(ctx, &config.node_name, &config.namespace).expect("error");
+    let joint_state_publisher = node.create_publisher::<JointState>(&config.joint_state_publish_topic, QosProfile::default()).expect("error");
+    let tf_publisher = node.create_publisher::<TFMessage>(&config.tf_topic, QosProfile::default()).expect("error");
+    let joint_state_subscriber = node.subscribe::<JointState>(&config.joint_state_subscribe_topic, QosProfile::default()).expect("error");
+    let ik_service = node.create_service::<GetPositionIK::Service>(&config.ik_service_name, QosProfile::default()).expect("error");
+    let mut clock = r2r::Clock::create(r2r::ClockType::RosTime).expect("error");
+
+    let dof_names = robot_dof_names(&robot);
+
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+
+    let dof_names_ = dof_names.clone();
+    let mut partial_state = vec![None; dof_names.len()];
+    spawner.spawn_local(async move {
+        joint_state_subscriber.for_each(|msg| {
+            if let Some(state) = merge_named_positions_into_robot_state(&msg.name, &msg.position, &dof_names_, &mut partial_state) {
+                incoming_tx.send(state).ok();
+            }
+            future::ready(())
+        }).await
+    }).expect("error");
+
+    if config.subscribe_to_tf {
+        let tf_subscriber = node.subscribe::<TFMessage>(&config.tf_topic, QosProfile::default()).expect("error");
+        // static transforms are only sent once, so late joiners rely on them being latched.
+        let tf_static_subscriber = node.subscribe::<TFMessage>(&config.tf_static_topic, QosProfile::default().transient_local()).expect("error");
+        let tf_tree = Rc::new(RefCell::new(TfTree::new()));
+        let base_link_name = robot.links()[robot.base_link_idx()].name().to_string();
+
+        for subscriber in [tf_subscriber, tf_static_subscriber] {
+            let tf_tree = tf_tree.clone();
+            let base_link_name = base_link_name.clone();
+            let world_frame = config.world_frame.clone();
+            let incoming_base_poses_tx = incoming_base_poses_tx.clone();
+            spawner.spawn_local(async move {
+                subscriber.for_each(|msg| {
+                    let mut tf_tree = tf_tree.borrow_mut();
+                    tf_tree.add_transforms(&msg.transforms);
+                    if msg.transforms.iter().any(|x| tf_tree.is_ancestor_or_self(&x.child_frame_id, &base_link_name)) {
+                        if let Some(base_pose) = tf_tree.lookup(&world_frame, &base_link_name) { incoming_base_poses_tx.send(base_pose).ok(); }
+                    }
+                    future::ready(())
+                }).await
+            }).expect("error");
+        }
+    }
+
+    let robot_ = robot.clone();
+    let dof_names_ = dof_names.clone();
+    spawner.spawn_local(async move {
+        ik_service.for_each(|request| {
+            let response = solve_ik_request(&robot_, &dof_names_, &request.message);
+            request.respond(response).expect("error");
+            future::ready(())
+        }).await
+    }).expect("error");
+
+    loop {
+        node.spin_once(Duration::from_millis(config.spin_period_in_ms));
+        pool.run_until_stalled();
+
+        let mut latest_state = None;
+        loop {
+            match outgoing_rx.try_recv() {
+                Ok(state) => { latest_state = Some(state); }
+                Err(TryRecvError::Empty) => { break; }
+                Err(TryRecvError::Disconnected) => { return; }
+            }
+        }
+
+        if let Some(state) = latest_state {
+            let stamp = r2r::Clock::to_builtin_time(&clock.get_now().expect("error"));
+            joint_state_publisher.publish(&state_to_joint_state_msg(&state, &dof_names, stamp.clone())).expect("error");
+            if !config.subscribe_to_tf { tf_publisher.publish(&state_to_tf_msg(&robot, &state, &config.world_frame, stamp)).expect("error"); }
+        }
+    }
+}
+
+/// The most recent transform to every frame from its parent, as received on the tf topics.
+struct TfTree {
+    parents: HashMap<String, (String, Isometry3<f64>)>
+}
+impl TfTree {
+    fn new() -> Self {
+        Self { parents: HashMap::new() }
+    }
+    fn add_transforms(&mut self, transforms: &Vec<TransformStamped>) {
+        transforms.iter().for_each(|x| {
+            let transform = ros_transform_to_o3dpose(&x.transform);
+            self.parents.insert(trim_frame_id(&x.child_frame_id).to_string(), (trim_frame_id(&x.header.frame_id).to_string(), transform));
+        });
+    }
+    /// Pose of `frame` in `root_frame`, if `frame` is connected to `root_frame` through its parents.
+    fn lookup(&self, root_frame: &str, frame: &str) -> Option<Isometry3<f64>> {
+        let root_frame = trim_frame_id(root_frame);
+        let mut out = Isometry3::identity();
+        let mut curr_frame = trim_frame_id(frame);
+        for _ in 0..MAX_TF_CHAIN_LENGTH {
+            if curr_frame == root_frame { return Some(out); }
+            let (parent_frame, transform) = self.parents.get(curr_frame)?;
+            out = transform * out;
+            curr_frame = parent_frame.as_str();
+        }
+        None
+    }
+    fn is_ancestor_or_self(&self, frame: &str, of_frame: &str) -> bool {
+        let frame = trim_frame_id(frame);
+        let mut curr_frame = trim_frame_id(of_frame);
+        for _ in 0..MAX_TF_CHAIN_LENGTH {
+            if curr_frame == frame { return true; }
+            match self.parents.get(curr_frame) {
+                None => { return false; }
+                Some((parent_frame, _)) => { curr_frame = parent_frame.as_str(); }
+            }
+        }
+        false
+    }
+}
+
+/// tf2 ignores a leading slash on frame ids, but some older publishers still send one.
+fn trim_frame_id(frame_id: &str) -> &str {
+    frame_id.trim_start_matches('/')
+}
+
+fn solve_ik_request(robot: &ORobotDefault, dof_names: &Vec<String>, request: &GetPositionIK::Request) -> GetPositionIK::Response {
+    let mut response = GetPositionIK::Response::default();
+    let ik_request = &request.ik_request;
+
+    let link_idx = match robot.links().iter().position(|x| x.name() == ik_request.ik_link_name) {
+        None => {
+            response.error_code.val = MOVEIT_INVALID_LINK_NAME;
+            return response;
+        }
+        Some(link_idx) => { link_idx }
+    };
+
+    let joint_state = &ik_request.robot_state.joint_state;
+    let init_state = named_positions_to_robot_state(&joint_state.name, &joint_state.position, dof_names).unwrap_or(vec![0.0; robot.num_dofs()]);
+
+    let goal_pose: Isometry3<f64> = ros_pose_to_o3dpose(&ik_request.pose_stamped.pose);
+
+    let db = robot.get_ik_differentiable_block(ForwardADMulti::<FAD>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![link_idx], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0));
+    db.update_ik_pose(0, goal_pose.clone(), IKGoalUpdateMode::Absolute);
+
+    let o = SimpleOpEnOptimizer::new(robot.get_dof_lower_bounds(), robot.get_dof_upper_bounds(), 0.001);
+    let res = o.optimize_unconstrained(&init_state, &db);
+    let solution = res.x_star().to_vec();
+
+    let fk_res = robot.forward_kinematics(&solution, None);
+    let reached = match fk_res.get_link_pose(link_idx) {
+        Err(_) => { false }
+        Ok(pose) => {
+            let disp = goal_pose.inverse() * pose;
+            disp.translation.vector.norm() < 0.001 && disp.rotation.angle() < 0.01
+        }
+    };
+
+    response.solution.joint_state = state_to_joint_state_msg(&solution, dof_names, ik_request.pose_stamped.header.stamp.clone());
+    response.error_code.val = if reached { MOVEIT_SUCCESS } else { MOVEIT_NO_IK_SOLUTION };
+    response
+}
+
+fn state_to_joint_state_msg(state: &Vec<f64>, dof_names: &Vec<String>, stamp: Time) -> JointState {
+    JointState {
+        header: Header { stamp, frame_id: String::new() },
+        name: dof_names.clone(),
+        position: state.clone(),
+        velocity: vec![],
+        effort: vec![],
+    }
+}
+
+fn state_to_tf_msg(robot: &ORobotDefault, state: &Vec<f64>, world_frame: &str, stamp: Time) -> TFMessage {
+    let fk_res = robot.forward_kinematics(state, None);
+    let mut transforms = vec![];
+    robot.links().iter().enumerate().for_each(|(link_idx, link)| {
+        if link.is_present_in_model() {
+            if let Ok(pose) = fk_res.get_link_pose(link_idx) {
+                transforms.push(TransformStamped {
+                    header: Header { stamp: stamp.clone(), frame_id: world_frame.to_string() },
+                    child_frame_id: link.name().to_string(),
+                    transform: o3dpose_to_ros_transform(pose),
+                });
+            }
+        }
+    });
+
+    TFMessage { transforms }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "visualization")]
+#[derive(Resource)]
+pub struct BevyRos2Bridge {
+    handle: OptimaRos2BridgeHandle,
+    robot_instance_idx: usize,
+    last_published_state: Option<Vec<f64>>,
+    base_pose_warning_shown: bool
+}
+
+#[cfg(feature = "visualization")]
+pub struct Ros2BridgeSystems;
+#[cfg(feature = "visualization")]
+impl Ros2BridgeSystems {
+    /// States received from ROS are forwarded to the `RobotStateEngine`, and any change in the
+    /// engine's state is published back out.  Base poses from tf can only be applied if the robot
+    /// was added as a robot instance (see `optima_bevy_robot_instance`).
+    pub fn system_ros2_bridge(mut bridge: ResMut<BevyRos2Bridge>,
+                              instances: Option<ResMut<BevyORobotInstances<f64, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra>>>,
+                              mut robot_state_engine: ResMut<RobotStateEngine>) {
+        let robot_instance_idx = bridge.robot_instance_idx;
+
+        let state = bridge.handle.try_recv_latest_state();
+        let mut base_pose_changed = false;
+        if let Some(base_pose) = bridge.handle.try_recv_latest_base_pose() {
+            base_pose_changed = instances.map(|mut x| x.set_base_pose(robot_instance_idx, base_pose)).unwrap_or(false);
+            if !base_pose_changed && !bridge.base_pose_warning_shown {
+                warn!("base pose received on tf, but robot {} is not a robot instance, so it is not moved.", robot_instance_idx);
+                bridge.base_pose_warning_shown = true;
+            }
+        }
+
+        match (state, base_pose_changed) {
+            (Some(state), _) => { robot_state_engine.add_update_request(robot_instance_idx, &state); }
+            // the link meshes are only moved to a new base pose when the state is set again.
+            (None, true) => {
+                if let Some(state) = robot_state_engine.get_robot_state(robot_instance_idx).cloned() { robot_state_engine.add_update_request(robot_instance_idx, &state); }
+            }
+            (None, false) => { }
+        }
+
+        if let Some(state) = robot_state_engine.get_robot_state(robot_instance_idx) {
+            if bridge.last_published_state.as_ref() != Some(state) {
+                bridge.handle.publish_state(state);
+                bridge.last_published_state = Some(state.clone());
+            }
+        }
+    }
+}
+
+#[cfg(feature = "visualization")]
+pub trait OptimaBevyRos2Trait {
+    fn optima_bevy_ros2_bridge(&mut self, robot: ORobotDefault, config: OptimaRos2BridgeConfig) -> &mut Self;
+}
+#[cfg(feature = "visualization")]
+impl OptimaBevyRos2Trait for App {
+    fn optima_bevy_ros2_bridge(&mut self, robot: ORobotDefault, config: OptimaRos2BridgeConfig) -> &mut Self {
+        let robot_instance_idx = config.robot_instance_idx;
+        self
+            .insert_resource(BevyRos2Bridge { handle: OptimaRos2BridgeHandle::spawn(robot, config), robot_instance_idx, last_published_state: None, base_pose_warning_shown: false })
+            .add_systems(Update, Ros2BridgeSystems::system_ros2_bridge);
+
+        self
+    }
+}
@@ -0,0 +1,96 @@
+use ad_trait::AD;
+use r2r::builtin_interfaces::msg::Duration as RosDuration;
+use r2r::geometry_msgs::msg::{Point, Pose, Quaternion as RosQuaternion, Transform as RosTransform, Vector3 as RosVector3};
+use r2r::trajectory_msgs::msg::{JointTrajectory, JointTrajectoryPoint};
+use optima_3d_spatial::optima_3d_pose::O3DPose;
+use optima_3d_spatial::optima_3d_rotation::O3DRotation;
+use optima_3d_spatial::optima_3d_vec::O3DVec;
+use optima_linalg::OVec;
+use optima_robotics::robotics_traits::{AsTrajectory, AsTrajectoryWaypoint};
+use optima_robotics::utils::{RobotTrajectory, RobotTrajectoryWaypoint};
+use crate::named_positions_to_robot_state;
+
+// Conversions between optima's spatial types and ROS 2 message structs.  Both use z up, x forward
+// frames, so only the number types and quaternion component order (ROS stores x, y, z, w; optima's
+// slices are w, x, y, z) differ.
+
+pub fn o3dvec_to_ros_vector3<T: AD, V: O3DVec<T>>(v: &V) -> RosVector3 {
+    RosVector3 { x: v.x().to_constant(), y: v.y().to_constant(), z: v.z().to_constant() }
+}
+
+pub fn ros_vector3_to_o3dvec<T: AD, V: O3DVec<T>>(v: &RosVector3) -> V {
+    V::o3dvec_from_slice(&[T::constant(v.x), T::constant(v.y), T::constant(v.z)])
+}
+
+pub fn o3dvec_to_ros_point<T: AD, V: O3DVec<T>>(v: &V) -> Point {
+    Point { x: v.x().to_constant(), y: v.y().to_constant(), z: v.z().to_constant() }
+}
+
+pub fn ros_point_to_o3dvec<T: AD, V: O3DVec<T>>(p: &Point) -> V {
+    V::o3dvec_from_slice(&[T::constant(p.x), T::constant(p.y), T::constant(p.z)])
+}
+
+pub fn o3drot_to_ros_quaternion<T: AD, R: O3DRotation<T>>(rotation: &R) -> RosQuaternion {
+    let q = rotation.unit_quaternion_as_wxyz_slice();
+    RosQuaternion { x: q[1].to_constant(), y: q[2].to_constant(), z: q[3].to_constant(), w: q[0].to_constant() }
+}
+
+/// The quaternion is normalized, so slightly off messages (e.g., from rounding in yaml) still work.
+pub fn ros_quaternion_to_o3drot<T: AD, R: O3DRotation<T>>(q: &RosQuaternion) -> R {
+    R::from_unit_quaternion_as_wxyz_slice(&[T::constant(q.w), T::constant(q.x), T::constant(q.y), T::constant(q.z)])
+}
+
+pub fn o3dpose_to_ros_pose<T: AD, P: O3DPose<T>>(pose: &P) -> Pose {
+    Pose { position: o3dvec_to_ros_point(pose.translation()), orientation: o3drot_to_ros_quaternion(pose.rotation()) }
+}
+
+pub fn ros_pose_to_o3dpose<T: AD, P: O3DPose<T>>(pose: &Pose) -> P {
+    let translation: [T; 3] = ros_point_to_o3dvec(&pose.position);
+    let rotation: P::RotationType = ros_quaternion_to_o3drot(&pose.orientation);
+    P::from_translation_and_rotation(&translation, &rotation)
+}
+
+pub fn o3dpose_to_ros_transform<T: AD, P: O3DPose<T>>(pose: &P) -> RosTransform {
+    RosTransform { translation: o3dvec_to_ros_vector3(pose.translation()), rotation: o3drot_to_ros_quaternion(pose.rotation()) }
+}
+
+pub fn ros_transform_to_o3dpose<T: AD, P: O3DPose<T>>(transform: &RosTransform) -> P {
+    let translation: [T; 3] = ros_vector3_to_o3dvec(&transform.translation);
+    let rotation: P::RotationType = ros_quaternion_to_o3drot(&transform.rotation);
+    P::from_translation_and_rotation(&translation, &rotation)
+}
+
+/// One trajectory point per waypoint, with positions only and waypoint times as `time_from_start`
+/// (waypoint times are assumed to be in seconds from the start of the trajectory).  `dof_names`
+/// are usually from `robot_dof_names`.
+pub fn robot_trajectory_to_ros_joint_trajectory<T: AD, V: OVec<T>>(trajectory: &RobotTrajectory<T, V>, dof_names: &Vec<String>) -> JointTrajectory {
+    let points = trajectory.get_waypoints().iter().map(|waypoint| {
+        JointTrajectoryPoint {
+            positions: waypoint.waypoint_as_slice().iter().map(|x| x.to_constant()).collect(),
+            time_from_start: seconds_to_ros_duration(waypoint.get_waypoint_time().to_constant()),
+            ..Default::default()
+        }
+    }).collect();
+
+    JointTrajectory { joint_names: dof_names.clone(), points, ..Default::default() }
+}
+
+/// Joints are matched to the robot's dofs by name (see `named_positions_to_robot_state`), so the
+/// message may list them in any order; every dof has to be covered by every point.
+pub fn ros_joint_trajectory_to_robot_trajectory(trajectory: &JointTrajectory, dof_names: &Vec<String>) -> Result<RobotTrajectory<f64, Vec<f64>>, String> {
+    let mut out = RobotTrajectory::new_empty();
+    for (i, point) in trajectory.points.iter().enumerate() {
+        let state = named_positions_to_robot_state(&trajectory.joint_names, &point.positions, dof_names).ok_or(format!("point {} of the joint trajectory does not cover every dof of the robot.", i))?;
+        out.add_waypoint(RobotTrajectoryWaypoint::new(ros_duration_to_seconds(&point.time_from_start), state));
+    }
+    Ok(out)
+}
+
+pub fn seconds_to_ros_duration(seconds: f64) -> RosDuration {
+    let sec = seconds.floor();
+    RosDuration { sec: sec as i32, nanosec: ((seconds - sec) * 1e9).round().min(999_999_999.0) as u32 }
+}
+
+pub fn ros_duration_to_seconds(duration: &RosDuration) -> f64 {
+    duration.sec as f64 + duration.nanosec as f64 * 1e-9
+}
@@ -1,23 +1,111 @@
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
 use ad_trait::AD;
-use rand::Rng;
-use rand_chacha::ChaCha20Rng;
+pub use rand::{Rng, RngCore};
+pub use rand_chacha::ChaCha20Rng;
 use rand_chacha::rand_core::SeedableRng;
 use rand_distr::Normal;
 use rand_distr::Distribution;
 
+/// Environment variable that, if set to an integer, seeds the global rng on first use.  Any other
+/// value is ignored with a warning.
+pub const OPTIMA_SEED_ENV_VAR: &str = "OPTIMA_SEED";
+
+/// Returns an rng seeded with `random_seed`.  Without a seed, the rng is drawn from the global rng
+/// (see `OGlobalRng`), so it is reproducible whenever a global seed is set and comes from entropy
+/// otherwise.
 pub fn get_rng(random_seed: Option<u64>) -> ChaCha20Rng {
     match random_seed {
-        None => { ChaCha20Rng::from_entropy() }
+        None => { OGlobalRng::next_rng() }
         Some(seed) => { ChaCha20Rng::seed_from_u64(seed) }
     }
 }
 
+/// The toolbox-wide rng service.  Every unseeded sample in optima (`get_rng(None)`, the
+/// `SimpleSampler` functions with `seed: None`, `sample_pseudorandom_state`, robot preprocessing,
+/// optimizer perturbations, etc.) goes through here.
+///
+/// Once a seed is set, either with `OGlobalRng::set_seed` or through the `OPTIMA_SEED` environment
+/// variable, each request is given its own ChaCha20 stream whose seed is the next value of a master
+/// ChaCha20 stream.  ChaCha20 output does not depend on the platform, so the same seed and the same
+/// sequence of requests give the same samples everywhere.  Requests made concurrently from several
+/// threads are served in whatever order the threads arrive; code that needs reproducibility across
+/// threads should pass explicit seeds instead.  Without a seed, no lock is taken, so unseeded
+/// sampling from many threads does not contend.
+pub struct OGlobalRng;
+impl OGlobalRng {
+    /// Sets (or, with None, clears) the global seed and restarts the master stream.
+    pub fn set_seed(seed: Option<u64>) {
+        let global = Self::global();
+        let mut state = global.state.lock().expect("error");
+        *state = OGlobalRngState::new(seed);
+        global.seeded.store(seed.is_some(), Ordering::Release);
+    }
+    pub fn seed() -> Option<u64> {
+        let global = Self::global();
+        if !global.seeded.load(Ordering::Acquire) { return None; }
+        global.state.lock().expect("error").seed
+    }
+    /// Returns a fresh rng: derived from the master stream if a global seed is set, seeded from
+    /// the thread-local rng otherwise.
+    pub fn next_rng() -> ChaCha20Rng {
+        let global = Self::global();
+        if global.seeded.load(Ordering::Acquire) {
+            // the seed may have been cleared since the flag was read.
+            if let Some(master) = &mut global.state.lock().expect("error").master {
+                return ChaCha20Rng::seed_from_u64(master.next_u64());
+            }
+        }
+        ChaCha20Rng::from_rng(rand::thread_rng()).expect("error")
+    }
+    /// A seed for code that takes `Option<u64>` seeds: from the master stream if a global seed is
+    /// set, from entropy otherwise.
+    pub fn next_seed() -> u64 {
+        Self::next_rng().next_u64()
+    }
+    fn global() -> &'static OGlobalRngCell {
+        static GLOBAL: OnceLock<OGlobalRngCell> = OnceLock::new();
+        GLOBAL.get_or_init(|| {
+            let seed = match std::env::var(OPTIMA_SEED_ENV_VAR) {
+                Err(_) => { None }
+                Ok(s) => {
+                    let seed = s.trim().parse::<u64>().ok();
+                    if seed.is_none() { tracing::warn!("{} is set to {:?}, which is not a non-negative integer, so the global rng is not seeded.", OPTIMA_SEED_ENV_VAR, s); }
+                    seed
+                }
+            };
+            OGlobalRngCell { seeded: AtomicBool::new(seed.is_some()), state: Mutex::new(OGlobalRngState::new(seed)) }
+        })
+    }
+}
+
+struct OGlobalRngCell {
+    /// Mirrors `state.seed.is_some()`, so that unseeded requests can skip the lock.
+    seeded: AtomicBool,
+    state: Mutex<OGlobalRngState>
+}
+
+struct OGlobalRngState {
+    seed: Option<u64>,
+    master: Option<ChaCha20Rng>
+}
+impl OGlobalRngState {
+    fn new(seed: Option<u64>) -> Self {
+        Self { seed, master: seed.map(|s| ChaCha20Rng::seed_from_u64(s)) }
+    }
+}
+
 pub struct SimpleSampler;
 impl SimpleSampler {
     pub fn uniform_samples<T: AD>(bounds: &Vec<(T, T)>, seed: Option<u64>) -> Vec<T> {
+        Self::uniform_samples_with_rng(bounds, &mut get_rng(seed))
+    }
+
+    /// Same as `uniform_samples`, but draws from the given rng, so a single seeded rng can be
+    /// carried through a sequence of samples.
+    pub fn uniform_samples_with_rng<T: AD, R: Rng>(bounds: &Vec<(T, T)>, rng: &mut R) -> Vec<T> {
         let mut out_vec = vec![];
 
-        let mut rng = get_rng(seed);
         for b in bounds {
             if b.0 == b.1 { out_vec.push(b.0) }
             else {
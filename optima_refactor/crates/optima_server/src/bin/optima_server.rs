@@ -0,0 +1,46 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use optima_server::grpc::serve_grpc;
+use optima_server::http::serve_http;
+use optima_server::robot_registry::RobotRegistry;
+
+/// usage: optima_server [--grpc <address>] [--http <address>]
+///
+/// If neither is given, only the gRPC server is started on 0.0.0.0:50051.  When both are given,
+/// the two servers share the same set of loaded robots.
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let mut grpc_addr: Option<SocketAddr> = None;
+    let mut http_addr: Option<SocketAddr> = None;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--grpc" => { grpc_addr = Some(args.get(i + 1).expect("missing address after --grpc").parse().expect("invalid address")); i += 2; }
+            "--http" => { http_addr = Some(args.get(i + 1).expect("missing address after --http").parse().expect("invalid address")); i += 2; }
+            s => { panic!("unrecognized argument {}", s); }
+        }
+    }
+    if grpc_addr.is_none() && http_addr.is_none() { grpc_addr = Some("0.0.0.0:50051".parse().unwrap()); }
+
+    let registry = Arc::new(RobotRegistry::new());
+
+    let grpc_registry = registry.clone();
+    let grpc = async move {
+        if let Some(addr) = grpc_addr {
+            println!("optima_server listening for gRPC on {}", addr);
+            serve_grpc(addr, grpc_registry).await.expect("error");
+        }
+    };
+
+    let http_registry = registry.clone();
+    let http = async move {
+        if let Some(addr) = http_addr {
+            println!("optima_server listening for http on {}", addr);
+            serve_http(addr, http_registry).await.expect("error");
+        }
+    };
+
+    tokio::join!(grpc, http);
+}
@@ -0,0 +1,107 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tonic::transport::Server;
+use crate::operations;
+use crate::proto::optima_service_server::{OptimaService, OptimaServiceServer};
+use crate::proto::{CheckCollisionRequest, CheckCollisionResponse, ForwardKinematicsRequest, ForwardKinematicsResponse, InterpolateTrajectoryRequest, LoadRobotRequest, LoadRobotResponse, OptionalPose, Pose, SolveIkRequest, SolveIkResponse, TrajectoryPoint};
+use crate::robot_registry::RobotRegistry;
+
+pub struct OptimaGrpcService {
+    registry: Arc<RobotRegistry>
+}
+impl OptimaGrpcService {
+    pub fn new(registry: Arc<RobotRegistry>) -> Self {
+        Self { registry }
+    }
+}
+
+#[tonic::async_trait]
+impl OptimaService for OptimaGrpcService {
+    async fn load_robot(&self, request: Request<LoadRobotRequest>) -> Result<Response<LoadRobotResponse>, Status> {
+        let request = request.into_inner();
+        let registry = self.registry.clone();
+        let robot_handle = run_blocking(move || registry.load(&request.robot_name, request.from_urdf)).await?;
+        let robot = self.registry.get(robot_handle).map_err(Status::not_found)?;
+
+        Ok(Response::new(LoadRobotResponse {
+            robot_handle,
+            num_dofs: robot.num_dofs() as u32,
+            link_names: robot.links().iter().map(|x| x.name().to_string()).collect(),
+            dof_lower_bounds: robot.get_dof_lower_bounds(),
+            dof_upper_bounds: robot.get_dof_upper_bounds(),
+        }))
+    }
+
+    async fn forward_kinematics(&self, request: Request<ForwardKinematicsRequest>) -> Result<Response<ForwardKinematicsResponse>, Status> {
+        let request = request.into_inner();
+        let robot = self.registry.get(request.robot_handle).map_err(Status::not_found)?;
+        let link_poses = operations::forward_kinematics(&robot, &request.state).map_err(Status::invalid_argument)?;
+
+        let link_poses = link_poses.iter().map(|x| {
+            match x {
+                None => { OptionalPose { has_pose: false, pose: None } }
+                Some(pose) => {
+                    let (position, orientation) = operations::pose_to_slices(pose);
+                    OptionalPose { has_pose: true, pose: Some(Pose { position: position.to_vec(), orientation: orientation.to_vec() }) }
+                }
+            }
+        }).collect();
+
+        Ok(Response::new(ForwardKinematicsResponse { link_poses }))
+    }
+
+    async fn solve_ik(&self, request: Request<SolveIkRequest>) -> Result<Response<SolveIkResponse>, Status> {
+        let request = request.into_inner();
+        let robot = self.registry.get(request.robot_handle).map_err(Status::not_found)?;
+        let goal_pose = request.goal_pose.ok_or(Status::invalid_argument("goal_pose is required"))?;
+        let (solution, objective_value) = run_blocking(move || operations::solve_ik(&robot, &request.init_state, request.link_idx as usize, &goal_pose.position, &goal_pose.orientation)).await?;
+
+        Ok(Response::new(SolveIkResponse { solution, objective_value }))
+    }
+
+    async fn check_collision(&self, request: Request<CheckCollisionRequest>) -> Result<Response<CheckCollisionResponse>, Status> {
+        let request = request.into_inner();
+        let robot = self.registry.get(request.robot_handle).map_err(Status::not_found)?;
+        let in_collision = run_blocking(move || operations::check_collision(&robot, &request.state)).await?;
+
+        Ok(Response::new(CheckCollisionResponse { in_collision }))
+    }
+
+    type InterpolateTrajectoryStream = ReceiverStream<Result<TrajectoryPoint, Status>>;
+
+    async fn interpolate_trajectory(&self, request: Request<InterpolateTrajectoryRequest>) -> Result<Response<Self::InterpolateTrajectoryStream>, Status> {
+        let request = request.into_inner();
+        let waypoints = request.waypoints.into_iter().map(|x| x.values).collect();
+        let points = run_blocking(move || operations::interpolate_trajectory(waypoints, request.num_points as usize, &request.spline_type)).await?;
+
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            for (idx, values) in points.into_iter().enumerate() {
+                if tx.send(Ok(TrajectoryPoint { idx: idx as u32, values })).await.is_err() { break; }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+/// Optimization and collision queries can take a while, so they are kept off of the async runtime's
+/// worker threads.
+async fn run_blocking<R, F>(f: F) -> Result<R, Status>
+    where R: Send + 'static,
+          F: FnOnce() -> Result<R, String> + Send + 'static
+{
+    tokio::task::spawn_blocking(f).await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(Status::invalid_argument)
+}
+
+pub async fn serve_grpc(addr: SocketAddr, registry: Arc<RobotRegistry>) -> Result<(), tonic::transport::Error> {
+    Server::builder()
+        .add_service(OptimaServiceServer::new(OptimaGrpcService::new(registry)))
+        .serve(addr)
+        .await
+}
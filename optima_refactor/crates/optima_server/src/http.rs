@@ -0,0 +1,176 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use axum::{Json, Router};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use serde::{Deserialize, Serialize};
+use crate::operations;
+use crate::robot_registry::RobotRegistry;
+
+/// Plain json-over-http version of the gRPC api, for clients that cannot easily use gRPC (web
+/// dashboards, cell controllers, curl, etc.).
+///
+/// POST   /robots                          {"robot_name": "ur5", "from_urdf": false}
+/// GET    /robots
+/// DELETE /robots/:handle
+/// POST   /robots/:handle/fk               {"state": [...]}
+/// POST   /robots/:handle/ik               {"init_state": [...], "link_idx": 9, "position": [x, y, z], "orientation": [w, x, y, z]}
+/// POST   /robots/:handle/collision        {"state": [...]}
+/// POST   /trajectory/interpolate          {"waypoints": [[...], ...], "num_points": 100, "spline_type": "linear"}
+pub fn http_router(registry: Arc<RobotRegistry>) -> Router {
+    Router::new()
+        .route("/robots", post(load_robot).get(list_robots))
+        .route("/robots/:handle", delete(remove_robot))
+        .route("/robots/:handle/fk", post(forward_kinematics))
+        .route("/robots/:handle/ik", post(solve_ik))
+        .route("/robots/:handle/collision", post(check_collision))
+        .route("/trajectory/interpolate", post(interpolate_trajectory))
+        .route("/health", get(|| async { "ok" }))
+        .with_state(registry)
+}
+
+pub async fn serve_http(addr: SocketAddr, registry: Arc<RobotRegistry>) -> Result<(), hyper::Error> {
+    axum::Server::bind(&addr)
+        .serve(http_router(registry).into_make_service())
+        .await
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Deserialize)]
+pub struct LoadRobotBody {
+    pub robot_name: String,
+    #[serde(default)]
+    pub from_urdf: bool
+}
+
+#[derive(Serialize)]
+pub struct LoadRobotReply {
+    pub robot_handle: u64,
+    pub num_dofs: usize,
+    pub link_names: Vec<String>,
+    pub dof_lower_bounds: Vec<f64>,
+    pub dof_upper_bounds: Vec<f64>
+}
+
+#[derive(Deserialize)]
+pub struct StateBody {
+    pub state: Vec<f64>
+}
+
+#[derive(Serialize)]
+pub struct PoseReply {
+    pub position: [f64; 3],
+    pub orientation: [f64; 4]
+}
+
+#[derive(Serialize)]
+pub struct ForwardKinematicsReply {
+    pub link_poses: Vec<Option<PoseReply>>
+}
+
+#[derive(Deserialize)]
+pub struct SolveIKBody {
+    pub init_state: Vec<f64>,
+    pub link_idx: usize,
+    pub position: Vec<f64>,
+    pub orientation: Vec<f64>
+}
+
+#[derive(Serialize)]
+pub struct SolveIKReply {
+    pub solution: Vec<f64>,
+    pub objective_value: f64
+}
+
+#[derive(Serialize)]
+pub struct CheckCollisionReply {
+    pub in_collision: bool
+}
+
+#[derive(Deserialize)]
+pub struct InterpolateTrajectoryBody {
+    pub waypoints: Vec<Vec<f64>>,
+    pub num_points: usize,
+    pub spline_type: String
+}
+
+#[derive(Serialize)]
+pub struct InterpolateTrajectoryReply {
+    pub points: Vec<Vec<f64>>
+}
+
+pub struct HttpError(StatusCode, String);
+impl IntoResponse for HttpError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+async fn load_robot(State(registry): State<Arc<RobotRegistry>>, Json(body): Json<LoadRobotBody>) -> Result<Json<LoadRobotReply>, HttpError> {
+    let registry_ = registry.clone();
+    let robot_handle = run_blocking(move || registry_.load(&body.robot_name, body.from_urdf)).await?;
+    let robot = registry.get(robot_handle).map_err(|e| HttpError(StatusCode::NOT_FOUND, e))?;
+
+    Ok(Json(LoadRobotReply {
+        robot_handle,
+        num_dofs: robot.num_dofs(),
+        link_names: robot.links().iter().map(|x| x.name().to_string()).collect(),
+        dof_lower_bounds: robot.get_dof_lower_bounds(),
+        dof_upper_bounds: robot.get_dof_upper_bounds(),
+    }))
+}
+
+async fn list_robots(State(registry): State<Arc<RobotRegistry>>) -> Json<Vec<u64>> {
+    Json(registry.handles())
+}
+
+async fn remove_robot(State(registry): State<Arc<RobotRegistry>>, Path(handle): Path<u64>) -> StatusCode {
+    if registry.remove(handle) { StatusCode::NO_CONTENT } else { StatusCode::NOT_FOUND }
+}
+
+async fn forward_kinematics(State(registry): State<Arc<RobotRegistry>>, Path(handle): Path<u64>, Json(body): Json<StateBody>) -> Result<Json<ForwardKinematicsReply>, HttpError> {
+    let robot = registry.get(handle).map_err(|e| HttpError(StatusCode::NOT_FOUND, e))?;
+    let link_poses = operations::forward_kinematics(&robot, &body.state).map_err(|e| HttpError(StatusCode::BAD_REQUEST, e))?;
+    let link_poses = link_poses.iter().map(|x| {
+        x.as_ref().map(|pose| {
+            let (position, orientation) = operations::pose_to_slices(pose);
+            PoseReply { position, orientation }
+        })
+    }).collect();
+
+    Ok(Json(ForwardKinematicsReply { link_poses }))
+}
+
+async fn solve_ik(State(registry): State<Arc<RobotRegistry>>, Path(handle): Path<u64>, Json(body): Json<SolveIKBody>) -> Result<Json<SolveIKReply>, HttpError> {
+    let robot = registry.get(handle).map_err(|e| HttpError(StatusCode::NOT_FOUND, e))?;
+    let (solution, objective_value) = run_blocking(move || operations::solve_ik(&robot, &body.init_state, body.link_idx, &body.position, &body.orientation)).await?;
+
+    Ok(Json(SolveIKReply { solution, objective_value }))
+}
+
+async fn check_collision(State(registry): State<Arc<RobotRegistry>>, Path(handle): Path<u64>, Json(body): Json<StateBody>) -> Result<Json<CheckCollisionReply>, HttpError> {
+    let robot = registry.get(handle).map_err(|e| HttpError(StatusCode::NOT_FOUND, e))?;
+    let in_collision = run_blocking(move || operations::check_collision(&robot, &body.state)).await?;
+
+    Ok(Json(CheckCollisionReply { in_collision }))
+}
+
+async fn interpolate_trajectory(Json(body): Json<InterpolateTrajectoryBody>) -> Result<Json<InterpolateTrajectoryReply>, HttpError> {
+    let points = run_blocking(move || operations::interpolate_trajectory(body.waypoints, body.num_points, &body.spline_type)).await?;
+
+    Ok(Json(InterpolateTrajectoryReply { points }))
+}
+
+async fn run_blocking<R, F>(f: F) -> Result<R, HttpError>
+    where R: Send + 'static,
+          F: FnOnce() -> Result<R, String> + Send + 'static
+{
+    tokio::task::spawn_blocking(f).await
+        .map_err(|e| HttpError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| HttpError(StatusCode::BAD_REQUEST, e))
+}
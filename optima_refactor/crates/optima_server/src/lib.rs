@@ -0,0 +1,8 @@
+pub mod robot_registry;
+pub mod operations;
+pub mod grpc;
+pub mod http;
+
+pub mod proto {
+    tonic::include_proto!("optima");
+}
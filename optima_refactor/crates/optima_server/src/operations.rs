@@ -0,0 +1,91 @@
+use ad_trait::differentiable_function::ForwardADMulti;
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+use optima_interpolation::InterpolatorTraitLite;
+use optima_interpolation::splines::{InterpolatingSpline, InterpolatingSplineType};
+use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
+use optima_optimization::open::SimpleOpEnOptimizer;
+use optima_proximity::pair_group_queries::{OParryIntersectGroupArgs, OParryPairSelector, OwnedEmptyParryFilter, OwnedEmptyToProximityQry, OwnedParryIntersectGroupQry};
+use optima_proximity::pair_queries::ParryShapeRep;
+use optima_robotics::robot::ORobotDefault;
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
+use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
+
+/// Transport-independent implementations of the server's operations.  Inputs coming from clients
+/// are validated here and reported as an Err instead of panicking.
+
+type FAD = adfn<8>;
+
+pub fn forward_kinematics(robot: &ORobotDefault, state: &[f64]) -> Result<Vec<Option<Isometry3<f64>>>, String> {
+    check_state_length(robot, state)?;
+    let fk_res = robot.forward_kinematics(&state.to_vec(), None);
+    Ok(fk_res.link_poses().clone())
+}
+
+/// `orientation` is a unit quaternion in [w x y z] format.  Returns the solution along with the
+/// final objective value.
+pub fn solve_ik(robot: &ORobotDefault, init_state: &[f64], link_idx: usize, position: &[f64], orientation: &[f64]) -> Result<(Vec<f64>, f64), String> {
+    check_state_length(robot, init_state)?;
+    check_link_idx(robot, link_idx)?;
+    let goal_pose = pose_from_slices(position, orientation)?;
+
+    let init_state = init_state.to_vec();
+    let db = robot.get_ik_differentiable_block(ForwardADMulti::<FAD>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![link_idx], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0));
+    db.update_ik_pose(0, goal_pose, IKGoalUpdateMode::Absolute);
+
+    let o = SimpleOpEnOptimizer::new(robot.get_dof_lower_bounds(), robot.get_dof_upper_bounds(), 0.001);
+    let res = o.optimize_unconstrained(&init_state, &db);
+
+    Ok((res.x_star().to_vec(), res.f_star()))
+}
+
+pub fn check_collision(robot: &ORobotDefault, state: &[f64]) -> Result<bool, String> {
+    check_state_length(robot, state)?;
+    let q = OwnedParryIntersectGroupQry::new(OParryIntersectGroupArgs::new(ParryShapeRep::Full, ParryShapeRep::Full, true, false));
+    let res = robot.parry_shape_scene_self_query(&state.to_vec(), &q, &OParryPairSelector::HalfPairs, false);
+    Ok(res.intersect())
+}
+
+pub fn interpolate_trajectory(waypoints: Vec<Vec<f64>>, num_points: usize, spline_type: &str) -> Result<Vec<Vec<f64>>, String> {
+    let spline_type = parse_spline_type(spline_type)?;
+    if waypoints.len() < 2 { return Err("at least two waypoints are required".to_string()); }
+    let dim = waypoints[0].len();
+    if waypoints.iter().any(|x| x.len() != dim) { return Err("all waypoints must have the same length".to_string()); }
+
+    let spline = InterpolatingSpline::new(waypoints, spline_type);
+    Ok(spline.interpolate_points_by_num_points(num_points))
+}
+
+pub fn parse_spline_type(spline_type: &str) -> Result<InterpolatingSplineType, String> {
+    match spline_type {
+        "linear" => { Ok(InterpolatingSplineType::Linear) }
+        "quadratic" => { Ok(InterpolatingSplineType::Quadratic) }
+        "hermite_cubic" => { Ok(InterpolatingSplineType::HermiteCubic) }
+        "natural_cubic" => { Ok(InterpolatingSplineType::NaturalCubic) }
+        "bezier_cubic" => { Ok(InterpolatingSplineType::BezierCubic) }
+        _ => { Err(format!("unsupported spline type {}", spline_type)) }
+    }
+}
+
+pub fn pose_from_slices(position: &[f64], orientation: &[f64]) -> Result<Isometry3<f64>, String> {
+    if position.len() != 3 { return Err(format!("position must have length 3, got {}", position.len())); }
+    if orientation.len() != 4 { return Err(format!("orientation must have length 4, got {}", orientation.len())); }
+    Ok(Isometry3::from_parts(Translation3::new(position[0], position[1], position[2]), UnitQuaternion::from_quaternion(Quaternion::new(orientation[0], orientation[1], orientation[2], orientation[3]))))
+}
+
+/// Returns the position and the [w x y z] orientation of the given pose.
+pub fn pose_to_slices(pose: &Isometry3<f64>) -> ([f64; 3], [f64; 4]) {
+    let t = &pose.translation.vector;
+    let q = pose.rotation.quaternion();
+    ([t[0], t[1], t[2]], [q.w, q.i, q.j, q.k])
+}
+
+fn check_state_length(robot: &ORobotDefault, state: &[f64]) -> Result<(), String> {
+    if state.len() != robot.num_dofs() { return Err(format!("expected a state of length {}, got {}", robot.num_dofs(), state.len())); }
+    Ok(())
+}
+
+fn check_link_idx(robot: &ORobotDefault, link_idx: usize) -> Result<(), String> {
+    if link_idx >= robot.links().len() { return Err(format!("link idx {} is out of range", link_idx)); }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use optima_robotics::robot::ORobotDefault;
+
+/// Robots loaded by remote clients, referred to by opaque integer handles.
+pub struct RobotRegistry {
+    robots: RwLock<HashMap<u64, Arc<ORobotDefault>>>,
+    next_handle: AtomicU64
+}
+impl RobotRegistry {
+    pub fn new() -> Self {
+        Self { robots: RwLock::new(HashMap::new()), next_handle: AtomicU64::new(1) }
+    }
+    /// Loads the robot and returns its handle.  Loading errors (e.g., an unknown robot name) are
+    /// returned as an Err rather than bringing down the server.
+    pub fn load(&self, robot_name: &str, from_urdf: bool) -> Result<u64, String> {
+        let robot_name_ = robot_name.to_string();
+        let robot = std::panic::catch_unwind(AssertUnwindSafe(move || {
+            if from_urdf { ORobotDefault::from_urdf(&robot_name_) } else { ORobotDefault::load_from_saved_robot(&robot_name_) }
+        })).map_err(|_| format!("could not load robot {}", robot_name))??;
+
+        let handle = self.next_handle.fetch_add(1, Ordering::SeqCst);
+        self.robots.write().unwrap().insert(handle, Arc::new(robot));
+        Ok(handle)
+    }
+    pub fn get(&self, robot_handle: u64) -> Result<Arc<ORobotDefault>, String> {
+        self.robots.read().unwrap().get(&robot_handle).cloned().ok_or(format!("no robot with handle {}", robot_handle))
+    }
+    /// Returns true if a robot was removed.
+    pub fn remove(&self, robot_handle: u64) -> bool {
+        self.robots.write().unwrap().remove(&robot_handle).is_some()
+    }
+    pub fn handles(&self) -> Vec<u64> {
+        self.robots.read().unwrap().keys().cloned().collect()
+    }
+}
+impl Default for RobotRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use ad_trait::differentiable_function::ForwardADMulti;
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
+use optima_optimization::open::SimpleOpEnOptimizer;
+use optima_proximity::pair_group_queries::{OwnedEmptyParryFilter, OwnedEmptyToProximityQry};
+use optima_error::OptimaError;
+use optima_robotics::robot::ORobotDefault;
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
+use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
+use crate::{OSharedMemoryChannelPaths, OSharedMemoryRingBuffer, OSHM_IK_GOAL_SLOT_LENGTH};
+
+type FAD = adfn<8>;
+
+/// Serves IK requests written into the `ik_goals` ring buffer by an external controller.  Each
+/// solve is warm started from the most recent entry in the `joint_states` ring buffer, and the
+/// result is written into the `ik_solutions` ring buffer.
+pub struct OSharedMemoryIKServer {
+    robot: ORobotDefault,
+    joint_states: OSharedMemoryRingBuffer,
+    ik_goals: OSharedMemoryRingBuffer,
+    ik_solutions: OSharedMemoryRingBuffer
+}
+impl OSharedMemoryIKServer {
+    /// Creates all three ring buffers, each with `num_slots` slots.
+    pub fn new(robot: ORobotDefault, paths: &OSharedMemoryChannelPaths, num_slots: usize) -> Result<Self, OptimaError> {
+        let num_dofs = robot.num_dofs();
+        let joint_states = OSharedMemoryRingBuffer::create(&paths.joint_states, num_slots, num_dofs)?;
+        let ik_goals = OSharedMemoryRingBuffer::create(&paths.ik_goals, num_slots, OSHM_IK_GOAL_SLOT_LENGTH)?;
+        let ik_solutions = OSharedMemoryRingBuffer::create(&paths.ik_solutions, num_slots, num_dofs + 2)?;
+
+        Ok(Self { robot, joint_states, ik_goals, ik_solutions })
+    }
+    /// Blocks, serving requests until `stop` is set.  When there is nothing to do, the thread
+    /// sleeps for `idle_sleep` between polls.  Requests are skipped (with a warning) if their link
+    /// index is invalid or if the `joint_states` file was re-created with slots that do not hold
+    /// exactly num_dofs values.
+    pub fn run(&self, stop: &AtomicBool, idle_sleep: Duration) -> Result<(), OptimaError> {
+        let num_dofs = self.robot.num_dofs();
+        let o = SimpleOpEnOptimizer::new(self.robot.get_dof_lower_bounds(), self.robot.get_dof_upper_bounds(), 0.001);
+        let mut blocks = HashMap::new();
+        let mut last_goal_count = self.ik_goals.write_count();
+        let mut init_state = vec![0.0; num_dofs];
+        let mut solution_slot = vec![0.0; num_dofs + 2];
+
+        while !stop.load(Ordering::Relaxed) {
+            let goals = self.ik_goals.read_since(last_goal_count);
+            if goals.is_empty() {
+                std::thread::sleep(idle_sleep);
+                continue;
+            }
+
+            for (goal_count, goal) in goals {
+                last_goal_count = goal_count;
+
+                let link_idx = goal[0] as usize;
+                if !(goal[0] >= 0.0) || link_idx >= self.robot.links().len() {
+                    tracing::warn!(goal_count, link_idx = goal[0], "skipping ik goal with an invalid link index");
+                    continue;
+                }
+                let joint_states_slot_length = self.joint_states.header_slot_length();
+                if joint_states_slot_length != num_dofs {
+                    tracing::warn!(goal_count, joint_states_slot_length, num_dofs, "skipping ik goal since joint_states slots do not match the robot's number of dofs");
+                    continue;
+                }
+                let goal_pose = Isometry3::from_parts(Translation3::new(goal[1], goal[2], goal[3]), UnitQuaternion::from_quaternion(Quaternion::new(goal[4], goal[5], goal[6], goal[7])));
+
+                self.joint_states.read_latest_into(&mut init_state)?;
+
+                let db = blocks.entry(link_idx).or_insert_with(|| {
+                    self.robot.get_ik_differentiable_block(ForwardADMulti::<FAD>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![link_idx], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0))
+                });
+                db.update_ik_pose(0, goal_pose, IKGoalUpdateMode::Absolute);
+                db.update_prev_states(init_state.clone());
+
+                let res = o.optimize_unconstrained(&init_state, db);
+                solution_slot[0] = goal_count as f64;
+                solution_slot[1] = res.f_star();
+                solution_slot[2..].copy_from_slice(res.x_star());
+                self.ik_solutions.write(&solution_slot)?;
+            }
+        }
+
+        Ok(())
+    }
+    #[inline(always)]
+    pub fn joint_states(&self) -> &OSharedMemoryRingBuffer {
+        &self.joint_states
+    }
+    #[inline(always)]
+    pub fn ik_goals(&self) -> &OSharedMemoryRingBuffer {
+        &self.ik_goals
+    }
+    #[inline(always)]
+    pub fn ik_solutions(&self) -> &OSharedMemoryRingBuffer {
+        &self.ik_solutions
+    }
+}
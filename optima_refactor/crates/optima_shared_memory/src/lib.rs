@@ -0,0 +1,343 @@
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use memmap2::MmapMut;
+use optima_error::OptimaError;
+
+pub mod ik_server;
+
+/// A single-writer, multi-reader ring buffer of fixed-length f64 slots living in a memory-mapped
+/// file (e.g., under /dev/shm on linux), so that a separate process such as a real-time controller
+/// can exchange joint states with Optima at kHz rates without any serialization.
+///
+/// Memory layout (all values little-endian, offsets in bytes):
+///
+/// ```text
+/// header (64 bytes):
+///   0   u64  magic (OSHM_MAGIC)
+///   8   u32  version (OSHM_VERSION)
+///   12  u32  num_slots
+///   16  u32  slot_length (number of f64 values per slot)
+///   20  u32  padding
+///   24  u64  write_count (atomic; total number of completed writes)
+///   32  ..   reserved
+/// slot i (8 + 8 * slot_length bytes each, starting at 64 + i * slot size):
+///   0   u64  sequence (atomic; odd while the slot is being written)
+///   8   f64  values[slot_length]
+/// ```
+///
+/// Write `n` (zero-indexed) goes into slot `n % num_slots`.  Readers use the per-slot sequence
+/// number as a seqlock: a read is valid if the sequence is even and unchanged before and after
+/// copying the values.
+pub struct OSharedMemoryRingBuffer {
+    mmap: MmapMut,
+    path: PathBuf,
+    num_slots: usize,
+    slot_length: usize
+}
+impl OSharedMemoryRingBuffer {
+    /// Creates (or truncates) the backing file and initializes the header.  Returns an error if
+    /// `num_slots` is zero or either size does not fit in the header's u32 fields.
+    pub fn create<P: AsRef<Path>>(path: P, num_slots: usize, slot_length: usize) -> Result<Self, OptimaError> {
+        let path = path.as_ref();
+        if num_slots == 0 { return Err(OptimaError::InvalidInput("a shared memory ring buffer needs at least one slot".to_string())); }
+        let num_slots_u32 = u32::try_from(num_slots).map_err(|_| OptimaError::InvalidInput(format!("num_slots {} does not fit in a u32", num_slots)))?;
+        let slot_length_u32 = u32::try_from(slot_length).map_err(|_| OptimaError::InvalidInput(format!("slot_length {} does not fit in a u32", slot_length)))?;
+        let file_size = Self::file_size(num_slots, slot_length).ok_or(OptimaError::InvalidInput(format!("a ring buffer with {} slots of length {} is too large", num_slots, slot_length)))?;
+
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path).map_err(|e| OptimaError::new_file_io(path.display(), e))?;
+        file.set_len(file_size as u64).map_err(|e| OptimaError::new_file_io(path.display(), e))?;
+        let mut mmap = unsafe { MmapMut::map_mut(&file) }.map_err(|e| OptimaError::new_file_io(path.display(), e))?;
+
+        mmap[0..8].copy_from_slice(&OSHM_MAGIC.to_le_bytes());
+        mmap[8..12].copy_from_slice(&OSHM_VERSION.to_le_bytes());
+        mmap[12..16].copy_from_slice(&num_slots_u32.to_le_bytes());
+        mmap[16..20].copy_from_slice(&slot_length_u32.to_le_bytes());
+        mmap.flush().map_err(|e| OptimaError::new_file_io(path.display(), e))?;
+
+        Ok(Self { mmap, path: path.to_path_buf(), num_slots, slot_length })
+    }
+    /// Opens a ring buffer previously created by this or another process.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, OptimaError> {
+        let path = path.as_ref();
+        let invalid = |message: &str| OptimaError::new_file_io(path.display(), message);
+        let file = OpenOptions::new().read(true).write(true).open(path).map_err(|e| OptimaError::new_file_io(path.display(), e))?;
+        let mmap = unsafe { MmapMut::map_mut(&file) }.map_err(|e| OptimaError::new_file_io(path.display(), e))?;
+        if mmap.len() < OSHM_HEADER_SIZE { return Err(invalid("file is too small to be a shared memory ring buffer")); }
+
+        if Self::header_u64(&mmap, 0) != OSHM_MAGIC { return Err(invalid("file is not a shared memory ring buffer")); }
+        let version = Self::header_u32(&mmap, 8);
+        if version != OSHM_VERSION { return Err(invalid(&format!("unsupported shared memory ring buffer version {}", version))); }
+        let num_slots = Self::header_u32(&mmap, 12) as usize;
+        let slot_length = Self::header_u32(&mmap, 16) as usize;
+        if num_slots == 0 { return Err(invalid("shared memory ring buffer has no slots")); }
+        match Self::file_size(num_slots, slot_length) {
+            Some(size) if size <= mmap.len() => { }
+            _ => { return Err(invalid("shared memory ring buffer is truncated")); }
+        }
+
+        Ok(Self { mmap, path: path.to_path_buf(), num_slots, slot_length })
+    }
+    /// Only one process (and thread) should write to a given ring buffer.  Returns an error (and
+    /// writes nothing) if `values` is not exactly one slot long.
+    pub fn write(&self, values: &[f64]) -> Result<(), OptimaError> {
+        self.check_slot_length(values.len())?;
+        let n = self.write_count_atomic().load(Ordering::Acquire);
+        let slot_idx = (n % self.num_slots as u64) as usize;
+        let sequence = self.slot_sequence_atomic(slot_idx);
+
+        let s = sequence.load(Ordering::Relaxed);
+        sequence.store(s | 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        let data = self.slot_data_ptr(slot_idx);
+        for (i, v) in values.iter().enumerate() {
+            unsafe { std::ptr::write_volatile(data.add(i), *v); }
+        }
+        sequence.store((s | 1) + 1, Ordering::Release);
+
+        self.write_count_atomic().store(n + 1, Ordering::Release);
+        Ok(())
+    }
+    /// Copies the most recent entry into `out` without allocating.  Returns its write index (the
+    /// value of write_count just after it was written), or None if nothing has been written yet or
+    /// the writer kept overwriting the slot while it was being read.  Returns an error if `out` is
+    /// not exactly one slot long.
+    pub fn read_latest_into(&self, out: &mut [f64]) -> Result<Option<u64>, OptimaError> {
+        self.check_slot_length(out.len())?;
+        let n = self.write_count();
+        if n == 0 { return Ok(None); }
+        let slot_idx = ((n - 1) % self.num_slots as u64) as usize;
+        if self.read_slot_into(slot_idx, out) { Ok(Some(n)) } else { Ok(None) }
+    }
+    pub fn read_latest(&self) -> Option<(u64, Vec<f64>)> {
+        let mut out = vec![0.0; self.slot_length];
+        self.read_latest_into(&mut out).expect("error").map(|n| (n, out))
+    }
+    /// All entries written after `last_write_count` that are still in the buffer, oldest first.
+    pub fn read_since(&self, last_write_count: u64) -> Vec<(u64, Vec<f64>)> {
+        let n = self.write_count();
+        let start = last_write_count.max(n.saturating_sub(self.num_slots as u64));
+        let mut out = vec![];
+        for i in start..n {
+            let mut values = vec![0.0; self.slot_length];
+            if self.read_slot_into((i % self.num_slots as u64) as usize, &mut values) { out.push((i + 1, values)); }
+        }
+        out
+    }
+    /// The slot length currently stored in the file's header.  This differs from `slot_length`
+    /// if another process has re-created the file since it was opened.
+    #[inline(always)]
+    pub fn header_slot_length(&self) -> usize {
+        Self::header_u32(&self.mmap, 16) as usize
+    }
+    #[inline(always)]
+    pub fn write_count(&self) -> u64 {
+        self.write_count_atomic().load(Ordering::Acquire)
+    }
+    #[inline(always)]
+    pub fn num_slots(&self) -> usize {
+        self.num_slots
+    }
+    #[inline(always)]
+    pub fn slot_length(&self) -> usize {
+        self.slot_length
+    }
+    #[inline(always)]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    fn read_slot_into(&self, slot_idx: usize, out: &mut [f64]) -> bool {
+        let sequence = self.slot_sequence_atomic(slot_idx);
+        let data = self.slot_data_ptr(slot_idx);
+        for _ in 0..OSHM_MAX_READ_ATTEMPTS {
+            let s1 = sequence.load(Ordering::Acquire);
+            if s1 % 2 == 1 { std::hint::spin_loop(); continue; }
+            for (i, v) in out.iter_mut().enumerate() {
+                *v = unsafe { std::ptr::read_volatile(data.add(i)) };
+            }
+            fence(Ordering::Acquire);
+            let s2 = sequence.load(Ordering::Relaxed);
+            if s1 == s2 { return true; }
+        }
+        false
+    }
+    fn check_slot_length(&self, length: usize) -> Result<(), OptimaError> {
+        if length == self.slot_length { Ok(()) } else { Err(OptimaError::InvalidInput(format!("expected {} values (one slot), got {}", self.slot_length, length))) }
+    }
+    #[inline(always)]
+    fn slot_size(slot_length: usize) -> usize {
+        8 + 8 * slot_length
+    }
+    fn file_size(num_slots: usize, slot_length: usize) -> Option<usize> {
+        slot_length.checked_mul(8)?.checked_add(8)?.checked_mul(num_slots)?.checked_add(OSHM_HEADER_SIZE)
+    }
+    #[inline(always)]
+    fn header_u32(mmap: &MmapMut, offset: usize) -> u32 {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&mmap[offset..offset + 4]);
+        u32::from_le_bytes(bytes)
+    }
+    #[inline(always)]
+    fn header_u64(mmap: &MmapMut, offset: usize) -> u64 {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&mmap[offset..offset + 8]);
+        u64::from_le_bytes(bytes)
+    }
+    #[inline(always)]
+    fn write_count_atomic(&self) -> &AtomicU64 {
+        unsafe { &*(self.mmap.as_ptr().add(24) as *const AtomicU64) }
+    }
+    #[inline(always)]
+    fn slot_sequence_atomic(&self, slot_idx: usize) -> &AtomicU64 {
+        let offset = OSHM_HEADER_SIZE + slot_idx * Self::slot_size(self.slot_length);
+        unsafe { &*(self.mmap.as_ptr().add(offset) as *const AtomicU64) }
+    }
+    #[inline(always)]
+    fn slot_data_ptr(&self, slot_idx: usize) -> *mut f64 {
+        let offset = OSHM_HEADER_SIZE + slot_idx * Self::slot_size(self.slot_length) + 8;
+        unsafe { self.mmap.as_ptr().add(offset) as *mut f64 }
+    }
+}
+unsafe impl Send for OSharedMemoryRingBuffer { }
+unsafe impl Sync for OSharedMemoryRingBuffer { }
+
+pub const OSHM_MAGIC: u64 = 0x4f5054494d415348; // "OPTIMASH"
+pub const OSHM_VERSION: u32 = 1;
+pub const OSHM_HEADER_SIZE: usize = 64;
+const OSHM_MAX_READ_ATTEMPTS: usize = 64;
+
+/// The ring buffers used for exchanging data with an external controller, named
+/// `<prefix>_joint_states`, `<prefix>_ik_goals`, and `<prefix>_ik_solutions` inside the given
+/// directory.
+///
+/// - joint_states (controller -> Optima): num_dofs values.
+/// - ik_goals (controller -> Optima): [link_idx, x, y, z, qw, qx, qy, qz].
+/// - ik_solutions (Optima -> controller): [goal write index, cost, num_dofs solution values].
+#[derive(Clone, Debug)]
+pub struct OSharedMemoryChannelPaths {
+    pub joint_states: PathBuf,
+    pub ik_goals: PathBuf,
+    pub ik_solutions: PathBuf
+}
+impl OSharedMemoryChannelPaths {
+    pub fn new<P: AsRef<Path>>(dir: P, prefix: &str) -> Self {
+        let dir = dir.as_ref();
+        Self {
+            joint_states: dir.join(format!("{}_joint_states", prefix)),
+            ik_goals: dir.join(format!("{}_ik_goals", prefix)),
+            ik_solutions: dir.join(format!("{}_ik_solutions", prefix)),
+        }
+    }
+    /// Uses /dev/shm when available (so the buffers never touch disk), otherwise the temp directory.
+    pub fn new_default(prefix: &str) -> Self {
+        let dev_shm = Path::new("/dev/shm");
+        if dev_shm.is_dir() { Self::new(dev_shm, prefix) } else { Self::new(std::env::temp_dir(), prefix) }
+    }
+}
+
+pub const OSHM_IK_GOAL_SLOT_LENGTH: usize = 8;
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use super::*;
+
+    fn test_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("optima_shared_memory_test_{}_{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn invalid_sizes_and_lengths_are_errors() {
+        let path = test_path("invalid");
+        assert!(OSharedMemoryRingBuffer::create(&path, 0, 4).is_err());
+        assert!(OSharedMemoryRingBuffer::create(&path, usize::MAX, 4).is_err());
+
+        let buffer = OSharedMemoryRingBuffer::create(&path, 2, 4).expect("error");
+        assert!(buffer.write(&[1.0; 5]).is_err());
+        assert!(buffer.write(&[1.0; 3]).is_err());
+        assert_eq!(buffer.write_count(), 0);
+        assert!(buffer.read_latest_into(&mut [0.0; 5]).is_err());
+        assert_eq!(buffer.read_latest_into(&mut [0.0; 4]).expect("error"), None);
+
+        std::fs::write(&path, vec![0u8; 128]).expect("error");
+        assert!(OSharedMemoryRingBuffer::open(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn wraps_around_and_keeps_the_most_recent_slots() {
+        let path = test_path("wraparound");
+        let buffer = OSharedMemoryRingBuffer::create(&path, 4, 3).expect("error");
+        for i in 0..10 { buffer.write(&[i as f64; 3]).expect("error"); }
+
+        assert_eq!(buffer.write_count(), 10);
+        assert_eq!(buffer.read_latest(), Some((10, vec![9.0; 3])));
+        let all = buffer.read_since(0);
+        assert_eq!(all.iter().map(|x| x.0).collect::<Vec<u64>>(), vec![7, 8, 9, 10]);
+        assert!(all.iter().all(|(n, values)| values == &vec![(*n - 1) as f64; 3]));
+        assert_eq!(buffer.read_since(8).iter().map(|x| x.0).collect::<Vec<u64>>(), vec![9, 10]);
+        assert!(buffer.read_since(10).is_empty());
+
+        let reopened = OSharedMemoryRingBuffer::open(&path).expect("error");
+        assert_eq!((reopened.num_slots(), reopened.slot_length(), reopened.header_slot_length()), (4, 3, 3));
+        assert_eq!(reopened.read_latest(), Some((10, vec![9.0; 3])));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn slots_being_written_are_not_read() {
+        let path = test_path("torn");
+        let buffer = OSharedMemoryRingBuffer::create(&path, 1, 2).expect("error");
+        buffer.write(&[1.0, 1.0]).expect("error");
+
+        // what a reader sees if the writer stops halfway through overwriting the slot.
+        let sequence = buffer.slot_sequence_atomic(0);
+        let s = sequence.load(Ordering::Relaxed);
+        sequence.store(s | 1, Ordering::Relaxed);
+        unsafe { std::ptr::write_volatile(buffer.slot_data_ptr(0), 2.0); }
+        let mut out = [0.0; 2];
+        assert_eq!(buffer.read_latest_into(&mut out).expect("error"), None);
+
+        sequence.store((s | 1) + 1, Ordering::Release);
+        assert_eq!(buffer.read_latest_into(&mut out).expect("error"), Some(1));
+        assert_eq!(out, [2.0, 1.0]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn concurrent_reads_are_never_torn() {
+        let path = test_path("concurrent");
+        let writer = OSharedMemoryRingBuffer::create(&path, 3, 64).expect("error");
+        let reader = OSharedMemoryRingBuffer::open(&path).expect("error");
+        let done = Arc::new(AtomicBool::new(false));
+
+        let done_clone = done.clone();
+        let reader_thread = std::thread::spawn(move || {
+            let mut out = vec![0.0; 64];
+            let mut last = 0;
+            let mut num_reads = 0;
+            loop {
+                let finished = done_clone.load(Ordering::Acquire);
+                if let Some(n) = reader.read_latest_into(&mut out).expect("error") {
+                    assert!(out.iter().all(|x| *x == out[0]), "torn read at write {}", n);
+                    // the writer may have lapped the buffer since write_count was read, so the slot can
+                    // hold a later write that went into the same slot.
+                    assert!(out[0] >= (n - 1) as f64 && (out[0] as u64) % 3 == (n - 1) % 3);
+                    assert!(n >= last);
+                    last = n;
+                    num_reads += 1;
+                }
+                if finished { break; }
+            }
+            num_reads
+        });
+
+        for i in 0..200_000 { writer.write(&vec![i as f64; 64]).expect("error"); }
+        done.store(true, Ordering::Release);
+        assert!(reader_thread.join().expect("error") > 0);
+        assert_eq!(writer.read_latest().expect("error").1, vec![199_999.0; 64]);
+        let _ = std::fs::remove_file(&path);
+    }
+}
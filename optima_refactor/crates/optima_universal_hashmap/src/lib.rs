@@ -69,15 +69,15 @@ impl<'de, K : Serialize + DeserializeOwned + Hash + Eq, V: Serialize + Deseriali
         let mut out = AHashMapWrapper { hashmap: AHashMap::new() };
 
         'l: loop {
-            let element: Option<(String, String)> = seq.next_element().expect("error");
+            let element: Option<(String, String)> = seq.next_element()?;
             match element {
                 None => { break 'l; }
                 Some(el) => {
                     let k_str = el.0;
                     let v_str = el.1;
 
-                    let k = K::from_json_string(&k_str);
-                    let v = V::from_json_string(&v_str);
+                    let k = K::from_json_string(&k_str).map_err(serde::de::Error::custom)?;
+                    let v = V::from_json_string(&v_str).map_err(serde::de::Error::custom)?;
                     out.hashmap.insert(k, v);
                 }
             }
@@ -99,14 +99,14 @@ impl<'de, K : Serialize + DeserializeOwned + Hash + Eq, T: AD> Visitor<'de> for
         let mut out = AHashMapWrapper { hashmap: AHashMap::new() };
 
         'l: loop {
-            let element: Option<(String, f64)> = seq.next_element().expect("error");
+            let element: Option<(String, f64)> = seq.next_element()?;
             match element {
                 None => { break 'l; }
                 Some(el) => {
                     let k_str = el.0;
                     let f = el.1;
 
-                    let k = K::from_json_string(&k_str);
+                    let k = K::from_json_string(&k_str).map_err(serde::de::Error::custom)?;
                     let v = T::constant(f);
                     out.hashmap.insert(k, v);
                 }
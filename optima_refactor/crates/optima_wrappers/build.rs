@@ -0,0 +1,25 @@
+fn main() {
+    #[cfg(feature = "c_header")]
+    generate_c_header();
+}
+
+#[cfg(feature = "c_header")]
+fn generate_c_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("error");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).expect("error");
+
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src/ffi_wrappers");
+
+    // build scripts must not write into the source tree, so the header goes to OUT_DIR.  Use the
+    // cbindgen cli (see cbindgen.toml) to refresh the checked in include/optima.h.
+    let out_dir = std::env::var("OUT_DIR").expect("error");
+    let header_path = format!("{}/optima.h", out_dir);
+    match cbindgen::generate_with_config(&crate_dir, config) {
+        Ok(bindings) => {
+            bindings.write_to_file(&header_path);
+            println!("cargo:rustc-env=OPTIMA_C_HEADER={}", header_path);
+        }
+        Err(e) => { println!("cargo:warning=could not generate optima.h: {}", e); }
+    }
+}
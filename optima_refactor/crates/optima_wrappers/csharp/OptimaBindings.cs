@@ -0,0 +1,149 @@
+// P/Invoke bindings for the flattened optima_robot_* C abi in optima_wrappers.
+//
+// Build the native library with `cargo build --release -p optima_wrappers` and put
+// liboptima_wrappers.so / liboptima_wrappers.dylib / optima_wrappers.dll next to the application
+// (or, for Unity, in the project's Assets/Plugins folder along with OptimaUnity.cs).  This file only
+// depends on the .NET base library.
+
+using System;
+using System.Runtime.InteropServices;
+using System.Text;
+
+namespace Optima
+{
+    public enum OptimaStatus
+    {
+        Ok = 0,
+        NullPointer = 1,
+        InvalidArgument = 2,
+        Panic = 3,
+        NotFound = 4,
+        FileIO = 5,
+        Serialization = 6,
+        Internal = 7
+    }
+
+    /// Position and unit quaternion in Optima's z-up, right-handed world frame.
+    [StructLayout(LayoutKind.Sequential)]
+    public struct OptimaPose
+    {
+        public double px, py, pz;
+        public double qw, qx, qy, qz;
+
+        /// Position in a y-up, left-handed frame (the convention of Unity, and of ROS# / Unity
+        /// Robotics Hub).  Does not depend on any engine's types; see OptimaUnity.cs for Unity.
+        public (double x, double y, double z) PositionYUpLeftHanded => (-py, pz, px);
+        /// Rotation as (x, y, z, w) in the same frame as `PositionYUpLeftHanded`.
+        public (double x, double y, double z, double w) RotationYUpLeftHanded => (qy, -qz, -qx, qw);
+
+        public static OptimaPose FromYUpLeftHanded(double x, double y, double z, double rx, double ry, double rz, double rw)
+        {
+            return new OptimaPose
+            {
+                px = z, py = -x, pz = y,
+                qw = rw, qx = -rz, qy = rx, qz = -ry
+            };
+        }
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct OptimaLinkPose
+    {
+        public int has_pose;
+        public OptimaPose pose;
+    }
+
+    internal static class Native
+    {
+        private const string Lib = "optima_wrappers";
+
+        [DllImport(Lib)] public static extern IntPtr last_error_message();
+        [DllImport(Lib)] public static extern OptimaStatus optima_robot_load([MarshalAs(UnmanagedType.LPUTF8Str)] string robotName, out IntPtr outHandle);
+        [DllImport(Lib)] public static extern OptimaStatus optima_robot_free(IntPtr handle);
+        [DllImport(Lib)] public static extern OptimaStatus optima_robot_num_dofs(IntPtr handle, out int outNumDofs);
+        [DllImport(Lib)] public static extern OptimaStatus optima_robot_num_links(IntPtr handle, out int outNumLinks);
+        [DllImport(Lib)] public static extern OptimaStatus optima_robot_link_name(IntPtr handle, int linkIdx, byte[] buffer, int bufferLength);
+        [DllImport(Lib)] public static extern OptimaStatus optima_robot_dof_bounds(IntPtr handle, double[] outLower, double[] outUpper);
+        [DllImport(Lib)] public static extern OptimaStatus optima_robot_forward_kinematics(IntPtr handle, double[] state, int stateLength, [Out] OptimaLinkPose[] outLinkPoses, int outLength);
+        [DllImport(Lib)] public static extern OptimaStatus optima_robot_link_pose(IntPtr handle, double[] state, int stateLength, int linkIdx, out OptimaPose outPose);
+        [DllImport(Lib)] public static extern OptimaStatus optima_robot_solve_ik(IntPtr handle, double[] initState, int stateLength, int linkIdx, OptimaPose goalPose, double[] outSolution, out double outCost);
+
+        public static void Check(OptimaStatus status)
+        {
+            if (status != OptimaStatus.Ok)
+            {
+                throw new OptimaException(status, Marshal.PtrToStringUTF8(last_error_message()));
+            }
+        }
+    }
+
+    public class OptimaException : Exception
+    {
+        public OptimaStatus Status { get; }
+        public OptimaException(OptimaStatus status, string message) : base($"{status}: {message}") { Status = status; }
+    }
+
+    public sealed class OptimaRobot : IDisposable
+    {
+        private IntPtr _handle;
+
+        public int NumDofs { get; }
+        public int NumLinks { get; }
+        public string[] LinkNames { get; }
+        public double[] LowerBounds { get; }
+        public double[] UpperBounds { get; }
+
+        public OptimaRobot(string robotName)
+        {
+            Native.Check(Native.optima_robot_load(robotName, out _handle));
+            Native.Check(Native.optima_robot_num_dofs(_handle, out int numDofs));
+            Native.Check(Native.optima_robot_num_links(_handle, out int numLinks));
+            NumDofs = numDofs;
+            NumLinks = numLinks;
+
+            LinkNames = new string[numLinks];
+            var buffer = new byte[1024];
+            for (int i = 0; i < numLinks; i++)
+            {
+                Native.Check(Native.optima_robot_link_name(_handle, i, buffer, buffer.Length));
+                LinkNames[i] = Encoding.UTF8.GetString(buffer, 0, Array.IndexOf(buffer, (byte)0));
+            }
+
+            LowerBounds = new double[numDofs];
+            UpperBounds = new double[numDofs];
+            Native.Check(Native.optima_robot_dof_bounds(_handle, LowerBounds, UpperBounds));
+        }
+
+        public OptimaLinkPose[] ForwardKinematics(double[] state)
+        {
+            var outPoses = new OptimaLinkPose[NumLinks];
+            Native.Check(Native.optima_robot_forward_kinematics(_handle, state, state.Length, outPoses, outPoses.Length));
+            return outPoses;
+        }
+
+        public OptimaPose LinkPose(double[] state, int linkIdx)
+        {
+            Native.Check(Native.optima_robot_link_pose(_handle, state, state.Length, linkIdx, out OptimaPose pose));
+            return pose;
+        }
+
+        public double[] SolveIK(double[] initState, int linkIdx, OptimaPose goalPose, out double cost)
+        {
+            var solution = new double[NumDofs];
+            Native.Check(Native.optima_robot_solve_ik(_handle, initState, initState.Length, linkIdx, goalPose, solution, out cost));
+            return solution;
+        }
+
+        public void Dispose()
+        {
+            if (_handle != IntPtr.Zero)
+            {
+                Native.optima_robot_free(_handle);
+                _handle = IntPtr.Zero;
+            }
+            GC.SuppressFinalize(this);
+        }
+
+        ~OptimaRobot() { Dispose(); }
+    }
+}
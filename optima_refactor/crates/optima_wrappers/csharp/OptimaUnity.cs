@@ -0,0 +1,29 @@
+// Unity conversions for the types in OptimaBindings.cs.  Only compiled inside Unity, so the
+// bindings themselves stay usable from any .NET application.
+
+#if UNITY_5_3_OR_NEWER
+using UnityEngine;
+
+namespace Optima
+{
+    public static class OptimaUnity
+    {
+        public static Vector3 UnityPosition(this OptimaPose pose)
+        {
+            var (x, y, z) = pose.PositionYUpLeftHanded;
+            return new Vector3((float)x, (float)y, (float)z);
+        }
+
+        public static Quaternion UnityRotation(this OptimaPose pose)
+        {
+            var (x, y, z, w) = pose.RotationYUpLeftHanded;
+            return new Quaternion((float)x, (float)y, (float)z, (float)w);
+        }
+
+        public static OptimaPose ToOptimaPose(Vector3 position, Quaternion rotation)
+        {
+            return OptimaPose.FromYUpLeftHanded(position.x, position.y, position.z, rotation.x, rotation.y, rotation.z, rotation.w);
+        }
+    }
+}
+#endif
@@ -0,0 +1,107 @@
+classdef OptimaRobot < handle
+    % MATLAB wrapper around the optima_mex_* C api in liboptima_wrappers.
+    %
+    %   r = OptimaRobot('ur5');
+    %   T = r.linkPose(zeros(r.NumDofs, 1), 7);      % 4x4 homogeneous transform
+    %   J = r.jacobian(zeros(r.NumDofs, 1), 7);      % 6 x NumDofs
+    %   q = r.solveIK(zeros(r.NumDofs, 1), 7, T);
+    %
+    % Link indices are one-based, as usual in MATLAB.  Build the library with
+    % `cargo build --release -p optima_wrappers` and put it (and optima_mex.h) on the MATLAB path.
+
+    properties (Constant, Access = private)
+        LibName = 'optima_wrappers'
+    end
+
+    properties (SetAccess = private)
+        NumDofs
+        NumLinks
+        LowerBounds
+        UpperBounds
+    end
+
+    properties (Access = private)
+        Handle
+    end
+
+    methods
+        function obj = OptimaRobot(robotName)
+            OptimaRobot.ensureLoaded();
+            handlePtr = libpointer('voidPtrPtr');
+            OptimaRobot.check(calllib(OptimaRobot.LibName, 'optima_mex_load_robot', robotName, handlePtr));
+            obj.Handle = handlePtr.Value;
+
+            numDofsPtr = libpointer('int32Ptr', 0);
+            OptimaRobot.check(calllib(OptimaRobot.LibName, 'optima_mex_num_dofs', obj.Handle, numDofsPtr));
+            obj.NumDofs = double(numDofsPtr.Value);
+
+            numLinksPtr = libpointer('int32Ptr', 0);
+            OptimaRobot.check(calllib(OptimaRobot.LibName, 'optima_mex_num_links', obj.Handle, numLinksPtr));
+            obj.NumLinks = double(numLinksPtr.Value);
+
+            lowerPtr = libpointer('doublePtr', zeros(obj.NumDofs, 1));
+            upperPtr = libpointer('doublePtr', zeros(obj.NumDofs, 1));
+            OptimaRobot.check(calllib(OptimaRobot.LibName, 'optima_mex_dof_bounds', obj.Handle, lowerPtr, upperPtr));
+            obj.LowerBounds = lowerPtr.Value;
+            obj.UpperBounds = upperPtr.Value;
+        end
+
+        function delete(obj)
+            if ~isempty(obj.Handle)
+                calllib(OptimaRobot.LibName, 'optima_mex_free_robot', obj.Handle);
+                obj.Handle = [];
+            end
+        end
+
+        function T = linkPose(obj, q, linkIdx)
+            outPtr = libpointer('doublePtr', zeros(16, 1));
+            OptimaRobot.check(calllib(OptimaRobot.LibName, 'optima_mex_link_pose', obj.Handle, obj.checkState(q), obj.NumDofs, int32(linkIdx - 1), outPtr));
+            T = reshape(outPtr.Value, 4, 4);
+        end
+
+        function T = forwardKinematics(obj, q)
+            % 4x4xNumLinks array.  Links without a pose are filled with NaN.
+            outPtr = libpointer('doublePtr', zeros(16 * obj.NumLinks, 1));
+            OptimaRobot.check(calllib(OptimaRobot.LibName, 'optima_mex_forward_kinematics', obj.Handle, obj.checkState(q), obj.NumDofs, outPtr));
+            T = reshape(outPtr.Value, 4, 4, obj.NumLinks);
+        end
+
+        function J = jacobian(obj, q, linkIdx)
+            outPtr = libpointer('doublePtr', zeros(6 * obj.NumDofs, 1));
+            OptimaRobot.check(calllib(OptimaRobot.LibName, 'optima_mex_jacobian', obj.Handle, obj.checkState(q), obj.NumDofs, int32(linkIdx - 1), outPtr));
+            J = reshape(outPtr.Value, 6, obj.NumDofs);
+        end
+
+        function [q, cost] = solveIK(obj, q0, linkIdx, goalPose)
+            % goalPose is a 4x4 homogeneous transform.
+            solutionPtr = libpointer('doublePtr', zeros(obj.NumDofs, 1));
+            costPtr = libpointer('doublePtr', 0);
+            OptimaRobot.check(calllib(OptimaRobot.LibName, 'optima_mex_solve_ik', obj.Handle, obj.checkState(q0), obj.NumDofs, int32(linkIdx - 1), double(goalPose(:)), solutionPtr, costPtr));
+            q = solutionPtr.Value;
+            cost = costPtr.Value;
+        end
+    end
+
+    methods (Access = private)
+        function q = checkState(obj, q)
+            if numel(q) ~= obj.NumDofs
+                error('OptimaRobot:InvalidState', 'expected a state with %d elements, got %d', obj.NumDofs, numel(q));
+            end
+            q = double(q(:));
+        end
+    end
+
+    methods (Static, Access = private)
+        function ensureLoaded()
+            if ~libisloaded(OptimaRobot.LibName)
+                loadlibrary(OptimaRobot.LibName, 'optima_mex.h');
+            end
+        end
+
+        function check(status)
+            if status ~= 0
+                error('OptimaRobot:Error', 'optima status %d: %s', status, calllib(OptimaRobot.LibName, 'last_error_message'));
+            end
+        end
+    end
+end
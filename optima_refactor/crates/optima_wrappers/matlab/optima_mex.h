@@ -0,0 +1,20 @@
+/* Declarations for the optima_mex_* functions in liboptima_wrappers, used by MATLAB's loadlibrary.
+   Every function returns 0 on success; otherwise, last_error_message() describes the failure.
+   Matrices are column-major and indices are zero-based. */
+
+#ifndef OPTIMA_MEX_H
+#define OPTIMA_MEX_H
+
+const char* last_error_message(void);
+
+int optima_mex_load_robot(const char* robot_name, void** out_robot);
+int optima_mex_free_robot(void* robot);
+int optima_mex_num_dofs(const void* robot, int* out_num_dofs);
+int optima_mex_num_links(const void* robot, int* out_num_links);
+int optima_mex_dof_bounds(const void* robot, double* out_lower, double* out_upper);
+int optima_mex_link_pose(const void* robot, const double* state, int state_length, int link_idx, double* out_pose);
+int optima_mex_forward_kinematics(const void* robot, const double* state, int state_length, double* out_poses);
+int optima_mex_jacobian(const void* robot, const double* state, int state_length, int link_idx, double* out_jacobian);
+int optima_mex_solve_ik(const void* robot, const double* init_state, int state_length, int link_idx, const double* goal_pose, double* out_solution, double* out_cost);
+
+#endif
@@ -0,0 +1,109 @@
+use std::ffi::c_void;
+use std::os::raw::*;
+use std::sync::Arc;
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjectiveTerm;
+use optima_robotics::robotics_optimization::robotics_optimization_custom::{finite_difference_gradient, CustomObjectiveTerm, CustomObjectiveTermHandle};
+use optima_robotics::robotics_optimization::robotics_optimization_ik::DifferentiableBlockIKObjectiveTrait;
+use crate::ffi_wrappers::handles::OptimaIKBlockHandle;
+use crate::ffi_wrappers::ik_solvers::checked_weight;
+use crate::ffi_wrappers::status::{ffi_guard, ffi_out, ffi_ref, ffi_string, FFIError, OptimaStatus};
+
+/// Function table for a cost term implemented in the host language.  `inputs` holds `num_inputs`
+/// doubles (the robot state for ik blocks) and is only valid for the duration of the call.
+///
+/// - `value` is required.
+/// - `value_and_gradient` may be null, in which case the gradient is computed with finite
+///   differences over `value`.  Otherwise it must write `num_inputs` doubles into `out_gradient`.
+/// - `free_user_data` may be null.  It is called once, when the last block using the term is freed.
+///
+/// The callbacks run on whichever thread is optimizing, so `user_data` must be safe to use from
+/// there.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct OptimaCustomObjectiveTermVTable {
+    pub user_data: *mut c_void,
+    pub value: Option<unsafe extern "C" fn(user_data: *mut c_void, inputs: *const c_double, num_inputs: c_int) -> c_double>,
+    pub value_and_gradient: Option<unsafe extern "C" fn(user_data: *mut c_void, inputs: *const c_double, num_inputs: c_int, out_gradient: *mut c_double) -> c_double>,
+    pub free_user_data: Option<unsafe extern "C" fn(user_data: *mut c_void)>
+}
+
+struct HostCustomObjectiveTerm {
+    name: String,
+    vtable: OptimaCustomObjectiveTermVTable
+}
+unsafe impl Send for HostCustomObjectiveTerm { }
+unsafe impl Sync for HostCustomObjectiveTerm { }
+impl CustomObjectiveTerm for HostCustomObjectiveTerm {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn value(&self, inputs: &[f64]) -> f64 {
+        let value = self.vtable.value.expect("error");
+        unsafe { value(self.vtable.user_data, inputs.as_ptr(), inputs.len() as c_int) }
+    }
+    fn value_and_gradient(&self, inputs: &[f64]) -> (f64, Vec<f64>) {
+        match self.vtable.value_and_gradient {
+            None => { (self.value(inputs), finite_difference_gradient(|x| self.value(x), inputs)) }
+            Some(value_and_gradient) => {
+                let mut gradient = vec![0.0; inputs.len()];
+                let value = unsafe { value_and_gradient(self.vtable.user_data, inputs.as_ptr(), inputs.len() as c_int, gradient.as_mut_ptr()) };
+                (value, gradient)
+            }
+        }
+    }
+}
+impl Drop for HostCustomObjectiveTerm {
+    fn drop(&mut self) {
+        if let Some(free_user_data) = self.vtable.free_user_data { unsafe { free_user_data(self.vtable.user_data) } }
+    }
+}
+
+/// Opaque to the host language.  The same term can be added to any number of blocks.
+pub struct OptimaCustomObjectiveTermHandle {
+    term: CustomObjectiveTermHandle
+}
+
+/// `name` is only used for diagnostics.  Ownership of `vtable.user_data` passes to the term.
+#[no_mangle]
+pub unsafe extern "C" fn optima_custom_objective_term_create(name: *const c_char, vtable: OptimaCustomObjectiveTermVTable, out_handle: *mut *mut OptimaCustomObjectiveTermHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        let name = ffi_string(name, "name")?;
+        if vtable.value.is_none() { return Err(FFIError::NullPointer("vtable.value is null".to_string())); }
+        let out_handle = ffi_out(out_handle, "out_handle")?;
+        let term: Arc<dyn CustomObjectiveTerm> = Arc::new(HostCustomObjectiveTerm { name, vtable });
+        *out_handle = Box::into_raw(Box::new(OptimaCustomObjectiveTermHandle { term: CustomObjectiveTermHandle::new_from_arc(term) }));
+        Ok(())
+    })
+}
+
+/// Blocks that the term was added to keep it alive, so this can be called right after adding it.
+#[no_mangle]
+pub unsafe extern "C" fn optima_custom_objective_term_free(handle: *mut OptimaCustomObjectiveTermHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        if !handle.is_null() { let _ = Box::from_raw(handle); }
+        Ok(())
+    })
+}
+
+/// Adds the term to the block's objective with the given weight, or updates its weight if it was
+/// already added.  Returns `InvalidArgument` if the weight is negative or not finite.
+#[no_mangle]
+pub unsafe extern "C" fn optima_ik_block_set_custom_term(differentiable_block: *const OptimaIKBlockHandle, term: *const OptimaCustomObjectiveTermHandle, weight: c_double) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(differentiable_block, "differentiable_block")?;
+        let term = ffi_ref(term, "term")?;
+        let weight = checked_weight(weight, "weight")?;
+        h.block.update_custom_objective_term(term.term.clone(), weight)?;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_ik_block_remove_custom_term(differentiable_block: *const OptimaIKBlockHandle, term: *const OptimaCustomObjectiveTermHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(differentiable_block, "differentiable_block")?;
+        let term = ffi_ref(term, "term")?;
+        h.block.remove_objective_term(CompositeObjectiveTerm::Custom(term.term.id()));
+        Ok(())
+    })
+}
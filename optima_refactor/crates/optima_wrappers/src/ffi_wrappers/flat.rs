@@ -0,0 +1,198 @@
+use std::os::raw::*;
+use ad_trait::differentiable_function::ForwardADMulti;
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+use optima_robotics::robotics_optimization::robotics_optimization_ik_batch::IKBatchSettings;
+use crate::ffi_wrappers::handles::{ffi_robot_free, ffi_robot_load, ffi_robot_num_dofs, ffi_robot_solve_ik, FAD, OptimaRobotHandle};
+use crate::ffi_wrappers::ik_solvers::{checked_link_idx, checked_state};
+use crate::ffi_wrappers::status::{ffi_array, ffi_array_mut, ffi_guard, ffi_out, ffi_ref, ffi_slice, ffi_slice_mut, FFIError, OptimaStatus};
+
+// Flattened C abi meant for P/Invoke (C#/Unity) and similar binding generators: only opaque
+// handles, plain structs, and primitive types appear in signatures.  Every function returns an
+// `OptimaStatus`.
+
+/// Position and [w x y z] unit quaternion, in Optima's z-up right-handed world frame.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OptimaPose {
+    pub px: c_double,
+    pub py: c_double,
+    pub pz: c_double,
+    pub qw: c_double,
+    pub qx: c_double,
+    pub qy: c_double,
+    pub qz: c_double
+}
+impl OptimaPose {
+    pub fn from_isometry(pose: &Isometry3<f64>) -> Self {
+        let t = &pose.translation.vector;
+        let q = pose.rotation.quaternion();
+        Self { px: t[0], py: t[1], pz: t[2], qw: q.w, qx: q.i, qy: q.j, qz: q.k }
+    }
+    pub fn to_isometry(&self) -> Isometry3<f64> {
+        Isometry3::from_parts(Translation3::new(self.px, self.py, self.pz), UnitQuaternion::from_quaternion(Quaternion::new(self.qw, self.qx, self.qy, self.qz)))
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OptimaLinkPose {
+    /// 1 if the link has a pose in the model, 0 otherwise (in which case `pose` is all zeros).
+    pub has_pose: c_int,
+    pub pose: OptimaPose
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_load(robot_name: *const c_char, out_handle: *mut *mut OptimaRobotHandle) -> OptimaStatus {
+    ffi_guard(|| ffi_robot_load(robot_name, out_handle))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_free(handle: *mut OptimaRobotHandle) -> OptimaStatus {
+    ffi_guard(|| ffi_robot_free(handle))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_num_dofs(handle: *const OptimaRobotHandle, out_num_dofs: *mut c_int) -> OptimaStatus {
+    ffi_guard(|| ffi_robot_num_dofs(handle, out_num_dofs))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_num_links(handle: *const OptimaRobotHandle, out_num_links: *mut c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        *ffi_out(out_num_links, "out_num_links")? = h.robot.links().len() as c_int;
+        Ok(())
+    })
+}
+
+/// Copies the null-terminated link name into `buffer`.  Fails with `InvalidArgument` if the buffer
+/// is too small.
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_link_name(handle: *const OptimaRobotHandle, link_idx: c_int, buffer: *mut c_char, buffer_length: c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        let link_idx = checked_link_idx(&h.robot, link_idx)?;
+        if buffer.is_null() { return Err(FFIError::NullPointer("buffer is null".to_string())); }
+        let name = h.robot.links()[link_idx].name().as_bytes();
+        if buffer_length < 0 || name.len() + 1 > buffer_length as usize { return Err(FFIError::InvalidArgument(format!("buffer must hold at least {} bytes", name.len() + 1))); }
+
+        let buffer = std::slice::from_raw_parts_mut(buffer as *mut u8, name.len() + 1);
+        buffer[..name.len()].copy_from_slice(name);
+        buffer[name.len()] = 0;
+        Ok(())
+    })
+}
+
+/// `out_lower` and `out_upper` must each hold num_dofs doubles.
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_dof_bounds(handle: *const OptimaRobotHandle, out_lower: *mut c_double, out_upper: *mut c_double) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        ffi_slice_mut(out_lower, h.robot.num_dofs(), "out_lower")?.copy_from_slice(&h.robot.get_dof_lower_bounds());
+        ffi_slice_mut(out_upper, h.robot.num_dofs(), "out_upper")?.copy_from_slice(&h.robot.get_dof_upper_bounds());
+        Ok(())
+    })
+}
+
+/// `out_link_poses` must hold num_links entries.
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_forward_kinematics(handle: *const OptimaRobotHandle, state: *const c_double, state_length: c_int, out_link_poses: *mut OptimaLinkPose, out_length: c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        let state = checked_state(&h.robot, state, state_length)?;
+        let num_links = h.robot.links().len();
+        if out_link_poses.is_null() { return Err(FFIError::NullPointer("out_link_poses is null".to_string())); }
+        if out_length < 0 || (out_length as usize) < num_links { return Err(FFIError::InvalidArgument(format!("out_link_poses must hold at least {} entries", num_links))); }
+
+        let out = std::slice::from_raw_parts_mut(out_link_poses, num_links);
+        let fk_res = h.robot.forward_kinematics(&state, None);
+        for (link_idx, pose) in fk_res.link_poses().iter().enumerate() {
+            out[link_idx] = match pose {
+                None => { OptimaLinkPose::default() }
+                Some(pose) => { OptimaLinkPose { has_pose: 1, pose: OptimaPose::from_isometry(pose) } }
+            };
+        }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_link_pose(handle: *const OptimaRobotHandle, state: *const c_double, state_length: c_int, link_idx: c_int, out_pose: *mut OptimaPose) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        let state = checked_state(&h.robot, state, state_length)?;
+        let link_idx = checked_link_idx(&h.robot, link_idx)?;
+        let out_pose = ffi_out(out_pose, "out_pose")?;
+
+        let fk_res = h.robot.forward_kinematics(&state, None);
+        let pose = fk_res.get_link_pose(link_idx)?;
+        *out_pose = OptimaPose::from_isometry(pose);
+        Ok(())
+    })
+}
+
+/// `out_solution` must hold num_dofs doubles.
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_solve_ik(handle: *const OptimaRobotHandle, init_state: *const c_double, state_length: c_int, link_idx: c_int, goal_pose: OptimaPose, out_solution: *mut c_double, out_cost: *mut c_double) -> OptimaStatus {
+    ffi_guard(|| ffi_robot_solve_ik(handle, init_state, state_length, link_idx, goal_pose.to_isometry(), out_solution, out_cost))
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct OptimaIKBatchSettings {
+    pub optimizer_tolerance: c_double,
+    /// A problem counts as solved if every goal is within this many meters of its target.
+    pub success_position_tolerance: c_double,
+    /// A problem counts as solved if every goal is within this many radians of its target.
+    pub success_orientation_tolerance: c_double,
+    /// 0 uses all available threads.
+    pub num_threads: c_int
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OptimaIKBatchResult {
+    pub success: c_int,
+    pub cost: c_double,
+    pub position_error: c_double,
+    pub orientation_error: c_double,
+    pub solve_time_seconds: c_double
+}
+
+/// Solves `num_problems` ik problems in parallel.  Every problem shares the same `num_goals` goal
+/// links; `goal_poses` holds num_problems * num_goals poses, problem by problem.  `seeds` holds
+/// either num_problems * num_dofs doubles (one seed per problem) or num_dofs doubles (one shared
+/// seed), with `seeds_length` giving which.  `settings` may be null for defaults.  `out_results`
+/// must hold num_problems entries and `out_solutions` num_problems * num_dofs doubles.
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_solve_ik_batch(handle: *const OptimaRobotHandle, goal_link_idxs: *const c_int, num_goals: c_int, goal_poses: *const OptimaPose, num_problems: c_int, seeds: *const c_double, seeds_length: c_int, settings: *const OptimaIKBatchSettings, out_results: *mut OptimaIKBatchResult, out_solutions: *mut c_double) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        let num_dofs = h.robot.num_dofs();
+        if num_dofs == 0 { return Err(FFIError::InvalidArgument("robot has no degrees of freedom to solve for".to_string())); }
+        if num_goals < 1 { return Err(FFIError::InvalidArgument("at least one goal is required".to_string())); }
+        if num_problems < 0 { return Err(FFIError::InvalidArgument("num_problems is negative".to_string())); }
+        let link_idxs = ffi_array(goal_link_idxs, num_goals, "goal_link_idxs")?.iter().map(|x| checked_link_idx(&h.robot, *x)).collect::<Result<Vec<usize>, FFIError>>()?;
+        let num_poses = num_problems.checked_mul(num_goals).ok_or(FFIError::InvalidArgument("too many goal poses".to_string()))?;
+        let goals: Vec<Vec<Isometry3<f64>>> = ffi_array(goal_poses, num_poses, "goal_poses")?.chunks(num_goals as usize).map(|x| x.iter().map(|p| p.to_isometry()).collect()).collect();
+        let seeds = ffi_slice(seeds, seeds_length, "seeds")?;
+        if seeds.len() != num_dofs && seeds.len() != num_dofs * num_problems as usize { return Err(FFIError::InvalidArgument(format!("seeds must hold {} or {} doubles, got {}", num_dofs, num_dofs * num_problems as usize, seeds.len()))); }
+        let seeds: Vec<Vec<f64>> = seeds.chunks(num_dofs).map(|x| x.to_vec()).collect();
+        let mut batch_settings = IKBatchSettings::default();
+        if let Some(settings) = settings.as_ref() {
+            batch_settings.optimizer_tolerance = settings.optimizer_tolerance;
+            batch_settings.success_position_tolerance = settings.success_position_tolerance;
+            batch_settings.success_orientation_tolerance = settings.success_orientation_tolerance;
+            batch_settings.num_threads = if settings.num_threads > 0 { Some(settings.num_threads as usize) } else { None };
+        }
+        let out_results = ffi_array_mut(out_results, num_problems as usize, "out_results")?;
+        let out_solutions = ffi_slice_mut(out_solutions, num_problems as usize * num_dofs, "out_solutions")?;
+
+        let results = h.robot.solve_ik_batch(ForwardADMulti::<FAD>::new(), &link_idxs, &goals, &seeds, &batch_settings)?;
+        for (i, res) in results.iter().enumerate() {
+            out_results[i] = OptimaIKBatchResult { success: res.success as c_int, cost: res.cost, position_error: res.position_error, orientation_error: res.orientation_error, solve_time_seconds: res.solve_time.as_secs_f64() };
+            out_solutions[i * num_dofs..(i + 1) * num_dofs].copy_from_slice(&res.solution);
+        }
+        Ok(())
+    })
+}
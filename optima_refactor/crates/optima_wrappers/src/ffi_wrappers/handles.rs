@@ -0,0 +1,219 @@
+use std::borrow::Cow;
+use std::os::raw::{c_char, c_double, c_int};
+use std::sync::{Arc, OnceLock};
+use ad_trait::differentiable_function::{DerivativeMethodTrait, ForwardADMulti, ReverseAD};
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{DMatrix, Isometry3};
+use optima_3d_spatial::optima_3d_pose::O3DPoseCategoryIsometry3;
+use optima_linalg::OLinalgCategoryNalgebra;
+use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
+use optima_optimization::open::SimpleOpEnOptimizer;
+use optima_proximity::pair_group_queries::{EmptyParryFilter, EmptyToParryProximity, OwnedEmptyParryFilter, OwnedEmptyToProximityQry};
+use optima_error::OptimaError;
+use optima_robotics::robot::{ORobot, ORobotDefault};
+use optima_robotics::robotics_optimization::robotics_optimization_composite::{CompositeObjective, CompositeObjectiveTerm};
+use optima_robotics::robotics_optimization::robotics_optimization_custom::CustomObjectiveTermHandle;
+use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjective, DifferentiableBlockIKObjectiveTrait, IKGoalTolerance, IKGoalUpdateMode};
+use crate::ffi_wrappers::ik_solvers::{checked_link_idx, checked_state};
+use crate::ffi_wrappers::status::{ffi_out, ffi_ref, ffi_slice_mut, ffi_string, FFIError};
+
+// Opaque handles passed across the C abi.  None of these are `#[repr(C)]`, so cbindgen emits them
+// as forward-declared structs and host code only ever holds pointers to them.  Each wraps a single
+// concrete (monomorphized) type so that no generic parameters leak into the generated header.
+
+pub (crate) type FAD = adfn<8>;
+
+pub (crate) type OptimaIKDifferentiableBlock<E> = DifferentiableBlockIKObjective<'static, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra, EmptyParryFilter, EmptyToParryProximity, E>;
+
+/// How an ik block computes gradients.  `ForwardADMulti` is usually fastest for robots with up to
+/// ~16 dofs; `ReverseAD` costs about the same no matter how many dofs there are, so it wins on
+/// larger robots.  A `ReverseAD` block must only be used from one thread at a time.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptimaDerivativeMode {
+    ForwardADMulti = 0,
+    ReverseAD = 1
+}
+impl OptimaDerivativeMode {
+    pub (crate) fn from_c_int(derivative_mode: c_int) -> Result<Self, FFIError> {
+        match derivative_mode {
+            0 => { Ok(Self::ForwardADMulti) }
+            1 => { Ok(Self::ReverseAD) }
+            _ => { Err(FFIError::InvalidArgument(format!("unknown derivative mode {}", derivative_mode))) }
+        }
+    }
+}
+
+/// One variant per derivative mode, so that the derivative method stays a concrete type on the
+/// rust side while being chosen at runtime by host code.
+pub (crate) enum OptimaIKBlock {
+    ForwardADMulti(OptimaIKDifferentiableBlock<ForwardADMulti<FAD>>),
+    ReverseAD(OptimaIKDifferentiableBlock<ReverseAD>)
+}
+impl OptimaIKBlock {
+    /// Only called by `OptimaIKBlockHandle::new`, which keeps `robot` alive for as long as the block.
+    fn new(robot: &'static ORobotDefault, derivative_mode: OptimaDerivativeMode, init_state: &[f64], goal_link_idxs: Vec<usize>, dis_filter_cutoff: f64, objective: CompositeObjective) -> Self {
+        match derivative_mode {
+            OptimaDerivativeMode::ForwardADMulti => { Self::ForwardADMulti(Self::new_block(robot, ForwardADMulti::new(), init_state, goal_link_idxs, dis_filter_cutoff, objective)) }
+            OptimaDerivativeMode::ReverseAD => { Self::ReverseAD(Self::new_block(robot, ReverseAD::new(), init_state, goal_link_idxs, dis_filter_cutoff, objective)) }
+        }
+    }
+    fn new_block<E: DerivativeMethodTrait>(robot: &'static ORobotDefault, derivative_method: E, init_state: &[f64], goal_link_idxs: Vec<usize>, dis_filter_cutoff: f64, objective: CompositeObjective) -> OptimaIKDifferentiableBlock<E> {
+        robot.get_ik_differentiable_block(derivative_method, OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, init_state, goal_link_idxs, 0.0, dis_filter_cutoff, objective)
+    }
+    #[inline(always)]
+    pub (crate) fn derivative_mode(&self) -> OptimaDerivativeMode {
+        match self {
+            OptimaIKBlock::ForwardADMulti(_) => { OptimaDerivativeMode::ForwardADMulti }
+            OptimaIKBlock::ReverseAD(_) => { OptimaDerivativeMode::ReverseAD }
+        }
+    }
+    /// Returns the optimized state.
+    pub (crate) fn optimize_unconstrained(&self, optimizer: &SimpleOpEnOptimizer, init_condition: &[f64]) -> Vec<f64> {
+        match self {
+            OptimaIKBlock::ForwardADMulti(db) => { optimizer.optimize_unconstrained(init_condition, db).x_star().to_vec() }
+            OptimaIKBlock::ReverseAD(db) => { optimizer.optimize_unconstrained(init_condition, db).x_star().to_vec() }
+        }
+    }
+    fn as_ik_objective(&self) -> &dyn DifferentiableBlockIKObjectiveTrait<'static, O3DPoseCategoryIsometry3> {
+        match self {
+            OptimaIKBlock::ForwardADMulti(db) => { db }
+            OptimaIKBlock::ReverseAD(db) => { db }
+        }
+    }
+}
+impl DifferentiableBlockIKObjectiveTrait<'static, O3DPoseCategoryIsometry3> for OptimaIKBlock {
+    fn update_ik_pose(&self, idx: usize, pose: Isometry3<f64>, update_mode: IKGoalUpdateMode) {
+        self.as_ik_objective().update_ik_pose(idx, pose, update_mode);
+    }
+    fn update_prev_states(&self, state: Vec<f64>) {
+        self.as_ik_objective().update_prev_states(state);
+    }
+    fn update_objective_weight(&self, term: CompositeObjectiveTerm, weight: f64) -> Result<(), OptimaError> {
+        self.as_ik_objective().update_objective_weight(term, weight)
+    }
+    fn update_ik_goal_weight(&self, idx: usize, weight: f64) {
+        self.as_ik_objective().update_ik_goal_weight(idx, weight);
+    }
+    fn update_ik_goal_tolerance(&self, idx: usize, tolerance: IKGoalTolerance) {
+        self.as_ik_objective().update_ik_goal_tolerance(idx, tolerance);
+    }
+    fn update_custom_objective_term(&self, term: CustomObjectiveTermHandle, weight: f64) -> Result<(), OptimaError> {
+        self.as_ik_objective().update_custom_objective_term(term, weight)
+    }
+    fn remove_objective_term(&self, term: CompositeObjectiveTerm) {
+        self.as_ik_objective().remove_objective_term(term);
+    }
+}
+
+pub struct OptimaRobotHandle {
+    /// Shared with the ik blocks made from this handle, so freeing the handle does not invalidate them.
+    pub (crate) robot: Arc<ORobotDefault>,
+    /// AD copy of `robot` for jacobians, made on first use since converting is slow.
+    ad_robot: OnceLock<ORobot<FAD, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra>>
+}
+impl OptimaRobotHandle {
+    pub fn new(robot: ORobotDefault) -> Self {
+        Self { robot: Arc::new(robot), ad_robot: OnceLock::new() }
+    }
+    #[inline(always)]
+    pub fn robot(&self) -> &ORobotDefault {
+        &self.robot
+    }
+    /// Copies the robot first if ik blocks still share it, so those blocks keep the robot as it
+    /// was when they were made.
+    pub (crate) fn robot_mut(&mut self) -> &mut ORobotDefault {
+        self.ad_robot = OnceLock::new();
+        Arc::make_mut(&mut self.robot)
+    }
+    /// 6 x num_dofs geometric jacobian of the given link (see `ORobot::jacobian`).
+    pub (crate) fn jacobian(&self, state: &[f64], link_idx: usize) -> Result<DMatrix<f64>, FFIError> {
+        let ad_robot = self.ad_robot.get_or_init(|| self.robot.to_other_ad_type::<FAD>());
+        let block = self.robot.get_link_jacobian_differentiable_block(ForwardADMulti::<FAD>::new(), Cow::Borrowed(ad_robot), link_idx)?;
+        Ok(self.robot.link_jacobian_from_block(&block, state)?)
+    }
+    /// Single-goal ik from `init_state` that only matches the pose of `link_idx`.  Returns the
+    /// solution and the final objective value.
+    pub (crate) fn solve_ik(&self, init_state: &[f64], link_idx: usize, goal_pose: Isometry3<f64>) -> (Vec<f64>, f64) {
+        let db = self.robot.get_ik_differentiable_block(ForwardADMulti::<FAD>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, init_state, vec![link_idx], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0));
+        db.update_ik_pose(0, goal_pose, IKGoalUpdateMode::Absolute);
+
+        let o = SimpleOpEnOptimizer::new(self.robot.get_dof_lower_bounds(), self.robot.get_dof_upper_bounds(), 0.001);
+        let res = o.optimize_unconstrained(init_state, &db);
+        (res.x_star().to_vec(), res.f_star())
+    }
+}
+
+// Bodies of the robot entry points that the c apis have in common (`ik_solvers`, `mex`, and
+// `flat` only differ in naming and in how the status is returned), so each is written once.
+
+pub (crate) unsafe fn ffi_robot_load(robot_name: *const c_char, out_robot: *mut *mut OptimaRobotHandle) -> Result<(), FFIError> {
+    let robot_name = ffi_string(robot_name, "robot_name")?;
+    let out_robot = ffi_out(out_robot, "out_robot")?;
+    let robot = ORobotDefault::load_from_saved_robot(&robot_name)?;
+    *out_robot = Box::into_raw(Box::new(OptimaRobotHandle::new(robot)));
+    Ok(())
+}
+
+/// Passing a null pointer is a no-op.
+pub (crate) unsafe fn ffi_robot_free(robot: *mut OptimaRobotHandle) -> Result<(), FFIError> {
+    if !robot.is_null() { let _ = Box::from_raw(robot); }
+    Ok(())
+}
+
+pub (crate) unsafe fn ffi_robot_num_dofs(robot: *const OptimaRobotHandle, out_num_dofs: *mut c_int) -> Result<(), FFIError> {
+    let r = &ffi_ref(robot, "robot")?.robot;
+    *ffi_out(out_num_dofs, "out_num_dofs")? = r.num_dofs() as c_int;
+    Ok(())
+}
+
+/// `out_solution` must hold num_dofs doubles.
+pub (crate) unsafe fn ffi_robot_solve_ik(robot: *const OptimaRobotHandle, init_state: *const c_double, state_length: c_int, link_idx: c_int, goal_pose: Isometry3<f64>, out_solution: *mut c_double, out_cost: *mut c_double) -> Result<(), FFIError> {
+    let h = ffi_ref(robot, "robot")?;
+    let init_state = checked_state(&h.robot, init_state, state_length)?;
+    let link_idx = checked_link_idx(&h.robot, link_idx)?;
+    let out_solution = ffi_slice_mut(out_solution, h.robot.num_dofs(), "out_solution")?;
+    let out_cost = ffi_out(out_cost, "out_cost")?;
+
+    let (solution, cost) = h.solve_ik(&init_state, link_idx, goal_pose);
+    out_solution.copy_from_slice(&solution);
+    *out_cost = cost;
+    Ok(())
+}
+
+/// Holds a reference to the robot it was created from, so the robot handle can be freed before or
+/// after the block.
+pub struct OptimaIKBlockHandle {
+    // declared before `robot` so that it is dropped first, since it borrows from `robot`.
+    pub (crate) block: OptimaIKBlock,
+    pub (crate) num_goals: usize,
+    robot: Arc<ORobotDefault>
+}
+impl OptimaIKBlockHandle {
+    pub (crate) fn new(robot: &Arc<ORobotDefault>, derivative_mode: OptimaDerivativeMode, init_state: &[f64], goal_link_idxs: Vec<usize>, dis_filter_cutoff: f64, objective: CompositeObjective) -> Self {
+        let robot = robot.clone();
+        // the robot lives in the Arc's allocation, which does not move and is only released after
+        // `block` has been dropped.
+        let r: &'static ORobotDefault = unsafe { &*Arc::as_ptr(&robot) };
+        let num_goals = goal_link_idxs.len();
+        let block = OptimaIKBlock::new(r, derivative_mode, init_state, goal_link_idxs, dis_filter_cutoff, objective);
+        Self { block, num_goals, robot }
+    }
+    #[inline(always)]
+    pub fn robot(&self) -> &ORobotDefault {
+        &self.robot
+    }
+}
+
+pub struct OptimaIKOptimizerHandle {
+    pub (crate) optimizer: SimpleOpEnOptimizer
+}
+impl OptimaIKOptimizerHandle {
+    pub fn new(optimizer: SimpleOpEnOptimizer) -> Self {
+        Self { optimizer }
+    }
+    #[inline(always)]
+    pub fn optimizer(&self) -> &SimpleOpEnOptimizer {
+        &self.optimizer
+    }
+}
@@ -1,184 +1,374 @@
 use std::ffi::CString;
 use std::os::raw::*;
-use ad_trait::differentiable_function::ForwardADMulti;
-use ad_trait::forward_ad::adfn::adfn;
 use nalgebra::{Isometry3, Quaternion, UnitQuaternion, Vector3};
-use optima_3d_spatial::optima_3d_pose::{O3DPose, O3DPoseCategoryIsometry3};
+use optima_3d_spatial::optima_3d_pose::O3DPose;
 use optima_interpolation::InterpolatorTrait;
 use optima_interpolation::splines::{InterpolatingSpline, InterpolatingSplineType};
-use optima_linalg::OLinalgCategoryNalgebra;
-use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
 use optima_optimization::open::SimpleOpEnOptimizer;
-use optima_proximity::pair_group_queries::{EmptyParryFilter, EmptyToParryProximity, OwnedEmptyParryFilter, OwnedEmptyToProximityQry};
 use optima_robotics::robot::ORobotDefault;
-use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjective, DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
+use optima_robotics::robotics_optimization::robotics_optimization_composite::{CompositeObjective, CompositeObjectiveTerm};
+use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalTolerance, IKGoalUpdateMode};
+use crate::ffi_wrappers::DoubleArray;
+use crate::ffi_wrappers::handles::{ffi_robot_free, ffi_robot_load, OptimaDerivativeMode, OptimaIKBlockHandle, OptimaIKOptimizerHandle, OptimaRobotHandle};
+use crate::ffi_wrappers::status::{ffi_array, ffi_guard, ffi_out, ffi_ref, ffi_slice, FFIError, OptimaStatus};
 
-type FAD = adfn<8>;
+// Every entry point returns an `OptimaStatus` and writes its result through an out pointer.  On
+// failure, `last_error_message` describes what went wrong, and nothing is written to the outputs.
 
 #[no_mangle]
-pub unsafe extern "C" fn get_default_robot(robot_name: *const c_char) -> *const ORobotDefault {
-    let c_str = std::ffi::CStr::from_ptr(robot_name);
-    let s = c_str.to_str().expect("Not a valid UTF-8 string");
-    let r = ORobotDefault::load_from_saved_robot(s);
-    let ptr = Box::into_raw(Box::new(r));
-    ptr
+pub unsafe extern "C" fn get_default_robot(robot_name: *const c_char, out_robot: *mut *mut OptimaRobotHandle) -> OptimaStatus {
+    ffi_guard(|| ffi_robot_load(robot_name, out_robot))
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn get_default_ik_differentiable_block<'a>(robot: *const ORobotDefault, goal_link_idx: *const c_int, init_state: *const c_double, joint_state_length: c_int) -> *const DifferentiableBlockIKObjective<'a, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra, EmptyParryFilter, EmptyToParryProximity, ForwardADMulti<FAD>> {
-    let x_slice: &[c_double] = std::slice::from_raw_parts(init_state, joint_state_length as usize);
-    let x = x_slice.to_vec();
-    let goal_link_idx = goal_link_idx as usize;
+pub unsafe extern "C" fn get_default_ik_differentiable_block(robot: *const OptimaRobotHandle, goal_link_idx: c_int, init_state: *const c_double, joint_state_length: c_int, out_differentiable_block: *mut *mut OptimaIKBlockHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        let r = &ffi_ref(robot, "robot")?.robot;
+        let x = checked_state(r, init_state, joint_state_length)?;
+        let goal_link_idx = checked_link_idx(r, goal_link_idx)?;
+        let out_differentiable_block = ffi_out(out_differentiable_block, "out_differentiable_block")?;
 
-    let fq = OwnedEmptyParryFilter::new(());
-    let q = OwnedEmptyToProximityQry::new(());
-    // let fq = OwnedParryDistanceGroupSequenceFilter::new(ParryDistanceGroupSequenceFilterArgs::new(vec![ParryShapeRep::BoundingSphere, ParryShapeRep::OBB, ParryShapeRep::Full], vec![], 0.6, true, ParryDisMode::ContactDis));
-    // let q = OwnedParryProximaAsProximityQry::new(PairGroupQryArgsParryProxima::new(ParryShapeRep::Full, ParryShapeRep::Full, true, false, ProximaTermination::MaxError(0.15), ProximityLossFunction::Hinge, 15.0, 0.6));
-    // let q = OwnedParryDistanceAsProximityGroupQry::new(ParryDistanceGroupArgs::new(ParryShapeRep::Full, ParryDisMode::ContactDis, true, false, -1000.0, false));
-    let db = robot.as_ref().unwrap().get_ik_differentiable_block(ForwardADMulti::<FAD>::new(), fq, q, None, &x, vec![goal_link_idx], 0.0, 0.6, 1.0, 0.0, 1.0, 0.3, 0.1);
+        // let fq = OwnedParryDistanceGroupSequenceFilter::new(ParryDistanceGroupSequenceFilterArgs::new(vec![ParryShapeRep::BoundingSphere, ParryShapeRep::OBB, ParryShapeRep::Full], vec![], 0.6, true, ParryDisMode::ContactDis));
+        // let q = OwnedParryProximaAsProximityQry::new(PairGroupQryArgsParryProxima::new(ParryShapeRep::Full, ParryShapeRep::Full, true, false, ProximaTermination::MaxError(0.15), ProximityLossFunction::Hinge, 15.0, 0.6));
+        // let q = OwnedParryDistanceAsProximityGroupQry::new(ParryDistanceGroupArgs::new(ParryShapeRep::Full, ParryDisMode::ContactDis, true, false, -1000.0, false));
+        let db = OptimaIKBlockHandle::new(r, OptimaDerivativeMode::ForwardADMulti, &x, vec![goal_link_idx], 0.6, CompositeObjective::new_ik_unchecked(1.0, 0.0, 1.0, 0.3, 0.1));
 
-    Box::into_raw(Box::new(db))
+        *out_differentiable_block = Box::into_raw(Box::new(db));
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn get_static_ik_differentiable_block<'a>(robot: *const ORobotDefault, goal_link_idx: *const c_int) -> *const DifferentiableBlockIKObjective<'a, O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra, EmptyParryFilter, EmptyToParryProximity, ForwardADMulti<FAD>> {
-    let joint_state_length = robot.as_ref().unwrap().num_dofs();
-    let init_state = vec![0.0; joint_state_length];
-    // let x_slice: &[c_double] = std::slice::from_raw_parts(init_state, joint_state_length);
-    let x = init_state.to_vec();
-    let goal_link_idx = goal_link_idx as usize;
+pub unsafe extern "C" fn get_static_ik_differentiable_block(robot: *const OptimaRobotHandle, goal_link_idx: c_int, out_differentiable_block: *mut *mut OptimaIKBlockHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        let r = &ffi_ref(robot, "robot")?.robot;
+        let goal_link_idx = checked_link_idx(r, goal_link_idx)?;
+        let out_differentiable_block = ffi_out(out_differentiable_block, "out_differentiable_block")?;
+        let x = vec![0.0; r.num_dofs()];
 
-    let fq = OwnedEmptyParryFilter::new(());
-    let q = OwnedEmptyToProximityQry::new(());
-    // let fq = OwnedParryDistanceGroupSequenceFilter::new(ParryDistanceGroupSequenceFilterArgs::new(vec![ParryShapeRep::BoundingSphere, ParryShapeRep::OBB, ParryShapeRep::Full], vec![], 0.6, true, ParryDisMode::ContactDis));
-    // let q = OwnedParryProximaAsProximityQry::new(PairGroupQryArgsParryProxima::new(ParryShapeRep::Full, ParryShapeRep::Full, true, false, ProximaTermination::MaxError(0.15), ProximityLossFunction::Hinge, 15.0, 0.6));
-    // let q = OwnedParryDistanceAsProximityGroupQry::new(ParryDistanceGroupArgs::new(ParryShapeRep::Full, ParryDisMode::ContactDis, true, false, -1000.0, false));
-    let db = robot.as_ref().unwrap().get_ik_differentiable_block(ForwardADMulti::<FAD>::new(), fq, q, None, &x, vec![goal_link_idx], 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0);
+        let db = OptimaIKBlockHandle::new(r, OptimaDerivativeMode::ForwardADMulti, &x, vec![goal_link_idx], 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0));
 
-    Box::into_raw(Box::new(db))
+        *out_differentiable_block = Box::into_raw(Box::new(db));
+        Ok(())
+    })
 }
 
+/// Builds a block with one ik goal per entry of `goal_link_idxs`.  `goal_weights` (one per goal),
+/// `goal_tolerances` (one per goal), and `objective_weights` may each be null, in which case every
+/// goal gets a weight of 1, goals are matched exactly, and the weights of
+/// `get_default_ik_differentiable_block` are used.  Goal poses start at the links' poses in
+/// `init_state`; set them with `update_ik_goal_poses`.
 #[no_mangle]
-pub unsafe extern "C" fn get_default_ik_optimizer(robot: *const ORobotDefault) -> *const SimpleOpEnOptimizer {
-    let r = robot.as_ref().unwrap();
-    let o = SimpleOpEnOptimizer::new(r.get_dof_lower_bounds(), r.get_dof_upper_bounds(), 0.001);
-    Box::into_raw(Box::new(o))
+pub unsafe extern "C" fn get_multi_goal_ik_differentiable_block(robot: *const OptimaRobotHandle, goal_link_idxs: *const c_int, num_goals: c_int, goal_weights: *const c_double, goal_tolerances: *const OptimaIKGoalTolerance, objective_weights: *const OptimaIKObjectiveWeights, init_state: *const c_double, joint_state_length: c_int, out_differentiable_block: *mut *mut OptimaIKBlockHandle) -> OptimaStatus {
+    get_multi_goal_ik_differentiable_block_with_derivative_mode(robot, goal_link_idxs, num_goals, goal_weights, goal_tolerances, objective_weights, init_state, joint_state_length, OptimaDerivativeMode::ForwardADMulti as c_int, out_differentiable_block)
 }
 
-/// new_ee_orientation should be list of four values, a unit quaternion in format [w x y z]
+/// Same as `get_multi_goal_ik_differentiable_block`, with the derivative method picked by
+/// `derivative_mode` (an `OptimaDerivativeMode`).
 #[no_mangle]
-pub unsafe extern "C" fn update_ik_differentiable_block(new_ee_position: *const c_double, new_ee_orientation: *const c_double, previous_solution: *const c_double, joint_state_length: c_int, differentiable_block: *const DifferentiableBlockIKObjective<O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra, EmptyParryFilter, EmptyToParryProximity, ForwardADMulti<FAD>>) {
-    let position_slice: &[c_double] = std::slice::from_raw_parts(new_ee_position, 3);
-    let position = position_slice.to_vec();
-    let pos: Vector3<f64> = Vector3::new(position[0], position[1], position[2]);
-    let orientation_slice: &[c_double] = std::slice::from_raw_parts(new_ee_orientation, 4);
-    let orientation = orientation_slice.to_vec();
-    let quat: UnitQuaternion<f64> = UnitQuaternion::from_quaternion(Quaternion::new(orientation[0], orientation[1], orientation[2], orientation[3]));
-    let previous_solution_slice: &[c_double] = std::slice::from_raw_parts(previous_solution, joint_state_length as usize);
-    let previous_solution = previous_solution_slice.to_vec();
+pub unsafe extern "C" fn get_multi_goal_ik_differentiable_block_with_derivative_mode(robot: *const OptimaRobotHandle, goal_link_idxs: *const c_int, num_goals: c_int, goal_weights: *const c_double, goal_tolerances: *const OptimaIKGoalTolerance, objective_weights: *const OptimaIKObjectiveWeights, init_state: *const c_double, joint_state_length: c_int, derivative_mode: c_int, out_differentiable_block: *mut *mut OptimaIKBlockHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        let r = &ffi_ref(robot, "robot")?.robot;
+        let x = checked_state(r, init_state, joint_state_length)?;
+        if num_goals < 1 { return Err(FFIError::InvalidArgument("at least one goal is required".to_string())); }
+        let mut link_idxs = vec![];
+        for link_idx in ffi_array(goal_link_idxs, num_goals, "goal_link_idxs")? { link_idxs.push(checked_link_idx(r, *link_idx)?); }
+        let objective = match objective_weights.as_ref() {
+            None => { CompositeObjective::new_ik_unchecked(1.0, 0.0, 1.0, 0.3, 0.1) }
+            Some(w) => { w.to_composite_objective()? }
+        };
+        let derivative_mode = OptimaDerivativeMode::from_c_int(derivative_mode)?;
+        let out_differentiable_block = ffi_out(out_differentiable_block, "out_differentiable_block")?;
+
+        let goal_weights = if goal_weights.is_null() { None } else { Some(checked_goal_weights(goal_weights, num_goals)?) };
+
+        let db = OptimaIKBlockHandle::new(r, derivative_mode, &x, link_idxs, 0.6, objective);
+        if let Some(goal_weights) = goal_weights {
+            for (i, w) in goal_weights.into_iter().enumerate() { db.block.update_ik_goal_weight(i, w); }
+        }
+        if !goal_tolerances.is_null() {
+            for (i, t) in ffi_array(goal_tolerances, num_goals, "goal_tolerances")?.iter().enumerate() { db.block.update_ik_goal_tolerance(i, t.to_ik_goal_tolerance()); }
+        }
 
-    differentiable_block.as_ref().unwrap().update_ik_pose(0, Isometry3::from_translation_and_rotation(&pos, &quat), IKGoalUpdateMode::Absolute);
-    differentiable_block.as_ref().unwrap().update_prev_states(previous_solution);
+        *out_differentiable_block = Box::into_raw(Box::new(db));
+        Ok(())
+    })
 }
 
+/// Writes the block's `OptimaDerivativeMode` into `out_derivative_mode`.
 #[no_mangle]
-pub unsafe extern "C" fn ik_optimize(init_condition: *const c_double, joint_state_length: c_int, differentiable_block: *const DifferentiableBlockIKObjective<O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra, EmptyParryFilter, EmptyToParryProximity, ForwardADMulti<FAD>>, optimizer: *const SimpleOpEnOptimizer) -> IKOptResult {
-    let x_slice: &[c_double] = std::slice::from_raw_parts(init_condition, joint_state_length as usize);
-    let x = x_slice.to_vec();
-    let o = optimizer.as_ref().unwrap();
-    let db = differentiable_block.as_ref().unwrap();
-    let res = o.optimize_unconstrained(&x, db);
-    let solution = res.x_star().to_vec();
-    let l = solution.len();
-    // let ptr = solution.as_ptr();
+pub unsafe extern "C" fn get_ik_differentiable_block_derivative_mode(differentiable_block: *const OptimaIKBlockHandle, out_derivative_mode: *mut c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(differentiable_block, "differentiable_block")?;
+        *ffi_out(out_derivative_mode, "out_derivative_mode")? = h.block.derivative_mode() as c_int;
+        Ok(())
+    })
+}
 
-    let boxed_slice = solution.into_boxed_slice();
-    let ptr = Box::into_raw(boxed_slice) as *const c_double;
+/// Retargets every goal without rebuilding the block.  `goal_positions` holds 3 * num_goals values
+/// ([x y z] per goal) and `goal_orientations` holds 4 * num_goals values ([w x y z] per goal).
+#[no_mangle]
+pub unsafe extern "C" fn update_ik_goal_poses(differentiable_block: *const OptimaIKBlockHandle, goal_positions: *const c_double, goal_orientations: *const c_double, num_goals: c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(differentiable_block, "differentiable_block")?;
+        checked_num_goals(h, num_goals)?;
+        let goal_positions = ffi_slice(goal_positions, 3 * num_goals, "goal_positions")?;
+        let goal_orientations = ffi_slice(goal_orientations, 4 * num_goals, "goal_orientations")?;
+        let mut poses = vec![];
+        for i in 0..h.num_goals {
+            poses.push(checked_pose(goal_positions[3 * i..].as_ptr(), goal_orientations[4 * i..].as_ptr())?);
+        }
+        for (i, pose) in poses.into_iter().enumerate() { h.block.update_ik_pose(i, pose, IKGoalUpdateMode::Absolute); }
+        Ok(())
+    })
+}
 
-    IKOptResult { data: ptr, length: l as c_int }
+#[no_mangle]
+pub unsafe extern "C" fn update_ik_goal_weights(differentiable_block: *const OptimaIKBlockHandle, goal_weights: *const c_double, num_goals: c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(differentiable_block, "differentiable_block")?;
+        checked_num_goals(h, num_goals)?;
+        for (i, w) in checked_goal_weights(goal_weights, num_goals)?.into_iter().enumerate() { h.block.update_ik_goal_weight(i, w); }
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn compute_interpolated_motion_path_to_ee_pose(ee_position: *const c_double, ee_orientation: *const c_double, init_state: *const c_double, joint_state_length: c_int, differentiable_block: *const DifferentiableBlockIKObjective<O3DPoseCategoryIsometry3, OLinalgCategoryNalgebra, EmptyParryFilter, EmptyToParryProximity, ForwardADMulti<FAD>>, optimizer: *const SimpleOpEnOptimizer) -> InterpolatedMotionPathResult {
-    let x_slice: &[c_double] = std::slice::from_raw_parts(init_state, joint_state_length as usize);
-    let x = x_slice.to_vec();
+pub unsafe extern "C" fn update_ik_goal_tolerances(differentiable_block: *const OptimaIKBlockHandle, goal_tolerances: *const OptimaIKGoalTolerance, num_goals: c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(differentiable_block, "differentiable_block")?;
+        checked_num_goals(h, num_goals)?;
+        for (i, t) in ffi_array(goal_tolerances, num_goals, "goal_tolerances")?.iter().enumerate() { h.block.update_ik_goal_tolerance(i, t.to_ik_goal_tolerance()); }
+        Ok(())
+    })
+}
 
-    let position_slice: &[c_double] = std::slice::from_raw_parts(ee_position, 3);
-    let position = position_slice.to_vec();
-    let pos: Vector3<f64> = Vector3::new(position[0], position[1], position[2]);
-    let orientation_slice: &[c_double] = std::slice::from_raw_parts(ee_orientation, 4);
-    let orientation = orientation_slice.to_vec();
-    let quat: UnitQuaternion<f64> = UnitQuaternion::from_quaternion(Quaternion::new(orientation[0], orientation[1], orientation[2], orientation[3]));
+#[no_mangle]
+pub unsafe extern "C" fn update_ik_objective_weights(differentiable_block: *const OptimaIKBlockHandle, objective_weights: *const OptimaIKObjectiveWeights) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(differentiable_block, "differentiable_block")?;
+        let w = ffi_ref(objective_weights, "objective_weights")?;
+        for (term, weight) in w.terms()? { h.block.update_objective_weight(term, weight)?; }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn get_default_ik_optimizer(robot: *const OptimaRobotHandle, out_optimizer: *mut *mut OptimaIKOptimizerHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        let r = &ffi_ref(robot, "robot")?.robot;
+        let out_optimizer = ffi_out(out_optimizer, "out_optimizer")?;
+        let o = SimpleOpEnOptimizer::new(r.get_dof_lower_bounds(), r.get_dof_upper_bounds(), 0.001);
+        *out_optimizer = Box::into_raw(Box::new(OptimaIKOptimizerHandle::new(o)));
+        Ok(())
+    })
+}
+
+/// new_ee_orientation should be list of four values, a unit quaternion in format [w x y z]
+#[no_mangle]
+pub unsafe extern "C" fn update_ik_differentiable_block(new_ee_position: *const c_double, new_ee_orientation: *const c_double, previous_solution: *const c_double, joint_state_length: c_int, differentiable_block: *const OptimaIKBlockHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        let db = &ffi_ref(differentiable_block, "differentiable_block")?.block;
+        let pose = checked_pose(new_ee_position, new_ee_orientation)?;
+        let previous_solution = ffi_slice(previous_solution, joint_state_length, "previous_solution")?.to_vec();
 
-    differentiable_block.as_ref().unwrap().update_ik_pose(0, Isometry3::from_translation_and_rotation(&pos, &quat), IKGoalUpdateMode::Absolute);
+        db.update_ik_pose(0, pose, IKGoalUpdateMode::Absolute);
+        db.update_prev_states(previous_solution);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ik_optimize(init_condition: *const c_double, joint_state_length: c_int, differentiable_block: *const OptimaIKBlockHandle, optimizer: *const OptimaIKOptimizerHandle, out_result: *mut IKOptResult) -> OptimaStatus {
+    ffi_guard(|| {
+        let x = ffi_slice(init_condition, joint_state_length, "init_condition")?.to_vec();
+        let o = &ffi_ref(optimizer, "optimizer")?.optimizer;
+        let db = &ffi_ref(differentiable_block, "differentiable_block")?.block;
+        let out_result = ffi_out(out_result, "out_result")?;
+
+        let solution = db.optimize_unconstrained(o, &x);
+        let l = solution.len();
+
+        let boxed_slice = solution.into_boxed_slice();
+        let ptr = Box::into_raw(boxed_slice) as *const c_double;
 
-    let o = optimizer.as_ref().unwrap();
-    let res = o.optimize_unconstrained(&x, differentiable_block.as_ref().unwrap());
-    let solution = res.x_star().to_vec();
-    let boxed_slice = solution.clone().into_boxed_slice();
-    let solution_point = Box::into_raw(boxed_slice) as *const c_double;
+        *out_result = IKOptResult { data: ptr, length: l as c_int };
+        Ok(())
+    })
+}
 
-    let spline = InterpolatingSpline::new(vec![x.clone(), solution.clone()], InterpolatingSplineType::Linear)
-        .to_arclength_parameterized_interpolator(40);
+#[no_mangle]
+pub unsafe extern "C" fn compute_interpolated_motion_path_to_ee_pose(ee_position: *const c_double, ee_orientation: *const c_double, init_state: *const c_double, joint_state_length: c_int, differentiable_block: *const OptimaIKBlockHandle, optimizer: *const OptimaIKOptimizerHandle, out_result: *mut InterpolatedMotionPathResult) -> OptimaStatus {
+    ffi_guard(|| {
+        let x = ffi_slice(init_state, joint_state_length, "init_state")?.to_vec();
+        let pose = checked_pose(ee_position, ee_orientation)?;
+        let db = &ffi_ref(differentiable_block, "differentiable_block")?.block;
+        let o = &ffi_ref(optimizer, "optimizer")?.optimizer;
+        let out_result = ffi_out(out_result, "out_result")?;
 
-    let path = spline.interpolate_points_by_arclength_absolute_stride(0.05);
+        db.update_ik_pose(0, pose, IKGoalUpdateMode::Absolute);
 
-    /*
-    let ptr: *const *const c_double = path.as_ptr().cast::<*const c_double>();
+        let solution = db.optimize_unconstrained(o, &x);
+        let boxed_slice = solution.clone().into_boxed_slice();
+        let solution_point = Box::into_raw(boxed_slice) as *const c_double;
 
-    std::mem::forget(path);
+        let spline = InterpolatingSpline::new(vec![x.clone(), solution.clone()], InterpolatingSplineType::Linear)
+            .to_arclength_parameterized_interpolator(40);
 
-    ptr
-    */
+        let path = spline.interpolate_points_by_arclength_absolute_stride(0.05);
 
-    let mut path_as_string = "".to_string();
-    for (i, point) in path.iter().enumerate() {
-        for (j, val) in point.iter().enumerate() {
-            path_as_string += format!("{:?}", val).as_str();
-            if j < point.len() - 1 { path_as_string += ","; }
+        let mut path_as_string = "".to_string();
+        for (i, point) in path.iter().enumerate() {
+            for (j, val) in point.iter().enumerate() {
+                path_as_string += format!("{:?}", val).as_str();
+                if j < point.len() - 1 { path_as_string += ","; }
+            }
+            if i < path.len() - 1 { path_as_string += ";"; }
         }
-        if i < path.len() - 1 { path_as_string += ";"; }
-    }
-    let c_string = CString::new(path_as_string).expect("error");
+        let c_string = CString::new(path_as_string).expect("error");
 
-    let mut out_arrays = vec![];
-    for point in &path {
-        let l = point.len();
-        let boxed_slice = point.clone().into_boxed_slice();
-        let ptr = Box::into_raw(boxed_slice) as *const c_double;
-        out_arrays.push(DoubleArray {
+        let mut out_arrays = vec![];
+        for point in &path {
+            let l = point.len();
+            let boxed_slice = point.clone().into_boxed_slice();
+            let ptr = Box::into_raw(boxed_slice) as *const c_double;
+            out_arrays.push(DoubleArray {
+                data: ptr,
+                length: l as c_int,
+            });
+        }
+
+        let l = path.len();
+        let boxed_slice = out_arrays.into_boxed_slice();
+        let ptr = Box::into_raw(boxed_slice) as *const DoubleArray;
+
+        *out_result = InterpolatedMotionPathResult {
             data: ptr,
             length: l as c_int,
-        });
-    }
+            path_as_str: c_string.into_raw(),
+            solution_point,
+            solution_length: solution.len() as c_int
+        };
+        Ok(())
+    })
+}
 
-    let l = path.len();
-    let boxed_slice = out_arrays.into_boxed_slice();
-    let ptr = Box::into_raw(boxed_slice) as *const DoubleArray;
+#[no_mangle]
+pub unsafe extern "C" fn free_ik_optimize_result(ptr: *mut c_double, length: c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        if !ptr.is_null() {
+            let _ = Box::from_raw(std::slice::from_raw_parts_mut(ptr, length as usize));
+        }
+        Ok(())
+    })
+}
 
-    InterpolatedMotionPathResult {
-        data: ptr,
-        length: l as c_int,
-        path_as_str: c_string.into_raw(),
-        solution_point,
-    }
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Every handle returned by a `get_*` function in this module is owned by the caller and must be
+/// released exactly once with the matching `free_*` function below.  Passing a null pointer is a
+/// no-op.  Robots and the differentiable blocks made from them can be freed in any order.
+#[no_mangle]
+pub unsafe extern "C" fn free_robot(robot: *mut OptimaRobotHandle) -> OptimaStatus {
+    ffi_guard(|| ffi_robot_free(robot))
 }
 
 #[no_mangle]
-pub extern "C" fn free_ik_optimize_result(ptr: *mut c_double, length: c_int) {
-    unsafe {
-        let _ = Box::from_raw(std::slice::from_raw_parts_mut(ptr, length as usize));
-    }
+pub unsafe extern "C" fn free_ik_differentiable_block(differentiable_block: *mut OptimaIKBlockHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        if !differentiable_block.is_null() {
+            let _ = Box::from_raw(differentiable_block);
+        }
+        Ok(())
+    })
 }
 
-#[repr(C)]
-pub struct IKOptResult {
-    pub data: *const c_double,
-    pub length: c_int,
+#[no_mangle]
+pub unsafe extern "C" fn free_optimizer(optimizer: *mut OptimaIKOptimizerHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        if !optimizer.is_null() {
+            let _ = Box::from_raw(optimizer);
+        }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn free_ik_result(result: IKOptResult) -> OptimaStatus {
+    ffi_guard(|| {
+        if !result.data.is_null() {
+            let _ = Box::from_raw(std::slice::from_raw_parts_mut(result.data as *mut c_double, result.length as usize));
+        }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn free_interpolated_motion_path_result(result: InterpolatedMotionPathResult) -> OptimaStatus {
+    ffi_guard(|| {
+        if !result.data.is_null() {
+            let arrays = Box::from_raw(std::slice::from_raw_parts_mut(result.data as *mut DoubleArray, result.length as usize));
+            for array in arrays.iter() {
+                if !array.data.is_null() {
+                    let _ = Box::from_raw(std::slice::from_raw_parts_mut(array.data as *mut c_double, array.length as usize));
+                }
+            }
+        }
+        if !result.path_as_str.is_null() {
+            let _ = CString::from_raw(result.path_as_str as *mut c_char);
+        }
+        if !result.solution_point.is_null() {
+            let _ = Box::from_raw(std::slice::from_raw_parts_mut(result.solution_point as *mut c_double, result.solution_length as usize));
+        }
+        Ok(())
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub (crate) unsafe fn checked_state(robot: &ORobotDefault, state: *const c_double, state_length: c_int) -> Result<Vec<f64>, FFIError> {
+    let state = ffi_slice(state, state_length, "state")?;
+    if state.len() != robot.num_dofs() { return Err(FFIError::InvalidArgument(format!("expected a state of length {}, got {}", robot.num_dofs(), state.len()))); }
+    Ok(state.to_vec())
+}
+
+pub (crate) fn checked_link_idx(robot: &ORobotDefault, link_idx: c_int) -> Result<usize, FFIError> {
+    if link_idx < 0 || link_idx as usize >= robot.links().len() { return Err(FFIError::InvalidArgument(format!("link idx {} is out of range", link_idx))); }
+    Ok(link_idx as usize)
+}
+
+/// Weights are checked here, before anything is handed to the block, so that bad values come back
+/// as `InvalidArgument` instead of surfacing later as a panic inside the optimizer.
+pub (crate) fn checked_weight(weight: f64, name: &str) -> Result<f64, FFIError> {
+    if !(weight.is_finite() && weight >= 0.0) { return Err(FFIError::InvalidArgument(format!("{} must be finite and non-negative, got {}", name, weight))); }
+    Ok(weight)
 }
 
+unsafe fn checked_goal_weights(goal_weights: *const c_double, num_goals: c_int) -> Result<Vec<f64>, FFIError> {
+    let goal_weights = ffi_slice(goal_weights, num_goals, "goal_weights")?;
+    goal_weights.iter().enumerate().map(|(i, w)| checked_weight(*w, &format!("goal_weights[{}]", i))).collect()
+}
+
+fn checked_num_goals(block: &OptimaIKBlockHandle, num_goals: c_int) -> Result<(), FFIError> {
+    if num_goals < 0 || num_goals as usize != block.num_goals { return Err(FFIError::InvalidArgument(format!("block has {} goals, got {}", block.num_goals, num_goals))); }
+    Ok(())
+}
+
+/// position is [x y z] and orientation is a unit quaternion in format [w x y z].
+pub (crate) unsafe fn checked_pose(position: *const c_double, orientation: *const c_double) -> Result<Isometry3<f64>, FFIError> {
+    let position = ffi_slice(position, 3, "position")?;
+    let orientation = ffi_slice(orientation, 4, "orientation")?;
+    let pos: Vector3<f64> = Vector3::new(position[0], position[1], position[2]);
+    let quat: UnitQuaternion<f64> = UnitQuaternion::from_quaternion(Quaternion::new(orientation[0], orientation[1], orientation[2], orientation[3]));
+    Ok(Isometry3::from_translation_and_rotation(&pos, &quat))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Owns its data (allocated as a boxed slice, so it stays valid after `ik_optimize` returns).
+/// Release with `free_ik_result`.
 #[repr(C)]
-pub struct DoubleArray {
+pub struct IKOptResult {
     pub data: *const c_double,
     pub length: c_int,
 }
@@ -188,5 +378,48 @@ pub struct InterpolatedMotionPathResult {
     pub data: *const DoubleArray,
     pub length: c_int,
     pub path_as_str: *const c_char,
-    pub solution_point: *const c_double
-}
\ No newline at end of file
+    pub solution_point: *const c_double,
+    pub solution_length: c_int
+}
+
+/// Flags are treated as booleans (0 is false).  Tolerances are in meters and radians; errors within
+/// them incur no cost.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct OptimaIKGoalTolerance {
+    pub match_position: c_int,
+    pub match_orientation: c_int,
+    pub position_tolerance: c_double,
+    pub orientation_tolerance: c_double
+}
+impl OptimaIKGoalTolerance {
+    pub fn to_ik_goal_tolerance(&self) -> IKGoalTolerance {
+        IKGoalTolerance::new(self.match_position != 0, self.match_orientation != 0, self.position_tolerance, self.orientation_tolerance)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct OptimaIKObjectiveWeights {
+    pub pose_matching: c_double,
+    pub self_proximity: c_double,
+    pub joint_limits: c_double,
+    pub velocity: c_double,
+    pub acceleration: c_double,
+    pub jerk: c_double
+}
+impl OptimaIKObjectiveWeights {
+    /// Errors if any weight is negative or not finite.
+    pub fn terms(&self) -> Result<Vec<(CompositeObjectiveTerm, f64)>, FFIError> {
+        let out = vec![(CompositeObjectiveTerm::PoseMatching, self.pose_matching), (CompositeObjectiveTerm::SelfProximity, self.self_proximity), (CompositeObjectiveTerm::JointLimits, self.joint_limits), (CompositeObjectiveTerm::Velocity, self.velocity), (CompositeObjectiveTerm::Acceleration, self.acceleration), (CompositeObjectiveTerm::Jerk, self.jerk)];
+        match out.iter().find(|(_, weight)| !(weight.is_finite() && *weight >= 0.0)) {
+            None => { Ok(out) }
+            Some((term, weight)) => { Err(FFIError::InvalidArgument(format!("objective weight for {:?} must be finite and non-negative, got {}", term, weight))) }
+        }
+    }
+    pub fn to_composite_objective(&self) -> Result<CompositeObjective, FFIError> {
+        let mut out = CompositeObjective::new();
+        for (term, weight) in self.terms()? { out = out.with_term(term, weight)?; }
+        Ok(out)
+    }
+}
@@ -11,8 +11,11 @@ use optima_linalg::OLinalgCategoryNalgebra;
 use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
 use optima_optimization::open::SimpleOpEnOptimizer;
 use optima_proximity::pair_group_queries::{EmptyParryFilter, EmptyToParryProximity, OwnedEmptyParryFilter, OwnedEmptyToProximityQry};
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
 use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjective, DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
 use crate::ffi_wrappers::{DoubleArray, ArrayOfDoubleArrays, FFIConverters, GLOBAL_ROBOT};
+use crate::ffi_wrappers::ik_solvers::{checked_link_idx, checked_state};
+use crate::ffi_wrappers::status::{ffi_guard, ffi_out, ffi_slice, FFIError, OptimaStatus};
 
 type FAD = adfn<8>;
 
@@ -26,7 +29,7 @@ pub fn compute_interpolated_motion_path_to_ee_pose(goal_link_idx: usize, ee_posi
 
     let res = GLOBAL_STATIC_IK_DB.with(|once_lock_ik_diff_block| {
         let res = GLOBAL_IK_OPTIMIZER.with(|once_lock_ik_optimizer| {
-            let db = once_lock_ik_diff_block.get_or_init(|| r.get_ik_differentiable_block(ForwardADMulti::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &[0.0; 8], vec![goal_link_idx], 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0));
+            let db = once_lock_ik_diff_block.get_or_init(|| r.get_ik_differentiable_block(ForwardADMulti::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![goal_link_idx], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0)));
 
             db.update_ik_pose(0, Isometry3::from_constructors(&ee_position, &QuatConstructor::new_from_wxyz_ovec(&ee_orientation)), IKGoalUpdateMode::Absolute);
 
@@ -47,32 +50,48 @@ pub fn compute_interpolated_motion_path_to_ee_pose(goal_link_idx: usize, ee_posi
     res
 }
 
+/// Requires the global robot to have been set with `ffi_set_global_robot`.  The path is written
+/// into `out_path` and must be released with `ffi_free_array_of_double_arrays`.
 #[no_mangle]
-pub unsafe extern "C" fn ffi_compute_interpolated_motion_path_to_ee_pose(goal_link_idx: c_int, ee_position: *const c_double, ee_orientation: *const c_double, init_state: *const c_double, state_length: c_int) -> ArrayOfDoubleArrays {
-    let goal_link_idx = goal_link_idx as usize;
-    let ee_position = FFIConverters::c_double_arr_to_rust_double_vec(ee_position, 3);
-    let ee_orientation = FFIConverters::c_double_arr_to_rust_double_vec(ee_orientation, 4);
-    let init_state = FFIConverters::c_double_arr_to_rust_double_vec(init_state, state_length);
-    let res = compute_interpolated_motion_path_to_ee_pose(goal_link_idx, ee_position, ee_orientation, init_state);
+pub unsafe extern "C" fn ffi_compute_interpolated_motion_path_to_ee_pose(goal_link_idx: c_int, ee_position: *const c_double, ee_orientation: *const c_double, init_state: *const c_double, state_length: c_int, out_path: *mut ArrayOfDoubleArrays) -> OptimaStatus {
+    ffi_guard(|| {
+        let r = GLOBAL_ROBOT.get().ok_or(FFIError::InvalidArgument("use ffi_set_global_robot to initialize robot".to_string()))?;
+        let goal_link_idx = checked_link_idx(r, goal_link_idx)?;
+        let ee_position = ffi_slice(ee_position, 3, "ee_position")?.to_vec();
+        let ee_orientation = ffi_slice(ee_orientation, 4, "ee_orientation")?.to_vec();
+        let init_state = checked_state(r, init_state, state_length)?;
+        let out_path = ffi_out(out_path, "out_path")?;
+        let res = compute_interpolated_motion_path_to_ee_pose(goal_link_idx, ee_position, ee_orientation, init_state);
 
-    FFIConverters::rust_vec_of_f64_vecs_to_array_of_double_arrays(res)
+        *out_path = FFIConverters::rust_vec_of_f64_vecs_to_array_of_double_arrays(res);
+        Ok(())
+    })
 }
 
+/// Takes the array by value (it used to take a `DoubleArray*`), so pass the struct that was
+/// returned rather than its address.  The old signature freed the struct but leaked its data.
 #[no_mangle]
-pub extern "C" fn ffi_free_double_array(ptr: *mut DoubleArray) {
-    unsafe {
-        if !ptr.is_null() {
-            let _ = Box::from_raw(ptr);
-        }
-    }
+pub unsafe extern "C" fn ffi_free_double_array(arr: DoubleArray) -> OptimaStatus {
+    ffi_guard(|| {
+        free_double_array(arr);
+        Ok(())
+    })
 }
+/// Takes the array by value (it used to take an `ArrayOfDoubleArrays*`) and frees every inner
+/// array as well, so the inner arrays must not also be passed to `ffi_free_double_array`.
 #[no_mangle]
-pub extern "C" fn ffi_free_array_of_double_arrays(ptr: *mut ArrayOfDoubleArrays) {
-    unsafe {
-        if !ptr.is_null() {
-            let _ = Box::from_raw(ptr);
+pub unsafe extern "C" fn ffi_free_array_of_double_arrays(arr: ArrayOfDoubleArrays) -> OptimaStatus {
+    ffi_guard(|| {
+        if !arr.data.is_null() {
+            let arrays = Box::from_raw(std::slice::from_raw_parts_mut(arr.data as *mut DoubleArray, arr.length as usize));
+            for a in arrays.into_vec() { free_double_array(a); }
         }
-    }
+        Ok(())
+    })
 }
 
-
+unsafe fn free_double_array(arr: DoubleArray) {
+    if !arr.data.is_null() {
+        let _ = Box::from_raw(std::slice::from_raw_parts_mut(arr.data as *mut c_double, arr.length as usize));
+    }
+}
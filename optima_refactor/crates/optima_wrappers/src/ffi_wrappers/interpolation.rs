@@ -0,0 +1,178 @@
+use std::os::raw::*;
+use optima_interpolation::{get_interpolation_range, InterpolatorTrait, InterpolatorTraitLite};
+use optima_interpolation::splines::{BSpline, InterpolatingSpline, InterpolatingSplineType};
+use crate::ffi_wrappers::status::{ffi_guard, ffi_out, ffi_ref, ffi_slice, ffi_slice_mut, FFIError, OptimaStatus};
+
+// Trajectory generation for controller-side code.  An interpolator is built once from a row-major
+// (num_waypoints x waypoint_dim) array of waypoints and a duration, after which it can be queried
+// at any time t in [0, duration] or resampled at a fixed dt.
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptimaSplineType {
+    Linear = 0,
+    Quadratic = 1,
+    HermiteCubic = 2,
+    NaturalCubic = 3,
+    /// `spline_param` is the tension w.
+    CardinalCubic = 4,
+    BezierCubic = 5,
+    /// `spline_param` is the order k (rounded to the nearest integer, must be at least 2).
+    BSpline = 6
+}
+impl OptimaSplineType {
+    /// Enums are passed across the boundary as plain ints so that an out-of-range value from the
+    /// host is reported as an error rather than being undefined behavior.
+    fn from_c_int(i: c_int) -> Result<Self, FFIError> {
+        match i {
+            0 => { Ok(Self::Linear) }
+            1 => { Ok(Self::Quadratic) }
+            2 => { Ok(Self::HermiteCubic) }
+            3 => { Ok(Self::NaturalCubic) }
+            4 => { Ok(Self::CardinalCubic) }
+            5 => { Ok(Self::BezierCubic) }
+            6 => { Ok(Self::BSpline) }
+            _ => { Err(FFIError::InvalidArgument(format!("{} is not a valid OptimaSplineType", i))) }
+        }
+    }
+}
+
+/// Opaque to the host language.
+pub struct OptimaInterpolatorHandle {
+    interpolator: Box<dyn InterpolatorTraitLite<f64, Vec<f64>>>,
+    duration: f64,
+    waypoint_dim: usize
+}
+impl OptimaInterpolatorHandle {
+    fn interpolate_at_time(&self, t: f64) -> Vec<f64> {
+        let u = if self.duration > 0.0 { (t / self.duration).clamp(0.0, 1.0) } else { 0.0 };
+        self.interpolator.interpolate_normalized(u)
+    }
+    fn sample_times(&self, dt: f64) -> Result<Vec<f64>, FFIError> {
+        if !(dt > 0.0) || !dt.is_finite() { return Err(FFIError::InvalidArgument("dt must be positive".to_string())); }
+        Ok(get_interpolation_range(0.0, self.duration, dt))
+    }
+}
+
+/// `spline_type` is an `OptimaSplineType`.  If `arclength_parameterize` is nonzero, the spline is
+/// reparameterized so that the trajectory moves at constant speed in joint space; otherwise time
+/// is mapped linearly onto the spline's own parameter.  Not every spline type accepts every number
+/// of waypoints (e.g., cubic bezier needs 4 + 2n), in which case `InvalidArgument` is returned.
+#[no_mangle]
+pub unsafe extern "C" fn optima_interpolator_create(waypoints: *const c_double, num_waypoints: c_int, waypoint_dim: c_int, spline_type: c_int, spline_param: c_double, arclength_parameterize: c_int, duration: c_double, out_handle: *mut *mut OptimaInterpolatorHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        if num_waypoints < 2 { return Err(FFIError::InvalidArgument("at least two waypoints are required".to_string())); }
+        if waypoint_dim < 1 { return Err(FFIError::InvalidArgument("waypoint_dim must be positive".to_string())); }
+        if !(duration >= 0.0) || !duration.is_finite() { return Err(FFIError::InvalidArgument("duration must be non-negative".to_string())); }
+        let spline_type = OptimaSplineType::from_c_int(spline_type)?;
+        let length = num_waypoints.checked_mul(waypoint_dim).ok_or(FFIError::InvalidArgument("too many waypoints".to_string()))?;
+        let waypoints = ffi_slice(waypoints, length, "waypoints")?;
+        let out_handle = ffi_out(out_handle, "out_handle")?;
+        let waypoints: Vec<Vec<f64>> = waypoints.chunks(waypoint_dim as usize).map(|x| x.to_vec()).collect();
+        let arclength_parameterize = arclength_parameterize != 0;
+
+        let interpolator = match spline_type {
+            OptimaSplineType::BSpline => {
+                let k = spline_param.round();
+                if !(k >= 2.0) { return Err(FFIError::InvalidArgument("b-spline order must be at least 2".to_string())); }
+                boxed_interpolator(BSpline::new(waypoints, k as usize), arclength_parameterize)
+            }
+            _ => {
+                let spline_type = match spline_type {
+                    OptimaSplineType::Linear => { InterpolatingSplineType::Linear }
+                    OptimaSplineType::Quadratic => { InterpolatingSplineType::Quadratic }
+                    OptimaSplineType::HermiteCubic => { InterpolatingSplineType::HermiteCubic }
+                    OptimaSplineType::NaturalCubic => { InterpolatingSplineType::NaturalCubic }
+                    OptimaSplineType::CardinalCubic => { InterpolatingSplineType::CardinalCubic { w: spline_param } }
+                    OptimaSplineType::BezierCubic => { InterpolatingSplineType::BezierCubic }
+                    OptimaSplineType::BSpline => { unreachable!() }
+                };
+                let per_segment = spline_type.num_control_points_per_segment();
+                let overlap = spline_type.num_overlap_between_segments();
+                if waypoints.len() < per_segment || (waypoints.len() - overlap) % (per_segment - overlap) != 0 {
+                    return Err(FFIError::InvalidArgument(format!("{:?} splines need {} + {}n waypoints, got {}", spline_type, per_segment, per_segment - overlap, waypoints.len())));
+                }
+                boxed_interpolator(InterpolatingSpline::new(waypoints, spline_type), arclength_parameterize)
+            }
+        };
+
+        *out_handle = Box::into_raw(Box::new(OptimaInterpolatorHandle { interpolator, duration, waypoint_dim: waypoint_dim as usize }));
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_interpolator_free(handle: *mut OptimaInterpolatorHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        if !handle.is_null() { let _ = Box::from_raw(handle); }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_interpolator_duration(handle: *const OptimaInterpolatorHandle, out_duration: *mut c_double) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        *ffi_out(out_duration, "out_duration")? = h.duration;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_interpolator_waypoint_dim(handle: *const OptimaInterpolatorHandle, out_waypoint_dim: *mut c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        *ffi_out(out_waypoint_dim, "out_waypoint_dim")? = h.waypoint_dim as c_int;
+        Ok(())
+    })
+}
+
+/// Writes the point at time `t` (clamped to [0, duration]) into `out_point`, which must hold
+/// waypoint_dim doubles.
+#[no_mangle]
+pub unsafe extern "C" fn optima_interpolator_query(handle: *const OptimaInterpolatorHandle, t: c_double, out_point: *mut c_double) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        if t.is_nan() { return Err(FFIError::InvalidArgument("t is nan".to_string())); }
+        let out_point = ffi_slice_mut(out_point, h.waypoint_dim, "out_point")?;
+        out_point.copy_from_slice(&h.interpolate_at_time(t));
+        Ok(())
+    })
+}
+
+/// Number of samples `optima_interpolator_resample` produces for the given `dt`: one at every
+/// multiple of dt, plus one at the duration if it does not land on a multiple of dt.
+#[no_mangle]
+pub unsafe extern "C" fn optima_interpolator_num_samples(handle: *const OptimaInterpolatorHandle, dt: c_double, out_num_samples: *mut c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        *ffi_out(out_num_samples, "out_num_samples")? = h.sample_times(dt)?.len() as c_int;
+        Ok(())
+    })
+}
+
+/// Writes a row-major (num_samples x waypoint_dim) array into `out_points`, where `out_length` is
+/// the number of doubles the buffer can hold.  Use `optima_interpolator_num_samples` to size it.
+/// If `out_times` is not null, the time of each sample is written into it as well.
+#[no_mangle]
+pub unsafe extern "C" fn optima_interpolator_resample(handle: *const OptimaInterpolatorHandle, dt: c_double, out_points: *mut c_double, out_length: c_int, out_times: *mut c_double) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        let times = h.sample_times(dt)?;
+        let required = times.len() * h.waypoint_dim;
+        if out_length < 0 || (out_length as usize) < required { return Err(FFIError::InvalidArgument(format!("out_points must hold at least {} doubles", required))); }
+        let out_points = ffi_slice_mut(out_points, required, "out_points")?;
+
+        for (i, t) in times.iter().enumerate() {
+            out_points[i * h.waypoint_dim..(i + 1) * h.waypoint_dim].copy_from_slice(&h.interpolate_at_time(*t));
+        }
+        if !out_times.is_null() {
+            std::slice::from_raw_parts_mut(out_times, times.len()).copy_from_slice(&times);
+        }
+        Ok(())
+    })
+}
+
+fn boxed_interpolator<I: InterpolatorTrait<f64, Vec<f64>> + 'static>(interpolator: I, arclength_parameterize: bool) -> Box<dyn InterpolatorTraitLite<f64, Vec<f64>>> {
+    if arclength_parameterize { Box::new(interpolator.to_arclength_parameterized_interpolator(100)) } else { Box::new(interpolator) }
+}
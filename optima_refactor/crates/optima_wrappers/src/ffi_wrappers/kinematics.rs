@@ -0,0 +1,40 @@
+use std::os::raw::*;
+use optima_robotics::robotics_optimization::robotics_optimization_jacobian::manipulability_from_jacobian;
+use crate::ffi_wrappers::handles::OptimaRobotHandle;
+use crate::ffi_wrappers::ik_solvers::{checked_link_idx, checked_state};
+use crate::ffi_wrappers::status::{ffi_guard, ffi_out, ffi_ref, ffi_slice_mut, OptimaStatus};
+
+/// Writes the 6 x joint_state_length jacobian of the given link into `out_ptr` in row-major order.
+/// The first three rows are the linear velocity components and the last three are the angular
+/// velocity components (both in the world frame).  `out_ptr` must point to at least
+/// 6 * joint_state_length doubles.
+#[no_mangle]
+pub unsafe extern "C" fn robot_jacobian(robot: *const OptimaRobotHandle, state: *const c_double, joint_state_length: c_int, link_idx: c_int, out_ptr: *mut c_double) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(robot, "robot")?;
+        let state = checked_state(&h.robot, state, joint_state_length)?;
+        let link_idx = checked_link_idx(&h.robot, link_idx)?;
+        let jacobian = h.jacobian(&state, link_idx)?;
+
+        let out = ffi_slice_mut(out_ptr, jacobian.nrows() * jacobian.ncols(), "out_ptr")?;
+        for i in 0..jacobian.nrows() {
+            for j in 0..jacobian.ncols() {
+                out[i * jacobian.ncols() + j] = jacobian[(i, j)];
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Yoshikawa manipulability measure, sqrt(det(J J^T)), of the given link (sqrt(det(J^T J)) for
+/// robots with fewer than 6 dofs).
+#[no_mangle]
+pub unsafe extern "C" fn robot_manipulability(robot: *const OptimaRobotHandle, state: *const c_double, joint_state_length: c_int, link_idx: c_int, out_manipulability: *mut c_double) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(robot, "robot")?;
+        let state = checked_state(&h.robot, state, joint_state_length)?;
+        let link_idx = checked_link_idx(&h.robot, link_idx)?;
+        *ffi_out(out_manipulability, "out_manipulability")? = manipulability_from_jacobian(&h.jacobian(&state, link_idx)?);
+        Ok(())
+    })
+}
@@ -0,0 +1,120 @@
+use std::os::raw::*;
+use nalgebra::{Isometry3, Matrix3, Matrix4, Rotation3, Translation3, UnitQuaternion};
+use crate::ffi_wrappers::handles::{ffi_robot_free, ffi_robot_load, ffi_robot_num_dofs, ffi_robot_solve_ik, OptimaRobotHandle};
+use crate::ffi_wrappers::ik_solvers::{checked_link_idx, checked_state};
+use crate::ffi_wrappers::status::{ffi_guard, ffi_out, ffi_ref, ffi_slice, ffi_slice_mut};
+
+// C api shaped for MATLAB (`loadlibrary`/`calllib`) and MEX wrapping:
+//  - every function returns a status code (0 is success, see `OptimaStatus`) and never panics
+//    across the boundary; call `last_error_message` for details on failure,
+//  - matrices are written in column-major order,
+//  - outputs are written into caller-allocated buffers,
+//  - indices are zero-based (the MATLAB class wrapper converts from one-based indices).
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_mex_load_robot(robot_name: *const c_char, out_robot: *mut *mut OptimaRobotHandle) -> c_int {
+    ffi_guard(|| ffi_robot_load(robot_name, out_robot)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_mex_free_robot(robot: *mut OptimaRobotHandle) -> c_int {
+    ffi_guard(|| ffi_robot_free(robot)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_mex_num_dofs(robot: *const OptimaRobotHandle, out_num_dofs: *mut c_int) -> c_int {
+    ffi_guard(|| ffi_robot_num_dofs(robot, out_num_dofs)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_mex_num_links(robot: *const OptimaRobotHandle, out_num_links: *mut c_int) -> c_int {
+    ffi_guard(|| {
+        let r = &ffi_ref(robot, "robot")?.robot;
+        *ffi_out(out_num_links, "out_num_links")? = r.links().len() as c_int;
+        Ok(())
+    }) as c_int
+}
+
+/// `out_lower` and `out_upper` must each hold num_dofs doubles.
+#[no_mangle]
+pub unsafe extern "C" fn optima_mex_dof_bounds(robot: *const OptimaRobotHandle, out_lower: *mut c_double, out_upper: *mut c_double) -> c_int {
+    ffi_guard(|| {
+        let r = &ffi_ref(robot, "robot")?.robot;
+        let out_lower = ffi_slice_mut(out_lower, r.num_dofs(), "out_lower")?;
+        out_lower.copy_from_slice(&r.get_dof_lower_bounds());
+        let out_upper = ffi_slice_mut(out_upper, r.num_dofs(), "out_upper")?;
+        out_upper.copy_from_slice(&r.get_dof_upper_bounds());
+        Ok(())
+    }) as c_int
+}
+
+/// Writes the 4x4 homogeneous transform of the given link (column-major) into `out_pose`.
+#[no_mangle]
+pub unsafe extern "C" fn optima_mex_link_pose(robot: *const OptimaRobotHandle, state: *const c_double, state_length: c_int, link_idx: c_int, out_pose: *mut c_double) -> c_int {
+    ffi_guard(|| {
+        let r = &ffi_ref(robot, "robot")?.robot;
+        let state = checked_state(r, state, state_length)?;
+        let link_idx = checked_link_idx(r, link_idx)?;
+        let out_pose = ffi_slice_mut(out_pose, 16, "out_pose")?;
+
+        let fk_res = r.forward_kinematics(&state, None);
+        let pose = fk_res.get_link_pose(link_idx)?;
+        out_pose.copy_from_slice(pose.to_homogeneous().as_slice());
+        Ok(())
+    }) as c_int
+}
+
+/// Writes a 4x4xnum_links array (column-major, so each link's transform is a contiguous block of
+/// 16 values) into `out_poses`.  Links without a pose are filled with NaN.
+#[no_mangle]
+pub unsafe extern "C" fn optima_mex_forward_kinematics(robot: *const OptimaRobotHandle, state: *const c_double, state_length: c_int, out_poses: *mut c_double) -> c_int {
+    ffi_guard(|| {
+        let r = &ffi_ref(robot, "robot")?.robot;
+        let state = checked_state(r, state, state_length)?;
+        let out_poses = ffi_slice_mut(out_poses, 16 * r.links().len(), "out_poses")?;
+
+        let fk_res = r.forward_kinematics(&state, None);
+        for (link_idx, pose) in fk_res.link_poses().iter().enumerate() {
+            let block = &mut out_poses[16 * link_idx..16 * (link_idx + 1)];
+            match pose {
+                None => { block.iter_mut().for_each(|x| *x = f64::NAN); }
+                Some(pose) => { block.copy_from_slice(pose.to_homogeneous().as_slice()); }
+            }
+        }
+        Ok(())
+    }) as c_int
+}
+
+/// Writes the 6 x num_dofs jacobian (column-major, linear rows first) into `out_jacobian`.
+#[no_mangle]
+pub unsafe extern "C" fn optima_mex_jacobian(robot: *const OptimaRobotHandle, state: *const c_double, state_length: c_int, link_idx: c_int, out_jacobian: *mut c_double) -> c_int {
+    ffi_guard(|| {
+        let h = ffi_ref(robot, "robot")?;
+        let state = checked_state(&h.robot, state, state_length)?;
+        let link_idx = checked_link_idx(&h.robot, link_idx)?;
+        let out_jacobian = ffi_slice_mut(out_jacobian, 6 * h.robot.num_dofs(), "out_jacobian")?;
+
+        let jacobian = h.jacobian(&state, link_idx)?;
+        out_jacobian.copy_from_slice(jacobian.as_slice());
+        Ok(())
+    }) as c_int
+}
+
+/// `goal_pose` is a 4x4 homogeneous transform in column-major order.  The solution (num_dofs
+/// doubles) is written into `out_solution` and the final objective value into `out_cost`.
+#[no_mangle]
+pub unsafe extern "C" fn optima_mex_solve_ik(robot: *const OptimaRobotHandle, init_state: *const c_double, state_length: c_int, link_idx: c_int, goal_pose: *const c_double, out_solution: *mut c_double, out_cost: *mut c_double) -> c_int {
+    ffi_guard(|| {
+        let goal_pose = mex_homogeneous_to_isometry(ffi_slice(goal_pose, 16, "goal_pose")?);
+        ffi_robot_solve_ik(robot, init_state, state_length, link_idx, goal_pose, out_solution, out_cost)
+    }) as c_int
+}
+
+/// The rotation block is re-orthonormalized, so slightly non-orthogonal inputs (e.g., printed with
+/// limited precision) are accepted.
+fn mex_homogeneous_to_isometry(m: &[c_double]) -> Isometry3<f64> {
+    let m = Matrix4::from_column_slice(m);
+    let rotation: Matrix3<f64> = m.fixed_view::<3, 3>(0, 0).into();
+    let rotation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix(&rotation));
+    Isometry3::from_parts(Translation3::new(m[(0, 3)], m[(1, 3)], m[(2, 3)]), rotation)
+}
@@ -2,9 +2,19 @@ use std::ffi::{c_int, CString};
 use std::os::raw::{c_char, c_double};
 use std::sync::OnceLock;
 use optima_robotics::robot::ORobotDefault;
+use optima_sampling::OGlobalRng;
+use crate::ffi_wrappers::status::{ffi_guard, ffi_string, FFIError, OptimaStatus};
 
 pub mod ik_solvers;
 pub mod ik_solvers2;
+pub mod kinematics;
+pub mod status;
+pub mod mex;
+pub mod flat;
+pub mod handles;
+pub mod interpolation;
+pub mod progress;
+pub mod custom_objective;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -14,12 +24,12 @@ pub struct FFIConverters;
 impl FFIConverters {
     pub (crate) unsafe fn c_str_to_rust_string(c_str: *const c_char) -> String {
         let c_str = std::ffi::CStr::from_ptr(c_str);
-        let s = c_str.to_str().expect("Not a valid UTF-8 string");
-        s.to_string()
+        c_str.to_string_lossy().to_string()
     }
 
     pub (crate) unsafe fn rust_string_to_c_str(rust_string: String) -> *const c_char {
-        let c_string = CString::new(rust_string).expect("error");
+        // interior nul bytes cannot be represented in a c string.
+        let c_string = CString::new(rust_string.replace('\0', " ")).unwrap_or_default();
         c_string.into_raw() as *const c_char
     }
 
@@ -36,10 +46,11 @@ impl FFIConverters {
         slice.to_vec()
     }
 
+    /// The returned array owns its data and must be released with `ffi_free_double_array`.
     pub (crate) unsafe fn rust_f64_vec_to_c_double_arr(rust_float_vec: Vec<f64>) -> DoubleArray {
         let length = rust_float_vec.len() as c_int;
-        let data = rust_float_vec.as_ptr() as *const c_double;
-        std::mem::forget(rust_float_vec); // Prevent Rust from freeing the memory
+        // boxed slice so that capacity == length, which is what the free functions assume.
+        let data = Box::into_raw(rust_float_vec.into_boxed_slice()) as *const c_double;
 
         DoubleArray { data, length }
     }
@@ -49,15 +60,10 @@ impl FFIConverters {
         let mut double_arrays = Vec::with_capacity(length as usize);
 
         for f64_vec in rust_vec_of_f64_vecs {
-            let inner_length = f64_vec.len() as c_int;
-            let data = f64_vec.as_ptr() as *const c_double;
-            std::mem::forget(f64_vec);
-
-            double_arrays.push(DoubleArray { data, length: inner_length });
+            double_arrays.push(Self::rust_f64_vec_to_c_double_arr(f64_vec));
         }
 
-        let data = double_arrays.as_ptr();
-        std::mem::forget(double_arrays);
+        let data = Box::into_raw(double_arrays.into_boxed_slice()) as *const DoubleArray;
 
         ArrayOfDoubleArrays { data, length }
     }
@@ -79,12 +85,25 @@ pub struct ArrayOfDoubleArrays {
 
 pub (crate) static GLOBAL_ROBOT: OnceLock<ORobotDefault> = OnceLock::new();
 
-pub fn set_global_robot(robot_name: &str) {
-    GLOBAL_ROBOT.set(ORobotDefault::load_from_saved_robot(robot_name)).expect("error");
+/// The global robot can only be set once; subsequent calls return an error.
+pub fn set_global_robot(robot_name: &str) -> Result<(), String> {
+    if GLOBAL_ROBOT.get().is_some() { return Err("global robot has already been set".to_string()); }
+    GLOBAL_ROBOT.set(ORobotDefault::load_from_saved_robot(robot_name)?).map_err(|_| "global robot has already been set".to_string())
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn ffi_set_global_robot(robot_name: *const c_char) {
-    let s = FFIConverters::c_str_to_rust_string(robot_name);
-    set_global_robot(&s);
-}
\ No newline at end of file
+pub unsafe extern "C" fn ffi_set_global_robot(robot_name: *const c_char) -> OptimaStatus {
+    ffi_guard(|| {
+        let s = ffi_string(robot_name, "robot_name")?;
+        set_global_robot(&s).map_err(FFIError::InvalidArgument)
+    })
+}
+/// Seeds the toolbox-wide rng so that sampling (random states, preprocessing, optimizer
+/// perturbations) is reproducible.  Pass `has_seed = 0` to go back to seeding from entropy.
+#[no_mangle]
+pub unsafe extern "C" fn ffi_set_global_seed(has_seed: c_int, seed: u64) -> OptimaStatus {
+    ffi_guard(|| {
+        OGlobalRng::set_seed(if has_seed != 0 { Some(seed) } else { None });
+        Ok(())
+    })
+}
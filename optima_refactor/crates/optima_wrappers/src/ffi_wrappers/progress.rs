@@ -0,0 +1,108 @@
+use std::ffi::c_void;
+use std::os::raw::*;
+use optima_console::progress::OProgressHandle;
+use optima_robotics::robot::SaveRobot;
+use crate::ffi_wrappers::handles::{OptimaIKOptimizerHandle, OptimaRobotHandle};
+use crate::ffi_wrappers::status::{ffi_guard, ffi_out, ffi_ref, OptimaStatus};
+
+/// Called with a null-terminated description of the current stage, the fraction of that stage that
+/// has been completed (in [0, 1]), and the `user_data` pointer given at registration.  Return 0 to
+/// request cancellation, or any other value to keep going.  The stage string is only valid for the
+/// duration of the call.  The callback runs on the thread doing the work.
+pub type OptimaProgressCallback = Option<unsafe extern "C" fn(stage: *const c_char, fraction: c_double, user_data: *mut c_void) -> c_int>;
+
+/// Opaque to the host language.  Can be passed to any long running operation that accepts one, and
+/// cancelled from any thread with `optima_progress_cancel`.
+pub struct OptimaProgressHandle {
+    progress: OProgressHandle
+}
+
+struct HostUserData(*mut c_void);
+unsafe impl Send for HostUserData { }
+
+/// `callback` may be null, in which case the handle only acts as a cancellation token.
+#[no_mangle]
+pub unsafe extern "C" fn optima_progress_create(callback: OptimaProgressCallback, user_data: *mut c_void, out_handle: *mut *mut OptimaProgressHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        let out_handle = ffi_out(out_handle, "out_handle")?;
+        let progress = OProgressHandle::new();
+        if let Some(callback) = callback {
+            let user_data = HostUserData(user_data);
+            progress.set_callback(move |stage, fraction| {
+                // binds the whole wrapper so the closure doesn't capture the raw (non-Send) field.
+                let user_data = &user_data;
+                let stage = std::ffi::CString::new(stage.replace('\0', " ")).unwrap_or_default();
+                callback(stage.as_ptr(), fraction, user_data.0) != 0
+            });
+        }
+        *out_handle = Box::into_raw(Box::new(OptimaProgressHandle { progress }));
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_progress_free(handle: *mut OptimaProgressHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        if !handle.is_null() { let _ = Box::from_raw(handle); }
+        Ok(())
+    })
+}
+
+/// Safe to call from any thread while an operation using the handle is running.
+#[no_mangle]
+pub unsafe extern "C" fn optima_progress_cancel(handle: *const OptimaProgressHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        ffi_ref(handle, "handle")?.progress.cancel();
+        Ok(())
+    })
+}
+
+/// Clears the cancel flag so that the handle can be reused.
+#[no_mangle]
+pub unsafe extern "C" fn optima_progress_reset(handle: *const OptimaProgressHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        ffi_ref(handle, "handle")?.progress.reset();
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn optima_progress_is_cancelled(handle: *const OptimaProgressHandle, out_is_cancelled: *mut c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_ref(handle, "handle")?;
+        *ffi_out(out_is_cancelled, "out_is_cancelled")? = h.progress.is_cancelled() as c_int;
+        Ok(())
+    })
+}
+
+/// Runs robot preprocessing (self-collision pair skips and average distances), which can take
+/// minutes on large robots.  `progress` may be null.  `out_completed` is set to 0 if preprocessing
+/// was cancelled (the robot is then left unchanged) and 1 otherwise.  If `save` is nonzero, the
+/// preprocessed robot is saved under its default name.  IK blocks made from the robot earlier keep
+/// using the robot as it was.
+#[no_mangle]
+pub unsafe extern "C" fn optima_robot_preprocess(robot: *mut OptimaRobotHandle, progress: *const OptimaProgressHandle, save: c_int, out_completed: *mut c_int) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_out(robot, "robot")?;
+        let out_completed = ffi_out(out_completed, "out_completed")?;
+        let progress = match progress.as_ref() {
+            None => { OProgressHandle::new() }
+            Some(p) => { p.progress.clone() }
+        };
+        let save = if save != 0 { SaveRobot::Save(None) } else { SaveRobot::DoNotSave };
+
+        *out_completed = h.robot_mut().preprocess_with_progress(save, &progress) as c_int;
+        Ok(())
+    })
+}
+
+/// Attaches the handle to an IK optimizer so that subsequent `ik_optimize` calls report progress
+/// and can be cancelled (returning the best iterate so far).  Pass a null `progress` to detach.
+#[no_mangle]
+pub unsafe extern "C" fn optima_ik_optimizer_set_progress(optimizer: *mut OptimaIKOptimizerHandle, progress: *const OptimaProgressHandle) -> OptimaStatus {
+    ffi_guard(|| {
+        let h = ffi_out(optimizer, "optimizer")?;
+        h.optimizer.set_progress_handle(progress.as_ref().map(|p| p.progress.clone()));
+        Ok(())
+    })
+}
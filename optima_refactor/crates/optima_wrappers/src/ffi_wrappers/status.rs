@@ -0,0 +1,168 @@
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_double, c_int};
+use std::panic::AssertUnwindSafe;
+use optima_error::OptimaError;
+
+/// Status code returned by every guarded entry point.  On anything other than `Ok`, a description
+/// of the problem is available through `last_error_message`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptimaStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidArgument = 2,
+    Panic = 3,
+    /// A robot, link, or joint with the given name does not exist.
+    NotFound = 4,
+    FileIO = 5,
+    Serialization = 6,
+    /// The arguments were valid, but the call still failed.
+    Internal = 7
+}
+
+#[derive(Clone, Debug)]
+pub enum FFIError {
+    NullPointer(String),
+    InvalidArgument(String),
+    NotFound(String),
+    FileIO(String),
+    Serialization(String),
+    Internal(String)
+}
+impl FFIError {
+    pub fn status(&self) -> OptimaStatus {
+        match self {
+            FFIError::NullPointer(_) => { OptimaStatus::NullPointer }
+            FFIError::InvalidArgument(_) => { OptimaStatus::InvalidArgument }
+            FFIError::NotFound(_) => { OptimaStatus::NotFound }
+            FFIError::FileIO(_) => { OptimaStatus::FileIO }
+            FFIError::Serialization(_) => { OptimaStatus::Serialization }
+            FFIError::Internal(_) => { OptimaStatus::Internal }
+        }
+    }
+    pub fn message(&self) -> &str {
+        match self {
+            FFIError::NullPointer(s) => { s }
+            FFIError::InvalidArgument(s) => { s }
+            FFIError::NotFound(s) => { s }
+            FFIError::FileIO(s) => { s }
+            FFIError::Serialization(s) => { s }
+            FFIError::Internal(s) => { s }
+        }
+    }
+}
+impl From<OptimaError> for FFIError {
+    fn from(value: OptimaError) -> Self {
+        let message = value.to_string();
+        match value {
+            OptimaError::FileIO { .. } => { FFIError::FileIO(message) }
+            OptimaError::Serialization { .. } => { FFIError::Serialization(message) }
+            OptimaError::RobotNotFound { .. } | OptimaError::LinkNotFound { .. } | OptimaError::JointNotFound { .. } => { FFIError::NotFound(message) }
+            OptimaError::IdxOutOfBounds { .. } | OptimaError::InvalidInput(_) => { FFIError::InvalidArgument(message) }
+            OptimaError::LinkHasNoPose { .. } | OptimaError::Generic(_) => { FFIError::Internal(message) }
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR_MESSAGE: RefCell<CString> = RefCell::new(CString::default());
+}
+
+pub (crate) fn set_last_error_message(message: &str) {
+    let c_string = CString::new(message.replace('\0', " ")).expect("error");
+    LAST_ERROR_MESSAGE.with(|x| *x.borrow_mut() = c_string);
+}
+
+/// Runs `f`, converting both returned errors and panics into a status code so that nothing unwinds
+/// across the ffi boundary.
+pub (crate) fn ffi_guard<F: FnOnce() -> Result<(), FFIError>>(f: F) -> OptimaStatus {
+    match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => {
+            set_last_error_message("");
+            OptimaStatus::Ok
+        }
+        Ok(Err(e)) => {
+            set_last_error_message(e.message());
+            e.status()
+        }
+        Err(payload) => {
+            let message = if let Some(s) = payload.downcast_ref::<&str>() { s.to_string() } else if let Some(s) = payload.downcast_ref::<String>() { s.clone() } else { "unknown panic".to_string() };
+            set_last_error_message(&format!("panic: {}", message));
+            OptimaStatus::Panic
+        }
+    }
+}
+
+/// The returned reference is only valid for the duration of the ffi call; anything that needs to
+/// outlive the call has to take shared ownership (e.g., clone the robot's `Arc`) or copy the data.
+pub (crate) unsafe fn ffi_ref<'a, T>(ptr: *const T, name: &str) -> Result<&'a T, FFIError> {
+    ptr.as_ref().ok_or(FFIError::NullPointer(format!("{} is null", name)))
+}
+
+pub (crate) unsafe fn ffi_out<'a, T>(ptr: *mut T, name: &str) -> Result<&'a mut T, FFIError> {
+    ptr.as_mut().ok_or(FFIError::NullPointer(format!("{} is null", name)))
+}
+
+pub (crate) unsafe fn ffi_slice<'a>(ptr: *const c_double, length: c_int, name: &str) -> Result<&'a [c_double], FFIError> {
+    if length < 0 { return Err(FFIError::InvalidArgument(format!("{} has negative length", name))); }
+    if length == 0 { return Ok(&[]); }
+    if ptr.is_null() { return Err(FFIError::NullPointer(format!("{} is null", name))); }
+    Ok(std::slice::from_raw_parts(ptr, length as usize))
+}
+
+/// Like `ffi_slice`, for arrays of plain `#[repr(C)]` values other than doubles.
+pub (crate) unsafe fn ffi_array<'a, T>(ptr: *const T, length: c_int, name: &str) -> Result<&'a [T], FFIError> {
+    if length < 0 { return Err(FFIError::InvalidArgument(format!("{} has negative length", name))); }
+    if length == 0 { return Ok(&[]); }
+    if ptr.is_null() { return Err(FFIError::NullPointer(format!("{} is null", name))); }
+    Ok(std::slice::from_raw_parts(ptr, length as usize))
+}
+
+pub (crate) unsafe fn ffi_slice_mut<'a>(ptr: *mut c_double, length: usize, name: &str) -> Result<&'a mut [c_double], FFIError> {
+    if length == 0 { return Ok(&mut []); }
+    if ptr.is_null() { return Err(FFIError::NullPointer(format!("{} is null", name))); }
+    Ok(std::slice::from_raw_parts_mut(ptr, length))
+}
+
+pub (crate) unsafe fn ffi_array_mut<'a, T>(ptr: *mut T, length: usize, name: &str) -> Result<&'a mut [T], FFIError> {
+    if length == 0 { return Ok(&mut []); }
+    if ptr.is_null() { return Err(FFIError::NullPointer(format!("{} is null", name))); }
+    Ok(std::slice::from_raw_parts_mut(ptr, length))
+}
+
+pub (crate) unsafe fn ffi_string(ptr: *const c_char, name: &str) -> Result<String, FFIError> {
+    if ptr.is_null() { return Err(FFIError::NullPointer(format!("{} is null", name))); }
+    std::ffi::CStr::from_ptr(ptr).to_str().map(|x| x.to_string()).map_err(|_| FFIError::InvalidArgument(format!("{} is not valid utf-8", name)))
+}
+
+/// Description of the most recent error on the calling thread (empty if the last call succeeded).
+/// The returned pointer is owned by the library and stays valid until the next guarded call on
+/// the same thread.
+#[no_mangle]
+pub extern "C" fn last_error_message() -> *const c_char {
+    LAST_ERROR_MESSAGE.with(|x| x.borrow().as_ptr())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optima_errors_map_to_matching_statuses() {
+        assert_eq!(FFIError::from(OptimaError::new_file_io("robot.urdf", "missing")).status(), OptimaStatus::FileIO);
+        assert_eq!(FFIError::from(OptimaError::RobotNotFound { robot_name: "ur5".to_string(), message: "".to_string() }).status(), OptimaStatus::NotFound);
+        assert_eq!(FFIError::from(OptimaError::LinkNotFound { link_name: "ee".to_string() }).status(), OptimaStatus::NotFound);
+        assert_eq!(FFIError::from(OptimaError::new_idx_out_of_bounds("link", 3, 2)).status(), OptimaStatus::InvalidArgument);
+        assert_eq!(FFIError::from(OptimaError::LinkHasNoPose { link_idx: 1 }).status(), OptimaStatus::Internal);
+        let e = OptimaError::JointNotFound { joint_name: "elbow".to_string() };
+        assert_eq!(FFIError::from(e.clone()).message(), e.to_string());
+    }
+
+    #[test]
+    fn ffi_guard_reports_errors_and_panics() {
+        assert_eq!(ffi_guard(|| Ok(())), OptimaStatus::Ok);
+        assert_eq!(ffi_guard(|| Err(OptimaError::InvalidInput("bad".to_string()).into())), OptimaStatus::InvalidArgument);
+        assert_eq!(ffi_guard(|| panic!("boom")), OptimaStatus::Panic);
+    }
+}
@@ -21,13 +21,14 @@ use optima_proximity::shape_scene::OParryGenericShapeScene;
 use optima_proximity::shapes::OParryShape;
 use optima_robotics::robot::{ORobotDefault};
 use optima_robotics::robotics_optimization::robotics_collision_state_resolver::{DifferentiableBlockCollisionStateResolver, DifferentiableFunctionCollisionStateResolver};
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
 use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
 
 fn main() {
     let robot = ORobotDefault::load_from_saved_robot("panda7");
     let robot = Arc::new(robot);
     let init_state = vec![1.3835341759012247, 0.644993809355163, -1.231244289043802, -1.599375351855726, -2.708679434572325, 2.9128314876962325, -2.173304133837488];
-    let ik = robot.get_ik_differentiable_block(ForwardADMulti::<adfn<7>>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![8], 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0);
+    let ik = robot.get_ik_differentiable_block(ForwardADMulti::<adfn<7>>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![8], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0));
     let o = SimpleOpEnOptimizer::new(robot.get_dof_lower_bounds(), robot.get_dof_upper_bounds(), 0.001);
     let pose_goal = Isometry3::from_constructors(&[0.6539468661968684,0.2720397734086678,0.3551723678894053], &QuatConstructor::new(0.2029080599152886, -0.6224496471954787, 0.3354943169507112, -0.6773686730440179));
     ik.update_ik_pose(0, pose_goal.clone(), IKGoalUpdateMode::Absolute);
@@ -6,12 +6,13 @@ use optima_optimization::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
 use optima_optimization::open::SimpleOpEnOptimizer;
 use optima_proximity::pair_group_queries::{OwnedEmptyParryFilter, OwnedEmptyToProximityQry};
 use optima_robotics::robot::ORobotDefault;
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
 use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
 
 fn main() {
     let robot = ORobotDefault::load_from_saved_robot("xarm7_with_gripper_and_rail_8dof");
     let init_state = vec![0.0; 8];
-    let db = robot.get_ik_differentiable_block(ForwardADMulti::<adfn<8>>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![19], 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0);
+    let db = robot.get_ik_differentiable_block(ForwardADMulti::<adfn<8>>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_state, vec![19], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 0.0, 0.0, 0.0));
     let o = SimpleOpEnOptimizer::new(robot.get_dof_lower_bounds(), robot.get_dof_upper_bounds(), 0.001);
 
     db.update_ik_pose(0, Isometry3::from_constructors(&[0.3,0.3,0.3], &[0.,0.,0.]), IKGoalUpdateMode::Absolute);
@@ -5,6 +5,7 @@ use optima_proximity::pair_group_queries::{OwnedEmptyParryFilter, OwnedEmptyToPr
 use optima_proximity::pair_queries::{ParryDisMode, ParryShapeRep};
 use optima_proximity::proxima::{OwnedParryProximaAsProximityQry, OParryProximaArgs, OProximaTermination};
 use optima_robotics::robot::ORobotDefault;
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
 
 fn main() {
     // let q = OwnedParryDistanceAsProximityGroupQry::new(ParryDistanceGroupArgs::new(ParryShapeRep::Full, ParryDisMode::ContactDis, true, false, f64::MIN));
@@ -12,7 +13,7 @@ fn main() {
     let q = OwnedEmptyToProximityQry::new(());
     let r = ORobotDefault::load_from_saved_robot("ur5");
 
-    let ik = r.get_ik_differentiable_block(ForwardAD2::new(), OwnedEmptyParryFilter::new(()), q, Some(OParryPairSelector::HalfPairs), &[0.0; 6], vec![6], 0.07, 0.7, 1.0, 1.0, 1.0, 1.0, 1.0);
+    let ik = r.get_ik_differentiable_block(ForwardAD2::new(), OwnedEmptyParryFilter::new(()), q, Some(OParryPairSelector::HalfPairs), &[0.0; 6], vec![6], 0.07, 0.7, CompositeObjective::new_ik_unchecked(1.0, 1.0, 1.0, 1.0, 1.0));
 
     let o = SimpleOpEnOptimizer::new(r.get_dof_lower_bounds(), r.get_dof_upper_bounds(), 0.001);
     let res = o.optimize_unconstrained(&[0.01; 6], &ik);
@@ -9,6 +9,7 @@ use optima_optimization2::{DiffBlockOptimizerTrait, OptimizerOutputTrait};
 use optima_optimization2::open::SimpleOpEnOptimizer;
 use optima_proximity::pair_group_queries::{OwnedEmptyParryFilter, OwnedEmptyToProximityQry};
 use optima_robotics::robot::{IKGoalMode, ORobotDefault};
+use optima_robotics::robotics_optimization::robotics_optimization_composite::CompositeObjective;
 use optima_robotics::robotics_optimization::robotics_optimization_ik::{DifferentiableBlockIKObjectiveTrait, IKGoalUpdateMode};
 
 fn main() {
@@ -17,7 +18,7 @@ fn main() {
     let init_condition = vec![0.001,0.001,2.0,0.001,0.001,0.001];
     let ik_goal = r.get_ik_goal(&init_condition, 9, IKGoalMode::GlobalRelativeSeparate { offset: Isometry3::from_constructors(&[0.,0.,-0.5], &[0.,0.,0.]) });
     println!("{:?}", ik_goal);
-    let db = r.get_ik_differentiable_block(ForwardADMulti2::<adfn<6>>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_condition, vec![9], 0.0, 0.0,  1.0, 0.0, 1.0, 0.5, 0.2);
+    let db = r.get_ik_differentiable_block(ForwardADMulti2::<adfn<6>>::new(), OwnedEmptyParryFilter::new(()), OwnedEmptyToProximityQry::new(()), None, &init_condition, vec![9], 0.0, 0.0, CompositeObjective::new_ik_unchecked(1.0, 0.0, 1.0, 0.5, 0.2));
 
     let o = SimpleOpEnOptimizer::new(r.get_dof_lower_bounds(), r.get_dof_upper_bounds(), 0.001);
 
@@ -0,0 +1,18 @@
+pub use optima_error;
+pub use optima_file;
+pub use optima_console;
+pub use optima_3d_spatial;
+pub use optima_robotics;
+pub use optima_misc;
+pub use optima_linalg;
+pub use optima_3d_mesh;
+pub use optima_network;
+pub use optima_optimization;
+pub use optima_geometry;
+pub use optima_sampling;
+pub use optima_interpolation;
+pub use optima_proximity;
+pub use optima_universal_hashmap;
+pub use optima_wrappers;
+#[cfg(feature = "visualization")]
+pub use optima_bevy;